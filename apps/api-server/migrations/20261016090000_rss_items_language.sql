@@ -0,0 +1,4 @@
+ALTER TABLE rss_items ADD COLUMN IF NOT EXISTS language TEXT NOT NULL DEFAULT '';
+
+CREATE INDEX IF NOT EXISTS idx_rss_items_language
+ON rss_items (language);
@@ -0,0 +1,5 @@
+ALTER TABLE rss_items
+    ADD COLUMN IF NOT EXISTS preview_title TEXT,
+    ADD COLUMN IF NOT EXISTS preview_description TEXT,
+    ADD COLUMN IF NOT EXISTS preview_image TEXT,
+    ADD COLUMN IF NOT EXISTS preview_published_timestamp BIGINT;
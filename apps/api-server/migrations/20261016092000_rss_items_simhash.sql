@@ -0,0 +1 @@
+ALTER TABLE rss_items ADD COLUMN IF NOT EXISTS simhash BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,15 @@
+ALTER TABLE rss_items
+    ALTER COLUMN category TYPE TEXT[] USING regexp_split_to_array(category, ','),
+    ALTER COLUMN category SET DEFAULT '{}';
+
+UPDATE rss_items SET category = ARRAY(
+    SELECT DISTINCT slug
+    FROM (
+        SELECT trim(BOTH '-' FROM regexp_replace(lower(name), '[^[:alnum:]]+', '-', 'g')) AS slug
+        FROM unnest(category) AS name
+    ) AS slugs
+    WHERE slug <> ''
+);
+
+CREATE INDEX IF NOT EXISTS idx_rss_items_category
+ON rss_items USING GIN (category);
@@ -0,0 +1,12 @@
+ALTER TABLE rss_items
+    ALTER COLUMN author TYPE JSONB USING CASE
+        WHEN author ~ '^\S+@\S+\s*\(.*\)$' THEN jsonb_build_object(
+            'name', trim(substring(author FROM '\((.*)\)$')),
+            'email', substring(author FROM '^(\S+@\S+?)\s*\(')
+        )
+        WHEN author ~ '^\S+@\S+$' THEN jsonb_build_object('name', '', 'email', author)
+        ELSE jsonb_build_object('name', trim(author), 'email', NULL)
+    END;
+
+CREATE INDEX IF NOT EXISTS idx_rss_items_author_name
+ON rss_items ((author ->> 'name'));
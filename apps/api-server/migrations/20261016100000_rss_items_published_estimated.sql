@@ -0,0 +1 @@
+ALTER TABLE rss_items ADD COLUMN IF NOT EXISTS published_timestamp_estimated BOOLEAN NOT NULL DEFAULT FALSE;
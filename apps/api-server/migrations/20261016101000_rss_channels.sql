@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS rss_channels (
+    hash TEXT PRIMARY KEY,
+    url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    link TEXT NOT NULL,
+    description TEXT NOT NULL,
+    language TEXT NOT NULL,
+    ttl INTEGER,
+    last_build_timestamp BIGINT,
+    self_url TEXT NOT NULL,
+    fetched_timestamp BIGINT NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE rss_items ADD COLUMN IF NOT EXISTS article_markdown TEXT NOT NULL DEFAULT '';
@@ -0,0 +1,2 @@
+CREATE INDEX IF NOT EXISTS idx_rss_items_published_timestamp
+ON rss_items (published_timestamp DESC);
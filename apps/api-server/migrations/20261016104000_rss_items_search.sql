@@ -0,0 +1,7 @@
+ALTER TABLE rss_items ADD COLUMN IF NOT EXISTS search_vector tsvector GENERATED ALWAYS AS (
+    setweight(to_tsvector('simple', coalesce(title, '')), 'A') ||
+    setweight(to_tsvector('simple', coalesce(description, '')), 'B') ||
+    setweight(to_tsvector('simple', coalesce(article, '')), 'C')
+) STORED;
+CREATE INDEX IF NOT EXISTS idx_rss_items_search_vector
+ON rss_items USING GIN (search_vector);
@@ -0,0 +1,2 @@
+CREATE INDEX IF NOT EXISTS idx_rss_items_feed_host
+ON rss_items ((regexp_replace(lower(substring(link FROM '^[a-zA-Z][a-zA-Z0-9+.-]*://([^/:?#]+)')), '^www\.', '')));
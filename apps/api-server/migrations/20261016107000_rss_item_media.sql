@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS rss_item_media (
+    object_key TEXT PRIMARY KEY,
+    item_hash TEXT NOT NULL REFERENCES rss_items (hash) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    source_url TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    archived_timestamp BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_rss_item_media_item_hash
+ON rss_item_media (item_hash);
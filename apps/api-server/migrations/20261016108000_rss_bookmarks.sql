@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS rss_bookmarks (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    item_hash TEXT NOT NULL REFERENCES rss_items (hash) ON DELETE CASCADE,
+    created_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_rss_bookmarks_user_id_created_at
+ON rss_bookmarks (user_id, created_at);
+
+CREATE TABLE IF NOT EXISTS rss_saved_searches (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    query TEXT NOT NULL,
+    saved_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_rss_saved_searches_user_id_saved_at
+ON rss_saved_searches (user_id, saved_at);
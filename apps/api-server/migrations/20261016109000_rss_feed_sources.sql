@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS rss_feed_sources (
+    hash TEXT PRIMARY KEY,
+    url TEXT NOT NULL UNIQUE,
+    interval_seconds BIGINT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_rss_feed_sources_created_at
+ON rss_feed_sources (created_at);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS webhook_subscriptions (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    event_types TEXT[] NOT NULL,
+    secret TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_webhook_subscriptions_user_id_created_at
+ON webhook_subscriptions (user_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_webhook_subscriptions_event_types
+ON webhook_subscriptions USING GIN (event_types);
@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS digest_preferences (
+    user_id TEXT PRIMARY KEY,
+    email TEXT NOT NULL,
+    frequency TEXT NOT NULL,
+    categories TEXT[] NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    last_sent_at BIGINT NOT NULL,
+    next_send_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_digest_preferences_enabled_next_send_at
+ON digest_preferences (enabled, next_send_at);
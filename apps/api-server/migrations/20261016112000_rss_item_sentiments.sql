@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS rss_item_sentiments (
+    item_hash TEXT PRIMARY KEY,
+    label TEXT NOT NULL,
+    score REAL NOT NULL,
+    model TEXT NOT NULL,
+    analyzed_timestamp BIGINT NOT NULL
+);
@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS auth_sessions (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    user_agent TEXT NOT NULL,
+    created_at BIGINT NOT NULL,
+    expires_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_auth_sessions_user_id_created_at
+ON auth_sessions (user_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_auth_sessions_expires_at
+ON auth_sessions (expires_at);
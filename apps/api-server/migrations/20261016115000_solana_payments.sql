@@ -0,0 +1,17 @@
+CREATE TABLE IF NOT EXISTS solana_payments (
+    signature TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    lamports BIGINT NOT NULL,
+    tier TEXT NOT NULL,
+    periods BIGINT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_solana_payments_user_id_created_at
+ON solana_payments (user_id, created_at);
+
+CREATE TABLE IF NOT EXISTS user_subscriptions (
+    user_id TEXT PRIMARY KEY,
+    tier TEXT NOT NULL,
+    expires_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
@@ -0,0 +1,43 @@
+CREATE TABLE IF NOT EXISTS organizations (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    owner_id TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS organization_members (
+    id TEXT PRIMARY KEY,
+    org_id TEXT NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    role TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_organization_members_org_id_user_id
+ON organization_members (org_id, user_id);
+CREATE INDEX IF NOT EXISTS idx_organization_members_user_id_created_at
+ON organization_members (user_id, created_at);
+
+CREATE TABLE IF NOT EXISTS organization_api_keys (
+    id TEXT PRIMARY KEY,
+    org_id TEXT NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    prefix TEXT NOT NULL,
+    key_hash TEXT NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_organization_api_keys_key_hash
+ON organization_api_keys (key_hash);
+CREATE INDEX IF NOT EXISTS idx_organization_api_keys_org_id_created_at
+ON organization_api_keys (org_id, created_at);
+
+-- Resources shared with an organization hold its ID, resources of a user alone hold ''.
+ALTER TABLE rss_bookmarks ADD COLUMN IF NOT EXISTS org_id TEXT NOT NULL DEFAULT '';
+ALTER TABLE rss_saved_searches ADD COLUMN IF NOT EXISTS org_id TEXT NOT NULL DEFAULT '';
+ALTER TABLE webhook_subscriptions ADD COLUMN IF NOT EXISTS org_id TEXT NOT NULL DEFAULT '';
+CREATE INDEX IF NOT EXISTS idx_rss_bookmarks_org_id_created_at
+ON rss_bookmarks (org_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_rss_saved_searches_org_id_saved_at
+ON rss_saved_searches (org_id, saved_at);
+CREATE INDEX IF NOT EXISTS idx_webhook_subscriptions_org_id_created_at
+ON webhook_subscriptions (org_id, created_at);
@@ -0,0 +1,5 @@
+ALTER TABLE rss_items ADD COLUMN IF NOT EXISTS deleted_at BIGINT;
+CREATE INDEX IF NOT EXISTS idx_rss_items_deleted_at
+ON rss_items (deleted_at) WHERE deleted_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_rss_items_fetched_timestamp
+ON rss_items (fetched_timestamp);
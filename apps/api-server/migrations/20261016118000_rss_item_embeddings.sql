@@ -0,0 +1,11 @@
+CREATE EXTENSION IF NOT EXISTS vector;
+
+CREATE TABLE IF NOT EXISTS rss_item_embeddings (
+    item_hash TEXT PRIMARY KEY,
+    embedding vector(384) NOT NULL,
+    model TEXT NOT NULL,
+    embedded_timestamp BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_rss_item_embeddings_embedding
+ON rss_item_embeddings USING hnsw (embedding vector_cosine_ops);
@@ -0,0 +1,4 @@
+ALTER TABLE digest_preferences
+    ADD COLUMN IF NOT EXISTS confirmation_token_hash TEXT NOT NULL DEFAULT '',
+    ADD COLUMN IF NOT EXISTS confirmation_sent_at BIGINT NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS confirmed_at BIGINT NOT NULL DEFAULT 0;
@@ -0,0 +1,108 @@
+use crate::{domain::Error, middleware_v1::RequestId, models::AuditEvent};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// AuditEventType is the type of the security-relevant events kept in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventType {
+    /// Registration of a wallet.
+    Registration,
+    /// Login of a wallet.
+    Login,
+    /// Registration or login with a signature not matching the wallet.
+    SignatureFailure,
+    /// Revocation of a session by its user.
+    SessionRevocation,
+    /// Change made through the admin endpoints.
+    AdminAction,
+}
+
+impl AuditEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Registration => "registration",
+            Self::Login => "login",
+            Self::SignatureFailure => "signature_failure",
+            Self::SessionRevocation => "session_revocation",
+            Self::AdminAction => "admin_action",
+        }
+    }
+
+    /// Type of a failed registration or login, signature mismatches are told apart so they can be
+    /// queried on their own.
+    ///
+    /// # Arguments
+    /// * `attempted` - Type of the attempted event.
+    /// * `err` - The error the attempt failed with.
+    pub fn of_failure(attempted: Self, err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<Error>() {
+            Some(Error::InvalidCredentials) => Self::SignatureFailure,
+            _ => attempted,
+        }
+    }
+}
+
+impl AuditEvent {
+    /// Creates an event that happened now.
+    ///
+    /// # Arguments
+    /// * `event_type` - The type of the event.
+    /// * `actor` - The wallet the event was triggered by, as sent by the client.
+    /// * `success` - Whether the action succeeded.
+    /// * `details` - Human readable description of the action or of the failure.
+    /// * `request_id` - ID of the request the event happened in.
+    pub fn new(
+        event_type: AuditEventType,
+        actor: &str,
+        success: bool,
+        details: impl Into<String>,
+        request_id: &RequestId,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            event_type: event_type.as_str().to_string(),
+            actor: actor.to_string(),
+            success,
+            details: details.into(),
+            request_id: request_id.to_string(),
+            created_at: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_audit_event_type_of_failure() {
+        let signature = anyhow::Error::from(Error::InvalidCredentials);
+        assert_eq!(
+            AuditEventType::of_failure(AuditEventType::Login, &signature),
+            AuditEventType::SignatureFailure
+        );
+        let expired = anyhow::Error::from(Error::TokenExpired);
+        assert_eq!(
+            AuditEventType::of_failure(AuditEventType::Registration, &expired),
+            AuditEventType::Registration
+        );
+        assert_eq!(
+            AuditEventType::of_failure(AuditEventType::Login, &anyhow!("storage is down")),
+            AuditEventType::Login
+        );
+
+        let event = AuditEvent::new(
+            AuditEventType::SignatureFailure,
+            "wallet",
+            false,
+            "Invalid credentials",
+            &RequestId::generate(),
+        );
+        assert_eq!(event.event_type, "signature_failure");
+        assert!(!event.success);
+    }
+}
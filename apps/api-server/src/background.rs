@@ -0,0 +1,174 @@
+use anyhow::Result;
+use std::{future::Future, time::Duration};
+use tokio::{
+    sync::watch,
+    task::{JoinHandle, JoinSet},
+    time::{Instant, sleep, timeout_at},
+};
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// BackgroundTasks supervises long running tasks such as message queue consumers.
+///
+/// A task that fails, panics or returns is restarted after an exponential backoff, the backoff is
+/// reset once the task has run longer than the maximal backoff. Once stopped, tasks are no longer
+/// restarted and running tasks are given until the deadline to finish, e.g. after their message
+/// queue subscriptions are drained.
+pub struct BackgroundTasks {
+    shutdown: watch::Sender<bool>,
+    handles: Vec<(String, JoinHandle<()>)>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl BackgroundTasks {
+    /// Creates a supervisor restarting tasks after 1 second, doubling up to 60 seconds.
+    pub fn new() -> Self {
+        Self {
+            shutdown: watch::channel(false).0,
+            handles: Vec::new(),
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        }
+    }
+
+    /// Sets the delay before the first restart and the limit the delay doubles up to.
+    #[allow(dead_code)]
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Spawns a supervised task.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the task used in logs.
+    /// * `task` - Creates the future of a single run of the task, called again on every restart.
+    pub fn spawn<F, Fut>(&mut self, name: &str, task: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let mut shutdown = self.shutdown.subscribe();
+        let (initial_backoff, max_backoff) = (self.initial_backoff, self.max_backoff);
+        let task_name = name.to_string();
+
+        let handle = tokio::spawn(async move {
+            let mut backoff = initial_backoff;
+            loop {
+                let started = Instant::now();
+                let mut run = JoinSet::new();
+                run.spawn(task());
+                let result = tokio::select! {
+                    result = run.join_next() => Some(result),
+                    _ = shutdown.wait_for(|stop| *stop) => None,
+                };
+                let Some(result) = result else {
+                    let _ = run.join_next().await;
+                    tracing::info!("Background task {task_name} stopped");
+                    return;
+                };
+
+                match result {
+                    Some(Ok(Ok(()))) | None => {
+                        tracing::warn!("Background task {task_name} returned")
+                    }
+                    Some(Ok(Err(e))) => tracing::error!("Background task {task_name} failed: {e}"),
+                    Some(Err(e)) => tracing::error!("Background task {task_name} panicked: {e}"),
+                }
+
+                if started.elapsed() > max_backoff {
+                    backoff = initial_backoff;
+                }
+                tracing::info!("Restarting background task {task_name} in {backoff:?}");
+                tokio::select! {
+                    _ = sleep(backoff) => {}
+                    _ = shutdown.wait_for(|stop| *stop) => return,
+                }
+                backoff = (backoff * 2).min(max_backoff);
+            }
+        });
+
+        self.handles.push((name.to_string(), handle));
+    }
+
+    /// Receiver notified once the tasks are stopped, for tasks that do not end on their own such
+    /// as servers.
+    pub fn stop_signal(&self) -> watch::Receiver<bool> {
+        self.shutdown.subscribe()
+    }
+
+    /// Stops restarting tasks and lets the running ones finish.
+    pub fn stop(&self) {
+        let _ = self.shutdown.send(true);
+    }
+
+    /// Stops all tasks and waits for them to finish.
+    ///
+    /// # Arguments
+    /// * `deadline` - Time all tasks have to finish by, tasks still running afterwards are aborted.
+    pub async fn shutdown(self, deadline: Instant) {
+        self.stop();
+
+        for (name, mut handle) in self.handles {
+            if timeout_at(deadline, &mut handle).await.is_err() {
+                tracing::warn!("Background task {name} did not stop in time, aborting");
+                handle.abort();
+            }
+        }
+    }
+}
+
+impl Default for BackgroundTasks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    #[tokio::test]
+    async fn test_background_tasks_restart_and_shutdown() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let mut tasks = BackgroundTasks::new()
+            .with_backoff(Duration::from_millis(1), Duration::from_millis(10));
+
+        let counter = runs.clone();
+        tasks.spawn("flaky", move || {
+            let counter = counter.clone();
+            async move {
+                match counter.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(anyhow!("subscriber is broken")),
+                    1 => panic!("consumer panicked"),
+                    _ => std::future::pending().await,
+                }
+            }
+        });
+
+        for _ in 0..100 {
+            if runs.load(Ordering::SeqCst) >= 3 {
+                break;
+            }
+            sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        tasks.stop();
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+        let started = Instant::now();
+        tasks
+            .shutdown(Instant::now() + Duration::from_millis(50))
+            .await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
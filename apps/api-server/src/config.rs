@@ -1,3 +1,4 @@
+use crate::models::Claims;
 use nats_middleware::NatsConfig;
 use serde::{Deserialize, Serialize};
 use std::env;
@@ -13,6 +14,14 @@ pub struct Config {
     pub redis: RedisConfig,
     pub nats: NatsConfig,
     pub minio: MinioConfig,
+    pub admin: AdminConfig,
+    pub smtp: SmtpConfig,
+    pub grpc: GrpcConfig,
+    pub response_cache: ResponseCacheConfig,
+    pub payments: PaymentsConfig,
+    pub quota: QuotaConfig,
+    pub retention: RetentionConfig,
+    pub route_limits: RouteLimitsConfig,
     pub generator_secret: GeneratorSecret,
 }
 
@@ -25,6 +34,10 @@ pub struct ServerConfig {
     pub max_connections: usize,
     pub keep_alive: u64,
     pub request_timeout: u64,
+    /// Seconds given to in-flight requests, queue consumers and connections to finish on shutdown.
+    pub shutdown_timeout: u64,
+    /// Milliseconds each dependency has to answer the readiness probe.
+    pub health_check_timeout_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +46,14 @@ pub struct JwtConfig {
     pub expiration_hours: i64,
     pub issuer: String,
     pub audience: String,
+    /// Deprecated, accepts register and login credentials in the query string besides the JSON body.
+    pub query_credentials_enabled: bool,
+    /// Signing algorithm, `HS256` with the shared secret, `RS256` or `EdDSA` with key files.
+    pub algorithm: String,
+    /// Private key tokens are signed with, its file name without extension is the key ID.
+    pub private_key_path: Option<String>,
+    /// Directory of the `<key ID>.pem` public keys tokens are verified with.
+    pub public_keys_dir: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,6 +119,104 @@ pub struct MinioConfig {
     pub bucket: String,
     pub region: String,
     pub use_ssl: bool,
+    /// Endpoint presigned URLs are issued for when clients reach the storage under another address.
+    pub public_endpoint: Option<String>,
+    /// How long presigned URLs stay valid.
+    pub presign_expiry_seconds: u64,
+    /// Largest media file archived from item feeds, larger files are skipped.
+    pub media_max_bytes: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdminConfig {
+    /// Solana wallet public keys allowed to use the admin endpoints.
+    pub wallets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpConfig {
+    /// Email digests are sent only when enabled.
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    pub password: String,
+    /// Sender of the emails, e.g. `Semantic Machine <digest@example.com>`.
+    pub from: String,
+    /// Upgrades the connection with STARTTLS, plain connections are meant for local relays only.
+    pub starttls: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    /// The internal gRPC API is served only when enabled.
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    /// Bearer token the clients have to send, None to accept all clients of the internal network.
+    pub auth_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCacheConfig {
+    /// Hot GET responses are cached in Redis only when enabled, ETags are sent regardless.
+    pub enabled: bool,
+    pub ttl_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentsConfig {
+    /// Solana payments for the pro tier are accepted only when enabled.
+    pub enabled: bool,
+    /// JSON-RPC endpoint transactions are verified with.
+    pub solana_rpc_url: String,
+    /// Base58 wallet the payments have to be transferred to.
+    pub recipient_wallet: String,
+    /// Price of a single pro tier period in lamports.
+    pub pro_price_lamports: u64,
+    /// Length of a single pro tier period in days.
+    pub pro_period_days: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaConfig {
+    /// Request quotas and feature gates are enforced only when enabled.
+    pub enabled: bool,
+    /// Requests a free tier user can make per day.
+    pub free_daily_requests: u64,
+    /// Requests a pro tier user can make per day.
+    pub pro_daily_requests: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// RSS items expire and deleted items are purged only when enabled.
+    pub enabled: bool,
+    /// Days since fetching after which an RSS item is deleted.
+    pub rss_item_days: i64,
+    /// Days a deleted RSS item is kept before it is purged from storage.
+    pub purge_grace_days: i64,
+    /// Seconds between retention runs.
+    pub interval_seconds: u64,
+}
+
+/// Limits of the requests of a group of routes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteLimit {
+    /// Seconds the request has to be answered in, it fails with `504 Gateway Timeout` after.
+    pub timeout_seconds: u64,
+    /// Largest accepted request body in bytes, larger ones fail with `413 Payload Too Large`.
+    pub max_body_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteLimitsConfig {
+    /// Register and login, small signed challenges answered quickly.
+    pub auth: RouteLimit,
+    /// Admin routes and the RSS export, bulk requests such as purges, imports and exports.
+    pub admin: RouteLimit,
+    /// All remaining routes.
+    pub default: RouteLimit,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -126,6 +245,14 @@ impl Config {
             redis: RedisConfig::from_env()?,
             nats: NatsConfig::from_env().map_err(|e| ConfigError::InvalidValue(e.to_string()))?,
             minio: MinioConfig::from_env()?,
+            admin: AdminConfig::from_env(),
+            smtp: SmtpConfig::from_env()?,
+            grpc: GrpcConfig::from_env()?,
+            response_cache: ResponseCacheConfig::from_env()?,
+            payments: PaymentsConfig::from_env()?,
+            quota: QuotaConfig::from_env()?,
+            retention: RetentionConfig::from_env()?,
+            route_limits: RouteLimitsConfig::from_env()?,
             generator_secret: GeneratorSecret::from_env()?,
         })
     }
@@ -138,10 +265,53 @@ impl Config {
             ));
         }
 
-        if self.jwt.secret.is_empty() {
+        if self.jwt.algorithm == "HS256" && self.jwt.secret.is_empty() {
             return Err(ConfigError::MissingRequired("JWT_SECRET".to_string()));
         }
 
+        if self.grpc.enabled && self.grpc.auth_token.is_none() {
+            return Err(ConfigError::MissingRequired("GRPC_AUTH_TOKEN".to_string()));
+        }
+
+        if self.payments.enabled && self.payments.recipient_wallet.is_empty() {
+            return Err(ConfigError::MissingRequired(
+                "PAYMENTS_RECIPIENT_WALLET".to_string(),
+            ));
+        }
+
+        if self.payments.enabled && self.payments.pro_price_lamports == 0 {
+            return Err(ConfigError::InvalidValue(
+                "PAYMENTS_PRO_PRICE_LAMPORTS cannot be 0".to_string(),
+            ));
+        }
+
+        if self.retention.enabled
+            && (self.retention.rss_item_days <= 0 || self.retention.purge_grace_days < 0)
+        {
+            return Err(ConfigError::InvalidValue(
+                "RETENTION_RSS_ITEM_DAYS must be positive and RETENTION_PURGE_GRACE_DAYS cannot be negative"
+                    .to_string(),
+            ));
+        }
+
+        if self.retention.enabled && self.retention.interval_seconds == 0 {
+            return Err(ConfigError::InvalidValue(
+                "RETENTION_INTERVAL_SECONDS cannot be 0".to_string(),
+            ));
+        }
+
+        for (scope, limit) in [
+            ("AUTH", self.route_limits.auth),
+            ("ADMIN", self.route_limits.admin),
+            ("DEFAULT", self.route_limits.default),
+        ] {
+            if limit.timeout_seconds == 0 || limit.max_body_bytes == 0 {
+                return Err(ConfigError::InvalidValue(format!(
+                    "ROUTE_LIMITS_{scope}_TIMEOUT_SECONDS and ROUTE_LIMITS_{scope}_MAX_BODY_BYTES cannot be 0"
+                )));
+            }
+        }
+
         Ok(())
     }
 }
@@ -171,6 +341,16 @@ impl ServerConfig {
                 .unwrap_or_else(|_| "30".to_string())
                 .parse()
                 .map_err(|_| ConfigError::ParseError("SERVER_REQUEST_TIMEOUT".to_string()))?,
+            shutdown_timeout: env::var("SERVER_SHUTDOWN_TIMEOUT")
+                .unwrap_or_else(|_| "25".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("SERVER_SHUTDOWN_TIMEOUT".to_string()))?,
+            health_check_timeout_ms: env::var("SERVER_HEALTH_CHECK_TIMEOUT_MS")
+                .unwrap_or_else(|_| "2000".to_string())
+                .parse()
+                .map_err(|_| {
+                    ConfigError::ParseError("SERVER_HEALTH_CHECK_TIMEOUT_MS".to_string())
+                })?,
         })
     }
 }
@@ -187,6 +367,15 @@ impl JwtConfig {
             issuer: env::var("JWT_ISSUER").unwrap_or_else(|_| "Semantic-Machine-api".to_string()),
             audience: env::var("JWT_AUDIENCE")
                 .unwrap_or_else(|_| "Semantic-Machine-services".to_string()),
+            query_credentials_enabled: env::var("AUTH_QUERY_CREDENTIALS_ENABLED")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .map_err(|_| {
+                    ConfigError::ParseError("AUTH_QUERY_CREDENTIALS_ENABLED".to_string())
+                })?,
+            algorithm: env::var("JWT_ALGORITHM").unwrap_or_else(|_| "HS256".to_string()),
+            private_key_path: env::var("JWT_PRIVATE_KEY_PATH").ok(),
+            public_keys_dir: env::var("JWT_PUBLIC_KEYS_DIR").ok(),
         })
     }
 }
@@ -364,10 +553,217 @@ impl MinioConfig {
                 .unwrap_or_else(|_| "false".to_string())
                 .parse()
                 .unwrap_or(false),
+            public_endpoint: env::var("MINIO_PUBLIC_ENDPOINT")
+                .ok()
+                .filter(|endpoint| !endpoint.trim().is_empty()),
+            presign_expiry_seconds: env::var("MINIO_PRESIGN_EXPIRY_SECONDS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .unwrap_or(3600),
+            media_max_bytes: env::var("MINIO_MEDIA_MAX_BYTES")
+                .unwrap_or_else(|_| "10485760".to_string())
+                .parse()
+                .unwrap_or(10485760),
+        })
+    }
+}
+
+impl SmtpConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(SmtpConfig {
+            enabled: env::var("SMTP_ENABLED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            host: env::var("SMTP_HOST").unwrap_or_else(|_| "localhost".to_string()),
+            port: env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("SMTP_PORT".to_string()))?,
+            username: env::var("SMTP_USERNAME").unwrap_or_default(),
+            password: env::var("SMTP_PASSWORD").unwrap_or_default(),
+            from: env::var("SMTP_FROM")
+                .unwrap_or_else(|_| "Semantic Machine <digest@localhost>".to_string()),
+            starttls: env::var("SMTP_STARTTLS")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+        })
+    }
+}
+
+impl GrpcConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(GrpcConfig {
+            enabled: env::var("GRPC_ENABLED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            host: env::var("GRPC_HOST").unwrap_or_else(|_| "0.0.0.0".to_string()),
+            port: env::var("GRPC_PORT")
+                .unwrap_or_else(|_| "50051".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("GRPC_PORT".to_string()))?,
+            auth_token: env::var("GRPC_AUTH_TOKEN")
+                .ok()
+                .filter(|token| !token.trim().is_empty()),
+        })
+    }
+}
+
+impl ResponseCacheConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(ResponseCacheConfig {
+            enabled: env::var("RESPONSE_CACHE_ENABLED")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            ttl_seconds: env::var("RESPONSE_CACHE_TTL_SECONDS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("RESPONSE_CACHE_TTL_SECONDS".to_string()))?,
+        })
+    }
+}
+
+impl PaymentsConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(PaymentsConfig {
+            enabled: env::var("PAYMENTS_ENABLED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            solana_rpc_url: env::var("PAYMENTS_SOLANA_RPC_URL")
+                .unwrap_or_else(|_| "https://api.mainnet-beta.solana.com".to_string()),
+            recipient_wallet: env::var("PAYMENTS_RECIPIENT_WALLET").unwrap_or_default(),
+            pro_price_lamports: env::var("PAYMENTS_PRO_PRICE_LAMPORTS")
+                .unwrap_or_else(|_| "100000000".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("PAYMENTS_PRO_PRICE_LAMPORTS".to_string()))?,
+            pro_period_days: env::var("PAYMENTS_PRO_PERIOD_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("PAYMENTS_PRO_PERIOD_DAYS".to_string()))?,
+        })
+    }
+}
+
+impl QuotaConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(QuotaConfig {
+            enabled: env::var("QUOTA_ENABLED")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            free_daily_requests: env::var("QUOTA_FREE_DAILY_REQUESTS")
+                .unwrap_or_else(|_| "1000".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("QUOTA_FREE_DAILY_REQUESTS".to_string()))?,
+            pro_daily_requests: env::var("QUOTA_PRO_DAILY_REQUESTS")
+                .unwrap_or_else(|_| "100000".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("QUOTA_PRO_DAILY_REQUESTS".to_string()))?,
+        })
+    }
+}
+
+impl RetentionConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(RetentionConfig {
+            enabled: env::var("RETENTION_ENABLED")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("RETENTION_ENABLED".to_string()))?,
+            rss_item_days: env::var("RETENTION_RSS_ITEM_DAYS")
+                .unwrap_or_else(|_| "365".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("RETENTION_RSS_ITEM_DAYS".to_string()))?,
+            purge_grace_days: env::var("RETENTION_PURGE_GRACE_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("RETENTION_PURGE_GRACE_DAYS".to_string()))?,
+            interval_seconds: env::var("RETENTION_INTERVAL_SECONDS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("RETENTION_INTERVAL_SECONDS".to_string()))?,
+        })
+    }
+}
+
+impl RouteLimitsConfig {
+    pub fn from_env() -> Result<Self, ConfigError> {
+        Ok(RouteLimitsConfig {
+            auth: RouteLimit::from_env("AUTH", 10, 4 * 1024)?,
+            admin: RouteLimit::from_env("ADMIN", 120, 1024 * 1024)?,
+            default: RouteLimit::from_env("DEFAULT", 30, 64 * 1024)?,
         })
     }
 }
 
+impl RouteLimitsConfig {
+    /// Name and limits of the route group of the path.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the request, e.g. `/api/v1/auth/login`.
+    pub fn of_path(&self, path: &str) -> (&'static str, RouteLimit) {
+        let path = path.strip_prefix("/api/v1").unwrap_or(path);
+        if path == "/auth/register" || path == "/auth/login" {
+            ("auth", self.auth)
+        } else if path == "/admin" || path.starts_with("/admin/") || path == "/rss/export" {
+            ("admin", self.admin)
+        } else {
+            ("default", self.default)
+        }
+    }
+}
+
+impl RouteLimit {
+    /// Reads the `ROUTE_LIMITS_<scope>_TIMEOUT_SECONDS` and `ROUTE_LIMITS_<scope>_MAX_BODY_BYTES`
+    /// variables, the defaults are used when unset.
+    fn from_env(
+        scope: &str,
+        timeout_seconds: u64,
+        max_body_bytes: usize,
+    ) -> Result<Self, ConfigError> {
+        let timeout = format!("ROUTE_LIMITS_{scope}_TIMEOUT_SECONDS");
+        let max_body = format!("ROUTE_LIMITS_{scope}_MAX_BODY_BYTES");
+        Ok(RouteLimit {
+            timeout_seconds: env::var(&timeout)
+                .unwrap_or_else(|_| timeout_seconds.to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError(timeout))?,
+            max_body_bytes: env::var(&max_body)
+                .unwrap_or_else(|_| max_body_bytes.to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError(max_body))?,
+        })
+    }
+}
+
+impl AdminConfig {
+    pub fn from_env() -> Self {
+        AdminConfig {
+            wallets: env::var("ADMIN_WALLETS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|wallet| wallet.trim().to_string())
+                .filter(|wallet| !wallet.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Checks if the wallet may use the admin endpoints.
+    pub fn is_admin(&self, wallet: &str) -> bool {
+        self.wallets.iter().any(|admin| admin == wallet)
+    }
+
+    /// Checks if the request may use the admin endpoints, API keys never may, even when they
+    /// were created by an admin wallet.
+    pub fn allows(&self, claims: &Claims) -> bool {
+        !claims.is_api_key() && self.is_admin(&claims.sub)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ConfigError {
     #[error("Missing required environment variable: {0}")]
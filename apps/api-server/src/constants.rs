@@ -1,2 +1,8 @@
 pub const BEARER: &str = "Bearer ";
 pub const API_VERSION: &str = "v1";
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+pub const RATE_LIMIT_LIMIT_HEADER: &str = "x-ratelimit-limit";
+pub const RATE_LIMIT_REMAINING_HEADER: &str = "x-ratelimit-remaining";
+pub const RATE_LIMIT_RESET_HEADER: &str = "x-ratelimit-reset";
+pub const ORGANIZATION_HEADER: &str = "x-organization-id";
+pub const API_KEY_HEADER: &str = "x-api-key";
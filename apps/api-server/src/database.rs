@@ -1,17 +1,44 @@
+use crate::config::DatabaseConfig;
 use anyhow::{Error as E, Result};
-use sqlx::{Pool, Postgres, migrate::Migrator};
-use std::collections::HashMap;
+use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerError};
+use serde::{Deserialize, Serialize};
+use sqlx::{
+    Arguments, FromRow, Pool, Postgres, migrate::Migrator, postgres::PgArguments,
+    postgres::PgPoolOptions,
+};
+use std::{fmt, future::Future, sync::Arc, time::Duration};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use utoipa::ToSchema;
 
 #[derive(Debug, Clone)]
 pub struct PostgresStorageGateway {
     pool: Pool<Postgres>,
+    breaker: Arc<CircuitBreaker>,
 }
 
 impl PostgresStorageGateway {
     #[inline(always)]
-    pub async fn new(connection_string: &str) -> Result<Self> {
-        let pool = Pool::connect(connection_string).await.map_err(E::msg)?;
-        Ok(Self { pool })
+    pub async fn new(config: &DatabaseConfig) -> Result<Self> {
+        let pool = PgPoolOptions::new()
+            .max_connections(config.pool_size.max(1))
+            .acquire_timeout(Duration::from_secs(config.connection_timeout))
+            .idle_timeout(
+                (config.idle_timeout > 0).then(|| Duration::from_secs(config.idle_timeout)),
+            )
+            .max_lifetime(
+                (config.max_lifetime > 0).then(|| Duration::from_secs(config.max_lifetime)),
+            )
+            .connect(&config.url)
+            .await
+            .map_err(E::msg)?;
+        Ok(Self {
+            pool,
+            breaker: Arc::new(CircuitBreaker::new(
+                "postgres",
+                CircuitBreakerConfig::default(),
+            )),
+        })
     }
 
     #[inline(always)]
@@ -23,6 +50,64 @@ impl PostgresStorageGateway {
     pub fn get_pool(&self) -> &Pool<Postgres> {
         &self.pool
     }
+
+    /// Number of connections in use and idle connections of the pool.
+    #[inline(always)]
+    pub fn pool_stats(&self) -> (u32, usize) {
+        let idle = self.pool.num_idle();
+        (self.pool.size().saturating_sub(idle as u32), idle)
+    }
+
+    /// Circuit breaker guarding the queries.
+    #[inline(always)]
+    pub fn circuit_breaker(&self) -> Arc<CircuitBreaker> {
+        self.breaker.clone()
+    }
+
+    /// Runs the query through the circuit breaker, so queries fail right away while the database
+    /// is unreachable instead of each waiting for a connection until the acquire timeout.
+    ///
+    /// Only connection errors count as failures, query errors such as constraint violations come
+    /// from a healthy database.
+    pub async fn guarded<T, F>(&self, query: F) -> Result<T>
+    where
+        F: Future<Output = Result<T, sqlx::Error>>,
+    {
+        self.breaker
+            .call_filtered(query, is_connection_error)
+            .await
+            .map_err(|e| match e {
+                CircuitBreakerError::Open(_) => E::new(e),
+                CircuitBreakerError::Inner(e) => E::new(e),
+            })
+    }
+
+    /// Checks the database answers a trivial query.
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1")
+            .execute(&self.pool)
+            .await
+            .map_err(E::msg)?;
+        Ok(())
+    }
+
+    /// Closes the pool, waiting for connections in use to be returned.
+    #[inline(always)]
+    pub async fn close(&self) {
+        self.pool.close().await
+    }
+}
+
+fn is_connection_error(error: &sqlx::Error) -> bool {
+    matches!(
+        error,
+        sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::Protocol(_)
+            | sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed
+    )
 }
 
 /// Represents a type that can insert entities in bulk into storage.
@@ -55,15 +140,159 @@ pub trait StoreReadBulkEntities<Entity, Identifier> {
     async fn read_bulk_by_ids(&self, ids: &[Identifier]) -> Result<Vec<Entity>>;
 }
 
+/// Represents a type that can delete multiple entities by their IDs from storage.
+#[async_trait::async_trait]
+pub trait StoreDeleteBulk<Entity, Identifier> {
+    /// Deletes multiple entities by their identifiers, missing entities are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - Slice of identifiers.
+    ///
+    /// # Returns
+    ///
+    /// * Returns the number of deleted entities on success, or an error otherwise.
+    async fn delete_bulk(&self, ids: &[Identifier]) -> Result<u64>;
+}
+
+/// StorageError describes invalid storage requests.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum StorageError {
+    #[error("Cannot sort `{table}` by `{field}`")]
+    InvalidSortField { table: String, field: String },
+}
+
+/// Direction entities are sorted in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl fmt::Display for SortDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Asc => write!(f, "ASC"),
+            Self::Desc => write!(f, "DESC"),
+        }
+    }
+}
+
+/// Sort orders entities by a field of the table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sort {
+    pub field: String,
+    pub direction: SortDirection,
+}
+
+/// FilterValue is a value bound to a filter placeholder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterValue {
+    Text(String),
+    Int(i64),
+    Bool(bool),
+    TextList(Vec<String>),
+}
+
+impl FilterValue {
+    /// ILIKE pattern matching text containing the value, wildcards of the value match literally.
+    pub fn contains_pattern(value: &str) -> Self {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        Self::Text(format!("%{escaped}%"))
+    }
+}
+
+/// Filter is a condition on a column or an SQL expression of the table.
+///
+/// The column is static so that only expressions written by the server end up in the query,
+/// values are always bound as parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// Column equals the value.
+    Eq(&'static str, FilterValue),
+    /// Column matches the pattern ignoring case.
+    ILike(&'static str, FilterValue),
+    /// Column equals one of the values of the list.
+    In(&'static str, FilterValue),
+    /// Array column contains the value.
+    Contains(&'static str, FilterValue),
+    /// Column is greater than or equal to the value.
+    Gte(&'static str, FilterValue),
+    /// Column is less than or equal to the value.
+    Lte(&'static str, FilterValue),
+}
+
+impl Filter {
+    /// SQL condition of the filter with the value at the given placeholder position.
+    pub fn condition(&self, position: usize) -> String {
+        match self {
+            Self::Eq(column, _) => format!("{column} = ${position}"),
+            Self::ILike(column, _) => format!("{column} ILIKE ${position}"),
+            Self::In(column, _) => format!("{column} = ANY(${position})"),
+            Self::Contains(column, _) => format!("${position} = ANY({column})"),
+            Self::Gte(column, _) => format!("{column} >= ${position}"),
+            Self::Lte(column, _) => format!("{column} <= ${position}"),
+        }
+    }
+
+    /// Value bound to the placeholder of the filter.
+    pub fn value(&self) -> &FilterValue {
+        match self {
+            Self::Eq(_, value)
+            | Self::ILike(_, value)
+            | Self::In(_, value)
+            | Self::Contains(_, value)
+            | Self::Gte(_, value)
+            | Self::Lte(_, value) => value,
+        }
+    }
+}
+
+/// Builds the WHERE clause of the filters with placeholders starting at `$1` and of conditions
+/// without values, such as `deleted_at IS NULL` of soft deleted tables, empty if there are none.
+pub fn where_clause(filters: &[Filter], conditions: &[&str]) -> String {
+    let conditions = filters
+        .iter()
+        .enumerate()
+        .map(|(i, filter)| filter.condition(i + 1))
+        .chain(conditions.iter().map(|condition| condition.to_string()))
+        .collect::<Vec<_>>();
+    if conditions.is_empty() {
+        return String::new();
+    }
+
+    format!(" WHERE {}", conditions.join(" AND "))
+}
+
+/// Binds the values of the filters in the order of their placeholders.
+pub fn filter_arguments(filters: &[Filter]) -> PgArguments {
+    let mut args = PgArguments::default();
+    for filter in filters {
+        let _ = match filter.value() {
+            FilterValue::Text(value) => args.add(value),
+            FilterValue::Int(value) => args.add(value),
+            FilterValue::Bool(value) => args.add(value),
+            FilterValue::TextList(values) => args.add(values),
+        };
+    }
+    args
+}
+
 /// Represents a type that can filter and paginate entities from storage.
 #[async_trait::async_trait]
 #[allow(dead_code)]
 pub trait StorePaginateBulkEntities<Entity> {
-    /// Filters and paginates entities from storage.
+    /// Filters, sorts and paginates entities from storage.
     ///
     /// # Arguments
     ///
-    /// * `field_map` - Map of field names and filter values.
+    /// * `filters` - Conditions all entities have to meet, none to read all entities.
+    /// * `sort` - Field to sort by, one of the fields read, newest first if not given.
     /// * `limit` - Number of entities per page.
     /// * `offset` - Offset to start pagination.
     ///
@@ -72,7 +301,173 @@ pub trait StorePaginateBulkEntities<Entity> {
     /// * Returns a vector of entities on success, or an error otherwise.
     async fn filter_paginate(
         &self,
-        field_map: &HashMap<String, String>,
+        filters: &[Filter],
+        sort: Option<&Sort>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Entity>>;
+}
+
+/// Represents a type that can count entities in storage.
+#[async_trait::async_trait]
+pub trait StoreCount<Entity> {
+    /// Counts entities meeting the filters.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` - Conditions counted entities have to meet, none to count all entities.
+    ///
+    /// # Returns
+    ///
+    /// * Returns the number of entities on success, or an error otherwise.
+    async fn count(&self, filters: &[Filter]) -> Result<i64>;
+}
+
+/// GroupCount is the number of entities sharing a group key.
+#[derive(Debug, Clone, PartialEq, Eq, FromRow)]
+pub struct GroupCount {
+    pub key: String,
+    pub count: i64,
+}
+
+/// Represents a type that can aggregate entities in storage.
+#[async_trait::async_trait]
+#[allow(dead_code)]
+pub trait StoreAggregate<Entity> {
+    /// Counts entities per group, largest groups first.
+    ///
+    /// # Arguments
+    ///
+    /// * `group_key` - SQL expression of the group key returning text, a set returning expression
+    ///   such as `unnest(category)` counts the entity in every group.
+    /// * `filters` - Conditions counted entities have to meet.
+    /// * `limit` - Maximal number of groups.
+    ///
+    /// # Returns
+    ///
+    /// * Returns the groups on success, or an error otherwise.
+    async fn count_by(
+        &self,
+        group_key: &'static str,
+        filters: &[Filter],
+        limit: i64,
+    ) -> Result<Vec<GroupCount>>;
+
+    /// Counts the distinct values of a field.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - SQL expression of the counted value.
+    /// * `filters` - Conditions counted entities have to meet.
+    ///
+    /// # Returns
+    ///
+    /// * Returns the number of distinct values on success, or an error otherwise.
+    async fn count_distinct(&self, field: &'static str, filters: &[Filter]) -> Result<i64>;
+
+    /// Computes a continuous percentile of a numeric expression.
+    ///
+    /// # Arguments
+    ///
+    /// * `expression` - SQL expression of the value.
+    /// * `fraction` - Percentile between 0 and 1, e.g. `0.5` for the median.
+    /// * `filters` - Conditions the entities have to meet.
+    ///
+    /// # Returns
+    ///
+    /// * Returns the percentile, None if no entity meets the filters, or an error otherwise.
+    async fn percentile(
+        &self,
+        expression: &'static str,
+        fraction: f64,
+        filters: &[Filter],
+    ) -> Result<Option<f64>>;
+}
+
+/// Represents a type whose entities are soft deleted, they are no longer read once deleted and are
+/// purged from storage later.
+///
+/// Reads of a soft deleted table skip deleted entities when its macros are given the
+/// `soft_delete = "deleted_at",` argument.
+#[async_trait::async_trait]
+pub trait StoreSoftDelete<Entity> {
+    /// Marks the entities meeting the filters deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` - Conditions deleted entities have to meet, at least one is required.
+    /// * `deleted_at` - Time of the deletion in Unix milliseconds.
+    ///
+    /// # Returns
+    ///
+    /// * Returns the number of entities deleted now on success, or an error otherwise.
+    async fn soft_delete(&self, filters: &[Filter], deleted_at: i64) -> Result<u64>;
+
+    /// Reads the identifiers of entities deleted before the given time, oldest deletions first.
+    ///
+    /// # Arguments
+    ///
+    /// * `deleted_before` - Time in Unix milliseconds.
+    /// * `limit` - Maximal number of identifiers.
+    ///
+    /// # Returns
+    ///
+    /// * Returns the identifiers on success, or an error otherwise.
+    async fn deleted_ids(&self, deleted_before: i64, limit: i64) -> Result<Vec<String>>;
+}
+
+/// Represents a type that can full-text search entities in storage.
+#[async_trait::async_trait]
+pub trait StoreSearch<Entity> {
+    /// Searches entities matching the query, best matches first.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Web search style query, e.g. `bitcoin -etf "rate cut"`.
+    /// * `limit` - Number of entities per page.
+    /// * `offset` - Offset to start pagination.
+    ///
+    /// # Returns
+    ///
+    /// * Returns a vector of entities on success, or an error otherwise.
+    async fn search(&self, query: &str, limit: i64, offset: i64) -> Result<Vec<Entity>>;
+}
+
+/// Represents a type that can stream entities from storage without reading them all at once.
+#[async_trait::async_trait]
+pub trait StoreStream<Entity> {
+    /// Sends the entities meeting the filters to the channel, oldest first, until all are sent or
+    /// the receiver is dropped. A failure of the storage is sent as the last message.
+    ///
+    /// The rows are read over a single connection for as long as the receiver takes them, so the
+    /// reads are not guarded by the circuit breaker.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` - Conditions all entities have to meet, none to stream all entities.
+    /// * `sender` - Channel the entities are sent to.
+    async fn stream(&self, filters: &[Filter], sender: mpsc::Sender<Result<Entity>>);
+}
+
+/// Represents a type that can search entities by the similarity of their embeddings.
+#[async_trait::async_trait]
+pub trait StoreNearest<Entity> {
+    /// Searches entities whose embeddings are nearest to the vector, most similar first.
+    ///
+    /// # Arguments
+    ///
+    /// * `vector` - Embedding of the query, of the dimension of the stored embeddings.
+    /// * `filters` - Conditions the entities have to meet, none to search all entities.
+    /// * `limit` - Number of entities per page.
+    /// * `offset` - Offset to start pagination.
+    ///
+    /// # Returns
+    ///
+    /// * Returns a vector of entities on success, or an error otherwise.
+    async fn nearest(
+        &self,
+        vector: &[f32],
+        filters: &[Filter],
         limit: i64,
         offset: i64,
     ) -> Result<Vec<Entity>>;
@@ -135,10 +530,15 @@ macro_rules! impl_store_bulk {
                     )+
                 }
 
-                let mut tx = self.get_pool().begin().await?;
-                let rows = query_builder.fetch_all(&mut *tx).await?;
+                let rows = self
+                    .guarded(async {
+                        let mut tx = self.get_pool().begin().await?;
+                        let rows = query_builder.fetch_all(&mut *tx).await?;
+                        tx.commit().await?;
+                        Ok(rows)
+                    })
+                    .await?;
                 let ids: Vec<$id_type> = rows.into_iter().map(|row| row.get($conflict_field)).collect();
-                tx.commit().await?;
 
                 Ok(ids)
             }
@@ -153,6 +553,7 @@ macro_rules! impl_read_bulk_by_ids {
         $table_name:literal,
         [$($field:ident),+ $(,)?],
         $id_field:literal,
+        $(soft_delete = $deleted_field:literal,)?
     ) => {
         #[async_trait::async_trait]
         impl StoreReadBulkEntities<$model, $id_type> for $crate::PostgresStorageGateway {
@@ -166,12 +567,14 @@ macro_rules! impl_read_bulk_by_ids {
                 let placeholders: Vec<String> = (1..=ids.len())
                     .map(|i| format!("${}", i))
                     .collect();
+                let live: &[&str] = &[$(concat!(" AND ", $deleted_field, " IS NULL"))?];
                 let query_str = format!(
-                    "SELECT {} FROM {} WHERE {} IN ({})",
+                    "SELECT {} FROM {} WHERE {} IN ({}){}",
                     fields,
                     $table_name,
                     $id_field,
-                    placeholders.join(", ")
+                    placeholders.join(", "),
+                    live.concat()
                 );
 
                 let mut args = PgArguments::default();
@@ -179,8 +582,11 @@ macro_rules! impl_read_bulk_by_ids {
                     let _ = args.add(id);
                 }
 
-                let rows = sqlx::query_as_with::<_, $model, _>(&query_str, args)
-                    .fetch_all(self.get_pool())
+                let rows = self
+                    .guarded(
+                        sqlx::query_as_with::<_, $model, _>(&query_str, args)
+                            .fetch_all(self.get_pool()),
+                    )
                     .await?;
 
                 Ok(rows)
@@ -189,50 +595,393 @@ macro_rules! impl_read_bulk_by_ids {
     };
 }
 
+#[macro_export]
+macro_rules! impl_delete_bulk_by_ids {
+    (
+        $model:ty, $id_type:ty,
+        $table_name:literal,
+        $id_field:literal,
+    ) => {
+        #[async_trait::async_trait]
+        impl $crate::database::StoreDeleteBulk<$model, $id_type>
+            for $crate::PostgresStorageGateway
+        {
+            #[inline(always)]
+            async fn delete_bulk(&self, ids: &[$id_type]) -> Result<u64> {
+                if ids.is_empty() {
+                    return Err(anyhow!(
+                        "Found zero identifiers to delete from `{}`.",
+                        $table_name
+                    ));
+                }
+
+                let placeholders: Vec<String> =
+                    (1..=ids.len()).map(|i| format!("${}", i)).collect();
+                let query_str = format!(
+                    "DELETE FROM {} WHERE {} IN ({})",
+                    $table_name,
+                    $id_field,
+                    placeholders.join(", ")
+                );
+
+                let mut args = PgArguments::default();
+                for id in ids {
+                    let _ = args.add(id);
+                }
+
+                let result = self
+                    .guarded(sqlx::query_with(&query_str, args).execute(self.get_pool()))
+                    .await?;
+
+                Ok(result.rows_affected())
+            }
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! impl_read_bulk_multiple {
     (
         $model:ty,
         $table_name:literal,
         [$($field:ident),+ $(,)?],
-        $field_map_type:ty
+        $order_field:literal,
+        $(soft_delete = $deleted_field:literal,)?
     ) => {
         #[async_trait::async_trait]
         impl $crate::database::StorePaginateBulkEntities<$model> for $crate::PostgresStorageGateway {
             #[inline(always)]
             async fn filter_paginate(
                 &self,
-                field_map: $field_map_type,
+                filters: &[$crate::database::Filter],
+                sort: Option<&$crate::database::Sort>,
                 limit: i64,
                 offset: i64,
             ) -> Result<Vec<$model>> {
-                let valid_fields: Vec<_> = field_map
-                    .iter()
-                    .filter(|(k, v)| !k.trim().is_empty() && !v.trim().is_empty())
-                    .collect();
+                let fields = [$(stringify!($field)),+];
+                let order = match sort {
+                    Some(sort) => {
+                        let Some(field) = fields.iter().find(|field| **field == sort.field) else {
+                            return Err($crate::database::StorageError::InvalidSortField {
+                                table: $table_name.to_string(),
+                                field: sort.field.clone(),
+                            }
+                            .into());
+                        };
+                        format!("{} {}, {} DESC", field, sort.direction, $order_field)
+                    }
+                    None => format!("{} DESC", $order_field),
+                };
+                let live: &[&str] = &[$(concat!($deleted_field, " IS NULL"))?];
+                let query_str = format!(
+                    "SELECT {} FROM {}{} ORDER BY {} LIMIT ${} OFFSET ${}",
+                    fields.join(", "),
+                    $table_name,
+                    $crate::database::where_clause(filters, live),
+                    order,
+                    filters.len() + 1,
+                    filters.len() + 2
+                );
+
+                let mut args = $crate::database::filter_arguments(filters);
+                let _ = args.add(limit);
+                let _ = args.add(offset);
+
+                let rows = self
+                    .guarded(
+                        sqlx::query_as_with::<_, $model, _>(&query_str, args)
+                            .fetch_all(self.get_pool()),
+                    )
+                    .await?;
+
+                Ok(rows)
+            }
+        }
+    };
+}
 
-                if valid_fields.is_empty() {
-                    return Err(anyhow!("No valid filters found for `{}`.", $table_name));
+#[macro_export]
+macro_rules! impl_count_aggregate {
+    (
+        $model:ty,
+        $table_name:literal,
+        $(soft_delete = $deleted_field:literal,)?
+    ) => {
+        #[async_trait::async_trait]
+        impl $crate::database::StoreCount<$model> for $crate::PostgresStorageGateway {
+            #[inline(always)]
+            async fn count(&self, filters: &[$crate::database::Filter]) -> Result<i64> {
+                let live: &[&str] = &[$(concat!($deleted_field, " IS NULL"))?];
+                let query_str = format!(
+                    "SELECT count(*) FROM {}{}",
+                    $table_name,
+                    $crate::database::where_clause(filters, live)
+                );
+
+                let count: i64 = self
+                    .guarded(
+                        sqlx::query_scalar_with(
+                            &query_str,
+                            $crate::database::filter_arguments(filters),
+                        )
+                        .fetch_one(self.get_pool()),
+                    )
+                    .await?;
+
+                Ok(count)
+            }
+        }
+
+        #[async_trait::async_trait]
+        impl $crate::database::StoreAggregate<$model> for $crate::PostgresStorageGateway {
+            #[inline(always)]
+            async fn count_by(
+                &self,
+                group_key: &'static str,
+                filters: &[$crate::database::Filter],
+                limit: i64,
+            ) -> Result<Vec<$crate::database::GroupCount>> {
+                let live: &[&str] = &[$(concat!($deleted_field, " IS NULL"))?];
+                let query_str = format!(
+                    "SELECT key, count(*) AS count FROM (SELECT {} AS key FROM {}{}) grouped \
+                     GROUP BY key ORDER BY count DESC, key LIMIT ${}",
+                    group_key,
+                    $table_name,
+                    $crate::database::where_clause(filters, live),
+                    filters.len() + 1
+                );
+
+                let mut args = $crate::database::filter_arguments(filters);
+                let _ = args.add(limit);
+
+                let rows = self
+                    .guarded(
+                        sqlx::query_as_with::<_, $crate::database::GroupCount, _>(&query_str, args)
+                            .fetch_all(self.get_pool()),
+                    )
+                    .await?;
+
+                Ok(rows)
+            }
+
+            #[inline(always)]
+            async fn count_distinct(
+                &self,
+                field: &'static str,
+                filters: &[$crate::database::Filter],
+            ) -> Result<i64> {
+                let live: &[&str] = &[$(concat!($deleted_field, " IS NULL"))?];
+                let query_str = format!(
+                    "SELECT count(DISTINCT {}) FROM {}{}",
+                    field,
+                    $table_name,
+                    $crate::database::where_clause(filters, live)
+                );
+
+                let count: i64 = self
+                    .guarded(
+                        sqlx::query_scalar_with(
+                            &query_str,
+                            $crate::database::filter_arguments(filters),
+                        )
+                        .fetch_one(self.get_pool()),
+                    )
+                    .await?;
+
+                Ok(count)
+            }
+
+            #[inline(always)]
+            async fn percentile(
+                &self,
+                expression: &'static str,
+                fraction: f64,
+                filters: &[$crate::database::Filter],
+            ) -> Result<Option<f64>> {
+                let live: &[&str] = &[$(concat!($deleted_field, " IS NULL"))?];
+                let query_str = format!(
+                    "SELECT percentile_cont(${}) WITHIN GROUP (ORDER BY ({})::double precision) \
+                     FROM {}{}",
+                    filters.len() + 1,
+                    expression,
+                    $table_name,
+                    $crate::database::where_clause(filters, live)
+                );
+
+                let mut args = $crate::database::filter_arguments(filters);
+                let _ = args.add(fraction);
+
+                let value: Option<f64> = self
+                    .guarded(sqlx::query_scalar_with(&query_str, args).fetch_one(self.get_pool()))
+                    .await?;
+
+                Ok(value)
+            }
+        }
+    };
+}
+
+/// Implements [`StoreSearch`] over the `search_vector` column of the table.
+///
+/// The model is read with the table fields plus a `rank` and a `headline` with matches wrapped in
+/// `<mark>` tags, built from the headline expression. Headlines are built for the page only.
+#[macro_export]
+macro_rules! impl_search {
+    (
+        $model:ty,
+        $table_name:literal,
+        [$($field:ident),+ $(,)?],
+        $headline_expr:literal,
+        $order_field:literal,
+        $(soft_delete = $deleted_field:literal,)?
+    ) => {
+        #[async_trait::async_trait]
+        impl $crate::database::StoreSearch<$model> for $crate::PostgresStorageGateway {
+            #[inline(always)]
+            async fn search(&self, query: &str, limit: i64, offset: i64) -> Result<Vec<$model>> {
+                if query.trim().is_empty() {
+                    return Err(anyhow!("Found empty query to search `{}`.", $table_name));
                 }
 
                 let fields = vec![$(stringify!($field)),+].join(", ");
-                let filters = valid_fields
-                    .iter().enumerate()
-                    .map(|(i, (field_name, _))| format!("{} = ${}", field_name, i + 1))
-                    .collect::<Vec<_>>()
-                    .join(" AND ");
+                let live: &[&str] = &[$(concat!(" AND ", $deleted_field, " IS NULL"))?];
                 let query_str = format!(
-                    "SELECT {} FROM {} WHERE {} LIMIT {} OFFSET {}",
-                    fields, $table_name, filters, limit, offset
+                    "SELECT {fields}, rank, ts_headline('simple', {headline}, q, \
+                     'MaxFragments=2, MaxWords=30, MinWords=10, StartSel=<mark>, StopSel=</mark>') AS headline \
+                     FROM (SELECT {fields}, q, ts_rank(search_vector, q) AS rank \
+                     FROM {table}, websearch_to_tsquery('simple', $1) q \
+                     WHERE search_vector @@ q{live} \
+                     ORDER BY rank DESC, {order} DESC LIMIT $2 OFFSET $3) hits \
+                     ORDER BY rank DESC, {order} DESC",
+                    fields = fields,
+                    headline = $headline_expr,
+                    table = $table_name,
+                    live = live.concat(),
+                    order = $order_field,
                 );
 
-                let mut args = PgArguments::default();
-                for (_, value) in valid_fields {
-                    let _ = args.add(value);
+                let rows = self
+                    .guarded(
+                        sqlx::query_as::<_, $model>(&query_str)
+                            .bind(query)
+                            .bind(limit)
+                            .bind(offset)
+                            .fetch_all(self.get_pool()),
+                    )
+                    .await?;
+
+                Ok(rows)
+            }
+        }
+    };
+}
+
+/// Implements [`StoreStream`] reading the entities in ascending order of the order field.
+#[macro_export]
+macro_rules! impl_stream {
+    (
+        $model:ty,
+        $table_name:literal,
+        [$($field:ident),+ $(,)?],
+        $order_field:literal,
+        $(soft_delete = $deleted_field:literal,)?
+    ) => {
+        #[async_trait::async_trait]
+        impl $crate::database::StoreStream<$model> for $crate::PostgresStorageGateway {
+            async fn stream(
+                &self,
+                filters: &[$crate::database::Filter],
+                sender: tokio::sync::mpsc::Sender<Result<$model>>,
+            ) {
+                let fields = [$(stringify!($field)),+];
+                let live: &[&str] = &[$(concat!($deleted_field, " IS NULL"))?];
+                let query_str = format!(
+                    "SELECT {} FROM {}{} ORDER BY {} ASC",
+                    fields.join(", "),
+                    $table_name,
+                    $crate::database::where_clause(filters, live),
+                    $order_field,
+                );
+
+                let args = $crate::database::filter_arguments(filters);
+                let mut rows = sqlx::query_as_with::<_, $model, _>(&query_str, args)
+                    .fetch(self.get_pool());
+                loop {
+                    let row = match futures::TryStreamExt::try_next(&mut rows).await {
+                        Ok(Some(row)) => Ok(row),
+                        Ok(None) => return,
+                        Err(e) => Err(e.into()),
+                    };
+                    let failed = row.is_err();
+                    if sender.send(row).await.is_err() || failed {
+                        return;
+                    }
                 }
+            }
+        }
+    };
+}
+
+/// Implements [`StoreNearest`] over a pgvector column of the embeddings table joined to the table.
+///
+/// The model is read with the table fields plus a `similarity`, the cosine similarity of the
+/// embedding to the searched vector. The vector is bound as `REAL[]` and cast to the column type.
+#[macro_export]
+macro_rules! impl_nearest {
+    (
+        $model:ty,
+        $table_name:literal,
+        [$($field:ident),+ $(,)?],
+        $embeddings_table:literal,
+        $join_condition:literal,
+        $vector_field:literal,
+        $(soft_delete = $deleted_field:literal,)?
+    ) => {
+        #[async_trait::async_trait]
+        impl $crate::database::StoreNearest<$model> for $crate::PostgresStorageGateway {
+            #[inline(always)]
+            async fn nearest(
+                &self,
+                vector: &[f32],
+                filters: &[$crate::database::Filter],
+                limit: i64,
+                offset: i64,
+            ) -> Result<Vec<$model>> {
+                if vector.is_empty() {
+                    return Err(anyhow!("Found empty vector to search `{}`.", $table_name));
+                }
+
+                let fields = vec![$(concat!($table_name, ".", stringify!($field))),+].join(", ");
+                let live: &[&str] = &[$(concat!($table_name, ".", $deleted_field, " IS NULL"))?];
+                let distance = format!(
+                    "{} <=> ${}::real[]::vector",
+                    $vector_field,
+                    filters.len() + 1
+                );
+                let query_str = format!(
+                    "SELECT {fields}, (1 - ({distance}))::real AS similarity \
+                     FROM {table} JOIN {embeddings} ON {join}{where_clause} \
+                     ORDER BY {distance} LIMIT ${limit} OFFSET ${offset}",
+                    fields = fields,
+                    distance = distance,
+                    table = $table_name,
+                    embeddings = $embeddings_table,
+                    join = $join_condition,
+                    where_clause = $crate::database::where_clause(filters, live),
+                    limit = filters.len() + 2,
+                    offset = filters.len() + 3,
+                );
+
+                let mut args = $crate::database::filter_arguments(filters);
+                let _ = args.add(vector);
+                let _ = args.add(limit);
+                let _ = args.add(offset);
 
-                let rows = sqlx::query_as_with::<_, $model, _>(&query_str, args)
-                    .fetch_all(self.get_pool())
+                let rows = self
+                    .guarded(
+                        sqlx::query_as_with::<_, $model, _>(&query_str, args)
+                            .fetch_all(self.get_pool()),
+                    )
                     .await?;
 
                 Ok(rows)
@@ -241,6 +990,72 @@ macro_rules! impl_read_bulk_multiple {
     };
 }
 
+/// Implements [`StoreSoftDelete`] over a nullable `BIGINT` deletion time column of the table.
+#[macro_export]
+macro_rules! impl_soft_delete {
+    (
+        $model:ty,
+        $table_name:literal,
+        $id_field:literal,
+        $deleted_field:literal,
+    ) => {
+        #[async_trait::async_trait]
+        impl $crate::database::StoreSoftDelete<$model> for $crate::PostgresStorageGateway {
+            #[inline(always)]
+            async fn soft_delete(
+                &self,
+                filters: &[$crate::database::Filter],
+                deleted_at: i64,
+            ) -> Result<u64> {
+                if filters.is_empty() {
+                    return Err(anyhow!(
+                        "Found zero filters to soft delete from `{}`.",
+                        $table_name
+                    ));
+                }
+
+                let query_str = format!(
+                    "UPDATE {} SET {} = ${}{}",
+                    $table_name,
+                    $deleted_field,
+                    filters.len() + 1,
+                    $crate::database::where_clause(filters, &[concat!($deleted_field, " IS NULL")])
+                );
+
+                let mut args = $crate::database::filter_arguments(filters);
+                let _ = args.add(deleted_at);
+
+                let result = self
+                    .guarded(sqlx::query_with(&query_str, args).execute(self.get_pool()))
+                    .await?;
+
+                Ok(result.rows_affected())
+            }
+
+            #[inline(always)]
+            async fn deleted_ids(&self, deleted_before: i64, limit: i64) -> Result<Vec<String>> {
+                let query_str = format!(
+                    "SELECT {id} FROM {table} WHERE {deleted} <= $1 ORDER BY {deleted} LIMIT $2",
+                    id = $id_field,
+                    table = $table_name,
+                    deleted = $deleted_field,
+                );
+
+                let ids: Vec<String> = self
+                    .guarded(
+                        sqlx::query_scalar(&query_str)
+                            .bind(deleted_before)
+                            .bind(limit)
+                            .fetch_all(self.get_pool()),
+                    )
+                    .await?;
+
+                Ok(ids)
+            }
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! read_all_last {
     (
@@ -254,11 +1069,61 @@ macro_rules! read_all_last {
                 let fields = vec![$(stringify!($field)),+].join(", ");
                 let query_str = format!("SELECT {} FROM {}", fields, $table_name);
 
-                let rows = sqlx::query_as::<_, $model>(&query_str)
-                    .fetch_all(self.get_pool())
+                let rows = self
+                    .guarded(
+                        sqlx::query_as::<_, $model>(&query_str).fetch_all(self.get_pool()),
+                    )
                     .await?;
                 Ok(rows)
             }
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_where_clause() {
+        let filters = vec![
+            Filter::Contains("category", FilterValue::Text("bitcoin".to_string())),
+            Filter::Lte("published_timestamp", FilterValue::Int(1716300000000)),
+        ];
+
+        assert_eq!(where_clause(&[], &[]), "");
+        assert_eq!(
+            where_clause(&filters, &[]),
+            " WHERE $1 = ANY(category) AND published_timestamp <= $2"
+        );
+        assert_eq!(filter_arguments(&filters).len(), 2);
+        assert_eq!(
+            where_clause(&filters[1..], &["deleted_at IS NULL"]),
+            " WHERE published_timestamp <= $1 AND deleted_at IS NULL"
+        );
+        assert_eq!(
+            where_clause(&[], &["deleted_at IS NULL"]),
+            " WHERE deleted_at IS NULL"
+        );
+    }
+
+    #[test]
+    fn test_filter_operators() {
+        let filters = vec![
+            Filter::ILike("title", FilterValue::contains_pattern("50%_off")),
+            Filter::In(
+                "language",
+                FilterValue::TextList(vec!["eng".to_string(), "deu".to_string()]),
+            ),
+        ];
+
+        assert_eq!(
+            filters[0].value(),
+            &FilterValue::Text(r"%50\%\_off%".to_string())
+        );
+        assert_eq!(
+            where_clause(&filters, &[]),
+            " WHERE title ILIKE $1 AND language = ANY($2)"
+        );
+    }
+}
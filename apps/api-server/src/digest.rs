@@ -0,0 +1,545 @@
+use crate::{
+    config::SmtpConfig,
+    database::StorePaginateBulkEntities,
+    database::{
+        Filter, FilterValue, PostgresStorageGateway, StoreInsertBulk, StoreReadBulkEntities,
+    },
+    models::{DigestPreference, RssItemSentiment},
+};
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::{Mailbox, MultiPart},
+    transport::smtp::authentication::Credentials,
+};
+use serde::{Deserialize, Serialize};
+use shared_states::{RssItem, is_near_duplicate};
+use std::{collections::HashMap, fmt, str::FromStr, time::Duration};
+use tokio::time::interval;
+use utoipa::ToSchema;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+/// Maximal number of items of a digest.
+const DIGEST_MAX_ITEMS: usize = 10;
+/// Number of candidate items read per category.
+const DIGEST_CANDIDATES: i64 = 100;
+/// Items whose fingerprints differ in at most this many bits are the same story from several feeds.
+const DIGEST_NEAR_DUPLICATE_DISTANCE: u32 = 3;
+/// Weight of the sentiment strength of an item against its recency, both are in range `0.0..=1.0`.
+const DIGEST_SENTIMENT_WEIGHT: f64 = 0.5;
+/// Number of due preferences processed at once.
+const DUE_PAGE_SIZE: i64 = 100;
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Delay before a digest that failed to send is tried again.
+const RETRY_DELAY_MS: i64 = 60 * 60 * 1000;
+
+/// DigestFrequency is how often a user receives the email digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFrequency {
+    Daily,
+    Weekly,
+}
+
+impl DigestFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+        }
+    }
+
+    /// Time between two digests in milliseconds.
+    pub fn period_ms(&self) -> i64 {
+        match self {
+            Self::Daily => DAY_MS,
+            Self::Weekly => 7 * DAY_MS,
+        }
+    }
+}
+
+impl FromStr for DigestFrequency {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            _ => Err(anyhow!("Unknown digest frequency {value}")),
+        }
+    }
+}
+
+impl fmt::Display for DigestFrequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Digest is a rendered email digest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    pub subject: String,
+    pub text: String,
+    pub html: String,
+}
+
+impl Digest {
+    /// Renders the digest of the items.
+    ///
+    /// # Arguments
+    /// * `frequency` - The frequency of the digest, used in the subject.
+    /// * `items` - The items in the order they are listed.
+    /// * `now` - The time the digest is sent in Unix milliseconds.
+    pub fn render(frequency: DigestFrequency, items: &[RssItem], now: i64) -> Self {
+        let date = DateTime::<Utc>::from_timestamp_millis(now)
+            .unwrap_or_default()
+            .format("%Y-%m-%d");
+        let title = match frequency {
+            DigestFrequency::Daily => "Your daily news digest",
+            DigestFrequency::Weekly => "Your weekly news digest",
+        };
+
+        let mut text = format!("{title}\n\n");
+        let mut html = format!("<h1>{}</h1>\n<ul>\n", escape_html(title));
+        for item in items {
+            let summary = summary(item);
+            text.push_str(&format!("* {}\n  {}\n", item.title, item.link));
+            if !summary.is_empty() {
+                text.push_str(&format!("  {summary}\n"));
+            }
+            text.push('\n');
+            html.push_str(&format!(
+                "<li><a href=\"{}\">{}</a><p>{}</p></li>\n",
+                escape_html(&item.link),
+                escape_html(&item.title),
+                escape_html(&summary)
+            ));
+        }
+        html.push_str("</ul>\n");
+
+        Self {
+            subject: format!("{title} ({date})"),
+            text,
+            html,
+        }
+    }
+}
+
+/// Description of the item shortened to a couple of sentences.
+fn summary(item: &RssItem) -> String {
+    const MAX_CHARS: usize = 280;
+    let description = item
+        .preview_description
+        .as_deref()
+        .filter(|description| !description.trim().is_empty())
+        .unwrap_or(&item.description)
+        .trim();
+    if description.chars().count() <= MAX_CHARS {
+        return description.to_string();
+    }
+
+    let shortened: String = description.chars().take(MAX_CHARS).collect();
+    match shortened.rfind(' ') {
+        Some(end) => format!("{}…", &shortened[..end]),
+        None => format!("{shortened}…"),
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Strength of the sentiment, the confidence of a positive or negative label, 0 if it is neutral.
+fn sentiment_strength(sentiment: &RssItemSentiment) -> f64 {
+    match sentiment.label.as_str() {
+        "positive" | "negative" => f64::from(sentiment.score.clamp(0.0, 1.0)),
+        _ => 0.0,
+    }
+}
+
+/// Picks the top items ranked by recency and sentiment strength, skipping repeated items and near
+/// duplicates of picked items. Recency is the position of the publication time in the digest
+/// period, items with a strong positive or negative sentiment rank above slightly newer items.
+///
+/// # Arguments
+/// * `candidates` - The candidate items in any order.
+/// * `sentiments` - The sentiments of the candidates, unanalyzed items count as neutral.
+/// * `since` - The start of the digest period in Unix milliseconds.
+/// * `now` - The end of the digest period in Unix milliseconds.
+/// * `max_items` - The maximal number of picked items.
+///
+/// # Returns
+/// The picked items, highest ranked first.
+pub fn select_top_items(
+    mut candidates: Vec<RssItem>,
+    sentiments: &[RssItemSentiment],
+    since: i64,
+    now: i64,
+    max_items: usize,
+) -> Vec<RssItem> {
+    let strengths: HashMap<&str, f64> = sentiments
+        .iter()
+        .map(|sentiment| (sentiment.item_hash.as_str(), sentiment_strength(sentiment)))
+        .collect();
+    let period = (now - since).max(1) as f64;
+    let rank = |item: &RssItem| {
+        let recency = ((item.published_timestamp - since) as f64 / period).clamp(0.0, 1.0);
+        let strength = strengths.get(item.hash.as_str()).copied().unwrap_or(0.0);
+        recency + DIGEST_SENTIMENT_WEIGHT * strength
+    };
+    candidates.sort_by(|a, b| {
+        rank(b)
+            .total_cmp(&rank(a))
+            .then(b.published_timestamp.cmp(&a.published_timestamp))
+    });
+
+    let mut picked: Vec<RssItem> = Vec::new();
+    for item in candidates {
+        if picked.len() == max_items {
+            break;
+        }
+        let duplicate = picked.iter().any(|other| {
+            other.hash == item.hash
+                || (item.simhash != 0
+                    && other.simhash != 0
+                    && is_near_duplicate(
+                        item.fingerprint(),
+                        other.fingerprint(),
+                        DIGEST_NEAR_DUPLICATE_DISTANCE,
+                    ))
+        });
+        if !duplicate {
+            picked.push(item);
+        }
+    }
+
+    picked
+}
+
+/// DigestComposer selects the items of a digest.
+#[derive(Clone)]
+pub struct DigestComposer {
+    storage: PostgresStorageGateway,
+}
+
+impl DigestComposer {
+    pub fn new(storage: PostgresStorageGateway) -> Self {
+        Self { storage }
+    }
+
+    /// Selects the top items published since the last digest, at most one period back, ranked by
+    /// [`select_top_items`].
+    ///
+    /// # Arguments
+    /// * `preference` - The digest preference of the user.
+    /// * `now` - The current time in Unix milliseconds.
+    ///
+    /// # Returns
+    /// The items of the digest, highest ranked first, or an error if the storage fails.
+    pub async fn compose(&self, preference: &DigestPreference, now: i64) -> Result<Vec<RssItem>> {
+        let since = preference
+            .last_sent_at
+            .max(now - preference.frequency().period_ms());
+        let published = Filter::Gte("published_timestamp", FilterValue::Int(since));
+
+        let mut candidates: Vec<RssItem> = Vec::new();
+        if preference.categories.is_empty() {
+            candidates = self
+                .storage
+                .filter_paginate(&[published], None, DIGEST_CANDIDATES, 0)
+                .await?;
+        } else {
+            for category in preference.categories.iter() {
+                let filters = [
+                    published.clone(),
+                    Filter::Contains("category", FilterValue::Text(category.clone())),
+                ];
+                let items: Vec<RssItem> = self
+                    .storage
+                    .filter_paginate(&filters, None, DIGEST_CANDIDATES, 0)
+                    .await?;
+                candidates.extend(items);
+            }
+        }
+
+        let hashes: Vec<String> = candidates.iter().map(|item| item.hash.clone()).collect();
+        let sentiments: Vec<RssItemSentiment> = self.storage.read_bulk_by_ids(&hashes).await?;
+
+        Ok(select_top_items(
+            candidates,
+            &sentiments,
+            since,
+            now,
+            DIGEST_MAX_ITEMS,
+        ))
+    }
+}
+
+/// DigestMailer sends digests over SMTP.
+#[derive(Clone)]
+pub struct DigestMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl DigestMailer {
+    /// Creates a new mailer, the connection is opened on the first email.
+    ///
+    /// # Arguments
+    /// * `config` - The SMTP configuration.
+    ///
+    /// # Returns
+    /// A new mailer or an error if the sender or the relay are invalid.
+    pub fn new(config: &SmtpConfig) -> Result<Self> {
+        let mut builder = if config.starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+        }
+        .port(config.port);
+        if !config.username.is_empty() {
+            builder = builder.credentials(Credentials::new(
+                config.username.clone(),
+                config.password.clone(),
+            ));
+        }
+
+        Ok(Self {
+            transport: builder.build(),
+            from: config.from.parse()?,
+        })
+    }
+
+    /// Sends the digest.
+    ///
+    /// # Arguments
+    /// * `to` - The address of the recipient.
+    /// * `digest` - The rendered digest.
+    pub async fn send(&self, to: &str, digest: &Digest) -> Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to.parse()?)
+            .subject(&digest.subject)
+            .multipart(MultiPart::alternative_plain_html(
+                digest.text.clone(),
+                digest.html.clone(),
+            ))?;
+        self.transport.send(message).await?;
+
+        Ok(())
+    }
+
+    /// Sends the token confirming the address of a digest.
+    ///
+    /// # Arguments
+    /// * `to` - The address to confirm.
+    /// * `token` - The confirmation token.
+    pub async fn send_confirmation(&self, to: &str, token: &str) -> Result<()> {
+        let text = format!(
+            "Confirm this address to receive your news digest by sending the token below to \
+             POST /api/v1/digest/confirm.\n\n{token}\n\n\
+             If you did not subscribe to a digest, ignore this email.\n"
+        );
+        let html = format!(
+            "<p>Confirm this address to receive your news digest by sending the token below to \
+             <code>POST /api/v1/digest/confirm</code>.</p>\n<p><code>{}</code></p>\n\
+             <p>If you did not subscribe to a digest, ignore this email.</p>\n",
+            escape_html(token)
+        );
+        let confirmation = Digest {
+            subject: "Confirm your news digest address".to_string(),
+            text,
+            html,
+        };
+
+        self.send(to, &confirmation).await
+    }
+}
+
+/// DigestScheduler sends the digests that are due.
+pub struct DigestScheduler {
+    storage: PostgresStorageGateway,
+    composer: DigestComposer,
+    mailer: DigestMailer,
+}
+
+impl DigestScheduler {
+    pub fn new(storage: PostgresStorageGateway, mailer: DigestMailer) -> Self {
+        Self {
+            composer: DigestComposer::new(storage.clone()),
+            storage,
+            mailer,
+        }
+    }
+
+    /// Run the scheduler checking for due digests every minute.
+    pub async fn run(&self) -> Result<()> {
+        let mut ticker = interval(SCHEDULE_CHECK_INTERVAL);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = self.send_due(Utc::now().timestamp_millis()).await {
+                tracing::error!("Failed to send due email digests: {e}");
+            }
+        }
+    }
+
+    async fn send_due(&self, now: i64) -> Result<()> {
+        let filters = [
+            Filter::Eq("enabled", FilterValue::Bool(true)),
+            Filter::Lte("next_send_at", FilterValue::Int(now)),
+            Filter::Gte("confirmed_at", FilterValue::Int(1)),
+        ];
+        loop {
+            let due: Vec<DigestPreference> = self
+                .storage
+                .filter_paginate(&filters, None, DUE_PAGE_SIZE, 0)
+                .await?;
+            if due.is_empty() {
+                return Ok(());
+            }
+
+            for mut preference in due {
+                match self.send_digest(&preference, now).await {
+                    Ok(sent) => {
+                        if sent {
+                            preference.last_sent_at = now;
+                        }
+                        preference.next_send_at = now + preference.frequency().period_ms();
+                    }
+                    Err(e) => {
+                        tracing::error!(
+                            "Failed to send email digest of user {}: {e}",
+                            preference.user_id
+                        );
+                        preference.next_send_at = now + RETRY_DELAY_MS;
+                    }
+                }
+                self.storage
+                    .insert_bulk(std::slice::from_ref(&preference))
+                    .await?;
+            }
+        }
+    }
+
+    /// Sends the digest of the user, nothing is sent if there are no new items.
+    async fn send_digest(&self, preference: &DigestPreference, now: i64) -> Result<bool> {
+        let items = self.composer.compose(preference, now).await?;
+        if items.is_empty() {
+            return Ok(false);
+        }
+
+        let digest = Digest::render(preference.frequency(), &items, now);
+        self.mailer.send(&preference.email, &digest).await?;
+        tracing::info!(
+            "Sent email digest with {} items to user {}",
+            items.len(),
+            preference.user_id
+        );
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_states::simhash;
+
+    fn item(hash: &str, title: &str, published_timestamp: i64) -> RssItem {
+        RssItem {
+            hash: hash.to_string(),
+            title: title.to_string(),
+            link: format!("https://news.example.com/{hash}"),
+            published_timestamp,
+            simhash: simhash(title) as i64,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_select_top_items() {
+        let candidates = vec![
+            item(
+                "a",
+                "Bitcoin rallies past record high as ETF inflows surge",
+                1,
+            ),
+            item(
+                "b",
+                "Ether gas fees fall to multi year low after upgrade",
+                3,
+            ),
+            item(
+                "c",
+                "Bitcoin rallies past record high as ETF inflows surge",
+                2,
+            ),
+            item(
+                "b",
+                "Ether gas fees fall to multi year low after upgrade",
+                3,
+            ),
+            item("d", "Solana validators vote on new fee market proposal", 4),
+        ];
+
+        let hashes = |sentiments: &[RssItemSentiment]| -> Vec<String> {
+            select_top_items(candidates.clone(), sentiments, 0, 4, 10)
+                .into_iter()
+                .map(|item| item.hash)
+                .collect()
+        };
+        assert_eq!(hashes(&[]), vec!["d", "b", "c"]);
+        assert_eq!(select_top_items(candidates.clone(), &[], 0, 4, 1).len(), 1);
+
+        let sentiment = |item_hash: &str, label: &str| RssItemSentiment {
+            item_hash: item_hash.to_string(),
+            label: label.to_string(),
+            score: 0.9,
+            model: "finbert".to_string(),
+            analyzed_timestamp: 4,
+        };
+        // The strongly negative older report outranks its newer near duplicate.
+        assert_eq!(
+            hashes(&[sentiment("a", "negative"), sentiment("d", "neutral")]),
+            vec!["d", "b", "a"]
+        );
+    }
+
+    #[test]
+    fn test_digest_render() {
+        let mut first = item("a", "Bitcoin <b>rallies</b>", 1);
+        first.description = "Markets & more. ".repeat(40);
+
+        let digest = Digest::render(DigestFrequency::Weekly, &[first], 1716300000000);
+
+        assert_eq!(digest.subject, "Your weekly news digest (2024-05-21)");
+        assert!(
+            digest
+                .text
+                .contains("* Bitcoin <b>rallies</b>\n  https://news.example.com/a\n")
+        );
+        assert!(digest.html.contains("Bitcoin &lt;b&gt;rallies&lt;/b&gt;"));
+        assert!(digest.html.contains("Markets &amp; more."));
+        assert!(digest.text.contains("…"));
+    }
+
+    #[test]
+    fn test_digest_frequency() {
+        assert_eq!(
+            "weekly".parse::<DigestFrequency>().unwrap(),
+            DigestFrequency::Weekly
+        );
+        assert!("hourly".parse::<DigestFrequency>().is_err());
+        assert_eq!(
+            DigestFrequency::Weekly.period_ms(),
+            7 * DigestFrequency::Daily.period_ms()
+        );
+    }
+}
@@ -1,20 +1,57 @@
 #![allow(dead_code)]
 use crate::{
-    auth::Authenticator, database::PostgresStorageGateway, database::StoreInsertBulk,
-    database::StoreReadBulkEntities, models::SolanaUser,
+    auth::Authenticator, database::Filter, database::FilterValue, database::PostgresStorageGateway,
+    database::Sort, database::StoreAggregate, database::StoreCount, database::StoreDeleteBulk,
+    database::StoreInsertBulk, database::StoreNearest, database::StorePaginateBulkEntities,
+    database::StoreReadBulkEntities, database::StoreSearch, database::StoreSoftDelete,
+    database::StoreStream, digest::DigestMailer, media::RssItemMedia, media::read_item_media,
+    models::ADMIN_STATS_MAX_FEED_DAYS, models::AdminStatsResponse, models::AuditEvent,
+    models::AuthSession, models::Claims, models::CreateRssFeedRequest,
+    models::CreateWebhookRequest, models::DigestConfirmationRequest, models::DigestPreference,
+    models::DigestPreferencesRequest, models::FeedDayCountResponse, models::IngestionLagResponse,
+    models::PurgeRssItemsRequest, models::RSS_FEED_DEFAULT_INTERVAL_SECONDS,
+    models::RSS_ITEM_FETCH_DAY_AND_FEED_HOST, models::RssBookmark, models::RssItemSentiment,
+    models::RssSavedSearch, models::RssSearchHit, models::RssSemanticHit,
+    models::SaveSearchRequest, models::SentimentCountResponse, models::SolanaUser,
+    models::UpdateRssFeedRequest, models::WebhookSubscription,
+    object_storage::ObjectStorageGateway, organizations::Scope,
 };
 use anyhow::{Context, Result};
 use base64::{Engine as _, engine::general_purpose};
 use chrono::Utc;
 use ed25519_dalek::{Signature, Verifier, VerifyingKey};
 use hmac::{Hmac, Mac};
-use sha2::Sha256;
-use std::{convert::TryInto, time::SystemTime};
+use nats_middleware::NatsQueue;
+use rand::RngCore;
+use redis_middleware::RedisMiddleware;
+use reqwest::Url;
+use sha2::{Digest as _, Sha256};
+use shared_states::{
+    EMBEDDING_DIMENSION, EMBEDDING_REQUEST_SUBJECT, Embedding, EmbeddingRequest, EmbeddingResponse,
+    Event, EventEnvelope, RssFeedChange, RssFeedSource, RssItem, check_public_destination,
+    feed_hash, normalize_categories,
+};
+use std::{
+    convert::TryInto,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 use thiserror::Error;
+use tokio::sync::mpsc;
 use tracing::info;
 use validator::Validate;
 
 const TOKEN_LIFETIME_MS: u64 = 5 * 60 * 1000;
+/// Number of exported items read ahead of the client.
+const EXPORT_BUFFER_ITEMS: usize = 64;
+/// Prefix of the Redis keys of the consumed challenges.
+const CHALLENGE_KEY_PREFIX: &str = "auth-challenge:";
+/// Number of random bytes of a digest confirmation token.
+const DIGEST_CONFIRMATION_TOKEN_BYTES: usize = 16;
+/// Time a digest confirmation token is valid for.
+const DIGEST_CONFIRMATION_LIFETIME_MS: i64 = 24 * 60 * 60 * 1000;
+/// Minimal time between two confirmation emails to the same unconfirmed address.
+const DIGEST_CONFIRMATION_RESEND_MS: i64 = 60 * 60 * 1000;
 
 type HmacSha256 = Hmac<Sha256>;
 
@@ -37,6 +74,37 @@ pub enum Error {
 
     #[error("Token expired")]
     TokenExpired,
+
+    #[error("Token already used")]
+    TokenAlreadyUsed,
+
+    #[error("Object storage is disabled")]
+    ObjectStorageDisabled,
+
+    #[error("Feed already exists")]
+    FeedAlreadyExists,
+
+    #[error("Invalid feed URL: {0}")]
+    InvalidFeedUrl(String),
+
+    #[error("Invalid webhook URL: {0}")]
+    InvalidWebhookUrl(String),
+
+    #[error("At least one of older_than_days, feed or hashes is required")]
+    MissingPurgeCriteria,
+
+    #[error("Embedding unavailable: {0}")]
+    EmbeddingUnavailable(String),
+
+    #[error("Email is disabled")]
+    EmailDisabled,
+
+    #[error("Invalid or expired digest confirmation token")]
+    InvalidDigestConfirmation,
+}
+
+fn digest_confirmation_hash(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
 }
 
 fn parse_pubkey(base58: &str) -> Result<[u8; 32], Error> {
@@ -79,9 +147,13 @@ fn verify_signature(public_key: &[u8; 32], message: &[u8], signature: &[u8; 64])
 /// Domain is contains business logic for the application.
 pub struct Domain {
     storage: PostgresStorageGateway,
-    auth: Authenticator,
+    objects: Option<ObjectStorageGateway>,
+    queue: NatsQueue,
+    auth: Arc<Authenticator>,
+    redis: RedisMiddleware,
     mac: Hmac<Sha256>,
     server_origin: String,
+    mailer: Option<DigestMailer>,
 }
 
 impl Domain {
@@ -89,14 +161,20 @@ impl Domain {
     ///
     /// # Arguments
     /// * `storage` - The storage gateway to use for data persistence.
+    /// * `objects` - The object storage gateway for article snapshots and media, None if disabled.
+    /// * `queue` - The message queue the workers are notified on.
     /// * `auth` - The authentication gateway to use for user authentication.
+    /// * `redis` - The Redis client the consumed challenges are recorded in.
     /// * `generator_secret` - The generator secret to use for generating tokens.
     ///
     /// # Returns
     /// A new instance of the Domain struct.
     pub fn try_new(
         storage: PostgresStorageGateway,
-        auth: Authenticator,
+        objects: Option<ObjectStorageGateway>,
+        queue: NatsQueue,
+        auth: Arc<Authenticator>,
+        redis: RedisMiddleware,
         generator_secret: [u8; 32],
         server_origin: String,
     ) -> Result<Self> {
@@ -104,12 +182,26 @@ impl Domain {
             .context("Wrong genrator secret key length")?;
         Ok(Self {
             storage,
+            objects,
+            queue,
             auth,
+            redis,
             mac,
             server_origin,
+            mailer: None,
         })
     }
 
+    /// Sets the mailer the digest confirmation tokens are sent with, digests cannot be saved
+    /// without it.
+    ///
+    /// # Arguments
+    /// * `mailer` - The mailer, None if email is disabled.
+    pub fn with_digest_mailer(mut self, mailer: Option<DigestMailer>) -> Self {
+        self.mailer = mailer;
+        self
+    }
+
     pub async fn issue_token_challenge_base64(
         &self,
         solana_wallet: &str,
@@ -166,6 +258,7 @@ impl Domain {
         let signature = parse_signature(signature)?;
 
         verify_signature(&solana_wallet_public_key, &token, &signature)?;
+        self.consume_challenge(&token, expires_at).await?;
 
         let solana_user = SolanaUser {
             solana_wallet_public_key,
@@ -187,7 +280,7 @@ impl Domain {
     /// * `signature` - The signature to verify.
     ///
     /// # Returns
-    /// * `Result<String>` - JWT token or error message otherwise.
+    /// * `Result<(String, Claims)>` - JWT token with its claims or error message otherwise.
     #[inline(always)]
     pub async fn login(
         &self,
@@ -195,7 +288,7 @@ impl Domain {
         token_b64: &str,
         expires_at: u64,
         signature: &str,
-    ) -> Result<String> {
+    ) -> Result<(String, Claims)> {
         let solana_wallet_public_key = parse_pubkey(solana_wallet)?;
         let candidate_token = self.generate_token(&solana_wallet_public_key, expires_at, None)?;
         let token = general_purpose::URL_SAFE_NO_PAD.decode(token_b64)?;
@@ -223,6 +316,7 @@ impl Domain {
         let signature = parse_signature(signature)?;
 
         verify_signature(&solana_user.solana_wallet_public_key, &token, &signature)?;
+        self.consume_challenge(&token, expires_at).await?;
 
         let solana_wallet_public_key =
             bs58::encode(solana_user.solana_wallet_public_key).into_string();
@@ -234,6 +328,938 @@ impl Domain {
         Ok(jwt)
     }
 
+    /// Reads a user by the Solana wallet.
+    ///
+    /// # Arguments
+    /// * `solana_wallet` - The base58 encoded wallet public key.
+    ///
+    /// # Returns
+    /// The user, None if not registered, or an error if the wallet is invalid or the storage fails.
+    pub async fn user(&self, solana_wallet: &str) -> Result<Option<SolanaUser>> {
+        let solana_wallet_public_key = parse_pubkey(solana_wallet)?;
+        let users: Vec<SolanaUser> = self
+            .storage
+            .read_bulk_by_ids(&[solana_wallet_public_key])
+            .await?;
+        Ok(users.into_iter().next())
+    }
+
+    /// Queues RSS items for ingestion, they are stored by the RSS items consumer.
+    ///
+    /// # Arguments
+    /// * `items` - The items to ingest.
+    ///
+    /// # Returns
+    /// The hashes of the queued items, or an error if publishing fails.
+    pub async fn ingest_rss_items(&self, items: Vec<RssItem>) -> Result<Vec<String>> {
+        let mut hashes = Vec::with_capacity(items.len());
+        for item in items {
+            let hash = item.hash.clone();
+            self.queue
+                .publish(RssItem::SUBJECT, &EventEnvelope::new(item))
+                .await?;
+            hashes.push(hash);
+        }
+
+        Ok(hashes)
+    }
+
+    /// Reads the latest sentiment of RSS items.
+    ///
+    /// # Arguments
+    /// * `hashes` - The hashes of the items.
+    ///
+    /// # Returns
+    /// The sentiments of the analyzed items, or an error if the storage fails.
+    pub async fn rss_item_sentiments(&self, hashes: &[String]) -> Result<Vec<RssItemSentiment>> {
+        self.storage.read_bulk_by_ids(hashes).await
+    }
+
+    /// Reads a page of RSS items.
+    ///
+    /// # Arguments
+    /// * `filters` - The conditions the items have to meet.
+    /// * `sort` - The field to sort by, newest first if not given.
+    /// * `limit` - The number of items per page.
+    /// * `offset` - The number of items to skip.
+    ///
+    /// # Returns
+    /// The items or an error if the storage fails.
+    pub async fn rss_items(
+        &self,
+        filters: &[Filter],
+        sort: Option<&Sort>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RssItem>> {
+        self.storage
+            .filter_paginate(filters, sort, limit, offset)
+            .await
+    }
+
+    /// Counts RSS items.
+    ///
+    /// # Arguments
+    /// * `filters` - The conditions the items have to meet.
+    ///
+    /// # Returns
+    /// The number of items or an error if the storage fails.
+    pub async fn count_rss_items(&self, filters: &[Filter]) -> Result<i64> {
+        StoreCount::<RssItem>::count(&self.storage, filters).await
+    }
+
+    /// Deletes the RSS items meeting all criteria of the request, they are no longer served and
+    /// are purged from storage by the retention policy after the grace period.
+    ///
+    /// # Arguments
+    /// * `request` - The criteria of the items to delete.
+    ///
+    /// # Returns
+    /// The number of deleted items, or an error if the request is invalid or the storage fails.
+    pub async fn delete_rss_items(&self, request: &PurgeRssItemsRequest) -> Result<u64> {
+        request.validate()?;
+        let now = Utc::now().timestamp_millis();
+        let filters = request.filters(now);
+        if filters.is_empty() {
+            return Err(Error::MissingPurgeCriteria.into());
+        }
+
+        StoreSoftDelete::<RssItem>::soft_delete(&self.storage, &filters, now).await
+    }
+
+    /// Streams the RSS items meeting the filters, oldest first, reading ahead of the receiver by a
+    /// bounded number of items. Reading stops once the receiver is dropped.
+    ///
+    /// # Arguments
+    /// * `filters` - The conditions the items have to meet.
+    ///
+    /// # Returns
+    /// The receiver of the items, a storage failure is received as the last message.
+    pub fn export_rss_items(&self, filters: Vec<Filter>) -> mpsc::Receiver<Result<RssItem>> {
+        let (sender, receiver) = mpsc::channel(EXPORT_BUFFER_ITEMS);
+        let storage = self.storage.clone();
+        tokio::spawn(async move {
+            StoreStream::<RssItem>::stream(&storage, &filters, sender).await;
+        });
+
+        receiver
+    }
+
+    /// Reads an RSS item by its hash.
+    ///
+    /// # Arguments
+    /// * `hash` - The hash of the item.
+    ///
+    /// # Returns
+    /// The item, None if it does not exist, or an error if the storage fails.
+    pub async fn rss_item(&self, hash: &str) -> Result<Option<RssItem>> {
+        let items: Vec<RssItem> = self.storage.read_bulk_by_ids(&[hash.to_string()]).await?;
+        Ok(items.into_iter().next())
+    }
+
+    /// Reads the archived media of an RSS item with presigned download URLs.
+    ///
+    /// # Arguments
+    /// * `hash` - The hash of the item.
+    ///
+    /// # Returns
+    /// The media with their URLs and the URL validity, None if the item does not exist, or an
+    /// error if the object storage is disabled or the storage fails.
+    pub async fn rss_item_media(
+        &self,
+        hash: &str,
+    ) -> Result<Option<(Vec<(RssItemMedia, String)>, Duration)>> {
+        let Some(objects) = &self.objects else {
+            return Err(Error::ObjectStorageDisabled.into());
+        };
+        if self.rss_item(hash).await?.is_none() {
+            return Ok(None);
+        }
+
+        let expires_in = objects.presign_expiry();
+        let mut media = Vec::new();
+        for item_media in read_item_media(&self.storage, hash).await? {
+            let url = objects
+                .presign_get(&item_media.object_key, expires_in)
+                .await?;
+            media.push((item_media, url));
+        }
+
+        Ok(Some((media, expires_in)))
+    }
+
+    /// Bookmarks an RSS item for the user, bookmarking it again keeps the original bookmark.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization the bookmark is saved in.
+    /// * `hash` - The hash of the item.
+    ///
+    /// # Returns
+    /// The bookmark with the item, None if the item does not exist, or an error if the storage fails.
+    pub async fn bookmark_rss_item(
+        &self,
+        scope: &Scope,
+        hash: &str,
+    ) -> Result<Option<(RssBookmark, RssItem)>> {
+        let Some(item) = self.rss_item(hash).await? else {
+            return Ok(None);
+        };
+
+        let id = RssBookmark::id(&scope.owner(), hash);
+        let existing: Vec<RssBookmark> = self.storage.read_bulk_by_ids(&[id]).await?;
+        let bookmark = match existing.into_iter().next() {
+            Some(bookmark) => bookmark,
+            None => {
+                let bookmark = RssBookmark::new(scope, hash, Utc::now().timestamp_millis());
+                self.storage
+                    .insert_bulk(std::slice::from_ref(&bookmark))
+                    .await?;
+                bookmark
+            }
+        };
+
+        Ok(Some((bookmark, item)))
+    }
+
+    /// Removes the bookmark of an RSS item.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization of the bookmark.
+    /// * `hash` - The hash of the item.
+    ///
+    /// # Returns
+    /// True if the item was bookmarked, or an error if the storage fails.
+    pub async fn remove_rss_bookmark(&self, scope: &Scope, hash: &str) -> Result<bool> {
+        let deleted = StoreDeleteBulk::<RssBookmark, String>::delete_bulk(
+            &self.storage,
+            &[RssBookmark::id(&scope.owner(), hash)],
+        )
+        .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Reads a page of the bookmarks of the user or the organization, newest first.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization.
+    /// * `limit` - The number of bookmarks per page.
+    /// * `offset` - The number of bookmarks to skip.
+    ///
+    /// # Returns
+    /// The bookmarks with their items or an error if the storage fails.
+    pub async fn rss_bookmarks(
+        &self,
+        scope: &Scope,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<(RssBookmark, RssItem)>> {
+        let bookmarks: Vec<RssBookmark> = self
+            .storage
+            .filter_paginate(&scope.filters(), None, limit, offset)
+            .await?;
+        if bookmarks.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let hashes: Vec<String> = bookmarks.iter().map(|b| b.item_hash.clone()).collect();
+        let mut items: Vec<RssItem> = self.storage.read_bulk_by_ids(&hashes).await?;
+
+        Ok(bookmarks
+            .into_iter()
+            .filter_map(|bookmark| {
+                let position = items
+                    .iter()
+                    .position(|item| item.hash == bookmark.item_hash)?;
+                Some((bookmark, items.swap_remove(position)))
+            })
+            .collect())
+    }
+
+    /// Counts the bookmarks of the user or the organization.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization.
+    ///
+    /// # Returns
+    /// The number of bookmarks or an error if the storage fails.
+    pub async fn count_rss_bookmarks(&self, scope: &Scope) -> Result<i64> {
+        StoreCount::<RssBookmark>::count(&self.storage, &scope.filters()).await
+    }
+
+    /// Saves a named search query of the user or the organization, replacing a search saved under
+    /// the same name.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization.
+    /// * `request` - The name and the query, surrounding whitespace is ignored.
+    ///
+    /// # Returns
+    /// The saved search, or an error if the request is invalid or the storage fails.
+    pub async fn save_rss_search(
+        &self,
+        scope: &Scope,
+        request: &SaveSearchRequest,
+    ) -> Result<RssSavedSearch> {
+        let request = SaveSearchRequest {
+            name: request.name.trim().to_string(),
+            query: request.query.trim().to_string(),
+        };
+        request.validate()?;
+
+        let search = RssSavedSearch::new(
+            scope,
+            &request.name,
+            &request.query,
+            Utc::now().timestamp_millis(),
+        );
+        self.storage
+            .insert_bulk(std::slice::from_ref(&search))
+            .await?;
+
+        Ok(search)
+    }
+
+    /// Reads a page of the saved searches of the user or the organization, most recently saved
+    /// first.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization.
+    /// * `limit` - The number of searches per page.
+    /// * `offset` - The number of searches to skip.
+    ///
+    /// # Returns
+    /// The saved searches or an error if the storage fails.
+    pub async fn rss_saved_searches(
+        &self,
+        scope: &Scope,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RssSavedSearch>> {
+        self.storage
+            .filter_paginate(&scope.filters(), None, limit, offset)
+            .await
+    }
+
+    /// Counts the saved searches of the user or the organization.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization.
+    ///
+    /// # Returns
+    /// The number of saved searches or an error if the storage fails.
+    pub async fn count_rss_saved_searches(&self, scope: &Scope) -> Result<i64> {
+        StoreCount::<RssSavedSearch>::count(&self.storage, &scope.filters()).await
+    }
+
+    /// Deletes a saved search of the user or the organization.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization.
+    /// * `name` - The name of the search.
+    ///
+    /// # Returns
+    /// True if the search existed, or an error if the storage fails.
+    pub async fn delete_rss_saved_search(&self, scope: &Scope, name: &str) -> Result<bool> {
+        let deleted = StoreDeleteBulk::<RssSavedSearch, String>::delete_bulk(
+            &self.storage,
+            &[RssSavedSearch::id(&scope.owner(), name.trim())],
+        )
+        .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Reads a page of the RSS feed sources, newest first.
+    ///
+    /// # Arguments
+    /// * `limit` - The number of feeds per page.
+    /// * `offset` - The number of feeds to skip.
+    ///
+    /// # Returns
+    /// The feeds or an error if the storage fails.
+    pub async fn rss_feeds(&self, limit: i64, offset: i64) -> Result<Vec<RssFeedSource>> {
+        self.storage.filter_paginate(&[], None, limit, offset).await
+    }
+
+    /// Counts the RSS feed sources.
+    ///
+    /// # Returns
+    /// The number of feeds or an error if the storage fails.
+    pub async fn count_rss_feeds(&self) -> Result<i64> {
+        StoreCount::<RssFeedSource>::count(&self.storage, &[]).await
+    }
+
+    /// Reads an RSS feed source by its hash.
+    ///
+    /// # Arguments
+    /// * `hash` - The hash of the feed URL.
+    ///
+    /// # Returns
+    /// The feed, None if it does not exist, or an error if the storage fails.
+    pub async fn rss_feed(&self, hash: &str) -> Result<Option<RssFeedSource>> {
+        let feeds: Vec<RssFeedSource> = self.storage.read_bulk_by_ids(&[hash.to_string()]).await?;
+        Ok(feeds.into_iter().next())
+    }
+
+    /// Adds an RSS feed source and notifies the rss-worker.
+    ///
+    /// # Arguments
+    /// * `request` - The feed URL and poll interval.
+    ///
+    /// # Returns
+    /// The added feed, or an error if the request is invalid, the feed exists or the storage fails.
+    pub async fn create_rss_feed(&self, request: &CreateRssFeedRequest) -> Result<RssFeedSource> {
+        request.validate()?;
+        let url = request.url.trim();
+        match Url::parse(url) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => (),
+            _ => return Err(Error::InvalidFeedUrl(url.to_string()).into()),
+        }
+        if self.rss_feed(&feed_hash(url)).await?.is_some() {
+            return Err(Error::FeedAlreadyExists.into());
+        }
+
+        let feed = RssFeedSource::new(
+            url,
+            request
+                .interval_seconds
+                .unwrap_or(RSS_FEED_DEFAULT_INTERVAL_SECONDS),
+            Utc::now().timestamp_millis(),
+        );
+        self.storage
+            .insert_bulk(std::slice::from_ref(&feed))
+            .await?;
+        self.notify_feed_change(&feed, false).await;
+
+        Ok(feed)
+    }
+
+    /// Updates the poll interval or the enabled flag of an RSS feed source and notifies the rss-worker.
+    ///
+    /// # Arguments
+    /// * `hash` - The hash of the feed URL.
+    /// * `request` - The changed settings, missing settings are kept.
+    ///
+    /// # Returns
+    /// The updated feed, None if it does not exist, or an error if the request is invalid or the
+    /// storage fails.
+    pub async fn update_rss_feed(
+        &self,
+        hash: &str,
+        request: &UpdateRssFeedRequest,
+    ) -> Result<Option<RssFeedSource>> {
+        request.validate()?;
+        let Some(mut feed) = self.rss_feed(hash).await? else {
+            return Ok(None);
+        };
+
+        if let Some(interval_seconds) = request.interval_seconds {
+            feed.interval_seconds = interval_seconds;
+        }
+        if let Some(enabled) = request.enabled {
+            feed.enabled = enabled;
+        }
+        feed.updated_at = Utc::now().timestamp_millis();
+        self.storage
+            .insert_bulk(std::slice::from_ref(&feed))
+            .await?;
+        self.notify_feed_change(&feed, false).await;
+
+        Ok(Some(feed))
+    }
+
+    /// Deletes an RSS feed source and notifies the rss-worker, items of the feed are kept.
+    ///
+    /// # Arguments
+    /// * `hash` - The hash of the feed URL.
+    ///
+    /// # Returns
+    /// The deleted feed, None if it does not exist, or an error if the storage fails.
+    pub async fn delete_rss_feed(&self, hash: &str) -> Result<Option<RssFeedSource>> {
+        let Some(feed) = self.rss_feed(hash).await? else {
+            return Ok(None);
+        };
+
+        StoreDeleteBulk::<RssFeedSource, String>::delete_bulk(
+            &self.storage,
+            std::slice::from_ref(&feed.hash),
+        )
+        .await?;
+        self.notify_feed_change(&feed, true).await;
+
+        Ok(Some(feed))
+    }
+
+    /// Publishes the feed change, the rss-worker reloads all feeds on restart so a lost
+    /// notification is only logged.
+    async fn notify_feed_change(&self, feed: &RssFeedSource, removed: bool) {
+        let change = RssFeedChange {
+            feed: feed.clone(),
+            removed,
+        };
+        if let Err(e) = self
+            .queue
+            .publish(RssFeedChange::SUBJECT, &EventEnvelope::new(change))
+            .await
+        {
+            tracing::error!(
+                "Failed to notify about change of RSS feed ( {} ): {e}",
+                feed.url
+            );
+        }
+    }
+
+    /// Registers a webhook of the user or the organization, replacing the subscription of the
+    /// same URL.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization.
+    /// * `request` - The URL, the event types and the signing secret.
+    ///
+    /// # Returns
+    /// The subscription, or an error if the request is invalid or the storage fails.
+    pub async fn create_webhook(
+        &self,
+        scope: &Scope,
+        request: &CreateWebhookRequest,
+    ) -> Result<WebhookSubscription> {
+        request.validate()?;
+        let url = request.url.trim();
+        let parsed = Url::parse(url).map_err(|_| Error::InvalidWebhookUrl(url.to_string()))?;
+        // Deliveries are sent from inside the cluster, internal services must not be reachable.
+        if let Err(e) = check_public_destination(&parsed).await {
+            return Err(Error::InvalidWebhookUrl(format!("{url}: {e}")).into());
+        }
+
+        let subscription = WebhookSubscription::new(
+            scope,
+            url,
+            &request.event_types,
+            &request.secret,
+            Utc::now().timestamp_millis(),
+        );
+        self.storage
+            .insert_bulk(std::slice::from_ref(&subscription))
+            .await?;
+
+        Ok(subscription)
+    }
+
+    /// Reads a page of the webhooks of the user or the organization, newest first.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization.
+    /// * `limit` - The number of webhooks per page.
+    /// * `offset` - The number of webhooks to skip.
+    ///
+    /// # Returns
+    /// The webhooks or an error if the storage fails.
+    pub async fn webhooks(
+        &self,
+        scope: &Scope,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<WebhookSubscription>> {
+        self.storage
+            .filter_paginate(&scope.filters(), None, limit, offset)
+            .await
+    }
+
+    /// Counts the webhooks of the user or the organization.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization.
+    ///
+    /// # Returns
+    /// The number of webhooks or an error if the storage fails.
+    pub async fn count_webhooks(&self, scope: &Scope) -> Result<i64> {
+        StoreCount::<WebhookSubscription>::count(&self.storage, &scope.filters()).await
+    }
+
+    /// Deletes a webhook of the user or the organization.
+    ///
+    /// # Arguments
+    /// * `scope` - The user or the organization.
+    /// * `id` - The ID of the webhook.
+    ///
+    /// # Returns
+    /// True if the scope had the webhook, or an error if the storage fails.
+    pub async fn delete_webhook(&self, scope: &Scope, id: &str) -> Result<bool> {
+        let subscriptions: Vec<WebhookSubscription> =
+            self.storage.read_bulk_by_ids(&[id.to_string()]).await?;
+        if !subscriptions
+            .iter()
+            .any(|subscription| scope.owns(&subscription.user_id, &subscription.org_id))
+        {
+            return Ok(false);
+        }
+
+        let deleted = StoreDeleteBulk::<WebhookSubscription, String>::delete_bulk(
+            &self.storage,
+            &[id.to_string()],
+        )
+        .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Appends a security-relevant event to the audit log.
+    ///
+    /// # Arguments
+    /// * `event` - The event.
+    ///
+    /// # Returns
+    /// An error if the storage fails.
+    pub async fn record_audit_event(&self, event: AuditEvent) -> Result<()> {
+        self.storage.insert_bulk(&[event]).await?;
+        Ok(())
+    }
+
+    /// Reads a page of the audit log, newest first.
+    ///
+    /// # Arguments
+    /// * `filters` - Conditions the events have to meet.
+    /// * `limit` - The number of events per page.
+    /// * `offset` - The number of events to skip.
+    ///
+    /// # Returns
+    /// The events or an error if the storage fails.
+    pub async fn audit_events(
+        &self,
+        filters: &[Filter],
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AuditEvent>> {
+        self.storage
+            .filter_paginate(filters, None, limit, offset)
+            .await
+    }
+
+    /// Counts the events of the audit log.
+    ///
+    /// # Arguments
+    /// * `filters` - Conditions the events have to meet.
+    ///
+    /// # Returns
+    /// The number of events or an error if the storage fails.
+    pub async fn count_audit_events(&self, filters: &[Filter]) -> Result<i64> {
+        StoreCount::<AuditEvent>::count(&self.storage, filters).await
+    }
+
+    /// Computes the statistics of the operations dashboard.
+    ///
+    /// # Arguments
+    /// * `days` - The number of days the statistics cover.
+    ///
+    /// # Returns
+    /// The statistics or an error if the storage fails.
+    pub async fn admin_stats(&self, days: i64) -> Result<AdminStatsResponse> {
+        let now = Utc::now().timestamp_millis();
+        let since = now - days * 24 * 60 * 60 * 1000;
+        let fetched = [Filter::Gte("fetched_timestamp", FilterValue::Int(since))];
+        let fetched_with_publication = [
+            Filter::Gte("fetched_timestamp", FilterValue::Int(since)),
+            Filter::Eq("published_timestamp_estimated", FilterValue::Bool(false)),
+        ];
+        let analyzed = [Filter::Gte("analyzed_timestamp", FilterValue::Int(since))];
+        let active = [Filter::Gte("expires_at", FilterValue::Int(now))];
+        let lag = "fetched_timestamp - published_timestamp";
+
+        let (feed_days, sentiments, registered_users, active_users, p50, p95, last_fetch) = tokio::try_join!(
+            StoreAggregate::<RssItem>::count_by(
+                &self.storage,
+                RSS_ITEM_FETCH_DAY_AND_FEED_HOST,
+                &fetched,
+                ADMIN_STATS_MAX_FEED_DAYS
+            ),
+            StoreAggregate::<RssItemSentiment>::count_by(&self.storage, "label", &analyzed, 10),
+            StoreCount::<SolanaUser>::count(&self.storage, &[]),
+            StoreAggregate::<AuthSession>::count_distinct(&self.storage, "user_id", &active),
+            StoreAggregate::<RssItem>::percentile(
+                &self.storage,
+                lag,
+                0.5,
+                &fetched_with_publication
+            ),
+            StoreAggregate::<RssItem>::percentile(
+                &self.storage,
+                lag,
+                0.95,
+                &fetched_with_publication
+            ),
+            StoreAggregate::<RssItem>::percentile(
+                &self.storage,
+                "fetched_timestamp",
+                1.0,
+                &fetched
+            ),
+        )?;
+
+        let mut items_per_feed_per_day: Vec<FeedDayCountResponse> = feed_days
+            .into_iter()
+            .map(FeedDayCountResponse::from)
+            .collect();
+        items_per_feed_per_day.sort_by(|a, b| {
+            b.day
+                .cmp(&a.day)
+                .then(b.count.cmp(&a.count))
+                .then(a.feed.cmp(&b.feed))
+        });
+
+        Ok(AdminStatsResponse {
+            since,
+            items_per_feed_per_day,
+            sentiment_distribution: sentiments
+                .into_iter()
+                .map(|group| SentimentCountResponse {
+                    label: group.key,
+                    count: group.count,
+                })
+                .collect(),
+            registered_users,
+            active_users,
+            ingestion_lag: IngestionLagResponse {
+                p50_ms: p50,
+                p95_ms: p95,
+                since_last_fetch_ms: last_fetch.map(|fetched| now - fetched as i64),
+            },
+        })
+    }
+
+    /// Reads the email digest preferences of the user.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// The preferences, None if the user has no digest, or an error if the storage fails.
+    pub async fn digest_preferences(&self, user_id: &str) -> Result<Option<DigestPreference>> {
+        let preferences: Vec<DigestPreference> = self
+            .storage
+            .read_bulk_by_ids(&[user_id.to_string()])
+            .await?;
+        Ok(preferences.into_iter().next())
+    }
+
+    /// Saves the email digest preferences of the user.
+    ///
+    /// The schedule of an existing digest is kept unless its frequency changes, a new digest is
+    /// first sent one period after it is saved. Digests are only sent to confirmed addresses, a
+    /// new or changed address is mailed a confirmation token, at most once per hour.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the user.
+    /// * `request` - The address, the frequency and the categories of the digest.
+    ///
+    /// # Returns
+    /// The saved preferences, or an error if the request is invalid or the storage fails.
+    pub async fn save_digest_preferences(
+        &self,
+        user_id: &str,
+        request: &DigestPreferencesRequest,
+    ) -> Result<DigestPreference> {
+        request.validate()?;
+        let now = Utc::now().timestamp_millis();
+        let existing = self.digest_preferences(user_id).await?;
+
+        let email = request.email.trim().to_string();
+        let frequency = request.frequency.as_str().to_string();
+        let next_send_at = match &existing {
+            Some(existing) if existing.frequency == frequency => existing.next_send_at,
+            _ => now + request.frequency.period_ms(),
+        };
+        let mut preference = DigestPreference {
+            user_id: user_id.to_string(),
+            email,
+            frequency,
+            categories: normalize_categories(&request.categories),
+            enabled: request.enabled.unwrap_or(true),
+            last_sent_at: existing
+                .as_ref()
+                .map_or(0, |existing| existing.last_sent_at),
+            next_send_at,
+            updated_at: now,
+            confirmation_token_hash: String::new(),
+            confirmation_sent_at: 0,
+            confirmed_at: 0,
+        };
+
+        let same_email = existing
+            .as_ref()
+            .filter(|existing| existing.email.eq_ignore_ascii_case(&preference.email));
+        match same_email {
+            Some(existing)
+                if existing.confirmed_at > 0
+                    || existing.confirmation_sent_at + DIGEST_CONFIRMATION_RESEND_MS > now =>
+            {
+                preference.confirmation_token_hash = existing.confirmation_token_hash.clone();
+                preference.confirmation_sent_at = existing.confirmation_sent_at;
+                preference.confirmed_at = existing.confirmed_at;
+            }
+            _ => {
+                let mailer = self.mailer.as_ref().ok_or(Error::EmailDisabled)?;
+                let mut bytes = [0u8; DIGEST_CONFIRMATION_TOKEN_BYTES];
+                rand::rng().fill_bytes(&mut bytes);
+                let token = bs58::encode(bytes).into_string();
+                mailer.send_confirmation(&preference.email, &token).await?;
+                preference.confirmation_token_hash = digest_confirmation_hash(&token);
+                preference.confirmation_sent_at = now;
+            }
+        }
+        self.storage
+            .insert_bulk(std::slice::from_ref(&preference))
+            .await?;
+
+        Ok(preference)
+    }
+
+    /// Confirms the address of the email digest of the user with the token mailed to it.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the user.
+    /// * `request` - The mailed token.
+    ///
+    /// # Returns
+    /// The confirmed preferences, or an error if the token is wrong or expired or the storage
+    /// fails.
+    pub async fn confirm_digest(
+        &self,
+        user_id: &str,
+        request: &DigestConfirmationRequest,
+    ) -> Result<DigestPreference> {
+        request.validate()?;
+        let now = Utc::now().timestamp_millis();
+        let mut preference = self
+            .digest_preferences(user_id)
+            .await?
+            .ok_or(Error::InvalidDigestConfirmation)?;
+        if preference.confirmed_at > 0 {
+            return Ok(preference);
+        }
+        if preference.confirmation_token_hash.is_empty()
+            || preference.confirmation_token_hash != digest_confirmation_hash(request.token.trim())
+            || preference.confirmation_sent_at + DIGEST_CONFIRMATION_LIFETIME_MS < now
+        {
+            return Err(Error::InvalidDigestConfirmation.into());
+        }
+
+        preference.confirmation_token_hash = String::new();
+        preference.confirmed_at = now;
+        preference.updated_at = now;
+        self.storage
+            .insert_bulk(std::slice::from_ref(&preference))
+            .await?;
+
+        Ok(preference)
+    }
+
+    /// Deletes the email digest of the user.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// True if the user had a digest, or an error if the storage fails.
+    pub async fn delete_digest_preferences(&self, user_id: &str) -> Result<bool> {
+        let deleted = StoreDeleteBulk::<DigestPreference, String>::delete_bulk(
+            &self.storage,
+            &[user_id.to_string()],
+        )
+        .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Full-text searches RSS items.
+    ///
+    /// # Arguments
+    /// * `query` - The web search style query.
+    /// * `limit` - The number of items per page.
+    /// * `offset` - The number of items to skip.
+    ///
+    /// # Returns
+    /// The matching items with rank and highlighted headline, best first, or an error if the storage fails.
+    pub async fn search_rss_items(
+        &self,
+        query: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RssSearchHit>> {
+        self.storage.search(query, limit, offset).await
+    }
+
+    /// Searches RSS items by the meaning of the query, the nearest embeddings first.
+    ///
+    /// Queries and items are embedded by the embedding service outside of this repository, see
+    /// the README.
+    ///
+    /// # Arguments
+    /// * `query` - The text to find items of similar meaning to.
+    /// * `filters` - The conditions the items have to meet.
+    /// * `limit` - The number of items per page.
+    /// * `offset` - The number of items to skip.
+    ///
+    /// # Returns
+    /// The items with their similarity to the query, best first, or an error if the query cannot
+    /// be embedded or the storage fails.
+    pub async fn semantic_search_rss_items(
+        &self,
+        query: &str,
+        filters: &[Filter],
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RssSemanticHit>> {
+        let embedding = self.embed_query(query).await?;
+        self.storage
+            .nearest(&embedding.vector, filters, limit, offset)
+            .await
+    }
+
+    /// Requests the embedding of the query from the embedding pipeline.
+    async fn embed_query(&self, query: &str) -> Result<Embedding> {
+        let request = EmbeddingRequest {
+            texts: vec![query.to_string()],
+        };
+        let response: EmbeddingResponse = self
+            .queue
+            .request(EMBEDDING_REQUEST_SUBJECT, &request)
+            .await
+            .map_err(|e| Error::EmbeddingUnavailable(e.to_string()))?;
+
+        let embedding = response.embeddings.into_iter().next().ok_or_else(|| {
+            Error::EmbeddingUnavailable("no embedding of the query returned".to_string())
+        })?;
+        if embedding.vector.len() != EMBEDDING_DIMENSION {
+            return Err(Error::EmbeddingUnavailable(format!(
+                "expected {EMBEDDING_DIMENSION} dimensions, got {} from {}",
+                embedding.vector.len(),
+                response.model
+            ))
+            .into());
+        }
+
+        Ok(embedding)
+    }
+
+    /// Marks the signed challenge as used until it expires, so it cannot be replayed.
+    ///
+    /// # Arguments
+    /// * `token` - The challenge token.
+    /// * `expires_at` - The expiration time of the token in Unix milliseconds.
+    ///
+    /// # Returns
+    /// An error if the challenge was already used or Redis fails.
+    async fn consume_challenge(&self, token: &[u8], expires_at: u64) -> Result<()> {
+        let now = Utc::now().timestamp_millis() as u64;
+        let ttl_seconds = expires_at.saturating_sub(now).div_ceil(1000).max(1);
+        let key = format!("{CHALLENGE_KEY_PREFIX}{}", hex::encode(token));
+        if !self
+            .redis
+            .store_if_absent_with_ttl(&key, "1", ttl_seconds)
+            .await?
+        {
+            return Err(Error::TokenAlreadyUsed.into());
+        }
+        Ok(())
+    }
+
     fn generate_token(
         &self,
         solana_wallet: &[u8],
@@ -0,0 +1,406 @@
+use crate::{
+    database::StorageError, domain, models::ProblemDetails, organizations::OrganizationError,
+    payments::PaymentError, telemetry::Metrics,
+};
+use actix_web::{HttpResponse, HttpResponseBuilder, ResponseError, http::StatusCode};
+use std::{collections::BTreeMap, fmt};
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+/// Media type of the RFC 7807 problem details bodies of failed requests.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// ApiError is a failed request, answered with an RFC 7807 `application/problem+json` body.
+///
+/// Handlers return it as the error of their result, failures of the domain and the services are
+/// mapped to the status telling the client what went wrong by [`ApiError::failure`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    status: StatusCode,
+    error: String,
+    detail: String,
+    request_id: String,
+    fields: BTreeMap<String, Vec<String>>,
+}
+
+impl ApiError {
+    /// Creates the error.
+    ///
+    /// # Arguments
+    /// * `status` - The status of the response.
+    /// * `error` - The machine readable kind of the error, e.g. `rss_item_not_found`.
+    /// * `detail` - The explanation of the error for the client.
+    /// * `request_id` - The ID of the failed request.
+    pub fn new(
+        status: StatusCode,
+        error: &str,
+        detail: impl Into<String>,
+        request_id: impl ToString,
+    ) -> Self {
+        Self {
+            status,
+            error: error.to_string(),
+            detail: detail.into(),
+            request_id: request_id.to_string(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Error of a request for a missing resource.
+    pub fn not_found(error: &str, detail: impl Into<String>, request_id: impl ToString) -> Self {
+        Self::new(StatusCode::NOT_FOUND, error, detail, request_id)
+    }
+
+    /// Error of a malformed request.
+    pub fn bad_request(error: &str, detail: impl Into<String>, request_id: impl ToString) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, error, detail, request_id)
+    }
+
+    /// Error of a request failing validation, the messages of the invalid fields are returned.
+    pub fn invalid(errors: &ValidationErrors, request_id: impl ToString) -> Self {
+        let mut fields = BTreeMap::new();
+        collect_fields(errors, "", &mut fields);
+        Self {
+            fields,
+            ..Self::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "validation_failed",
+                "The request failed validation.",
+                request_id,
+            )
+        }
+    }
+
+    /// Maps the failure of a request to its error, errors of the request are told apart from
+    /// failures of the server.
+    ///
+    /// Failures of the server are counted in the `api_errors_by_type` metric and logged, the
+    /// unexpected ones are answered with the generic `failure` kind and message, so internals do
+    /// not leak to clients.
+    ///
+    /// # Arguments
+    /// * `err` - The failure returned by the domain or a service.
+    /// * `failure` - The kind of the error if the server failed, e.g. `rss_feed_create_failed`.
+    /// * `failure_message` - The message of the error if the server failed.
+    /// * `path` - The path of the endpoint, the label of the metric.
+    /// * `metrics` - The metrics the failure is counted in.
+    /// * `request_id` - The ID of the failed request.
+    pub fn failure(
+        err: anyhow::Error,
+        failure: &str,
+        failure_message: &str,
+        path: &str,
+        metrics: &Metrics,
+        request_id: impl ToString,
+    ) -> Self {
+        if let Some(errors) = err.downcast_ref::<ValidationErrors>() {
+            return Self::invalid(errors, request_id);
+        }
+        let (status, error, detail) = match classify(&err) {
+            Some((status, error)) => (
+                status,
+                error,
+                upstream_detail(&err).map_or_else(|| err.to_string(), str::to_string),
+            ),
+            None => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                failure,
+                failure_message.to_string(),
+            ),
+        };
+        if status.is_server_error() {
+            metrics
+                .api_errors_by_type
+                .with_label_values(&[error, path])
+                .inc();
+            tracing::error!("{err}");
+        }
+
+        Self::new(status, error, detail, request_id)
+    }
+
+    /// Problem details body of the error.
+    pub fn problem(&self) -> ProblemDetails {
+        ProblemDetails {
+            problem_type: "about:blank".to_string(),
+            title: self
+                .status
+                .canonical_reason()
+                .unwrap_or_default()
+                .to_string(),
+            status: self.status.as_u16(),
+            detail: self.detail.clone(),
+            error: self.error.clone(),
+            request_id: self.request_id.clone(),
+            fields: self.fields.clone(),
+        }
+    }
+
+    /// Writes the error with the builder, which may already carry headers of the response.
+    pub fn respond(&self, mut builder: HttpResponseBuilder) -> HttpResponse {
+        builder.content_type(PROBLEM_JSON).json(self.problem())
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.error, self.detail)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        self.respond(HttpResponse::build(self.status))
+    }
+}
+
+/// Detail of the failures of upstream services, whose messages carry internals such as the URL
+/// and credentials of the service, None for the other errors. The failures are logged in full.
+fn upstream_detail(err: &anyhow::Error) -> Option<&'static str> {
+    if let Some(PaymentError::Rpc(_)) = err.downcast_ref::<PaymentError>() {
+        return Some("Solana RPC is unavailable.");
+    }
+    if let Some(domain::Error::EmbeddingUnavailable(_)) = err.downcast_ref::<domain::Error>() {
+        return Some("The embedding service is unavailable.");
+    }
+
+    None
+}
+
+/// Status and kind of the errors caused by the request, None for failures of the server.
+fn classify(err: &anyhow::Error) -> Option<(StatusCode, &'static str)> {
+    if let Some(err) = err.downcast_ref::<domain::Error>() {
+        return Some(match err {
+            domain::Error::UserAlreadyExists => (StatusCode::CONFLICT, "user_already_exists"),
+            domain::Error::InvalidCredentials => (StatusCode::UNAUTHORIZED, "invalid_credentials"),
+            domain::Error::UserNotFound => (StatusCode::NOT_FOUND, "user_not_found"),
+            domain::Error::ParsingFailure(_) => (StatusCode::BAD_REQUEST, "parsing_failure"),
+            domain::Error::InvalidToken => (StatusCode::UNAUTHORIZED, "invalid_token"),
+            domain::Error::TokenExpired => (StatusCode::UNAUTHORIZED, "token_expired"),
+            domain::Error::TokenAlreadyUsed => (StatusCode::CONFLICT, "token_already_used"),
+            domain::Error::ObjectStorageDisabled => {
+                (StatusCode::SERVICE_UNAVAILABLE, "object_storage_disabled")
+            }
+            domain::Error::FeedAlreadyExists => (StatusCode::CONFLICT, "rss_feed_exists"),
+            domain::Error::InvalidFeedUrl(_) => (StatusCode::BAD_REQUEST, "invalid_rss_feed"),
+            domain::Error::InvalidWebhookUrl(_) => (StatusCode::BAD_REQUEST, "invalid_webhook"),
+            domain::Error::MissingPurgeCriteria => {
+                (StatusCode::BAD_REQUEST, "invalid_purge_request")
+            }
+            domain::Error::EmbeddingUnavailable(_) => {
+                (StatusCode::SERVICE_UNAVAILABLE, "embedding_unavailable")
+            }
+            domain::Error::EmailDisabled => (StatusCode::SERVICE_UNAVAILABLE, "email_disabled"),
+            domain::Error::InvalidDigestConfirmation => {
+                (StatusCode::BAD_REQUEST, "invalid_digest_confirmation")
+            }
+        });
+    }
+    if let Some(err) = err.downcast_ref::<OrganizationError>() {
+        return Some(match err {
+            OrganizationError::NotMember => (StatusCode::FORBIDDEN, "not_organization_member"),
+            OrganizationError::NotOwner => (StatusCode::FORBIDDEN, "not_organization_owner"),
+            OrganizationError::OwnerRemoval => (StatusCode::CONFLICT, "organization_owner"),
+            OrganizationError::InvalidWallet(_) => (StatusCode::BAD_REQUEST, "invalid_wallet"),
+        });
+    }
+    if let Some(err) = err.downcast_ref::<PaymentError>() {
+        return Some(match err {
+            PaymentError::Disabled => (StatusCode::SERVICE_UNAVAILABLE, "payments_disabled"),
+            PaymentError::InvalidSignature | PaymentError::NotAPayment(_) => {
+                (StatusCode::BAD_REQUEST, "invalid_payment")
+            }
+            PaymentError::TransactionFailed => (StatusCode::BAD_REQUEST, "transaction_failed"),
+            PaymentError::TransactionNotFound => (StatusCode::NOT_FOUND, "transaction_not_found"),
+            PaymentError::AlreadyRedeemed => (StatusCode::CONFLICT, "payment_already_redeemed"),
+            PaymentError::Rpc(_) => (StatusCode::BAD_GATEWAY, "solana_rpc_failed"),
+        });
+    }
+    if err.is::<StorageError>() {
+        return Some((StatusCode::BAD_REQUEST, "invalid_query"));
+    }
+    if err.is::<base64::DecodeError>() {
+        return Some((StatusCode::UNAUTHORIZED, "invalid_token"));
+    }
+    if err.is::<ed25519_dalek::SignatureError>() {
+        return Some((StatusCode::UNAUTHORIZED, "invalid_credentials"));
+    }
+
+    None
+}
+
+/// Collects the messages of the invalid fields, fields of nested structs and lists are prefixed
+/// with their parents, e.g. `items[0].url`.
+fn collect_fields(
+    errors: &ValidationErrors,
+    prefix: &str,
+    fields: &mut BTreeMap<String, Vec<String>>,
+) {
+    for (field, kind) in errors.errors() {
+        let field = match prefix {
+            "" => field.to_string(),
+            _ => format!("{prefix}.{field}"),
+        };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                fields
+                    .entry(field)
+                    .or_default()
+                    .extend(errors.iter().map(describe));
+            }
+            ValidationErrorsKind::Struct(errors) => collect_fields(errors, &field, fields),
+            ValidationErrorsKind::List(errors) => {
+                for (index, errors) in errors {
+                    collect_fields(errors, &format!("{field}[{index}]"), fields);
+                }
+            }
+        }
+    }
+}
+
+/// Message of the failed validation, the message of the rule if it has one.
+fn describe(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+    let param = |name: &str| error.params.get(name).map(ToString::to_string);
+    let bounds = match (param("min"), param("max")) {
+        (Some(min), Some(max)) => format!("between {min} and {max}"),
+        (Some(min), None) => format!("at least {min}"),
+        (None, Some(max)) => format!("at most {max}"),
+        (None, None) => "valid".to_string(),
+    };
+    match error.code.as_ref() {
+        "length" => format!("length must be {bounds}"),
+        "range" => format!("must be {bounds}"),
+        "url" => "must be a URL".to_string(),
+        "email" => "must be an email address".to_string(),
+        code => format!("must pass the {code} check"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{body::to_bytes, http::header::CONTENT_TYPE};
+
+    #[test]
+    fn test_failure_status() {
+        let metrics = Metrics::new().unwrap();
+        let failure = |err: anyhow::Error| {
+            ApiError::failure(
+                err,
+                "webhook_create_failed",
+                "Failed to register webhook.",
+                "/api/v1/webhooks",
+                &metrics,
+                "req-1",
+            )
+        };
+
+        let error = failure(domain::Error::UserAlreadyExists.into());
+        assert_eq!(error.status_code(), StatusCode::CONFLICT);
+        assert_eq!(error.problem().error, "user_already_exists");
+        let error = failure(OrganizationError::NotOwner.into());
+        assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+        let error = failure(
+            PaymentError::Rpc(
+                "error sending request for url (https://rpc.example.com/?api-key=secret)"
+                    .to_string(),
+            )
+            .into(),
+        );
+        assert_eq!(error.status_code(), StatusCode::BAD_GATEWAY);
+        assert_eq!(error.problem().detail, "Solana RPC is unavailable.");
+        let error = failure(domain::Error::EmbeddingUnavailable("timed out".to_string()).into());
+        assert_eq!(
+            error.problem().detail,
+            "The embedding service is unavailable."
+        );
+        let error = failure(domain::Error::InvalidWebhookUrl("ftp://x".to_string()).into());
+        assert_eq!(error.problem().error, "invalid_webhook");
+        assert_eq!(
+            metrics
+                .api_errors_by_type
+                .with_label_values(&["webhook_create_failed", "/api/v1/webhooks"])
+                .get(),
+            0
+        );
+
+        let error = failure(anyhow::anyhow!("connection refused"));
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.problem().detail, "Failed to register webhook.");
+        assert_eq!(
+            metrics
+                .api_errors_by_type
+                .with_label_values(&["webhook_create_failed", "/api/v1/webhooks"])
+                .get(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_invalid_fields() {
+        #[derive(validator::Validate)]
+        struct Request {
+            #[validate(range(min = 1, max = 100))]
+            limit: i64,
+            #[validate(url, length(max = 2048))]
+            url: String,
+            #[validate(length(min = 32, message = "must be a base58 encoded public key"))]
+            wallet: String,
+        }
+
+        let errors = validator::Validate::validate(&Request {
+            limit: 500,
+            url: "not a url".to_string(),
+            wallet: "short".to_string(),
+        })
+        .unwrap_err();
+        let problem = ApiError::invalid(&errors, "req-1").problem();
+        assert_eq!(problem.status, 422);
+        assert_eq!(problem.error, "validation_failed");
+        assert_eq!(problem.fields["limit"], ["must be between 1 and 100"]);
+        assert_eq!(problem.fields["url"], ["must be a URL"]);
+        assert_eq!(
+            problem.fields["wallet"],
+            ["must be a base58 encoded public key"]
+        );
+
+        let metrics = Metrics::new().unwrap();
+        let error = ApiError::failure(
+            errors.into(),
+            "webhook_create_failed",
+            "Failed to register webhook.",
+            "/api/v1/webhooks",
+            &metrics,
+            "req-1",
+        );
+        assert_eq!(error.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[actix_web::test]
+    async fn test_problem_response() {
+        let response = ApiError::not_found("rss_item_not_found", "RSS item 42 not found.", "req-1")
+            .error_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers().get(CONTENT_TYPE).unwrap(), PROBLEM_JSON);
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            problem,
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "RSS item 42 not found.",
+                "error": "rss_item_not_found",
+                "request_id": "req-1",
+            })
+        );
+    }
+}
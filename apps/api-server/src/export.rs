@@ -0,0 +1,168 @@
+use crate::models::RssItemResponse;
+use actix_web::web::Bytes;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use shared_states::RssItem;
+use std::{borrow::Cow, fmt};
+use utoipa::ToSchema;
+
+/// Columns of the CSV export, in order.
+const CSV_COLUMNS: [&str; 13] = [
+    "hash",
+    "title",
+    "link",
+    "description",
+    "published_timestamp",
+    "published_timestamp_estimated",
+    "fetched_timestamp",
+    "category",
+    "author",
+    "language",
+    "word_count",
+    "reading_time_minutes",
+    "article",
+];
+
+/// ExportFormat is the encoding of exported RSS items.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON object per line, as returned by `/api/v1/rss/items`.
+    #[default]
+    Jsonl,
+    /// Comma separated values with a header row, categories are joined with `;`.
+    Csv,
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Jsonl => write!(f, "jsonl"),
+            Self::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+impl ExportFormat {
+    /// Content type of the export.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Jsonl => "application/x-ndjson",
+            Self::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    /// First chunk of the export, the header row of CSV.
+    pub fn header(&self) -> Option<Bytes> {
+        match self {
+            Self::Jsonl => None,
+            Self::Csv => Some(Bytes::from(format!("{}\r\n", CSV_COLUMNS.join(",")))),
+        }
+    }
+
+    /// Encodes an item as a line of the export.
+    ///
+    /// # Arguments
+    /// * `item` - The exported item.
+    ///
+    /// # Returns
+    /// The line with its terminator, or an error if the item cannot be serialized.
+    pub fn encode(&self, item: RssItem) -> Result<Bytes> {
+        match self {
+            Self::Jsonl => {
+                let mut line = serde_json::to_vec(&RssItemResponse::from(item))?;
+                line.push(b'\n');
+                Ok(Bytes::from(line))
+            }
+            Self::Csv => {
+                let fields = [
+                    Cow::from(item.hash),
+                    item.title.into(),
+                    item.link.into(),
+                    item.description.into(),
+                    item.published_timestamp.to_string().into(),
+                    item.published_timestamp_estimated.to_string().into(),
+                    item.fetched_timestamp.to_string().into(),
+                    item.category.join(";").into(),
+                    item.author.name.into(),
+                    item.language.into(),
+                    item.word_count.to_string().into(),
+                    item.reading_time_minutes.to_string().into(),
+                    item.article.into(),
+                ];
+                let mut line = fields
+                    .iter()
+                    .map(|field| csv_field(field))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                line.push_str("\r\n");
+                Ok(Bytes::from(line))
+            }
+        }
+    }
+}
+
+/// Quotes the field if it contains a separator, a quote or a line break, doubling the quotes.
+///
+/// Text starting like a spreadsheet formula is prefixed with `'`, so feed-supplied fields are not
+/// evaluated when the export is opened in Excel or Sheets. Numbers are kept as they are.
+fn csv_field(value: &str) -> Cow<'_, str> {
+    let value =
+        if value.starts_with(['=', '+', '-', '@', '\t', '\r']) && value.parse::<f64>().is_err() {
+            Cow::Owned(format!("'{value}"))
+        } else {
+            Cow::Borrowed(value)
+        };
+    if value.contains([',', '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_states::Author;
+
+    #[test]
+    fn test_export_format_encode() {
+        let item = RssItem {
+            hash: "abc".to_string(),
+            title: "Fed says \"wait\", markets slip".to_string(),
+            link: "https://news.example.com/fed".to_string(),
+            published_timestamp: 1716300000000,
+            category: vec!["markets".to_string(), "fed".to_string()],
+            author: Author {
+                name: "Jane Doe".to_string(),
+                email: None,
+            },
+            article: "First line\nsecond line".to_string(),
+            ..Default::default()
+        };
+
+        let header = ExportFormat::Csv.header().unwrap();
+        assert!(header.starts_with(b"hash,title,link,"));
+        let line = ExportFormat::Csv.encode(item.clone()).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&line),
+            "abc,\"Fed says \"\"wait\"\", markets slip\",https://news.example.com/fed,,\
+             1716300000000,false,0,markets;fed,Jane Doe,,0,0,\"First line\nsecond line\"\r\n"
+        );
+
+        assert_eq!(
+            csv_field("=HYPERLINK(\"https://x.example.com\")"),
+            "\"'=HYPERLINK(\"\"https://x.example.com\"\")\""
+        );
+        assert_eq!(csv_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(csv_field("+1 more"), "'+1 more");
+        assert_eq!(csv_field("-42"), "-42");
+
+        assert_eq!(ExportFormat::Jsonl.header(), None);
+        let line = ExportFormat::Jsonl.encode(item).unwrap();
+        assert!(line.ends_with(b"}\n"));
+        let value: serde_json::Value = serde_json::from_slice(&line).unwrap();
+        assert_eq!(value["hash"], "abc");
+        assert_eq!(value["author"]["name"], "Jane Doe");
+    }
+}
@@ -0,0 +1,294 @@
+use crate::{
+    config::GrpcConfig, constants::REQUEST_ID_HEADER, domain::Domain, domain::Error,
+    middleware_v1::RequestId, models::RssItemSentiment,
+};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use grpc_proto::v1::{
+    self, GetSentimentsRequest, GetSentimentsResponse, GetUserRequest, GetUserResponse,
+    IngestRssItemsRequest, IngestRssItemsResponse,
+    semantic_machine_server::{SemanticMachine, SemanticMachineServer},
+};
+use nats_middleware::with_request_id;
+use shared_states::{
+    Author, RssItem, detect_language, item_hash, normalize_categories, sanitize_html, simhash,
+};
+use std::{net::SocketAddr, sync::Arc};
+use subtle::ConstantTimeEq;
+use tokio::sync::watch;
+use tonic::{Request, Response, Status, service::Interceptor, transport::Server};
+
+/// Maximal number of items or hashes in a single request.
+const MAX_BATCH_SIZE: usize = 100;
+
+/// SemanticMachineService exposes the domain to other backend services over gRPC.
+pub struct SemanticMachineService {
+    domain: Arc<Domain>,
+}
+
+impl SemanticMachineService {
+    /// Creates a new service.
+    ///
+    /// # Arguments
+    /// * `domain` - The domain the calls are served by.
+    pub fn new(domain: Arc<Domain>) -> Self {
+        Self { domain }
+    }
+}
+
+#[tonic::async_trait]
+impl SemanticMachine for SemanticMachineService {
+    async fn ingest_rss_items(
+        &self,
+        request: Request<IngestRssItemsRequest>,
+    ) -> Result<Response<IngestRssItemsResponse>, Status> {
+        let request_id = request
+            .metadata()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(RequestId::from_header)
+            .unwrap_or_else(RequestId::generate);
+        let items = request.into_inner().items;
+        check_batch_size(items.len())?;
+        let items = items
+            .into_iter()
+            .map(rss_item_from_proto)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let hashes = with_request_id(request_id.to_string(), self.domain.ingest_rss_items(items))
+            .await
+            .map_err(to_status)?;
+
+        Ok(Response::new(IngestRssItemsResponse { hashes }))
+    }
+
+    async fn get_user(
+        &self,
+        request: Request<GetUserRequest>,
+    ) -> Result<Response<GetUserResponse>, Status> {
+        let solana_wallet = request.into_inner().solana_wallet;
+        let user = self
+            .domain
+            .user(&solana_wallet)
+            .await
+            .map_err(to_status)?
+            .ok_or_else(|| Status::not_found(format!("User {solana_wallet} not found")))?;
+
+        Ok(Response::new(GetUserResponse {
+            user: Some(v1::User {
+                solana_wallet: bs58::encode(user.solana_wallet_public_key).into_string(),
+                created_at: user.created_at,
+            }),
+        }))
+    }
+
+    async fn get_sentiments(
+        &self,
+        request: Request<GetSentimentsRequest>,
+    ) -> Result<Response<GetSentimentsResponse>, Status> {
+        let item_hashes = request.into_inner().item_hashes;
+        check_batch_size(item_hashes.len())?;
+        let sentiments = self
+            .domain
+            .rss_item_sentiments(&item_hashes)
+            .await
+            .map_err(to_status)?;
+
+        Ok(Response::new(GetSentimentsResponse {
+            sentiments: sentiments.into_iter().map(sentiment_to_proto).collect(),
+        }))
+    }
+}
+
+/// BearerAuth rejects calls without the configured `authorization: Bearer <token>` metadata.
+#[derive(Clone)]
+pub struct BearerAuth {
+    token: String,
+}
+
+impl Interceptor for BearerAuth {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let provided = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match provided {
+            Some(provided) if bool::from(provided.as_bytes().ct_eq(self.token.as_bytes())) => {
+                Ok(request)
+            }
+            _ => Err(Status::unauthenticated("Invalid or missing bearer token")),
+        }
+    }
+}
+
+/// Serves the gRPC API until the shutdown signal is received.
+///
+/// # Arguments
+/// * `domain` - The domain the calls are served by.
+/// * `config` - The gRPC server configuration.
+/// * `shutdown` - Receiver of the signal the server stops on once it is `true`.
+pub async fn serve(
+    domain: Arc<Domain>,
+    config: &GrpcConfig,
+    mut shutdown: watch::Receiver<bool>,
+) -> Result<()> {
+    let addr: SocketAddr = format!("{}:{}", config.host, config.port)
+        .parse()
+        .context("Invalid gRPC server address")?;
+    let token = config
+        .auth_token
+        .clone()
+        .context("GRPC_AUTH_TOKEN is required to serve the gRPC API")?;
+    let service = SemanticMachineServer::with_interceptor(
+        SemanticMachineService::new(domain),
+        BearerAuth { token },
+    );
+
+    tracing::info!("Starting gRPC server on {addr}");
+    Server::builder()
+        .add_service(service)
+        .serve_with_shutdown(addr, async move {
+            let _ = shutdown.wait_for(|stop| *stop).await;
+        })
+        .await?;
+
+    Ok(())
+}
+
+fn check_batch_size(len: usize) -> Result<(), Status> {
+    if len > MAX_BATCH_SIZE {
+        return Err(Status::invalid_argument(format!(
+            "At most {MAX_BATCH_SIZE} entries are allowed, got {len}"
+        )));
+    }
+    Ok(())
+}
+
+/// Converts an ingested item the same way the rss-worker converts feed items.
+fn rss_item_from_proto(item: v1::RssItem) -> Result<RssItem, Status> {
+    if item.title.trim().is_empty() {
+        return Err(Status::invalid_argument("Item title is required"));
+    }
+    if !item.link.starts_with("http://") && !item.link.starts_with("https://") {
+        return Err(Status::invalid_argument(format!(
+            "Item link ( {} ) is not an HTTP URL",
+            item.link
+        )));
+    }
+
+    let fetched_timestamp = Utc::now().timestamp_millis();
+    let (published_timestamp, published_timestamp_estimated) = match item.published_timestamp {
+        0 => (fetched_timestamp, true),
+        published => (published, false),
+    };
+    let hash = item_hash(
+        &item.title,
+        &item.author,
+        &item.link,
+        &item.description,
+        &item.published_timestamp.to_string(),
+    );
+
+    let description = sanitize_html(&item.description);
+    let text = format!("{} {description}", item.title);
+    let language = detect_language(&text).unwrap_or_default();
+
+    Ok(RssItem {
+        hash,
+        title: item.title,
+        link: item.link,
+        description,
+        published_timestamp,
+        published_timestamp_estimated,
+        fetched_timestamp,
+        category: normalize_categories(&item.categories),
+        author: Author::parse(&item.author),
+        article: item.article,
+        language,
+        simhash: simhash(&text) as i64,
+        ..Default::default()
+    })
+}
+
+fn sentiment_to_proto(sentiment: RssItemSentiment) -> v1::Sentiment {
+    let label = match sentiment.label.as_str() {
+        "positive" => v1::SentimentLabel::Positive,
+        "neutral" => v1::SentimentLabel::Neutral,
+        "negative" => v1::SentimentLabel::Negative,
+        _ => v1::SentimentLabel::Unspecified,
+    };
+    v1::Sentiment {
+        item_hash: sentiment.item_hash,
+        label: label.into(),
+        score: sentiment.score,
+        model: sentiment.model,
+        analyzed_timestamp: sentiment.analyzed_timestamp,
+    }
+}
+
+fn to_status(err: anyhow::Error) -> Status {
+    match err.downcast_ref::<Error>() {
+        Some(Error::ParsingFailure(message)) => Status::invalid_argument(message.clone()),
+        _ => {
+            tracing::error!("gRPC call failed: {err}");
+            Status::internal("Internal server error")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rss_item_from_proto() {
+        let proto = v1::RssItem {
+            title: "Bitcoin hits new high".to_string(),
+            link: "https://news.example.com/btc".to_string(),
+            description: "<p>Markets <b>rally</b></p>".to_string(),
+            published_timestamp: 0,
+            categories: vec!["Crypto".to_string(), "crypto".to_string()],
+            author: "Jane Doe".to_string(),
+            article: String::new(),
+        };
+
+        let item = rss_item_from_proto(proto.clone()).unwrap();
+        assert_eq!(item.hash.len(), 64);
+        assert_eq!(item.hash, rss_item_from_proto(proto.clone()).unwrap().hash);
+        assert!(item.published_timestamp_estimated);
+        assert_eq!(item.category, vec!["crypto".to_string()]);
+        assert!(!item.description.contains('<'));
+
+        let invalid = v1::RssItem {
+            link: "ftp://news.example.com/btc".to_string(),
+            ..proto
+        };
+        assert_eq!(
+            rss_item_from_proto(invalid).unwrap_err().code(),
+            tonic::Code::InvalidArgument
+        );
+    }
+
+    #[test]
+    fn test_bearer_auth() {
+        let mut auth = BearerAuth {
+            token: "secret".to_string(),
+        };
+
+        let status = auth.call(Request::new(())).unwrap_err();
+        assert_eq!(status.code(), tonic::Code::Unauthenticated);
+
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer secret".parse().unwrap());
+        assert!(auth.call(request).is_ok());
+
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert("authorization", "Bearer secrex".parse().unwrap());
+        assert!(auth.call(request).is_err());
+    }
+}
@@ -1,29 +1,77 @@
+use crate::audit::AuditEventType;
+use crate::auth::Authenticator;
+use crate::config::Config;
 use crate::domain::Domain;
-use crate::models::{ErrorResponse, LoginRequest, RegisterRequest, UserResponse};
+use crate::errors::ApiError;
+use crate::health::ReadinessProbe;
+use crate::middleware_v1::{Credentials, RequestId, ValidatedJson, ValidatedQuery};
+use crate::models::{
+    AddOrganizationMemberRequest, AdminStatsQuery, AdminStatsResponse, ApiKeyResponse,
+    ApiKeysResponse, AuditEventResponse, AuditEventsResponse, AuditLogQuery, AuthSessionResponse,
+    AuthSessionsResponse, CreateApiKeyRequest, CreateOrganizationRequest, CreateRssFeedRequest,
+    CreateWebhookRequest, CreatedApiKeyResponse, DigestConfirmationRequest,
+    DigestPreferencesRequest, DigestPreferencesResponse, HealthResponse, JwksResponse,
+    LoginRequest, OrganizationHeader, OrganizationMemberResponse, OrganizationMembersResponse,
+    OrganizationResponse, OrganizationsResponse, PageQuery, ProblemDetails, PurgeRssItemsRequest,
+    PurgeRssItemsResponse, ReadinessResponse, RegisterRequest, RssBookmarkResponse,
+    RssBookmarksResponse, RssExportQuery, RssFeedResponse, RssFeedsResponse, RssItemMediaResponse,
+    RssItemResponse, RssItemSentimentResponse, RssItemsQuery, RssItemsResponse, RssMediaResponse,
+    RssSavedSearchResponse, RssSavedSearchesResponse, RssSearchHitResponse, RssSearchQuery,
+    RssSearchResponse, RssSemanticHitResponse, RssSemanticSearchQuery, RssSemanticSearchResponse,
+    SaveSearchRequest, SolanaPaymentRequest, SubscriptionResponse, UpdateRssFeedRequest,
+    UserResponse, WebhookResponse, WebhooksResponse,
+};
+use crate::models::{AuditEvent, Claims};
+use crate::organizations::{OrganizationMember, OrganizationRole, Organizations, Scope};
+use crate::payments::SolanaPayments;
+use crate::response_cache::ResponseCache;
+use crate::sessions::SessionRegistry;
 use crate::telemetry::Metrics;
 use actix_web::cookie::{Cookie, SameSite};
-use actix_web::{HttpResponse, get, post, web};
+use actix_web::http::StatusCode;
+use actix_web::http::header::{CACHE_CONTROL, CONTENT_DISPOSITION, USER_AGENT};
+use actix_web::{HttpRequest, HttpResponse, delete, get, patch, post, put, web};
 use chrono::Utc;
+use futures::{StreamExt, stream};
 
 #[utoipa::path(
     get,
-    path = "/health",
+    path = "/health/live",
     tag = "health",
     responses(
-        (status = 200, description = "Service is healthy", body = String),
+        (status = 200, description = "Process is alive", body = HealthResponse),
     )
 )]
-#[get("/health")]
-pub async fn health(metrics_data: web::Data<Metrics>) -> HttpResponse {
+#[get("/health/live")]
+pub async fn health_live(metrics_data: web::Data<Metrics>) -> HttpResponse {
     metrics_data.update_system_metrics();
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "healthy",
-        "timestamp": Utc::now(),
-        "uptime_seconds": metrics_data.uptime_seconds.get(),
-        "active_connections": metrics_data.active_connections.get(),
-        "active_sessions": metrics_data.active_sessions.get()
-    }))
+    HttpResponse::Ok().json(HealthResponse {
+        status: "healthy".to_string(),
+        timestamp: Utc::now(),
+        uptime_seconds: metrics_data.uptime_seconds.get(),
+        active_connections: metrics_data.active_connections.get(),
+        active_sessions: metrics_data.active_sessions.get(),
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "All dependencies are up, traffic can be routed", body = ReadinessResponse),
+        (status = 503, description = "A dependency is down", body = ReadinessResponse),
+    )
+)]
+#[get("/health/ready")]
+pub async fn health_ready(probe: web::Data<ReadinessProbe>) -> HttpResponse {
+    let readiness = probe.check().await;
+    if readiness.status == "ready" {
+        HttpResponse::Ok().json(readiness)
+    } else {
+        HttpResponse::ServiceUnavailable().json(readiness)
+    }
 }
 
 #[utoipa::path(
@@ -35,108 +83,2137 @@ pub async fn health(metrics_data: web::Data<Metrics>) -> HttpResponse {
     )
 )]
 #[get("/metrics")]
-pub async fn metrics_endpoint(metrics: web::Data<Metrics>) -> HttpResponse {
-    match metrics.export() {
-        Ok(metrics_text) => HttpResponse::Ok()
-            .content_type("text/plain; version=0.0.4")
-            .body(metrics_text),
-        Err(e) => HttpResponse::InternalServerError().json(ErrorResponse {
-            error: "metrics_error".to_string(),
-            message: format!("Failed to export metrics: {e}"),
-        }),
-    }
+pub async fn metrics_endpoint(
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let metrics_text = metrics.export().map_err(|err| {
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "metrics_error",
+            format!("Failed to export metrics: {err}"),
+            &request_id,
+        )
+    })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics_text))
+}
+
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Public keys tokens are verified with", body = JwksResponse),
+    )
+)]
+#[get("/.well-known/jwks.json")]
+pub async fn jwks(authenticator: web::Data<Authenticator>) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((CACHE_CONTROL, "public, max-age=300"))
+        .json(authenticator.jwks())
 }
 
 #[utoipa::path(
     post,
     path = "/api/v1/auth/register",
     tag = "auth",
-    params(RegisterRequest),
+    request_body(
+        content = RegisterRequest,
+        description = "Signed challenge, the query string is still accepted but deprecated"
+    ),
     responses(
         (status = 201, description = "User registered successfully", body = UserResponse),
-        (status = 400, description = "Bad request", body = ErrorResponse),
+        (status = 400, description = "Bad request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Invalid signature or challenge token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Wallet already registered or challenge token already used", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
     )
 )]
 #[post("/auth/register")]
 pub async fn register(
-    query: web::Query<RegisterRequest>,
+    credentials: Credentials<RegisterRequest>,
     domain: web::Data<Domain>,
     metrics: web::Data<Metrics>,
-) -> HttpResponse {
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
     if let Err(err) = domain
         .register(
-            &query.token,
-            query.expires_at,
-            &query.solana_wallet_public_key,
-            &query.signature,
+            &credentials.token,
+            credentials.expires_at,
+            &credentials.solana_wallet_public_key,
+            &credentials.signature,
         )
         .await
     {
         metrics.record_user_registration(false);
-        tracing::error!("{err}");
-        return HttpResponse::BadRequest().json(ErrorResponse {
-            error: "registration_failed".to_string(),
-            message: "Failed to register user.".to_string(),
-        });
+        audit(
+            &domain,
+            AuditEvent::new(
+                AuditEventType::of_failure(AuditEventType::Registration, &err),
+                &credentials.solana_wallet_public_key,
+                false,
+                err.to_string(),
+                &request_id,
+            ),
+        )
+        .await;
+        return Err(ApiError::failure(
+            err,
+            "registration_failed",
+            "Failed to register user.",
+            "/api/v1/auth/register",
+            &metrics,
+            &request_id,
+        ));
     }
     metrics.record_user_registration(true);
+    audit(
+        &domain,
+        AuditEvent::new(
+            AuditEventType::Registration,
+            &credentials.solana_wallet_public_key,
+            true,
+            "Wallet registered",
+            &request_id,
+        ),
+    )
+    .await;
 
-    HttpResponse::Created().json(UserResponse {
-        solana_wallet_public_key: query.solana_wallet_public_key.to_string(),
-    })
+    Ok(HttpResponse::Created().json(UserResponse {
+        solana_wallet_public_key: credentials.solana_wallet_public_key.to_string(),
+    }))
 }
 
 #[utoipa::path(
     post,
     path = "/api/v1/auth/login",
     tag = "auth",
-    params(LoginRequest),
+    request_body(
+        content = LoginRequest,
+        description = "Signed challenge, the query string is still accepted but deprecated"
+    ),
     responses(
         (status = 200, description = "Login successful", body = UserResponse),
-        (status = 401, description = "Invalid credentials", body = ErrorResponse),
+        (status = 400, description = "Bad request", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Wallet not registered", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Challenge token already used", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
     )
 )]
 #[post("/auth/login")]
 pub async fn login(
-    query: web::Query<LoginRequest>,
+    req: HttpRequest,
+    credentials: Credentials<LoginRequest>,
     domain: web::Data<Domain>,
+    sessions: web::Data<SessionRegistry>,
     metrics: web::Data<Metrics>,
-) -> HttpResponse {
-    match domain
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let issued = domain
         .login(
-            &query.solana_wallet_public_key,
-            &query.token,
-            query.expires_at,
-            &query.signature,
+            &credentials.solana_wallet_public_key,
+            &credentials.token,
+            credentials.expires_at,
+            &credentials.signature,
         )
-        .await
-    {
-        Ok(token) => {
-            metrics.record_auth_attempt("login", true);
-            metrics.record_user_login(true);
-            metrics.active_sessions.inc();
-            let cookie = Cookie::build("auth_token", token.clone())
-                .path("/")
-                .http_only(true)
-                .same_site(SameSite::Strict)
-                .secure(true)
-                .finish();
-            HttpResponse::Ok().cookie(cookie).json(UserResponse {
-                solana_wallet_public_key: query.solana_wallet_public_key.to_string(),
-            })
-        }
+        .await;
+    let (token, claims) = match issued {
+        Ok(issued) => issued,
         Err(err) => {
             metrics.record_auth_attempt("login", false);
             metrics.record_user_login(false);
-            metrics
-                .api_errors_by_type
-                .with_label_values(&["token_generation_failed", "/api/v1/auth/login"])
-                .inc();
-            tracing::error!("{err}");
-            HttpResponse::InternalServerError().json(ErrorResponse {
-                error: "login_failed".to_string(),
-                message: "Failed to generate authentication token".to_string(),
-            })
+            audit(
+                &domain,
+                AuditEvent::new(
+                    AuditEventType::of_failure(AuditEventType::Login, &err),
+                    &credentials.solana_wallet_public_key,
+                    false,
+                    err.to_string(),
+                    &request_id,
+                ),
+            )
+            .await;
+            return Err(ApiError::failure(
+                err,
+                "login_failed",
+                "Failed to generate authentication token.",
+                "/api/v1/auth/login",
+                &metrics,
+                &request_id,
+            ));
         }
+    };
+
+    let user_agent = req
+        .headers()
+        .get(USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if let Err(err) = sessions.open(&claims, user_agent).await {
+        metrics.record_auth_attempt("login", false);
+        metrics.record_user_login(false);
+        return Err(ApiError::failure(
+            err,
+            "session_open_failed",
+            "Failed to open the session.",
+            "/api/v1/auth/login",
+            &metrics,
+            &request_id,
+        ));
     }
+
+    metrics.record_auth_attempt("login", true);
+    metrics.record_user_login(true);
+    audit(
+        &domain,
+        AuditEvent::new(
+            AuditEventType::Login,
+            &credentials.solana_wallet_public_key,
+            true,
+            format!("Logged in, session {}", claims.jti),
+            &request_id,
+        ),
+    )
+    .await;
+    let cookie = Cookie::build("auth_token", token)
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .secure(true)
+        .finish();
+    Ok(HttpResponse::Ok().cookie(cookie).json(UserResponse {
+        solana_wallet_public_key: credentials.solana_wallet_public_key.to_string(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/auth/sessions",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    params(PageQuery),
+    responses(
+        (status = 200, description = "Active sessions of the user, newest first", body = AuthSessionsResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/auth/sessions")]
+pub async fn auth_sessions(
+    query: ValidatedQuery<PageQuery>,
+    claims: Claims,
+    sessions: web::Data<SessionRegistry>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = (query.limit(), query.offset());
+    let (page, total) = tokio::try_join!(
+        sessions.sessions(&claims.sub, limit, offset),
+        sessions.count_sessions(&claims.sub)
+    )
+    .map_err(|err| {
+        ApiError::failure(
+            err,
+            "sessions_read_failed",
+            "Failed to read sessions.",
+            "/api/v1/auth/sessions",
+            &metrics,
+            &request_id,
+        )
+    })?;
+
+    Ok(HttpResponse::Ok().json(AuthSessionsResponse {
+        sessions: page
+            .into_iter()
+            .map(|session| AuthSessionResponse::new(session, &claims.jti))
+            .collect(),
+        total,
+        limit,
+        offset,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/auth/sessions/{id}",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = String, Path, description = "ID of the session")
+    ),
+    responses(
+        (status = 204, description = "Session revoked, its token is rejected from now on"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Session not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[delete("/auth/sessions/{id}")]
+pub async fn revoke_auth_session(
+    path: web::Path<String>,
+    claims: Claims,
+    domain: web::Data<Domain>,
+    sessions: web::Data<SessionRegistry>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let revoked = sessions.revoke(&claims.sub, &path).await.map_err(|err| {
+        ApiError::failure(
+            err,
+            "session_revoke_failed",
+            "Failed to revoke session.",
+            "/api/v1/auth/sessions/{id}",
+            &metrics,
+            &request_id,
+        )
+    })?;
+    if !revoked {
+        return Err(ApiError::not_found(
+            "session_not_found",
+            format!("Session {} not found.", path.as_str()),
+            &request_id,
+        ));
+    }
+    audit(
+        &domain,
+        AuditEvent::new(
+            AuditEventType::SessionRevocation,
+            &claims.sub,
+            true,
+            format!("Revoked session {}", path.as_str()),
+            &request_id,
+        ),
+    )
+    .await;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/payments/solana",
+    tag = "payments",
+    security(("bearer_auth" = [])),
+    request_body = SolanaPaymentRequest,
+    responses(
+        (status = 200, description = "Payment redeemed, the pro tier is extended by the paid periods", body = SubscriptionResponse),
+        (status = 400, description = "Invalid signature or the transaction does not pay for the wallet", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Transaction not found or not finalized yet", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Transaction already redeemed", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 502, description = "Solana RPC failure", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "Payments are disabled", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[post("/payments/solana")]
+pub async fn redeem_solana_payment(
+    payload: ValidatedJson<SolanaPaymentRequest>,
+    claims: Claims,
+    payments: web::Data<SolanaPayments>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let subscription = payments
+        .redeem(&claims.sub, payload.signature.trim())
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "payment_failed",
+                "Failed to redeem payment.",
+                "/api/v1/payments/solana",
+                &metrics,
+                &request_id,
+            )
+        })?;
+
+    Ok(HttpResponse::Ok().json(SubscriptionResponse::from(subscription)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rss/items",
+    tag = "rss",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(RssItemsQuery),
+    responses(
+        (status = 200, description = "Page of RSS items matching the filters, newest first unless sorted", body = RssItemsResponse),
+        (status = 304, description = "Page not modified since the ETag in If-None-Match"),
+        (status = 400, description = "Invalid sort field", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/rss/items")]
+pub async fn rss_items(
+    req: HttpRequest,
+    query: ValidatedQuery<RssItemsQuery>,
+    domain: web::Data<Domain>,
+    cache: web::Data<ResponseCache>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> HttpResponse {
+    let (limit, offset) = (query.limit(), query.offset());
+    let (filters, sort) = (query.filters(), query.sort());
+    cache
+        .respond(&req, &metrics, "rss_items", &request_id, || async {
+            let (items, total) = tokio::try_join!(
+                domain.rss_items(&filters, sort.as_ref(), limit, offset),
+                domain.count_rss_items(&filters)
+            )
+            .map_err(|err| {
+                ApiError::failure(
+                    err,
+                    "rss_items_read_failed",
+                    "Failed to read RSS items.",
+                    "/api/v1/rss/items",
+                    &metrics,
+                    &request_id,
+                )
+            })?;
+
+            Ok(RssItemsResponse {
+                items: items.into_iter().map(RssItemResponse::from).collect(),
+                total,
+                limit,
+                offset,
+            })
+        })
+        .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rss/items/{hash}",
+    tag = "rss",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("hash" = String, Path, description = "Hash of the RSS item")
+    ),
+    responses(
+        (status = 200, description = "RSS item", body = RssItemResponse),
+        (status = 304, description = "RSS item not modified since the ETag in If-None-Match"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "RSS item not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/rss/items/{hash}")]
+pub async fn rss_item(
+    req: HttpRequest,
+    path: web::Path<String>,
+    domain: web::Data<Domain>,
+    cache: web::Data<ResponseCache>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> HttpResponse {
+    cache
+        .respond(&req, &metrics, "rss_item", &request_id, || async {
+            domain
+                .rss_item(&path)
+                .await
+                .map_err(|err| {
+                    ApiError::failure(
+                        err,
+                        "rss_item_read_failed",
+                        "Failed to read RSS item.",
+                        "/api/v1/rss/items/{hash}",
+                        &metrics,
+                        &request_id,
+                    )
+                })?
+                .map(RssItemResponse::from)
+                .ok_or_else(|| {
+                    ApiError::not_found(
+                        "rss_item_not_found",
+                        format!("RSS item {} not found.", path.as_str()),
+                        &request_id,
+                    )
+                })
+        })
+        .await
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rss/items/{hash}/sentiment",
+    tag = "rss",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("hash" = String, Path, description = "Hash of the RSS item")
+    ),
+    responses(
+        (status = 200, description = "Latest sentiment analysis of the RSS item", body = RssItemSentimentResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "RSS item not analyzed", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/rss/items/{hash}/sentiment")]
+pub async fn rss_item_sentiment(
+    path: web::Path<String>,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let sentiment = domain
+        .rss_item_sentiments(std::slice::from_ref(&path))
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "rss_item_sentiment_read_failed",
+                "Failed to read RSS item sentiment.",
+                "/api/v1/rss/items/{hash}/sentiment",
+                &metrics,
+                &request_id,
+            )
+        })?
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            ApiError::not_found(
+                "rss_item_sentiment_not_found",
+                format!("RSS item {} is not analyzed.", path.as_str()),
+                &request_id,
+            )
+        })?;
+
+    Ok(HttpResponse::Ok().json(RssItemSentimentResponse::from(sentiment)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rss/items/{hash}/media",
+    tag = "rss",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        ("hash" = String, Path, description = "Hash of the RSS item")
+    ),
+    responses(
+        (status = 200, description = "Archived media of the RSS item with presigned URLs", body = RssItemMediaResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "RSS item not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "Object storage is disabled", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/rss/items/{hash}/media")]
+pub async fn rss_item_media(
+    path: web::Path<String>,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (media, expires_in) = domain
+        .rss_item_media(&path)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "rss_item_media_read_failed",
+                "Failed to read RSS item media.",
+                "/api/v1/rss/items/{hash}/media",
+                &metrics,
+                &request_id,
+            )
+        })?
+        .ok_or_else(|| {
+            ApiError::not_found(
+                "rss_item_not_found",
+                format!("RSS item {} not found.", path.as_str()),
+                &request_id,
+            )
+        })?;
+
+    Ok(HttpResponse::Ok().json(RssItemMediaResponse {
+        hash: path.into_inner(),
+        media: media
+            .into_iter()
+            .map(|(media, url)| RssMediaResponse::new(media, url))
+            .collect(),
+        expires_in_seconds: expires_in.as_secs(),
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rss/search",
+    tag = "rss",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(RssSearchQuery),
+    responses(
+        (status = 200, description = "RSS items matching the query, best first", body = RssSearchResponse),
+        (status = 304, description = "Results not modified since the ETag in If-None-Match"),
+        (status = 400, description = "Empty query", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/rss/search")]
+pub async fn rss_search(
+    req: HttpRequest,
+    query: ValidatedQuery<RssSearchQuery>,
+    domain: web::Data<Domain>,
+    cache: web::Data<ResponseCache>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    if query.q.trim().is_empty() {
+        return Err(ApiError::bad_request(
+            "empty_query",
+            "Search query must not be empty.",
+            &request_id,
+        ));
+    }
+
+    let (limit, offset) = (query.limit(), query.offset());
+    Ok(cache
+        .respond(&req, &metrics, "rss_search", &request_id, || async {
+            let hits = domain
+                .search_rss_items(&query.q, limit, offset)
+                .await
+                .map_err(|err| {
+                    ApiError::failure(
+                        err,
+                        "rss_search_failed",
+                        "Failed to search RSS items.",
+                        "/api/v1/rss/search",
+                        &metrics,
+                        &request_id,
+                    )
+                })?;
+
+            Ok(RssSearchResponse {
+                query: query.q.clone(),
+                hits: hits.into_iter().map(RssSearchHitResponse::from).collect(),
+                limit,
+                offset,
+            })
+        })
+        .await)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rss/semantic-search",
+    tag = "rss",
+    description = "Requires the external embedding service answering on the `embedding_request` NATS subject, see the README.",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(RssSemanticSearchQuery),
+    responses(
+        (status = 200, description = "RSS items nearest in meaning to the query, best first", body = RssSemanticSearchResponse),
+        (status = 304, description = "Results not modified since the ETag in If-None-Match"),
+        (status = 400, description = "Empty query", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "Query could not be embedded, e.g. no embedding service is running", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/rss/semantic-search")]
+pub async fn rss_semantic_search(
+    req: HttpRequest,
+    query: ValidatedQuery<RssSemanticSearchQuery>,
+    domain: web::Data<Domain>,
+    cache: web::Data<ResponseCache>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    if query.q.trim().is_empty() {
+        return Err(ApiError::bad_request(
+            "empty_query",
+            "Search query must not be empty.",
+            &request_id,
+        ));
+    }
+
+    let (limit, offset) = (query.limit(), query.offset());
+    Ok(cache
+        .respond(
+            &req,
+            &metrics,
+            "rss_semantic_search",
+            &request_id,
+            || async {
+                let hits = domain
+                    .semantic_search_rss_items(query.q.trim(), &query.filters(), limit, offset)
+                    .await
+                    .map_err(|err| {
+                        ApiError::failure(
+                            err,
+                            "rss_semantic_search_failed",
+                            "Failed to search RSS items.",
+                            "/api/v1/rss/semantic-search",
+                            &metrics,
+                            &request_id,
+                        )
+                    })?;
+
+                Ok(RssSemanticSearchResponse {
+                    query: query.q.clone(),
+                    hits: hits.into_iter().map(RssSemanticHitResponse::from).collect(),
+                    limit,
+                    offset,
+                })
+            },
+        )
+        .await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/rss/items/{hash}/bookmark",
+    tag = "rss",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        OrganizationHeader,
+        ("hash" = String, Path, description = "Hash of the RSS item")
+    ),
+    responses(
+        (status = 201, description = "RSS item bookmarked", body = RssBookmarkResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a member of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "RSS item not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[post("/rss/items/{hash}/bookmark")]
+pub async fn bookmark_rss_item(
+    path: web::Path<String>,
+    scope: Scope,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (bookmark, item) = domain
+        .bookmark_rss_item(&scope, &path)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "rss_bookmark_failed",
+                "Failed to bookmark RSS item.",
+                "/api/v1/rss/items/{hash}/bookmark",
+                &metrics,
+                &request_id,
+            )
+        })?
+        .ok_or_else(|| {
+            ApiError::not_found(
+                "rss_item_not_found",
+                format!("RSS item {} not found.", path.as_str()),
+                &request_id,
+            )
+        })?;
+
+    Ok(HttpResponse::Created().json(RssBookmarkResponse {
+        item: item.into(),
+        created_at: bookmark.created_at,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/rss/items/{hash}/bookmark",
+    tag = "rss",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        OrganizationHeader,
+        ("hash" = String, Path, description = "Hash of the RSS item")
+    ),
+    responses(
+        (status = 204, description = "Bookmark removed"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a member of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "RSS item is not bookmarked", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[delete("/rss/items/{hash}/bookmark")]
+pub async fn remove_rss_bookmark(
+    path: web::Path<String>,
+    scope: Scope,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let removed = domain
+        .remove_rss_bookmark(&scope, &path)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "rss_bookmark_delete_failed",
+                "Failed to remove RSS item bookmark.",
+                "/api/v1/rss/items/{hash}/bookmark",
+                &metrics,
+                &request_id,
+            )
+        })?;
+    if !removed {
+        return Err(ApiError::not_found(
+            "rss_bookmark_not_found",
+            format!("RSS item {} is not bookmarked.", path.as_str()),
+            &request_id,
+        ));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rss/bookmarks",
+    tag = "rss",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(PageQuery, OrganizationHeader),
+    responses(
+        (status = 200, description = "Bookmarked RSS items, newest bookmark first", body = RssBookmarksResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a member of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/rss/bookmarks")]
+pub async fn rss_bookmarks(
+    query: ValidatedQuery<PageQuery>,
+    scope: Scope,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = (query.limit(), query.offset());
+    let (bookmarks, total) = tokio::try_join!(
+        domain.rss_bookmarks(&scope, limit, offset),
+        domain.count_rss_bookmarks(&scope)
+    )
+    .map_err(|err| {
+        ApiError::failure(
+            err,
+            "rss_bookmarks_read_failed",
+            "Failed to read bookmarks.",
+            "/api/v1/rss/bookmarks",
+            &metrics,
+            &request_id,
+        )
+    })?;
+
+    Ok(HttpResponse::Ok().json(RssBookmarksResponse {
+        bookmarks: bookmarks
+            .into_iter()
+            .map(|(bookmark, item)| RssBookmarkResponse {
+                item: item.into(),
+                created_at: bookmark.created_at,
+            })
+            .collect(),
+        total,
+        limit,
+        offset,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/rss/searches",
+    tag = "rss",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    request_body = SaveSearchRequest,
+    params(OrganizationHeader),
+    responses(
+        (status = 201, description = "Search saved", body = RssSavedSearchResponse),
+        (status = 400, description = "Invalid name or query", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a member of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[post("/rss/searches")]
+pub async fn save_rss_search(
+    body: ValidatedJson<SaveSearchRequest>,
+    scope: Scope,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let search = domain.save_rss_search(&scope, &body).await.map_err(|err| {
+        ApiError::failure(
+            err,
+            "rss_search_save_failed",
+            "Failed to save search.",
+            "/api/v1/rss/searches",
+            &metrics,
+            &request_id,
+        )
+    })?;
+
+    Ok(HttpResponse::Created().json(RssSavedSearchResponse::from(search)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rss/searches",
+    tag = "rss",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(PageQuery, OrganizationHeader),
+    responses(
+        (status = 200, description = "Saved searches, most recently saved first", body = RssSavedSearchesResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a member of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/rss/searches")]
+pub async fn rss_saved_searches(
+    query: ValidatedQuery<PageQuery>,
+    scope: Scope,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = (query.limit(), query.offset());
+    let (searches, total) = tokio::try_join!(
+        domain.rss_saved_searches(&scope, limit, offset),
+        domain.count_rss_saved_searches(&scope)
+    )
+    .map_err(|err| {
+        ApiError::failure(
+            err,
+            "rss_searches_read_failed",
+            "Failed to read saved searches.",
+            "/api/v1/rss/searches",
+            &metrics,
+            &request_id,
+        )
+    })?;
+
+    Ok(HttpResponse::Ok().json(RssSavedSearchesResponse {
+        searches: searches
+            .into_iter()
+            .map(RssSavedSearchResponse::from)
+            .collect(),
+        total,
+        limit,
+        offset,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/rss/searches/{name}",
+    tag = "rss",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        OrganizationHeader,
+        ("name" = String, Path, description = "Name of the saved search")
+    ),
+    responses(
+        (status = 204, description = "Saved search deleted"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a member of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Saved search not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[delete("/rss/searches/{name}")]
+pub async fn delete_rss_saved_search(
+    path: web::Path<String>,
+    scope: Scope,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let deleted = domain
+        .delete_rss_saved_search(&scope, &path)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "rss_search_delete_failed",
+                "Failed to delete saved search.",
+                "/api/v1/rss/searches/{name}",
+                &metrics,
+                &request_id,
+            )
+        })?;
+    if !deleted {
+        return Err(ApiError::not_found(
+            "rss_saved_search_not_found",
+            format!("Saved search {} not found.", path.as_str()),
+            &request_id,
+        ));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/feeds",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(PageQuery),
+    responses(
+        (status = 200, description = "RSS feed sources, newest first", body = RssFeedsResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Admin privileges required", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/feeds")]
+pub async fn rss_feeds(
+    query: ValidatedQuery<PageQuery>,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = (query.limit(), query.offset());
+    let (feeds, total) =
+        tokio::try_join!(domain.rss_feeds(limit, offset), domain.count_rss_feeds()).map_err(
+            |err| {
+                ApiError::failure(
+                    err,
+                    "rss_feeds_read_failed",
+                    "Failed to read RSS feeds.",
+                    "/api/v1/admin/feeds",
+                    &metrics,
+                    &request_id,
+                )
+            },
+        )?;
+
+    Ok(HttpResponse::Ok().json(RssFeedsResponse {
+        feeds: feeds.into_iter().map(RssFeedResponse::from).collect(),
+        total,
+        limit,
+        offset,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/feeds/{hash}",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(
+        ("hash" = String, Path, description = "Hash of the RSS feed URL")
+    ),
+    responses(
+        (status = 200, description = "RSS feed source", body = RssFeedResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Admin privileges required", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "RSS feed not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/feeds/{hash}")]
+pub async fn rss_feed(
+    path: web::Path<String>,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let feed = domain
+        .rss_feed(&path)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "rss_feed_read_failed",
+                "Failed to read RSS feed.",
+                "/api/v1/admin/feeds/{hash}",
+                &metrics,
+                &request_id,
+            )
+        })?
+        .ok_or_else(|| feed_not_found(&path, &request_id))?;
+
+    Ok(HttpResponse::Ok().json(RssFeedResponse::from(feed)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/feeds",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    request_body = CreateRssFeedRequest,
+    responses(
+        (status = 201, description = "RSS feed added", body = RssFeedResponse),
+        (status = 400, description = "Invalid URL or interval", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Admin privileges required", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "RSS feed already exists", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[post("/feeds")]
+pub async fn create_rss_feed(
+    body: ValidatedJson<CreateRssFeedRequest>,
+    claims: Claims,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let result = domain.create_rss_feed(&body).await;
+    audit(
+        &domain,
+        AuditEvent::new(
+            AuditEventType::AdminAction,
+            &claims.sub,
+            result.is_ok(),
+            format!("Add RSS feed {}", body.url),
+            &request_id,
+        ),
+    )
+    .await;
+    let feed = result.map_err(|err| {
+        ApiError::failure(
+            err,
+            "rss_feed_create_failed",
+            "Failed to add RSS feed.",
+            "/api/v1/admin/feeds",
+            &metrics,
+            &request_id,
+        )
+    })?;
+
+    Ok(HttpResponse::Created().json(RssFeedResponse::from(feed)))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/admin/feeds/{hash}",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(
+        ("hash" = String, Path, description = "Hash of the RSS feed URL")
+    ),
+    request_body = UpdateRssFeedRequest,
+    responses(
+        (status = 200, description = "RSS feed updated", body = RssFeedResponse),
+        (status = 400, description = "Invalid interval", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Admin privileges required", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "RSS feed not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[patch("/feeds/{hash}")]
+pub async fn update_rss_feed(
+    path: web::Path<String>,
+    body: ValidatedJson<UpdateRssFeedRequest>,
+    claims: Claims,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let result = domain.update_rss_feed(&path, &body).await;
+    audit(
+        &domain,
+        AuditEvent::new(
+            AuditEventType::AdminAction,
+            &claims.sub,
+            matches!(result, Ok(Some(_))),
+            format!("Update RSS feed {}", path.as_str()),
+            &request_id,
+        ),
+    )
+    .await;
+    let feed = result
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "rss_feed_update_failed",
+                "Failed to update RSS feed.",
+                "/api/v1/admin/feeds/{hash}",
+                &metrics,
+                &request_id,
+            )
+        })?
+        .ok_or_else(|| feed_not_found(&path, &request_id))?;
+
+    Ok(HttpResponse::Ok().json(RssFeedResponse::from(feed)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/feeds/{hash}",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(
+        ("hash" = String, Path, description = "Hash of the RSS feed URL")
+    ),
+    responses(
+        (status = 204, description = "RSS feed deleted"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Admin privileges required", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "RSS feed not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[delete("/feeds/{hash}")]
+pub async fn delete_rss_feed(
+    path: web::Path<String>,
+    claims: Claims,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let result = domain.delete_rss_feed(&path).await;
+    audit(
+        &domain,
+        AuditEvent::new(
+            AuditEventType::AdminAction,
+            &claims.sub,
+            matches!(result, Ok(Some(_))),
+            format!("Delete RSS feed {}", path.as_str()),
+            &request_id,
+        ),
+    )
+    .await;
+    result
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "rss_feed_delete_failed",
+                "Failed to delete RSS feed.",
+                "/api/v1/admin/feeds/{hash}",
+                &metrics,
+                &request_id,
+            )
+        })?
+        .ok_or_else(|| feed_not_found(&path, &request_id))?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn feed_not_found(hash: &str, request_id: &RequestId) -> ApiError {
+    ApiError::not_found(
+        "rss_feed_not_found",
+        format!("RSS feed {hash} not found."),
+        request_id,
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/rss/items/purge",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    request_body = PurgeRssItemsRequest,
+    responses(
+        (status = 200, description = "RSS items meeting all criteria deleted, they are purged from storage after the retention grace period", body = PurgeRssItemsResponse),
+        (status = 400, description = "Missing or invalid criteria", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Admin privileges required", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[post("/rss/items/purge")]
+pub async fn purge_rss_items(
+    body: ValidatedJson<PurgeRssItemsRequest>,
+    claims: Claims,
+    domain: web::Data<Domain>,
+    cache: web::Data<ResponseCache>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let result = domain.delete_rss_items(&body).await;
+    audit(
+        &domain,
+        AuditEvent::new(
+            AuditEventType::AdminAction,
+            &claims.sub,
+            result.is_ok(),
+            match &result {
+                Ok(deleted) => format!("Delete {deleted} RSS items"),
+                Err(_) => "Delete RSS items".to_string(),
+            },
+            &request_id,
+        ),
+    )
+    .await;
+    let deleted = result.map_err(|err| {
+        ApiError::failure(
+            err,
+            "rss_items_purge_failed",
+            "Failed to delete RSS items.",
+            "/api/v1/admin/rss/items/purge",
+            &metrics,
+            &request_id,
+        )
+    })?;
+    if deleted > 0
+        && let Err(e) = cache.invalidate().await
+    {
+        tracing::warn!("Failed to invalidate response cache: {e}");
+    }
+
+    Ok(HttpResponse::Ok().json(PurgeRssItemsResponse { deleted }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rss/export",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(RssExportQuery),
+    responses(
+        (status = 200, description = "RSS items published in the period, oldest first, streamed as JSON lines or CSV", content(
+            (String = "application/x-ndjson"),
+            (String = "text/csv"),
+        )),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Admin privileges required", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/rss/export")]
+pub async fn export_rss_items(
+    query: ValidatedQuery<RssExportQuery>,
+    claims: Claims,
+    config: web::Data<Config>,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    // Mounted next to the RSS routes for analysts, so the admin check is made here rather than by
+    // the admin scope.
+    if !config.admin.allows(&claims) {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "forbidden",
+            "Admin privileges required",
+            request_id,
+        ));
+    }
+
+    let format = query.format.unwrap_or_default();
+    audit(
+        &domain,
+        AuditEvent::new(
+            AuditEventType::AdminAction,
+            &claims.sub,
+            true,
+            format!("Export RSS items as {format}"),
+            &request_id,
+        ),
+    )
+    .await;
+
+    let mut items = domain.export_rss_items(query.filters());
+    let rows = stream::poll_fn(move |cx| items.poll_recv(cx)).map(move |item| {
+        item.and_then(|item| format.encode(item)).map_err(|err| {
+            ApiError::failure(
+                err,
+                "rss_export_failed",
+                "Failed to export RSS items.",
+                "/api/v1/rss/export",
+                &metrics,
+                &request_id,
+            )
+            .into()
+        })
+    });
+    let body = stream::iter(format.header().map(Ok)).chain(rows);
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"rss-items.{format}\""),
+        ))
+        .streaming::<_, actix_web::Error>(body))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/audit",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(AuditLogQuery),
+    responses(
+        (status = 200, description = "Page of audit log events matching the filters, newest first", body = AuditEventsResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Admin privileges required", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/audit")]
+pub async fn audit_log(
+    query: ValidatedQuery<AuditLogQuery>,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = (query.limit(), query.offset());
+    let filters = query.filters();
+    let (events, total) = tokio::try_join!(
+        domain.audit_events(&filters, limit, offset),
+        domain.count_audit_events(&filters)
+    )
+    .map_err(|err| {
+        ApiError::failure(
+            err,
+            "audit_log_read_failed",
+            "Failed to read the audit log.",
+            "/api/v1/admin/audit",
+            &metrics,
+            &request_id,
+        )
+    })?;
+
+    Ok(HttpResponse::Ok().json(AuditEventsResponse {
+        events: events.into_iter().map(AuditEventResponse::from).collect(),
+        total,
+        limit,
+        offset,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/stats",
+    tag = "admin",
+    security(("bearer_auth" = [])),
+    params(AdminStatsQuery),
+    responses(
+        (status = 200, description = "Aggregated statistics of the covered period", body = AdminStatsResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Admin privileges required", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/stats")]
+pub async fn admin_stats(
+    query: ValidatedQuery<AdminStatsQuery>,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let stats = domain.admin_stats(query.days()).await.map_err(|err| {
+        ApiError::failure(
+            err,
+            "admin_stats_failed",
+            "Failed to compute statistics.",
+            "/api/v1/admin/stats",
+            &metrics,
+            &request_id,
+        )
+    })?;
+
+    Ok(HttpResponse::Ok().json(stats))
+}
+
+/// Appends the event to the audit log, failures are logged and do not fail the request.
+async fn audit(domain: &Domain, event: AuditEvent) {
+    if let Err(err) = domain.record_audit_event(event).await {
+        tracing::error!("Failed to record audit event: {err}");
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    request_body = CreateWebhookRequest,
+    params(OrganizationHeader),
+    responses(
+        (status = 201, description = "Webhook registered", body = WebhookResponse),
+        (status = 400, description = "Invalid URL, event types or secret", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 402, description = "Not available in the free tier", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a member of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[post("/webhooks")]
+pub async fn create_webhook(
+    body: ValidatedJson<CreateWebhookRequest>,
+    scope: Scope,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let subscription = domain.create_webhook(&scope, &body).await.map_err(|err| {
+        ApiError::failure(
+            err,
+            "webhook_create_failed",
+            "Failed to register webhook.",
+            "/api/v1/webhooks",
+            &metrics,
+            &request_id,
+        )
+    })?;
+
+    Ok(HttpResponse::Created().json(WebhookResponse::from(subscription)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(PageQuery, OrganizationHeader),
+    responses(
+        (status = 200, description = "Webhooks, newest first", body = WebhooksResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 402, description = "Not available in the free tier", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a member of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/webhooks")]
+pub async fn webhooks(
+    query: ValidatedQuery<PageQuery>,
+    scope: Scope,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = (query.limit(), query.offset());
+    let (webhooks, total) = tokio::try_join!(
+        domain.webhooks(&scope, limit, offset),
+        domain.count_webhooks(&scope)
+    )
+    .map_err(|err| {
+        ApiError::failure(
+            err,
+            "webhooks_read_failed",
+            "Failed to read webhooks.",
+            "/api/v1/webhooks",
+            &metrics,
+            &request_id,
+        )
+    })?;
+
+    Ok(HttpResponse::Ok().json(WebhooksResponse {
+        webhooks: webhooks.into_iter().map(WebhookResponse::from).collect(),
+        total,
+        limit,
+        offset,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{id}",
+    tag = "webhooks",
+    security(("bearer_auth" = []), ("api_key" = [])),
+    params(
+        OrganizationHeader,
+        ("id" = String, Path, description = "ID of the webhook")
+    ),
+    responses(
+        (status = 204, description = "Webhook deleted"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 402, description = "Not available in the free tier", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a member of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Webhook not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[delete("/webhooks/{id}")]
+pub async fn delete_webhook(
+    path: web::Path<String>,
+    scope: Scope,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let deleted = domain.delete_webhook(&scope, &path).await.map_err(|err| {
+        ApiError::failure(
+            err,
+            "webhook_delete_failed",
+            "Failed to delete webhook.",
+            "/api/v1/webhooks/{id}",
+            &metrics,
+            &request_id,
+        )
+    })?;
+    if !deleted {
+        return Err(ApiError::not_found(
+            "webhook_not_found",
+            format!("Webhook {} not found.", path.as_str()),
+            &request_id,
+        ));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/digest",
+    tag = "digest",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Email digest preferences", body = DigestPreferencesResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 402, description = "Not available in the free tier", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "No email digest", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/digest")]
+pub async fn digest_preferences(
+    claims: Claims,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let preference = domain
+        .digest_preferences(&claims.sub)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "digest_read_failed",
+                "Failed to read email digest preferences.",
+                "/api/v1/digest",
+                &metrics,
+                &request_id,
+            )
+        })?
+        .ok_or_else(|| digest_not_found(&request_id))?;
+
+    Ok(HttpResponse::Ok().json(DigestPreferencesResponse::from(preference)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/digest",
+    tag = "digest",
+    security(("bearer_auth" = [])),
+    request_body = DigestPreferencesRequest,
+    responses(
+        (status = 200, description = "Email digest preferences saved, a new address is mailed a confirmation token", body = DigestPreferencesResponse),
+        (status = 400, description = "Invalid email or categories", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 402, description = "Not available in the free tier", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "Email is disabled", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[put("/digest")]
+pub async fn save_digest_preferences(
+    body: ValidatedJson<DigestPreferencesRequest>,
+    claims: Claims,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let preference = domain
+        .save_digest_preferences(&claims.sub, &body)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "digest_save_failed",
+                "Failed to save email digest preferences.",
+                "/api/v1/digest",
+                &metrics,
+                &request_id,
+            )
+        })?;
+
+    Ok(HttpResponse::Ok().json(DigestPreferencesResponse::from(preference)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/digest/confirm",
+    tag = "digest",
+    security(("bearer_auth" = [])),
+    request_body = DigestConfirmationRequest,
+    responses(
+        (status = 200, description = "Email digest address confirmed", body = DigestPreferencesResponse),
+        (status = 400, description = "Invalid or expired token", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 402, description = "Not available in the free tier", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[post("/digest/confirm")]
+pub async fn confirm_digest(
+    body: ValidatedJson<DigestConfirmationRequest>,
+    claims: Claims,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let preference = domain
+        .confirm_digest(&claims.sub, &body)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "digest_confirm_failed",
+                "Failed to confirm email digest address.",
+                "/api/v1/digest/confirm",
+                &metrics,
+                &request_id,
+            )
+        })?;
+
+    Ok(HttpResponse::Ok().json(DigestPreferencesResponse::from(preference)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/digest",
+    tag = "digest",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 204, description = "Email digest deleted"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 402, description = "Not available in the free tier", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "No email digest", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[delete("/digest")]
+pub async fn delete_digest_preferences(
+    claims: Claims,
+    domain: web::Data<Domain>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let deleted = domain
+        .delete_digest_preferences(&claims.sub)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "digest_delete_failed",
+                "Failed to delete email digest.",
+                "/api/v1/digest",
+                &metrics,
+                &request_id,
+            )
+        })?;
+    if !deleted {
+        return Err(digest_not_found(&request_id));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+fn digest_not_found(request_id: &RequestId) -> ApiError {
+    ApiError::not_found(
+        "digest_not_found",
+        "No email digest is configured.",
+        request_id,
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations",
+    tag = "organizations",
+    security(("bearer_auth" = [])),
+    request_body = CreateOrganizationRequest,
+    responses(
+        (status = 201, description = "Organization created, the user is its owner", body = OrganizationResponse),
+        (status = 400, description = "Invalid name", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[post("/organizations")]
+pub async fn create_organization(
+    body: ValidatedJson<CreateOrganizationRequest>,
+    claims: Claims,
+    organizations: web::Data<Organizations>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let organization = organizations
+        .create(&claims.sub, &body)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "organization_create_failed",
+                "Failed to create organization.",
+                "/api/v1/organizations",
+                &metrics,
+                &request_id,
+            )
+        })?;
+    let membership = OrganizationMember::new(
+        &organization.id,
+        &claims.sub,
+        OrganizationRole::Owner,
+        organization.created_at,
+    );
+
+    Ok(HttpResponse::Created().json(OrganizationResponse::new(organization, &membership)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations",
+    tag = "organizations",
+    security(("bearer_auth" = [])),
+    params(PageQuery),
+    responses(
+        (status = 200, description = "Organizations of the user, most recently joined first", body = OrganizationsResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/organizations")]
+pub async fn user_organizations(
+    query: ValidatedQuery<PageQuery>,
+    claims: Claims,
+    organizations: web::Data<Organizations>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = (query.limit(), query.offset());
+    let (page, total) = tokio::try_join!(
+        organizations.organizations(&claims.sub, limit, offset),
+        organizations.count_organizations(&claims.sub)
+    )
+    .map_err(|err| {
+        ApiError::failure(
+            err,
+            "organizations_read_failed",
+            "Failed to read organizations.",
+            "/api/v1/organizations",
+            &metrics,
+            &request_id,
+        )
+    })?;
+
+    Ok(HttpResponse::Ok().json(OrganizationsResponse {
+        organizations: page
+            .into_iter()
+            .map(|(membership, organization)| OrganizationResponse::new(organization, &membership))
+            .collect(),
+        total,
+        limit,
+        offset,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/members",
+    tag = "organizations",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = String, Path, description = "ID of the organization"),
+        PageQuery
+    ),
+    responses(
+        (status = 200, description = "Members of the organization, newest first", body = OrganizationMembersResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not a member of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/organizations/{id}/members")]
+pub async fn organization_members(
+    path: web::Path<String>,
+    query: ValidatedQuery<PageQuery>,
+    claims: Claims,
+    organizations: web::Data<Organizations>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = (query.limit(), query.offset());
+    let (members, total) = organizations
+        .members(&claims.sub, &path, limit, offset)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "organization_members_read_failed",
+                "Failed to read organization members.",
+                "/api/v1/organizations/{id}/members",
+                &metrics,
+                &request_id,
+            )
+        })?;
+
+    Ok(HttpResponse::Ok().json(OrganizationMembersResponse {
+        members: members
+            .into_iter()
+            .map(OrganizationMemberResponse::from)
+            .collect(),
+        total,
+        limit,
+        offset,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{id}/members",
+    tag = "organizations",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = String, Path, description = "ID of the organization")
+    ),
+    request_body = AddOrganizationMemberRequest,
+    responses(
+        (status = 201, description = "Member added", body = OrganizationMemberResponse),
+        (status = 400, description = "Invalid wallet", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[post("/organizations/{id}/members")]
+pub async fn add_organization_member(
+    path: web::Path<String>,
+    body: ValidatedJson<AddOrganizationMemberRequest>,
+    claims: Claims,
+    organizations: web::Data<Organizations>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let member = organizations
+        .add_member(&claims.sub, &path, &body.user_id)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "organization_member_add_failed",
+                "Failed to add organization member.",
+                "/api/v1/organizations/{id}/members",
+                &metrics,
+                &request_id,
+            )
+        })?;
+
+    Ok(HttpResponse::Created().json(OrganizationMemberResponse::from(member)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/members/{user_id}",
+    tag = "organizations",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = String, Path, description = "ID of the organization"),
+        ("user_id" = String, Path, description = "Wallet of the member")
+    ),
+    responses(
+        (status = 204, description = "Member removed, the API keys the member created are revoked"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Member not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The owner cannot leave the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[delete("/organizations/{id}/members/{user_id}")]
+pub async fn remove_organization_member(
+    path: web::Path<(String, String)>,
+    claims: Claims,
+    organizations: web::Data<Organizations>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (org_id, user_id) = path.into_inner();
+    let removed = organizations
+        .remove_member(&claims.sub, &org_id, &user_id)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "organization_member_remove_failed",
+                "Failed to remove organization member.",
+                "/api/v1/organizations/{id}/members/{user_id}",
+                &metrics,
+                &request_id,
+            )
+        })?;
+    if !removed {
+        return Err(ApiError::not_found(
+            "organization_member_not_found",
+            format!("Member {user_id} not found."),
+            &request_id,
+        ));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/organizations/{id}/api-keys",
+    tag = "organizations",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = String, Path, description = "ID of the organization")
+    ),
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "API key created, the key is only returned once", body = CreatedApiKeyResponse),
+        (status = 400, description = "Invalid name", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[post("/organizations/{id}/api-keys")]
+pub async fn create_organization_api_key(
+    path: web::Path<String>,
+    body: ValidatedJson<CreateApiKeyRequest>,
+    claims: Claims,
+    organizations: web::Data<Organizations>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (api_key, key) = organizations
+        .create_api_key(&claims.sub, &path, &body)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "organization_api_key_create_failed",
+                "Failed to create API key.",
+                "/api/v1/organizations/{id}/api-keys",
+                &metrics,
+                &request_id,
+            )
+        })?;
+
+    Ok(HttpResponse::Created().json(CreatedApiKeyResponse {
+        api_key: api_key.into(),
+        key,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/organizations/{id}/api-keys",
+    tag = "organizations",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = String, Path, description = "ID of the organization"),
+        PageQuery
+    ),
+    responses(
+        (status = 200, description = "API keys of the organization, newest first", body = ApiKeysResponse),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Request failed validation", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[get("/organizations/{id}/api-keys")]
+pub async fn organization_api_keys(
+    path: web::Path<String>,
+    query: ValidatedQuery<PageQuery>,
+    claims: Claims,
+    organizations: web::Data<Organizations>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (limit, offset) = (query.limit(), query.offset());
+    let (api_keys, total) = organizations
+        .api_keys(&claims.sub, &path, limit, offset)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "organization_api_keys_read_failed",
+                "Failed to read API keys.",
+                "/api/v1/organizations/{id}/api-keys",
+                &metrics,
+                &request_id,
+            )
+        })?;
+
+    Ok(HttpResponse::Ok().json(ApiKeysResponse {
+        api_keys: api_keys.into_iter().map(ApiKeyResponse::from).collect(),
+        total,
+        limit,
+        offset,
+    }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/organizations/{id}/api-keys/{key_id}",
+    tag = "organizations",
+    security(("bearer_auth" = [])),
+    params(
+        ("id" = String, Path, description = "ID of the organization"),
+        ("key_id" = String, Path, description = "ID of the API key")
+    ),
+    responses(
+        (status = 204, description = "API key revoked, it is rejected from now on"),
+        (status = 401, description = "Unauthorized", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Not the owner of the organization", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "API key not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json"),
+    )
+)]
+#[delete("/organizations/{id}/api-keys/{key_id}")]
+pub async fn revoke_organization_api_key(
+    path: web::Path<(String, String)>,
+    claims: Claims,
+    organizations: web::Data<Organizations>,
+    metrics: web::Data<Metrics>,
+    request_id: RequestId,
+) -> Result<HttpResponse, ApiError> {
+    let (org_id, key_id) = path.into_inner();
+    let revoked = organizations
+        .revoke_api_key(&claims.sub, &org_id, &key_id)
+        .await
+        .map_err(|err| {
+            ApiError::failure(
+                err,
+                "organization_api_key_revoke_failed",
+                "Failed to revoke API key.",
+                "/api/v1/organizations/{id}/api-keys/{key_id}",
+                &metrics,
+                &request_id,
+            )
+        })?;
+    if !revoked {
+        return Err(ApiError::not_found(
+            "organization_api_key_not_found",
+            format!("API key {key_id} not found."),
+            &request_id,
+        ));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
 }
@@ -0,0 +1,137 @@
+use crate::{
+    database::PostgresStorageGateway,
+    models::{DependencyCheck, ReadinessResponse},
+};
+use anyhow::Result;
+use chrono::Utc;
+use nats_middleware::NatsQueue;
+use redis_middleware::RedisMiddleware;
+use std::{collections::BTreeMap, future::Future, time::Duration};
+use tokio::time::{Instant, timeout};
+
+const STATUS_UP: &str = "up";
+const STATUS_DOWN: &str = "down";
+
+/// ReadinessProbe pings the dependencies the server cannot serve requests without.
+pub struct ReadinessProbe {
+    storage: PostgresStorageGateway,
+    redis: RedisMiddleware,
+    queue: NatsQueue,
+    timeout: Duration,
+}
+
+impl ReadinessProbe {
+    /// Creates a new readiness probe.
+    ///
+    /// # Arguments
+    /// * `storage` - The Postgres storage gateway.
+    /// * `redis` - The Redis client.
+    /// * `queue` - The NATS queue.
+    /// * `timeout` - Time each dependency has to answer.
+    pub fn new(
+        storage: PostgresStorageGateway,
+        redis: RedisMiddleware,
+        queue: NatsQueue,
+        timeout: Duration,
+    ) -> Self {
+        Self {
+            storage,
+            redis,
+            queue,
+            timeout,
+        }
+    }
+
+    /// Pings all dependencies concurrently.
+    ///
+    /// # Returns
+    /// The readiness of the server with the status of every dependency.
+    pub async fn check(&self) -> ReadinessResponse {
+        let (postgres, redis, nats) = tokio::join!(
+            check_dependency(self.timeout, self.storage.ping()),
+            check_dependency(self.timeout, self.redis.ping()),
+            check_dependency(self.timeout, async {
+                self.queue.ping().await.map_err(anyhow::Error::from)
+            }),
+        );
+
+        readiness(BTreeMap::from([
+            ("postgres".to_string(), postgres),
+            ("redis".to_string(), redis),
+            ("nats".to_string(), nats),
+        ]))
+    }
+}
+
+/// Runs a single dependency check, a check not finished within the timeout is down.
+async fn check_dependency<F>(limit: Duration, ping: F) -> DependencyCheck
+where
+    F: Future<Output = Result<()>>,
+{
+    let started = Instant::now();
+    let error = match timeout(limit, ping).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("Timed out after {} ms", limit.as_millis())),
+    };
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match error {
+        None => DependencyCheck {
+            status: STATUS_UP.to_string(),
+            latency_ms,
+            error: None,
+        },
+        Some(error) => {
+            tracing::warn!("Readiness check failed: {error}");
+            DependencyCheck {
+                status: STATUS_DOWN.to_string(),
+                latency_ms,
+                error: Some(error),
+            }
+        }
+    }
+}
+
+fn readiness(checks: BTreeMap<String, DependencyCheck>) -> ReadinessResponse {
+    let ready = checks.values().all(|check| check.status == STATUS_UP);
+    ReadinessResponse {
+        status: if ready { "ready" } else { "not_ready" }.to_string(),
+        timestamp: Utc::now(),
+        checks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[tokio::test]
+    async fn test_check_dependency() {
+        let limit = Duration::from_millis(20);
+
+        let up = check_dependency(limit, async { Ok(()) }).await;
+        assert_eq!(up.status, STATUS_UP);
+        assert_eq!(up.error, None);
+
+        let failed = check_dependency(limit, async { Err(anyhow!("connection refused")) }).await;
+        assert_eq!(failed.status, STATUS_DOWN);
+        assert_eq!(failed.error.as_deref(), Some("connection refused"));
+
+        let hanging = check_dependency(limit, std::future::pending()).await;
+        assert_eq!(hanging.status, STATUS_DOWN);
+        assert_eq!(hanging.error.as_deref(), Some("Timed out after 20 ms"));
+
+        let response = readiness(BTreeMap::from([
+            ("postgres".to_string(), up.clone()),
+            ("redis".to_string(), up.clone()),
+        ]));
+        assert_eq!(response.status, "ready");
+        let response = readiness(BTreeMap::from([
+            ("postgres".to_string(), up),
+            ("nats".to_string(), hanging),
+        ]));
+        assert_eq!(response.status, "not_ready");
+    }
+}
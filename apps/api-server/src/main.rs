@@ -1,68 +1,70 @@
 use actix_cors::Cors;
-use actix_web::{App, HttpServer, middleware::Logger, web};
+use actix_web::{App, HttpServer, ResponseError, middleware::Logger, web};
 use anyhow::Context;
 use anyhow::anyhow;
 use auth::Authenticator;
+use background::BackgroundTasks;
+use circuit_breaker::CircuitBreaker;
 use config::Config;
 use database::PostgresStorageGateway;
+use digest::{DigestMailer, DigestScheduler};
 use domain::Domain;
 use dotenvy::dotenv;
+use errors::ApiError;
+use health::ReadinessProbe;
+use media::MediaArchiver;
 use message_queue::RssFeedsProcessor;
+use middleware_v1::RequestId;
 use nats_middleware::NatsQueue;
+use object_storage::ObjectStorageGateway;
+use organizations::Organizations;
+use payments::SolanaPayments;
+use redis_middleware::RedisMiddleware;
+use response_cache::ResponseCache;
+use retention::RetentionPolicy;
+use routes::ApiDoc;
+use sessions::SessionRegistry;
+use shared_states::{RssItem, SentimentResult};
 use sqlx::migrate::Migrator;
 use std::io::{Error, ErrorKind};
 use std::sync::Arc;
 use std::time::Duration;
 use telemetry::Metrics;
-use tokio::time::interval;
+use tiers::Quotas;
+use tokio::sync::oneshot;
+use tokio::time::{Instant, interval, timeout_at};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
+use webhooks::{WebhookDispatcher, WebhookEventType};
 
+mod audit;
 mod auth;
+mod background;
 mod config;
 mod constants;
 mod database;
+mod digest;
 mod domain;
+mod errors;
+mod export;
+mod grpc;
 mod handlers_v1;
+mod health;
+mod media;
 mod message_queue;
 mod middleware_v1;
 mod models;
+mod object_storage;
+mod organizations;
+mod payments;
+mod response_cache;
+mod retention;
+mod routes;
+mod sessions;
+mod shutdown;
 mod telemetry;
-
-#[derive(OpenApi)]
-#[openapi(
-    paths(
-        handlers_v1::register,
-        handlers_v1::login,
-        handlers_v1::health,
-        handlers_v1::metrics_endpoint
-    ),
-    components(
-        schemas(
-            models::UserResponse,
-            models::Claims,
-            models::ErrorResponse
-        )
-    ),
-    tags(
-        (name = "auth", description = "Authentication endpoints"),
-        (name = "health", description = "Health check endpoints")
-    ),
-    info(
-        title = "Semantic Machine API",
-        version = "1.0.0",
-        description = "REST API for Semantic-Machine services",
-        contact(
-            name = "Bartosz Lenart",
-            email = "bartossh@pm.me"
-        ),
-    ),
-    servers(
-        (url = "http://localhost:8080", description = "Local development server"),
-        (url = "https://semantic-machine-dev.up.railway.app", description = "Development server")
-    )
-)]
-struct ApiDoc;
+mod tiers;
+mod webhooks;
 
 #[inline(always)]
 #[allow(clippy::io_other_error)]
@@ -70,14 +72,29 @@ fn to_io_error(e: anyhow::Error) -> Error {
     Error::new(ErrorKind::Other, format!("{e}"))
 }
 
-/// Start a background task to periodically update system metrics
+/// Start a background task to periodically update system, database pool, circuit breaker and
+/// session metrics
 #[inline(always)]
-async fn start_metrics_updater(metrics: Arc<Metrics>) {
+async fn start_metrics_updater(
+    metrics: Arc<Metrics>,
+    storage: PostgresStorageGateway,
+    breakers: Vec<Arc<CircuitBreaker>>,
+    sessions: Arc<SessionRegistry>,
+) {
     tokio::spawn(async move {
         let mut ticker = interval(Duration::from_secs(10));
         loop {
             ticker.tick().await;
             metrics.update_system_metrics();
+            let (active, idle) = storage.pool_stats();
+            metrics.record_db_pool(active, idle);
+            for breaker in &breakers {
+                metrics.record_circuit_breaker(breaker);
+            }
+            match sessions.count_active().await {
+                Ok(active) => metrics.active_sessions.set(active),
+                Err(e) => tracing::warn!("Failed to count active sessions: {e}"),
+            }
         }
     });
 }
@@ -99,9 +116,7 @@ async fn main() -> std::io::Result<()> {
 
     let metrics = Arc::new(Metrics::new().expect("Failed to create metrics"));
 
-    start_metrics_updater(metrics.clone()).await;
-
-    let storage = PostgresStorageGateway::new(&config.database.url)
+    let storage = PostgresStorageGateway::new(&config.database)
         .await
         .map_err(to_io_error)?;
 
@@ -113,15 +128,134 @@ async fn main() -> std::io::Result<()> {
         .map_err(|e| anyhow!("Cannot connect to NATs, {e}"))
         .map_err(to_io_error)?;
 
-    let message_queue_processor = RssFeedsProcessor::new(storage.clone(), nats_queue);
-    tokio::spawn(async move {
-        if let Err(e) = message_queue_processor.run().await {
-            panic!("Error running message queue processor: {}", e);
+    let objects = if config.minio.enabled {
+        let objects = ObjectStorageGateway::new(&config.minio);
+        objects.ensure_bucket().await.map_err(to_io_error)?;
+        Some(objects)
+    } else {
+        None
+    };
+
+    let redis = RedisMiddleware::new(&config.redis.url).map_err(to_io_error)?;
+    let breakers = vec![
+        storage.circuit_breaker(),
+        redis.circuit_breaker(),
+        nats_queue.circuit_breaker(),
+    ];
+    let sessions = Arc::new(SessionRegistry::new(storage.clone(), redis.clone()));
+    let organizations = Arc::new(Organizations::new(storage.clone()));
+    let quotas = Arc::new(Quotas::new(
+        storage.clone(),
+        redis.clone(),
+        config.quota.clone(),
+    ));
+    let payments = Arc::new(
+        SolanaPayments::new(storage.clone(), redis.clone(), config.payments.clone())
+            .map_err(to_io_error)?,
+    );
+    let response_cache = Arc::new(if config.response_cache.enabled {
+        ResponseCache::new(redis.clone(), config.response_cache.ttl_seconds)
+    } else {
+        ResponseCache::disabled()
+    });
+
+    let mut rss_processor =
+        RssFeedsProcessor::new(storage.clone(), nats_queue.clone(), metrics.clone())
+            .with_response_cache(response_cache.clone());
+    if let Some(objects) = &objects {
+        rss_processor = rss_processor.with_media_archiver(
+            MediaArchiver::new(
+                storage.clone(),
+                objects.clone(),
+                config.minio.media_max_bytes,
+            )
+            .map_err(to_io_error)?,
+        );
+    }
+    start_metrics_updater(metrics.clone(), storage.clone(), breakers, sessions.clone()).await;
+
+    let rss_processor = Arc::new(rss_processor);
+    let mut background_tasks = BackgroundTasks::new();
+    let processor = rss_processor.clone();
+    background_tasks.spawn("rss-items-consumer", move || {
+        let processor = processor.clone();
+        async move { processor.run().await }
+    });
+    let processor = rss_processor.clone();
+    background_tasks.spawn("rss-channels-consumer", move || {
+        let processor = processor.clone();
+        async move { processor.run_channels().await }
+    });
+    let processor = rss_processor.clone();
+    background_tasks.spawn("rss-feeds-responder", move || {
+        let processor = processor.clone();
+        async move { processor.run_feed_requests().await }
+    });
+    let processor = rss_processor.clone();
+    background_tasks.spawn("rss-sentiments-consumer", move || {
+        let processor = processor.clone();
+        async move { processor.run_sentiments().await }
+    });
+    let processor = rss_processor.clone();
+    background_tasks.spawn("rss-embeddings-consumer", move || {
+        let processor = processor.clone();
+        async move { processor.run_embeddings().await }
+    });
+
+    let webhook_dispatcher = Arc::new(
+        WebhookDispatcher::new(storage.clone(), nats_queue.clone(), metrics.clone())
+            .map_err(to_io_error)?,
+    );
+    let dispatcher = webhook_dispatcher.clone();
+    background_tasks.spawn("webhooks-rss-items", move || {
+        let dispatcher = dispatcher.clone();
+        async move { dispatcher.run::<RssItem>(WebhookEventType::RssItem).await }
+    });
+    let dispatcher = webhook_dispatcher.clone();
+    background_tasks.spawn("webhooks-sentiments", move || {
+        let dispatcher = dispatcher.clone();
+        async move {
+            dispatcher
+                .run::<SentimentResult>(WebhookEventType::Sentiment)
+                .await
         }
     });
 
-    let auth = Authenticator::new(&config.jwt);
-    let auth_arc = Arc::new(Authenticator::new(&config.jwt));
+    let digest_mailer = if config.smtp.enabled {
+        Some(DigestMailer::new(&config.smtp).map_err(to_io_error)?)
+    } else {
+        None
+    };
+    if let Some(mailer) = digest_mailer.clone() {
+        let scheduler = Arc::new(DigestScheduler::new(storage.clone(), mailer));
+        background_tasks.spawn("email-digests", move || {
+            let scheduler = scheduler.clone();
+            async move { scheduler.run().await }
+        });
+    }
+
+    if config.retention.enabled {
+        let retention = Arc::new(RetentionPolicy::new(
+            storage.clone(),
+            objects.clone(),
+            config.retention.clone(),
+        ));
+        background_tasks.spawn("rss-items-retention", move || {
+            let retention = retention.clone();
+            async move { retention.run().await }
+        });
+    }
+
+    let auth = Arc::new(Authenticator::try_new(&config.jwt).map_err(to_io_error)?);
+    #[cfg(unix)]
+    {
+        let jwt_config = config.jwt.clone();
+        let authenticator = auth.clone();
+        background_tasks.spawn("jwt-keys-reloader", move || {
+            let (authenticator, config) = (authenticator.clone(), jwt_config.clone());
+            async move { auth::reload_keys_on_hangup(authenticator, config).await }
+        });
+    }
     let generator_secret_bytes: [u8; 32] =
         hex::decode(config.generator_secret.secret_key.as_bytes())
             .context("Cannot decode generator secret, not an hex strning")
@@ -130,23 +264,56 @@ async fn main() -> std::io::Result<()> {
             .map_err(|_| anyhow!("Cannot convert to array of 32 bytes"))
             .map_err(to_io_error)?;
 
-    let domain = web::Data::new(Domain::try_new(
-        storage,
-        auth,
-        generator_secret_bytes,
-        config.server.origin.clone(),
+    let domain = web::Data::new(
+        Domain::try_new(
+            storage.clone(),
+            objects,
+            nats_queue.clone(),
+            auth.clone(),
+            redis.clone(),
+            generator_secret_bytes,
+            config.server.origin.clone(),
+        )
+        .map_err(to_io_error)?
+        .with_digest_mailer(digest_mailer),
+    );
+
+    if config.grpc.enabled {
+        let grpc_domain = domain.clone().into_inner();
+        let grpc_config = Arc::new(config.grpc.clone());
+        let stop_signal = background_tasks.stop_signal();
+        background_tasks.spawn("grpc-server", move || {
+            let (domain, config) = (grpc_domain.clone(), grpc_config.clone());
+            let stop_signal = stop_signal.clone();
+            async move { grpc::serve(domain, &config, stop_signal).await }
+        });
+    }
+
+    let readiness_probe = web::Data::new(ReadinessProbe::new(
+        storage.clone(),
+        redis,
+        nats_queue.clone(),
+        Duration::from_millis(config.server.health_check_timeout_ms),
     ));
 
     let openapi = ApiDoc::openapi();
 
     let metrics_middleware = middleware_v1::MetricsMiddleware::new(metrics.clone());
-    let jwt_middleware = middleware_v1::JwtMiddleware::new(auth_arc.clone());
+    let jwt_middleware =
+        middleware_v1::JwtMiddleware::new(auth.clone(), sessions.clone(), organizations.clone());
+    let quota_middleware = middleware_v1::QuotaMiddleware::new(quotas);
+    let admin_middleware = middleware_v1::AdminMiddleware::new(Arc::new(config.admin.clone()));
+    let route_limits_middleware = middleware_v1::RouteLimitsMiddleware::new(
+        Arc::new(config.route_limits.clone()),
+        metrics.clone(),
+    );
 
     let server_host = config.server.host.clone();
     let server_port = config.server.port;
     let server_workers = config.server.workers;
     let server_keep_alive = config.server.keep_alive;
     let server_request_timeout = config.server.request_timeout;
+    let server_shutdown_timeout = config.server.shutdown_timeout;
     let jaeger_enabled = config.telemetry.jaeger_enabled;
     let jaeger_endpoint = config.telemetry.jaeger_endpoint.clone();
     let prometheus_enabled = config.metrics.prometheus_enabled;
@@ -161,7 +328,7 @@ async fn main() -> std::io::Result<()> {
         } else {
             Cors::default()
                 .allowed_origin(&config.server.origin)
-                .allowed_methods(vec!["GET", "POST", "PUT", "DELETE", "OPTIONS"])
+                .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
                 .allowed_headers(vec![
                     actix_web::http::header::AUTHORIZATION,
                     actix_web::http::header::ACCEPT,
@@ -172,36 +339,65 @@ async fn main() -> std::io::Result<()> {
 
         App::new()
             .app_data(domain.to_owned())
+            .app_data(readiness_probe.clone())
+            .app_data(web::Data::from(response_cache.clone()))
+            .app_data(web::Data::from(sessions.clone()))
+            .app_data(web::Data::from(organizations.clone()))
+            .app_data(web::Data::from(payments.clone()))
+            .app_data(web::Data::from(auth.clone()))
             .app_data(web::Data::new((*metrics).clone()))
             .app_data(web::Data::new(config.clone()))
+            .wrap(route_limits_middleware.clone())
+            .wrap(middleware_v1::RequestIdMiddleware)
             .wrap(metrics_middleware.clone())
             .wrap(Logger::new(
-                "%a %t \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T",
+                "%a %t \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T %{x-request-id}o",
             ))
             .wrap(tracing_actix_web::TracingLogger::default())
             .wrap(cors)
-            .service(handlers_v1::health)
-            .service(handlers_v1::metrics_endpoint)
+            .configure(routes::root_routes)
             .service(
                 SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-docs/openapi.json", openapi.clone()),
             )
             .service(
                 web::scope("/api/v1")
-                    .service(handlers_v1::register)
-                    .service(handlers_v1::login)
-                    .service(web::scope("").wrap(jwt_middleware.clone())),
+                    .configure(routes::public_routes)
+                    .service(
+                        web::scope("")
+                            .wrap(quota_middleware.clone())
+                            .wrap(jwt_middleware.clone())
+                            .configure(routes::user_routes)
+                            .service(
+                                web::scope("/admin")
+                                    .wrap(admin_middleware.clone())
+                                    .configure(routes::admin_routes),
+                            ),
+                    ),
             )
-            .default_service(web::route().to(|| async {
-                actix_web::HttpResponse::NotFound().json(serde_json::json!({
-                    "error": "not_found",
-                    "message": "The requested resource was not found"
-                }))
+            .default_service(web::route().to(|request_id: RequestId| async move {
+                ApiError::not_found(
+                    "not_found",
+                    "The requested resource was not found",
+                    request_id,
+                )
+                .error_response()
             }))
     })
     .workers(server_workers)
     .keep_alive(Duration::from_secs(server_keep_alive))
     .client_request_timeout(Duration::from_secs(server_request_timeout))
-    .bind(format!("{server_host}:{server_port}"))?;
+    .shutdown_timeout(server_shutdown_timeout)
+    .disable_signals()
+    .bind(format!("{server_host}:{server_port}"))?
+    .run();
+
+    let server_handle = server.handle();
+    let (signalled_tx, mut signalled_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        shutdown::signal().await;
+        let _ = signalled_tx.send(Instant::now());
+        server_handle.stop(true).await;
+    });
 
     tracing::info!(
         "🚀 Server running at http://{}:{}",
@@ -227,5 +423,21 @@ async fn main() -> std::io::Result<()> {
         tracing::info!("📈 Prometheus metrics enabled at /metrics");
     }
 
-    server.run().await
+    let result = server.await;
+
+    let deadline = signalled_rx.try_recv().unwrap_or_else(|_| Instant::now())
+        + Duration::from_secs(server_shutdown_timeout);
+    background_tasks.stop();
+    match timeout_at(deadline, nats_queue.drain()).await {
+        Ok(Ok(())) => (),
+        Ok(Err(e)) => tracing::error!("Failed to drain NATS subscriptions: {e}"),
+        Err(_) => tracing::warn!("NATS subscriptions did not drain in time"),
+    }
+    background_tasks.shutdown(deadline).await;
+    if timeout_at(deadline, storage.close()).await.is_err() {
+        tracing::warn!("Database pool did not close in time");
+    }
+    tracing::info!("Shutdown complete");
+
+    result
 }
@@ -0,0 +1,322 @@
+use crate::{
+    database::{
+        Filter, FilterValue, PostgresStorageGateway, StoreInsertBulk, StorePaginateBulkEntities,
+    },
+    impl_read_bulk_multiple, impl_store_bulk,
+    object_storage::ObjectStorageGateway,
+};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use reqwest::{Client, Url, header::CONTENT_TYPE};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared_states::{
+    DEFAULT_USER_AGENT, RssItem, check_public_url, public_client_builder, read_body_capped,
+};
+use sqlx::{Arguments, Row, prelude::FromRow};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+const MEDIA_KIND_IMAGE: &str = "image";
+const MEDIA_KIND_ENCLOSURE: &str = "enclosure";
+const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+/// Maximal number of media files archived for a single item.
+const MAX_ITEM_MEDIA: i64 = 16;
+/// Maximal number of items whose media are archived at the same time.
+const MAX_CONCURRENT_ARCHIVES: usize = 8;
+const MAX_REDIRECTS: usize = 5;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// RssItemMedia is a media file of an RSS item archived in the object storage.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct RssItemMedia {
+    pub object_key: String,
+    pub item_hash: String,
+    /// `image` for the lead image of the article, `enclosure` for the file attached to the item.
+    pub kind: String,
+    pub source_url: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub archived_timestamp: i64,
+}
+
+impl_store_bulk!(
+    RssItemMedia,
+    String,
+    "rss_item_media",
+    [
+        object_key,
+        item_hash,
+        kind,
+        source_url,
+        content_type,
+        size_bytes,
+        archived_timestamp
+    ],
+    "object_key",
+);
+
+impl_read_bulk_multiple!(
+    RssItemMedia,
+    "rss_item_media",
+    [
+        object_key,
+        item_hash,
+        kind,
+        source_url,
+        content_type,
+        size_bytes,
+        archived_timestamp
+    ],
+    "archived_timestamp",
+);
+
+/// Reads the archived media of an item.
+///
+/// # Arguments
+/// * `storage` - The storage gateway.
+/// * `item_hash` - The hash of the item.
+///
+/// # Returns
+/// The media of the item, newest first, or an error if the storage fails.
+pub async fn read_item_media(
+    storage: &PostgresStorageGateway,
+    item_hash: &str,
+) -> Result<Vec<RssItemMedia>> {
+    let filters = [Filter::Eq(
+        "item_hash",
+        FilterValue::Text(item_hash.to_string()),
+    )];
+    storage
+        .filter_paginate(&filters, None, MAX_ITEM_MEDIA, 0)
+        .await
+}
+
+/// Reads the archived media of items.
+///
+/// # Arguments
+/// * `storage` - The storage gateway.
+/// * `item_hashes` - The hashes of the items.
+///
+/// # Returns
+/// The media of the items, newest first, or an error if the storage fails.
+pub async fn read_items_media(
+    storage: &PostgresStorageGateway,
+    item_hashes: &[String],
+) -> Result<Vec<RssItemMedia>> {
+    if item_hashes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let filters = [Filter::In(
+        "item_hash",
+        FilterValue::TextList(item_hashes.to_vec()),
+    )];
+    storage
+        .filter_paginate(&filters, None, MAX_ITEM_MEDIA * item_hashes.len() as i64, 0)
+        .await
+}
+
+/// MediaArchiver copies lead images and enclosures of RSS items to the object storage, so clients
+/// are not hotlinking third-party servers that may remove them.
+///
+/// Media URLs come from feeds, so they are requested only if they and every redirect resolve to
+/// public addresses, see [`public_client_builder`].
+pub struct MediaArchiver {
+    storage: PostgresStorageGateway,
+    objects: ObjectStorageGateway,
+    client: Client,
+    max_bytes: usize,
+    slots: Arc<Semaphore>,
+}
+
+impl MediaArchiver {
+    /// Creates a new media archiver.
+    ///
+    /// # Arguments
+    /// * `storage` - The storage gateway the archived media are recorded in.
+    /// * `objects` - The object storage gateway the media files are stored in.
+    /// * `max_bytes` - Largest archived file, larger files are skipped.
+    ///
+    /// # Returns
+    /// A new media archiver, or an error if the HTTP client cannot be built.
+    pub fn new(
+        storage: PostgresStorageGateway,
+        objects: ObjectStorageGateway,
+        max_bytes: usize,
+    ) -> Result<Self> {
+        let client = public_client_builder(MAX_REDIRECTS)
+            .timeout(DOWNLOAD_TIMEOUT)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .user_agent(DEFAULT_USER_AGENT)
+            .build()?;
+        Ok(Self {
+            storage,
+            objects,
+            client,
+            max_bytes,
+            slots: Arc::new(Semaphore::new(MAX_CONCURRENT_ARCHIVES)),
+        })
+    }
+
+    /// Waits for a free archiving slot, at most [`MAX_CONCURRENT_ARCHIVES`] items are archived at
+    /// the same time.
+    ///
+    /// # Returns
+    /// The slot, released once dropped.
+    pub async fn slot(&self) -> OwnedSemaphorePermit {
+        self.slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Archiving slots are never closed")
+    }
+
+    /// Downloads the media of the item and stores them in the object storage.
+    ///
+    /// Media that cannot be downloaded or are too large are logged and skipped.
+    ///
+    /// # Arguments
+    /// * `item` - The RSS item.
+    ///
+    /// # Returns
+    /// The archived media, or an error if recording them fails.
+    pub async fn archive(&self, item: &RssItem) -> Result<Vec<RssItemMedia>> {
+        let mut archived = Vec::new();
+        for (kind, source_url) in media_sources(item) {
+            match self.archive_one(&item.hash, kind, source_url).await {
+                Ok(media) => archived.push(media),
+                Err(e) => tracing::warn!(
+                    "Failed to archive {kind} ( {source_url} ) of RSS item {}: {e}",
+                    item.hash
+                ),
+            }
+        }
+
+        if !archived.is_empty() {
+            self.storage.insert_bulk(&archived).await?;
+        }
+
+        Ok(archived)
+    }
+
+    async fn archive_one(
+        &self,
+        item_hash: &str,
+        kind: &str,
+        source_url: &str,
+    ) -> Result<RssItemMedia> {
+        let url = Url::parse(source_url)?;
+        check_public_url(&url)?;
+
+        let response = self.client.get(url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Got HTTP status {}", response.status()));
+        }
+        let content_type = content_type(
+            response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok()),
+        );
+        if kind == MEDIA_KIND_IMAGE && !content_type.starts_with("image/") {
+            return Err(anyhow!("Expected an image, got {content_type}"));
+        }
+        let body = read_body_capped(response, self.max_bytes).await?;
+
+        let media = RssItemMedia {
+            object_key: object_key(item_hash, source_url),
+            item_hash: item_hash.to_string(),
+            kind: kind.to_string(),
+            source_url: source_url.to_string(),
+            content_type,
+            size_bytes: body.len() as i64,
+            archived_timestamp: Utc::now().timestamp_millis(),
+        };
+        self.objects
+            .put(&media.object_key, body, &media.content_type)
+            .await?;
+
+        Ok(media)
+    }
+}
+
+/// Media of the item worth archiving as pairs of kind and URL, without duplicates. Enclosures the
+/// rss-worker archived already are skipped.
+fn media_sources(item: &RssItem) -> Vec<(&'static str, &str)> {
+    let mut sources: Vec<(&'static str, &str)> = Vec::new();
+    let enclosure_url = item
+        .enclosure_url
+        .as_deref()
+        .filter(|_| item.enclosure_object_key.is_none());
+    let candidates = [
+        (MEDIA_KIND_IMAGE, item.preview_image.as_deref()),
+        (MEDIA_KIND_ENCLOSURE, enclosure_url),
+    ];
+    for (kind, url) in candidates {
+        let Some(url) = url.map(str::trim).filter(|url| !url.is_empty()) else {
+            continue;
+        };
+        if sources.iter().all(|(_, source)| *source != url) {
+            sources.push((kind, url));
+        }
+    }
+
+    sources
+}
+
+/// Key of the media file, stable for the item and source URL so re-archiving overwrites it.
+fn object_key(item_hash: &str, source_url: &str) -> String {
+    let digest = hex::encode(Sha256::digest(source_url.as_bytes()));
+    format!("media/{item_hash}/{}", &digest[..32])
+}
+
+/// MIME type of the media without parameters, e.g. `image/png; charset=binary` becomes `image/png`.
+fn content_type(header: Option<&str>) -> String {
+    header
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_sources() {
+        let mut item = RssItem {
+            preview_image: Some("https://news.example.com/lead.jpg".to_string()),
+            enclosure_url: Some(" https://news.example.com/lead.jpg ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            media_sources(&item),
+            vec![(MEDIA_KIND_IMAGE, "https://news.example.com/lead.jpg")]
+        );
+
+        item.enclosure_url = Some("https://cdn.example.com/episode.mp3".to_string());
+        item.preview_image = Some(" ".to_string());
+        assert_eq!(
+            media_sources(&item),
+            vec![(MEDIA_KIND_ENCLOSURE, "https://cdn.example.com/episode.mp3")]
+        );
+
+        item.enclosure_object_key = Some("enclosures/abc/0123".to_string());
+        assert!(media_sources(&item).is_empty());
+    }
+
+    #[test]
+    fn test_object_key_and_content_type() {
+        let key = object_key("abc", "https://news.example.com/lead.jpg");
+
+        assert!(key.starts_with("media/abc/"));
+        assert_eq!(key.len(), "media/abc/".len() + 32);
+        assert_eq!(key, object_key("abc", "https://news.example.com/lead.jpg"));
+        assert_ne!(key, object_key("abc", "https://news.example.com/other.jpg"));
+        assert_eq!(content_type(Some("Image/PNG; charset=binary")), "image/png");
+        assert_eq!(content_type(None), DEFAULT_CONTENT_TYPE);
+    }
+}
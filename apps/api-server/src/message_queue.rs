@@ -1,12 +1,25 @@
 use crate::{
+    database::{Filter, FilterValue, StorePaginateBulkEntities},
     database::{PostgresStorageGateway, StoreInsertBulk, StoreReadBulkEntities},
-    impl_read_bulk_by_ids, impl_store_bulk,
+    impl_count_aggregate, impl_delete_bulk_by_ids, impl_read_bulk_by_ids, impl_read_bulk_multiple,
+    impl_soft_delete, impl_store_bulk, impl_stream,
+    media::MediaArchiver,
+    models::{RssItemEmbedding, RssItemSentiment},
+    response_cache::ResponseCache,
+    telemetry::Metrics,
 };
 use anyhow::{Result, anyhow};
+use async_nats::Message;
 use futures::StreamExt;
-use nats_middleware::NatsQueue;
-use shared_states::{RSS_QUEUE_NAME, RssItem};
+use nats_middleware::{NatsQueue, message_request_id};
+use shared_states::{
+    EMBEDDING_DIMENSION, EMBEDDING_QUEUE_NAME, EmbeddingResult, RSS_CHANNEL_QUEUE_NAME,
+    RSS_FEEDS_REQUEST_SUBJECT, RSS_QUEUE_NAME, RssChannelInfo, RssFeedSource, RssItem,
+    SENTIMENT_QUEUE_NAME, SentimentResult, decode_event,
+};
 use sqlx::{Arguments, Row, postgres::PgArguments};
+use std::{sync::Arc, time::Instant};
+use tracing::{Instrument, Span};
 
 impl_store_bulk!(
     RssItem,
@@ -18,11 +31,23 @@ impl_store_bulk!(
         link,
         description,
         published_timestamp,
+        published_timestamp_estimated,
         fetched_timestamp,
         comments_url,
         category,
         author,
-        article
+        article,
+        article_markdown,
+        language,
+        preview_title,
+        preview_description,
+        preview_image,
+        preview_published_timestamp,
+        enclosure_url,
+        enclosure_object_key,
+        simhash,
+        word_count,
+        reading_time_minutes
     ],
     "hash",
 );
@@ -37,23 +62,184 @@ impl_read_bulk_by_ids!(
         link,
         description,
         published_timestamp,
+        published_timestamp_estimated,
         fetched_timestamp,
         comments_url,
         category,
         author,
-        article
+        article,
+        article_markdown,
+        language,
+        preview_title,
+        preview_description,
+        preview_image,
+        preview_published_timestamp,
+        enclosure_url,
+        enclosure_object_key,
+        simhash,
+        word_count,
+        reading_time_minutes
     ],
     "hash",
+    soft_delete = "deleted_at",
 );
 
+impl_read_bulk_multiple!(
+    RssItem,
+    "rss_items",
+    [
+        hash,
+        title,
+        link,
+        description,
+        published_timestamp,
+        published_timestamp_estimated,
+        fetched_timestamp,
+        comments_url,
+        category,
+        author,
+        article,
+        article_markdown,
+        language,
+        preview_title,
+        preview_description,
+        preview_image,
+        preview_published_timestamp,
+        enclosure_url,
+        enclosure_object_key,
+        simhash,
+        word_count,
+        reading_time_minutes
+    ],
+    "published_timestamp",
+    soft_delete = "deleted_at",
+);
+
+impl_stream!(
+    RssItem,
+    "rss_items",
+    [
+        hash,
+        title,
+        link,
+        description,
+        published_timestamp,
+        published_timestamp_estimated,
+        fetched_timestamp,
+        comments_url,
+        category,
+        author,
+        article,
+        article_markdown,
+        language,
+        preview_title,
+        preview_description,
+        preview_image,
+        preview_published_timestamp,
+        enclosure_url,
+        enclosure_object_key,
+        simhash,
+        word_count,
+        reading_time_minutes
+    ],
+    "published_timestamp",
+    soft_delete = "deleted_at",
+);
+
+impl_count_aggregate!(RssItem, "rss_items", soft_delete = "deleted_at",);
+
+impl_soft_delete!(RssItem, "rss_items", "hash", "deleted_at",);
+
+impl_delete_bulk_by_ids!(RssItem, String, "rss_items", "hash",);
+
+impl_store_bulk!(
+    RssChannelInfo,
+    String,
+    "rss_channels",
+    [
+        hash,
+        url,
+        title,
+        link,
+        description,
+        language,
+        ttl,
+        last_build_timestamp,
+        self_url,
+        fetched_timestamp
+    ],
+    "hash",
+);
+
+impl_store_bulk!(
+    RssFeedSource,
+    String,
+    "rss_feed_sources",
+    [hash, url, interval_seconds, enabled, created_at, updated_at],
+    "hash",
+);
+
+impl_read_bulk_by_ids!(
+    RssFeedSource,
+    String,
+    "rss_feed_sources",
+    [hash, url, interval_seconds, enabled, created_at, updated_at],
+    "hash",
+);
+
+impl_read_bulk_multiple!(
+    RssFeedSource,
+    "rss_feed_sources",
+    [hash, url, interval_seconds, enabled, created_at, updated_at],
+    "created_at",
+);
+
+impl_count_aggregate!(RssFeedSource, "rss_feed_sources",);
+
+impl_delete_bulk_by_ids!(RssFeedSource, String, "rss_feed_sources", "hash",);
+
+/// Maximal number of feeds sent to the rss-worker.
+const MAX_WORKER_FEEDS: i64 = 10_000;
+
+/// Reason a consumed message failed to process, the `failure_reason` of the queue metrics.
+type Failure = &'static str;
+
+const DECODE_FAILURE: Failure = "decode";
+const STORAGE_FAILURE: Failure = "storage";
+const REPLY_FAILURE: Failure = "reply";
+
+/// RssFeedsProcessor consumes the events of the rss-worker, the sentiment analyzer and the
+/// embedding pipeline, recording per subject processed and failed messages, processing time and
+/// lag in the metrics.
 pub struct RssFeedsProcessor {
     storage: PostgresStorageGateway,
     queue: NatsQueue,
+    metrics: Arc<Metrics>,
+    media_archiver: Option<Arc<MediaArchiver>>,
+    response_cache: Option<Arc<ResponseCache>>,
 }
 
 impl RssFeedsProcessor {
-    pub fn new(storage: PostgresStorageGateway, queue: NatsQueue) -> Self {
-        Self { storage, queue }
+    pub fn new(storage: PostgresStorageGateway, queue: NatsQueue, metrics: Arc<Metrics>) -> Self {
+        Self {
+            storage,
+            queue,
+            metrics,
+            media_archiver: None,
+            response_cache: None,
+        }
+    }
+
+    /// Archives media of newly inserted items in the background.
+    pub fn with_media_archiver(mut self, archiver: MediaArchiver) -> Self {
+        self.media_archiver = Some(Arc::new(archiver));
+        self
+    }
+
+    /// Invalidates cached responses once new items or embeddings are inserted.
+    pub fn with_response_cache(mut self, cache: Arc<ResponseCache>) -> Self {
+        self.response_cache = Some(cache);
+        self
     }
 
     /// Run the processor reading messages from the queue and saving them to the database.
@@ -61,34 +247,250 @@ impl RssFeedsProcessor {
         let mut channel = self.queue.subscribe(RSS_QUEUE_NAME).await?;
 
         while let Some(message) = channel.next().await {
-            let rss_item: RssItem = serde_json::from_slice(&message.payload)?;
-            let hash = rss_item.hash.clone();
-            match self.storage.read_bulk_by_ids(&[hash]).await {
-                Ok(item) => {
-                    if !item.is_empty() {
-                        let Some(item) = item.first() else {
-                            continue;
-                        };
-                        tracing::info!("RSS item already exists: {}", item.hash);
-                        continue;
+            let started = Instant::now();
+            let result = self
+                .process_item(&message)
+                .instrument(message_span(RSS_QUEUE_NAME, &message))
+                .await;
+            self.record(RSS_QUEUE_NAME, started, result);
+        }
+
+        Err(anyhow!(
+            "Message queue subscriber is broken for subject ( {RSS_QUEUE_NAME} )"
+        ))
+    }
+
+    async fn process_item(&self, message: &Message) -> Result<(), Failure> {
+        let rss_item: RssItem = decode_event(&message.payload).map_err(|e| {
+            tracing::error!("Failed to decode RSS item: {}", e);
+            DECODE_FAILURE
+        })?;
+        self.metrics
+            .record_queue_lag(RSS_QUEUE_NAME, rss_item.fetched_timestamp);
+        let hash = rss_item.hash.clone();
+        let existing: Vec<RssItem> = self.storage.read_bulk_by_ids(&[hash]).await.map_err(|e| {
+            tracing::error!("Failed to read RSS item: {}", e);
+            STORAGE_FAILURE
+        })?;
+        if let Some(item) = existing.first() {
+            tracing::info!("RSS item already exists: {}", item.hash);
+            return Ok(());
+        }
+        match self
+            .storage
+            .insert_bulk(std::slice::from_ref(&rss_item))
+            .await
+        {
+            Ok(hash) => tracing::info!("Successfully inserted RSS item: {hash:?}"),
+            Err(e) => {
+                tracing::error!("Failed to insert RSS item: {}", e);
+                return Err(STORAGE_FAILURE);
+            }
+        };
+        if let Some(cache) = &self.response_cache
+            && let Err(e) = cache.invalidate().await
+        {
+            tracing::warn!("Failed to invalidate cached responses: {e}");
+        }
+        if let Some(archiver) = self.media_archiver.clone() {
+            // Consuming waits while all archiving slots are taken.
+            let slot = archiver.slot().await;
+            tokio::spawn(
+                async move {
+                    let _slot = slot;
+                    if let Err(e) = archiver.archive(&rss_item).await {
+                        tracing::error!(
+                            "Failed to archive media of RSS item {}: {e}",
+                            rss_item.hash
+                        );
                     }
                 }
-                Err(e) => {
-                    tracing::error!("Failed to read RSS item: {}", e);
-                    continue;
-                }
+                .in_current_span(),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Run the processor reading feed channel metadata from the queue and upserting it to the database.
+    pub async fn run_channels(&self) -> Result<()> {
+        let mut channel = self.queue.subscribe(RSS_CHANNEL_QUEUE_NAME).await?;
+
+        while let Some(message) = channel.next().await {
+            let started = Instant::now();
+            let result = self
+                .process_channel(&message)
+                .instrument(message_span(RSS_CHANNEL_QUEUE_NAME, &message))
+                .await;
+            self.record(RSS_CHANNEL_QUEUE_NAME, started, result);
+        }
+
+        Err(anyhow!(
+            "Message queue subscriber is broken for subject ( {RSS_CHANNEL_QUEUE_NAME} )"
+        ))
+    }
+
+    async fn process_channel(&self, message: &Message) -> Result<(), Failure> {
+        let channel_info: RssChannelInfo = decode_event(&message.payload).map_err(|e| {
+            tracing::error!("Failed to decode RSS channel info: {}", e);
+            DECODE_FAILURE
+        })?;
+        self.metrics
+            .record_queue_lag(RSS_CHANNEL_QUEUE_NAME, channel_info.fetched_timestamp);
+        match self.storage.insert_bulk(&[channel_info]).await {
+            Ok(hash) => tracing::info!("Successfully stored RSS channel info: {hash:?}"),
+            Err(e) => {
+                tracing::error!("Failed to store RSS channel info: {}", e);
+                return Err(STORAGE_FAILURE);
             }
-            match self.storage.insert_bulk(&[rss_item]).await {
-                Ok(hash) => tracing::info!("Successfully inserted RSS item: {hash:?}"),
-                Err(e) => {
-                    tracing::error!("Failed to insert RSS item: {}", e);
-                    continue;
-                }
-            };
+        };
+
+        Ok(())
+    }
+
+    /// Run the processor reading sentiment results from the queue and upserting them to the database.
+    pub async fn run_sentiments(&self) -> Result<()> {
+        let mut channel = self.queue.subscribe(SENTIMENT_QUEUE_NAME).await?;
+
+        while let Some(message) = channel.next().await {
+            let started = Instant::now();
+            let result = self
+                .process_sentiment(&message)
+                .instrument(message_span(SENTIMENT_QUEUE_NAME, &message))
+                .await;
+            self.record(SENTIMENT_QUEUE_NAME, started, result);
         }
 
         Err(anyhow!(
-            "Message queue subscriber is broken for subject ( {RSS_QUEUE_NAME} )"
+            "Message queue subscriber is broken for subject ( {SENTIMENT_QUEUE_NAME} )"
         ))
     }
+
+    async fn process_sentiment(&self, message: &Message) -> Result<(), Failure> {
+        let result: SentimentResult = decode_event(&message.payload).map_err(|e| {
+            tracing::error!("Failed to decode sentiment result: {}", e);
+            DECODE_FAILURE
+        })?;
+        self.metrics
+            .record_queue_lag(SENTIMENT_QUEUE_NAME, result.analyzed_timestamp);
+        match self
+            .storage
+            .insert_bulk(&[RssItemSentiment::from(result)])
+            .await
+        {
+            Ok(hash) => tracing::info!("Successfully stored sentiment of RSS item: {hash:?}"),
+            Err(e) => {
+                tracing::error!("Failed to store sentiment result: {}", e);
+                return Err(STORAGE_FAILURE);
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Run the processor reading item embeddings from the queue and upserting them to the database.
+    pub async fn run_embeddings(&self) -> Result<()> {
+        let mut channel = self.queue.subscribe(EMBEDDING_QUEUE_NAME).await?;
+
+        while let Some(message) = channel.next().await {
+            let started = Instant::now();
+            let result = self
+                .process_embedding(&message)
+                .instrument(message_span(EMBEDDING_QUEUE_NAME, &message))
+                .await;
+            self.record(EMBEDDING_QUEUE_NAME, started, result);
+        }
+
+        Err(anyhow!(
+            "Message queue subscriber is broken for subject ( {EMBEDDING_QUEUE_NAME} )"
+        ))
+    }
+
+    async fn process_embedding(&self, message: &Message) -> Result<(), Failure> {
+        let result: EmbeddingResult = decode_event(&message.payload).map_err(|e| {
+            tracing::error!("Failed to decode embedding result: {}", e);
+            DECODE_FAILURE
+        })?;
+        if result.embedding.vector.len() != EMBEDDING_DIMENSION {
+            tracing::error!(
+                "Embedding of RSS item {} has {} dimensions, expected {EMBEDDING_DIMENSION}",
+                result.item_hash,
+                result.embedding.vector.len()
+            );
+            return Err(DECODE_FAILURE);
+        }
+        self.metrics
+            .record_queue_lag(EMBEDDING_QUEUE_NAME, result.embedded_timestamp);
+        match self
+            .storage
+            .insert_bulk(&[RssItemEmbedding::from(result)])
+            .await
+        {
+            Ok(hash) => tracing::info!("Successfully stored embedding of RSS item: {hash:?}"),
+            Err(e) => {
+                tracing::error!("Failed to store embedding result: {}", e);
+                return Err(STORAGE_FAILURE);
+            }
+        };
+        if let Some(cache) = &self.response_cache
+            && let Err(e) = cache.invalidate().await
+        {
+            tracing::warn!("Failed to invalidate cached responses: {e}");
+        }
+
+        Ok(())
+    }
+
+    /// Run the responder replying to rss-worker requests with the enabled feeds.
+    pub async fn run_feed_requests(&self) -> Result<()> {
+        let mut requests = self.queue.subscribe(RSS_FEEDS_REQUEST_SUBJECT).await?;
+
+        while let Some(message) = requests.next().await {
+            let started = Instant::now();
+            let result = self
+                .process_feed_request(&message)
+                .instrument(message_span(RSS_FEEDS_REQUEST_SUBJECT, &message))
+                .await;
+            self.record(RSS_FEEDS_REQUEST_SUBJECT, started, result);
+        }
+
+        Err(anyhow!(
+            "Message queue subscriber is broken for subject ( {RSS_FEEDS_REQUEST_SUBJECT} )"
+        ))
+    }
+
+    async fn process_feed_request(&self, message: &Message) -> Result<(), Failure> {
+        let filters = [Filter::Eq("enabled", FilterValue::Bool(true))];
+        let feeds: Vec<RssFeedSource> = self
+            .storage
+            .filter_paginate(&filters, None, MAX_WORKER_FEEDS, 0)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to read RSS feed sources: {}", e);
+                STORAGE_FAILURE
+            })?;
+        match self.queue.reply(message, &feeds).await {
+            Ok(()) => tracing::info!("Sent {} RSS feed sources to the worker", feeds.len()),
+            Err(e) => {
+                tracing::error!("Failed to reply with RSS feed sources: {}", e);
+                return Err(REPLY_FAILURE);
+            }
+        };
+
+        Ok(())
+    }
+
+    fn record(&self, subject: &str, started: Instant, result: Result<(), Failure>) {
+        self.metrics
+            .record_queue_message(subject, result.err(), started.elapsed().as_secs_f64());
+    }
+}
+
+/// Span of processing the message, carrying the request ID the message was published for.
+fn message_span(subject: &str, message: &Message) -> Span {
+    tracing::info_span!(
+        "message",
+        subject,
+        request_id = message_request_id(message).unwrap_or_default()
+    )
 }
@@ -1,30 +1,66 @@
+use crate::errors::ApiError;
 use crate::models::Claims;
 use crate::telemetry::Metrics;
 use crate::{
     auth::Authenticator,
-    constants::{API_VERSION, BEARER},
+    config::{AdminConfig, Config, RouteLimitsConfig},
+    constants::{
+        API_KEY_HEADER, API_VERSION, BEARER, ORGANIZATION_HEADER, RATE_LIMIT_LIMIT_HEADER,
+        RATE_LIMIT_REMAINING_HEADER, RATE_LIMIT_RESET_HEADER, REQUEST_ID_HEADER,
+    },
+    organizations::{OrganizationError, Organizations, Scope, api_key_allows},
+    sessions::SessionRegistry,
+    tiers::{Feature, Quota, Quotas},
 };
 use actix_web::{
-    Error, HttpMessage,
-    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
-    error::ErrorUnauthorized,
-    http::header::AUTHORIZATION,
+    Error, FromRequest, HttpMessage, HttpRequest, ResponseError,
+    body::EitherBody,
+    dev::{JsonBody, Payload, Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    http::{
+        StatusCode,
+        header::{
+            AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, HeaderMap, HeaderName, HeaderValue,
+            RETRY_AFTER,
+        },
+    },
+    web,
 };
 use futures::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
 use std::{
+    fmt,
     future::{Ready, ready},
+    ops::Deref,
     sync::Arc,
-    time::Instant,
+    time::{Duration, Instant},
 };
+use tracing::Instrument;
+use validator::Validate;
 
+const REQUEST_ID_MAX_LEN: usize = 128;
+
+/// JwtMiddleware lets through requests with a valid token whose session was not revoked.
+///
+/// Requests without a token may authenticate with an organization API key in the `X-Api-Key`
+/// header, on the paths API keys are accepted on.
 #[derive(Clone)]
 pub struct JwtMiddleware {
     authenticator: Arc<Authenticator>,
+    sessions: Arc<SessionRegistry>,
+    organizations: Arc<Organizations>,
 }
 
 impl JwtMiddleware {
-    pub fn new(authenticator: Arc<Authenticator>) -> Self {
-        Self { authenticator }
+    pub fn new(
+        authenticator: Arc<Authenticator>,
+        sessions: Arc<SessionRegistry>,
+        organizations: Arc<Organizations>,
+    ) -> Self {
+        Self {
+            authenticator,
+            sessions,
+            organizations,
+        }
     }
 }
 
@@ -34,7 +70,7 @@ where
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type InitError = ();
     type Transform = JwtMiddlewareService<S>;
@@ -45,6 +81,8 @@ where
         ready(Ok(JwtMiddlewareService {
             service: Arc::new(service),
             authenticator: self.authenticator.clone(),
+            sessions: self.sessions.clone(),
+            organizations: self.organizations.clone(),
         }))
     }
 }
@@ -52,6 +90,8 @@ where
 pub struct JwtMiddlewareService<S> {
     service: Arc<S>,
     authenticator: Arc<Authenticator>,
+    sessions: Arc<SessionRegistry>,
+    organizations: Arc<Organizations>,
 }
 
 impl<S, B> Service<ServiceRequest> for JwtMiddlewareService<S>
@@ -60,7 +100,7 @@ where
     S::Future: 'static,
     B: 'static,
 {
-    type Response = ServiceResponse<B>;
+    type Response = ServiceResponse<EitherBody<B>>;
     type Error = Error;
     type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
 
@@ -70,6 +110,8 @@ where
     fn call(&self, req: ServiceRequest) -> Self::Future {
         let service = self.service.clone();
         let authenticator = self.authenticator.clone();
+        let sessions = self.sessions.clone();
+        let organizations = self.organizations.clone();
 
         Box::pin(async move {
             let auth_header = req
@@ -78,35 +120,625 @@ where
                 .and_then(|h| h.to_str().ok());
 
             if let Some(auth_str) = auth_header
-                && auth_str.starts_with(BEARER)
+                && let Some(token) = auth_str.strip_prefix(BEARER)
             {
-                let Some(token) = auth_str.strip_prefix(BEARER) else {
-                    return Err(ErrorUnauthorized("Invalid token"));
+                let Ok(claims) = authenticator.validate_token(token) else {
+                    return Ok(unauthorized(req, "Invalid token"));
                 };
-
-                match authenticator.validate_token(token) {
-                    Ok(claims) => {
+                return match sessions.is_active(&claims).await {
+                    Ok(true) => {
                         req.extensions_mut().insert(claims);
                         let res = service.call(req).await?;
-                        return Ok(res);
+                        Ok(res.map_into_left_body())
+                    }
+                    Ok(false) => Ok(unauthorized(req, "Session revoked or expired")),
+                    Err(e) => {
+                        tracing::error!("Failed to check session: {e}");
+                        Ok(error_response(
+                            req,
+                            StatusCode::SERVICE_UNAVAILABLE,
+                            "session_check_failed",
+                            "Failed to check the session",
+                        ))
                     }
-                    Err(_) => {
-                        return Err(ErrorUnauthorized("Invalid token"));
+                };
+            }
+
+            if let Some(key) = req
+                .headers()
+                .get(API_KEY_HEADER)
+                .and_then(|h| h.to_str().ok())
+            {
+                return match organizations.authenticate(key).await {
+                    Ok(Some(api_key)) if api_key_allows(req.path()) => {
+                        req.extensions_mut().insert(api_key.claims());
+                        let res = service.call(req).await?;
+                        Ok(res.map_into_left_body())
                     }
+                    Ok(Some(_)) => Ok(error_response(
+                        req,
+                        StatusCode::FORBIDDEN,
+                        "forbidden",
+                        "API keys are not accepted on this path",
+                    )),
+                    Ok(None) => Ok(unauthorized(req, "Invalid API key")),
+                    Err(e) => {
+                        tracing::error!("Failed to check API key: {e}");
+                        Ok(error_response(
+                            req,
+                            StatusCode::SERVICE_UNAVAILABLE,
+                            "api_key_check_failed",
+                            "Failed to check the API key",
+                        ))
+                    }
+                };
+            }
+
+            Ok(unauthorized(req, "Missing or invalid authorization header"))
+        })
+    }
+}
+
+fn unauthorized<B>(req: ServiceRequest, message: &str) -> ServiceResponse<EitherBody<B>> {
+    error_response(req, StatusCode::UNAUTHORIZED, "unauthorized", message)
+}
+
+fn error_response<B>(
+    req: ServiceRequest,
+    status: StatusCode,
+    error: &str,
+    message: &str,
+) -> ServiceResponse<EitherBody<B>> {
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(RequestId::to_string)
+        .unwrap_or_default();
+    let response = ApiError::new(status, error, message, request_id).error_response();
+
+    req.into_response(response).map_into_right_body()
+}
+
+/// AdminMiddleware lets only admin wallets through, it has to be wrapped by [`JwtMiddleware`].
+#[derive(Clone)]
+pub struct AdminMiddleware {
+    admins: Arc<AdminConfig>,
+}
+
+impl AdminMiddleware {
+    pub fn new(admins: Arc<AdminConfig>) -> Self {
+        Self { admins }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AdminMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = AdminMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    #[inline(always)]
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AdminMiddlewareService {
+            service: Arc::new(service),
+            admins: self.admins.clone(),
+        }))
+    }
+}
+
+pub struct AdminMiddlewareService<S> {
+    service: Arc<S>,
+    admins: Arc<AdminConfig>,
+}
+
+impl<S, B> Service<ServiceRequest> for AdminMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    #[inline(always)]
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let is_admin = req
+            .extensions()
+            .get::<Claims>()
+            .is_some_and(|claims| self.admins.allows(claims));
+
+        Box::pin(async move {
+            if !is_admin {
+                return Ok(error_response(
+                    req,
+                    StatusCode::FORBIDDEN,
+                    "forbidden",
+                    "Admin privileges required",
+                ));
+            }
+
+            let res = service.call(req).await?;
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+/// QuotaMiddleware enforces the daily request quotas and feature gates of the tiers, it has to be
+/// wrapped by [`JwtMiddleware`].
+///
+/// Responses carry the `X-RateLimit-*` quota headers, requests over the quota are rejected with
+/// `429 Too Many Requests` and requests for features of a higher tier with `402 Payment Required`.
+/// Requests are let through when Redis or the storage fail.
+#[derive(Clone)]
+pub struct QuotaMiddleware {
+    quotas: Arc<Quotas>,
+}
+
+impl QuotaMiddleware {
+    pub fn new(quotas: Arc<Quotas>) -> Self {
+        Self { quotas }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for QuotaMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = QuotaMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    #[inline(always)]
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(QuotaMiddlewareService {
+            service: Arc::new(service),
+            quotas: self.quotas.clone(),
+        }))
+    }
+}
+
+pub struct QuotaMiddlewareService<S> {
+    service: Arc<S>,
+    quotas: Arc<Quotas>,
+}
+
+impl<S, B> Service<ServiceRequest> for QuotaMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    #[inline(always)]
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let quotas = self.quotas.clone();
+        let user_id = req
+            .extensions()
+            .get::<Claims>()
+            .map(|claims| claims.sub.clone());
+
+        Box::pin(async move {
+            let Some(user_id) = user_id.filter(|_| quotas.enabled()) else {
+                let res = service.call(req).await?;
+                return Ok(res.map_into_left_body());
+            };
+
+            let tier = match quotas.tier(&user_id).await {
+                Ok(tier) => tier,
+                Err(e) => {
+                    tracing::warn!("Failed to read tier, skipping quota: {e}");
+                    let res = service.call(req).await?;
+                    return Ok(res.map_into_left_body());
                 }
+            };
+            if let Some(feature) = Feature::of_path(req.path())
+                && !tier.allows(feature)
+            {
+                return Ok(error_response(
+                    req,
+                    StatusCode::PAYMENT_REQUIRED,
+                    "payment_required",
+                    &format!("The feature is not available in the {} tier", tier.as_str()),
+                ));
             }
 
-            Err(ErrorUnauthorized("Missing or invalid authorization header"))
+            let (quota, used) = match quotas.consume(&user_id, tier).await {
+                Ok(consumed) => consumed,
+                Err(e) => {
+                    tracing::warn!("Failed to count request, skipping quota: {e}");
+                    let res = service.call(req).await?;
+                    return Ok(res.map_into_left_body());
+                }
+            };
+            if quota.exceeded(used) {
+                let mut res = error_response(
+                    req,
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "quota_exceeded",
+                    &format!(
+                        "Daily quota of {} requests of the {} tier exceeded",
+                        quota.limit,
+                        tier.as_str()
+                    ),
+                );
+                insert_quota_headers(res.headers_mut(), &quota);
+                let retry_after = (quota.reset_at - chrono::Utc::now().timestamp()).max(1);
+                res.headers_mut()
+                    .insert(RETRY_AFTER, HeaderValue::from(retry_after));
+                return Ok(res);
+            }
+
+            let mut res = service.call(req).await?;
+            insert_quota_headers(res.headers_mut(), &quota);
+            Ok(res.map_into_left_body())
+        })
+    }
+}
+
+fn quota_headers(quota: &Quota) -> [(HeaderName, HeaderValue); 3] {
+    [
+        (
+            HeaderName::from_static(RATE_LIMIT_LIMIT_HEADER),
+            HeaderValue::from(quota.limit),
+        ),
+        (
+            HeaderName::from_static(RATE_LIMIT_REMAINING_HEADER),
+            HeaderValue::from(quota.remaining),
+        ),
+        (
+            HeaderName::from_static(RATE_LIMIT_RESET_HEADER),
+            HeaderValue::from(quota.reset_at),
+        ),
+    ]
+}
+
+fn insert_quota_headers(headers: &mut HeaderMap, quota: &Quota) {
+    for (name, value) in quota_headers(quota) {
+        headers.insert(name, value);
+    }
+}
+
+/// Largest accepted body of a request in bytes, read by [`ValidatedJson`].
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimit(pub usize);
+
+/// RouteLimitsMiddleware enforces the timeout and the body size limit of the route group of the
+/// request, see [`RouteLimitsConfig::of_path`].
+///
+/// Bodies announced larger than the limit are rejected with `413 Payload Too Large` before they
+/// are read, streamed bodies stop being read at the limit. Requests not answered in time are
+/// dropped and answered with `504 Gateway Timeout`.
+#[derive(Clone)]
+pub struct RouteLimitsMiddleware {
+    limits: Arc<RouteLimitsConfig>,
+    metrics: Arc<Metrics>,
+}
+
+impl RouteLimitsMiddleware {
+    pub fn new(limits: Arc<RouteLimitsConfig>, metrics: Arc<Metrics>) -> Self {
+        Self { limits, metrics }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RouteLimitsMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RouteLimitsMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    #[inline(always)]
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RouteLimitsMiddlewareService {
+            service: Arc::new(service),
+            limits: self.limits.clone(),
+            metrics: self.metrics.clone(),
+        }))
+    }
+}
+
+pub struct RouteLimitsMiddlewareService<S> {
+    service: Arc<S>,
+    limits: Arc<RouteLimitsConfig>,
+    metrics: Arc<Metrics>,
+}
+
+impl<S, B> Service<ServiceRequest> for RouteLimitsMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    #[inline(always)]
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let metrics = self.metrics.clone();
+        let (group, limit) = self.limits.of_path(req.path());
+
+        Box::pin(async move {
+            let length = req
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<usize>().ok());
+            if length.is_some_and(|length| length > limit.max_body_bytes) {
+                return Ok(error_response(
+                    req,
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    "payload_too_large",
+                    &format!(
+                        "The request body exceeds the limit of {} bytes",
+                        limit.max_body_bytes
+                    ),
+                ));
+            }
+            req.extensions_mut().insert(BodyLimit(limit.max_body_bytes));
+
+            let request_id = request_id_of(req.request());
+            let timeout = Duration::from_secs(limit.timeout_seconds);
+            match tokio::time::timeout(timeout, service.call(req)).await {
+                Ok(res) => Ok(res?.map_into_left_body()),
+                Err(_) => {
+                    metrics
+                        .api_errors_by_type
+                        .with_label_values(&["request_timeout", group])
+                        .inc();
+                    tracing::warn!("Request {request_id} timed out after {timeout:?}");
+                    Err(ApiError::new(
+                        StatusCode::GATEWAY_TIMEOUT,
+                        "request_timeout",
+                        format!(
+                            "The request was not answered within {} seconds",
+                            limit.timeout_seconds
+                        ),
+                        request_id,
+                    )
+                    .into())
+                }
+            }
+        })
+    }
+}
+
+impl FromRequest for Claims {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    /// Reads the claims validated by [`JwtMiddleware`], rejects the request if the middleware is not used.
+    #[inline(always)]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(extract_claims(req).ok_or_else(|| missing_claims(request_id_of(req)).into()))
+    }
+}
+
+fn missing_claims(request_id: String) -> ApiError {
+    ApiError::new(
+        StatusCode::UNAUTHORIZED,
+        "unauthorized",
+        "Missing authorization claims",
+        request_id,
+    )
+}
+
+impl FromRequest for Scope {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    /// Resolves the scope of the request with [`Organizations::scope`], the organization of the
+    /// `X-Organization-Id` header if sent, rejects the request if the user is not a member.
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let claims = extract_claims(req);
+        let organizations = req.app_data::<web::Data<Organizations>>().cloned();
+        let org_id = req
+            .headers()
+            .get(ORGANIZATION_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .map(|org_id| org_id.trim().to_string())
+            .filter(|org_id| !org_id.is_empty());
+        let request_id = request_id_of(req);
+
+        Box::pin(async move {
+            let (Some(claims), Some(organizations)) = (claims, organizations) else {
+                return Err(missing_claims(request_id).into());
+            };
+            organizations
+                .scope(&claims, org_id.as_deref())
+                .await
+                .map_err(|err| {
+                    match err.downcast_ref::<OrganizationError>() {
+                        Some(_) => ApiError::new(
+                            StatusCode::FORBIDDEN,
+                            "not_organization_member",
+                            err.to_string(),
+                            request_id,
+                        ),
+                        None => {
+                            tracing::error!("Failed to resolve organization scope: {err}");
+                            ApiError::new(
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                "organization_scope_failed",
+                                "Failed to check the organization membership.",
+                                request_id,
+                            )
+                        }
+                    }
+                    .into()
+                })
         })
     }
 }
 
 #[inline(always)]
-#[allow(dead_code)]
 pub fn extract_claims(req: &actix_web::HttpRequest) -> Option<Claims> {
     req.extensions().get::<Claims>().cloned()
 }
 
+/// Credentials of a register or login request read from the JSON body.
+///
+/// While `AUTH_QUERY_CREDENTIALS_ENABLED` is set, requests without a JSON body fall back to the
+/// deprecated query string, which leaks the signature into access logs.
+pub struct Credentials<T>(pub T);
+
+impl<T> Deref for Credentials<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + Validate + 'static> FromRequest for Credentials<T> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let query_enabled = req
+            .app_data::<web::Data<Config>>()
+            .is_some_and(|config| config.jwt.query_credentials_enabled);
+        let is_json = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/json"));
+        if query_enabled && !is_json && !req.query_string().is_empty() {
+            tracing::warn!(
+                "Deprecated credentials in the query string of {}",
+                req.path()
+            );
+            let credentials = query(req).map(Credentials).map_err(Error::from);
+            return Box::pin(ready(credentials));
+        }
+
+        let json = ValidatedJson::<T>::from_request(req, payload);
+        Box::pin(async move { Ok(Credentials(json.await?.into_inner())) })
+    }
+}
+
+/// Limit of the JSON bodies of requests outside of [`RouteLimitsMiddleware`].
+const DEFAULT_BODY_LIMIT: usize = 32 * 1024;
+
+/// JSON body of a request, validated before the handler is called.
+///
+/// Malformed bodies are rejected with `400 Bad Request`, bodies over the [`BodyLimit`] with
+/// `413 Payload Too Large` and bodies failing validation with `422 Unprocessable Entity` listing
+/// the invalid fields.
+pub struct ValidatedJson<T>(pub T);
+
+impl<T> ValidatedJson<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for ValidatedJson<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + Validate + 'static> FromRequest for ValidatedJson<T> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let request_id = request_id_of(req);
+        let limit = req
+            .extensions()
+            .get::<BodyLimit>()
+            .map_or(DEFAULT_BODY_LIMIT, |limit| limit.0);
+        let json = JsonBody::<T>::new(req, payload, None, true).limit(limit);
+        Box::pin(async move {
+            let body = json.await.map_err(|err| {
+                let error = match err.status_code() {
+                    StatusCode::PAYLOAD_TOO_LARGE => "payload_too_large",
+                    StatusCode::UNSUPPORTED_MEDIA_TYPE => "unsupported_media_type",
+                    _ => "invalid_body",
+                };
+                ApiError::new(err.status_code(), error, err.to_string(), &request_id)
+            })?;
+            Ok(ValidatedJson(validated(body, &request_id)?))
+        })
+    }
+}
+
+/// Query string of a request, validated before the handler is called.
+///
+/// Malformed query strings are rejected with `400 Bad Request`, values failing validation, e.g.
+/// pages out of bounds, with `422 Unprocessable Entity` listing the invalid fields.
+pub struct ValidatedQuery<T>(pub T);
+
+impl<T> Deref for ValidatedQuery<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: DeserializeOwned + Validate> FromRequest for ValidatedQuery<T> {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(query(req).map(ValidatedQuery).map_err(Error::from))
+    }
+}
+
+fn query<T: DeserializeOwned + Validate>(req: &HttpRequest) -> Result<T, ApiError> {
+    let request_id = request_id_of(req);
+    let query = web::Query::<T>::from_query(req.query_string())
+        .map_err(|err| ApiError::bad_request("invalid_query_string", err.to_string(), &request_id))?
+        .into_inner();
+    validated(query, &request_id)
+}
+
+fn validated<T: Validate>(value: T, request_id: &str) -> Result<T, ApiError> {
+    value
+        .validate()
+        .map_err(|errors| ApiError::invalid(&errors, request_id))?;
+    Ok(value)
+}
+
+fn request_id_of(req: &HttpRequest) -> String {
+    req.extensions()
+        .get::<RequestId>()
+        .map(RequestId::to_string)
+        .unwrap_or_default()
+}
+
 #[derive(Clone)]
 pub struct MetricsMiddleware {
     metrics: Arc<Metrics>,
@@ -189,3 +821,439 @@ where
         })
     }
 }
+
+/// RequestId identifies a request across the api-server logs, error responses and the clients.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(String);
+
+impl RequestId {
+    /// Creates a new random request ID.
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().to_string())
+    }
+
+    /// Accepts a request ID sent by the client or a proxy.
+    ///
+    /// # Returns
+    /// The request ID, None if it is empty, too long or contains characters other than
+    /// alphanumerics, `-`, `_`, `.` and `:`.
+    pub fn from_header(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let valid = !value.is_empty()
+            && value.len() <= REQUEST_ID_MAX_LEN
+            && value
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+
+        valid.then(|| Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromRequest for RequestId {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    /// Reads the request ID assigned by [`RequestIdMiddleware`], a new one if the middleware is not used.
+    #[inline(always)]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(Ok(req
+            .extensions()
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(RequestId::generate)))
+    }
+}
+
+/// RequestIdMiddleware propagates the `X-Request-Id` header of the request or generates a new one,
+/// records it on the request span and returns it in the `X-Request-Id` header of every response.
+#[derive(Clone, Default)]
+pub struct RequestIdMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestIdMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestIdMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    #[inline(always)]
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddlewareService {
+            service: Arc::new(service),
+        }))
+    }
+}
+
+pub struct RequestIdMiddlewareService<S> {
+    service: Arc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    #[inline(always)]
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .and_then(RequestId::from_header)
+            .unwrap_or_else(RequestId::generate);
+        req.extensions_mut().insert(request_id.clone());
+        let span = tracing::info_span!("request", request_id = %request_id);
+
+        Box::pin(
+            async move {
+                // Messages published while handling the request carry its ID.
+                let mut res =
+                    nats_middleware::with_request_id(request_id.to_string(), service.call(req))
+                        .await
+                        .inspect_err(|err| {
+                            tracing::error!("{err}");
+                        })?;
+
+                if let Ok(value) = HeaderValue::from_str(request_id.as_str()) {
+                    res.headers_mut()
+                        .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+                }
+
+                Ok(res)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{App, HttpResponse, error::ErrorUnauthorized, test as actix_test, web};
+
+    #[test]
+    fn test_request_id_from_header() {
+        assert_eq!(
+            RequestId::from_header(" 4f1c-aa:01 ").map(|id| id.to_string()),
+            Some("4f1c-aa:01".to_string())
+        );
+        assert_eq!(RequestId::from_header(""), None);
+        assert_eq!(RequestId::from_header("id with spaces"), None);
+        assert_eq!(RequestId::from_header(&"a".repeat(129)), None);
+        assert_eq!(RequestId::generate().as_str().len(), 36);
+    }
+
+    #[actix_web::test]
+    async fn test_request_id_middleware() {
+        let app = actix_test::init_service(
+            App::new()
+                .wrap(RequestIdMiddleware)
+                .route(
+                    "/ok",
+                    web::get().to(|id: RequestId| async move { HttpResponse::Ok().body(id.0) }),
+                )
+                .route(
+                    "/err",
+                    web::get().to(|| async {
+                        Err::<HttpResponse, Error>(ErrorUnauthorized("Invalid token"))
+                    }),
+                ),
+        )
+        .await;
+
+        let req = actix_test::TestRequest::get()
+            .uri("/ok")
+            .insert_header((REQUEST_ID_HEADER, "client-id-1"))
+            .to_request();
+        let res = actix_test::call_service(&app, req).await;
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "client-id-1");
+        assert_eq!(actix_test::read_body(res).await, "client-id-1");
+
+        let req = actix_test::TestRequest::get().uri("/err").to_request();
+        let res = actix_test::call_service(&app, req).await;
+        assert_eq!(res.status(), 401);
+        assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap().len(), 36);
+    }
+
+    #[actix_web::test]
+    async fn test_claims_extractor() {
+        let req = actix_test::TestRequest::default().to_http_request();
+        assert!(Claims::extract(&req).await.is_err());
+
+        req.extensions_mut().insert(Claims {
+            sub: "wallet".to_string(),
+            user_id: "wallet".to_string(),
+            name: "wallet-wallet".to_string(),
+            exp: 0,
+            iat: 0,
+            aud: "api".to_string(),
+            iss: "api".to_string(),
+            jti: "session".to_string(),
+            org_id: None,
+        });
+        assert_eq!(Claims::extract(&req).await.unwrap().sub, "wallet");
+    }
+
+    #[actix_web::test]
+    async fn test_credentials_extractor() {
+        #[derive(serde::Deserialize, Validate)]
+        struct Login {
+            #[validate(length(min = 3))]
+            signature: String,
+        }
+
+        let (req, mut payload) = actix_test::TestRequest::post()
+            .uri("/auth/login")
+            .set_json(serde_json::json!({ "signature": "sig" }))
+            .to_http_parts();
+        let credentials = Credentials::<Login>::from_request(&req, &mut payload)
+            .await
+            .unwrap();
+        assert_eq!(credentials.signature, "sig");
+
+        let (req, mut payload) = actix_test::TestRequest::post()
+            .uri("/auth/login?signature=sig")
+            .to_http_parts();
+        assert!(
+            Credentials::<Login>::from_request(&req, &mut payload)
+                .await
+                .is_err()
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_validated_extractors() {
+        use crate::models::{CreateOrganizationRequest, PageQuery};
+
+        let app = actix_test::init_service(
+            App::new()
+                .route(
+                    "/organizations",
+                    web::get().to(|query: ValidatedQuery<PageQuery>| async move {
+                        HttpResponse::Ok().body(query.limit().to_string())
+                    }),
+                )
+                .route(
+                    "/organizations",
+                    web::post().to(
+                        |body: ValidatedJson<CreateOrganizationRequest>| async move {
+                            HttpResponse::Created().body(body.into_inner().name)
+                        },
+                    ),
+                ),
+        )
+        .await;
+
+        for (uri, status) in [
+            ("/organizations?limit=50", 200),
+            ("/organizations?limit=500", 422),
+            ("/organizations?offset=-1", 422),
+            ("/organizations?limit=many", 400),
+        ] {
+            let req = actix_test::TestRequest::get().uri(uri).to_request();
+            let res = actix_test::call_service(&app, req).await;
+            assert_eq!(res.status().as_u16(), status, "{uri}");
+        }
+
+        let req = actix_test::TestRequest::post()
+            .uri("/organizations")
+            .set_json(serde_json::json!({ "name": "" }))
+            .to_request();
+        let res = actix_test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let problem: crate::models::ProblemDetails = actix_test::read_body_json(res).await;
+        assert_eq!(problem.fields["name"], ["length must be between 1 and 100"]);
+
+        let req = actix_test::TestRequest::post()
+            .uri("/organizations")
+            .insert_header((CONTENT_TYPE, "application/json"))
+            .set_payload("{")
+            .to_request();
+        let res = actix_test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_web::test]
+    async fn test_route_limits_middleware() {
+        use crate::config::RouteLimit;
+        use crate::models::CreateOrganizationRequest;
+
+        let limits = RouteLimitsConfig {
+            auth: RouteLimit {
+                timeout_seconds: 1,
+                max_body_bytes: 16,
+            },
+            admin: RouteLimit {
+                timeout_seconds: 5,
+                max_body_bytes: 1024,
+            },
+            default: RouteLimit {
+                timeout_seconds: 1,
+                max_body_bytes: 64,
+            },
+        };
+        assert_eq!(limits.of_path("/api/v1/auth/login"), ("auth", limits.auth));
+        assert_eq!(
+            limits.of_path("/api/v1/admin/feeds"),
+            ("admin", limits.admin)
+        );
+        assert_eq!(
+            limits.of_path("/api/v1/rss/export"),
+            ("admin", limits.admin)
+        );
+        assert_eq!(
+            limits.of_path("/api/v1/auth/me"),
+            ("default", limits.default)
+        );
+        assert_eq!(
+            limits.of_path("/administrators"),
+            ("default", limits.default)
+        );
+
+        let app = actix_test::init_service(
+            App::new()
+                .wrap(RouteLimitsMiddleware::new(
+                    Arc::new(limits),
+                    Arc::new(Metrics::new().unwrap()),
+                ))
+                .service(
+                    web::scope("/api/v1")
+                        .route(
+                            "/auth/login",
+                            web::post().to(
+                                |body: ValidatedJson<CreateOrganizationRequest>| async move {
+                                    HttpResponse::Ok().body(body.into_inner().name)
+                                },
+                            ),
+                        )
+                        .route(
+                            "/organizations",
+                            web::post().to(
+                                |body: ValidatedJson<CreateOrganizationRequest>| async move {
+                                    HttpResponse::Ok().body(body.into_inner().name)
+                                },
+                            ),
+                        )
+                        .route(
+                            "/slow",
+                            web::get().to(|| async {
+                                tokio::time::sleep(Duration::from_secs(3)).await;
+                                HttpResponse::Ok().finish()
+                            }),
+                        ),
+                ),
+        )
+        .await;
+
+        let body = serde_json::json!({ "name": "a longer organization name" });
+        for (uri, status) in [
+            ("/api/v1/auth/login", StatusCode::PAYLOAD_TOO_LARGE),
+            ("/api/v1/organizations", StatusCode::OK),
+        ] {
+            let req = actix_test::TestRequest::post()
+                .uri(uri)
+                .set_json(&body)
+                .to_request();
+            let res = actix_test::call_service(&app, req).await;
+            assert_eq!(res.status(), status, "{uri}");
+        }
+
+        let req = actix_test::TestRequest::get()
+            .uri("/api/v1/slow")
+            .to_request();
+        let err = actix_test::try_call_service(&app, req)
+            .await
+            .expect_err("slow request must time out");
+        assert_eq!(
+            err.as_response_error().status_code(),
+            StatusCode::GATEWAY_TIMEOUT
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_admin_middleware() {
+        use crate::organizations::OrganizationApiKey;
+
+        let admins = Arc::new(AdminConfig {
+            wallets: vec!["admin-wallet".to_string()],
+        });
+        let app = actix_test::init_service(
+            App::new()
+                .wrap(AdminMiddleware::new(admins))
+                .wrap_fn(|req, srv| {
+                    if let Some(wallet) =
+                        req.headers().get("x-wallet").and_then(|h| h.to_str().ok())
+                    {
+                        let claims = Claims {
+                            sub: wallet.to_string(),
+                            user_id: wallet.to_string(),
+                            name: wallet.to_string(),
+                            exp: 0,
+                            iat: 0,
+                            aud: "api".to_string(),
+                            iss: "api".to_string(),
+                            jti: "session".to_string(),
+                            org_id: None,
+                        };
+                        req.extensions_mut().insert(claims);
+                    }
+                    if let Some(wallet) = req
+                        .headers()
+                        .get("x-api-key-wallet")
+                        .and_then(|h| h.to_str().ok())
+                    {
+                        let api_key = OrganizationApiKey {
+                            id: "key".to_string(),
+                            org_id: "acme".to_string(),
+                            name: "ci".to_string(),
+                            prefix: "smk_abcdefgh".to_string(),
+                            key_hash: String::new(),
+                            created_by: wallet.to_string(),
+                            created_at: 0,
+                        };
+                        req.extensions_mut().insert(api_key.claims());
+                    }
+                    srv.call(req)
+                })
+                .route("/feeds", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        for (header, wallet, status) in [
+            ("x-wallet", Some("admin-wallet"), 200),
+            ("x-wallet", Some("user-wallet"), 403),
+            ("x-api-key-wallet", Some("admin-wallet"), 403),
+            ("x-wallet", None, 403),
+        ] {
+            let mut req = actix_test::TestRequest::get().uri("/feeds");
+            if let Some(wallet) = wallet {
+                req = req.insert_header((header, wallet));
+            }
+            let res = actix_test::call_service(&app, req.to_request()).await;
+            assert_eq!(res.status(), status);
+        }
+    }
+}
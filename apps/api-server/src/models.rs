@@ -1,17 +1,32 @@
 use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared_states::{
+    Author, EmbeddingResult, RssFeedSource, RssItem, SentimentLabel, SentimentResult,
+};
 use sqlx::Arguments;
 use sqlx::Row;
 use sqlx::postgres::PgArguments;
 use sqlx::prelude::FromRow;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use utoipa::IntoParams;
 use utoipa::ToSchema;
 use validator::Validate;
 
 use crate::{
-    database::StoreReadBulkEntities, impl_read_bulk_by_ids, impl_read_bulk_multiple,
-    impl_store_bulk,
+    audit::AuditEventType,
+    database::{Filter, FilterValue, GroupCount, Sort, SortDirection, StoreReadBulkEntities},
+    digest::DigestFrequency,
+    export::ExportFormat,
+    impl_count_aggregate, impl_delete_bulk_by_ids, impl_nearest, impl_read_bulk_by_ids,
+    impl_read_bulk_multiple, impl_search, impl_store_bulk,
+    media::RssItemMedia,
+    organizations::{
+        API_KEY_AUDIENCE, Organization, OrganizationApiKey, OrganizationMember, Scope,
+    },
+    payments::UserSubscription,
+    webhooks::WebhookEventType,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow, Validate)]
@@ -32,7 +47,7 @@ impl_read_bulk_multiple!(
     SolanaUser,
     "solana_users",
     [user_id, solana_wallet_public_key, created_at],
-    &HashMap<String, String>
+    "created_at",
 );
 
 impl_read_bulk_by_ids!(
@@ -43,32 +58,40 @@ impl_read_bulk_by_ids!(
     "solana_wallet_public_key",
 );
 
+impl_count_aggregate!(SolanaUser, "solana_users",);
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UserResponse {
     pub solana_wallet_public_key: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
 pub struct RegisterRequest {
     /// Solana wallet public key
+    #[validate(length(min = 32, max = 44, message = "must be a base58 encoded public key"))]
     pub solana_wallet_public_key: String,
     /// Temporary token from Telegram
+    #[validate(length(min = 1, max = 512))]
     pub token: String,
     /// Expiration time of the token
     pub expires_at: u64,
     /// Wallet signature to prove ownership
+    #[validate(length(min = 64, max = 88, message = "must be a base58 encoded signature"))]
     pub signature: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
 pub struct LoginRequest {
     /// Solana wallet public key
+    #[validate(length(min = 32, max = 44, message = "must be a base58 encoded public key"))]
     pub solana_wallet_public_key: String,
     /// Temporary token from Telegram
+    #[validate(length(min = 1, max = 512))]
     pub token: String,
     /// Expiration time of the token
     pub expires_at: u64,
     /// Wallet signature to prove ownership
+    #[validate(length(min = 64, max = 88, message = "must be a base58 encoded signature"))]
     pub signature: String,
 }
 
@@ -81,10 +104,1833 @@ pub struct Claims {
     pub iat: i64,
     pub aud: String,
     pub iss: String,
+    /// Unique ID of the token, the ID of its session.
+    pub jti: String,
+    /// Organization the request is bound to, set for requests authenticated with an organization
+    /// API key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<String>,
+}
+
+impl Claims {
+    /// Checks if the request was authenticated with an organization API key.
+    pub fn is_api_key(&self) -> bool {
+        self.aud == API_KEY_AUDIENCE
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
-pub struct ErrorResponse {
+pub struct HealthResponse {
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+    pub uptime_seconds: i64,
+    pub active_connections: i64,
+    pub active_sessions: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DependencyCheck {
+    /// `up` if the dependency answered in time, `down` otherwise
+    pub status: String,
+    pub latency_ms: u64,
+    /// Reason the dependency is down
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReadinessResponse {
+    /// `ready` if all dependencies are up, `not_ready` otherwise
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+    /// Checks by dependency name, `postgres`, `redis` and `nats`
+    pub checks: BTreeMap<String, DependencyCheck>,
+}
+
+/// ProblemDetails is the RFC 7807 `application/problem+json` body of a failed request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ProblemDetails {
+    /// Type of the problem, `about:blank` as problems are told apart by `error`
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Reason phrase of the status, e.g. `Not Found`
+    pub title: String,
+    /// HTTP status code of the response
+    pub status: u16,
+    /// Explanation of the problem for the client
+    pub detail: String,
+    /// Machine readable kind of the problem, e.g. `rss_item_not_found`
     pub error: String,
-    pub message: String,
+    /// ID of the request, also returned in the `X-Request-Id` header
+    pub request_id: String,
+    /// Messages of the invalid fields of a request failing validation, keyed by field
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, Vec<String>>,
+}
+
+/// RssSearchHit is an RSS item matching a full-text search query.
+#[derive(Debug, Clone, FromRow)]
+pub struct RssSearchHit {
+    #[sqlx(flatten)]
+    pub item: RssItem,
+    pub rank: f32,
+    pub headline: String,
+}
+
+impl_search!(
+    RssSearchHit,
+    "rss_items",
+    [
+        hash,
+        title,
+        link,
+        description,
+        published_timestamp,
+        published_timestamp_estimated,
+        fetched_timestamp,
+        comments_url,
+        category,
+        author,
+        article,
+        article_markdown,
+        language,
+        preview_title,
+        preview_description,
+        preview_image,
+        preview_published_timestamp,
+        enclosure_url,
+        enclosure_object_key,
+        simhash,
+        word_count,
+        reading_time_minutes
+    ],
+    "COALESCE(NULLIF(article, ''), description)",
+    "published_timestamp",
+    soft_delete = "deleted_at",
+);
+
+/// RssItemSentiment is the latest sentiment analysis of an RSS item.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct RssItemSentiment {
+    pub item_hash: String,
+    /// `positive`, `neutral` or `negative`.
+    pub label: String,
+    pub score: f32,
+    pub model: String,
+    pub analyzed_timestamp: i64,
+}
+
+impl From<SentimentResult> for RssItemSentiment {
+    fn from(result: SentimentResult) -> Self {
+        let label = match result.label {
+            SentimentLabel::Positive => "positive",
+            SentimentLabel::Neutral => "neutral",
+            SentimentLabel::Negative => "negative",
+        };
+        Self {
+            item_hash: result.item_hash,
+            label: label.to_string(),
+            score: result.score,
+            model: result.model,
+            analyzed_timestamp: result.analyzed_timestamp,
+        }
+    }
+}
+
+impl_store_bulk!(
+    RssItemSentiment,
+    String,
+    "rss_item_sentiments",
+    [item_hash, label, score, model, analyzed_timestamp],
+    "item_hash",
+);
+
+impl_read_bulk_by_ids!(
+    RssItemSentiment,
+    String,
+    "rss_item_sentiments",
+    [item_hash, label, score, model, analyzed_timestamp],
+    "item_hash",
+);
+
+impl_count_aggregate!(RssItemSentiment, "rss_item_sentiments",);
+
+impl_delete_bulk_by_ids!(RssItemSentiment, String, "rss_item_sentiments", "item_hash",);
+
+/// RssItemEmbedding is the embedding of an RSS item used for semantic search.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRow)]
+pub struct RssItemEmbedding {
+    pub item_hash: String,
+    /// Stored as `vector(384)`, see [`shared_states::EMBEDDING_DIMENSION`].
+    pub embedding: Vec<f32>,
+    pub model: String,
+    pub embedded_timestamp: i64,
+}
+
+impl From<EmbeddingResult> for RssItemEmbedding {
+    fn from(result: EmbeddingResult) -> Self {
+        Self {
+            item_hash: result.item_hash,
+            embedding: result.embedding.vector,
+            model: result.model,
+            embedded_timestamp: result.embedded_timestamp,
+        }
+    }
+}
+
+impl_store_bulk!(
+    RssItemEmbedding,
+    String,
+    "rss_item_embeddings",
+    [item_hash, embedding, model, embedded_timestamp],
+    "item_hash",
+);
+
+impl_delete_bulk_by_ids!(RssItemEmbedding, String, "rss_item_embeddings", "item_hash",);
+
+/// RssSemanticHit is an RSS item whose embedding is near to the embedding of a search query.
+#[derive(Debug, Clone, FromRow)]
+pub struct RssSemanticHit {
+    #[sqlx(flatten)]
+    pub item: RssItem,
+    pub similarity: f32,
+}
+
+impl_nearest!(
+    RssSemanticHit,
+    "rss_items",
+    [
+        hash,
+        title,
+        link,
+        description,
+        published_timestamp,
+        published_timestamp_estimated,
+        fetched_timestamp,
+        comments_url,
+        category,
+        author,
+        article,
+        article_markdown,
+        language,
+        preview_title,
+        preview_description,
+        preview_image,
+        preview_published_timestamp,
+        enclosure_url,
+        enclosure_object_key,
+        simhash,
+        word_count,
+        reading_time_minutes
+    ],
+    "rss_item_embeddings",
+    "rss_item_embeddings.item_hash = rss_items.hash",
+    "rss_item_embeddings.embedding",
+    soft_delete = "deleted_at",
+);
+
+/// Identifier of a record owned by the user, stable for the same user and key so saving twice
+/// updates the record.
+fn user_scoped_id(user_id: &str, key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(user_id.as_bytes());
+    hasher.update([0]);
+    hasher.update(key.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// RssBookmark is an RSS item bookmarked by a user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct RssBookmark {
+    pub id: String,
+    pub user_id: String,
+    /// Organization the bookmark is shared with, empty for the user alone.
+    pub org_id: String,
+    pub item_hash: String,
+    pub created_at: i64,
+}
+
+impl RssBookmark {
+    /// Identifier of the bookmark of the item by the owner, see [`Scope::owner`].
+    pub fn id(owner: &str, item_hash: &str) -> String {
+        user_scoped_id(owner, item_hash)
+    }
+
+    pub fn new(scope: &Scope, item_hash: &str, created_at: i64) -> Self {
+        Self {
+            id: Self::id(&scope.owner(), item_hash),
+            user_id: scope.user_id().to_string(),
+            org_id: scope.org_id().to_string(),
+            item_hash: item_hash.to_string(),
+            created_at,
+        }
+    }
+}
+
+impl_store_bulk!(
+    RssBookmark,
+    String,
+    "rss_bookmarks",
+    [id, user_id, org_id, item_hash, created_at],
+    "id",
+);
+
+impl_read_bulk_by_ids!(
+    RssBookmark,
+    String,
+    "rss_bookmarks",
+    [id, user_id, org_id, item_hash, created_at],
+    "id",
+);
+
+impl_read_bulk_multiple!(
+    RssBookmark,
+    "rss_bookmarks",
+    [id, user_id, org_id, item_hash, created_at],
+    "created_at",
+);
+
+impl_count_aggregate!(RssBookmark, "rss_bookmarks",);
+
+impl_delete_bulk_by_ids!(RssBookmark, String, "rss_bookmarks", "id",);
+
+/// AuthSession is a JWT issued to a user, revoking the session invalidates the token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct AuthSession {
+    /// ID of the token, the `jti` claim.
+    pub id: String,
+    pub user_id: String,
+    pub user_agent: String,
+    pub created_at: i64,
+    /// Time the token expires at in Unix milliseconds.
+    pub expires_at: i64,
+}
+
+impl_store_bulk!(
+    AuthSession,
+    String,
+    "auth_sessions",
+    [id, user_id, user_agent, created_at, expires_at],
+    "id",
+);
+
+impl_read_bulk_by_ids!(
+    AuthSession,
+    String,
+    "auth_sessions",
+    [id, user_id, user_agent, created_at, expires_at],
+    "id",
+);
+
+impl_read_bulk_multiple!(
+    AuthSession,
+    "auth_sessions",
+    [id, user_id, user_agent, created_at, expires_at],
+    "created_at",
+);
+
+impl_count_aggregate!(AuthSession, "auth_sessions",);
+
+impl_delete_bulk_by_ids!(AuthSession, String, "auth_sessions", "id",);
+
+/// RssSavedSearch is a named full-text search query saved by a user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct RssSavedSearch {
+    pub id: String,
+    pub user_id: String,
+    /// Organization the search is shared with, empty for the user alone.
+    pub org_id: String,
+    pub name: String,
+    pub query: String,
+    pub saved_at: i64,
+}
+
+impl RssSavedSearch {
+    /// Identifier of the search saved by the owner under the name, see [`Scope::owner`].
+    pub fn id(owner: &str, name: &str) -> String {
+        user_scoped_id(owner, name)
+    }
+
+    pub fn new(scope: &Scope, name: &str, query: &str, saved_at: i64) -> Self {
+        Self {
+            id: Self::id(&scope.owner(), name),
+            user_id: scope.user_id().to_string(),
+            org_id: scope.org_id().to_string(),
+            name: name.to_string(),
+            query: query.to_string(),
+            saved_at,
+        }
+    }
+}
+
+impl_store_bulk!(
+    RssSavedSearch,
+    String,
+    "rss_saved_searches",
+    [id, user_id, org_id, name, query, saved_at],
+    "id",
+);
+
+impl_read_bulk_multiple!(
+    RssSavedSearch,
+    "rss_saved_searches",
+    [id, user_id, org_id, name, query, saved_at],
+    "saved_at",
+);
+
+impl_count_aggregate!(RssSavedSearch, "rss_saved_searches",);
+
+impl_delete_bulk_by_ids!(RssSavedSearch, String, "rss_saved_searches", "id",);
+
+/// WebhookSubscription is a URL events of the selected types are POSTed to on behalf of a user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct WebhookSubscription {
+    pub id: String,
+    pub user_id: String,
+    /// Organization the webhook is shared with, empty for the user alone.
+    pub org_id: String,
+    pub url: String,
+    pub event_types: Vec<String>,
+    /// Secret the payloads are signed with, never returned to the client.
+    pub secret: String,
+    pub created_at: i64,
+}
+
+impl WebhookSubscription {
+    /// Identifier of the subscription of the URL by the owner, see [`Scope::owner`].
+    pub fn id(owner: &str, url: &str) -> String {
+        user_scoped_id(owner, url)
+    }
+
+    pub fn new(
+        scope: &Scope,
+        url: &str,
+        event_types: &[WebhookEventType],
+        secret: &str,
+        created_at: i64,
+    ) -> Self {
+        let mut types: Vec<String> = event_types
+            .iter()
+            .map(|event_type| event_type.as_str().to_string())
+            .collect();
+        types.sort();
+        types.dedup();
+
+        Self {
+            id: Self::id(&scope.owner(), url),
+            user_id: scope.user_id().to_string(),
+            org_id: scope.org_id().to_string(),
+            url: url.to_string(),
+            event_types: types,
+            secret: secret.to_string(),
+            created_at,
+        }
+    }
+}
+
+impl_store_bulk!(
+    WebhookSubscription,
+    String,
+    "webhook_subscriptions",
+    [id, user_id, org_id, url, event_types, secret, created_at],
+    "id",
+);
+
+impl_read_bulk_by_ids!(
+    WebhookSubscription,
+    String,
+    "webhook_subscriptions",
+    [id, user_id, org_id, url, event_types, secret, created_at],
+    "id",
+);
+
+impl_read_bulk_multiple!(
+    WebhookSubscription,
+    "webhook_subscriptions",
+    [id, user_id, org_id, url, event_types, secret, created_at],
+    "created_at",
+);
+
+impl_count_aggregate!(WebhookSubscription, "webhook_subscriptions",);
+
+impl_delete_bulk_by_ids!(WebhookSubscription, String, "webhook_subscriptions", "id",);
+
+/// AuditEvent is a security-relevant event, the audit log is append-only.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct AuditEvent {
+    pub id: String,
+    /// Type of the event, see [`AuditEventType`].
+    pub event_type: String,
+    /// Wallet the event was triggered by.
+    pub actor: String,
+    pub success: bool,
+    pub details: String,
+    pub request_id: String,
+    pub created_at: i64,
+}
+
+impl_store_bulk!(
+    AuditEvent,
+    String,
+    "audit_log",
+    [
+        id, event_type, actor, success, details, request_id, created_at
+    ],
+    "id",
+);
+
+impl_read_bulk_multiple!(
+    AuditEvent,
+    "audit_log",
+    [
+        id, event_type, actor, success, details, request_id, created_at
+    ],
+    "created_at",
+);
+
+impl_count_aggregate!(AuditEvent, "audit_log",);
+
+/// DigestPreference configures the email digest of a user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct DigestPreference {
+    pub user_id: String,
+    pub email: String,
+    /// `daily` or `weekly`, see [`DigestFrequency`].
+    pub frequency: String,
+    /// Category slugs of the items, all categories if empty.
+    pub categories: Vec<String>,
+    pub enabled: bool,
+    /// Time the last digest was sent in Unix milliseconds, 0 if none was sent.
+    pub last_sent_at: i64,
+    /// Time the next digest is due in Unix milliseconds.
+    pub next_send_at: i64,
+    pub updated_at: i64,
+    /// SHA-256 hex digest of the token mailed to the address, empty once it is confirmed.
+    pub confirmation_token_hash: String,
+    /// Time the confirmation token was mailed in Unix milliseconds, 0 if none was mailed.
+    pub confirmation_sent_at: i64,
+    /// Time the address was confirmed in Unix milliseconds, 0 if it is not, digests are only sent
+    /// to confirmed addresses.
+    pub confirmed_at: i64,
+}
+
+impl DigestPreference {
+    /// Frequency of the digest, daily if the stored value is unknown.
+    pub fn frequency(&self) -> DigestFrequency {
+        self.frequency.parse().unwrap_or(DigestFrequency::Daily)
+    }
+}
+
+impl_store_bulk!(
+    DigestPreference,
+    String,
+    "digest_preferences",
+    [
+        user_id,
+        email,
+        frequency,
+        categories,
+        enabled,
+        last_sent_at,
+        next_send_at,
+        updated_at,
+        confirmation_token_hash,
+        confirmation_sent_at,
+        confirmed_at
+    ],
+    "user_id",
+);
+
+impl_read_bulk_by_ids!(
+    DigestPreference,
+    String,
+    "digest_preferences",
+    [
+        user_id,
+        email,
+        frequency,
+        categories,
+        enabled,
+        last_sent_at,
+        next_send_at,
+        updated_at,
+        confirmation_token_hash,
+        confirmation_sent_at,
+        confirmed_at
+    ],
+    "user_id",
+);
+
+impl_read_bulk_multiple!(
+    DigestPreference,
+    "digest_preferences",
+    [
+        user_id,
+        email,
+        frequency,
+        categories,
+        enabled,
+        last_sent_at,
+        next_send_at,
+        updated_at,
+        confirmation_token_hash,
+        confirmation_sent_at,
+        confirmed_at
+    ],
+    "next_send_at",
+);
+
+impl_delete_bulk_by_ids!(DigestPreference, String, "digest_preferences", "user_id",);
+
+/// Host of the item link without the `www.` prefix, identifies the website the item comes from.
+/// Must match the expression of the `idx_rss_items_feed_host` index.
+const RSS_ITEM_FEED_HOST: &str = r"regexp_replace(lower(substring(link FROM '^[a-zA-Z][a-zA-Z0-9+.-]*://([^/:?#]+)')), '^www\.', '')";
+
+/// UTC fetch day and feed host of the item separated by a space, e.g. `2026-10-16 coindesk.com`.
+/// The host part must match [`RSS_ITEM_FEED_HOST`].
+pub const RSS_ITEM_FETCH_DAY_AND_FEED_HOST: &str = r"to_char(to_timestamp(fetched_timestamp / 1000.0) AT TIME ZONE 'UTC', 'YYYY-MM-DD') || ' ' || coalesce(regexp_replace(lower(substring(link FROM '^[a-zA-Z][a-zA-Z0-9+.-]*://([^/:?#]+)')), '^www\.', ''), '')";
+
+pub const ADMIN_STATS_DEFAULT_DAYS: i64 = 7;
+pub const ADMIN_STATS_MAX_DAYS: i64 = 90;
+/// Maximal number of feed and day pairs in the statistics.
+pub const ADMIN_STATS_MAX_FEED_DAYS: i64 = 1000;
+
+pub const RSS_FEED_DEFAULT_INTERVAL_SECONDS: i64 = 300;
+
+pub const RSS_ITEMS_DEFAULT_LIMIT: i64 = 20;
+pub const RSS_ITEMS_MAX_LIMIT: i64 = 100;
+
+fn page(limit: Option<i64>, offset: Option<i64>) -> (i64, i64) {
+    (
+        limit
+            .unwrap_or(RSS_ITEMS_DEFAULT_LIMIT)
+            .clamp(1, RSS_ITEMS_MAX_LIMIT),
+        offset.unwrap_or_default().max(0),
+    )
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams, Validate)]
+pub struct RssItemsQuery {
+    /// Number of items per page, 20 by default, at most 100
+    #[validate(range(min = 1, max = RSS_ITEMS_MAX_LIMIT))]
+    pub limit: Option<i64>,
+    /// Number of items to skip
+    #[validate(range(min = 0))]
+    pub offset: Option<i64>,
+    /// Category slug the items are tagged with, e.g. `bitcoin`
+    pub category: Option<String>,
+    /// Author name
+    pub author: Option<String>,
+    /// Text the title contains, case insensitive
+    pub title: Option<String>,
+    /// Comma separated ISO 639-3 language codes, e.g. `eng,deu`
+    pub language: Option<String>,
+    /// Domain of the website the items link to, e.g. `coindesk.com`
+    pub feed: Option<String>,
+    /// Earliest publication time in Unix milliseconds, inclusive
+    pub date_from: Option<i64>,
+    /// Latest publication time in Unix milliseconds, inclusive
+    pub date_to: Option<i64>,
+    /// Field to sort by, e.g. `title` or `word_count`, newest first by default
+    pub sort: Option<String>,
+    /// Sort direction, descending by default
+    pub order: Option<SortDirection>,
+}
+
+impl RssItemsQuery {
+    /// Page size clamped to the allowed range.
+    pub fn limit(&self) -> i64 {
+        page(self.limit, self.offset).0
+    }
+
+    /// Offset, never negative.
+    pub fn offset(&self) -> i64 {
+        page(self.limit, self.offset).1
+    }
+
+    /// Sort of the query, None to list the newest items first.
+    pub fn sort(&self) -> Option<Sort> {
+        let field = self.sort.as_deref().map(str::trim)?;
+        (!field.is_empty()).then(|| Sort {
+            field: field.to_string(),
+            direction: self.order.unwrap_or_default(),
+        })
+    }
+
+    /// Storage filters of the query, blank values are ignored.
+    pub fn filters(&self) -> Vec<Filter> {
+        let text = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(|value| FilterValue::Text(value.to_string()))
+        };
+
+        let mut filters = Vec::new();
+        if let Some(category) = text(&self.category) {
+            filters.push(Filter::Contains("category", category));
+        }
+        if let Some(author) = text(&self.author) {
+            filters.push(Filter::Eq("author ->> 'name'", author));
+        }
+        if let Some(FilterValue::Text(title)) = text(&self.title) {
+            filters.push(Filter::ILike(
+                "title",
+                FilterValue::contains_pattern(&title),
+            ));
+        }
+        let languages: Vec<String> = self
+            .language
+            .iter()
+            .flat_map(|languages| languages.split(','))
+            .map(|language| language.trim().to_lowercase())
+            .filter(|language| !language.is_empty())
+            .collect();
+        if !languages.is_empty() {
+            filters.push(Filter::In("language", FilterValue::TextList(languages)));
+        }
+        if let Some(FilterValue::Text(feed)) = text(&self.feed) {
+            filters.push(feed_filter(&feed));
+        }
+        if let Some(date_from) = self.date_from {
+            filters.push(Filter::Gte(
+                "published_timestamp",
+                FilterValue::Int(date_from),
+            ));
+        }
+        if let Some(date_to) = self.date_to {
+            filters.push(Filter::Lte(
+                "published_timestamp",
+                FilterValue::Int(date_to),
+            ));
+        }
+        filters
+    }
+}
+
+/// Filter of the items linking to the website, the `www.` prefix is ignored.
+fn feed_filter(feed: &str) -> Filter {
+    let feed = feed.to_lowercase();
+    let feed = feed.strip_prefix("www.").unwrap_or(&feed);
+    Filter::Eq(RSS_ITEM_FEED_HOST, FilterValue::Text(feed.to_string()))
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema, Validate)]
+pub struct PurgeRssItemsRequest {
+    /// Deletes the items fetched more than the number of days ago
+    #[validate(range(min = 1))]
+    pub older_than_days: Option<i64>,
+    /// Deletes the items of the website, e.g. `coindesk.com`
+    pub feed: Option<String>,
+    /// Deletes the items with the hashes, e.g. on a removal request
+    #[validate(length(max = 1000))]
+    pub hashes: Option<Vec<String>>,
+}
+
+impl PurgeRssItemsRequest {
+    /// Storage filters of the items to delete, all criteria have to be met, empty if none is given.
+    ///
+    /// # Arguments
+    /// * `now` - The current time in Unix milliseconds.
+    pub fn filters(&self, now: i64) -> Vec<Filter> {
+        let mut filters = Vec::new();
+        if let Some(days) = self.older_than_days {
+            filters.push(Filter::Lte(
+                "fetched_timestamp",
+                FilterValue::Int(now - days * 86_400_000),
+            ));
+        }
+        if let Some(feed) = self.feed.as_deref().map(str::trim)
+            && !feed.is_empty()
+        {
+            filters.push(feed_filter(feed));
+        }
+        if let Some(hashes) = self.hashes.as_ref().filter(|hashes| !hashes.is_empty()) {
+            filters.push(Filter::In("hash", FilterValue::TextList(hashes.clone())));
+        }
+        filters
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PurgeRssItemsResponse {
+    /// Number of items deleted, they are removed from storage after the retention grace period
+    pub deleted: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams, Validate)]
+pub struct RssSearchQuery {
+    /// Search query, supports quoted phrases, `or` and `-` to exclude words
+    #[validate(length(max = 500))]
+    pub q: String,
+    /// Number of items per page, 20 by default, at most 100
+    #[validate(range(min = 1, max = RSS_ITEMS_MAX_LIMIT))]
+    pub limit: Option<i64>,
+    /// Number of items to skip
+    #[validate(range(min = 0))]
+    pub offset: Option<i64>,
+}
+
+impl RssSearchQuery {
+    /// Page size clamped to the allowed range.
+    pub fn limit(&self) -> i64 {
+        page(self.limit, self.offset).0
+    }
+
+    /// Offset, never negative.
+    pub fn offset(&self) -> i64 {
+        page(self.limit, self.offset).1
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams, Validate)]
+pub struct RssExportQuery {
+    /// Encoding of the items, `jsonl` by default
+    pub format: Option<ExportFormat>,
+    /// Earliest publication time in Unix milliseconds, inclusive
+    pub from: Option<i64>,
+    /// Latest publication time in Unix milliseconds, inclusive
+    pub to: Option<i64>,
+}
+
+impl RssExportQuery {
+    /// Storage filters of the query.
+    pub fn filters(&self) -> Vec<Filter> {
+        RssItemsQuery {
+            date_from: self.from,
+            date_to: self.to,
+            ..Default::default()
+        }
+        .filters()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams, Validate)]
+pub struct RssSemanticSearchQuery {
+    /// Text to find items of similar meaning to, e.g. `central banks buying gold`
+    #[validate(length(max = 500))]
+    pub q: String,
+    /// Number of items per page, 20 by default, at most 100
+    #[validate(range(min = 1, max = RSS_ITEMS_MAX_LIMIT))]
+    pub limit: Option<i64>,
+    /// Number of items to skip
+    #[validate(range(min = 0))]
+    pub offset: Option<i64>,
+    /// Category slug the items are tagged with, e.g. `bitcoin`
+    pub category: Option<String>,
+    /// Author name
+    pub author: Option<String>,
+    /// Comma separated ISO 639-3 language codes, e.g. `eng,deu`
+    pub language: Option<String>,
+    /// Domain of the website the items link to, e.g. `coindesk.com`
+    pub feed: Option<String>,
+    /// Earliest publication time in Unix milliseconds, inclusive
+    pub date_from: Option<i64>,
+    /// Latest publication time in Unix milliseconds, inclusive
+    pub date_to: Option<i64>,
+}
+
+impl RssSemanticSearchQuery {
+    /// Page size clamped to the allowed range.
+    pub fn limit(&self) -> i64 {
+        page(self.limit, self.offset).0
+    }
+
+    /// Offset, never negative.
+    pub fn offset(&self) -> i64 {
+        page(self.limit, self.offset).1
+    }
+
+    /// Storage filters of the query, the same as of [`RssItemsQuery::filters`].
+    pub fn filters(&self) -> Vec<Filter> {
+        RssItemsQuery {
+            category: self.category.clone(),
+            author: self.author.clone(),
+            language: self.language.clone(),
+            feed: self.feed.clone(),
+            date_from: self.date_from,
+            date_to: self.date_to,
+            ..Default::default()
+        }
+        .filters()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams, Validate)]
+pub struct PageQuery {
+    /// Number of entries per page, 20 by default, at most 100
+    #[validate(range(min = 1, max = RSS_ITEMS_MAX_LIMIT))]
+    pub limit: Option<i64>,
+    /// Number of entries to skip
+    #[validate(range(min = 0))]
+    pub offset: Option<i64>,
+}
+
+impl PageQuery {
+    /// Page size clamped to the allowed range.
+    pub fn limit(&self) -> i64 {
+        page(self.limit, self.offset).0
+    }
+
+    /// Offset, never negative.
+    pub fn offset(&self) -> i64 {
+        page(self.limit, self.offset).1
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams, Validate)]
+pub struct AuditLogQuery {
+    /// Number of events per page, 20 by default, at most 100
+    #[validate(range(min = 1, max = RSS_ITEMS_MAX_LIMIT))]
+    pub limit: Option<i64>,
+    /// Number of events to skip
+    #[validate(range(min = 0))]
+    pub offset: Option<i64>,
+    /// Type of the events
+    pub event_type: Option<AuditEventType>,
+    /// Base58 wallet the events were triggered by
+    #[validate(length(min = 32, max = 44, message = "must be a base58 encoded public key"))]
+    pub actor: Option<String>,
+    /// Earliest event time in Unix milliseconds, inclusive
+    pub date_from: Option<i64>,
+    /// Latest event time in Unix milliseconds, inclusive
+    pub date_to: Option<i64>,
+}
+
+impl AuditLogQuery {
+    /// Page size clamped to the allowed range.
+    pub fn limit(&self) -> i64 {
+        page(self.limit, self.offset).0
+    }
+
+    /// Offset, never negative.
+    pub fn offset(&self) -> i64 {
+        page(self.limit, self.offset).1
+    }
+
+    /// Storage filters of the query, blank values are ignored.
+    pub fn filters(&self) -> Vec<Filter> {
+        let mut filters = Vec::new();
+        if let Some(event_type) = self.event_type {
+            filters.push(Filter::Eq(
+                "event_type",
+                FilterValue::Text(event_type.as_str().to_string()),
+            ));
+        }
+        if let Some(actor) = self.actor.as_deref().map(str::trim)
+            && !actor.is_empty()
+        {
+            filters.push(Filter::Eq("actor", FilterValue::Text(actor.to_string())));
+        }
+        if let Some(date_from) = self.date_from {
+            filters.push(Filter::Gte("created_at", FilterValue::Int(date_from)));
+        }
+        if let Some(date_to) = self.date_to {
+            filters.push(Filter::Lte("created_at", FilterValue::Int(date_to)));
+        }
+        filters
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct SaveSearchRequest {
+    /// Name of the search, saving a search under an existing name replaces it
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    /// Full-text search query as accepted by `/api/v1/rss/search`
+    #[validate(length(min = 1, max = 500))]
+    pub query: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateRssFeedRequest {
+    /// URL of the RSS feed
+    #[validate(url, length(max = 2048))]
+    pub url: String,
+    /// Seconds between polls of the feed, 300 by default
+    #[validate(range(min = 60, max = 86400))]
+    pub interval_seconds: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct UpdateRssFeedRequest {
+    /// Seconds between polls of the feed
+    #[validate(range(min = 60, max = 86400))]
+    pub interval_seconds: Option<i64>,
+    /// Disabled feeds are not polled
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateWebhookRequest {
+    /// URL the events are POSTed to, registering the URL again replaces its subscription
+    #[validate(url, length(max = 2048))]
+    pub url: String,
+    /// Types of the delivered events
+    #[validate(length(min = 1))]
+    pub event_types: Vec<WebhookEventType>,
+    /// Secret the payloads are signed with, see the `X-Webhook-Signature` header
+    #[validate(length(min = 16, max = 256))]
+    pub secret: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct DigestPreferencesRequest {
+    /// Address the digest is sent to
+    #[validate(email, length(max = 254))]
+    pub email: String,
+    pub frequency: DigestFrequency,
+    /// Category slugs of the items, e.g. `bitcoin`, all categories if empty
+    #[validate(length(max = 20))]
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Disabled digests are kept but not sent, enabled by default
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct DigestConfirmationRequest {
+    /// Token mailed to the address of the digest
+    #[validate(length(min = 1, max = 64))]
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssAuthorResponse {
+    pub name: String,
+    pub email: Option<String>,
+}
+
+impl From<Author> for RssAuthorResponse {
+    fn from(author: Author) -> Self {
+        Self {
+            name: author.name,
+            email: author.email,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssItemResponse {
+    pub hash: String,
+    pub title: String,
+    pub link: String,
+    pub description: String,
+    pub published_timestamp: i64,
+    pub published_timestamp_estimated: bool,
+    pub fetched_timestamp: i64,
+    pub comments_url: String,
+    pub category: Vec<String>,
+    pub author: RssAuthorResponse,
+    pub article: String,
+    pub article_markdown: String,
+    pub language: String,
+    pub preview_title: Option<String>,
+    pub preview_description: Option<String>,
+    pub preview_image: Option<String>,
+    pub preview_published_timestamp: Option<i64>,
+    pub enclosure_url: Option<String>,
+    /// Object storage key of the archived audio enclosure.
+    pub enclosure_object_key: Option<String>,
+    pub word_count: i32,
+    pub reading_time_minutes: i32,
+}
+
+impl From<RssItem> for RssItemResponse {
+    fn from(item: RssItem) -> Self {
+        Self {
+            hash: item.hash,
+            title: item.title,
+            link: item.link,
+            description: item.description,
+            published_timestamp: item.published_timestamp,
+            published_timestamp_estimated: item.published_timestamp_estimated,
+            fetched_timestamp: item.fetched_timestamp,
+            comments_url: item.comments_url,
+            category: item.category,
+            author: item.author.into(),
+            article: item.article,
+            article_markdown: item.article_markdown,
+            language: item.language,
+            preview_title: item.preview_title,
+            preview_description: item.preview_description,
+            preview_image: item.preview_image,
+            preview_published_timestamp: item.preview_published_timestamp,
+            enclosure_url: item.enclosure_url,
+            enclosure_object_key: item.enclosure_object_key,
+            word_count: item.word_count,
+            reading_time_minutes: item.reading_time_minutes,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssItemSentimentResponse {
+    pub item_hash: String,
+    /// `positive`, `neutral` or `negative`
+    pub label: String,
+    /// Confidence of the label in range `0.0..=1.0`
+    pub score: f32,
+    /// Name of the model that produced the result
+    pub model: String,
+    pub analyzed_timestamp: i64,
+}
+
+impl From<RssItemSentiment> for RssItemSentimentResponse {
+    fn from(sentiment: RssItemSentiment) -> Self {
+        Self {
+            item_hash: sentiment.item_hash,
+            label: sentiment.label,
+            score: sentiment.score,
+            model: sentiment.model,
+            analyzed_timestamp: sentiment.analyzed_timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssSearchHitResponse {
+    pub item: RssItemResponse,
+    /// Relevance of the item, higher is better
+    pub rank: f32,
+    /// Best matching fragments with matches wrapped in `<mark>` tags
+    pub headline: String,
+}
+
+impl From<RssSearchHit> for RssSearchHitResponse {
+    fn from(hit: RssSearchHit) -> Self {
+        Self {
+            item: hit.item.into(),
+            rank: hit.rank,
+            headline: hit.headline,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssSemanticHitResponse {
+    pub item: RssItemResponse,
+    /// Cosine similarity of the item to the query in range `-1.0..=1.0`, higher is better
+    pub similarity: f32,
+}
+
+impl From<RssSemanticHit> for RssSemanticHitResponse {
+    fn from(hit: RssSemanticHit) -> Self {
+        Self {
+            item: hit.item.into(),
+            similarity: hit.similarity,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssSemanticSearchResponse {
+    pub query: String,
+    pub hits: Vec<RssSemanticHitResponse>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssSearchResponse {
+    pub query: String,
+    pub hits: Vec<RssSearchHitResponse>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssItemsResponse {
+    pub items: Vec<RssItemResponse>,
+    /// Number of items matching the filters on all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssMediaResponse {
+    /// `image` for the lead image of the article, `enclosure` for the file attached to the item
+    pub kind: String,
+    /// URL the media was archived from
+    pub source_url: String,
+    /// Presigned URL of the archived copy
+    pub url: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub archived_timestamp: i64,
+}
+
+impl RssMediaResponse {
+    pub fn new(media: RssItemMedia, url: String) -> Self {
+        Self {
+            kind: media.kind,
+            source_url: media.source_url,
+            url,
+            content_type: media.content_type,
+            size_bytes: media.size_bytes,
+            archived_timestamp: media.archived_timestamp,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssItemMediaResponse {
+    pub hash: String,
+    pub media: Vec<RssMediaResponse>,
+    /// Number of seconds the presigned URLs stay valid
+    pub expires_in_seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssBookmarkResponse {
+    pub item: RssItemResponse,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssBookmarksResponse {
+    pub bookmarks: Vec<RssBookmarkResponse>,
+    /// Number of bookmarks of the user on all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssSavedSearchResponse {
+    pub name: String,
+    pub query: String,
+    pub saved_at: i64,
+}
+
+impl From<RssSavedSearch> for RssSavedSearchResponse {
+    fn from(search: RssSavedSearch) -> Self {
+        Self {
+            name: search.name,
+            query: search.query,
+            saved_at: search.saved_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssSavedSearchesResponse {
+    pub searches: Vec<RssSavedSearchResponse>,
+    /// Number of searches of the user on all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssFeedResponse {
+    /// Hash of the feed URL
+    pub hash: String,
+    pub url: String,
+    pub interval_seconds: i64,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl From<RssFeedSource> for RssFeedResponse {
+    fn from(feed: RssFeedSource) -> Self {
+        Self {
+            hash: feed.hash,
+            url: feed.url,
+            interval_seconds: feed.interval_seconds,
+            enabled: feed.enabled,
+            created_at: feed.created_at,
+            updated_at: feed.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RssFeedsResponse {
+    pub feeds: Vec<RssFeedResponse>,
+    /// Number of feeds on all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: String,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub created_at: i64,
+}
+
+impl From<WebhookSubscription> for WebhookResponse {
+    fn from(subscription: WebhookSubscription) -> Self {
+        Self {
+            id: subscription.id,
+            url: subscription.url,
+            event_types: subscription.event_types,
+            created_at: subscription.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhooksResponse {
+    pub webhooks: Vec<WebhookResponse>,
+    /// Number of webhooks of the user on all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuthSessionResponse {
+    pub id: String,
+    pub user_agent: String,
+    pub created_at: i64,
+    /// Time the token expires at in Unix milliseconds
+    pub expires_at: i64,
+    /// Whether the session is the one of the token of the request
+    pub current: bool,
+}
+
+impl AuthSessionResponse {
+    pub fn new(session: AuthSession, current_id: &str) -> Self {
+        Self {
+            current: session.id == current_id,
+            id: session.id,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuthSessionsResponse {
+    pub sessions: Vec<AuthSessionResponse>,
+    /// Number of active sessions of the user on all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams, Validate)]
+pub struct AdminStatsQuery {
+    /// Number of days the statistics cover, 7 by default, at most 90
+    #[validate(range(min = 1, max = ADMIN_STATS_MAX_DAYS))]
+    pub days: Option<i64>,
+}
+
+impl AdminStatsQuery {
+    /// Number of days clamped to the allowed range.
+    pub fn days(&self) -> i64 {
+        self.days
+            .unwrap_or(ADMIN_STATS_DEFAULT_DAYS)
+            .clamp(1, ADMIN_STATS_MAX_DAYS)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FeedDayCountResponse {
+    /// UTC day the items were fetched on, e.g. `2026-10-16`
+    pub day: String,
+    /// Domain of the website the items link to
+    pub feed: String,
+    pub count: i64,
+}
+
+impl From<GroupCount> for FeedDayCountResponse {
+    fn from(group: GroupCount) -> Self {
+        let (day, feed) = group.key.split_once(' ').unwrap_or((&group.key, ""));
+        Self {
+            day: day.to_string(),
+            feed: feed.to_string(),
+            count: group.count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SentimentCountResponse {
+    /// `positive`, `neutral` or `negative`
+    pub label: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct IngestionLagResponse {
+    /// Median time between publication and fetch of the items in milliseconds
+    pub p50_ms: Option<f64>,
+    /// 95th percentile of the time between publication and fetch in milliseconds
+    pub p95_ms: Option<f64>,
+    /// Time since the last item was fetched in milliseconds
+    pub since_last_fetch_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AdminStatsResponse {
+    /// Start of the covered period in Unix milliseconds
+    pub since: i64,
+    /// Items fetched per feed per day, newest days first and largest counts first within a day
+    pub items_per_feed_per_day: Vec<FeedDayCountResponse>,
+    /// Sentiments of the items analyzed in the period
+    pub sentiment_distribution: Vec<SentimentCountResponse>,
+    pub registered_users: i64,
+    /// Users with a session that did not expire yet
+    pub active_users: i64,
+    pub ingestion_lag: IngestionLagResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct SolanaPaymentRequest {
+    /// Base58 signature of the finalized transaction paying for the tier
+    #[validate(length(min = 64, max = 88, message = "must be a base58 encoded signature"))]
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SubscriptionResponse {
+    pub tier: String,
+    /// Time the tier expires at in Unix milliseconds
+    pub expires_at: i64,
+}
+
+impl From<UserSubscription> for SubscriptionResponse {
+    fn from(subscription: UserSubscription) -> Self {
+        Self {
+            tier: subscription.tier,
+            expires_at: subscription.expires_at,
+        }
+    }
+}
+
+/// Organization a request acts in, without it the resources of the user alone are used.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+#[into_params(names("X-Organization-Id"), parameter_in = Header)]
+pub struct OrganizationHeader(
+    /// ID of the organization the bookmarks, saved searches and webhooks are shared in, the user
+    /// must be a member of it
+    pub Option<String>,
+);
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateOrganizationRequest {
+    /// Name of the organization
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrganizationResponse {
+    pub id: String,
+    pub name: String,
+    pub owner_id: String,
+    /// Role of the user in the organization
+    pub role: String,
+    pub created_at: i64,
+}
+
+impl OrganizationResponse {
+    pub fn new(organization: Organization, membership: &OrganizationMember) -> Self {
+        Self {
+            id: organization.id,
+            name: organization.name,
+            owner_id: organization.owner_id,
+            role: membership.role.clone(),
+            created_at: organization.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrganizationsResponse {
+    pub organizations: Vec<OrganizationResponse>,
+    /// Number of organizations of the user on all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct AddOrganizationMemberRequest {
+    /// Base58 wallet of the new member
+    #[validate(length(min = 32, max = 44, message = "must be a base58 encoded public key"))]
+    pub user_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrganizationMemberResponse {
+    pub user_id: String,
+    /// `owner` or `member`
+    pub role: String,
+    pub created_at: i64,
+}
+
+impl From<OrganizationMember> for OrganizationMemberResponse {
+    fn from(member: OrganizationMember) -> Self {
+        Self {
+            user_id: member.user_id,
+            role: member.role,
+            created_at: member.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct OrganizationMembersResponse {
+    pub members: Vec<OrganizationMemberResponse>,
+    /// Number of members of the organization on all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+pub struct CreateApiKeyRequest {
+    /// Name telling the key apart, e.g. the service using it
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    /// Leading characters of the key
+    pub prefix: String,
+    /// Wallet of the member the key acts on behalf of
+    pub created_by: String,
+    pub created_at: i64,
+}
+
+impl From<OrganizationApiKey> for ApiKeyResponse {
+    fn from(key: OrganizationApiKey) -> Self {
+        Self {
+            id: key.id,
+            name: key.name,
+            prefix: key.prefix,
+            created_by: key.created_by,
+            created_at: key.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreatedApiKeyResponse {
+    #[serde(flatten)]
+    pub api_key: ApiKeyResponse,
+    /// The API key, sent in the `X-Api-Key` header, it is only returned once
+    pub key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeysResponse {
+    pub api_keys: Vec<ApiKeyResponse>,
+    /// Number of API keys of the organization on all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Public key tokens are verified with, as a JSON Web Key of RFC 7517.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct JwkResponse {
+    /// Key type, `RSA` or `OKP`
+    pub kty: String,
+    /// Key ID matching the `kid` header of the tokens
+    pub kid: String,
+    /// Signing algorithm, `RS256` or `EdDSA`
+    pub alg: String,
+    #[serde(rename = "use")]
+    pub key_use: String,
+    /// RSA modulus, base64url encoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    /// RSA exponent, base64url encoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+    /// Curve of the octet key pair, `Ed25519`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    /// Ed25519 public key, base64url encoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JwksResponse {
+    /// Keys of the tokens currently accepted, empty when tokens are signed with a shared secret
+    pub keys: Vec<JwkResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditEventResponse {
+    pub id: String,
+    pub event_type: String,
+    pub actor: String,
+    pub success: bool,
+    pub details: String,
+    pub request_id: String,
+    pub created_at: i64,
+}
+
+impl From<AuditEvent> for AuditEventResponse {
+    fn from(event: AuditEvent) -> Self {
+        Self {
+            id: event.id,
+            event_type: event.event_type,
+            actor: event.actor,
+            success: event.success,
+            details: event.details,
+            request_id: event.request_id,
+            created_at: event.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditEventsResponse {
+    pub events: Vec<AuditEventResponse>,
+    /// Number of events matching the filters on all pages
+    pub total: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DigestPreferencesResponse {
+    pub email: String,
+    pub frequency: DigestFrequency,
+    pub categories: Vec<String>,
+    pub enabled: bool,
+    /// Digests are only sent once the address is confirmed with the mailed token
+    pub confirmed: bool,
+    /// Time the last digest was sent in Unix milliseconds
+    pub last_sent_at: Option<i64>,
+    /// Time the next digest is due in Unix milliseconds
+    pub next_send_at: i64,
+}
+
+impl From<DigestPreference> for DigestPreferencesResponse {
+    fn from(preference: DigestPreference) -> Self {
+        Self {
+            frequency: preference.frequency(),
+            email: preference.email,
+            categories: preference.categories,
+            enabled: preference.enabled,
+            confirmed: preference.confirmed_at > 0,
+            last_sent_at: (preference.last_sent_at > 0).then_some(preference.last_sent_at),
+            next_send_at: preference.next_send_at,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rss_items_query_bounds() {
+        let query = RssItemsQuery::default();
+        assert_eq!((query.limit(), query.offset()), (20, 0));
+
+        let query = RssItemsQuery {
+            limit: Some(1000),
+            offset: Some(-5),
+            ..Default::default()
+        };
+        assert_eq!((query.limit(), query.offset()), (100, 0));
+
+        let query = RssSearchQuery {
+            q: "bitcoin".to_string(),
+            limit: Some(0),
+            offset: Some(40),
+        };
+        assert_eq!((query.limit(), query.offset()), (1, 40));
+    }
+
+    #[test]
+    fn test_rss_items_query_filters() {
+        let query = RssItemsQuery {
+            category: Some("bitcoin".to_string()),
+            author: Some("  ".to_string()),
+            feed: Some("WWW.CoinDesk.com".to_string()),
+            language: Some("eng, ,DEU".to_string()),
+            date_from: Some(1716300000000),
+            ..Default::default()
+        };
+
+        let filters = query.filters();
+
+        assert_eq!(
+            filters,
+            vec![
+                Filter::Contains("category", FilterValue::Text("bitcoin".to_string())),
+                Filter::In(
+                    "language",
+                    FilterValue::TextList(vec!["eng".to_string(), "deu".to_string()])
+                ),
+                Filter::Eq(
+                    RSS_ITEM_FEED_HOST,
+                    FilterValue::Text("coindesk.com".to_string())
+                ),
+                Filter::Gte("published_timestamp", FilterValue::Int(1716300000000)),
+            ]
+        );
+        assert_eq!(filters[0].condition(1), "$1 = ANY(category)");
+        assert_eq!(filters[3].condition(4), "published_timestamp >= $4");
+        assert!(RssItemsQuery::default().filters().is_empty());
+    }
+
+    #[test]
+    fn test_rss_semantic_search_query_filters() {
+        let query = RssSemanticSearchQuery {
+            q: "central banks buying gold".to_string(),
+            category: Some("markets".to_string()),
+            language: Some("eng".to_string()),
+            date_to: Some(1716300000000),
+            limit: Some(500),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.filters(),
+            vec![
+                Filter::Contains("category", FilterValue::Text("markets".to_string())),
+                Filter::In("language", FilterValue::TextList(vec!["eng".to_string()])),
+                Filter::Lte("published_timestamp", FilterValue::Int(1716300000000)),
+            ]
+        );
+        assert_eq!(query.limit(), RSS_ITEMS_MAX_LIMIT);
+        assert!(query.validate().is_err());
+    }
+
+    #[test]
+    fn test_audit_log_query_filters() {
+        let query = AuditLogQuery {
+            event_type: Some(AuditEventType::SignatureFailure),
+            actor: Some(" ".to_string()),
+            date_to: Some(1716300000000),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.filters(),
+            vec![
+                Filter::Eq(
+                    "event_type",
+                    FilterValue::Text("signature_failure".to_string())
+                ),
+                Filter::Lte("created_at", FilterValue::Int(1716300000000)),
+            ]
+        );
+        assert!(AuditLogQuery::default().filters().is_empty());
+    }
+
+    #[test]
+    fn test_admin_stats() {
+        assert_eq!(AdminStatsQuery::default().days(), ADMIN_STATS_DEFAULT_DAYS);
+        assert_eq!(
+            AdminStatsQuery { days: Some(365) }.days(),
+            ADMIN_STATS_MAX_DAYS
+        );
+        assert_eq!(AdminStatsQuery { days: Some(0) }.days(), 1);
+
+        assert_eq!(
+            FeedDayCountResponse::from(GroupCount {
+                key: "2026-10-16 coindesk.com".to_string(),
+                count: 42,
+            }),
+            FeedDayCountResponse {
+                day: "2026-10-16".to_string(),
+                feed: "coindesk.com".to_string(),
+                count: 42,
+            }
+        );
+    }
+
+    #[test]
+    fn test_purge_rss_items_request_filters() {
+        assert!(PurgeRssItemsRequest::default().filters(0).is_empty());
+        assert!(
+            PurgeRssItemsRequest {
+                feed: Some(" ".to_string()),
+                hashes: Some(Vec::new()),
+                ..Default::default()
+            }
+            .filters(0)
+            .is_empty()
+        );
+
+        let request = PurgeRssItemsRequest {
+            older_than_days: Some(2),
+            feed: Some("WWW.CoinDesk.com".to_string()),
+            hashes: Some(vec!["abc".to_string()]),
+        };
+        assert_eq!(
+            request.filters(200_000_000),
+            vec![
+                Filter::Lte("fetched_timestamp", FilterValue::Int(27_200_000)),
+                Filter::Eq(
+                    RSS_ITEM_FEED_HOST,
+                    FilterValue::Text("coindesk.com".to_string())
+                ),
+                Filter::In("hash", FilterValue::TextList(vec!["abc".to_string()])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rss_items_query_sort() {
+        let query = RssItemsQuery {
+            sort: Some("word_count".to_string()),
+            order: Some(SortDirection::Asc),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            query.sort(),
+            Some(Sort {
+                field: "word_count".to_string(),
+                direction: SortDirection::Asc
+            })
+        );
+        assert_eq!(RssItemsQuery::default().sort(), None);
+    }
+
+    #[test]
+    fn test_user_scoped_ids() {
+        let bookmark = RssBookmark::new(&Scope::personal("alice"), "abc", 1);
+
+        assert_eq!(bookmark.id, RssBookmark::id("alice", "abc"));
+        assert_ne!(bookmark.id, RssBookmark::id("bob", "abc"));
+        assert_ne!(RssBookmark::id("ab", "c"), RssBookmark::id("a", "bc"));
+        assert_eq!(
+            RssSavedSearch::new(&Scope::personal("alice"), "btc", "bitcoin -scam", 2).id,
+            RssSavedSearch::id("alice", "btc")
+        );
+    }
+
+    #[test]
+    fn test_webhook_subscription_new() {
+        let subscription = WebhookSubscription::new(
+            &Scope::personal("alice"),
+            "https://hooks.example.com/news",
+            &[
+                WebhookEventType::Sentiment,
+                WebhookEventType::RssItem,
+                WebhookEventType::Sentiment,
+            ],
+            "top-secret-value",
+            1,
+        );
+
+        assert_eq!(
+            subscription.id,
+            WebhookSubscription::id("alice", "https://hooks.example.com/news")
+        );
+        assert_eq!(subscription.event_types, vec!["rss_item", "sentiment"]);
+    }
 }
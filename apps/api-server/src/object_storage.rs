@@ -0,0 +1,204 @@
+#![allow(dead_code)]
+use crate::config::MinioConfig;
+use anyhow::{Context, Result};
+use aws_sdk_s3::{
+    Client,
+    config::{Builder, Credentials, Region},
+    presigning::PresigningConfig,
+    primitives::ByteStream,
+};
+use shared_states::endpoint_url;
+use std::time::Duration;
+
+/// Longest validity of presigned URLs accepted by S3.
+const MAX_PRESIGN_EXPIRY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// ObjectStorageGateway stores objects such as article snapshots and media in an S3 compatible
+/// bucket, MinIO in development.
+#[derive(Debug, Clone)]
+pub struct ObjectStorageGateway {
+    client: Client,
+    presign_client: Client,
+    bucket: String,
+    presign_expiry: Duration,
+}
+
+impl ObjectStorageGateway {
+    /// Creates a new gateway, no request is made until the first operation.
+    ///
+    /// # Arguments
+    /// * `config` - The MinIO configuration.
+    ///
+    /// # Returns
+    /// A new instance of the gateway.
+    pub fn new(config: &MinioConfig) -> Self {
+        let endpoint = endpoint_url(&config.endpoint, config.use_ssl);
+        let public_endpoint = config
+            .public_endpoint
+            .as_deref()
+            .map(|endpoint| endpoint_url(endpoint, config.use_ssl))
+            .unwrap_or_else(|| endpoint.clone());
+
+        Self {
+            client: client(config, endpoint),
+            presign_client: client(config, public_endpoint),
+            bucket: config.bucket.clone(),
+            presign_expiry: Duration::from_secs(config.presign_expiry_seconds.max(1))
+                .min(MAX_PRESIGN_EXPIRY),
+        }
+    }
+
+    /// Configured validity of presigned URLs.
+    pub fn presign_expiry(&self) -> Duration {
+        self.presign_expiry
+    }
+
+    /// Creates the bucket unless it exists.
+    pub async fn ensure_bucket(&self) -> Result<()> {
+        match self.client.head_bucket().bucket(&self.bucket).send().await {
+            Ok(_) => Ok(()),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => {
+                self.client
+                    .create_bucket()
+                    .bucket(&self.bucket)
+                    .send()
+                    .await
+                    .with_context(|| format!("Cannot create bucket {}", self.bucket))?;
+                tracing::info!("Created bucket {}", self.bucket);
+                Ok(())
+            }
+            Err(e) => Err(e).with_context(|| format!("Cannot access bucket {}", self.bucket)),
+        }
+    }
+
+    /// Stores the object, replacing an object with the same key.
+    ///
+    /// # Arguments
+    /// * `key` - The key of the object.
+    /// * `bytes` - The content of the object.
+    /// * `content_type` - The MIME type served with the object.
+    pub async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(ByteStream::from(bytes))
+            .send()
+            .await
+            .with_context(|| format!("Cannot store object {key}"))?;
+        Ok(())
+    }
+
+    /// Reads the object.
+    ///
+    /// # Returns
+    /// The content of the object, None if it does not exist, or an error otherwise.
+    pub async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let output = match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+        {
+            Ok(output) => output,
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Cannot read object {key}")),
+        };
+        let bytes = output
+            .body
+            .collect()
+            .await
+            .with_context(|| format!("Cannot read object {key}"))?;
+
+        Ok(Some(bytes.into_bytes().to_vec()))
+    }
+
+    /// Deletes the object, deleting a missing object succeeds.
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .with_context(|| format!("Cannot delete object {key}"))?;
+        Ok(())
+    }
+
+    /// Creates a URL the object can be downloaded from without credentials.
+    ///
+    /// # Arguments
+    /// * `key` - The key of the object.
+    /// * `expires_in` - How long the URL is valid for, at most 7 days.
+    ///
+    /// # Returns
+    /// The presigned URL on the public endpoint, or an error otherwise.
+    pub async fn presign_get(&self, key: &str, expires_in: Duration) -> Result<String> {
+        let request = self
+            .presign_client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .presigned(PresigningConfig::expires_in(expires_in)?)
+            .await
+            .with_context(|| format!("Cannot presign object {key}"))?;
+
+        Ok(request.uri().to_string())
+    }
+}
+
+fn client(config: &MinioConfig, endpoint: String) -> Client {
+    let credentials = Credentials::new(
+        &config.access_key,
+        &config.secret_key,
+        None,
+        None,
+        "minio-config",
+    );
+    let s3_config = Builder::new()
+        .behavior_version_latest()
+        .region(Region::new(config.region.clone()))
+        .endpoint_url(endpoint)
+        .credentials_provider(credentials)
+        .force_path_style(true)
+        .build();
+
+    Client::from_conf(s3_config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> MinioConfig {
+        MinioConfig {
+            enabled: true,
+            endpoint: "minio:9000".to_string(),
+            access_key: "minioadmin".to_string(),
+            secret_key: "minioadmin123".to_string(),
+            bucket: "articles".to_string(),
+            region: "us-east-1".to_string(),
+            use_ssl: false,
+            public_endpoint: Some("https://media.example.com/".to_string()),
+            presign_expiry_seconds: 30 * 24 * 60 * 60,
+            media_max_bytes: 1024,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_presign_get_uses_public_endpoint() {
+        let gateway = ObjectStorageGateway::new(&config());
+
+        assert_eq!(gateway.presign_expiry(), MAX_PRESIGN_EXPIRY);
+        let url = gateway
+            .presign_get("media/abc.jpg", Duration::from_secs(600))
+            .await
+            .unwrap();
+
+        assert!(url.starts_with("https://media.example.com/articles/media/abc.jpg?"));
+        assert!(url.contains("X-Amz-Expires=600"));
+    }
+}
@@ -0,0 +1,738 @@
+use crate::{
+    database::{
+        Filter, FilterValue, PostgresStorageGateway, StoreCount, StoreDeleteBulk, StoreInsertBulk,
+        StorePaginateBulkEntities, StoreReadBulkEntities,
+    },
+    impl_count_aggregate, impl_delete_bulk_by_ids, impl_read_bulk_by_ids, impl_read_bulk_multiple,
+    impl_store_bulk,
+    models::{Claims, CreateApiKeyRequest, CreateOrganizationRequest},
+};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{Arguments, Row, postgres::PgArguments, prelude::FromRow};
+use thiserror::Error;
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+const API_KEY_PREFIX: &str = "smk_";
+const API_KEY_BYTES: usize = 32;
+/// Number of leading characters of a key shown to tell keys apart.
+const API_KEY_DISPLAY_LEN: usize = 12;
+/// Audience of the claims of requests authenticated with an API key.
+pub const API_KEY_AUDIENCE: &str = "organization-api-key";
+/// Paths an API key is accepted on, the shared resources of the organization and the items.
+const API_KEY_PATHS: [&str; 2] = ["/api/v1/rss", "/api/v1/webhooks"];
+/// Admin-only paths under [`API_KEY_PATHS`], keys act for their creator and are never admins.
+const API_KEY_EXCLUDED_PATHS: [&str; 1] = ["/api/v1/rss/export"];
+
+/// Error of an organization request the user is not allowed to make.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum OrganizationError {
+    #[error("Not a member of the organization")]
+    NotMember,
+
+    #[error("Only the owner of the organization can do this")]
+    NotOwner,
+
+    #[error("The owner cannot leave the organization")]
+    OwnerRemoval,
+
+    #[error("Invalid wallet: {0}")]
+    InvalidWallet(String),
+}
+
+/// OrganizationRole is the role of a member of an organization.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrganizationRole {
+    /// Creator of the organization, manages its members and API keys.
+    Owner,
+    Member,
+}
+
+impl OrganizationRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Owner => "owner",
+            Self::Member => "member",
+        }
+    }
+}
+
+/// Organization is a team sharing bookmarks, saved searches and webhooks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct Organization {
+    pub id: String,
+    pub name: String,
+    pub owner_id: String,
+    pub created_at: i64,
+}
+
+impl_store_bulk!(
+    Organization,
+    String,
+    "organizations",
+    [id, name, owner_id, created_at],
+    "id",
+);
+
+impl_read_bulk_by_ids!(
+    Organization,
+    String,
+    "organizations",
+    [id, name, owner_id, created_at],
+    "id",
+);
+
+/// OrganizationMember is the membership of a user in an organization.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct OrganizationMember {
+    pub id: String,
+    pub org_id: String,
+    pub user_id: String,
+    /// Role of the member, see [`OrganizationRole`].
+    pub role: String,
+    pub created_at: i64,
+}
+
+impl OrganizationMember {
+    /// Identifier of the membership of the user in the organization.
+    pub fn id(org_id: &str, user_id: &str) -> String {
+        format!("{org_id}:{user_id}")
+    }
+
+    pub fn new(org_id: &str, user_id: &str, role: OrganizationRole, created_at: i64) -> Self {
+        Self {
+            id: Self::id(org_id, user_id),
+            org_id: org_id.to_string(),
+            user_id: user_id.to_string(),
+            role: role.as_str().to_string(),
+            created_at,
+        }
+    }
+
+    pub fn is_owner(&self) -> bool {
+        self.role == OrganizationRole::Owner.as_str()
+    }
+}
+
+impl_store_bulk!(
+    OrganizationMember,
+    String,
+    "organization_members",
+    [id, org_id, user_id, role, created_at],
+    "id",
+);
+
+impl_read_bulk_by_ids!(
+    OrganizationMember,
+    String,
+    "organization_members",
+    [id, org_id, user_id, role, created_at],
+    "id",
+);
+
+impl_read_bulk_multiple!(
+    OrganizationMember,
+    "organization_members",
+    [id, org_id, user_id, role, created_at],
+    "created_at",
+);
+
+impl_count_aggregate!(OrganizationMember, "organization_members",);
+
+impl_delete_bulk_by_ids!(OrganizationMember, String, "organization_members", "id",);
+
+/// OrganizationApiKey is a key services of an organization authenticate with instead of a JWT.
+///
+/// A key acts on behalf of the member that created it and only within the organization, it is
+/// revoked when the member leaves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct OrganizationApiKey {
+    pub id: String,
+    pub org_id: String,
+    pub name: String,
+    /// Leading characters of the key, to tell keys apart.
+    pub prefix: String,
+    /// SHA-256 of the key, the key itself is only returned once.
+    pub key_hash: String,
+    pub created_by: String,
+    pub created_at: i64,
+}
+
+impl OrganizationApiKey {
+    /// Claims of the requests authenticated with the key.
+    pub fn claims(&self) -> Claims {
+        Claims {
+            sub: self.created_by.clone(),
+            user_id: self.created_by.clone(),
+            name: self.name.clone(),
+            exp: 0,
+            iat: self.created_at / 1000,
+            aud: API_KEY_AUDIENCE.to_string(),
+            iss: String::new(),
+            jti: self.id.clone(),
+            org_id: Some(self.org_id.clone()),
+        }
+    }
+}
+
+impl_store_bulk!(
+    OrganizationApiKey,
+    String,
+    "organization_api_keys",
+    [id, org_id, name, prefix, key_hash, created_by, created_at],
+    "id",
+);
+
+impl_read_bulk_by_ids!(
+    OrganizationApiKey,
+    String,
+    "organization_api_keys",
+    [id, org_id, name, prefix, key_hash, created_by, created_at],
+    "id",
+);
+
+impl_read_bulk_multiple!(
+    OrganizationApiKey,
+    "organization_api_keys",
+    [id, org_id, name, prefix, key_hash, created_by, created_at],
+    "created_at",
+);
+
+impl_count_aggregate!(OrganizationApiKey, "organization_api_keys",);
+
+impl_delete_bulk_by_ids!(OrganizationApiKey, String, "organization_api_keys", "id",);
+
+/// Scope is the owner of the resources a request reads and writes, the user alone or an
+/// organization the user is a member of.
+///
+/// A scope of an organization is only built by [`Organizations::scope`] once the membership is
+/// checked, so domain methods taking a scope cannot reach the resources of other organizations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    user_id: String,
+    org_id: Option<String>,
+}
+
+impl Scope {
+    /// Scope of the resources of the user alone.
+    pub fn personal(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            org_id: None,
+        }
+    }
+
+    fn organization(user_id: &str, org_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            org_id: Some(org_id.to_string()),
+        }
+    }
+
+    /// ID of the user making the request.
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// Value of the `org_id` column of the resources of the scope, empty for the user alone.
+    pub fn org_id(&self) -> &str {
+        self.org_id.as_deref().unwrap_or_default()
+    }
+
+    /// Key the identifiers of the resources are derived from, so the same key saved by the user
+    /// and by an organization are different records.
+    pub fn owner(&self) -> String {
+        match &self.org_id {
+            Some(org_id) => format!("org:{org_id}"),
+            None => self.user_id.clone(),
+        }
+    }
+
+    /// Whether the resource of the given creator and organization belongs to the scope.
+    pub fn owns(&self, user_id: &str, org_id: &str) -> bool {
+        match &self.org_id {
+            Some(scope_org_id) => scope_org_id == org_id,
+            None => org_id.is_empty() && self.user_id == user_id,
+        }
+    }
+
+    /// Filters of the resources of the scope.
+    pub fn filters(&self) -> Vec<Filter> {
+        match &self.org_id {
+            Some(org_id) => vec![Filter::Eq("org_id", FilterValue::Text(org_id.clone()))],
+            None => vec![
+                Filter::Eq("user_id", FilterValue::Text(self.user_id.clone())),
+                Filter::Eq("org_id", FilterValue::Text(String::new())),
+            ],
+        }
+    }
+}
+
+/// Whether a request authenticated with an API key may access the path.
+///
+/// # Arguments
+/// * `path` - The path of the request, e.g. `/api/v1/rss/bookmarks`.
+pub fn api_key_allows(path: &str) -> bool {
+    let under = |prefix: &&str| {
+        path.strip_prefix(*prefix)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    };
+    API_KEY_PATHS.iter().any(under) && !API_KEY_EXCLUDED_PATHS.iter().any(under)
+}
+
+fn api_key_hash(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Organizations manages organizations, their members and API keys, and resolves the scope of
+/// requests.
+pub struct Organizations {
+    storage: PostgresStorageGateway,
+}
+
+impl Organizations {
+    /// Creates the organizations.
+    ///
+    /// # Arguments
+    /// * `storage` - The Postgres storage gateway.
+    pub fn new(storage: PostgresStorageGateway) -> Self {
+        Self { storage }
+    }
+
+    /// Resolves the scope of a request.
+    ///
+    /// # Arguments
+    /// * `claims` - The claims of the request, bound to an organization for API keys.
+    /// * `org_id` - The organization the request acts in, None for the user alone.
+    ///
+    /// # Returns
+    /// The scope, an OrganizationError if the user is not a member of the organization, or an
+    /// error if the storage fails.
+    pub async fn scope(&self, claims: &Claims, org_id: Option<&str>) -> Result<Scope> {
+        match (claims.org_id.as_deref(), org_id) {
+            (Some(bound), Some(requested)) if bound != requested => {
+                Err(OrganizationError::NotMember.into())
+            }
+            (Some(bound), _) => Ok(Scope::organization(&claims.sub, bound)),
+            (None, Some(requested)) => {
+                self.member(requested, &claims.sub).await?;
+                Ok(Scope::organization(&claims.sub, requested))
+            }
+            (None, None) => Ok(Scope::personal(&claims.sub)),
+        }
+    }
+
+    /// Creates an organization owned by the user.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the user.
+    /// * `request` - The name of the organization, surrounding whitespace is ignored.
+    ///
+    /// # Returns
+    /// The organization, or an error if the request is invalid or the storage fails.
+    pub async fn create(
+        &self,
+        user_id: &str,
+        request: &CreateOrganizationRequest,
+    ) -> Result<Organization> {
+        let request = CreateOrganizationRequest {
+            name: request.name.trim().to_string(),
+        };
+        request.validate()?;
+
+        let now = Utc::now().timestamp_millis();
+        let organization = Organization {
+            id: Uuid::new_v4().to_string(),
+            name: request.name,
+            owner_id: user_id.to_string(),
+            created_at: now,
+        };
+        self.storage
+            .insert_bulk(std::slice::from_ref(&organization))
+            .await?;
+        self.storage
+            .insert_bulk(&[OrganizationMember::new(
+                &organization.id,
+                user_id,
+                OrganizationRole::Owner,
+                now,
+            )])
+            .await?;
+
+        Ok(organization)
+    }
+
+    /// Reads a page of the organizations of the user, most recently joined first.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the user.
+    /// * `limit` - The number of organizations per page.
+    /// * `offset` - The number of organizations to skip.
+    ///
+    /// # Returns
+    /// The organizations with the membership of the user, or an error if the storage fails.
+    pub async fn organizations(
+        &self,
+        user_id: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<(OrganizationMember, Organization)>> {
+        let memberships: Vec<OrganizationMember> = self
+            .storage
+            .filter_paginate(&member_filter(user_id), None, limit, offset)
+            .await?;
+        if memberships.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let ids: Vec<String> = memberships.iter().map(|m| m.org_id.clone()).collect();
+        let mut organizations: Vec<Organization> = self.storage.read_bulk_by_ids(&ids).await?;
+
+        Ok(memberships
+            .into_iter()
+            .filter_map(|membership| {
+                let position = organizations
+                    .iter()
+                    .position(|organization| organization.id == membership.org_id)?;
+                Some((membership, organizations.swap_remove(position)))
+            })
+            .collect())
+    }
+
+    /// Counts the organizations of the user.
+    pub async fn count_organizations(&self, user_id: &str) -> Result<i64> {
+        StoreCount::<OrganizationMember>::count(&self.storage, &member_filter(user_id)).await
+    }
+
+    /// Reads a page of the members of the organization, newest first.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the requesting user, a member of the organization.
+    /// * `org_id` - The ID of the organization.
+    /// * `limit` - The number of members per page.
+    /// * `offset` - The number of members to skip.
+    ///
+    /// # Returns
+    /// The members and their total, an OrganizationError if the user is not a member, or an error
+    /// if the storage fails.
+    pub async fn members(
+        &self,
+        user_id: &str,
+        org_id: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<OrganizationMember>, i64)> {
+        self.member(org_id, user_id).await?;
+        let filters = org_filter(org_id);
+        tokio::try_join!(
+            self.storage.filter_paginate(&filters, None, limit, offset),
+            StoreCount::<OrganizationMember>::count(&self.storage, &filters)
+        )
+    }
+
+    /// Adds a member to the organization, a member already in it keeps the role.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the requesting user, the owner of the organization.
+    /// * `org_id` - The ID of the organization.
+    /// * `member_id` - The base58 wallet of the new member.
+    ///
+    /// # Returns
+    /// The membership, an OrganizationError if the user is not the owner or the wallet is
+    /// invalid, or an error if the storage fails.
+    pub async fn add_member(
+        &self,
+        user_id: &str,
+        org_id: &str,
+        member_id: &str,
+    ) -> Result<OrganizationMember> {
+        self.owner(org_id, user_id).await?;
+        let member_id = member_id.trim();
+        if bs58::decode(member_id)
+            .into_vec()
+            .map_or(true, |bytes| bytes.len() != 32)
+        {
+            return Err(OrganizationError::InvalidWallet(member_id.to_string()).into());
+        }
+
+        if let Ok(existing) = self.member(org_id, member_id).await {
+            return Ok(existing);
+        }
+        let member = OrganizationMember::new(
+            org_id,
+            member_id,
+            OrganizationRole::Member,
+            Utc::now().timestamp_millis(),
+        );
+        self.storage
+            .insert_bulk(std::slice::from_ref(&member))
+            .await?;
+
+        Ok(member)
+    }
+
+    /// Removes a member from the organization and revokes the API keys the member created.
+    ///
+    /// The owner removes any other member, a member may only leave.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the requesting user.
+    /// * `org_id` - The ID of the organization.
+    /// * `member_id` - The ID of the removed member.
+    ///
+    /// # Returns
+    /// True if the user was a member, an OrganizationError if the removal is not allowed, or an
+    /// error if the storage fails.
+    pub async fn remove_member(
+        &self,
+        user_id: &str,
+        org_id: &str,
+        member_id: &str,
+    ) -> Result<bool> {
+        let requester = self.member(org_id, user_id).await?;
+        if user_id != member_id && !requester.is_owner() {
+            return Err(OrganizationError::NotOwner.into());
+        }
+        let members: Vec<OrganizationMember> = self
+            .storage
+            .read_bulk_by_ids(&[OrganizationMember::id(org_id, member_id)])
+            .await?;
+        let Some(member) = members.into_iter().next() else {
+            return Ok(false);
+        };
+        if member.is_owner() {
+            return Err(OrganizationError::OwnerRemoval.into());
+        }
+
+        let mut filters = org_filter(org_id);
+        filters.push(Filter::Eq(
+            "created_by",
+            FilterValue::Text(member_id.to_string()),
+        ));
+        let keys: Vec<OrganizationApiKey> = self
+            .storage
+            .filter_paginate(&filters, None, i64::MAX, 0)
+            .await?;
+        if !keys.is_empty() {
+            let ids: Vec<String> = keys.into_iter().map(|key| key.id).collect();
+            StoreDeleteBulk::<OrganizationApiKey, String>::delete_bulk(&self.storage, &ids).await?;
+        }
+        let deleted =
+            StoreDeleteBulk::<OrganizationMember, String>::delete_bulk(&self.storage, &[member.id])
+                .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Creates an API key of the organization.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the requesting user, the owner of the organization.
+    /// * `org_id` - The ID of the organization.
+    /// * `request` - The name of the key, surrounding whitespace is ignored.
+    ///
+    /// # Returns
+    /// The key record and the key itself, an OrganizationError if the user is not the owner, or an
+    /// error if the request is invalid or the storage fails.
+    pub async fn create_api_key(
+        &self,
+        user_id: &str,
+        org_id: &str,
+        request: &CreateApiKeyRequest,
+    ) -> Result<(OrganizationApiKey, String)> {
+        let request = CreateApiKeyRequest {
+            name: request.name.trim().to_string(),
+        };
+        request.validate()?;
+        self.owner(org_id, user_id).await?;
+        let mut bytes = [0u8; API_KEY_BYTES];
+        rand::rng().fill_bytes(&mut bytes);
+        let key = format!("{API_KEY_PREFIX}{}", bs58::encode(bytes).into_string());
+        let api_key = OrganizationApiKey {
+            id: Uuid::new_v4().to_string(),
+            org_id: org_id.to_string(),
+            name: request.name,
+            prefix: key.chars().take(API_KEY_DISPLAY_LEN).collect(),
+            key_hash: api_key_hash(&key),
+            created_by: user_id.to_string(),
+            created_at: Utc::now().timestamp_millis(),
+        };
+        self.storage
+            .insert_bulk(std::slice::from_ref(&api_key))
+            .await?;
+
+        Ok((api_key, key))
+    }
+
+    /// Reads a page of the API keys of the organization, newest first.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the requesting user, the owner of the organization.
+    /// * `org_id` - The ID of the organization.
+    /// * `limit` - The number of keys per page.
+    /// * `offset` - The number of keys to skip.
+    ///
+    /// # Returns
+    /// The keys and their total, an OrganizationError if the user is not the owner, or an error if
+    /// the storage fails.
+    pub async fn api_keys(
+        &self,
+        user_id: &str,
+        org_id: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<OrganizationApiKey>, i64)> {
+        self.owner(org_id, user_id).await?;
+        let filters = org_filter(org_id);
+        tokio::try_join!(
+            self.storage.filter_paginate(&filters, None, limit, offset),
+            StoreCount::<OrganizationApiKey>::count(&self.storage, &filters)
+        )
+    }
+
+    /// Revokes an API key of the organization.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the requesting user, the owner of the organization.
+    /// * `org_id` - The ID of the organization.
+    /// * `id` - The ID of the key.
+    ///
+    /// # Returns
+    /// True if the organization had the key, an OrganizationError if the user is not the owner, or
+    /// an error if the storage fails.
+    pub async fn revoke_api_key(&self, user_id: &str, org_id: &str, id: &str) -> Result<bool> {
+        self.owner(org_id, user_id).await?;
+        let keys: Vec<OrganizationApiKey> =
+            self.storage.read_bulk_by_ids(&[id.to_string()]).await?;
+        if !keys.iter().any(|key| key.org_id == org_id) {
+            return Ok(false);
+        }
+
+        let deleted = StoreDeleteBulk::<OrganizationApiKey, String>::delete_bulk(
+            &self.storage,
+            &[id.to_string()],
+        )
+        .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Reads the API key matching the key sent by a client.
+    ///
+    /// # Arguments
+    /// * `key` - The API key.
+    ///
+    /// # Returns
+    /// The key record, None if the key is unknown or revoked, or an error if the storage fails.
+    pub async fn authenticate(&self, key: &str) -> Result<Option<OrganizationApiKey>> {
+        if !key.starts_with(API_KEY_PREFIX) {
+            return Ok(None);
+        }
+        let keys: Vec<OrganizationApiKey> = self
+            .storage
+            .filter_paginate(
+                &[Filter::Eq("key_hash", FilterValue::Text(api_key_hash(key)))],
+                None,
+                1,
+                0,
+            )
+            .await?;
+        Ok(keys.into_iter().next())
+    }
+
+    async fn member(&self, org_id: &str, user_id: &str) -> Result<OrganizationMember> {
+        let members: Vec<OrganizationMember> = self
+            .storage
+            .read_bulk_by_ids(&[OrganizationMember::id(org_id, user_id)])
+            .await?;
+        Ok(members
+            .into_iter()
+            .next()
+            .ok_or(OrganizationError::NotMember)?)
+    }
+
+    async fn owner(&self, org_id: &str, user_id: &str) -> Result<OrganizationMember> {
+        let member = self.member(org_id, user_id).await?;
+        if !member.is_owner() {
+            return Err(OrganizationError::NotOwner.into());
+        }
+        Ok(member)
+    }
+}
+
+fn member_filter(user_id: &str) -> Vec<Filter> {
+    vec![Filter::Eq(
+        "user_id",
+        FilterValue::Text(user_id.to_string()),
+    )]
+}
+
+fn org_filter(org_id: &str) -> Vec<Filter> {
+    vec![Filter::Eq("org_id", FilterValue::Text(org_id.to_string()))]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope() {
+        let personal = Scope::personal("alice");
+        assert_eq!(personal.owner(), "alice");
+        assert_eq!(personal.org_id(), "");
+        assert!(personal.owns("alice", ""));
+        assert!(!personal.owns("alice", "acme"));
+        assert!(!personal.owns("bob", ""));
+        assert_eq!(
+            personal.filters(),
+            vec![
+                Filter::Eq("user_id", FilterValue::Text("alice".to_string())),
+                Filter::Eq("org_id", FilterValue::Text(String::new())),
+            ]
+        );
+
+        let shared = Scope::organization("alice", "acme");
+        assert_eq!(shared.owner(), "org:acme");
+        assert_eq!(shared.org_id(), "acme");
+        assert!(shared.owns("bob", "acme"));
+        assert!(!shared.owns("alice", ""));
+        assert_eq!(
+            shared.filters(),
+            vec![Filter::Eq("org_id", FilterValue::Text("acme".to_string()))]
+        );
+    }
+
+    #[test]
+    fn test_api_keys() {
+        assert!(api_key_allows("/api/v1/rss/bookmarks"));
+        assert!(api_key_allows("/api/v1/webhooks"));
+        assert!(!api_key_allows("/api/v1/rssfeeds"));
+        assert!(!api_key_allows("/api/v1/auth/sessions"));
+        assert!(!api_key_allows("/api/v1/organizations"));
+        assert!(!api_key_allows("/api/v1/rss/export"));
+
+        assert_eq!(api_key_hash("smk_a"), api_key_hash("smk_a"));
+        assert_ne!(api_key_hash("smk_a"), api_key_hash("smk_b"));
+
+        let key = OrganizationApiKey {
+            id: "key".to_string(),
+            org_id: "acme".to_string(),
+            name: "ci".to_string(),
+            prefix: "smk_abcdefgh".to_string(),
+            key_hash: api_key_hash("smk_abcdefgh"),
+            created_by: "alice".to_string(),
+            created_at: 5_000,
+        };
+        let claims = key.claims();
+        assert_eq!(claims.sub, "alice");
+        assert_eq!(claims.org_id.as_deref(), Some("acme"));
+        assert_eq!(
+            OrganizationMember::new("acme", "bob", OrganizationRole::Member, 1).id,
+            "acme:bob"
+        );
+    }
+}
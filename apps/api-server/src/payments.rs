@@ -0,0 +1,472 @@
+use crate::{
+    config::PaymentsConfig,
+    database::{PostgresStorageGateway, StoreInsertBulk, StoreReadBulkEntities},
+    impl_read_bulk_by_ids, impl_store_bulk,
+    tiers::{Tier, tier_key},
+};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use redis_middleware::RedisMiddleware;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::{Arguments, Row, postgres::PgArguments, prelude::FromRow};
+use std::time::Duration;
+use thiserror::Error;
+
+const SYSTEM_PROGRAM: &str = "system";
+const MEMO_PROGRAM: &str = "spl-memo";
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+const SIGNATURE_LEN: usize = 64;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const REDEMPTION_KEY_PREFIX: &str = "payment-redemption:";
+/// Time a redemption is claimed for while it is verified, long enough for the RPC call.
+const REDEMPTION_CLAIM_SECONDS: u64 = 60;
+
+/// Error of a payment that cannot be redeemed.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PaymentError {
+    #[error("Payments are disabled")]
+    Disabled,
+
+    #[error("Invalid transaction signature")]
+    InvalidSignature,
+
+    #[error("Transaction not found or not finalized yet")]
+    TransactionNotFound,
+
+    #[error("Transaction failed on-chain")]
+    TransactionFailed,
+
+    #[error("Transaction does not pay for the wallet: {0}")]
+    NotAPayment(String),
+
+    #[error("Transaction already redeemed")]
+    AlreadyRedeemed,
+
+    #[error("Solana RPC failure: {0}")]
+    Rpc(String),
+}
+
+/// SolanaPayment is a transaction redeemed for a tier, each transaction is redeemed once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct SolanaPayment {
+    /// Base58 signature of the transaction.
+    pub signature: String,
+    pub user_id: String,
+    pub lamports: i64,
+    pub tier: String,
+    /// Number of tier periods the payment was redeemed for.
+    pub periods: i64,
+    pub created_at: i64,
+}
+
+impl_store_bulk!(
+    SolanaPayment,
+    String,
+    "solana_payments",
+    [signature, user_id, lamports, tier, periods, created_at],
+    "signature",
+);
+
+impl_read_bulk_by_ids!(
+    SolanaPayment,
+    String,
+    "solana_payments",
+    [signature, user_id, lamports, tier, periods, created_at],
+    "signature",
+);
+
+/// UserSubscription is the paid tier of a user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct UserSubscription {
+    pub user_id: String,
+    pub tier: String,
+    /// Time the tier expires at in Unix milliseconds.
+    pub expires_at: i64,
+    pub updated_at: i64,
+}
+
+impl_store_bulk!(
+    UserSubscription,
+    String,
+    "user_subscriptions",
+    [user_id, tier, expires_at, updated_at],
+    "user_id",
+);
+
+impl_read_bulk_by_ids!(
+    UserSubscription,
+    String,
+    "user_subscriptions",
+    [user_id, tier, expires_at, updated_at],
+    "user_id",
+);
+
+impl UserSubscription {
+    /// Extends the subscription by the given number of periods, from now if it already expired.
+    ///
+    /// # Arguments
+    /// * `current` - The current subscription of the user, if any.
+    /// * `user_id` - The ID of the user.
+    /// * `periods` - The number of paid periods.
+    /// * `period_days` - The length of a period in days.
+    /// * `now` - The current time in Unix milliseconds.
+    pub fn extend(
+        current: Option<Self>,
+        user_id: &str,
+        periods: i64,
+        period_days: i64,
+        now: i64,
+    ) -> Self {
+        let start = current
+            .map(|subscription| subscription.expires_at)
+            .unwrap_or(now)
+            .max(now);
+        Self {
+            user_id: user_id.to_string(),
+            tier: Tier::Pro.as_str().to_string(),
+            expires_at: start + periods * period_days * DAY_MS,
+            updated_at: now,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    result: Option<ParsedTransaction>,
+    error: Option<Value>,
+}
+
+/// Transaction returned by `getTransaction` with the `jsonParsed` encoding, only the verified
+/// fields are read.
+#[derive(Debug, Deserialize)]
+struct ParsedTransaction {
+    meta: Option<TransactionMeta>,
+    transaction: TransactionBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransactionMeta {
+    err: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransactionBody {
+    message: TransactionMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct TransactionMessage {
+    instructions: Vec<ParsedInstruction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParsedInstruction {
+    #[serde(default)]
+    program: Option<String>,
+    #[serde(default)]
+    parsed: Option<Value>,
+}
+
+/// SolanaPayments upgrades users to the pro tier for SOL transfers verified on-chain.
+///
+/// A payment is a finalized transaction transferring lamports to the recipient wallet with a memo
+/// holding the wallet of the user, so a payment is bound to a single user whoever sent it.
+pub struct SolanaPayments {
+    storage: PostgresStorageGateway,
+    redis: RedisMiddleware,
+    client: Client,
+    config: PaymentsConfig,
+}
+
+impl SolanaPayments {
+    /// Creates new Solana payments.
+    ///
+    /// # Arguments
+    /// * `storage` - The Postgres storage gateway.
+    /// * `redis` - The Redis client redemptions are claimed in.
+    /// * `config` - The payments configuration.
+    pub fn new(
+        storage: PostgresStorageGateway,
+        redis: RedisMiddleware,
+        config: PaymentsConfig,
+    ) -> Result<Self> {
+        Ok(Self {
+            storage,
+            redis,
+            client: Client::builder().timeout(RPC_TIMEOUT).build()?,
+            config,
+        })
+    }
+
+    /// Redeems a payment transaction, extending the pro tier of the user by the paid periods.
+    ///
+    /// # Arguments
+    /// * `user_id` - The base58 wallet of the user, expected in the memo of the transaction.
+    /// * `signature` - The base58 signature of the transaction.
+    ///
+    /// # Returns
+    /// The extended subscription, a PaymentError if the payment cannot be redeemed, or an error
+    /// if the storage fails.
+    pub async fn redeem(&self, user_id: &str, signature: &str) -> Result<UserSubscription> {
+        if !self.config.enabled {
+            return Err(PaymentError::Disabled.into());
+        }
+        if bs58::decode(signature)
+            .into_vec()
+            .map_or(true, |bytes| bytes.len() != SIGNATURE_LEN)
+        {
+            return Err(PaymentError::InvalidSignature.into());
+        }
+
+        let claim_key = format!("{REDEMPTION_KEY_PREFIX}{signature}");
+        if !self
+            .redis
+            .store_if_absent_with_ttl(&claim_key, user_id, REDEMPTION_CLAIM_SECONDS)
+            .await?
+        {
+            return Err(PaymentError::AlreadyRedeemed.into());
+        }
+        let result = self.redeem_claimed(user_id, signature).await;
+        if let Err(e) = self.redis.delete(&claim_key).await {
+            tracing::warn!("Failed to release payment redemption claim: {e}");
+        }
+
+        result
+    }
+
+    async fn redeem_claimed(&self, user_id: &str, signature: &str) -> Result<UserSubscription> {
+        let payments: Vec<SolanaPayment> = self
+            .storage
+            .read_bulk_by_ids(&[signature.to_string()])
+            .await?;
+        if !payments.is_empty() {
+            return Err(PaymentError::AlreadyRedeemed.into());
+        }
+
+        let transaction = self
+            .transaction(signature)
+            .await?
+            .ok_or(PaymentError::TransactionNotFound)?;
+        let lamports = verify_payment(&transaction, user_id, &self.config.recipient_wallet)?;
+        let periods = (lamports / self.config.pro_price_lamports) as i64;
+        if periods == 0 {
+            return Err(PaymentError::NotAPayment(format!(
+                "paid {lamports} lamports, the price is {} lamports",
+                self.config.pro_price_lamports
+            ))
+            .into());
+        }
+
+        let now = Utc::now().timestamp_millis();
+        let current: Vec<UserSubscription> = self
+            .storage
+            .read_bulk_by_ids(&[user_id.to_string()])
+            .await?;
+        let subscription = UserSubscription::extend(
+            current.into_iter().next(),
+            user_id,
+            periods,
+            self.config.pro_period_days,
+            now,
+        );
+        self.storage
+            .insert_bulk(&[SolanaPayment {
+                signature: signature.to_string(),
+                user_id: user_id.to_string(),
+                lamports: lamports as i64,
+                tier: Tier::Pro.as_str().to_string(),
+                periods,
+                created_at: now,
+            }])
+            .await?;
+        self.storage
+            .insert_bulk(std::slice::from_ref(&subscription))
+            .await?;
+        if let Err(e) = self.redis.delete(&tier_key(user_id)).await {
+            tracing::warn!("Failed to invalidate cached tier: {e}");
+        }
+
+        Ok(subscription)
+    }
+
+    /// Reads the finalized transaction, None if it is unknown or not finalized yet.
+    async fn transaction(
+        &self,
+        signature: &str,
+    ) -> Result<Option<ParsedTransaction>, PaymentError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getTransaction",
+            "params": [
+                signature,
+                {
+                    "encoding": "jsonParsed",
+                    "commitment": "finalized",
+                    "maxSupportedTransactionVersion": 0
+                }
+            ]
+        });
+        let response: RpcResponse = self
+            .client
+            .post(&self.config.solana_rpc_url)
+            .json(&request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| PaymentError::Rpc(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| PaymentError::Rpc(e.to_string()))?;
+        if let Some(error) = response.error {
+            return Err(PaymentError::Rpc(error.to_string()));
+        }
+
+        Ok(response.result)
+    }
+}
+
+/// Verifies the transaction pays for the user.
+///
+/// # Arguments
+/// * `transaction` - The finalized transaction.
+/// * `user_id` - The wallet of the user, expected as the memo.
+/// * `recipient` - The wallet the payment has to be transferred to.
+///
+/// # Returns
+/// The lamports transferred to the recipient.
+fn verify_payment(
+    transaction: &ParsedTransaction,
+    user_id: &str,
+    recipient: &str,
+) -> Result<u64, PaymentError> {
+    if transaction
+        .meta
+        .as_ref()
+        .is_none_or(|meta| meta.err.is_some())
+    {
+        return Err(PaymentError::TransactionFailed);
+    }
+
+    let instructions = &transaction.transaction.message.instructions;
+    let memo_matches = instructions.iter().any(|instruction| {
+        instruction.program.as_deref() == Some(MEMO_PROGRAM)
+            && instruction
+                .parsed
+                .as_ref()
+                .and_then(Value::as_str)
+                .is_some_and(|memo| memo.trim() == user_id)
+    });
+    if !memo_matches {
+        return Err(PaymentError::NotAPayment(
+            "memo does not hold the wallet".to_string(),
+        ));
+    }
+
+    let lamports: u64 = instructions
+        .iter()
+        .filter(|instruction| instruction.program.as_deref() == Some(SYSTEM_PROGRAM))
+        .filter_map(|instruction| instruction.parsed.as_ref())
+        .filter(|parsed| parsed["type"] == "transfer" && parsed["info"]["destination"] == recipient)
+        .filter_map(|parsed| parsed["info"]["lamports"].as_u64())
+        .sum();
+    if lamports == 0 {
+        return Err(PaymentError::NotAPayment(
+            "no transfer to the recipient".to_string(),
+        ));
+    }
+
+    Ok(lamports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECIPIENT: &str = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+    const WALLET: &str = "4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T";
+
+    fn transaction(memo: &str, destination: &str, err: Value) -> ParsedTransaction {
+        serde_json::from_value(json!({
+            "slot": 1,
+            "meta": { "err": err, "fee": 5000 },
+            "transaction": {
+                "signatures": ["sig"],
+                "message": {
+                    "instructions": [
+                        {
+                            "program": "system",
+                            "programId": "11111111111111111111111111111111",
+                            "parsed": {
+                                "type": "transfer",
+                                "info": {
+                                    "source": WALLET,
+                                    "destination": destination,
+                                    "lamports": 250_000_000u64
+                                }
+                            }
+                        },
+                        {
+                            "program": "spl-memo",
+                            "programId": "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr",
+                            "parsed": memo
+                        }
+                    ]
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_verify_payment() {
+        assert_eq!(
+            verify_payment(
+                &transaction(WALLET, RECIPIENT, Value::Null),
+                WALLET,
+                RECIPIENT
+            ),
+            Ok(250_000_000)
+        );
+        assert!(matches!(
+            verify_payment(
+                &transaction(RECIPIENT, RECIPIENT, Value::Null),
+                WALLET,
+                RECIPIENT
+            ),
+            Err(PaymentError::NotAPayment(_))
+        ));
+        assert!(matches!(
+            verify_payment(&transaction(WALLET, WALLET, Value::Null), WALLET, RECIPIENT),
+            Err(PaymentError::NotAPayment(_))
+        ));
+        assert_eq!(
+            verify_payment(
+                &transaction(
+                    WALLET,
+                    RECIPIENT,
+                    json!({ "InstructionError": [0, "Custom"] })
+                ),
+                WALLET,
+                RECIPIENT
+            ),
+            Err(PaymentError::TransactionFailed)
+        );
+    }
+
+    #[test]
+    fn test_extend_subscription() {
+        let now = 1_000 * DAY_MS;
+        let subscription = UserSubscription::extend(None, WALLET, 2, 30, now);
+        assert_eq!(subscription.tier, "pro");
+        assert_eq!(subscription.expires_at, now + 60 * DAY_MS);
+
+        let extended = UserSubscription::extend(Some(subscription), WALLET, 1, 30, now + DAY_MS);
+        assert_eq!(extended.expires_at, now + 90 * DAY_MS);
+
+        let expired = UserSubscription::extend(Some(extended), WALLET, 1, 30, now + 100 * DAY_MS);
+        assert_eq!(expired.expires_at, now + 130 * DAY_MS);
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{errors::ApiError, middleware_v1::RequestId, telemetry::Metrics};
+use actix_web::{
+    HttpRequest, HttpResponse, ResponseError,
+    http::{
+        StatusCode,
+        header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH},
+    },
+};
+use anyhow::Result;
+use redis_middleware::RedisMiddleware;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::future::Future;
+
+/// Key of the counter bumped on invalidation, cached entries are keyed by its value.
+const GENERATION_KEY: &str = "response-cache:generation";
+
+/// ResponseCache caches JSON bodies of hot GET responses in Redis and answers conditional requests
+/// with `304 Not Modified` based on content ETags.
+///
+/// Entries are keyed by the cache generation, invalidation bumps the generation so every replica
+/// stops reading the old entries, which then expire on their own.
+pub struct ResponseCache {
+    redis: Option<RedisMiddleware>,
+    ttl_seconds: u64,
+}
+
+impl ResponseCache {
+    /// Creates a cache storing responses in Redis.
+    ///
+    /// # Arguments
+    /// * `redis` - The Redis client.
+    /// * `ttl_seconds` - Time cached responses are served for.
+    pub fn new(redis: RedisMiddleware, ttl_seconds: u64) -> Self {
+        Self {
+            redis: Some(redis),
+            ttl_seconds,
+        }
+    }
+
+    /// Creates a cache that only sets ETags and answers conditional requests.
+    pub fn disabled() -> Self {
+        Self {
+            redis: None,
+            ttl_seconds: 0,
+        }
+    }
+
+    /// Serves the response from the cache or builds and caches it.
+    ///
+    /// Cache failures are logged and the response is built as if it was not cached.
+    ///
+    /// # Arguments
+    /// * `req` - The request, its path and query identify the response.
+    /// * `metrics` - Metrics the cache hits and misses are recorded in.
+    /// * `name` - Name of the cached response in metrics.
+    /// * `request_id` - ID of the request returned in errors.
+    /// * `build` - Builds the body, or the error that is returned without caching.
+    pub async fn respond<T, F, Fut>(
+        &self,
+        req: &HttpRequest,
+        metrics: &Metrics,
+        name: &str,
+        request_id: &RequestId,
+        build: F,
+    ) -> HttpResponse
+    where
+        T: Serialize,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, ApiError>>,
+    {
+        let key = self.key(req).await;
+        if let (Some(redis), Some(key)) = (&self.redis, &key) {
+            match redis.retrieve(key).await {
+                Ok(Some(body)) => {
+                    metrics.record_cache_hit(name);
+                    return conditional_response(req, body);
+                }
+                Ok(None) => metrics.record_cache_miss(name),
+                Err(e) => tracing::warn!("Failed to read cached response: {e}"),
+            }
+        }
+
+        let body = match build().await.map(|value| serde_json::to_string(&value)) {
+            Ok(Ok(body)) => body,
+            Ok(Err(e)) => {
+                tracing::error!("Failed to serialize response: {e}");
+                return ApiError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "serialization_failed",
+                    "Failed to serialize response.",
+                    request_id,
+                )
+                .error_response();
+            }
+            Err(err) => return err.error_response(),
+        };
+
+        if let (Some(redis), Some(key)) = (&self.redis, &key)
+            && let Err(e) = redis.store_with_ttl(key, &body, self.ttl_seconds).await
+        {
+            tracing::warn!("Failed to cache response: {e}");
+        }
+
+        conditional_response(req, body)
+    }
+
+    /// Drops all cached responses.
+    pub async fn invalidate(&self) -> Result<()> {
+        if let Some(redis) = &self.redis {
+            redis.increment(GENERATION_KEY).await?;
+        }
+        Ok(())
+    }
+
+    async fn key(&self, req: &HttpRequest) -> Option<String> {
+        let redis = self.redis.as_ref()?;
+        let generation = match redis.retrieve(GENERATION_KEY).await {
+            Ok(generation) => generation.unwrap_or_else(|| "0".to_string()),
+            Err(e) => {
+                tracing::warn!("Failed to read response cache generation: {e}");
+                return None;
+            }
+        };
+        let target = req
+            .uri()
+            .path_and_query()
+            .map(|target| target.as_str())
+            .unwrap_or_else(|| req.path());
+
+        Some(format!(
+            "response-cache:{generation}:{}",
+            hex::encode(Sha256::digest(target.as_bytes()))
+        ))
+    }
+}
+
+/// Strong ETag of the body.
+fn etag(body: &str) -> String {
+    format!(
+        "\"{}\"",
+        &hex::encode(Sha256::digest(body.as_bytes()))[..32]
+    )
+}
+
+/// Checks the `If-None-Match` header value lists the ETag, weak comparison as in RFC 9110.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == etag
+    })
+}
+
+fn conditional_response(req: &HttpRequest, body: String) -> HttpResponse {
+    let etag = etag(&body);
+    let not_modified = req
+        .headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| etag_matches(value, &etag));
+
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header((ETAG, etag))
+            .insert_header((CACHE_CONTROL, "no-cache"))
+            .finish();
+    }
+
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .insert_header((ETAG, etag))
+        .insert_header((CACHE_CONTROL, "no-cache"))
+        .body(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{body::to_bytes, test::TestRequest};
+
+    #[test]
+    fn test_etag_matches() {
+        let etag = etag(r#"{"items":[]}"#);
+
+        assert_eq!(etag.len(), 34);
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(&format!("\"other\", W/{etag}"), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"other\"", &etag));
+    }
+
+    #[actix_web::test]
+    async fn test_respond_with_etag() {
+        let cache = ResponseCache::disabled();
+        let metrics = Metrics::new().unwrap();
+        let request_id = RequestId::generate();
+        let body = serde_json::json!({ "items": [], "total": 0 });
+
+        let req = TestRequest::get()
+            .uri("/api/v1/rss/items")
+            .to_http_request();
+        let res = cache
+            .respond(&req, &metrics, "rss_items", &request_id, || async {
+                Ok(body.clone())
+            })
+            .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let tag = res
+            .headers()
+            .get(ETAG)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            to_bytes(res.into_body()).await.unwrap(),
+            serde_json::to_string(&body).unwrap()
+        );
+
+        let req = TestRequest::get()
+            .uri("/api/v1/rss/items")
+            .insert_header((IF_NONE_MATCH, tag.as_str()))
+            .to_http_request();
+        let res = cache
+            .respond(&req, &metrics, "rss_items", &request_id, || async {
+                Ok(body.clone())
+            })
+            .await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let res = cache
+            .respond(&req, &metrics, "rss_items", &request_id, || async {
+                Err::<serde_json::Value, _>(ApiError::bad_request(
+                    "invalid_query",
+                    "Invalid sort.",
+                    &request_id,
+                ))
+            })
+            .await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
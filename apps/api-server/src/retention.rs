@@ -0,0 +1,114 @@
+use crate::{
+    config::RetentionConfig,
+    database::{Filter, FilterValue, PostgresStorageGateway, StoreDeleteBulk, StoreSoftDelete},
+    media::read_items_media,
+    models::{RssItemEmbedding, RssItemSentiment},
+    object_storage::ObjectStorageGateway,
+};
+use anyhow::Result;
+use chrono::Utc;
+use shared_states::RssItem;
+use std::time::Duration;
+use tokio::time::interval;
+
+const DAY_MS: i64 = 86_400_000;
+/// Number of deleted items purged at once.
+const PURGE_BATCH_SIZE: i64 = 500;
+
+/// RetentionPolicy keeps the RSS items table from growing forever.
+///
+/// Items fetched longer ago than the retention period are soft deleted, so they are no longer
+/// served. Soft deleted items, whether expired or deleted by an admin, are purged from storage with
+/// their sentiments, embeddings and archived media once the grace period passes.
+pub struct RetentionPolicy {
+    storage: PostgresStorageGateway,
+    objects: Option<ObjectStorageGateway>,
+    config: RetentionConfig,
+}
+
+impl RetentionPolicy {
+    /// Creates the retention policy.
+    ///
+    /// # Arguments
+    /// * `storage` - The Postgres storage gateway.
+    /// * `objects` - The object storage the media of the items is archived in, if enabled.
+    /// * `config` - The retention configuration.
+    pub fn new(
+        storage: PostgresStorageGateway,
+        objects: Option<ObjectStorageGateway>,
+        config: RetentionConfig,
+    ) -> Self {
+        Self {
+            storage,
+            objects,
+            config,
+        }
+    }
+
+    /// Run the policy every configured interval.
+    pub async fn run(&self) -> Result<()> {
+        let mut ticker = interval(Duration::from_secs(self.config.interval_seconds));
+        loop {
+            ticker.tick().await;
+            let now = Utc::now().timestamp_millis();
+            match self.expire(now).await {
+                Ok(0) => (),
+                Ok(expired) => tracing::info!("Deleted {expired} expired RSS items"),
+                Err(e) => tracing::error!("Failed to delete expired RSS items: {e}"),
+            }
+            match self.purge(now).await {
+                Ok(0) => (),
+                Ok(purged) => tracing::info!("Purged {purged} deleted RSS items"),
+                Err(e) => tracing::error!("Failed to purge deleted RSS items: {e}"),
+            }
+        }
+    }
+
+    /// Soft deletes the items fetched before the retention period.
+    async fn expire(&self, now: i64) -> Result<u64> {
+        let filters = [Filter::Lte(
+            "fetched_timestamp",
+            FilterValue::Int(now - self.config.rss_item_days * DAY_MS),
+        )];
+        StoreSoftDelete::<RssItem>::soft_delete(&self.storage, &filters, now).await
+    }
+
+    /// Removes the items deleted before the grace period from storage.
+    async fn purge(&self, now: i64) -> Result<usize> {
+        let deleted_before = now - self.config.purge_grace_days * DAY_MS;
+        let mut purged = 0;
+        loop {
+            let hashes = StoreSoftDelete::<RssItem>::deleted_ids(
+                &self.storage,
+                deleted_before,
+                PURGE_BATCH_SIZE,
+            )
+            .await?;
+            if hashes.is_empty() {
+                return Ok(purged);
+            }
+
+            self.purge_media(&hashes).await?;
+            StoreDeleteBulk::<RssItemSentiment, String>::delete_bulk(&self.storage, &hashes)
+                .await?;
+            StoreDeleteBulk::<RssItemEmbedding, String>::delete_bulk(&self.storage, &hashes)
+                .await?;
+            // Media rows and bookmarks of the items are removed by the foreign key cascades.
+            StoreDeleteBulk::<RssItem, String>::delete_bulk(&self.storage, &hashes).await?;
+            purged += hashes.len();
+        }
+    }
+
+    /// Deletes the archived media files of the items, their rows are removed with the items.
+    async fn purge_media(&self, hashes: &[String]) -> Result<()> {
+        let Some(objects) = &self.objects else {
+            return Ok(());
+        };
+
+        for media in read_items_media(&self.storage, hashes).await? {
+            objects.delete(&media.object_key).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,314 @@
+use crate::{
+    audit, constants::API_KEY_HEADER, database, digest, export, handlers_v1, models, webhooks,
+};
+use actix_web::web;
+use utoipa::{
+    Modify, OpenApi,
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        handlers_v1::register,
+        handlers_v1::login,
+        handlers_v1::auth_sessions,
+        handlers_v1::revoke_auth_session,
+        handlers_v1::redeem_solana_payment,
+        handlers_v1::health_live,
+        handlers_v1::health_ready,
+        handlers_v1::metrics_endpoint,
+        handlers_v1::jwks,
+        handlers_v1::rss_items,
+        handlers_v1::rss_item,
+        handlers_v1::rss_item_media,
+        handlers_v1::rss_item_sentiment,
+        handlers_v1::rss_search,
+        handlers_v1::rss_semantic_search,
+        handlers_v1::bookmark_rss_item,
+        handlers_v1::remove_rss_bookmark,
+        handlers_v1::rss_bookmarks,
+        handlers_v1::save_rss_search,
+        handlers_v1::rss_saved_searches,
+        handlers_v1::delete_rss_saved_search,
+        handlers_v1::rss_feeds,
+        handlers_v1::rss_feed,
+        handlers_v1::create_rss_feed,
+        handlers_v1::update_rss_feed,
+        handlers_v1::delete_rss_feed,
+        handlers_v1::purge_rss_items,
+        handlers_v1::export_rss_items,
+        handlers_v1::audit_log,
+        handlers_v1::admin_stats,
+        handlers_v1::create_webhook,
+        handlers_v1::webhooks,
+        handlers_v1::delete_webhook,
+        handlers_v1::digest_preferences,
+        handlers_v1::save_digest_preferences,
+        handlers_v1::confirm_digest,
+        handlers_v1::delete_digest_preferences,
+        handlers_v1::create_organization,
+        handlers_v1::user_organizations,
+        handlers_v1::organization_members,
+        handlers_v1::add_organization_member,
+        handlers_v1::remove_organization_member,
+        handlers_v1::create_organization_api_key,
+        handlers_v1::organization_api_keys,
+        handlers_v1::revoke_organization_api_key
+    ),
+    components(
+        schemas(
+            models::RegisterRequest,
+            models::LoginRequest,
+            models::UserResponse,
+            models::Claims,
+            models::AuthSessionResponse,
+            models::AuthSessionsResponse,
+            models::SolanaPaymentRequest,
+            models::SubscriptionResponse,
+            models::FeedDayCountResponse,
+            models::SentimentCountResponse,
+            models::IngestionLagResponse,
+            models::AdminStatsResponse,
+            models::JwkResponse,
+            models::JwksResponse,
+            models::ProblemDetails,
+            models::HealthResponse,
+            models::DependencyCheck,
+            models::ReadinessResponse,
+            models::RssItemResponse,
+            models::RssAuthorResponse,
+            models::RssItemsResponse,
+            models::RssItemSentimentResponse,
+            models::RssSearchHitResponse,
+            models::RssSearchResponse,
+            models::RssSemanticHitResponse,
+            models::RssSemanticSearchResponse,
+            models::RssItemMediaResponse,
+            models::RssMediaResponse,
+            models::RssBookmarkResponse,
+            models::RssBookmarksResponse,
+            models::SaveSearchRequest,
+            models::RssSavedSearchResponse,
+            models::RssSavedSearchesResponse,
+            models::CreateRssFeedRequest,
+            models::UpdateRssFeedRequest,
+            models::PurgeRssItemsRequest,
+            models::PurgeRssItemsResponse,
+            models::RssFeedResponse,
+            models::RssFeedsResponse,
+            models::CreateWebhookRequest,
+            models::WebhookResponse,
+            models::WebhooksResponse,
+            webhooks::WebhookEventType,
+            models::DigestPreferencesRequest,
+            models::DigestConfirmationRequest,
+            models::DigestPreferencesResponse,
+            digest::DigestFrequency,
+            models::CreateOrganizationRequest,
+            models::OrganizationResponse,
+            models::OrganizationsResponse,
+            models::AddOrganizationMemberRequest,
+            models::OrganizationMemberResponse,
+            models::OrganizationMembersResponse,
+            models::CreateApiKeyRequest,
+            models::ApiKeyResponse,
+            models::CreatedApiKeyResponse,
+            models::ApiKeysResponse,
+            models::AuditEventResponse,
+            models::AuditEventsResponse,
+            audit::AuditEventType,
+            export::ExportFormat,
+            database::SortDirection
+        )
+    ),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "auth", description = "Authentication endpoints"),
+        (name = "health", description = "Health check endpoints"),
+        (name = "rss", description = "RSS items endpoints"),
+        (name = "webhooks", description = "Outbound webhook subscriptions"),
+        (name = "digest", description = "Email digest preferences"),
+        (name = "payments", description = "Tier upgrades paid on Solana"),
+        (name = "organizations", description = "Organizations sharing bookmarks, saved searches and webhooks"),
+        (name = "admin", description = "Administration endpoints, restricted to admin wallets")
+    ),
+    info(
+        title = "Semantic Machine API",
+        version = "1.0.0",
+        description = "REST API for Semantic-Machine services",
+        contact(
+            name = "Bartosz Lenart",
+            email = "bartossh@pm.me"
+        ),
+    ),
+    servers(
+        (url = "http://localhost:8080", description = "Local development server"),
+        (url = "https://semantic-machine-dev.up.railway.app", description = "Development server")
+    )
+)]
+pub struct ApiDoc;
+
+/// SecurityAddon registers the JWT bearer scheme referenced by the protected endpoints and the
+/// organization API key accepted on the RSS and webhook endpoints.
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))),
+        );
+    }
+}
+
+/// Routes served at the root, outside of the API version scope.
+pub fn root_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers_v1::health_live)
+        .service(handlers_v1::health_ready)
+        .service(handlers_v1::metrics_endpoint)
+        .service(handlers_v1::jwks);
+}
+
+/// Routes of the `/api/v1` scope available without a token.
+pub fn public_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers_v1::register)
+        .service(handlers_v1::login);
+}
+
+/// Routes of the `/api/v1` scope requiring a valid JWT.
+pub fn user_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers_v1::auth_sessions)
+        .service(handlers_v1::revoke_auth_session)
+        .service(handlers_v1::redeem_solana_payment)
+        .service(handlers_v1::rss_items)
+        .service(handlers_v1::rss_item)
+        .service(handlers_v1::rss_item_media)
+        .service(handlers_v1::rss_item_sentiment)
+        .service(handlers_v1::rss_search)
+        .service(handlers_v1::rss_semantic_search)
+        .service(handlers_v1::export_rss_items)
+        .service(handlers_v1::bookmark_rss_item)
+        .service(handlers_v1::remove_rss_bookmark)
+        .service(handlers_v1::rss_bookmarks)
+        .service(handlers_v1::save_rss_search)
+        .service(handlers_v1::rss_saved_searches)
+        .service(handlers_v1::delete_rss_saved_search)
+        .service(handlers_v1::create_webhook)
+        .service(handlers_v1::webhooks)
+        .service(handlers_v1::delete_webhook)
+        .service(handlers_v1::digest_preferences)
+        .service(handlers_v1::save_digest_preferences)
+        .service(handlers_v1::confirm_digest)
+        .service(handlers_v1::delete_digest_preferences)
+        .service(handlers_v1::create_organization)
+        .service(handlers_v1::user_organizations)
+        .service(handlers_v1::organization_members)
+        .service(handlers_v1::add_organization_member)
+        .service(handlers_v1::remove_organization_member)
+        .service(handlers_v1::create_organization_api_key)
+        .service(handlers_v1::organization_api_keys)
+        .service(handlers_v1::revoke_organization_api_key);
+}
+
+/// Routes of the `/api/v1/admin` scope restricted to admin wallets.
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(handlers_v1::rss_feeds)
+        .service(handlers_v1::rss_feed)
+        .service(handlers_v1::create_rss_feed)
+        .service(handlers_v1::update_rss_feed)
+        .service(handlers_v1::delete_rss_feed)
+        .service(handlers_v1::purge_rss_items)
+        .service(handlers_v1::audit_log)
+        .service(handlers_v1::admin_stats);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{App, http::Method, http::StatusCode, test as actix_test};
+    use utoipa::openapi::path::HttpMethod;
+
+    /// Documented routes as `METHOD /path` lines, sorted.
+    fn documented_routes() -> Vec<String> {
+        let openapi = ApiDoc::openapi();
+        let methods = [
+            (HttpMethod::Get, "GET"),
+            (HttpMethod::Post, "POST"),
+            (HttpMethod::Put, "PUT"),
+            (HttpMethod::Patch, "PATCH"),
+            (HttpMethod::Delete, "DELETE"),
+        ];
+        let mut routes = Vec::new();
+        for (path, item) in &openapi.paths.paths {
+            for (method, name) in &methods {
+                let operation = match method {
+                    HttpMethod::Get => &item.get,
+                    HttpMethod::Post => &item.post,
+                    HttpMethod::Put => &item.put,
+                    HttpMethod::Patch => &item.patch,
+                    HttpMethod::Delete => &item.delete,
+                    _ => continue,
+                };
+                if operation.is_some() {
+                    routes.push(format!("{name} {path}"));
+                }
+            }
+        }
+        routes.sort();
+        routes
+    }
+
+    #[test]
+    fn test_openapi_routes_snapshot() {
+        let snapshot: Vec<String> = include_str!("snapshots/openapi_routes.txt")
+            .lines()
+            .map(str::to_string)
+            .collect();
+
+        assert_eq!(
+            documented_routes(),
+            snapshot,
+            "OpenAPI routes changed, update src/snapshots/openapi_routes.txt"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_openapi_routes_are_mounted() {
+        let app = actix_test::init_service(
+            App::new().configure(root_routes).service(
+                web::scope("/api/v1").configure(public_routes).service(
+                    web::scope("")
+                        .configure(user_routes)
+                        .service(web::scope("/admin").configure(admin_routes)),
+                ),
+            ),
+        )
+        .await;
+
+        for route in documented_routes() {
+            let (method, path) = route.split_once(' ').unwrap();
+            let uri = path.replace(['{', '}'], "");
+            let req = actix_test::TestRequest::default()
+                .method(Method::from_bytes(method.as_bytes()).unwrap())
+                .uri(&uri)
+                .to_request();
+            let res = actix_test::call_service(&app, req).await;
+            assert_ne!(
+                res.status(),
+                StatusCode::NOT_FOUND,
+                "{route} is documented but not mounted"
+            );
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    database::{
+        Filter, FilterValue, PostgresStorageGateway, StoreCount, StoreDeleteBulk, StoreInsertBulk,
+        StorePaginateBulkEntities, StoreReadBulkEntities,
+    },
+    models::{AuthSession, Claims},
+};
+use anyhow::Result;
+use chrono::Utc;
+use redis_middleware::RedisMiddleware;
+
+const SESSION_KEY_PREFIX: &str = "auth-session:";
+/// Maximal length of the stored user agent.
+const USER_AGENT_MAX_LEN: usize = 256;
+
+/// SessionRegistry tracks the JWTs issued to users, so they can list and revoke them.
+///
+/// Sessions are kept in Postgres for listing and in Redis with the lifetime of the token, the JWT
+/// middleware only accepts tokens whose session is still in Redis.
+pub struct SessionRegistry {
+    storage: PostgresStorageGateway,
+    redis: RedisMiddleware,
+}
+
+impl SessionRegistry {
+    /// Creates a new session registry.
+    ///
+    /// # Arguments
+    /// * `storage` - The Postgres storage gateway.
+    /// * `redis` - The Redis client.
+    pub fn new(storage: PostgresStorageGateway, redis: RedisMiddleware) -> Self {
+        Self { storage, redis }
+    }
+
+    /// Opens the session of a newly issued token.
+    ///
+    /// # Arguments
+    /// * `claims` - The claims of the token.
+    /// * `user_agent` - The user agent the token was issued to.
+    ///
+    /// # Returns
+    /// The session or an error if the storage fails.
+    pub async fn open(&self, claims: &Claims, user_agent: &str) -> Result<AuthSession> {
+        let session = AuthSession {
+            id: claims.jti.clone(),
+            user_id: claims.sub.clone(),
+            user_agent: user_agent.chars().take(USER_AGENT_MAX_LEN).collect(),
+            created_at: claims.iat * 1000,
+            expires_at: claims.exp * 1000,
+        };
+        self.storage
+            .insert_bulk(std::slice::from_ref(&session))
+            .await?;
+        let ttl_seconds = (claims.exp - Utc::now().timestamp()).max(1) as u64;
+        self.redis
+            .store_with_ttl(&session_key(&session.id), &session.user_id, ttl_seconds)
+            .await?;
+
+        Ok(session)
+    }
+
+    /// Checks the session of the token was not revoked.
+    ///
+    /// # Arguments
+    /// * `claims` - The validated claims of the token.
+    ///
+    /// # Returns
+    /// True if the session is active, or an error if Redis fails.
+    pub async fn is_active(&self, claims: &Claims) -> Result<bool> {
+        let user_id = self.redis.retrieve(&session_key(&claims.jti)).await?;
+        Ok(user_id.is_some_and(|user_id| user_id == claims.sub))
+    }
+
+    /// Reads a page of the active sessions of the user, newest first.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the user.
+    /// * `limit` - The number of sessions per page.
+    /// * `offset` - The number of sessions to skip.
+    ///
+    /// # Returns
+    /// The sessions or an error if the storage fails.
+    pub async fn sessions(
+        &self,
+        user_id: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<AuthSession>> {
+        self.storage
+            .filter_paginate(&active_filters(Some(user_id)), None, limit, offset)
+            .await
+    }
+
+    /// Counts the active sessions of the user.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the user.
+    ///
+    /// # Returns
+    /// The number of sessions or an error if the storage fails.
+    pub async fn count_sessions(&self, user_id: &str) -> Result<i64> {
+        StoreCount::<AuthSession>::count(&self.storage, &active_filters(Some(user_id))).await
+    }
+
+    /// Counts the active sessions of all users.
+    pub async fn count_active(&self) -> Result<i64> {
+        StoreCount::<AuthSession>::count(&self.storage, &active_filters(None)).await
+    }
+
+    /// Revokes a session of the user, its token is rejected from then on.
+    ///
+    /// # Arguments
+    /// * `user_id` - The ID of the user.
+    /// * `id` - The ID of the session.
+    ///
+    /// # Returns
+    /// True if the user had the session, or an error if the storage fails.
+    pub async fn revoke(&self, user_id: &str, id: &str) -> Result<bool> {
+        let sessions: Vec<AuthSession> = self.storage.read_bulk_by_ids(&[id.to_string()]).await?;
+        if !sessions.iter().any(|session| session.user_id == user_id) {
+            return Ok(false);
+        }
+
+        self.redis.delete(&session_key(id)).await?;
+        StoreDeleteBulk::<AuthSession, String>::delete_bulk(&self.storage, &[id.to_string()])
+            .await?;
+        Ok(true)
+    }
+}
+
+fn session_key(id: &str) -> String {
+    format!("{SESSION_KEY_PREFIX}{id}")
+}
+
+/// Filters of the sessions that did not expire yet, of a single user if given.
+fn active_filters(user_id: Option<&str>) -> Vec<Filter> {
+    let mut filters = vec![Filter::Gte(
+        "expires_at",
+        FilterValue::Int(Utc::now().timestamp_millis()),
+    )];
+    if let Some(user_id) = user_id {
+        filters.push(Filter::Eq(
+            "user_id",
+            FilterValue::Text(user_id.to_string()),
+        ));
+    }
+    filters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_active_filters() {
+        let filters = active_filters(Some("wallet"));
+        assert_eq!(filters.len(), 2);
+        assert_eq!(filters[0].condition(1), "expires_at >= $1");
+        assert_eq!(
+            filters[1],
+            Filter::Eq("user_id", FilterValue::Text("wallet".to_string()))
+        );
+        assert_eq!(active_filters(None).len(), 1);
+        assert_eq!(session_key("4f1c"), "auth-session:4f1c");
+    }
+}
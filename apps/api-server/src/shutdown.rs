@@ -0,0 +1,20 @@
+use tokio::signal;
+
+/// Waits for a termination request, SIGTERM sent by the orchestrator or SIGINT (Ctrl+C).
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler");
+        tokio::select! {
+            _ = terminate.recv() => tracing::info!("Received SIGTERM, shutting down"),
+            _ = signal::ctrl_c() => tracing::info!("Received SIGINT, shutting down"),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = signal::ctrl_c().await;
+        tracing::info!("Received Ctrl+C, shutting down");
+    }
+}
@@ -1,4 +1,6 @@
 use crate::config::{Config, TelemetryConfig};
+use chrono::Utc;
+use circuit_breaker::CircuitBreaker;
 use opentelemetry::global;
 use opentelemetry_sdk::propagation::TraceContextPropagator;
 use prometheus::{
@@ -65,6 +67,15 @@ pub struct Metrics {
     pub feature_usage: IntCounterVec,
     pub webhook_deliveries: IntCounterVec,
     pub webhook_failures: IntCounterVec,
+    pub circuit_breaker_state: IntGaugeVec,
+    pub circuit_breaker_rejections: IntCounterVec,
+
+    // Message Queue Consumer Metrics
+    pub queue_messages_processed: IntCounterVec,
+    pub queue_messages_failed: IntCounterVec,
+    pub queue_processing_duration: HistogramVec,
+    pub queue_message_lag: GaugeVec,
+    pub queue_last_processed: IntGaugeVec,
 }
 
 #[allow(dead_code)]
@@ -277,6 +288,65 @@ impl Metrics {
             &["event_type", "failure_reason"],
         )?;
 
+        let circuit_breaker_state = IntGaugeVec::new(
+            Opts::new(
+                "api_circuit_breaker_state",
+                "State of the dependency circuit breaker, 0 closed, 1 half-open and 2 open",
+            ),
+            &["dependency"],
+        )?;
+
+        let circuit_breaker_rejections = IntCounterVec::new(
+            Opts::new(
+                "api_circuit_breaker_rejections_total",
+                "Total calls rejected by an open dependency circuit breaker",
+            ),
+            &["dependency"],
+        )?;
+
+        let queue_messages_processed = IntCounterVec::new(
+            Opts::new(
+                "api_queue_messages_processed_total",
+                "Total messages consumed from the queue and processed",
+            ),
+            &["subject"],
+        )?;
+
+        let queue_messages_failed = IntCounterVec::new(
+            Opts::new(
+                "api_queue_messages_failed_total",
+                "Total messages consumed from the queue that failed to process",
+            ),
+            &["subject", "failure_reason"],
+        )?;
+
+        let queue_processing_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "api_queue_processing_duration_seconds",
+                "Time spent processing a message consumed from the queue",
+            )
+            .buckets(vec![
+                0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+            ]),
+            &["subject"],
+        )?;
+
+        let queue_message_lag = GaugeVec::new(
+            Opts::new(
+                "api_queue_message_lag_seconds",
+                "Age of the last processed message, from the time its event was produced",
+            ),
+            &["subject"],
+        )?;
+
+        let queue_last_processed = IntGaugeVec::new(
+            Opts::new(
+                "api_queue_last_processed_timestamp_seconds",
+                "Unix time the last message of the subject was processed at",
+            ),
+            &["subject"],
+        )?;
+
         registry.register(Box::new(http_requests_total.clone()))?;
         registry.register(Box::new(http_request_duration.clone()))?;
         registry.register(Box::new(http_request_size.clone()))?;
@@ -310,6 +380,13 @@ impl Metrics {
         registry.register(Box::new(feature_usage.clone()))?;
         registry.register(Box::new(webhook_deliveries.clone()))?;
         registry.register(Box::new(webhook_failures.clone()))?;
+        registry.register(Box::new(circuit_breaker_state.clone()))?;
+        registry.register(Box::new(circuit_breaker_rejections.clone()))?;
+        registry.register(Box::new(queue_messages_processed.clone()))?;
+        registry.register(Box::new(queue_messages_failed.clone()))?;
+        registry.register(Box::new(queue_processing_duration.clone()))?;
+        registry.register(Box::new(queue_message_lag.clone()))?;
+        registry.register(Box::new(queue_last_processed.clone()))?;
 
         Ok(Self {
             registry,
@@ -346,6 +423,13 @@ impl Metrics {
             feature_usage,
             webhook_deliveries,
             webhook_failures,
+            circuit_breaker_state,
+            circuit_breaker_rejections,
+            queue_messages_processed,
+            queue_messages_failed,
+            queue_processing_duration,
+            queue_message_lag,
+            queue_last_processed,
         })
     }
 
@@ -430,6 +514,67 @@ impl Metrics {
             .observe(duration);
     }
 
+    #[inline(always)]
+    pub fn record_db_pool(&self, active: u32, idle: usize) {
+        self.db_connections_active.set(active as i64);
+        self.db_connections_idle.set(idle as i64);
+    }
+
+    /// Records the state of the circuit breaker and the calls it rejected since the last update.
+    #[inline(always)]
+    pub fn record_circuit_breaker(&self, breaker: &CircuitBreaker) {
+        let dependency = breaker.name();
+        self.circuit_breaker_state
+            .with_label_values(&[dependency])
+            .set(breaker.state().as_metric());
+        let rejections = self
+            .circuit_breaker_rejections
+            .with_label_values(&[dependency]);
+        rejections.inc_by(breaker.rejected().saturating_sub(rejections.get()));
+    }
+
+    /// Records a message consumed from the queue subject.
+    ///
+    /// # Arguments
+    /// * `subject` - The subject the message was consumed from.
+    /// * `failure` - The reason the message failed to process, None if it was processed.
+    /// * `duration` - Seconds spent processing the message.
+    #[inline(always)]
+    pub fn record_queue_message(&self, subject: &str, failure: Option<&str>, duration: f64) {
+        match failure {
+            Some(reason) => self
+                .queue_messages_failed
+                .with_label_values(&[subject, reason])
+                .inc(),
+            None => self
+                .queue_messages_processed
+                .with_label_values(&[subject])
+                .inc(),
+        }
+        self.queue_processing_duration
+            .with_label_values(&[subject])
+            .observe(duration);
+        self.queue_last_processed
+            .with_label_values(&[subject])
+            .set(Utc::now().timestamp());
+    }
+
+    /// Records the lag of a processed message, the time since its event was produced.
+    ///
+    /// Subjects are consumed with core NATS subscriptions that do not report pending messages, the
+    /// lag of the last processed event is what shows a stalled or backed up consumer.
+    ///
+    /// # Arguments
+    /// * `subject` - The subject the message was consumed from.
+    /// * `produced_at` - Unix time in milliseconds the event was produced at.
+    #[inline(always)]
+    pub fn record_queue_lag(&self, subject: &str, produced_at: i64) {
+        let lag_ms = (Utc::now().timestamp_millis() - produced_at).max(0);
+        self.queue_message_lag
+            .with_label_values(&[subject])
+            .set(lag_ms as f64 / 1000.0);
+    }
+
     #[inline(always)]
     pub fn record_db_error(&self, error_type: &str, operation: &str) {
         self.db_errors
@@ -596,9 +741,70 @@ mod tests {
         metrics.record_jwt_validation(true);
         metrics.record_cache_hit("session");
         metrics.record_cache_miss("user");
+        metrics.record_db_pool(3, 7);
 
         let export = metrics.export().unwrap();
         assert!(export.contains("http_requests_total"));
         assert!(export.contains("auth_attempts_total"));
+        assert_eq!(metrics.db_connections_active.get(), 3);
+        assert_eq!(metrics.db_connections_idle.get(), 7);
+    }
+
+    #[test]
+    fn test_record_queue_message() {
+        let metrics = Metrics::new().unwrap();
+
+        metrics.record_queue_message("rss", None, 0.01);
+        metrics.record_queue_message("rss", Some("decode"), 0.002);
+        metrics.record_queue_lag("rss", Utc::now().timestamp_millis() - 90_000);
+
+        assert_eq!(
+            metrics
+                .queue_messages_processed
+                .with_label_values(&["rss"])
+                .get(),
+            1
+        );
+        assert_eq!(
+            metrics
+                .queue_messages_failed
+                .with_label_values(&["rss", "decode"])
+                .get(),
+            1
+        );
+        let lag = metrics.queue_message_lag.with_label_values(&["rss"]).get();
+        assert!((90.0..100.0).contains(&lag));
+        assert!(
+            metrics
+                .queue_last_processed
+                .with_label_values(&["rss"])
+                .get()
+                > 0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_circuit_breaker() {
+        let metrics = Metrics::new().unwrap();
+        let breaker = CircuitBreaker::new(
+            "redis",
+            circuit_breaker::CircuitBreakerConfig {
+                failure_threshold: 1,
+                open_duration: std::time::Duration::from_secs(60),
+            },
+        );
+
+        let _ = breaker.call(async { Err::<(), _>("timeout") }).await;
+        let _ = breaker.call(async { Ok::<_, ()>(()) }).await;
+        let _ = breaker.call(async { Ok::<_, ()>(()) }).await;
+        metrics.record_circuit_breaker(&breaker);
+        metrics.record_circuit_breaker(&breaker);
+
+        let state = metrics.circuit_breaker_state.with_label_values(&["redis"]);
+        let rejections = metrics
+            .circuit_breaker_rejections
+            .with_label_values(&["redis"]);
+        assert_eq!(state.get(), 2);
+        assert_eq!(rejections.get(), 2);
     }
 }
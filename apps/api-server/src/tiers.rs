@@ -0,0 +1,277 @@
+use crate::{
+    config::QuotaConfig,
+    database::{PostgresStorageGateway, StoreReadBulkEntities},
+    payments::UserSubscription,
+};
+use anyhow::Result;
+use chrono::{DateTime, Days, Utc};
+use redis_middleware::RedisMiddleware;
+use std::str::FromStr;
+
+const TIER_KEY_PREFIX: &str = "user-tier:";
+const QUOTA_KEY_PREFIX: &str = "quota:";
+/// Time the tier of a user is cached for, upgrades invalidate the cached tier right away.
+const TIER_CACHE_SECONDS: u64 = 60;
+
+/// Tier is the subscription level of a user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tier {
+    Free,
+    /// Paid tier, see [`crate::payments::SolanaPayments`].
+    Pro,
+}
+
+impl Tier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Free => "free",
+            Self::Pro => "pro",
+        }
+    }
+
+    /// Tier of the subscription, free if there is none or it expired.
+    ///
+    /// # Arguments
+    /// * `subscription` - The subscription of the user, if any.
+    /// * `now` - The current time in Unix milliseconds.
+    pub fn of_subscription(subscription: Option<&UserSubscription>, now: i64) -> Self {
+        subscription
+            .filter(|subscription| subscription.expires_at > now)
+            .and_then(|subscription| subscription.tier.parse().ok())
+            .unwrap_or(Self::Free)
+    }
+
+    /// Whether the feature is available in the tier.
+    pub fn allows(&self, feature: Feature) -> bool {
+        match (self, feature) {
+            (Self::Pro, _) => true,
+            (Self::Free, Feature::Webhooks | Feature::Digests) => false,
+        }
+    }
+}
+
+impl FromStr for Tier {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "free" => Ok(Self::Free),
+            "pro" => Ok(Self::Pro),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Feature is a part of the API gated by tier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    /// Outbound webhook subscriptions.
+    Webhooks,
+    /// Email digests.
+    Digests,
+}
+
+impl Feature {
+    /// Feature the API path belongs to, None for paths available in every tier.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the request, e.g. `/api/v1/webhooks`.
+    pub fn of_path(path: &str) -> Option<Self> {
+        let path = path.strip_prefix("/api/v1").unwrap_or(path);
+        if path.starts_with("/webhooks") {
+            Some(Self::Webhooks)
+        } else if path.starts_with("/digest") {
+            Some(Self::Digests)
+        } else {
+            None
+        }
+    }
+}
+
+/// Quota is the state of the request quota of a user after a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    /// Requests allowed per day.
+    pub limit: u64,
+    /// Requests left until the reset.
+    pub remaining: u64,
+    /// Time the quota resets at in Unix seconds.
+    pub reset_at: i64,
+}
+
+impl Quota {
+    /// Whether the request exceeded the quota.
+    pub fn exceeded(&self, used: u64) -> bool {
+        used > self.limit
+    }
+}
+
+/// Quotas enforces the daily request quotas of the tiers with counters in Redis.
+pub struct Quotas {
+    storage: PostgresStorageGateway,
+    redis: RedisMiddleware,
+    config: QuotaConfig,
+}
+
+impl Quotas {
+    /// Creates the quotas.
+    ///
+    /// # Arguments
+    /// * `storage` - The Postgres storage gateway subscriptions are read from.
+    /// * `redis` - The Redis client tiers are cached and requests are counted in.
+    /// * `config` - The quota configuration.
+    pub fn new(
+        storage: PostgresStorageGateway,
+        redis: RedisMiddleware,
+        config: QuotaConfig,
+    ) -> Self {
+        Self {
+            storage,
+            redis,
+            config,
+        }
+    }
+
+    /// Whether quotas and feature gates are enforced.
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Reads the tier of the user, cached in Redis.
+    ///
+    /// # Arguments
+    /// * `user_id` - The base58 wallet of the user.
+    ///
+    /// # Returns
+    /// The tier or an error if the storage fails.
+    pub async fn tier(&self, user_id: &str) -> Result<Tier> {
+        let key = tier_key(user_id);
+        if let Some(tier) = self
+            .redis
+            .retrieve(&key)
+            .await?
+            .and_then(|tier| tier.parse().ok())
+        {
+            return Ok(tier);
+        }
+
+        let subscriptions: Vec<UserSubscription> = self
+            .storage
+            .read_bulk_by_ids(&[user_id.to_string()])
+            .await?;
+        let now = Utc::now().timestamp_millis();
+        let subscription = subscriptions.first();
+        let tier = Tier::of_subscription(subscription, now);
+        let ttl_seconds = match subscription {
+            Some(subscription) if tier == Tier::Pro => {
+                ((subscription.expires_at - now) / 1000).clamp(1, TIER_CACHE_SECONDS as i64) as u64
+            }
+            _ => TIER_CACHE_SECONDS,
+        };
+        self.redis
+            .store_with_ttl(&key, tier.as_str(), ttl_seconds)
+            .await?;
+
+        Ok(tier)
+    }
+
+    /// Counts a request of the user against the daily quota of the tier.
+    ///
+    /// # Arguments
+    /// * `user_id` - The base58 wallet of the user.
+    /// * `tier` - The tier of the user.
+    ///
+    /// # Returns
+    /// The quota and the number of requests made today including this one, or an error if Redis
+    /// fails.
+    pub async fn consume(&self, user_id: &str, tier: Tier) -> Result<(Quota, u64)> {
+        let now = Utc::now();
+        let reset_at = next_reset(now);
+        let key = format!("{QUOTA_KEY_PREFIX}{}:{user_id}", now.format("%Y-%m-%d"));
+        let ttl_seconds = (reset_at.timestamp() - now.timestamp()).max(1) as u64;
+        let used = self
+            .redis
+            .increment_with_ttl(&key, ttl_seconds)
+            .await?
+            .max(0) as u64;
+
+        let limit = self.limit(tier);
+        Ok((
+            Quota {
+                limit,
+                remaining: limit.saturating_sub(used),
+                reset_at: reset_at.timestamp(),
+            },
+            used,
+        ))
+    }
+
+    fn limit(&self, tier: Tier) -> u64 {
+        match tier {
+            Tier::Free => self.config.free_daily_requests,
+            Tier::Pro => self.config.pro_daily_requests,
+        }
+    }
+}
+
+/// Key the tier of the user is cached at.
+pub fn tier_key(user_id: &str) -> String {
+    format!("{TIER_KEY_PREFIX}{user_id}")
+}
+
+/// Start of the next UTC day, when the quotas reset.
+fn next_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+    now.date_naive()
+        .checked_add_days(Days::new(1))
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc())
+        .unwrap_or(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_tier_of_subscription() {
+        let subscription = UserSubscription {
+            user_id: "wallet".to_string(),
+            tier: "pro".to_string(),
+            expires_at: 2_000,
+            updated_at: 1_000,
+        };
+        assert_eq!(Tier::of_subscription(Some(&subscription), 1_500), Tier::Pro);
+        assert_eq!(
+            Tier::of_subscription(Some(&subscription), 2_000),
+            Tier::Free
+        );
+        assert_eq!(Tier::of_subscription(None, 1_500), Tier::Free);
+
+        assert!(!Tier::Free.allows(Feature::Webhooks));
+        assert!(Tier::Pro.allows(Feature::Digests));
+        assert_eq!(
+            Feature::of_path("/api/v1/webhooks/42"),
+            Some(Feature::Webhooks)
+        );
+        assert_eq!(Feature::of_path("/api/v1/digest"), Some(Feature::Digests));
+        assert_eq!(Feature::of_path("/api/v1/rss/items"), None);
+    }
+
+    #[test]
+    fn test_quota() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 13, 45, 0).unwrap();
+        assert_eq!(
+            next_reset(now),
+            Utc.with_ymd_and_hms(2026, 10, 17, 0, 0, 0).unwrap()
+        );
+
+        let quota = Quota {
+            limit: 2,
+            remaining: 0,
+            reset_at: next_reset(now).timestamp(),
+        };
+        assert!(!quota.exceeded(2));
+        assert!(quota.exceeded(3));
+    }
+}
@@ -0,0 +1,347 @@
+use crate::{
+    database::{Filter, FilterValue, PostgresStorageGateway, StorePaginateBulkEntities},
+    models::WebhookSubscription,
+    telemetry::Metrics,
+};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use nats_middleware::{NatsQueue, REQUEST_ID_HEADER, message_request_id};
+use reqwest::{Client, Url, header::CONTENT_TYPE, redirect::Policy};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use shared_states::{
+    Event, HttpFailure, check_public_destination, decode_event, public_client_builder,
+};
+use std::{sync::Arc, time::Duration};
+use tokio::{sync::Semaphore, time::sleep};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Queue group of the dispatchers, so every event is delivered by a single api-server instance.
+const WEBHOOK_QUEUE_GROUP: &str = "api-server-webhooks";
+const WEBHOOK_ID_HEADER: &str = "X-Webhook-Id";
+const WEBHOOK_EVENT_HEADER: &str = "X-Webhook-Event";
+const WEBHOOK_TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+const WEBHOOK_SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_DELIVERY_ATTEMPTS: u32 = 5;
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_CONCURRENT_DELIVERIES: usize = 32;
+/// Number of subscriptions read from the storage at once.
+const SUBSCRIPTIONS_PAGE_SIZE: i64 = 500;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// WebhookEventType is the type of the internal events users can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    /// RSS item fetched by the rss-worker.
+    RssItem,
+    /// Sentiment analysis of an RSS item.
+    Sentiment,
+}
+
+impl WebhookEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::RssItem => "rss_item",
+            Self::Sentiment => "sentiment",
+        }
+    }
+}
+
+/// WebhookPayload is the body POSTed to the subscribed URLs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    /// Unique ID of the delivered event, the same for all retries.
+    pub id: Uuid,
+    pub event_type: WebhookEventType,
+    /// Time the event was received in Unix milliseconds.
+    pub created_at: i64,
+    pub data: Value,
+}
+
+impl WebhookPayload {
+    pub fn new<T: Event>(event_type: WebhookEventType, event: &T) -> Result<Self> {
+        Ok(Self {
+            id: Uuid::new_v4(),
+            event_type,
+            created_at: Utc::now().timestamp_millis(),
+            data: serde_json::to_value(event)?,
+        })
+    }
+}
+
+/// Signs the payload, so the receiver can check it was sent by the api-server and not replayed.
+///
+/// # Arguments
+/// * `secret` - The secret of the subscription.
+/// * `timestamp` - The time of the delivery attempt in Unix seconds.
+/// * `body` - The serialized payload.
+///
+/// # Returns
+/// The value of the signature header, `sha256=` followed by the hex encoded HMAC-SHA256 of
+/// `{timestamp}.{body}`.
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// WebhookDispatcher delivers internal events to the URLs users subscribed to.
+///
+/// Payloads are signed with the secret of the subscription and retried with an exponential
+/// backoff, outcomes are recorded in the `webhook_deliveries` and `webhook_failures` metrics.
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    storage: PostgresStorageGateway,
+    queue: NatsQueue,
+    client: Client,
+    metrics: Arc<Metrics>,
+    deliveries: Arc<Semaphore>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(
+        storage: PostgresStorageGateway,
+        queue: NatsQueue,
+        metrics: Arc<Metrics>,
+    ) -> Result<Self> {
+        Ok(Self {
+            storage,
+            queue,
+            // Redirects are not followed, the destination is checked before every attempt.
+            client: public_client_builder(0)
+                .redirect(Policy::none())
+                .timeout(DELIVERY_TIMEOUT)
+                .build()?,
+            metrics,
+            deliveries: Arc::new(Semaphore::new(MAX_CONCURRENT_DELIVERIES)),
+        })
+    }
+
+    /// Run the dispatcher reading events of the type from the queue and delivering them to the
+    /// subscriptions.
+    ///
+    /// # Arguments
+    /// * `event_type` - The type the events of the queue are delivered as.
+    pub async fn run<T: Event>(&self, event_type: WebhookEventType) -> Result<()> {
+        let mut events = self
+            .queue
+            .queue_subscribe(T::SUBJECT, WEBHOOK_QUEUE_GROUP)
+            .await?;
+
+        while let Some(message) = events.next().await {
+            let event: T = match decode_event(&message.payload) {
+                Ok(event) => event,
+                Err(e) => {
+                    tracing::error!("Failed to decode {} event: {}", event_type.as_str(), e);
+                    continue;
+                }
+            };
+            let subscriptions = match self.subscriptions(event_type).await {
+                Ok(subscriptions) => subscriptions,
+                Err(e) => {
+                    tracing::error!("Failed to read webhook subscriptions: {}", e);
+                    continue;
+                }
+            };
+            if subscriptions.is_empty() {
+                continue;
+            }
+            let payload = match WebhookPayload::new(event_type, &event) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    tracing::error!("Failed to encode {} event: {}", event_type.as_str(), e);
+                    continue;
+                }
+            };
+            let body = match serde_json::to_vec(&payload) {
+                Ok(body) => body,
+                Err(e) => {
+                    tracing::error!("Failed to encode webhook payload {}: {}", payload.id, e);
+                    continue;
+                }
+            };
+            let id = payload.id;
+            let request_id = message_request_id(&message).map(str::to_string);
+
+            for subscription in subscriptions {
+                let permit = self.deliveries.clone().acquire_owned().await?;
+                let dispatcher = self.clone();
+                let body = body.clone();
+                let request_id = request_id.clone();
+                tokio::spawn(async move {
+                    dispatcher
+                        .deliver(&subscription, id, event_type, &body, request_id.as_deref())
+                        .await;
+                    drop(permit);
+                });
+            }
+        }
+
+        Err(anyhow!(
+            "Message queue subscriber is broken for subject ( {} )",
+            T::SUBJECT
+        ))
+    }
+
+    async fn subscriptions(
+        &self,
+        event_type: WebhookEventType,
+    ) -> Result<Vec<WebhookSubscription>> {
+        let filters = [Filter::Contains(
+            "event_types",
+            FilterValue::Text(event_type.as_str().to_string()),
+        )];
+        let mut subscriptions = Vec::new();
+        loop {
+            let page: Vec<WebhookSubscription> = self
+                .storage
+                .filter_paginate(
+                    &filters,
+                    None,
+                    SUBSCRIPTIONS_PAGE_SIZE,
+                    subscriptions.len() as i64,
+                )
+                .await?;
+            let last = (page.len() as i64) < SUBSCRIPTIONS_PAGE_SIZE;
+            subscriptions.extend(page);
+            if last {
+                return Ok(subscriptions);
+            }
+        }
+    }
+
+    async fn deliver(
+        &self,
+        subscription: &WebhookSubscription,
+        id: Uuid,
+        event_type: WebhookEventType,
+        body: &[u8],
+        request_id: Option<&str>,
+    ) {
+        let mut backoff = INITIAL_RETRY_BACKOFF;
+        for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+            let failure = match self
+                .attempt(subscription, id, event_type, body, request_id)
+                .await
+            {
+                Ok(()) => {
+                    self.metrics
+                        .webhook_deliveries
+                        .with_label_values(&[event_type.as_str(), "delivered"])
+                        .inc();
+                    return;
+                }
+                Err(failure) => failure,
+            };
+
+            self.metrics
+                .webhook_failures
+                .with_label_values(&[event_type.as_str(), failure.reason()])
+                .inc();
+            tracing::warn!(
+                "Webhook delivery {id} to ( {} ) failed on attempt {attempt}: {failure}",
+                subscription.url
+            );
+            if !failure.is_retryable() || attempt == MAX_DELIVERY_ATTEMPTS {
+                break;
+            }
+            sleep(backoff).await;
+            backoff *= 2;
+        }
+
+        self.metrics
+            .webhook_deliveries
+            .with_label_values(&[event_type.as_str(), "failed"])
+            .inc();
+        tracing::error!(
+            "Gave up webhook delivery {id} to ( {} ) of subscription {}",
+            subscription.url,
+            subscription.id
+        );
+    }
+
+    async fn attempt(
+        &self,
+        subscription: &WebhookSubscription,
+        id: Uuid,
+        event_type: WebhookEventType,
+        body: &[u8],
+        request_id: Option<&str>,
+    ) -> Result<(), HttpFailure> {
+        let url =
+            Url::parse(&subscription.url).map_err(|e| HttpFailure::Forbidden(e.to_string()))?;
+        check_public_destination(&url)
+            .await
+            .map_err(|e| HttpFailure::Forbidden(e.to_string()))?;
+
+        let timestamp = Utc::now().timestamp();
+        let mut request = self.client.post(url);
+        // Receivers can correlate the delivery with the request that caused the event.
+        if let Some(request_id) = request_id {
+            request = request.header(REQUEST_ID_HEADER, request_id);
+        }
+        let response = request
+            .header(CONTENT_TYPE, "application/json")
+            .header(WEBHOOK_ID_HEADER, id.to_string())
+            .header(WEBHOOK_EVENT_HEADER, event_type.as_str())
+            .header(WEBHOOK_TIMESTAMP_HEADER, timestamp.to_string())
+            .header(
+                WEBHOOK_SIGNATURE_HEADER,
+                sign(&subscription.secret, timestamp, body),
+            )
+            .body(body.to_vec())
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(HttpFailure::Status(response.status()));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shared_states::RssItem;
+
+    #[test]
+    fn test_sign() {
+        let body = br#"{"id":"1"}"#;
+        let signature = sign("top-secret-value", 1716300000, body);
+
+        let mut mac = HmacSha256::new_from_slice(b"top-secret-value").unwrap();
+        mac.update(b"1716300000.{\"id\":\"1\"}");
+        let expected = mac.finalize().into_bytes();
+
+        assert_eq!(signature, format!("sha256={}", hex::encode(expected)));
+        assert_ne!(signature, sign("top-secret-value", 1716300001, body));
+        assert_ne!(signature, sign("other-secret-value", 1716300000, body));
+    }
+
+    #[test]
+    fn test_webhook_payload() {
+        let item = RssItem {
+            hash: "abc".to_string(),
+            ..Default::default()
+        };
+
+        let payload = WebhookPayload::new(WebhookEventType::RssItem, &item).unwrap();
+        let json = serde_json::to_value(&payload).unwrap();
+
+        assert_eq!(json["event_type"], "rss_item");
+        assert_eq!(json["data"]["hash"], "abc");
+        assert_eq!(WebhookEventType::Sentiment.as_str(), "sentiment");
+    }
+}
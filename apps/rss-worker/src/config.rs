@@ -1,22 +1,346 @@
-use anyhow::{Context, Result};
+use crate::fetch::{FeedClient, http_client};
+use anyhow::{Context, Result, anyhow};
+use reqwest::header::{HeaderName, HeaderValue};
 use serde::{Deserialize, Serialize};
-use std::{env, time::Duration};
+use shared_states::{DEFAULT_USER_AGENT, normalize_categories, parse_opml};
+use std::{collections::BTreeMap, env, time::Duration};
+
+/// EnrichmentStages are the enrichment steps applied to the items of a feed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EnrichmentStages {
+    /// Use the `content:encoded` of the item as the article, feeds with full content need no
+    /// article fetches.
+    pub feed_content: bool,
+    /// Fetch the linked page and extract the article when the item carries no content.
+    pub article_extraction: bool,
+    /// Detect the item language, left empty otherwise.
+    pub language_detection: bool,
+}
+
+impl Default for EnrichmentStages {
+    fn default() -> Self {
+        Self {
+            feed_content: false,
+            article_extraction: true,
+            language_detection: true,
+        }
+    }
+}
+
+impl EnrichmentStages {
+    /// Parses the enabled stages given as `none` or a `+` separated list of `content`, `article`
+    /// and `language`.
+    fn parse(value: &str) -> Result<Self> {
+        let mut stages = Self {
+            feed_content: false,
+            article_extraction: false,
+            language_detection: false,
+        };
+        for stage in value.split('+').map(str::trim) {
+            match stage {
+                "none" => {}
+                "content" => stages.feed_content = true,
+                "article" => stages.article_extraction = true,
+                "language" => stages.language_detection = true,
+                _ => return Err(anyhow!("Unknown enrichment stage ( {stage} )")),
+            }
+        }
+        Ok(stages)
+    }
+}
+
+/// ConfiguredFeed is a feed of the `RSS_FEEDS_FILE` file, the `RSS_URLS` configuration or the
+/// `RSS_OPML` file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConfiguredFeed {
+    pub url: String,
+    /// Time between polls of the feed, the default interval if not given.
+    pub interval: Option<Duration>,
+    /// Number of the latest items processed per poll, the default count if not given.
+    #[serde(default)]
+    pub items_count: Option<usize>,
+    /// Category slugs the items of the feed are tagged with, the tags or OPML folders of the feed.
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub stages: EnrichmentStages,
+    /// Headers sent with requests of the feed, e.g. `Authorization` of paywalled feeds.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// The URL is a sitemap of a site publishing no feed, its new pages are the items.
+    #[serde(default)]
+    pub sitemap: bool,
+}
+
+impl ConfiguredFeed {
+    /// Parses a feed given as `<url>`, `<url>|<interval seconds>` or
+    /// `<url>|<interval seconds>|<stages>`, the interval may be left empty.
+    fn parse(value: &str) -> Result<Self> {
+        let mut parts = value.splitn(3, '|');
+        let url = parts.next().unwrap_or_default();
+        let interval = match parts
+            .next()
+            .map(str::trim)
+            .filter(|seconds| !seconds.is_empty())
+        {
+            Some(seconds) => Some(Duration::from_secs(
+                seconds
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|seconds| *seconds > 0)
+                    .ok_or_else(|| anyhow!("Invalid polling interval of feed ( {value} )"))?,
+            )),
+            None => None,
+        };
+        let stages = match parts.next() {
+            Some(stages) => EnrichmentStages::parse(stages)
+                .with_context(|| format!("Invalid enrichment stages of feed ( {value} )"))?,
+            None => EnrichmentStages::default(),
+        };
+
+        Ok(Self {
+            url: url.trim().to_string(),
+            interval,
+            stages,
+            ..Default::default()
+        })
+    }
+}
+
+/// FeedsFile is the `RSS_FEEDS_FILE` TOML file, a `[[feeds]]` table per feed.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FeedsFile {
+    #[serde(default)]
+    feeds: Vec<FeedsFileEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FeedsFileEntry {
+    url: String,
+    interval_seconds: Option<u64>,
+    items_count: Option<usize>,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Enrichment stages as in `RSS_URLS`, e.g. `content+language`.
+    stages: Option<String>,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    #[serde(default)]
+    sitemap: bool,
+}
+
+impl FeedsFileEntry {
+    fn into_feed(self) -> Result<ConfiguredFeed> {
+        let url = self.url.trim().to_string();
+        if url.is_empty() {
+            return Err(anyhow!("Feed without url"));
+        }
+        let interval = match self.interval_seconds {
+            Some(0) => return Err(anyhow!("Invalid polling interval of feed ( {url} )")),
+            seconds => seconds.map(Duration::from_secs),
+        };
+        if self.items_count == Some(0) {
+            return Err(anyhow!("Invalid items count of feed ( {url} )"));
+        }
+        let stages = match self.stages.as_deref() {
+            Some(stages) => EnrichmentStages::parse(stages)
+                .with_context(|| format!("Invalid enrichment stages of feed ( {url} )"))?,
+            None => EnrichmentStages::default(),
+        };
+        if self.sitemap && !stages.article_extraction {
+            return Err(anyhow!(
+                "Sitemap ( {url} ) needs the article stage, its pages carry no content"
+            ));
+        }
+        for (name, value) in &self.headers {
+            HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| format!("Invalid header name {name} of feed ( {url} )"))?;
+            HeaderValue::from_str(value)
+                .with_context(|| format!("Invalid value of header {name} of feed ( {url} )"))?;
+        }
+
+        Ok(ConfiguredFeed {
+            url,
+            interval,
+            items_count: self.items_count,
+            categories: normalize_categories(&self.tags),
+            stages,
+            headers: self.headers,
+            sitemap: self.sitemap,
+        })
+    }
+}
+
+/// EnclosureConfig is the MinIO bucket audio enclosures of items are archived in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnclosureConfig {
+    /// MinIO endpoint, the scheme is added to endpoints given as `host:port`.
+    pub endpoint: String,
+    pub access_key: String,
+    pub secret_key: String,
+    pub bucket: String,
+    pub region: String,
+    pub use_ssl: bool,
+    /// Largest archived enclosure, larger enclosures are skipped.
+    pub max_bytes: usize,
+    /// Time the download of a single enclosure may take.
+    pub timeout: Duration,
+}
+
+impl EnclosureConfig {
+    /// Reads the configuration, None unless `RSS_ARCHIVE_ENCLOSURES` is enabled.
+    fn try_from_env() -> Result<Option<Self>> {
+        let enabled: bool = env::var("RSS_ARCHIVE_ENCLOSURES")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse()
+            .context("RSS_ARCHIVE_ENCLOSURES must be true or false")?;
+        if !enabled {
+            return Ok(None);
+        }
+
+        let max_bytes = match env::var("RSS_ENCLOSURE_MAX_BYTES") {
+            Ok(value) => value
+                .parse::<usize>()
+                .ok()
+                .filter(|bytes| *bytes > 0)
+                .context("RSS_ENCLOSURE_MAX_BYTES must be a positive number")?,
+            Err(_) => 209_715_200,
+        };
+        let timeout = match env::var("RSS_ENCLOSURE_TIMEOUT_SECONDS") {
+            Ok(value) => value
+                .parse::<u64>()
+                .ok()
+                .filter(|seconds| *seconds > 0)
+                .context("RSS_ENCLOSURE_TIMEOUT_SECONDS must be a positive number")?,
+            Err(_) => 600,
+        };
+
+        Ok(Some(Self {
+            endpoint: env::var("MINIO_ENDPOINT")
+                .unwrap_or_else(|_| "http://localhost:9000".to_string()),
+            access_key: env::var("MINIO_ACCESS_KEY").unwrap_or_else(|_| "minioadmin".to_string()),
+            secret_key: env::var("MINIO_SECRET_KEY")
+                .unwrap_or_else(|_| "minioadmin123".to_string()),
+            bucket: env::var("MINIO_BUCKET").unwrap_or_else(|_| "batobite-bucket".to_string()),
+            region: env::var("MINIO_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            use_ssl: env::var("MINIO_USE_SSL")
+                .unwrap_or_else(|_| "false".to_string())
+                .parse()
+                .unwrap_or(false),
+            max_bytes,
+            timeout: Duration::from_secs(timeout),
+        }))
+    }
+}
+
+/// TelemetryConfig is where the traces of the fetch cycles are exported to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// OTLP gRPC endpoint of the collector, None if traces are not exported.
+    pub otlp_endpoint: Option<String>,
+    pub service_name: String,
+    /// Share of the fetch cycles traced in range `0.0..=1.0`.
+    pub sample_rate: f64,
+}
+
+impl TelemetryConfig {
+    pub fn try_from_env() -> Result<Self> {
+        let otlp_endpoint = env::var("RSS_OTLP_ENDPOINT")
+            .or_else(|_| env::var("OTEL_EXPORTER_OTLP_ENDPOINT"))
+            .ok()
+            .map(|endpoint| endpoint.trim().to_string())
+            .filter(|endpoint| !endpoint.is_empty());
+        let sample_rate = match env::var("RSS_TRACE_SAMPLE_RATE") {
+            Ok(value) => value
+                .parse::<f64>()
+                .ok()
+                .filter(|rate| (0.0..=1.0).contains(rate))
+                .context("RSS_TRACE_SAMPLE_RATE must be a number from 0 to 1")?,
+            Err(_) => 1.0,
+        };
+
+        Ok(Self {
+            otlp_endpoint,
+            service_name: env::var("RSS_TELEMETRY_SERVICE_NAME")
+                .unwrap_or_else(|_| "rss-worker".to_string()),
+            sample_rate,
+        })
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RssConfig {
-    pub rss_urls: Vec<String>,
+    /// Feeds polled in addition to the feeds managed through the api-server, the feeds of the
+    /// feeds file, `RSS_URLS` and the OPML file once loaded with [`RssConfig::load_feeds`].
+    pub feeds: Vec<ConfiguredFeed>,
+    /// Feeds of `RSS_URLS`, kept for deployments without a feeds file.
+    pub url_feeds: Vec<ConfiguredFeed>,
+    /// Path of the TOML file of the polled feeds, reloaded on SIGHUP.
+    pub feeds_file: Option<String>,
+    /// Path or URL of an OPML file whose feeds are polled as well.
+    pub opml: Option<String>,
+    /// Interval of feeds without their own interval.
     pub interval: Duration,
+    /// Shortest interval polling of busy feeds speeds up to.
+    pub min_interval: Duration,
+    /// Longest interval polling of quiet feeds backs off to.
+    pub max_interval: Duration,
     pub items_count: usize,
+    /// Time processed item hashes are remembered for deduplication.
+    pub dedup_ttl: Duration,
+    /// Time a single attempt to fetch a feed may take.
+    pub fetch_timeout: Duration,
+    /// Number of attempts to fetch a feed, transient failures are retried.
+    pub fetch_attempts: u32,
+    /// User-Agent header of feed and article requests.
+    pub user_agent: String,
+    /// URL of the proxy feed and article requests go through.
+    pub proxy: Option<String>,
+    /// Idle connections kept open per host by the shared HTTP client.
+    pub pool_max_idle_per_host: usize,
+    /// Time idle connections of the shared HTTP client are kept open.
+    pub pool_idle_timeout: Duration,
+    /// Number of polls failed in a row after which a feed is dead.
+    pub dead_feed_failures: u32,
+    /// Time without new items after which a feed is dead.
+    pub dead_feed_silence: Duration,
+    /// Time running polls get to finish on shutdown.
+    pub shutdown_timeout: Duration,
+    /// Number of articles extracted at the same time across all feeds.
+    pub extraction_workers: usize,
+    /// Number of items waiting for article extraction before polls wait for the queue.
+    pub extraction_queue_size: usize,
+    /// Bucket audio enclosures are archived in, None if they are not archived.
+    pub enclosures: Option<EnclosureConfig>,
+    /// Identifier of this replica in the leases of the feeds it polls.
+    pub instance_id: String,
+    /// JetStream stream items are stored in, items are published with server acks if set.
+    pub jetstream_stream: Option<String>,
+    /// Time the JetStream stream keeps items.
+    pub jetstream_max_age: Duration,
 }
 
 impl RssConfig {
     pub fn try_from_env() -> Result<Self> {
-        let rss_urls = env::var("RSS_URLS")
-            .context("RSS_URLS must be set")?
+        let url_feeds: Vec<_> = env::var("RSS_URLS")
+            .unwrap_or_default()
             .split(',')
-            .map(|s| s.trim().to_string())
+            .map(str::trim)
             .filter(|s| !s.is_empty())
-            .collect();
+            .map(ConfiguredFeed::parse)
+            .collect::<Result<_>>()?;
+
+        let feeds_file = env::var("RSS_FEEDS_FILE")
+            .ok()
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty());
+
+        let opml = env::var("RSS_OPML")
+            .ok()
+            .map(|opml| opml.trim().to_string())
+            .filter(|opml| !opml.is_empty());
 
         let interval = Duration::from_secs(
             env::var("RSS_INTERVAL_SECONDS")
@@ -24,15 +348,357 @@ impl RssConfig {
                 .parse::<u64>()?,
         );
 
+        let seconds = |name: &str, default: u64| -> Result<Duration> {
+            match env::var(name) {
+                Ok(value) => {
+                    Ok(Duration::from_secs(value.parse().with_context(|| {
+                        format!("{name} must be a valid number")
+                    })?))
+                }
+                Err(_) => Ok(Duration::from_secs(default)),
+            }
+        };
+        let min_interval = seconds("RSS_MIN_INTERVAL_SECONDS", 60)?;
+        let max_interval = seconds("RSS_MAX_INTERVAL_SECONDS", 86_400)?;
+        let fetch_timeout = seconds("RSS_FETCH_TIMEOUT_SECONDS", 30)?;
+        let fetch_attempts = match env::var("RSS_FETCH_ATTEMPTS") {
+            Ok(value) => value
+                .parse::<u32>()
+                .ok()
+                .filter(|attempts| *attempts > 0)
+                .context("RSS_FETCH_ATTEMPTS must be a positive number")?,
+            Err(_) => 3,
+        };
+        let user_agent = env::var("RSS_USER_AGENT")
+            .ok()
+            .filter(|user_agent| !user_agent.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string());
+        let proxy = env::var("RSS_PROXY_URL")
+            .ok()
+            .map(|proxy| proxy.trim().to_string())
+            .filter(|proxy| !proxy.is_empty());
+        let pool_max_idle_per_host = match env::var("RSS_HTTP_POOL_MAX_IDLE_PER_HOST") {
+            Ok(value) => value
+                .parse::<usize>()
+                .context("RSS_HTTP_POOL_MAX_IDLE_PER_HOST must be a valid number")?,
+            Err(_) => 8,
+        };
+        let pool_idle_timeout = seconds("RSS_HTTP_POOL_IDLE_SECONDS", 90)?;
+        let dead_feed_failures = match env::var("RSS_DEAD_FEED_FAILURES") {
+            Ok(value) => value
+                .parse::<u32>()
+                .ok()
+                .filter(|failures| *failures > 0)
+                .context("RSS_DEAD_FEED_FAILURES must be a positive number")?,
+            Err(_) => 10,
+        };
+        let dead_feed_silence = Duration::from_secs(
+            match env::var("RSS_DEAD_FEED_DAYS") {
+                Ok(value) => value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|days| *days > 0)
+                    .context("RSS_DEAD_FEED_DAYS must be a positive number")?,
+                Err(_) => 30,
+            }
+            .saturating_mul(86_400),
+        );
+        let dedup_ttl = Duration::from_secs(
+            match env::var("RSS_DEDUP_TTL_DAYS") {
+                Ok(value) => value
+                    .parse::<u64>()
+                    .ok()
+                    .filter(|days| *days > 0)
+                    .context("RSS_DEDUP_TTL_DAYS must be a positive number")?,
+                Err(_) => 90,
+            }
+            .saturating_mul(86_400),
+        );
+        let shutdown_timeout = seconds("RSS_SHUTDOWN_TIMEOUT_SECONDS", 30)?;
+        let extraction_workers = match env::var("RSS_EXTRACTION_WORKERS") {
+            Ok(value) => value
+                .parse::<usize>()
+                .ok()
+                .filter(|workers| *workers > 0)
+                .context("RSS_EXTRACTION_WORKERS must be a positive number")?,
+            Err(_) => 8,
+        };
+        let extraction_queue_size = match env::var("RSS_EXTRACTION_QUEUE_SIZE") {
+            Ok(value) => value
+                .parse::<usize>()
+                .ok()
+                .filter(|size| *size > 0)
+                .context("RSS_EXTRACTION_QUEUE_SIZE must be a positive number")?,
+            Err(_) => 100,
+        };
+        let enclosures = EnclosureConfig::try_from_env()?;
+        let jetstream_stream = env::var("RSS_JETSTREAM_STREAM")
+            .ok()
+            .map(|stream| stream.trim().to_string())
+            .filter(|stream| !stream.is_empty());
+        let jetstream_max_age = seconds("RSS_JETSTREAM_MAX_AGE_SECONDS", 7 * 86_400)?;
+        // Replicas in Kubernetes or Docker get their pod or container name as the hostname.
+        let instance_id = env::var("RSS_INSTANCE_ID")
+            .or_else(|_| env::var("HOSTNAME"))
+            .ok()
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| format!("rss-worker-{:016x}", rand::random::<u64>()));
+        if min_interval.is_zero() || min_interval > max_interval {
+            return Err(anyhow!(
+                "RSS_MIN_INTERVAL_SECONDS must be positive and at most RSS_MAX_INTERVAL_SECONDS"
+            ));
+        }
+
         let items_count = env::var("RSS_ITEMS_COUNT")
             .context("RSS_ITEMS_COUNT must be set")?
             .parse()
             .context("RSS_ITEMS_COUNT must be a valid number")?;
 
         Ok(Self {
-            rss_urls,
+            feeds: url_feeds.clone(),
+            url_feeds,
+            feeds_file,
+            opml,
             interval,
+            min_interval,
+            max_interval,
             items_count,
+            dedup_ttl,
+            fetch_timeout,
+            fetch_attempts,
+            user_agent,
+            proxy,
+            pool_max_idle_per_host,
+            pool_idle_timeout,
+            dead_feed_failures,
+            dead_feed_silence,
+            shutdown_timeout,
+            extraction_workers,
+            extraction_queue_size,
+            enclosures,
+            instance_id,
+            jetstream_stream,
+            jetstream_max_age,
         })
     }
+
+    /// Loads the polled feeds from the feeds file, `RSS_URLS` and the OPML file, in this order of
+    /// precedence for feeds listed more than once.
+    ///
+    /// # Returns
+    /// The feeds, or an error if the feeds file or the OPML file cannot be read or parsed.
+    pub async fn load_feeds(&self) -> Result<Vec<ConfiguredFeed>> {
+        let mut feeds = match self.feeds_file.as_deref() {
+            Some(path) => load_feeds_file(path).await?,
+            None => Vec::new(),
+        };
+        let configured = |feeds: &[ConfiguredFeed], url: &str| feeds.iter().any(|f| f.url == url);
+
+        for feed in &self.url_feeds {
+            if !configured(&feeds, &feed.url) {
+                feeds.push(feed.clone());
+            }
+        }
+        for feed in self.load_opml().await? {
+            if !configured(&feeds, &feed.url) {
+                feeds.push(feed);
+            }
+        }
+
+        Ok(feeds)
+    }
+
+    /// Feeds of the OPML file tagged with the categories of their folders, none without a file.
+    async fn load_opml(&self) -> Result<Vec<ConfiguredFeed>> {
+        let Some(source) = self.opml.as_deref() else {
+            return Ok(Vec::new());
+        };
+        let xml = if source.starts_with("http://") || source.starts_with("https://") {
+            let client = FeedClient::new(http_client(self)?, self);
+            String::from_utf8(client.fetch(source, &BTreeMap::new()).await?)
+                .with_context(|| format!("OPML from ( {source} ) is not valid UTF-8"))?
+        } else {
+            tokio::fs::read_to_string(source)
+                .await
+                .with_context(|| format!("Failed to read OPML file ( {source} )"))?
+        };
+        let opml_feeds =
+            parse_opml(&xml).with_context(|| format!("Failed to parse OPML from ( {source} )"))?;
+
+        Ok(opml_feeds
+            .into_iter()
+            .map(|feed| ConfiguredFeed {
+                url: feed.xml_url,
+                categories: feed.categories,
+                ..Default::default()
+            })
+            .collect())
+    }
+}
+
+/// Reads the feeds of the TOML feeds file.
+async fn load_feeds_file(path: &str) -> Result<Vec<ConfiguredFeed>> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read feeds file ( {path} )"))?;
+    let file: FeedsFile = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse feeds file ( {path} )"))?;
+
+    let mut feeds: Vec<ConfiguredFeed> = Vec::new();
+    for entry in file.feeds {
+        let feed = entry
+            .into_feed()
+            .with_context(|| format!("Invalid feed in feeds file ( {path} )"))?;
+        if feeds.iter().any(|configured| configured.url == feed.url) {
+            return Err(anyhow!(
+                "Feed ( {} ) is listed twice in feeds file ( {path} )",
+                feed.url
+            ));
+        }
+        feeds.push(feed);
+    }
+
+    Ok(feeds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feeds_file(content: &str) -> Result<Vec<ConfiguredFeed>> {
+        let file: FeedsFile = toml::from_str(content)?;
+        file.feeds
+            .into_iter()
+            .map(FeedsFileEntry::into_feed)
+            .collect()
+    }
+
+    #[test]
+    fn test_configured_feed_parse() {
+        let feed =
+            ConfiguredFeed::parse(" https://news.example.com/rss|600|content+language").unwrap();
+
+        assert_eq!(feed.url, "https://news.example.com/rss");
+        assert_eq!(feed.interval, Some(Duration::from_secs(600)));
+        assert!(feed.stages.feed_content && feed.stages.language_detection);
+        assert!(!feed.stages.article_extraction);
+
+        let feed = ConfiguredFeed::parse("https://news.example.com/rss||none").unwrap();
+        assert_eq!(feed.interval, None);
+        assert!(!feed.stages.article_extraction);
+
+        let feed = ConfiguredFeed::parse("https://news.example.com/rss").unwrap();
+        assert_eq!(feed.stages, EnrichmentStages::default());
+
+        assert!(ConfiguredFeed::parse("https://news.example.com/rss|0").is_err());
+        assert!(ConfiguredFeed::parse("https://news.example.com/rss|ten").is_err());
+        assert!(ConfiguredFeed::parse("https://news.example.com/rss|60|video").is_err());
+    }
+
+    #[test]
+    fn test_feeds_file_entries() {
+        let feeds = feeds_file(
+            r#"
+            [[feeds]]
+            url = "https://news.example.com/rss"
+            interval_seconds = 300
+            items_count = 5
+            tags = ["World News"]
+            stages = "content+language"
+            headers = { Authorization = "Bearer token" }
+
+            [[feeds]]
+            url = "https://blog.example.com/sitemap.xml"
+            sitemap = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(feeds.len(), 2);
+        assert_eq!(feeds[0].interval, Some(Duration::from_secs(300)));
+        assert_eq!(feeds[0].items_count, Some(5));
+        assert_eq!(feeds[0].categories, vec!["world-news"]);
+        assert_eq!(feeds[0].headers["Authorization"], "Bearer token");
+        assert!(feeds[1].sitemap);
+
+        for invalid in [
+            "[[feeds]]\nurl = \" \"",
+            "[[feeds]]\nurl = \"https://a.example.com\"\ninterval_seconds = 0",
+            "[[feeds]]\nurl = \"https://a.example.com\"\nitems_count = 0",
+            "[[feeds]]\nurl = \"https://a.example.com\"\nstages = \"video\"",
+            "[[feeds]]\nurl = \"https://a.example.com\"\nsitemap = true\nstages = \"language\"",
+            "[[feeds]]\nurl = \"https://a.example.com\"\nheaders = { \"Bad Name\" = \"x\" }",
+            "[[feeds]]\nurl = \"https://a.example.com\"\nunknown = true",
+        ] {
+            assert!(feeds_file(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    /// Reads the configuration with the variables set, all other `RSS_` variables unset.
+    fn config_with(vars: &[(&str, &str)]) -> Result<RssConfig> {
+        // SAFETY: this is the only test of the binary reading or writing the environment.
+        unsafe {
+            for (name, _) in env::vars().filter(|(name, _)| name.starts_with("RSS_")) {
+                env::remove_var(name);
+            }
+            for (name, value) in vars {
+                env::set_var(name, value);
+            }
+        }
+        RssConfig::try_from_env()
+    }
+
+    #[test]
+    fn test_rss_config_try_from_env() {
+        let required = [("RSS_INTERVAL_SECONDS", "300"), ("RSS_ITEMS_COUNT", "10")];
+
+        let config = config_with(&[
+            ("RSS_INTERVAL_SECONDS", "300"),
+            ("RSS_ITEMS_COUNT", "10"),
+            (
+                "RSS_URLS",
+                "https://a.example.com/rss|60, https://b.example.com/rss",
+            ),
+            ("RSS_INSTANCE_ID", "replica-1"),
+        ])
+        .unwrap();
+        assert_eq!(config.url_feeds.len(), 2);
+        assert_eq!(config.feeds, config.url_feeds);
+        assert_eq!(config.interval, Duration::from_secs(300));
+        assert_eq!(config.items_count, 10);
+        assert_eq!(config.min_interval, Duration::from_secs(60));
+        assert_eq!(config.max_interval, Duration::from_secs(86_400));
+        assert_eq!(config.fetch_attempts, 3);
+        assert_eq!(config.dead_feed_silence, Duration::from_secs(30 * 86_400));
+        assert_eq!(config.user_agent, DEFAULT_USER_AGENT);
+        assert_eq!(config.instance_id, "replica-1");
+        assert!(config.enclosures.is_none());
+        assert!(config.jetstream_stream.is_none());
+
+        let mut vars = required.to_vec();
+        vars.push(("RSS_DEAD_FEED_DAYS", "18446744073709551615"));
+        assert_eq!(
+            config_with(&vars).unwrap().dead_feed_silence,
+            Duration::from_secs(u64::MAX)
+        );
+
+        assert!(config_with(&required[..1]).is_err());
+        assert!(config_with(&required[1..]).is_err());
+        for invalid in [
+            ("RSS_URLS", "https://a.example.com/rss|0"),
+            ("RSS_MIN_INTERVAL_SECONDS", "0"),
+            ("RSS_MIN_INTERVAL_SECONDS", "100000"),
+            ("RSS_FETCH_ATTEMPTS", "0"),
+            ("RSS_DEAD_FEED_FAILURES", "-1"),
+            ("RSS_DEAD_FEED_DAYS", "0"),
+            ("RSS_DEDUP_TTL_DAYS", "0"),
+            ("RSS_EXTRACTION_WORKERS", "0"),
+            ("RSS_ARCHIVE_ENCLOSURES", "yes"),
+        ] {
+            let mut vars = required.to_vec();
+            vars.push(invalid);
+            assert!(config_with(&vars).is_err(), "{invalid:?}");
+        }
+    }
 }
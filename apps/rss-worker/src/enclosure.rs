@@ -0,0 +1,174 @@
+use crate::config::EnclosureConfig;
+use anyhow::{Context, Result, anyhow};
+use aws_sdk_s3::{
+    Client as S3Client,
+    config::{Builder, Credentials, Region},
+    primitives::ByteStream,
+};
+use reqwest::{Client, Url, header::CONTENT_TYPE};
+use shared_states::{
+    check_public_url, endpoint_url, feed_hash, public_client_builder, read_body_capped,
+};
+use std::time::Duration;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_REDIRECTS: usize = 5;
+
+/// EnclosureArchiver downloads audio enclosures of items, e.g. podcast episodes, to MinIO so they
+/// can be transcribed or analyzed without fetching them from the publisher again.
+///
+/// Enclosure URLs come from feeds, so they are requested only if they and every redirect resolve
+/// to public addresses, see [`public_client_builder`]. The proxy of the worker is not used for
+/// them.
+pub struct EnclosureArchiver {
+    client: Client,
+    objects: S3Client,
+    bucket: String,
+    max_bytes: usize,
+    timeout: Duration,
+}
+
+impl EnclosureArchiver {
+    /// Create a new archiver, no request is made until the first enclosure is archived.
+    ///
+    /// # Arguments
+    /// * `enclosures` - The bucket and limits of archived enclosures.
+    /// * `user_agent` - The User-Agent enclosures are downloaded with.
+    ///
+    /// # Returns
+    /// A new archiver, or an error if the HTTP client cannot be built.
+    pub fn new(enclosures: &EnclosureConfig, user_agent: &str) -> Result<Self> {
+        let credentials = Credentials::new(
+            &enclosures.access_key,
+            &enclosures.secret_key,
+            None,
+            None,
+            "minio-config",
+        );
+        let s3_config = Builder::new()
+            .behavior_version_latest()
+            .region(Region::new(enclosures.region.clone()))
+            .endpoint_url(endpoint_url(&enclosures.endpoint, enclosures.use_ssl))
+            .credentials_provider(credentials)
+            .force_path_style(true)
+            .build();
+
+        let client = public_client_builder(MAX_REDIRECTS)
+            .connect_timeout(CONNECT_TIMEOUT)
+            .user_agent(user_agent)
+            .build()?;
+
+        Ok(Self {
+            client,
+            objects: S3Client::from_conf(s3_config),
+            bucket: enclosures.bucket.clone(),
+            max_bytes: enclosures.max_bytes,
+            timeout: enclosures.timeout,
+        })
+    }
+
+    /// Download the enclosure and store it in the bucket.
+    ///
+    /// # Arguments
+    /// * `item_hash` - The hash of the item the enclosure is attached to.
+    /// * `source_url` - The URL of the enclosure.
+    ///
+    /// # Returns
+    /// The object key of the enclosure, None if it is not audio, or an error if it is not a public
+    /// http(s) URL, cannot be downloaded, is larger than the limit or cannot be stored.
+    pub async fn archive(&self, item_hash: &str, source_url: &str) -> Result<Option<String>> {
+        let url = Url::parse(source_url)?;
+        check_public_url(&url)?;
+
+        let response = self.client.get(url).timeout(self.timeout).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("Got HTTP status {}", response.status()));
+        }
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(';').next())
+            .map(|value| value.trim().to_lowercase())
+            .unwrap_or_default();
+        if !content_type.starts_with("audio/") {
+            return Ok(None);
+        }
+        let body = read_body_capped(response, self.max_bytes).await?;
+
+        let key = object_key(item_hash, source_url);
+        self.objects
+            .put_object()
+            .bucket(&self.bucket)
+            .key(&key)
+            .content_type(content_type)
+            .body(ByteStream::from(body))
+            .send()
+            .await
+            .with_context(|| format!("Cannot store object {key}"))?;
+
+        Ok(Some(key))
+    }
+}
+
+/// Key of the enclosure, stable for the item and source URL so archiving it again overwrites it.
+fn object_key(item_hash: &str, source_url: &str) -> String {
+    format!("enclosures/{item_hash}/{}", &feed_hash(source_url)[..32])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archiver() -> EnclosureArchiver {
+        let enclosures = EnclosureConfig {
+            endpoint: "minio:9000".to_string(),
+            access_key: "minioadmin".to_string(),
+            secret_key: "minioadmin123".to_string(),
+            bucket: "enclosures".to_string(),
+            region: "us-east-1".to_string(),
+            use_ssl: false,
+            max_bytes: 1024,
+            timeout: Duration::from_secs(5),
+        };
+        EnclosureArchiver::new(&enclosures, "rss-worker-test").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_archive_rejects_unsupported_schemes() {
+        let archiver = archiver();
+
+        for url in [
+            "ftp://cdn.example.com/episode.mp3",
+            "file:///etc/passwd",
+            "not a url",
+        ] {
+            assert!(archiver.archive("abc", url).await.is_err(), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_archive_rejects_private_hosts() {
+        let archiver = archiver();
+
+        for url in [
+            "http://127.0.0.1:9000/episode.mp3",
+            "http://169.254.169.254/latest/meta-data",
+            "http://10.0.0.5/episode.mp3",
+            "http://[::1]/episode.mp3",
+            "http://localhost:9000/episode.mp3",
+        ] {
+            let err = archiver.archive("abc", url).await.unwrap_err();
+            assert!(!err.to_string().contains("HTTP status"), "{url}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_object_key() {
+        let key = object_key("abc", "https://cdn.example.com/episode.mp3");
+
+        assert!(key.starts_with("enclosures/abc/"));
+        assert_eq!(key.len(), "enclosures/abc/".len() + 32);
+        assert_ne!(key, object_key("abc", "https://cdn.example.com/other.mp3"));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::config::EnrichmentStages;
+use crate::enclosure::EnclosureArchiver;
+use anyhow::{Result, anyhow};
+use reqwest::Url;
+use shared_states::{ExtractionError, HttpArticleFetcher, RssItem, parse_article_fragment};
+use std::sync::Arc;
+use tokio::sync::{Mutex, mpsc, oneshot};
+use tracing::{info, warn};
+
+/// ExtractionJob is an item waiting for its article to be extracted.
+struct ExtractionJob {
+    item: RssItem,
+    /// The `content:encoded` of the item, used instead of fetching the article if given.
+    content: Option<String>,
+    stages: EnrichmentStages,
+    done: oneshot::Sender<Extracted>,
+}
+
+/// Extracted is an item enriched by the extraction pool.
+pub struct Extracted {
+    pub item: RssItem,
+    /// Link of the item if robots.txt disallowed extracting its article.
+    pub skipped_by_robots: Option<String>,
+}
+
+/// ExtractionPool extracts articles and archives enclosures of items on a fixed number of tasks
+/// fed by a bounded queue, so a slow publisher page delays only the items waiting behind it and
+/// not the rest of the feed.
+pub struct ExtractionPool {
+    jobs: mpsc::Sender<ExtractionJob>,
+}
+
+impl ExtractionPool {
+    /// Start the tasks of the pool, they stop once the pool is dropped and the queue drained.
+    ///
+    /// # Arguments
+    /// * `workers` - Number of items extracted at the same time.
+    /// * `queue_size` - Number of items waiting for extraction, submitting more waits.
+    /// * `fetcher` - The fetcher used for article extraction.
+    /// * `enclosures` - The archiver of audio enclosures, None if they are not archived.
+    ///
+    /// # Returns
+    /// The pool.
+    pub fn start(
+        workers: usize,
+        queue_size: usize,
+        fetcher: Arc<HttpArticleFetcher>,
+        enclosures: Option<Arc<EnclosureArchiver>>,
+    ) -> Self {
+        let (jobs, receiver) = mpsc::channel::<ExtractionJob>(queue_size.max(1));
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            let fetcher = fetcher.clone();
+            let enclosures = enclosures.clone();
+            tokio::spawn(async move {
+                loop {
+                    let Some(job) = receiver.lock().await.recv().await else {
+                        break;
+                    };
+                    let extracted = extract(job.item, job.content, job.stages, &fetcher).await;
+                    let extracted = archive_enclosure(extracted, enclosures.as_deref()).await;
+                    // The poll stops waiting for its items once it is aborted on shutdown.
+                    let _ = job.done.send(extracted);
+                }
+            });
+        }
+
+        Self { jobs }
+    }
+
+    /// Queue the item for extraction, waiting while the queue is full.
+    ///
+    /// # Arguments
+    /// * `item` - The item to enrich.
+    /// * `content` - The `content:encoded` of the item.
+    /// * `stages` - The enrichment stages of the feed of the item.
+    ///
+    /// # Returns
+    /// A receiver of the enriched item, or an error if the pool stopped.
+    pub async fn submit(
+        &self,
+        item: RssItem,
+        content: Option<String>,
+        stages: EnrichmentStages,
+    ) -> Result<oneshot::Receiver<Extracted>> {
+        let (done, extracted) = oneshot::channel();
+        self.jobs
+            .send(ExtractionJob {
+                item,
+                content,
+                stages,
+                done,
+            })
+            .await
+            .map_err(|_| anyhow!("Extraction pool stopped"))?;
+        Ok(extracted)
+    }
+}
+
+/// Use the feed content as the article if the feed has the content stage, otherwise fetch the
+/// article if it has the article stage.
+async fn extract(
+    mut item: RssItem,
+    content: Option<String>,
+    stages: EnrichmentStages,
+    fetcher: &HttpArticleFetcher,
+) -> Extracted {
+    let content = content.filter(|_| stages.feed_content).and_then(|content| {
+        parse_article_fragment(&content, Url::parse(&item.link).ok().as_ref()).ok()
+    });
+    let extracted = match content {
+        Some(article) => {
+            item.set_article(article, stages.language_detection);
+            Ok(())
+        }
+        None if stages.article_extraction => {
+            item.extract_article_from_source(fetcher, stages.language_detection)
+                .await
+        }
+        None => Ok(()),
+    };
+
+    let mut skipped_by_robots = None;
+    if let Err(e) = extracted {
+        match e.downcast_ref::<ExtractionError>() {
+            Some(ExtractionError::DisallowedByRobots(link)) => {
+                info!("Skipped article extraction by robots.txt for ( {link} )");
+                skipped_by_robots = Some(link.clone());
+            }
+            _ => warn!(
+                "Failed to extract article from source for item {} ( {} ): {e}",
+                item.hash, item.link
+            ),
+        }
+    }
+
+    Extracted {
+        item,
+        skipped_by_robots,
+    }
+}
+
+async fn archive_enclosure(
+    mut extracted: Extracted,
+    enclosures: Option<&EnclosureArchiver>,
+) -> Extracted {
+    let item = &mut extracted.item;
+    if let (Some(archiver), Some(enclosure_url)) = (enclosures, item.enclosure_url.as_deref()) {
+        match archiver.archive(&item.hash, enclosure_url).await {
+            Ok(key) => item.enclosure_object_key = key,
+            Err(e) => warn!(
+                "Failed to archive enclosure ( {enclosure_url} ) of item {}: {e}",
+                item.hash
+            ),
+        }
+    }
+    extracted
+}
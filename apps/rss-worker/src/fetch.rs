@@ -0,0 +1,146 @@
+use crate::config::RssConfig;
+use anyhow::{Result, anyhow};
+use reqwest::{Client, Proxy, redirect::Policy};
+use shared_states::HttpFailure;
+use std::{collections::BTreeMap, time::Duration};
+use tokio::time::sleep;
+use tracing::warn;
+
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(60);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+const MAX_REDIRECTS: usize = 5;
+
+/// Build the HTTP client shared by feed and article requests, so connections to a host
+/// are pooled and reused across polls. HTTP/2 is negotiated with hosts supporting it.
+///
+/// Requests set their own timeouts, the client only limits establishing connections.
+///
+/// # Arguments
+/// * `config` - The configuration of the worker.
+///
+/// # Returns
+/// The client, or an error if it cannot be built or the proxy URL is invalid.
+pub fn http_client(config: &RssConfig) -> Result<Client> {
+    let mut builder = Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .tcp_keepalive(TCP_KEEPALIVE)
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .pool_idle_timeout(config.pool_idle_timeout)
+        .http2_adaptive_window(true)
+        .redirect(Policy::limited(MAX_REDIRECTS))
+        .user_agent(config.user_agent.clone());
+    if let Some(proxy) = &config.proxy {
+        builder = builder.proxy(Proxy::all(proxy)?);
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Jitter between half and the whole backoff, so retries of feeds of one host spread out.
+fn jittered(backoff: Duration) -> Duration {
+    backoff.mul_f64(rand::random_range(0.5..=1.0))
+}
+
+/// Doubles the backoff up to the longest backoff.
+fn next_backoff(backoff: Duration) -> Duration {
+    (backoff * 2).min(MAX_RETRY_BACKOFF)
+}
+
+/// FeedClient downloads feeds, transient failures are retried with a jittered exponential
+/// backoff.
+#[derive(Debug, Clone)]
+pub struct FeedClient {
+    client: Client,
+    timeout: Duration,
+    attempts: u32,
+}
+
+impl FeedClient {
+    /// Create a new feed client with the timeout and retries of the configuration.
+    ///
+    /// # Arguments
+    /// * `client` - The shared HTTP client, see [`http_client`].
+    /// * `config` - The configuration of the worker.
+    ///
+    /// # Returns
+    /// A new feed client.
+    pub fn new(client: Client, config: &RssConfig) -> Self {
+        Self {
+            client,
+            timeout: config.fetch_timeout,
+            attempts: config.fetch_attempts.max(1),
+        }
+    }
+
+    /// Fetch the feed, retrying timeouts, connection failures, server errors and rate limiting.
+    ///
+    /// # Arguments
+    /// * `url` - The feed URL.
+    /// * `headers` - Headers of the feed configuration, e.g. `Authorization`.
+    ///
+    /// # Returns
+    /// The body of the feed, or an error once the failure is not transient or all attempts failed.
+    pub async fn fetch(&self, url: &str, headers: &BTreeMap<String, String>) -> Result<Vec<u8>> {
+        let mut backoff = INITIAL_RETRY_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            let failure = match self.attempt(url, headers).await {
+                Ok(body) => return Ok(body),
+                Err(failure) => failure,
+            };
+            if !failure.is_retryable() || attempt == self.attempts {
+                return Err(anyhow!(
+                    "Failed to fetch feed from ( {url} ) on attempt {attempt}: {failure}"
+                ));
+            }
+
+            let delay = jittered(backoff);
+            warn!(
+                "Fetching feed from ( {url} ) failed on attempt {attempt}, retrying in {delay:?}: {failure}"
+            );
+            sleep(delay).await;
+            backoff = next_backoff(backoff);
+            attempt += 1;
+        }
+    }
+
+    async fn attempt(
+        &self,
+        url: &str,
+        headers: &BTreeMap<String, String>,
+    ) -> Result<Vec<u8>, HttpFailure> {
+        let mut request = self.client.get(url).timeout(self.timeout);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(HttpFailure::Status(status));
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_backoff() {
+        for _ in 0..100 {
+            let delay = jittered(Duration::from_secs(4));
+            assert!((Duration::from_secs(2)..=Duration::from_secs(4)).contains(&delay));
+        }
+
+        let mut backoff = INITIAL_RETRY_BACKOFF;
+        let mut backoffs = Vec::new();
+        for _ in 0..8 {
+            backoffs.push(backoff.as_secs());
+            backoff = next_backoff(backoff);
+        }
+        assert_eq!(backoffs, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+    }
+}
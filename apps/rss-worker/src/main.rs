@@ -2,34 +2,86 @@ use crate::telemetry::init_telemetry;
 use anyhow::anyhow;
 use nats_middleware::{NatsConfig, NatsQueue};
 use redis_middleware::{Config as RedisConfig, RedisMiddleware};
+use shared_states::{HttpArticleFetcher, HttpFetcherConfig, RSS_QUEUE_NAME, RSS_RETRY_QUEUE_NAME};
 use std::{error::Error, sync::Arc};
 use tracing::info;
 
 mod config;
+mod enclosure;
+mod extraction;
+mod fetch;
 mod processor;
+mod schedule;
+mod shutdown;
+mod sitemap;
+mod state;
 mod telemetry;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<(), Box<dyn Error>> {
     dotenvy::dotenv().ok();
-    init_telemetry()?;
+    let telemetry_config = config::TelemetryConfig::try_from_env().map_err(|e| anyhow!("{e}"))?;
+    let tracer_provider = init_telemetry(&telemetry_config)?;
 
-    let worker_config = config::RssConfig::try_from_env().map_err(|e| anyhow!("{e}"))?;
+    let mut worker_config = config::RssConfig::try_from_env().map_err(|e| anyhow!("{e}"))?;
+    worker_config.feeds = worker_config
+        .load_feeds()
+        .await
+        .map_err(|e| anyhow!("{e:#}"))?;
     let nats_config = NatsConfig::from_env().map_err(|e| anyhow!("{e}"))?;
     let redis_config = RedisConfig::from_env().map_err(|e| anyhow!("{e}"))?;
     let queue = NatsQueue::new(nats_config)
         .await
         .map_err(|e| anyhow!("{e}"))?;
+    if let Some(stream) = &worker_config.jetstream_stream {
+        queue
+            .ensure_stream(
+                stream,
+                &[RSS_QUEUE_NAME, RSS_RETRY_QUEUE_NAME],
+                worker_config.jetstream_max_age,
+            )
+            .await
+            .map_err(|e| anyhow!("Cannot open JetStream stream {stream}: {e}"))?;
+        info!("Publishing items with acks of JetStream stream {stream}");
+    }
 
     let redis_middleware = RedisMiddleware::new(&redis_config.redis_url)?;
 
     info!(
-        "Starting RSS worker for feeds: {:?}",
-        worker_config.rss_urls
+        "Configured feeds: {:?}, more are loaded from the api-server",
+        worker_config
+            .feeds
+            .iter()
+            .map(|feed| feed.url.as_str())
+            .collect::<Vec<_>>()
+    );
+
+    // One client for feeds and articles, so connections are pooled across them.
+    let client = fetch::http_client(&worker_config).map_err(|e| anyhow!("{e}"))?;
+    let fetcher = HttpArticleFetcher::with_client(client.clone(), HttpFetcherConfig::default());
+
+    let mut processor = processor::Processor::new(
+        Arc::new(queue),
+        Arc::new(redis_middleware),
+        Arc::new(fetcher),
+        fetch::FeedClient::new(client, &worker_config),
     );
+    if let Some(enclosures) = &worker_config.enclosures {
+        let archiver = enclosure::EnclosureArchiver::new(enclosures, &worker_config.user_agent)
+            .map_err(|e| anyhow!("{e}"))?;
+        processor = processor.with_enclosure_archiver(archiver);
+        info!("Archiving audio enclosures in bucket {}", enclosures.bucket);
+    }
+    processor
+        .run(&worker_config, shutdown::signal(), shutdown::reloads())
+        .await?;
 
-    let processor = processor::Processor::new(Arc::new(queue), Arc::new(redis_middleware));
-    processor.run(&worker_config).await?;
+    // The exporter needs the runtime to send the last spans, the shutdown blocks until they are.
+    if let Some(provider) = tracer_provider
+        && let Ok(Err(e)) = tokio::task::spawn_blocking(move || provider.shutdown()).await
+    {
+        tracing::error!("Failed to export the last spans: {e}");
+    }
 
     Ok(())
 }
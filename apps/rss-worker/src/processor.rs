@@ -1,122 +1,1149 @@
-use crate::config::RssConfig;
+use crate::config::{ConfiguredFeed, RssConfig};
+use crate::enclosure::EnclosureArchiver;
+use crate::extraction::{Extracted, ExtractionPool};
+use crate::fetch::FeedClient;
+use crate::schedule::{FeedSchedule, PollOutcome};
+use crate::sitemap::sitemap_items;
+use crate::state::{FeedStateChanges, FeedStates};
 use anyhow::{Result, anyhow};
-use nats_middleware::NatsQueue;
+use chrono::Utc;
+use futures::{StreamExt, stream::FuturesUnordered};
+use nats_middleware::{NatsQueue, SubjectBuilder};
 use redis_middleware::RedisMiddleware;
-use reqwest::Client;
-use rss::Channel;
-use shared_states::{RSS_QUEUE_NAME, RssItem};
-use std::sync::Arc;
-use tokio::{spawn, time::sleep};
-use tracing::{error, info, warn};
+use rss::{Channel, extension::syndication::UpdatePeriod};
+use serde::Serialize;
+use shared_states::{
+    DeadFeedReason, Event, EventEnvelope, HttpArticleFetcher, RSS_FEEDS_REQUEST_SUBJECT,
+    RSS_WORKER_SUBJECT_PREFIX, RssChannelInfo, RssFeedAlert, RssFeedChange, RssFeedSource, RssItem,
+    RssItemRetry, RssWorkerHealth, canonical_url, decode_event, feed_hash, normalize_categories,
+    parse_feed,
+};
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{mpsc, watch},
+    task::JoinSet,
+    time::{Instant, sleep, sleep_until, timeout_at},
+};
+use tracing::{Instrument, Span, error, field, info, info_span, warn};
+
+/// Time a claimed item may take to be published before another poll may process it.
+const PENDING_TTL: Duration = Duration::from_secs(15 * 60);
+/// Prefix of the dedup cache keys of canonical article URLs.
+const CANONICAL_KEY_PREFIX: &str = "canonical:";
+/// Prefix of the cache keys of the leases replicas take on the feeds they poll.
+const FEED_LEASE_KEY_PREFIX: &str = "feed-lease:";
+/// Queue group of the retry subject, every retry is published again by a single replica.
+const RETRY_QUEUE_GROUP: &str = "rss-worker-retries";
+
+/// Polled is how a poll of a feed ended.
+enum Polled {
+    /// Another replica holds the lease of the feed.
+    Leased,
+    /// What the poll found, None if it failed.
+    Finished(Option<PollOutcome>),
+}
 
 /// Processor for RSS feeds.
 pub struct Processor {
     queue: Arc<NatsQueue>,
     cache: Arc<RedisMiddleware>,
+    fetcher: Arc<HttpArticleFetcher>,
+    client: FeedClient,
+    enclosures: Option<Arc<EnclosureArchiver>>,
 }
 
 impl Processor {
     /// Create a new instance of the processor.
     ///
+    /// # Arguments
+    /// * `queue` - The queue the items are published to.
+    /// * `cache` - The cache of already processed items.
+    /// * `fetcher` - The fetcher used for article extraction.
+    /// * `client` - The client feeds are fetched with.
+    ///
     /// # Returns
     /// A new instance of the processor.
-    pub fn new(queue: Arc<NatsQueue>, cache: Arc<RedisMiddleware>) -> Self {
-        Self { queue, cache }
+    pub fn new(
+        queue: Arc<NatsQueue>,
+        cache: Arc<RedisMiddleware>,
+        fetcher: Arc<HttpArticleFetcher>,
+        client: FeedClient,
+    ) -> Self {
+        Self {
+            queue,
+            cache,
+            fetcher,
+            client,
+            enclosures: None,
+        }
+    }
+
+    /// Archives audio enclosures of new items before they are published.
+    pub fn with_enclosure_archiver(mut self, archiver: EnclosureArchiver) -> Self {
+        self.enclosures = Some(Arc::new(archiver));
+        self
     }
 
     /// Run the processor.
     ///
+    /// Feeds are loaded from the api-server, feeds of the configuration are polled as well. Each
+    /// feed is polled on its own interval, see [`FeedSchedule`]. Sources publishing no feed are
+    /// polled through their sitemap, see [`sitemap_items`]. Feed changes published by the
+    /// api-server are applied while running. Items of configured feeds are tagged with the
+    /// categories of the feed. Items are deduplicated by their hash and by the canonical URL of
+    /// their link, so an article syndicated by several feeds is published once. Articles are
+    /// extracted by an [`ExtractionPool`] shared by all feeds. Items failing to be published are
+    /// sent to the `rss_items.retry` subject and published again after a delay, see
+    /// [`RssItemRetry`], each retry by one of the replicas. With a JetStream stream configured
+    /// items are published with server acks, so items lost to a network partition are retried as
+    /// well. Health requests on the `rss_worker.health` subject are replied with
+    /// [`RssWorkerHealth`].
+    ///
+    /// Every fetch cycle is traced with a span per polled feed and its fetch, parse, extract and
+    /// publish stages.
+    ///
+    /// Replicas polling the same feeds share them, each feed is polled by the replica holding its
+    /// lease in the cache and skipped by the others. The lease is renewed when the poll ends, so
+    /// the holder keeps the feed while it runs.
+    ///
+    /// Configured feeds are loaded again on every message of `reloads`, feeds removed from the
+    /// configuration stop being polled.
+    ///
+    /// Once `shutdown` completes no new polls start, running polls and retries get until the
+    /// shutdown timeout to publish their items and the queue is flushed. Retries waiting for their
+    /// delay are published right away.
+    ///
     /// # Arguments
     /// * `config` - The configuration for the processor.
+    /// * `shutdown` - Future completing when the processor has to stop.
+    /// * `reloads` - Requests to reload the configured feeds.
     ///
     /// # Returns
     /// A result indicating success or failure.
-    pub async fn run(&self, config: &RssConfig) -> Result<()> {
-        info!("Starting RSS worker for feeds: {:?}", config.rss_urls);
-        let items_count = config.items_count;
+    pub async fn run(
+        &self,
+        config: &RssConfig,
+        shutdown: impl Future<Output = ()>,
+        mut reloads: mpsc::Receiver<()>,
+    ) -> Result<()> {
+        let settings = Arc::new(config.clone());
+        let publisher = ItemPublisher::new(self.queue.clone(), config.jetstream_stream.is_some());
+        let states = Arc::new(FeedStates::open(&self.queue, config.dedup_ttl).await);
+        let extraction = Arc::new(ExtractionPool::start(
+            config.extraction_workers,
+            config.extraction_queue_size,
+            self.fetcher.clone(),
+            self.enclosures.clone(),
+        ));
+        let mut changes = self.queue.subscribe(RssFeedChange::SUBJECT).await?;
+        let health_subject = SubjectBuilder::new(RSS_WORKER_SUBJECT_PREFIX).health_check();
+        let mut health_requests = self.queue.subscribe(&health_subject).await?;
+        let mut retry_requests = self
+            .queue
+            .queue_subscribe(RssItemRetry::SUBJECT, RETRY_QUEUE_GROUP)
+            .await?;
+        let mut retries = JoinSet::new();
+        let (stop_retries, retries_stopped) = watch::channel(false);
+        let mut last_successful_poll = None;
+        let mut polls: JoinSet<(String, Polled)> = JoinSet::new();
+        tokio::pin!(shutdown);
+
+        let mut schedule =
+            FeedSchedule::new(config.interval, config.min_interval, config.max_interval)
+                .with_dead_feed_thresholds(config.dead_feed_failures, config.dead_feed_silence);
+        for feed in config.feeds.iter() {
+            schedule.add(&feed.url, feed.interval);
+        }
+        let mut configured = by_url(&config.feeds);
+        match self
+            .queue
+            .request::<_, Vec<RssFeedSource>>(RSS_FEEDS_REQUEST_SUBJECT, &())
+            .await
+        {
+            Ok(sources) => sources
+                .iter()
+                .for_each(|source| schedule.add_source(source)),
+            Err(e) => {
+                warn!("Failed to load feeds from the api-server, polling configured feeds: {e}")
+            }
+        }
+        info!("Starting RSS worker for feeds: {:?}", schedule.urls());
 
         loop {
-            for url in config.rss_urls.iter() {
-                let queue = self.queue.clone();
+            let due = schedule.take_due(Instant::now());
+            // The cycle span ends once the last poll holding it finished.
+            let cycle = info_span!("fetch_cycle", feeds = due.len());
+            for url in due {
+                let publisher = publisher.clone();
                 let cache = self.cache.clone();
-                let url = url.clone();
-                spawn(async move {
-                    match Self::process_url(queue, cache, url.clone(), items_count).await {
-                        Ok(_) => (),
-                        Err(e) => error!("Failed to process feed from ( {} ): {e}", url),
+                let extraction = extraction.clone();
+                let client = self.client.clone();
+                let settings = settings.clone();
+                let states = states.clone();
+                let feed = configured
+                    .get(&url)
+                    .cloned()
+                    .unwrap_or_else(|| ConfiguredFeed {
+                        url: url.clone(),
+                        ..Default::default()
+                    });
+                let lease = lease_ttl(
+                    schedule.interval(&url).unwrap_or(config.interval),
+                    config.fetch_timeout,
+                );
+                let instance_id = settings.instance_id.clone();
+                let span = info_span!(
+                    parent: &cycle,
+                    "poll_feed",
+                    feed.url = %url,
+                    feed.sitemap = feed.sitemap,
+                    new_items = field::Empty,
+                    otel.status_code = field::Empty,
+                );
+                let cycle = cycle.clone();
+                polls.spawn(
+                    async move {
+                        let _cycle = cycle;
+                        match lease_feed(&cache, &url, &instance_id, lease).await {
+                            Ok(true) => {}
+                            Ok(false) => return (url, Polled::Leased),
+                            Err(e) => {
+                                warn!("Failed to lease feed ( {url} ), polling it anyway: {e}")
+                            }
+                        }
+                        let outcome = match Self::process_url(
+                            publisher,
+                            cache.clone(),
+                            extraction,
+                            client,
+                            &feed,
+                            states,
+                            settings,
+                        )
+                        .await
+                        {
+                            Ok(outcome) => {
+                                Span::current().record("new_items", outcome.new_items);
+                                Some(outcome)
+                            }
+                            Err(e) => {
+                                Span::current().record("otel.status_code", "ERROR");
+                                error!("Failed to process feed from ( {} ): {e}", url);
+                                None
+                            }
+                        };
+                        // Renewed from the end of the poll, the next poll starts one interval later.
+                        if let Err(e) = lease_feed(&cache, &url, &instance_id, lease).await {
+                            warn!("Failed to renew lease of feed ( {url} ): {e}");
+                        }
+                        (url, Polled::Finished(outcome))
+                    }
+                    .instrument(span),
+                );
+            }
+
+            let next_due = schedule
+                .next_due()
+                .unwrap_or_else(|| Instant::now() + config.interval);
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = sleep_until(next_due) => {}
+                Some(polled) = polls.join_next() => match polled {
+                    Ok((url, Polled::Leased)) => {
+                        info!("Feed ( {url} ) is polled by another replica");
+                        schedule.skip(&url, Instant::now());
+                    }
+                    Ok((url, Polled::Finished(outcome))) => {
+                        let succeeded = outcome.is_some();
+                        if let Some(reason) = schedule.finish(&url, Instant::now(), outcome) {
+                            self.alert_dead_feed(&url, reason, schedule.failures(&url)).await;
+                        }
+                        if succeeded {
+                            last_successful_poll = Some(Utc::now().timestamp_millis());
+                        } else {
+                            warn!(
+                                "Feed ( {url} ) failed {} polls in a row",
+                                schedule.failures(&url)
+                            );
+                        }
+                    }
+                    Err(e) => error!("Feed poll task failed: {e}"),
+                },
+                Some(message) = retry_requests.next() => {
+                    match decode_event::<RssItemRetry>(&message.payload) {
+                        Ok(retry) => {
+                            let items_count = configured
+                                .get(&retry.feed_url)
+                                .and_then(|feed| feed.items_count)
+                                .unwrap_or(config.items_count);
+                            retries.spawn(Self::retry_item(
+                                publisher.clone(),
+                                self.cache.clone(),
+                                states.clone(),
+                                retry,
+                                state_window(items_count),
+                                config.dedup_ttl.as_secs().max(1),
+                                retries_stopped.clone(),
+                            ));
+                        }
+                        Err(e) => error!("Failed to decode rss item retry: {e}"),
+                    }
+                }
+                Some(retried) = retries.join_next() => {
+                    if let Err(e) = retried {
+                        error!("Rss item retry task failed: {e}");
+                    }
+                }
+                Some(()) = reloads.recv() => match config.load_feeds().await {
+                    Ok(feeds) => {
+                        let reloaded = by_url(&feeds);
+                        for url in configured.keys().filter(|url| !reloaded.contains_key(*url)) {
+                            info!("Feed ( {url} ) removed from the configuration");
+                            schedule.remove(url);
+                        }
+                        for feed in &feeds {
+                            schedule.add(&feed.url, feed.interval);
+                        }
+                        info!("Reloaded {} configured feeds", reloaded.len());
+                        configured = reloaded;
+                    }
+                    Err(e) => error!("Failed to reload configured feeds, keeping the current feeds: {e:#}"),
+                },
+                Some(request) = health_requests.next() => {
+                    let health = RssWorkerHealth::new(
+                        last_successful_poll,
+                        schedule.len(),
+                        schedule.in_error(),
+                        self.queue.connection_status().is_connected,
+                    )
+                    .with_unacked_items(publisher.unacked());
+                    if let Err(e) = self.queue.reply(&request, &health).await {
+                        error!("Failed to reply to health request: {e}");
+                    }
+                }
+                message = changes.next() => {
+                    let Some(message) = message else {
+                        return Err(anyhow!(
+                            "Message queue subscriber is broken for subject ( {} )",
+                            RssFeedChange::SUBJECT
+                        ));
                     };
-                });
+                    match decode_event::<RssFeedChange>(&message.payload) {
+                        Ok(change) => {
+                            info!(
+                                "Feed ( {} ) {}",
+                                change.feed.url,
+                                if change.removed { "removed" } else { "changed" }
+                            );
+                            schedule.apply(&change);
+                        }
+                        Err(e) => error!("Failed to decode feed change: {e}"),
+                    }
+                }
             }
+        }
 
-            sleep(config.interval).await;
+        info!(
+            "Stopping RSS worker, waiting for {} running polls and {} retries",
+            polls.len(),
+            retries.len()
+        );
+        // Retries sent from now on are published by the other replicas.
+        if let Err(e) = retry_requests.unsubscribe().await {
+            warn!("Failed to unsubscribe from the retry subject: {e}");
+        }
+        stop_retries.send_replace(true);
+        let deadline = Instant::now() + config.shutdown_timeout;
+        let drained = timeout_at(deadline, async {
+            while let Some(polled) = polls.join_next().await {
+                if let Err(e) = polled {
+                    error!("Feed poll task failed: {e}");
+                }
+            }
+            while let Some(retried) = retries.join_next().await {
+                if let Err(e) = retried {
+                    error!("Rss item retry task failed: {e}");
+                }
+            }
+        })
+        .await;
+        if drained.is_err() {
+            warn!(
+                "{} polls and {} retries did not finish within the shutdown timeout, aborting",
+                polls.len(),
+                retries.len()
+            );
+            polls.shutdown().await;
+            retries.shutdown().await;
         }
+
+        self.queue.flush().await?;
+        info!("RSS worker stopped");
+        Ok(())
     }
 
-    async fn process_url(
-        queue: Arc<NatsQueue>,
-        cache: Arc<RedisMiddleware>,
-        url: String,
-        items_count: usize,
-    ) -> Result<()> {
-        let xml = match Client::new().get(&url).send().await?.bytes().await {
-            Ok(bytes) => bytes,
+    /// Send the item to the retry subject, its claims are kept until it is published. Items that
+    /// cannot be retried are released, so the next poll processes them again.
+    async fn schedule_retry(
+        publisher: &ItemPublisher,
+        cache: &RedisMiddleware,
+        retry: RssItemRetry,
+        canonical_key: Option<&str>,
+    ) {
+        if !retry.should_retry() {
+            error!(
+                "Giving up publishing rss item {} after {} retries",
+                retry.item.hash, retry.retry_count
+            );
+            release(cache, &retry.item.hash, canonical_key).await;
+            return;
+        }
+        match publisher
+            .publish(RssItemRetry::SUBJECT, &EventEnvelope::new(retry.clone()))
+            .await
+        {
+            Ok(_) => warn!(
+                "Rss item {} is published again in {:?}",
+                retry.item.hash,
+                retry.delay()
+            ),
             Err(e) => {
-                return Err(anyhow!("Failed to fetch feed from ( {url} ): {e}"));
+                error!(
+                    "Failed to send rss item {} to the retry subject: {e}",
+                    retry.item.hash
+                );
+                release(cache, &retry.item.hash, canonical_key).await;
+            }
+        }
+    }
+
+    /// Publish the item of the retry subject after its delay, or right away once `stopped` is set.
+    /// The published item is recorded in the state of its feed.
+    async fn retry_item(
+        publisher: ItemPublisher,
+        cache: Arc<RedisMiddleware>,
+        states: Arc<FeedStates>,
+        mut retry: RssItemRetry,
+        window: usize,
+        dedup_ttl: u64,
+        mut stopped: watch::Receiver<bool>,
+    ) {
+        tokio::select! {
+            _ = sleep(retry.delay()) => {}
+            _ = stopped.wait_for(|stopped| *stopped) => {}
+        }
+        let canonical_key = canonical_key(&retry.item.link);
+        match publisher
+            .publish(RssItem::SUBJECT, &EventEnvelope::new(retry.item.clone()))
+            .await
+        {
+            Ok(_) => {
+                info!(
+                    "Successfully sent rss item {} to NATs queue on retry {}",
+                    retry.item.hash,
+                    retry.retry_count + 1
+                );
+                let seen = SeenItem {
+                    first_seen_timestamp: retry.item.fetched_timestamp,
+                    feed_url: &retry.feed_url,
+                    published_timestamp: retry.item.published_timestamp,
+                    pending: false,
+                };
+                mark_published(
+                    &cache,
+                    &seen,
+                    &retry.item.hash,
+                    canonical_key.as_deref(),
+                    dedup_ttl,
+                )
+                .await;
+                let mut changes = FeedStateChanges::new(window);
+                changes.record(&retry.item.hash, retry.item.published_timestamp);
+                states.save(&retry.feed_url, &changes).await;
             }
-        };
-        let channel = match Channel::read_from(&xml[..]) {
-            Ok(channel) => channel,
             Err(e) => {
-                return Err(anyhow!("Failed to parse feed from ( {url} ): {e}"));
+                error!(
+                    "Failed to send rss item {} to NATs queue on retry {}: {e}",
+                    retry.item.hash,
+                    retry.retry_count + 1
+                );
+                retry.increment_retry();
+                Self::schedule_retry(&publisher, &cache, retry, canonical_key.as_deref()).await;
             }
-        };
+        }
+    }
 
-        info!("Feed: {}", channel.title());
+    async fn alert_dead_feed(&self, url: &str, reason: DeadFeedReason, failures: u32) {
+        warn!("Feed ( {url} ) is dead ( {reason:?} ), polling it at the longest interval");
+        let alert = RssFeedAlert {
+            url: url.to_string(),
+            reason,
+            failures,
+            alerted_timestamp: Utc::now().timestamp_millis(),
+        };
+        if let Err(e) = self
+            .queue
+            .publish(RssFeedAlert::SUBJECT, &EventEnvelope::new(alert))
+            .await
+        {
+            error!("Failed to send alert of dead feed ( {url} ) to NATs queue. {e}");
+        }
+    }
 
-        for item in channel.items().iter().take(items_count) {
-            let mut rss_item: RssItem = match item.try_into() {
-                Ok(item) => item,
+    async fn process_url(
+        publisher: ItemPublisher,
+        cache: Arc<RedisMiddleware>,
+        extraction: Arc<ExtractionPool>,
+        client: FeedClient,
+        feed: &ConfiguredFeed,
+        states: Arc<FeedStates>,
+        settings: Arc<RssConfig>,
+    ) -> Result<PollOutcome> {
+        let (url, categories, stages) = (&feed.url, &feed.categories, feed.stages);
+        let items_count = feed.items_count.unwrap_or(settings.items_count);
+        let mut outcome = PollOutcome::default();
+        // Items with the `content:encoded` of feed items, sitemap pages carry no content.
+        let items: Vec<(RssItem, Option<String>)> = if feed.sitemap {
+            info!("Sitemap: {url}");
+            sitemap_items(&client, feed, items_count)
+                .instrument(info_span!("fetch_sitemap"))
+                .await?
+                .into_iter()
+                .map(|item| (item, None))
+                .collect()
+        } else {
+            let xml = client
+                .fetch(url, &feed.headers)
+                .instrument(info_span!("fetch"))
+                .await?;
+            let channel = match info_span!("parse", bytes = xml.len()).in_scope(|| parse_feed(&xml))
+            {
+                Ok(channel) => channel,
                 Err(e) => {
-                    error!("Failed to convert item [ {:?} ]: {e}", item);
-                    continue;
+                    return Err(anyhow!("Failed to parse feed from ( {url} ): {e}"));
                 }
             };
 
-            if match cache.retrieve(&rss_item.hash).await {
-                Err(e) => {
-                    error!("Cache connection faulure, {e}");
-                    None
+            info!("Feed: {}", channel.title());
+            outcome.hint = update_hint(&channel);
+
+            let channel_info = RssChannelInfo::new(&channel, url);
+            if let Err(e) = publisher
+                .queue
+                .publish(RssChannelInfo::SUBJECT, &EventEnvelope::new(channel_info))
+                .await
+            {
+                error!("Failed to send rss channel info of ( {url} ) to NATs queue. {e}");
+            }
+
+            channel
+                .items()
+                .iter()
+                .take(items_count)
+                .filter_map(|item| match RssItem::try_from(item) {
+                    Ok(rss_item) => Some((rss_item, item.content().map(str::to_string))),
+                    Err(e) => {
+                        error!("Failed to convert item [ {:?} ]: {e}", item);
+                        None
+                    }
+                })
+                .collect()
+        };
+
+        let dedup_ttl = settings.dedup_ttl.as_secs().max(1);
+        let window = state_window(items_count);
+        let state = match states.load(url).await {
+            Ok(state) => Some(state),
+            Err(e) => {
+                error!(
+                    "Failed to load state of feed ( {url} ), deduplicating with the cache only: {e}"
+                );
+                None
+            }
+        };
+        let mut changes = FeedStateChanges::new(window);
+        // Items are extracted by the pool while the next items are claimed, each one is published
+        // as soon as it is extracted.
+        let mut extractions = FuturesUnordered::new();
+        for (mut rss_item, content) in items {
+            if !categories.is_empty() {
+                rss_item.category =
+                    normalize_categories(rss_item.category.iter().chain(categories));
+            }
+            if !stages.language_detection {
+                rss_item.language.clear();
+            }
+
+            let mut seen = SeenItem {
+                first_seen_timestamp: rss_item.fetched_timestamp,
+                feed_url: url,
+                published_timestamp: rss_item.published_timestamp,
+                pending: true,
+            };
+            let claimed = seen
+                .claim(&cache, &rss_item.hash)
+                .await
+                .unwrap_or_else(|e| {
+                    error!("Failed to claim item {} in cache: {e}", rss_item.hash);
+                    true
+                });
+            if !claimed {
+                // Items remembered before records had a TTL are set to expire once seen again.
+                if let Err(e) = cache.expire_if_persistent(&rss_item.hash, dedup_ttl).await {
+                    error!("Failed to set TTL of item {} in cache: {e}", rss_item.hash);
                 }
-                Ok(value) => value,
+                info!("RSS Item {} already processed", rss_item.hash);
+                continue;
             }
-            .is_some()
+            if state
+                .as_ref()
+                .is_some_and(|state| state.contains(&rss_item.hash))
             {
+                // The cache lost the item, it is restored from the feed state.
+                seen.pending = false;
+                if let Err(e) = seen.mark_processed(&cache, &rss_item.hash, dedup_ttl).await {
+                    error!("Failed to restore item {} in cache: {e}", rss_item.hash);
+                }
                 info!("RSS Item {} already processed", rss_item.hash);
                 continue;
             }
+            outcome.new_items += 1;
 
-            if let Err(e) = cache.store(&rss_item.hash, "").await {
-                error!("Failed to store item in cache: {e}");
+            // Aggregators and mirrors syndicate the same article under other item hashes.
+            let canonical_key = canonical_key(&rss_item.link);
+            if let Some(key) = &canonical_key {
+                let claimed = claim_canonical(&cache, key, &rss_item.hash)
+                    .await
+                    .unwrap_or_else(|e| {
+                        error!(
+                            "Failed to claim canonical URL of item {}: {e}",
+                            rss_item.hash
+                        );
+                        true
+                    });
+                if !claimed {
+                    seen.pending = false;
+                    if let Err(e) = seen.mark_processed(&cache, &rss_item.hash, dedup_ttl).await {
+                        error!(
+                            "Failed to mark item {} as processed in cache: {e}",
+                            rss_item.hash
+                        );
+                    }
+                    changes.record(&rss_item.hash, rss_item.published_timestamp);
+                    info!(
+                        "RSS Item {} links to an article already processed from another feed ( {} )",
+                        rss_item.hash, rss_item.link
+                    );
+                    continue;
+                }
             }
 
-            if let Err(e) = rss_item.extract_article_from_source().await {
-                warn!(
-                    "Failed to extract article from source for item [ {:?} ]: {e}",
-                    item
-                );
+            let fallback = rss_item.clone();
+            // Covers the wait for a free extraction worker as well.
+            let span = info_span!("extract", item.hash = %rss_item.hash);
+            let extraction = extraction
+                .submit(rss_item, content, stages)
+                .instrument(span.clone())
+                .await;
+            extractions.push(
+                async move {
+                    let extracted = match extraction {
+                        Ok(extracted) => extracted.await.ok(),
+                        Err(_) => None,
+                    };
+                    let extracted = extracted.unwrap_or_else(|| {
+                        warn!("Publishing item {} without extraction", fallback.hash);
+                        Extracted {
+                            item: fallback,
+                            skipped_by_robots: None,
+                        }
+                    });
+                    (seen, canonical_key, extracted)
+                }
+                .instrument(span),
+            );
+        }
+
+        while let Some((mut seen, canonical_key, extracted)) = extractions.next().await {
+            let mut rss_item = extracted.item;
+            // Sitemap pages are titled by their article.
+            if rss_item.title.is_empty()
+                && let Some(title) = &rss_item.preview_title
+            {
+                rss_item.title = title.clone();
+            }
+            if let Some(link) = &extracted.skipped_by_robots {
+                changes.record_skipped(link);
             }
 
-            match queue.publish(RSS_QUEUE_NAME, &rss_item).await {
-                Ok(_) => info!(
-                    "Successfully sent rss item to NATs queue. Rss item title: ( {} ) and hash: ( {} )",
-                    rss_item.title, rss_item.hash
-                ),
-                Err(e) => error!(
-                    "Failed to send rss item to NATs queue. Rss item title: ( {} ) and hash: ( {} ). {e}",
-                    rss_item.title, rss_item.hash
-                ),
+            match publisher
+                .publish(RssItem::SUBJECT, &EventEnvelope::new(rss_item.clone()))
+                .instrument(info_span!("publish", item.hash = %rss_item.hash))
+                .await
+            {
+                Ok(_) => {
+                    info!(
+                        "Successfully sent rss item to NATs queue. Rss item title: ( {} ) and hash: ( {} )",
+                        rss_item.title, rss_item.hash
+                    );
+                    seen.pending = false;
+                    mark_published(
+                        &cache,
+                        &seen,
+                        &rss_item.hash,
+                        canonical_key.as_deref(),
+                        dedup_ttl,
+                    )
+                    .await;
+                    changes.record(&rss_item.hash, rss_item.published_timestamp);
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to send rss item to NATs queue. Rss item title: ( {} ) and hash: ( {} ). {e}",
+                        rss_item.title, rss_item.hash
+                    );
+                    let retry = RssItemRetry::new(rss_item, url.clone());
+                    Self::schedule_retry(&publisher, &cache, retry, canonical_key.as_deref()).await;
+                }
             };
         }
+
+        // The store failed to read the state, the poll is left to the cache alone.
+        if state.is_some() {
+            changes.polled(Utc::now().timestamp_millis());
+            states.save(url, &changes).await;
+        }
+        Ok(outcome)
+    }
+}
+
+/// SeenItem is the dedup cache record of an item, stored under the item hash.
+///
+/// Items are claimed as pending before they are processed and marked processed only once
+/// published, so an item failing to be published is retried by a later poll.
+#[derive(Debug, Serialize)]
+struct SeenItem<'a> {
+    /// Time the item was first fetched in Unix milliseconds.
+    first_seen_timestamp: i64,
+    /// URL of the feed the item was first fetched from.
+    feed_url: &'a str,
+    /// Time the item was published in Unix milliseconds, the fetch time if the feed has none.
+    published_timestamp: i64,
+    /// True while the item is processed.
+    pending: bool,
+}
+
+impl SeenItem<'_> {
+    /// Claims the item for processing, false if it is processed or claimed already.
+    async fn claim(&self, cache: &RedisMiddleware, hash: &str) -> Result<bool> {
+        let record = serde_json::to_string(self)?;
+        cache
+            .store_if_absent_with_ttl(hash, &record, PENDING_TTL.as_secs())
+            .await
+    }
+
+    /// Marks the claimed item as processed, it is remembered for the TTL in seconds.
+    async fn mark_processed(&self, cache: &RedisMiddleware, hash: &str, ttl: u64) -> Result<()> {
+        let record = serde_json::to_string(self)?;
+        cache.store_with_ttl(hash, &record, ttl).await
+    }
+}
+
+/// Configured feeds by their URL.
+fn by_url(feeds: &[ConfiguredFeed]) -> HashMap<String, ConfiguredFeed> {
+    feeds
+        .iter()
+        .map(|feed| (feed.url.clone(), feed.clone()))
+        .collect()
+}
+
+/// Number of item hashes kept in the state of a feed polling `items_count` items.
+fn state_window(items_count: usize) -> usize {
+    items_count.max(1) * 2
+}
+
+/// Dedup cache key of the canonical URL of the link, None if the link is not an http(s) URL.
+fn canonical_key(link: &str) -> Option<String> {
+    canonical_url(link).map(|canonical| format!("{CANONICAL_KEY_PREFIX}{}", feed_hash(&canonical)))
+}
+
+/// ItemPublisher publishes items and retries, waiting for the ack of the JetStream stream
+/// capturing the subject if a stream is configured, so a message lost to a network partition
+/// fails instead of being dropped.
+#[derive(Clone)]
+struct ItemPublisher {
+    queue: Arc<NatsQueue>,
+    acked: bool,
+    /// Number of published messages waiting for their ack.
+    unacked: Arc<AtomicUsize>,
+}
+
+impl ItemPublisher {
+    fn new(queue: Arc<NatsQueue>, acked: bool) -> Self {
+        Self {
+            queue,
+            acked,
+            unacked: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    async fn publish<T: Serialize>(&self, subject: &str, payload: &T) -> Result<()> {
+        if !self.acked {
+            self.queue.publish(subject, payload).await?;
+            return Ok(());
+        }
+        let _pending = PendingAck::new(&self.unacked);
+        self.queue.publish_acked(subject, payload).await?;
+        Ok(())
+    }
+
+    /// Number of published messages waiting for their ack.
+    fn unacked(&self) -> usize {
+        self.unacked.load(Ordering::Relaxed)
+    }
+}
+
+/// PendingAck counts a message as unacked until it is dropped, also if its publish is aborted.
+struct PendingAck<'a>(&'a AtomicUsize);
+
+impl<'a> PendingAck<'a> {
+    fn new(unacked: &'a AtomicUsize) -> Self {
+        unacked.fetch_add(1, Ordering::Relaxed);
+        Self(unacked)
+    }
+}
+
+impl Drop for PendingAck<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Marks the published item and its canonical URL as processed for the TTL in seconds.
+async fn mark_published(
+    cache: &RedisMiddleware,
+    seen: &SeenItem<'_>,
+    hash: &str,
+    canonical_key: Option<&str>,
+    ttl: u64,
+) {
+    if let Err(e) = seen.mark_processed(cache, hash, ttl).await {
+        error!("Failed to mark item {hash} as processed in cache: {e}");
+    }
+    if let Some(key) = canonical_key
+        && let Err(e) = cache.store_with_ttl(key, hash, ttl).await
+    {
+        error!("Failed to mark canonical URL of item {hash} as processed in cache: {e}");
+    }
+}
+
+/// Releases the claims of an item that was not published, the next poll processes it again, at
+/// the latest once the claims expire.
+async fn release(cache: &RedisMiddleware, hash: &str, canonical_key: Option<&str>) {
+    if let Err(e) = cache.delete(hash).await {
+        error!("Failed to release item {hash} in cache: {e}");
+    }
+    if let Some(key) = canonical_key
+        && let Err(e) = cache.delete(key).await
+    {
+        error!("Failed to release canonical URL of item {hash} in cache: {e}");
+    }
+}
+
+/// Takes the lease of the feed for this replica, stored under the key of the feed with the
+/// instance id as the value. The lease lasts [`lease_ttl`] and is renewed by the replica holding
+/// it, so every feed is fetched by a single replica per interval and taken over by another one
+/// once its replica stops.
+///
+/// # Returns
+/// False if another replica holds the lease.
+async fn lease_feed(
+    cache: &RedisMiddleware,
+    url: &str,
+    instance_id: &str,
+    lease: Duration,
+) -> Result<bool> {
+    let key = format!("{FEED_LEASE_KEY_PREFIX}{}", feed_hash(url));
+    let ttl = lease.as_secs().max(1);
+    if cache
+        .store_if_absent_with_ttl(&key, instance_id, ttl)
+        .await?
+    {
+        return Ok(true);
+    }
+    // Renewed only while still held, a lease expiring meanwhile may be taken by another replica.
+    cache
+        .store_if_equal_with_ttl(&key, instance_id, instance_id, ttl)
+        .await
+}
+
+/// Time the lease of a feed polled every `interval` is taken for.
+///
+/// The next poll of the holder starts one interval after its poll ended, so the lease is renewed
+/// when the poll ends and lasts the fetch timeout longer than the interval, it has not expired
+/// when the holder comes back.
+fn lease_ttl(interval: Duration, fetch_timeout: Duration) -> Duration {
+    interval + fetch_timeout.max(Duration::from_secs(1))
+}
+
+/// Claims the canonical URL for the item, stored under the key with the item hash as the value.
+///
+/// # Returns
+/// False if an item with another hash claimed or published the URL already.
+async fn claim_canonical(cache: &RedisMiddleware, key: &str, hash: &str) -> Result<bool> {
+    if cache
+        .store_if_absent_with_ttl(key, hash, PENDING_TTL.as_secs())
+        .await?
+    {
+        return Ok(true);
+    }
+    Ok(cache.retrieve(key).await?.is_none_or(|owner| owner == hash))
+}
+
+/// Shortest interval the channel asks to be polled at, the longer of its `<ttl>` in minutes and
+/// its `sy:updatePeriod` divided by `sy:updateFrequency`.
+fn update_hint(channel: &Channel) -> Option<Duration> {
+    let ttl = channel
+        .ttl()
+        .and_then(|ttl| ttl.trim().parse::<u64>().ok())
+        .map(|minutes| Duration::from_secs(minutes * 60));
+    let update_period = channel.syndication_ext().map(|syndication| {
+        let period = match syndication.period() {
+            UpdatePeriod::Hourly => 3_600,
+            UpdatePeriod::Daily => 86_400,
+            UpdatePeriod::Weekly => 604_800,
+            UpdatePeriod::Monthly => 2_592_000,
+            UpdatePeriod::Yearly => 31_536_000,
+        };
+        Duration::from_secs(period / u64::from(syndication.frequency().max(1)))
+    });
+
+    ttl.max(update_period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "integrations")]
+    const REDIS_URL: &str = "redis://:password@localhost:6379";
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    /// Channel with the elements added to an otherwise empty feed.
+    fn channel(elements: &str) -> Channel {
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <rss version="2.0" xmlns:sy="http://purl.org/rss/1.0/modules/syndication/">
+                <channel>
+                    <title>News</title>
+                    <link>https://news.example.com</link>
+                    <description>Crypto news</description>
+                    {elements}
+                </channel>
+            </rss>"#
+        );
+        Channel::read_from(xml.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_update_hint() {
+        assert_eq!(update_hint(&channel("")), None);
+        assert_eq!(update_hint(&channel("<ttl>60</ttl>")), Some(secs(3_600)));
+        assert_eq!(update_hint(&channel("<ttl> 15 </ttl>")), Some(secs(900)));
+        assert_eq!(update_hint(&channel("<ttl>soon</ttl>")), None);
+        assert_eq!(
+            update_hint(&channel(
+                "<sy:updatePeriod>hourly</sy:updatePeriod><sy:updateFrequency>4</sy:updateFrequency>"
+            )),
+            Some(secs(900))
+        );
+        assert_eq!(
+            update_hint(&channel("<sy:updatePeriod>daily</sy:updatePeriod>")),
+            Some(secs(86_400))
+        );
+        // A frequency of zero counts as one update per period.
+        assert_eq!(
+            update_hint(&channel(
+                "<sy:updatePeriod>weekly</sy:updatePeriod><sy:updateFrequency>0</sy:updateFrequency>"
+            )),
+            Some(secs(604_800))
+        );
+        // The longer of both hints wins.
+        assert_eq!(
+            update_hint(&channel(
+                "<ttl>5</ttl><sy:updatePeriod>hourly</sy:updatePeriod><sy:updateFrequency>2</sy:updateFrequency>"
+            )),
+            Some(secs(1_800))
+        );
+        assert_eq!(
+            update_hint(&channel(
+                "<ttl>120</ttl><sy:updatePeriod>hourly</sy:updatePeriod><sy:updateFrequency>2</sy:updateFrequency>"
+            )),
+            Some(secs(7_200))
+        );
+    }
+
+    #[test]
+    fn test_update_hint_is_clamped_by_the_schedule() {
+        const URL: &str = "https://news.example.com/rss";
+        let mut schedule = FeedSchedule::new(secs(600), secs(300), secs(3_600));
+        schedule.add(URL, None);
+        let start = Instant::now();
+        schedule.take_due(start);
+
+        let yearly = update_hint(&channel("<sy:updatePeriod>yearly</sy:updatePeriod>"));
+        schedule.finish(
+            URL,
+            start,
+            Some(PollOutcome {
+                new_items: 1,
+                hint: yearly,
+            }),
+        );
+        assert_eq!(schedule.interval(URL), Some(secs(3_600)));
+
+        // Hints below the shortest interval do not hold busy feeds back.
+        let minute = update_hint(&channel("<ttl>1</ttl>"));
+        for poll in 1..10 {
+            schedule.finish(
+                URL,
+                start + secs(3_600 + poll),
+                Some(PollOutcome {
+                    new_items: 1_000,
+                    hint: minute,
+                }),
+            );
+        }
+        assert_eq!(schedule.interval(URL), Some(secs(300)));
+    }
+
+    #[test]
+    fn test_canonical_key() {
+        let key = canonical_key("https://news.example.com/markets/eth-etf?utm_source=rss").unwrap();
+
+        assert!(key.starts_with(CANONICAL_KEY_PREFIX));
+        // The same article syndicated by an aggregator with other tracking and formatting.
+        assert_eq!(
+            canonical_key(" http://www.news.example.com/markets/eth-etf/?fbclid=abc#comments "),
+            Some(key.clone())
+        );
+        assert_ne!(
+            canonical_key("https://news.example.com/markets/btc-etf"),
+            Some(key)
+        );
+        assert_eq!(canonical_key("/markets/eth-etf"), None);
+        assert_eq!(canonical_key("mailto:desk@news.example.com"), None);
+    }
+
+    #[cfg(feature = "integrations")]
+    #[tokio::test]
+    async fn test_claim_canonical_across_feeds() -> Result<()> {
+        let cache = RedisMiddleware::new(REDIS_URL)?;
+        let key = canonical_key(&format!(
+            "https://news.example.com/markets/eth-etf-{}",
+            std::process::id()
+        ))
+        .unwrap();
+        cache.delete(&key).await?;
+
+        // The item of the first feed claims the article, the item of the aggregator does not.
+        assert!(claim_canonical(&cache, &key, "hash-of-publisher").await?);
+        assert!(!claim_canonical(&cache, &key, "hash-of-aggregator").await?);
+        assert!(claim_canonical(&cache, &key, "hash-of-publisher").await?);
+
+        // Published items keep the article.
+        cache.store_with_ttl(&key, "hash-of-publisher", 60).await?;
+        assert!(!claim_canonical(&cache, &key, "hash-of-aggregator").await?);
+
+        // Released claims free the article for the next poll.
+        release(&cache, "hash-of-publisher", Some(&key)).await;
+        assert!(claim_canonical(&cache, &key, "hash-of-aggregator").await?);
+        cache.delete(&key).await?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "integrations")]
+    #[tokio::test]
+    async fn test_lease_feed() -> Result<()> {
+        let cache = RedisMiddleware::new(REDIS_URL)?;
+        let url = format!("https://news.example.com/rss-{}", std::process::id());
+        let key = format!("{FEED_LEASE_KEY_PREFIX}{}", feed_hash(&url));
+        cache.delete(&key).await?;
+
+        // The first replica acquires the lease, the second one is refused.
+        assert!(lease_feed(&cache, &url, "replica-a", secs(60)).await?);
+        assert!(!lease_feed(&cache, &url, "replica-b", secs(60)).await?);
+        assert_eq!(cache.retrieve(&key).await?, Some("replica-a".to_string()));
+
+        // The holder renews its lease.
+        assert!(lease_feed(&cache, &url, "replica-a", secs(60)).await?);
+        assert_eq!(cache.retrieve(&key).await?, Some("replica-a".to_string()));
+
+        // Once the lease expires it is lost to another replica, the former holder cannot renew it.
+        assert!(lease_feed(&cache, &url, "replica-a", secs(1)).await?);
+        sleep(Duration::from_millis(1_500)).await;
+        assert!(lease_feed(&cache, &url, "replica-b", secs(60)).await?);
+        assert!(!lease_feed(&cache, &url, "replica-a", secs(60)).await?);
+        assert_eq!(cache.retrieve(&key).await?, Some("replica-b".to_string()));
+        cache.delete(&key).await?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lease_ttl() {
+        assert_eq!(lease_ttl(secs(300), secs(30)), secs(330));
+        assert_eq!(lease_ttl(secs(300), Duration::ZERO), secs(301));
+    }
+
+    #[cfg(feature = "integrations")]
+    #[tokio::test]
+    async fn test_lease_feed_timing() -> Result<()> {
+        let cache = RedisMiddleware::new(REDIS_URL)?;
+        let url = format!("https://news.example.com/timing-{}", std::process::id());
+        let key = format!("{FEED_LEASE_KEY_PREFIX}{}", feed_hash(&url));
+        cache.delete(&key).await?;
+        let (interval, poll) = (secs(2), Duration::from_millis(1_500));
+        let lease = lease_ttl(interval, secs(1));
+
+        // The holder polls, renews the lease when the poll ends and comes back one interval later,
+        // another replica due in between is refused.
+        assert!(lease_feed(&cache, &url, "replica-a", lease).await?);
+        sleep(poll).await;
+        assert!(lease_feed(&cache, &url, "replica-a", lease).await?);
+        sleep(interval - Duration::from_millis(500)).await;
+        assert!(!lease_feed(&cache, &url, "replica-b", lease).await?);
+        sleep(Duration::from_millis(500)).await;
+        assert!(lease_feed(&cache, &url, "replica-a", lease).await?);
+        assert_eq!(cache.retrieve(&key).await?, Some("replica-a".to_string()));
+        cache.delete(&key).await?;
+
         Ok(())
     }
+
+    #[test]
+    fn test_pending_ack() {
+        let unacked = AtomicUsize::new(0);
+
+        let first = PendingAck::new(&unacked);
+        let second = PendingAck::new(&unacked);
+        assert_eq!(unacked.load(Ordering::Relaxed), 2);
+
+        drop(first);
+        assert_eq!(unacked.load(Ordering::Relaxed), 1);
+        drop(second);
+        assert_eq!(unacked.load(Ordering::Relaxed), 0);
+    }
 }
@@ -0,0 +1,393 @@
+use shared_states::{DeadFeedReason, FeedError, RssFeedChange, RssFeedSource};
+use std::{collections::HashMap, time::Duration};
+use tokio::time::Instant;
+
+/// Growth of the interval of a feed after a poll without new items.
+const BACK_OFF_FACTOR: f64 = 1.5;
+const DEFAULT_DEAD_AFTER_FAILURES: u32 = 10;
+const DEFAULT_DEAD_AFTER_SILENCE: Duration = Duration::from_secs(30 * 86_400);
+
+/// PollOutcome is what a finished poll of a feed found.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PollOutcome {
+    /// Number of items not seen before.
+    pub new_items: usize,
+    /// Shortest interval the feed asks to be polled at with `<ttl>` or `sy:updatePeriod`.
+    pub hint: Option<Duration>,
+}
+
+struct ScheduledFeed {
+    /// Interval of the feed configuration or source, adaptation starts from it.
+    base_interval: Duration,
+    /// Current interval, adapted to how often the feed publishes.
+    interval: Duration,
+    next_run: Instant,
+    last_poll: Option<Instant>,
+    /// Number of polls failed in a row.
+    failures: u32,
+    /// Time of the latest poll finding new items, or of the scheduling of the feed before it.
+    last_new_items: Instant,
+    /// Dead feeds are polled at the longest interval until they publish new items again.
+    dead: bool,
+    /// Whether a poll of the feed is running, the feed is not due again until it finishes.
+    polling: bool,
+}
+
+/// FeedSchedule tracks the polled feeds and when each of them is due.
+///
+/// Every feed has its own interval, counted from the end of its previous poll, so a slow feed is
+/// never polled twice at the same time. Intervals adapt to how often the feeds publish: they move
+/// towards the time between new items of busy feeds and back off for quiet feeds, within the
+/// bounds and never below the interval a feed asks for.
+///
+/// Feeds failing too many polls in a row or publishing nothing for too long are marked dead and
+/// polled at the longest interval only.
+pub struct FeedSchedule {
+    feeds: HashMap<String, ScheduledFeed>,
+    default_interval: Duration,
+    min_interval: Duration,
+    max_interval: Duration,
+    dead_after_failures: u32,
+    dead_after_silence: Duration,
+}
+
+impl FeedSchedule {
+    /// Create an empty schedule.
+    ///
+    /// # Arguments
+    /// * `default_interval` - Interval of feeds added without their own interval.
+    /// * `min_interval` - Shortest interval of busy feeds.
+    /// * `max_interval` - Longest interval of quiet feeds.
+    pub fn new(default_interval: Duration, min_interval: Duration, max_interval: Duration) -> Self {
+        Self {
+            feeds: HashMap::new(),
+            default_interval,
+            min_interval,
+            max_interval,
+            dead_after_failures: DEFAULT_DEAD_AFTER_FAILURES,
+            dead_after_silence: DEFAULT_DEAD_AFTER_SILENCE,
+        }
+    }
+
+    /// Set when feeds are considered dead.
+    ///
+    /// # Arguments
+    /// * `failures` - Number of polls failed in a row.
+    /// * `silence` - Time without new items.
+    pub fn with_dead_feed_thresholds(mut self, failures: u32, silence: Duration) -> Self {
+        self.dead_after_failures = failures.max(1);
+        self.dead_after_silence = silence;
+        self
+    }
+
+    /// Add a feed due immediately, a feed already scheduled only gets the new interval, adaptation
+    /// restarts from it.
+    ///
+    /// # Arguments
+    /// * `url` - The feed URL.
+    /// * `interval` - Time between polls of the feed, the default interval if not given.
+    pub fn add(&mut self, url: &str, interval: Option<Duration>) {
+        let interval = interval.unwrap_or(self.default_interval);
+        self.feeds
+            .entry(url.to_string())
+            .and_modify(|feed| {
+                if feed.base_interval != interval {
+                    feed.next_run = feed.next_run.min(Instant::now() + interval);
+                    feed.base_interval = interval;
+                    feed.interval = interval;
+                }
+            })
+            .or_insert(ScheduledFeed {
+                base_interval: interval,
+                interval,
+                next_run: Instant::now(),
+                last_poll: None,
+                failures: 0,
+                last_new_items: Instant::now(),
+                dead: false,
+                polling: false,
+            });
+    }
+
+    /// Remove a feed, a running poll of it finishes but the feed is not due again.
+    pub fn remove(&mut self, url: &str) {
+        self.feeds.remove(url);
+    }
+
+    /// Add a feed source managed through the api-server, disabled sources are removed.
+    pub fn add_source(&mut self, source: &RssFeedSource) {
+        if !source.enabled {
+            self.feeds.remove(&source.url);
+            return;
+        }
+        let interval = u64::try_from(source.interval_seconds)
+            .ok()
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs);
+        self.add(&source.url, interval);
+    }
+
+    /// Apply a change published by the api-server.
+    pub fn apply(&mut self, change: &RssFeedChange) {
+        if change.removed {
+            self.feeds.remove(&change.feed.url);
+        } else {
+            self.add_source(&change.feed);
+        }
+    }
+
+    /// Take the feeds due at the time, they are not due again until their polls finish.
+    ///
+    /// # Returns
+    /// URLs of the due feeds.
+    pub fn take_due(&mut self, now: Instant) -> Vec<String> {
+        self.feeds
+            .iter_mut()
+            .filter(|(_, feed)| !feed.polling && feed.next_run <= now)
+            .map(|(url, feed)| {
+                feed.polling = true;
+                url.clone()
+            })
+            .collect()
+    }
+
+    /// Adapt the interval of a feed to the outcome of its poll and schedule the next poll one
+    /// interval after it finished.
+    ///
+    /// # Arguments
+    /// * `url` - The feed URL.
+    /// * `now` - The time the poll finished.
+    /// * `outcome` - What the poll found, None if it failed, the interval is kept and the failure
+    ///   counted.
+    ///
+    /// # Returns
+    /// Why the feed died if the poll made it dead, dead feeds revive once they publish new items.
+    pub fn finish(
+        &mut self,
+        url: &str,
+        now: Instant,
+        outcome: Option<PollOutcome>,
+    ) -> Option<DeadFeedReason> {
+        let (min_interval, max_interval) = (self.min_interval, self.max_interval);
+        let (dead_after_failures, dead_after_silence) =
+            (self.dead_after_failures, self.dead_after_silence);
+        let feed = self.feeds.get_mut(url)?;
+        feed.polling = false;
+
+        let mut died = None;
+        if let Some(outcome) = outcome {
+            feed.failures = 0;
+            if outcome.new_items > 0 {
+                feed.last_new_items = now;
+                if feed.dead {
+                    feed.dead = false;
+                    feed.interval = feed.base_interval;
+                }
+            } else if !feed.dead && now - feed.last_new_items >= dead_after_silence {
+                died = Some(DeadFeedReason::Stale);
+            }
+            let elapsed = feed.last_poll.map(|last_poll| now - last_poll);
+            let interval = match (outcome.new_items, elapsed) {
+                (0, _) => feed.interval.mul_f64(BACK_OFF_FACTOR),
+                // The first poll finds the backlog of the feed, not its publishing rate.
+                (_, None) => feed.interval,
+                // Halfway towards the time between the new items.
+                (new_items, Some(elapsed)) => {
+                    (feed.interval + elapsed.div_f64(new_items as f64)) / 2
+                }
+            };
+            let floor = outcome
+                .hint
+                .map_or(min_interval, |hint| hint.clamp(min_interval, max_interval));
+            feed.interval = interval.clamp(floor, max_interval);
+            feed.last_poll = Some(now);
+        } else {
+            feed.failures += 1;
+            if !feed.dead && feed.failures >= dead_after_failures {
+                died = Some(DeadFeedReason::Failing);
+            }
+        }
+
+        feed.dead |= died.is_some();
+        if feed.dead {
+            feed.interval = max_interval;
+        }
+        feed.next_run = now + feed.interval;
+        died
+    }
+
+    /// Schedule the next poll of a feed polled by another replica one interval from now, its
+    /// interval and failures are kept.
+    ///
+    /// # Arguments
+    /// * `url` - The feed URL.
+    /// * `now` - The time the poll was skipped.
+    pub fn skip(&mut self, url: &str, now: Instant) {
+        if let Some(feed) = self.feeds.get_mut(url) {
+            feed.polling = false;
+            feed.next_run = now + feed.interval;
+        }
+    }
+
+    /// Current interval of the feed, None if it is not scheduled.
+    pub fn interval(&self, url: &str) -> Option<Duration> {
+        self.feeds.get(url).map(|feed| feed.interval)
+    }
+
+    /// Number of polls of the feed failed in a row.
+    pub fn failures(&self, url: &str) -> u32 {
+        self.feeds.get(url).map_or(0, |feed| feed.failures)
+    }
+
+    /// Time the next feed is due, None if no feed is waiting for its poll.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.feeds
+            .values()
+            .filter(|feed| !feed.polling)
+            .map(|feed| feed.next_run)
+            .min()
+    }
+
+    /// Feeds whose latest polls failed.
+    pub fn in_error(&self) -> Vec<FeedError> {
+        self.feeds
+            .iter()
+            .filter(|(_, feed)| feed.failures > 0)
+            .map(|(url, feed)| FeedError {
+                url: url.clone(),
+                failures: feed.failures,
+            })
+            .collect()
+    }
+
+    /// Number of scheduled feeds.
+    pub fn len(&self) -> usize {
+        self.feeds.len()
+    }
+
+    /// URLs of the scheduled feeds.
+    pub fn urls(&self) -> Vec<&str> {
+        self.feeds.keys().map(String::as_str).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://news.example.com/rss";
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    fn new_items(new_items: usize) -> Option<PollOutcome> {
+        Some(PollOutcome {
+            new_items,
+            hint: None,
+        })
+    }
+
+    #[test]
+    fn test_feed_schedule_adapts_interval() {
+        let mut schedule = FeedSchedule::new(secs(100), secs(10), secs(1000));
+        schedule.add(URL, None);
+        let start = Instant::now();
+
+        assert_eq!(schedule.take_due(start), vec![URL.to_string()]);
+        assert!(schedule.take_due(start).is_empty());
+
+        // The first poll finds the backlog, the interval is kept.
+        schedule.finish(URL, start, new_items(20));
+        assert_eq!(schedule.interval(URL), Some(secs(100)));
+        assert_eq!(schedule.next_due(), Some(start + secs(100)));
+
+        // Halfway towards 25 seconds between the new items.
+        schedule.finish(URL, start + secs(100), new_items(4));
+        assert_eq!(
+            schedule.interval(URL),
+            Some(secs(62) + Duration::from_millis(500))
+        );
+
+        schedule.finish(URL, start + secs(200), new_items(0));
+        assert_eq!(
+            schedule.interval(URL),
+            Some(secs(93) + Duration::from_millis(750))
+        );
+
+        // Never below the interval the feed asks for, never above the longest interval.
+        let hint = Some(PollOutcome {
+            new_items: 100,
+            hint: Some(secs(300)),
+        });
+        schedule.finish(URL, start + secs(300), hint);
+        assert_eq!(schedule.interval(URL), Some(secs(300)));
+        for poll in 4..20 {
+            schedule.finish(URL, start + secs(poll * 100), new_items(0));
+        }
+        assert_eq!(schedule.interval(URL), Some(secs(1000)));
+
+        // Busy feeds speed up to the shortest interval.
+        schedule.add(URL, Some(secs(15)));
+        schedule.finish(URL, start + secs(2000), new_items(1000));
+        assert_eq!(schedule.interval(URL), Some(secs(10)));
+    }
+
+    #[test]
+    fn test_feed_schedule_skip_and_remove() {
+        let mut schedule = FeedSchedule::new(secs(100), secs(10), secs(1000));
+        schedule.add(URL, Some(secs(50)));
+        let start = Instant::now();
+        assert_eq!(schedule.take_due(start).len(), 1);
+
+        schedule.skip(URL, start + secs(5));
+
+        assert_eq!(schedule.next_due(), Some(start + secs(55)));
+        assert_eq!(schedule.interval(URL), Some(secs(50)));
+        assert_eq!(schedule.failures(URL), 0);
+
+        schedule.remove(URL);
+        assert_eq!(schedule.len(), 0);
+        assert!(schedule.finish(URL, start, None).is_none());
+    }
+
+    #[test]
+    fn test_feed_schedule_dead_feeds() {
+        let mut schedule = FeedSchedule::new(secs(100), secs(10), secs(1000))
+            .with_dead_feed_thresholds(3, secs(5000));
+        let start = Instant::now();
+        schedule.add(URL, None);
+
+        assert!(schedule.finish(URL, start, None).is_none());
+        assert!(schedule.finish(URL, start + secs(1), None).is_none());
+        assert_eq!(schedule.in_error().len(), 1);
+        assert_eq!(
+            schedule.finish(URL, start + secs(2), None),
+            Some(DeadFeedReason::Failing)
+        );
+        assert_eq!(schedule.interval(URL), Some(secs(1000)));
+        // Dead feeds die once.
+        assert!(schedule.finish(URL, start + secs(3), None).is_none());
+
+        // New items revive the feed at its own interval.
+        assert!(
+            schedule
+                .finish(URL, start + secs(4), new_items(1))
+                .is_none()
+        );
+        assert_eq!(schedule.failures(URL), 0);
+        assert!(schedule.in_error().is_empty());
+        assert_eq!(schedule.interval(URL), Some(secs(100)));
+
+        assert!(
+            schedule
+                .finish(URL, start + secs(4000), new_items(0))
+                .is_none()
+        );
+        assert_eq!(
+            schedule.finish(URL, start + secs(5004), new_items(0)),
+            Some(DeadFeedReason::Stale)
+        );
+        assert_eq!(schedule.interval(URL), Some(secs(1000)));
+    }
+}
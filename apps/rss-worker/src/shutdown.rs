@@ -0,0 +1,42 @@
+use tokio::{signal, sync::mpsc};
+
+/// Waits for a termination request, SIGTERM sent by the orchestrator or SIGINT (Ctrl+C).
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler");
+        tokio::select! {
+            _ = terminate.recv() => tracing::info!("Received SIGTERM, shutting down"),
+            _ = signal::ctrl_c() => tracing::info!("Received SIGINT, shutting down"),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = signal::ctrl_c().await;
+        tracing::info!("Received Ctrl+C, shutting down");
+    }
+}
+
+/// Requests to reload the configuration, sent on every SIGHUP. No request is ever sent on
+/// platforms without SIGHUP.
+pub fn reloads() -> mpsc::Receiver<()> {
+    let (sender, receiver) = mpsc::channel(1);
+
+    #[cfg(unix)]
+    tokio::spawn(async move {
+        let mut hangup = signal::unix::signal(signal::unix::SignalKind::hangup())
+            .expect("Failed to install SIGHUP handler");
+        while hangup.recv().await.is_some() {
+            tracing::info!("Received SIGHUP, reloading configured feeds");
+            // A reload is pending already if the channel is full.
+            let _ = sender.try_send(());
+        }
+    });
+
+    #[cfg(not(unix))]
+    drop(sender);
+
+    receiver
+}
@@ -0,0 +1,150 @@
+use crate::config::ConfiguredFeed;
+use crate::fetch::FeedClient;
+use anyhow::{Result, anyhow};
+use rss::Item;
+use shared_states::{RssItem, Sitemap, SitemapEntry, parse_sitemap};
+use std::{cmp::Reverse, collections::HashSet};
+use tracing::warn;
+
+/// Number of the most recently modified child sitemaps of a sitemap index that are fetched, older
+/// ones list pages published before.
+const MAX_CHILD_SITEMAPS: usize = 3;
+
+/// Fetches the sitemap of a source publishing no feed and turns its most recently modified pages
+/// into items, their articles are extracted like the articles of feed items.
+///
+/// The item hash is derived from the page URL only, so a page is an item once no matter how
+/// often its `lastmod` changes. Child sitemaps of a sitemap index are fetched as well, up to the
+/// [`MAX_CHILD_SITEMAPS`] most recently modified ones.
+///
+/// # Arguments
+/// * `client` - The client the sitemaps are fetched with.
+/// * `feed` - The configured sitemap source.
+/// * `items_count` - Number of pages turned into items.
+///
+/// # Returns
+/// The items, newest first, or an error if the sitemap cannot be fetched or parsed.
+pub async fn sitemap_items(
+    client: &FeedClient,
+    feed: &ConfiguredFeed,
+    items_count: usize,
+) -> Result<Vec<RssItem>> {
+    let entries = match fetch_sitemap(client, feed, &feed.url).await? {
+        Sitemap::UrlSet(entries) => entries,
+        Sitemap::Index(mut children) => {
+            children.sort_by_key(|child| Reverse(child.lastmod));
+            let mut entries = Vec::new();
+            for child in children.iter().take(MAX_CHILD_SITEMAPS) {
+                match fetch_sitemap(client, feed, &child.loc).await {
+                    Ok(Sitemap::UrlSet(child_entries)) => entries.extend(child_entries),
+                    Ok(Sitemap::Index(_)) => {
+                        warn!("Skipped nested sitemap index ( {} )", child.loc)
+                    }
+                    Err(e) => warn!("Failed to fetch child sitemap ( {} ): {e}", child.loc),
+                }
+            }
+            entries
+        }
+    };
+
+    Ok(latest_entries(entries, items_count)
+        .iter()
+        .filter_map(|entry| match sitemap_item(entry) {
+            Ok(item) => Some(item),
+            Err(e) => {
+                warn!("Failed to convert sitemap entry ( {} ): {e}", entry.loc);
+                None
+            }
+        })
+        .collect())
+}
+
+async fn fetch_sitemap(client: &FeedClient, feed: &ConfiguredFeed, url: &str) -> Result<Sitemap> {
+    let xml = client.fetch(url, &feed.headers).await?;
+    parse_sitemap(&String::from_utf8_lossy(&xml))
+        .map_err(|e| anyhow!("Failed to parse sitemap from ( {url} ): {e}"))
+}
+
+/// The most recently modified pages, each listed once.
+fn latest_entries(mut entries: Vec<SitemapEntry>, count: usize) -> Vec<SitemapEntry> {
+    // Pages without lastmod are the least likely to be new.
+    entries.sort_by_key(|entry| Reverse(entry.lastmod));
+    let mut listed = HashSet::new();
+    entries.retain(|entry| listed.insert(entry.loc.clone()));
+    entries.truncate(count);
+    entries
+}
+
+fn sitemap_item(entry: &SitemapEntry) -> Result<RssItem> {
+    let mut item = Item::default();
+    item.set_link(entry.loc.clone());
+    let mut rss_item = RssItem::try_from(&item)?;
+    if let Some(lastmod) = entry.lastmod {
+        rss_item.published_timestamp = lastmod;
+        rss_item.published_timestamp_estimated = false;
+    }
+    Ok(rss_item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(loc: &str, lastmod: Option<i64>) -> SitemapEntry {
+        SitemapEntry {
+            loc: loc.to_string(),
+            lastmod,
+            changefreq: None,
+            priority: None,
+        }
+    }
+
+    #[test]
+    fn test_latest_entries() {
+        let entries = vec![
+            entry("https://news.example.com/undated", None),
+            entry("https://news.example.com/old", Some(1000)),
+            entry("https://news.example.com/new", Some(3000)),
+            entry("https://news.example.com/old", Some(2000)),
+        ];
+
+        let latest: Vec<String> = latest_entries(entries.clone(), 10)
+            .into_iter()
+            .map(|entry| entry.loc)
+            .collect();
+        let first = latest_entries(entries, 1);
+
+        assert_eq!(
+            latest,
+            vec![
+                "https://news.example.com/new",
+                "https://news.example.com/old",
+                "https://news.example.com/undated",
+            ]
+        );
+        assert_eq!(
+            first,
+            vec![entry("https://news.example.com/new", Some(3000))]
+        );
+    }
+
+    #[test]
+    fn test_sitemap_item() {
+        let dated =
+            sitemap_item(&entry("https://news.example.com/a", Some(1716300000000))).unwrap();
+        let undated = sitemap_item(&entry("https://news.example.com/a", None)).unwrap();
+
+        assert_eq!(dated.link, "https://news.example.com/a");
+        assert_eq!(dated.published_timestamp, 1716300000000);
+        assert!(!dated.published_timestamp_estimated);
+        assert!(undated.published_timestamp_estimated);
+        // The hash depends on the page URL only.
+        assert_eq!(dated.hash, undated.hash);
+        assert_ne!(
+            dated.hash,
+            sitemap_item(&entry("https://news.example.com/b", None))
+                .unwrap()
+                .hash
+        );
+    }
+}
@@ -0,0 +1,259 @@
+use anyhow::Result;
+use nats_middleware::{KeyValueStore, NatsQueue};
+use serde::{Deserialize, Serialize};
+use shared_states::{RSS_FEED_STATE_BUCKET, feed_hash};
+use std::{collections::VecDeque, time::Duration};
+use tracing::{error, warn};
+
+/// FeedState is the state of a feed kept in addition to the Redis dedup cache, so a flush or
+/// failover of the cache does not publish the latest items of the feed again.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeedState {
+    /// Hashes of the latest processed items, newest first.
+    #[serde(default)]
+    pub seen: VecDeque<String>,
+    /// Newest publication time of the processed items in Unix milliseconds.
+    pub last_published_timestamp: Option<i64>,
+    /// Time of the latest successful poll in Unix milliseconds.
+    pub last_poll_timestamp: Option<i64>,
+    /// Links of the latest items whose articles robots.txt disallowed extracting, newest first.
+    #[serde(default)]
+    pub skipped_by_robots: VecDeque<String>,
+}
+
+impl FeedState {
+    /// Whether the item was processed.
+    pub fn contains(&self, hash: &str) -> bool {
+        self.seen.iter().any(|seen| seen == hash)
+    }
+
+    /// Record a processed item, only the `window` latest items are kept.
+    pub fn record(&mut self, hash: &str, published_timestamp: i64, window: usize) {
+        self.seen.push_front(hash.to_string());
+        self.seen.truncate(window);
+        self.last_published_timestamp =
+            self.last_published_timestamp.max(Some(published_timestamp));
+    }
+
+    /// Record a link robots.txt disallowed, only the `window` latest links are kept.
+    pub fn record_skipped(&mut self, link: &str, window: usize) {
+        self.skipped_by_robots.retain(|skipped| skipped != link);
+        self.skipped_by_robots.push_front(link.to_string());
+        self.skipped_by_robots.truncate(window);
+    }
+}
+
+/// FeedStateChanges are the changes a poll or a retry makes to the state of a feed.
+///
+/// They are applied to the latest stored state when saved, so a retry and a poll of the same feed
+/// do not overwrite each other's records.
+#[derive(Debug, Clone, Default)]
+pub struct FeedStateChanges {
+    /// Hashes and publication times of the processed items, oldest first.
+    recorded: Vec<(String, i64)>,
+    /// Links robots.txt disallowed, oldest first.
+    skipped_by_robots: Vec<String>,
+    last_poll_timestamp: Option<i64>,
+    window: usize,
+}
+
+impl FeedStateChanges {
+    /// Changes keeping the `window` latest items and links of the feed.
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            ..Self::default()
+        }
+    }
+
+    /// Record a processed item, see [`FeedState::record`].
+    pub fn record(&mut self, hash: &str, published_timestamp: i64) {
+        self.recorded.push((hash.to_string(), published_timestamp));
+    }
+
+    /// Record a link robots.txt disallowed, see [`FeedState::record_skipped`].
+    pub fn record_skipped(&mut self, link: &str) {
+        self.skipped_by_robots.push(link.to_string());
+    }
+
+    /// Record a successful poll.
+    pub fn polled(&mut self, timestamp: i64) {
+        self.last_poll_timestamp = Some(timestamp);
+    }
+
+    /// Apply the changes to the state.
+    pub fn apply(&self, state: &mut FeedState) {
+        for (hash, published_timestamp) in &self.recorded {
+            state.record(hash, *published_timestamp, self.window);
+        }
+        for link in &self.skipped_by_robots {
+            state.record_skipped(link, self.window);
+        }
+        if self.last_poll_timestamp.is_some() {
+            state.last_poll_timestamp = self.last_poll_timestamp;
+        }
+    }
+}
+
+/// FeedStates persists the state of every feed in a NATS key-value bucket keyed by the feed hash.
+///
+/// The worker runs without it, deduplicating with Redis only, if JetStream is not available.
+pub struct FeedStates {
+    store: Option<KeyValueStore>,
+}
+
+impl FeedStates {
+    /// Attempts of saving changes while other replicas or retries write the same feed.
+    const SAVE_ATTEMPTS: usize = 5;
+
+    /// Open the bucket of the feed states.
+    ///
+    /// # Arguments
+    /// * `queue` - The queue the bucket is stored in.
+    /// * `max_age` - Time states of feeds no longer polled are kept.
+    pub async fn open(queue: &NatsQueue, max_age: Duration) -> Self {
+        match queue.key_value(RSS_FEED_STATE_BUCKET, max_age).await {
+            Ok(store) => Self { store: Some(store) },
+            Err(e) => {
+                warn!("Feed states are not persisted, deduplicating with the cache only: {e}");
+                Self { store: None }
+            }
+        }
+    }
+
+    /// Load the state of the feed, empty if it has none or states are not persisted.
+    ///
+    /// # Returns
+    /// The state, or an error if it cannot be read.
+    pub async fn load(&self, url: &str) -> Result<FeedState> {
+        let Some(store) = &self.store else {
+            return Ok(FeedState::default());
+        };
+        Ok(store.get(&feed_hash(url)).await?.unwrap_or_default())
+    }
+
+    /// Save the changes to the state of the feed, applied to the latest stored state.
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the feed.
+    /// * `changes` - The changes of the poll or the retry.
+    pub async fn save(&self, url: &str, changes: &FeedStateChanges) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let key = feed_hash(url);
+        for _ in 0..Self::SAVE_ATTEMPTS {
+            let saved = match store.get_with_revision::<FeedState>(&key).await {
+                Ok((state, revision)) => {
+                    let mut state = state.unwrap_or_default();
+                    changes.apply(&mut state);
+                    store.update(&key, &state, revision).await
+                }
+                Err(e) => Err(e),
+            };
+            match saved {
+                Ok(true) => return,
+                Ok(false) => continue,
+                Err(e) => {
+                    error!("Failed to save state of feed ( {url} ): {e}");
+                    return;
+                }
+            }
+        }
+        error!(
+            "Failed to save state of feed ( {url} ): changed by others {} times",
+            Self::SAVE_ATTEMPTS
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed_state_record_and_contains() {
+        let mut state = FeedState::default();
+        assert!(!state.contains("a"));
+
+        state.record("a", 1_716_300_000_000, 3);
+        state.record("b", 1_716_200_000_000, 3);
+
+        assert!(state.contains("a"));
+        assert!(state.contains("b"));
+        assert!(!state.contains("c"));
+        assert_eq!(state.seen, ["b", "a"]);
+        // The newest publication time is kept, also when older items are recorded later.
+        assert_eq!(state.last_published_timestamp, Some(1_716_300_000_000));
+    }
+
+    #[test]
+    fn test_feed_state_window_eviction() {
+        let mut state = FeedState::default();
+
+        for (i, hash) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            state.record(hash, i as i64, 3);
+        }
+
+        assert_eq!(state.seen, ["d", "c", "b"]);
+        assert!(!state.contains("a"));
+        assert_eq!(state.last_published_timestamp, Some(3));
+
+        // A smaller window of the feed evicts the oldest items on the next record.
+        state.record("e", 4, 2);
+        assert_eq!(state.seen, ["e", "d"]);
+    }
+
+    #[test]
+    fn test_feed_state_record_skipped() {
+        let mut state = FeedState::default();
+
+        state.record_skipped("https://news.example.com/a", 2);
+        state.record_skipped("https://news.example.com/b", 2);
+        state.record_skipped("https://news.example.com/a", 2);
+        state.record_skipped("https://news.example.com/c", 2);
+
+        assert_eq!(
+            state.skipped_by_robots,
+            ["https://news.example.com/c", "https://news.example.com/a"]
+        );
+    }
+
+    #[test]
+    fn test_feed_state_defaults_missing_fields() {
+        let state: FeedState =
+            serde_json::from_str(r#"{"last_published_timestamp": 7, "last_poll_timestamp": null}"#)
+                .unwrap();
+
+        assert!(state.seen.is_empty());
+        assert!(state.skipped_by_robots.is_empty());
+        assert_eq!(state.last_published_timestamp, Some(7));
+    }
+
+    #[test]
+    fn test_feed_state_changes_apply() {
+        let mut stored = FeedState {
+            seen: VecDeque::from(["a".to_string()]),
+            last_published_timestamp: Some(1),
+            last_poll_timestamp: Some(10),
+            ..Default::default()
+        };
+
+        // A poll and a retry of the feed save their changes to the same stored state.
+        let mut poll = FeedStateChanges::new(3);
+        poll.record("b", 2);
+        poll.record_skipped("https://news.example.com/b");
+        poll.polled(20);
+        let mut retry = FeedStateChanges::new(3);
+        retry.record("c", 3);
+
+        poll.apply(&mut stored);
+        retry.apply(&mut stored);
+
+        assert_eq!(stored.seen, ["c", "b", "a"]);
+        assert_eq!(stored.last_published_timestamp, Some(3));
+        assert_eq!(stored.skipped_by_robots, ["https://news.example.com/b"]);
+        // Retries do not poll, the time of the latest poll is kept.
+        assert_eq!(stored.last_poll_timestamp, Some(20));
+    }
+}
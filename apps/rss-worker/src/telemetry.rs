@@ -1,11 +1,35 @@
+use crate::config::TelemetryConfig;
+use opentelemetry::{global, trace::TracerProvider};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    Resource,
+    propagation::TraceContextPropagator,
+    trace::{Sampler, SdkTracerProvider},
+};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::{EnvFilter, Registry as TracingRegistry};
 
 /// Initialize telemetry with tracing and metrics
-pub fn init_telemetry() -> Result<(), Box<dyn std::error::Error>> {
+///
+/// Spans are exported to the OTLP endpoint of the configuration if it is set.
+///
+/// # Returns
+/// The tracer provider exporting the spans, it has to be shut down on exit so the last spans are
+/// exported, None if spans are not exported.
+pub fn init_telemetry(
+    config: &TelemetryConfig,
+) -> Result<Option<SdkTracerProvider>, Box<dyn std::error::Error>> {
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
 
-    let subscriber = TracingRegistry::default().with(env_filter);
+    let provider = match &config.otlp_endpoint {
+        Some(endpoint) => Some(init_tracer_provider(config, endpoint)?),
+        None => None,
+    };
+    let otel_layer = provider.as_ref().map(|provider| {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(config.service_name.clone()))
+    });
+
+    let subscriber = TracingRegistry::default().with(env_filter).with(otel_layer);
 
     let fmt_layer = tracing_subscriber::fmt::layer()
         .with_target(true)
@@ -14,5 +38,38 @@ pub fn init_telemetry() -> Result<(), Box<dyn std::error::Error>> {
     let subscriber = subscriber.with(fmt_layer);
     tracing::subscriber::set_global_default(subscriber)?;
 
-    Ok(())
+    if let Some(endpoint) = &config.otlp_endpoint {
+        tracing::info!(
+            "Exporting traces of service {} to {endpoint}",
+            config.service_name
+        );
+    }
+
+    Ok(provider)
+}
+
+fn init_tracer_provider(
+    config: &TelemetryConfig,
+    endpoint: &str,
+) -> Result<SdkTracerProvider, Box<dyn std::error::Error>> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let exporter = SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_rate,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build();
+    global::set_tracer_provider(provider.clone());
+
+    Ok(provider)
 }
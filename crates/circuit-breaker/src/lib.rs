@@ -0,0 +1,339 @@
+//! Circuit breaker guarding calls to a downstream dependency.
+//!
+//! After a number of consecutive failures the circuit opens and calls fail right away instead of
+//! waiting for the dependency to time out. Once the open period elapses a single trial call is let
+//! through, the circuit closes if it succeeds and opens again otherwise.
+
+use std::{
+    fmt,
+    future::Future,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+const DEFAULT_FAILURE_THRESHOLD: u32 = 5;
+const DEFAULT_OPEN_DURATION: Duration = Duration::from_secs(30);
+
+/// State of the circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls pass through.
+    Closed,
+    /// A single trial call passes through, other calls are rejected.
+    HalfOpen,
+    /// Calls are rejected.
+    Open,
+}
+
+impl CircuitState {
+    /// Value of the state for gauges, `0` closed, `1` half-open and `2` open.
+    pub fn as_metric(&self) -> i64 {
+        match self {
+            CircuitState::Closed => 0,
+            CircuitState::HalfOpen => 1,
+            CircuitState::Open => 2,
+        }
+    }
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::HalfOpen => write!(f, "half-open"),
+            CircuitState::Open => write!(f, "open"),
+        }
+    }
+}
+
+/// Error of a call guarded by the circuit breaker.
+#[derive(Error, Debug)]
+pub enum CircuitBreakerError<E> {
+    #[error("Circuit breaker of {0} is open")]
+    Open(String),
+
+    #[error(transparent)]
+    Inner(E),
+}
+
+/// Circuit breaker configuration.
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures opening the circuit.
+    pub failure_threshold: u32,
+    /// Time the circuit stays open before a trial call is let through.
+    pub open_duration: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: DEFAULT_FAILURE_THRESHOLD,
+            open_duration: DEFAULT_OPEN_DURATION,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+/// CircuitBreaker guards calls to a single dependency.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    name: String,
+    config: CircuitBreakerConfig,
+    inner: Mutex<Inner>,
+    rejected: AtomicU64,
+}
+
+impl CircuitBreaker {
+    /// Creates a closed circuit breaker.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the guarded dependency used in errors, logs and metrics.
+    /// * `config` - The circuit breaker configuration.
+    pub fn new(name: impl Into<String>, config: CircuitBreakerConfig) -> Self {
+        Self {
+            name: name.into(),
+            config,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                trial_in_flight: false,
+            }),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    /// Name of the guarded dependency.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Current state, an open circuit whose open period elapsed is reported as half-open.
+    pub fn state(&self) -> CircuitState {
+        let inner = self.lock();
+        match inner.state {
+            CircuitState::Open if self.open_elapsed(&inner) => CircuitState::HalfOpen,
+            state => state,
+        }
+    }
+
+    /// Number of calls rejected since the breaker was created.
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    /// Runs the operation if the circuit allows it, every error counts as a failure.
+    ///
+    /// # Arguments
+    /// * `operation` - The call to the dependency.
+    pub async fn call<T, E, F>(&self, operation: F) -> Result<T, CircuitBreakerError<E>>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        self.call_filtered(operation, |_| true).await
+    }
+
+    /// Runs the operation if the circuit allows it.
+    ///
+    /// # Arguments
+    /// * `operation` - The call to the dependency.
+    /// * `is_failure` - Tells errors of an unhealthy dependency, e.g. timeouts, from errors of a
+    ///   healthy one, e.g. constraint violations, that do not count as failures.
+    pub async fn call_filtered<T, E, F, P>(
+        &self,
+        operation: F,
+        is_failure: P,
+    ) -> Result<T, CircuitBreakerError<E>>
+    where
+        F: Future<Output = Result<T, E>>,
+        P: FnOnce(&E) -> bool,
+    {
+        if !self.acquire() {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(CircuitBreakerError::Open(self.name.clone()));
+        }
+
+        let mut permit = Permit {
+            breaker: self,
+            done: false,
+        };
+        let result = operation.await;
+        permit.done = true;
+        match &result {
+            Err(e) if is_failure(e) => self.on_failure(),
+            _ => self.on_success(),
+        }
+
+        result.map_err(CircuitBreakerError::Inner)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn open_elapsed(&self, inner: &Inner) -> bool {
+        inner
+            .opened_at
+            .is_none_or(|opened_at| opened_at.elapsed() >= self.config.open_duration)
+    }
+
+    fn acquire(&self) -> bool {
+        let mut inner = self.lock();
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open if self.open_elapsed(&inner) => {
+                tracing::info!("Circuit breaker of {} is half-open", self.name);
+                inner.state = CircuitState::HalfOpen;
+                inner.trial_in_flight = true;
+                true
+            }
+            CircuitState::Open => false,
+            CircuitState::HalfOpen if inner.trial_in_flight => false,
+            CircuitState::HalfOpen => {
+                inner.trial_in_flight = true;
+                true
+            }
+        }
+    }
+
+    fn on_success(&self) {
+        let mut inner = self.lock();
+        if inner.state != CircuitState::Closed {
+            tracing::info!("Circuit breaker of {} is closed", self.name);
+        }
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.trial_in_flight = false;
+    }
+
+    fn on_failure(&self) {
+        let mut inner = self.lock();
+        inner.trial_in_flight = false;
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        let open = match inner.state {
+            CircuitState::HalfOpen | CircuitState::Open => true,
+            CircuitState::Closed => inner.consecutive_failures >= self.config.failure_threshold,
+        };
+        if open {
+            if inner.state != CircuitState::Open {
+                tracing::warn!(
+                    "Circuit breaker of {} is open after {} consecutive failures",
+                    self.name,
+                    inner.consecutive_failures
+                );
+            }
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+/// Counts a call dropped before it finished, e.g. by a timeout of the caller, as a failure, so a
+/// half-open circuit is not stuck waiting for its trial call.
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    done: bool,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if !self.done {
+            self.breaker.on_failure();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(open_duration: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(
+            "redis",
+            CircuitBreakerConfig {
+                failure_threshold: 2,
+                open_duration,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_opens_and_recovers() {
+        let breaker = breaker(Duration::from_millis(30));
+
+        for _ in 0..2 {
+            let result = breaker.call(async { Err::<(), _>("timeout") }).await;
+            assert!(matches!(result, Err(CircuitBreakerError::Inner("timeout"))));
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        let result = breaker.call(async { Ok::<_, ()>(1) }).await;
+        assert!(matches!(result, Err(CircuitBreakerError::Open(name)) if name == "redis"));
+        assert_eq!(breaker.rejected(), 1);
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        let result = breaker.call(async { Err::<(), _>("timeout") }).await;
+        assert!(matches!(result, Err(CircuitBreakerError::Inner(_))));
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(breaker.call(async { Ok::<_, ()>(1) }).await.unwrap(), 1);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_ignores_filtered_errors() {
+        let breaker = breaker(Duration::from_secs(60));
+
+        for _ in 0..3 {
+            let result = breaker
+                .call_filtered(async { Err::<(), _>("duplicate key") }, |e| *e == "timeout")
+                .await;
+            assert!(matches!(result, Err(CircuitBreakerError::Inner(_))));
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        let result = breaker.call(async { Err::<(), _>("timeout") }).await;
+        assert!(result.is_err());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        let result = breaker.call(async { Ok::<_, &str>(()) }).await;
+        assert!(result.is_ok());
+        let result = breaker.call(async { Err::<(), _>("timeout") }).await;
+        assert!(result.is_err());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_half_open_allows_single_trial() {
+        let breaker = breaker(Duration::ZERO);
+        for _ in 0..2 {
+            let _ = breaker.call(async { Err::<(), _>(()) }).await;
+        }
+
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(1),
+            breaker.call(std::future::pending::<Result<(), ()>>()),
+        )
+        .await;
+        assert!(cancelled.is_err());
+
+        assert!(breaker.acquire());
+        assert!(!breaker.acquire());
+        breaker.on_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,13 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The vendored protoc keeps builds working on machines without protobuf installed.
+    let mut config = tonic_prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+
+    tonic_prost_build::configure().compile_with_config(
+        config,
+        &["proto/semantic_machine/v1/semantic_machine.proto"],
+        &["proto"],
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,8 @@
+//! Protocol buffer definitions of the internal gRPC API of the api-server.
+//!
+//! Backend services use the generated client to ingest items and query users and sentiments
+//! without going through the public HTTP API.
+
+pub mod v1 {
+    tonic::include_proto!("semantic_machine.v1");
+}
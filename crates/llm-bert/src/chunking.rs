@@ -0,0 +1,128 @@
+use serde::{Deserialize, Serialize};
+
+/// ChunkConfig configures how texts longer than the model input are split.
+///
+/// Lengths are counted in words, a word is about 1.3 BERT tokens, so the default of 350 words
+/// stays within the 512 token input of BERT models.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChunkConfig {
+    /// Maximum number of words passed to the model at once.
+    pub max_words: usize,
+    /// Number of words shared by consecutive chunks so context on a boundary is not lost.
+    pub overlap_words: usize,
+}
+
+impl Default for ChunkConfig {
+    fn default() -> Self {
+        Self {
+            max_words: 350,
+            overlap_words: 50,
+        }
+    }
+}
+
+/// Chunk is a part of one of the analyzed texts.
+pub(crate) struct Chunk<'a> {
+    /// Index of the text the chunk belongs to.
+    pub text: usize,
+    /// Byte offset of the chunk in the text.
+    pub offset: usize,
+    pub content: &'a str,
+    /// Number of words in the chunk.
+    pub words: usize,
+}
+
+/// Splits every text into chunks, texts without words become a single empty chunk so every text
+/// gets a result.
+pub(crate) fn chunk_texts<'a>(texts: &'a [String], config: &ChunkConfig) -> Vec<Chunk<'a>> {
+    texts
+        .iter()
+        .enumerate()
+        .flat_map(|(text, content)| {
+            let windows = split_words(content, config.max_words, config.overlap_words);
+            if windows.is_empty() {
+                return vec![Chunk {
+                    text,
+                    offset: 0,
+                    content: content.as_str(),
+                    words: 0,
+                }];
+            }
+            windows
+                .into_iter()
+                .map(|(offset, window)| Chunk {
+                    text,
+                    offset,
+                    content: window,
+                    words: window.split_whitespace().count(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Splits the text into windows of at most `max_words` words overlapping by `overlap` words.
+///
+/// # Returns
+/// Byte offsets of the windows in the text with the windows themselves.
+pub(crate) fn split_words(text: &str, max_words: usize, overlap: usize) -> Vec<(usize, &str)> {
+    let words: Vec<(usize, usize)> = text
+        .split_whitespace()
+        .map(|word| {
+            let start = word.as_ptr() as usize - text.as_ptr() as usize;
+            (start, start + word.len())
+        })
+        .collect();
+    if words.is_empty() {
+        return Vec::new();
+    }
+
+    let max_words = max_words.max(1);
+    let step = max_words.saturating_sub(overlap).max(1);
+    let mut windows = Vec::new();
+    let mut first = 0;
+    loop {
+        let last = (first + max_words).min(words.len()) - 1;
+        let (start, end) = (words[first].0, words[last].1);
+        windows.push((start, &text[start..end]));
+        if last == words.len() - 1 {
+            break;
+        }
+        first += step;
+    }
+
+    windows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_words() {
+        let windows = split_words("a b  c d e", 3, 1);
+
+        assert_eq!(windows, vec![(0, "a b  c"), (5, "c d e")]);
+        assert_eq!(split_words("a b", 3, 1), vec![(0, "a b")]);
+        assert!(split_words("  ", 3, 1).is_empty());
+    }
+
+    #[test]
+    fn test_chunk_texts() {
+        let texts = vec!["a b c d".to_string(), " ".to_string()];
+        let config = ChunkConfig {
+            max_words: 3,
+            overlap_words: 1,
+        };
+
+        let chunks: Vec<(usize, usize, &str, usize)> = chunk_texts(&texts, &config)
+            .iter()
+            .map(|c| (c.text, c.offset, c.content, c.words))
+            .collect();
+
+        assert_eq!(
+            chunks,
+            vec![(0, 0, "a b c", 3), (0, 4, "c d", 2), (1, 0, " ", 0)]
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use crate::inference::InferenceConfig;
+use crate::sentiment::SentimentDomain;
+use crate::source::{ModelSource, RemoteFile};
+use serde::{Deserialize, Serialize};
+use std::{env, fmt, path::PathBuf, str::FromStr, time::Duration};
+use thiserror::Error;
+
+const DEFAULT_MODEL_CACHE_DIR: &str = "models";
+
+/// BertConfigError describes invalid model configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BertConfigError {
+    #[error("Invalid value of {name}: {value}")]
+    InvalidValue { name: String, value: String },
+}
+
+/// Device the model runs on.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum Device {
+    #[default]
+    Cpu,
+    /// CUDA device with the given index.
+    Cuda(usize),
+}
+
+impl FromStr for Device {
+    type Err = BertConfigError;
+
+    /// Parses `cpu`, `cuda` (index 0) or `cuda:<index>`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || BertConfigError::InvalidValue {
+            name: "device".to_string(),
+            value: value.to_string(),
+        };
+
+        match value.trim().to_lowercase().as_str() {
+            "cpu" => Ok(Self::Cpu),
+            "cuda" | "gpu" => Ok(Self::Cuda(0)),
+            device => device
+                .strip_prefix("cuda:")
+                .and_then(|index| index.parse().ok())
+                .map(Self::Cuda)
+                .ok_or_else(invalid),
+        }
+    }
+}
+
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Cpu => write!(f, "cpu"),
+            Self::Cuda(index) => write!(f, "cuda:{index}"),
+        }
+    }
+}
+
+/// BertConfig selects the model and the hardware it runs on.
+///
+/// It is accepted by [`crate::SentimentClassifier::spawn`], which makes the model files available
+/// and hands the device, the model and the threads to the model loader as [`ModelSettings`].
+#[derive(Debug, Clone)]
+pub struct BertConfig {
+    pub device: Device,
+    /// Domain of the sentiment model, selects the default model and its label configuration.
+    pub sentiment_domain: SentimentDomain,
+    /// Where the model files come from.
+    pub model: ModelSource,
+    /// Directory remote models are downloaded to.
+    pub cache_dir: PathBuf,
+    /// Number of threads the model runtime uses for a single prediction.
+    pub num_threads: usize,
+    /// Batching configuration of the inference thread.
+    pub inference: InferenceConfig,
+}
+
+/// ModelSettings tells a model loader which model to load and where to run it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelSettings {
+    pub device: Device,
+    /// Where the model files come from.
+    pub model: ModelSource,
+    /// Local directory with the model files, None for models resolved by name by the model
+    /// runtime.
+    pub model_dir: Option<PathBuf>,
+    /// Number of threads the model runtime uses for a single prediction.
+    pub num_threads: usize,
+}
+
+impl Default for BertConfig {
+    fn default() -> Self {
+        Self {
+            device: Device::Cpu,
+            sentiment_domain: SentimentDomain::General,
+            model: SentimentDomain::General.model(),
+            cache_dir: PathBuf::from(DEFAULT_MODEL_CACHE_DIR),
+            num_threads: 1,
+            inference: InferenceConfig::default(),
+        }
+    }
+}
+
+impl BertConfig {
+    /// Reads the configuration from the environment, unset variables fall back to defaults.
+    ///
+    /// The model is loaded from `BERT_MODEL_PATH` if set, otherwise downloaded from `BERT_MODEL_URL`
+    /// with `BERT_MODEL_FILES` listed as `name=sha256` pairs separated by commas, otherwise
+    /// resolved by name from `BERT_MODEL_NAME` and `BERT_MODEL_REVISION`, otherwise the default model
+    /// of `BERT_SENTIMENT_DOMAIN` (`general` or `financial`) is used.
+    ///
+    /// Other variables: `BERT_DEVICE`, `BERT_MODEL_CACHE_DIR`, `BERT_NUM_THREADS`,
+    /// `BERT_MAX_BATCH_SIZE`, `BERT_BATCH_WINDOW_MS`, `BERT_WORKERS`, `BERT_QUEUE_CAPACITY`,
+    /// `BERT_QUEUE_TIMEOUT_MS` and `BERT_REQUEST_TIMEOUT_MS`.
+    pub fn from_env() -> Result<Self, BertConfigError> {
+        Self::from_vars(|name| env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self, BertConfigError> {
+        let defaults = Self::default();
+        let var = |name: &str| var(name).filter(|value| !value.trim().is_empty());
+        let parse = |name: &str, value: String| {
+            value
+                .trim()
+                .parse::<u64>()
+                .map_err(|_| BertConfigError::InvalidValue {
+                    name: name.to_string(),
+                    value,
+                })
+        };
+
+        let device = match var("BERT_DEVICE") {
+            Some(value) => value.parse().map_err(|_| BertConfigError::InvalidValue {
+                name: "BERT_DEVICE".to_string(),
+                value,
+            })?,
+            None => defaults.device,
+        };
+        let sentiment_domain = match var("BERT_SENTIMENT_DOMAIN") {
+            Some(value) => value.parse().map_err(|_| BertConfigError::InvalidValue {
+                name: "BERT_SENTIMENT_DOMAIN".to_string(),
+                value,
+            })?,
+            None => defaults.sentiment_domain,
+        };
+        let num_threads = match var("BERT_NUM_THREADS") {
+            Some(value) => parse("BERT_NUM_THREADS", value)? as usize,
+            None => defaults.num_threads,
+        };
+        let max_batch_size = match var("BERT_MAX_BATCH_SIZE") {
+            Some(value) => parse("BERT_MAX_BATCH_SIZE", value)? as usize,
+            None => defaults.inference.max_batch_size,
+        };
+        let batch_window = match var("BERT_BATCH_WINDOW_MS") {
+            Some(value) => Duration::from_millis(parse("BERT_BATCH_WINDOW_MS", value)?),
+            None => defaults.inference.batch_window,
+        };
+        let workers = match var("BERT_WORKERS") {
+            Some(value) => parse("BERT_WORKERS", value)? as usize,
+            None => defaults.inference.workers,
+        };
+        let queue_capacity = match var("BERT_QUEUE_CAPACITY") {
+            Some(value) => parse("BERT_QUEUE_CAPACITY", value)? as usize,
+            None => defaults.inference.queue_capacity,
+        };
+        let queue_timeout = match var("BERT_QUEUE_TIMEOUT_MS") {
+            Some(value) => Duration::from_millis(parse("BERT_QUEUE_TIMEOUT_MS", value)?),
+            None => defaults.inference.queue_timeout,
+        };
+        let request_timeout = match var("BERT_REQUEST_TIMEOUT_MS") {
+            Some(value) => Duration::from_millis(parse("BERT_REQUEST_TIMEOUT_MS", value)?),
+            None => defaults.inference.request_timeout,
+        };
+
+        let model = match (var("BERT_MODEL_PATH"), var("BERT_MODEL_URL")) {
+            (Some(path), _) => ModelSource::Local(PathBuf::from(path)),
+            (None, Some(base_url)) => ModelSource::Remote {
+                base_url,
+                files: parse_remote_files(&var("BERT_MODEL_FILES").unwrap_or_default())?,
+            },
+            (None, None) => match var("BERT_MODEL_NAME") {
+                Some(name) => ModelSource::Named {
+                    name,
+                    revision: var("BERT_MODEL_REVISION"),
+                },
+                None => sentiment_domain.model(),
+            },
+        };
+
+        Ok(Self {
+            device,
+            sentiment_domain,
+            model,
+            cache_dir: var("BERT_MODEL_CACHE_DIR")
+                .map(PathBuf::from)
+                .unwrap_or(defaults.cache_dir),
+            num_threads: num_threads.max(1),
+            inference: InferenceConfig {
+                max_batch_size,
+                batch_window,
+                workers: workers.max(1),
+                queue_capacity: queue_capacity.max(1),
+                queue_timeout,
+                request_timeout,
+                metrics: defaults.inference.metrics,
+            },
+        })
+    }
+}
+
+fn parse_remote_files(value: &str) -> Result<Vec<RemoteFile>, BertConfigError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|file| !file.is_empty())
+        .map(|file| match file.split_once('=') {
+            Some((name, sha256)) if !name.trim().is_empty() && !sha256.trim().is_empty() => {
+                Ok(RemoteFile {
+                    name: name.trim().to_string(),
+                    sha256: sha256.trim().to_string(),
+                })
+            }
+            _ => Err(BertConfigError::InvalidValue {
+                name: "BERT_MODEL_FILES".to_string(),
+                value: file.to_string(),
+            }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_device_from_str() {
+        assert_eq!("cpu".parse(), Ok(Device::Cpu));
+        assert_eq!("CUDA".parse(), Ok(Device::Cuda(0)));
+        assert_eq!("cuda:2".parse(), Ok(Device::Cuda(2)));
+        assert!("tpu".parse::<Device>().is_err());
+        assert_eq!(Device::Cuda(1).to_string(), "cuda:1");
+    }
+
+    #[test]
+    fn test_bert_config_from_vars() {
+        let vars = HashMap::from([
+            ("BERT_DEVICE", "cuda:1"),
+            ("BERT_MODEL_NAME", "ProsusAI/finbert"),
+            ("BERT_NUM_THREADS", "4"),
+            ("BERT_BATCH_WINDOW_MS", "20"),
+            ("BERT_WORKERS", "3"),
+            ("BERT_SENTIMENT_DOMAIN", "financial"),
+            ("BERT_QUEUE_TIMEOUT_MS", "250"),
+        ]);
+
+        let config = BertConfig::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+
+        assert_eq!(config.device, Device::Cuda(1));
+        assert_eq!(
+            config.model,
+            ModelSource::Named {
+                name: "ProsusAI/finbert".to_string(),
+                revision: None
+            }
+        );
+        assert_eq!(config.sentiment_domain, SentimentDomain::Financial);
+        assert_eq!(config.cache_dir, PathBuf::from("models"));
+        assert_eq!(config.num_threads, 4);
+        assert_eq!(config.inference.max_batch_size, 32);
+        assert_eq!(config.inference.batch_window, Duration::from_millis(20));
+        assert_eq!(config.inference.workers, 3);
+        assert_eq!(config.inference.queue_timeout, Duration::from_millis(250));
+        assert_eq!(config.inference.queue_capacity, 100);
+
+        let err =
+            BertConfig::from_vars(|name| (name == "BERT_NUM_THREADS").then(|| "many".to_string()))
+                .unwrap_err();
+        assert_eq!(
+            err,
+            BertConfigError::InvalidValue {
+                name: "BERT_NUM_THREADS".to_string(),
+                value: "many".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_bert_config_remote_model() {
+        let vars = HashMap::from([
+            (
+                "BERT_MODEL_URL",
+                "https://models.example.com/crypto-sentiment",
+            ),
+            (
+                "BERT_MODEL_FILES",
+                "config.json=ab12, model.safetensors=cd34",
+            ),
+            ("BERT_MODEL_NAME", "ignored"),
+        ]);
+
+        let config = BertConfig::from_vars(|name| vars.get(name).map(|v| v.to_string())).unwrap();
+
+        assert_eq!(
+            config.model,
+            ModelSource::Remote {
+                base_url: "https://models.example.com/crypto-sentiment".to_string(),
+                files: vec![
+                    RemoteFile {
+                        name: "config.json".to_string(),
+                        sha256: "ab12".to_string()
+                    },
+                    RemoteFile {
+                        name: "model.safetensors".to_string(),
+                        sha256: "cd34".to_string()
+                    },
+                ]
+            }
+        );
+        assert!(parse_remote_files("config.json").is_err());
+    }
+}
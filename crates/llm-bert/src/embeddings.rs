@@ -0,0 +1,242 @@
+use crate::BertAnalityze;
+use crate::inference::{InferenceConfig, InferenceError, InferenceWorker, Model};
+use anyhow::Result;
+use shared_states::{Embedding, cosine_similarity};
+
+const EMBEDDER_MODEL_NAME: &str = "embedder";
+
+/// Embedder turns texts into sentence embeddings with a model running on a dedicated inference thread.
+///
+/// Vectors returned by the model are checked against the configured dimension and L2 normalized,
+/// so similarity of embeddings is their dot product.
+pub struct Embedder {
+    worker: InferenceWorker<String, Vec<f32>>,
+    dimension: usize,
+}
+
+impl Embedder {
+    /// Creates a new embedder loading the model on its inference thread.
+    ///
+    /// # Arguments
+    /// * `dimension` - Size of the vectors produced by the model.
+    /// * `config` - Batching configuration of the inference thread.
+    /// * `loader` - Creates the model, one pooled vector is expected per text.
+    ///
+    /// # Returns
+    /// A new embedder or error if the model could not be loaded.
+    pub fn new<M, F>(dimension: usize, config: InferenceConfig, loader: F) -> Result<Self>
+    where
+        M: Model<String, Vec<f32>>,
+        F: Fn() -> Result<M> + Send + Sync + 'static,
+    {
+        Ok(Self {
+            worker: InferenceWorker::spawn(EMBEDDER_MODEL_NAME, config, loader)?,
+            dimension,
+        })
+    }
+
+    /// Size of the produced vectors.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Embeds the texts.
+    ///
+    /// # Arguments
+    /// * `texts` - The texts to embed.
+    ///
+    /// # Returns
+    /// One unit length vector per text in the same order, or error otherwise.
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let vectors = self.worker.predict(texts.to_vec()).await?;
+
+        vectors
+            .into_iter()
+            .map(|mut vector| {
+                if vector.len() != self.dimension {
+                    return Err(InferenceError::DimensionMismatch {
+                        expected: self.dimension,
+                        got: vector.len(),
+                    }
+                    .into());
+                }
+                normalize(&mut vector);
+                Ok(vector)
+            })
+            .collect()
+    }
+
+    /// Semantic similarity of two texts.
+    ///
+    /// # Returns
+    /// Cosine similarity of the text embeddings in range `-1.0..=1.0`, or error otherwise.
+    pub async fn similarity(&self, a: &str, b: &str) -> Result<f32> {
+        let vectors = self.embed(&[a.to_string(), b.to_string()]).await?;
+
+        Ok(cosine_similarity(&vectors[0], &vectors[1]))
+    }
+
+    /// Finds texts that are not near duplicates of earlier texts, e.g. the same wire story
+    /// republished by several feeds.
+    ///
+    /// # Arguments
+    /// * `texts` - The texts in order of preference, the first of duplicates is kept.
+    /// * `threshold` - Texts at least this similar to a kept text are duplicates.
+    ///
+    /// # Returns
+    /// Indices of the kept texts in ascending order, or error otherwise.
+    pub async fn dedup(&self, texts: &[String], threshold: f32) -> Result<Vec<usize>> {
+        let vectors = self.embed(texts).await?;
+        let mut kept: Vec<usize> = Vec::new();
+        for (i, vector) in vectors.iter().enumerate() {
+            if kept
+                .iter()
+                .all(|&k| cosine_similarity(&vectors[k], vector) < threshold)
+            {
+                kept.push(i);
+            }
+        }
+
+        Ok(kept)
+    }
+}
+
+impl<'a> BertAnalityze<'a, Embedding> for Embedder {
+    async fn analyze(&self, texts: &[String]) -> Result<Vec<Embedding>> {
+        Ok(self
+            .embed(texts)
+            .await?
+            .into_iter()
+            .map(|vector| Embedding { vector })
+            .collect())
+    }
+}
+
+/// Averages token embeddings over the tokens of the attention mask, sentence-transformers style.
+///
+/// # Arguments
+/// * `token_embeddings` - Embeddings of all tokens of a text.
+/// * `attention_mask` - `1` for real tokens and `0` for padding.
+///
+/// # Returns
+/// The pooled vector, zeros if no token is attended.
+pub fn mean_pooling(token_embeddings: &[Vec<f32>], attention_mask: &[u32]) -> Vec<f32> {
+    let dimension = token_embeddings.first().map(Vec::len).unwrap_or_default();
+    let mut pooled = vec![0.0; dimension];
+    let mut count = 0.0;
+
+    for (token, _) in token_embeddings
+        .iter()
+        .zip(attention_mask)
+        .filter(|(_, mask)| **mask != 0)
+    {
+        for (sum, value) in pooled.iter_mut().zip(token) {
+            *sum += value;
+        }
+        count += 1.0;
+    }
+
+    if count > 0.0 {
+        pooled.iter_mut().for_each(|value| *value /= count);
+    }
+
+    pooled
+}
+
+fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|value| value * value).sum::<f32>().sqrt()
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = norm(vector);
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|value| *value /= norm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Embeds texts as counts of the words `bitcoin`, `ether` and `football`.
+    struct BagOfWordsModel;
+
+    impl Model<String, Vec<f32>> for BagOfWordsModel {
+        fn predict(&mut self, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+            Ok(inputs
+                .iter()
+                .map(|text| {
+                    let text = text.to_lowercase();
+                    ["bitcoin", "ether", "football"]
+                        .iter()
+                        .map(|word| text.matches(word).count() as f32)
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_embedder_analyze() {
+        let embedder =
+            Embedder::new(3, InferenceConfig::default(), || Ok(BagOfWordsModel)).unwrap();
+        let texts = vec![
+            "Bitcoin and ether rally".to_string(),
+            "Ether follows bitcoin higher".to_string(),
+            "Football club wins".to_string(),
+        ];
+
+        let embeddings = embedder.analyze(&texts).await.unwrap();
+
+        assert_eq!(embedder.dimension(), 3);
+        assert!((norm(&embeddings[0].vector) - 1.0).abs() < 1e-6);
+        assert!((embeddings[0].similarity(&embeddings[1]) - 1.0).abs() < 1e-6);
+        assert_eq!(embeddings[0].similarity(&embeddings[2]), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_embedder_similarity_and_dedup() {
+        let embedder =
+            Embedder::new(3, InferenceConfig::default(), || Ok(BagOfWordsModel)).unwrap();
+        let texts = vec![
+            "Bitcoin and ether rally".to_string(),
+            "Football club wins".to_string(),
+            "Ether and bitcoin rally again".to_string(),
+            "Bitcoin slips".to_string(),
+        ];
+
+        let similarity = embedder
+            .similarity("bitcoin ether", "ether bitcoin")
+            .await
+            .unwrap();
+        let kept = embedder.dedup(&texts, 0.95).await.unwrap();
+
+        assert!((similarity - 1.0).abs() < 1e-6);
+        assert_eq!(kept, vec![0, 1, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_embedder_dimension_mismatch() {
+        let embedder =
+            Embedder::new(4, InferenceConfig::default(), || Ok(BagOfWordsModel)).unwrap();
+
+        let err = embedder.embed(&["bitcoin".to_string()]).await.unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<InferenceError>(),
+            Some(&InferenceError::DimensionMismatch {
+                expected: 4,
+                got: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_mean_pooling() {
+        let tokens = vec![vec![1.0, 2.0], vec![3.0, 4.0], vec![100.0, 100.0]];
+
+        assert_eq!(mean_pooling(&tokens, &[1, 1, 0]), vec![2.0, 3.0]);
+        assert_eq!(mean_pooling(&tokens, &[0, 0, 0]), vec![0.0, 0.0]);
+        assert_eq!(mean_pooling(&[], &[]), Vec::<f32>::new());
+    }
+}
@@ -0,0 +1,712 @@
+use crate::metrics::{MetricsSink, NoopMetrics};
+use anyhow::{Result, anyhow};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel, sync_channel};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, mpsc, oneshot};
+use tokio::time::timeout;
+
+/// InferenceError describes failures of the inference thread.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InferenceError {
+    #[error("Failed to load model ( {name} ): {reason}")]
+    ModelLoad { name: String, reason: String },
+
+    #[error("Inference thread ( {0} ) is stopped")]
+    Stopped(String),
+
+    #[error("Model returned {got} outputs for {expected} inputs")]
+    OutputMismatch { expected: usize, got: usize },
+
+    #[error("Model returned vector of dimension {got}, expected {expected}")]
+    DimensionMismatch { expected: usize, got: usize },
+
+    #[error("Inference queue ( {0} ) is full")]
+    Busy(String),
+
+    #[error("Inference ( {0} ) timed out")]
+    Timeout(String),
+}
+
+/// Model represents a blocking model that is run on a dedicated inference thread.
+///
+/// Models are created on the inference thread and never leave it, so they don't have to be `Send`.
+pub trait Model<I, O> {
+    /// Predicts the outputs of the inputs, one output per input in the same order.
+    ///
+    /// * `inputs` - slice of inputs to predict.
+    ///
+    /// # Returns
+    /// * Vector of outputs or error otherwise.
+    fn predict(&mut self, inputs: &[I]) -> Result<Vec<O>>;
+}
+
+/// InferenceConfig configures batching on the inference thread.
+#[derive(Clone)]
+pub struct InferenceConfig {
+    /// Maximum number of inputs passed to the model in a single call, larger batches are split.
+    pub max_batch_size: usize,
+    /// Time the inference thread waits for requests of other callers to merge them into one batch.
+    pub batch_window: Duration,
+    /// Number of model instances, each running on its own inference thread.
+    pub workers: usize,
+    /// Maximum number of requests queued or in progress, callers wait for a free slot.
+    pub queue_capacity: usize,
+    /// Time a caller waits for a free slot in the queue before failing as busy.
+    pub queue_timeout: Duration,
+    /// Time a caller waits for the result of a queued request.
+    pub request_timeout: Duration,
+    /// Receives latency, queue depth, batch size and model load time measurements.
+    pub metrics: Arc<dyn MetricsSink>,
+}
+
+impl fmt::Debug for InferenceConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InferenceConfig")
+            .field("max_batch_size", &self.max_batch_size)
+            .field("batch_window", &self.batch_window)
+            .field("workers", &self.workers)
+            .field("queue_capacity", &self.queue_capacity)
+            .field("queue_timeout", &self.queue_timeout)
+            .field("request_timeout", &self.request_timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for InferenceConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 32,
+            batch_window: Duration::from_millis(5),
+            workers: 1,
+            queue_capacity: 100,
+            queue_timeout: Duration::from_secs(1),
+            request_timeout: Duration::from_secs(60),
+            metrics: Arc::new(NoopMetrics),
+        }
+    }
+}
+
+/// Progress of a prediction reported after every chunk passed to the model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Number of inputs predicted so far.
+    pub completed: usize,
+    /// Number of inputs of the request.
+    pub total: usize,
+    /// Estimated time to predict the remaining inputs.
+    pub eta: Duration,
+}
+
+struct InferenceRequest<I, O> {
+    inputs: Vec<I>,
+    respond: oneshot::Sender<Result<Vec<O>>>,
+    progress: Option<mpsc::UnboundedSender<Progress>>,
+    /// Slot of the request, released once the worker thread is done with it, also if the caller
+    /// timed out waiting.
+    _slot: OwnedSemaphorePermit,
+}
+
+/// InferenceWorker owns a pool of model instances, each running on a dedicated OS thread, and serves
+/// predictions to async callers dispatching requests to the threads round-robin.
+///
+/// Requests of concurrent callers arriving within the batch window are merged, and the merged
+/// inputs are passed to the model in chunks of at most the maximum batch size.
+/// The threads stop when the worker is dropped.
+pub struct InferenceWorker<I, O> {
+    name: String,
+    senders: Vec<Sender<InferenceRequest<I, O>>>,
+    next: AtomicUsize,
+    slots: Arc<Semaphore>,
+    queue_capacity: usize,
+    metrics: Arc<dyn MetricsSink>,
+    queue_timeout: Duration,
+    request_timeout: Duration,
+    _handles: Vec<JoinHandle<()>>,
+}
+
+impl<I, O> InferenceWorker<I, O>
+where
+    I: Send + 'static,
+    O: Send + 'static,
+{
+    /// Spawns the inference threads and loads a model instance on each of them.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the model, used for the thread names and errors.
+    /// * `config` - Batching and pool configuration.
+    /// * `loader` - Creates the model on an inference thread, called once per thread.
+    ///
+    /// # Returns
+    /// The worker once all models are loaded, or error if any model could not be loaded.
+    pub fn spawn<M, F>(name: &str, config: InferenceConfig, loader: F) -> Result<Self>
+    where
+        M: Model<I, O>,
+        F: Fn() -> Result<M> + Send + Sync + 'static,
+    {
+        let workers = config.workers.max(1);
+        let loader = Arc::new(loader);
+        let (ready_sender, ready_receiver) = sync_channel::<Result<(), String>>(workers);
+        let mut senders = Vec::with_capacity(workers);
+        let mut handles = Vec::with_capacity(workers);
+
+        for i in 0..workers {
+            let (sender, receiver) = channel::<InferenceRequest<I, O>>();
+            let (loader, ready_sender, config) =
+                (loader.clone(), ready_sender.clone(), config.clone());
+            let model_name = name.to_string();
+            let handle = thread::Builder::new()
+                .name(format!("inference-{name}-{i}"))
+                .spawn(move || {
+                    let started = Instant::now();
+                    match loader() {
+                        Ok(model) => {
+                            config.metrics.model_loaded(&model_name, started.elapsed());
+                            let _ = ready_sender.send(Ok(()));
+                            drop(ready_sender);
+                            serve(model, &model_name, receiver, config);
+                        }
+                        Err(e) => {
+                            let _ = ready_sender.send(Err(e.to_string()));
+                        }
+                    }
+                })?;
+            senders.push(sender);
+            handles.push(handle);
+        }
+        drop(ready_sender);
+
+        let model_load_error = |reason: String| InferenceError::ModelLoad {
+            name: name.to_string(),
+            reason,
+        };
+        for _ in 0..workers {
+            match ready_receiver.recv() {
+                Ok(Ok(())) => {}
+                Ok(Err(reason)) => return Err(model_load_error(reason).into()),
+                Err(_) => {
+                    return Err(model_load_error("inference thread panicked".to_string()).into());
+                }
+            }
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            senders,
+            next: AtomicUsize::new(0),
+            slots: Arc::new(Semaphore::new(config.queue_capacity.max(1))),
+            queue_capacity: config.queue_capacity.max(1),
+            metrics: config.metrics,
+            queue_timeout: config.queue_timeout,
+            request_timeout: config.request_timeout,
+            _handles: handles,
+        })
+    }
+
+    /// Predicts the outputs of the inputs on the inference thread.
+    ///
+    /// # Arguments
+    /// * `inputs` - The inputs to predict.
+    ///
+    /// # Returns
+    /// One output per input in the same order, [`InferenceError::Busy`] if the queue stayed full
+    /// for the queue timeout, [`InferenceError::Timeout`] if the result didn't arrive in time,
+    /// or error otherwise.
+    pub async fn predict(&self, inputs: Vec<I>) -> Result<Vec<O>> {
+        self.send(inputs, None).await
+    }
+
+    /// Predicts the outputs of the inputs reporting progress, useful for large batches.
+    ///
+    /// # Arguments
+    /// * `inputs` - The inputs to predict.
+    /// * `progress` - Receives the progress from the inference thread after every predicted chunk.
+    ///
+    /// # Returns
+    /// Same as [`InferenceWorker::predict`].
+    pub async fn predict_with_progress(
+        &self,
+        inputs: Vec<I>,
+        progress: mpsc::UnboundedSender<Progress>,
+    ) -> Result<Vec<O>> {
+        self.send(inputs, Some(progress)).await
+    }
+
+    async fn send(
+        &self,
+        inputs: Vec<I>,
+        progress: Option<mpsc::UnboundedSender<Progress>>,
+    ) -> Result<Vec<O>> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let started = Instant::now();
+        let slot = timeout(self.queue_timeout, self.slots.clone().acquire_owned())
+            .await
+            .map_err(|_| InferenceError::Busy(self.name.clone()))?
+            .map_err(|_| InferenceError::Stopped(self.name.clone()))?;
+        self.report_queue_depth();
+
+        let expected = inputs.len();
+        let (respond, response) = oneshot::channel();
+        let next = self.next.fetch_add(1, Ordering::Relaxed) % self.senders.len();
+        self.senders[next]
+            .send(InferenceRequest {
+                inputs,
+                respond,
+                progress,
+                _slot: slot,
+            })
+            .map_err(|_| InferenceError::Stopped(self.name.clone()))?;
+
+        let outputs = timeout(self.request_timeout, response)
+            .await
+            .map_err(|_| InferenceError::Timeout(self.name.clone()))?
+            .map_err(|_| InferenceError::Stopped(self.name.clone()))??;
+        self.report_queue_depth();
+        self.metrics
+            .request_completed(&self.name, started.elapsed());
+        if outputs.len() != expected {
+            return Err(InferenceError::OutputMismatch {
+                expected,
+                got: outputs.len(),
+            }
+            .into());
+        }
+
+        Ok(outputs)
+    }
+
+    fn report_queue_depth(&self) {
+        let depth = self.queue_capacity - self.slots.available_permits();
+        self.metrics.queue_depth(&self.name, depth);
+    }
+}
+
+fn serve<I, O, M: Model<I, O>>(
+    mut model: M,
+    name: &str,
+    receiver: Receiver<InferenceRequest<I, O>>,
+    config: InferenceConfig,
+) {
+    let max_batch_size = config.max_batch_size.max(1);
+
+    while let Ok(request) = receiver.recv() {
+        let mut requests = vec![request];
+        let mut pending = requests[0].inputs.len();
+        let deadline = Instant::now() + config.batch_window;
+        while pending < max_batch_size {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match receiver.recv_timeout(remaining) {
+                Ok(request) => {
+                    pending += request.inputs.len();
+                    requests.push(request);
+                }
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        // Callers that timed out are gone, their inputs are not predicted.
+        requests.retain(|request| !request.respond.is_closed());
+        if requests.is_empty() {
+            continue;
+        }
+
+        let sizes: Vec<usize> = requests.iter().map(|r| r.inputs.len()).collect();
+        let inputs: Vec<I> = requests
+            .iter_mut()
+            .flat_map(|r| std::mem::take(&mut r.inputs))
+            .collect();
+
+        let started = Instant::now();
+        let report = |previous: usize, done: usize| {
+            let mut start = 0;
+            for (request, size) in requests.iter().zip(&sizes) {
+                let end = start + size;
+                if let Some(progress) = &request.progress
+                    && start < done
+                    && previous < end
+                {
+                    let completed = done.min(end) - start;
+                    let eta = started
+                        .elapsed()
+                        .mul_f64((size - completed) as f64 / done as f64);
+                    let _ = progress.send(Progress {
+                        completed,
+                        total: *size,
+                        eta,
+                    });
+                }
+                start = end;
+            }
+        };
+
+        let metrics = |batch_size: usize, duration: Duration| {
+            config.metrics.batch_predicted(name, batch_size, duration);
+        };
+
+        match predict_chunked(&mut model, &inputs, max_batch_size, report, metrics) {
+            Ok(outputs) => {
+                let mut outputs = outputs.into_iter();
+                for (request, size) in requests.into_iter().zip(sizes) {
+                    let _ = request
+                        .respond
+                        .send(Ok(outputs.by_ref().take(size).collect()));
+                }
+            }
+            Err(e) => {
+                for request in requests {
+                    let _ = request.respond.send(Err(share_error(&e)));
+                }
+            }
+        }
+    }
+}
+
+/// Copies the error for every caller of a merged batch keeping inference errors downcastable.
+fn share_error(e: &anyhow::Error) -> anyhow::Error {
+    match e.downcast_ref::<InferenceError>() {
+        Some(e) => e.clone().into(),
+        None => anyhow!("{e:#}"),
+    }
+}
+
+fn predict_chunked<I, O, M: Model<I, O>>(
+    model: &mut M,
+    inputs: &[I],
+    max_batch_size: usize,
+    mut on_chunk: impl FnMut(usize, usize),
+    mut on_batch: impl FnMut(usize, Duration),
+) -> Result<Vec<O>> {
+    let mut outputs = Vec::with_capacity(inputs.len());
+    for chunk in inputs.chunks(max_batch_size) {
+        let started = Instant::now();
+        let chunk_outputs = model.predict(chunk)?;
+        on_batch(chunk.len(), started.elapsed());
+        if chunk_outputs.len() != chunk.len() {
+            return Err(InferenceError::OutputMismatch {
+                expected: chunk.len(),
+                got: chunk_outputs.len(),
+            }
+            .into());
+        }
+        let previous = outputs.len();
+        outputs.extend(chunk_outputs);
+        on_chunk(previous, outputs.len());
+    }
+
+    Ok(outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct LengthModel;
+
+    impl Model<String, usize> for LengthModel {
+        fn predict(&mut self, inputs: &[String]) -> Result<Vec<usize>> {
+            Ok(inputs.iter().map(String::len).collect())
+        }
+    }
+
+    struct BrokenModel;
+
+    impl Model<String, usize> for BrokenModel {
+        fn predict(&mut self, _inputs: &[String]) -> Result<Vec<usize>> {
+            Ok(vec![0])
+        }
+    }
+
+    /// Records the size of every batch it predicts.
+    struct RecordingModel {
+        batches: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl Model<String, usize> for RecordingModel {
+        fn predict(&mut self, inputs: &[String]) -> Result<Vec<usize>> {
+            self.batches.lock().unwrap().push(inputs.len());
+            Ok(inputs.iter().map(String::len).collect())
+        }
+    }
+
+    fn recording_worker(
+        config: InferenceConfig,
+    ) -> (InferenceWorker<String, usize>, Arc<Mutex<Vec<usize>>>) {
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let model_batches = batches.clone();
+        let worker = InferenceWorker::spawn("recording", config, move || {
+            Ok(RecordingModel {
+                batches: model_batches.clone(),
+            })
+        })
+        .unwrap();
+
+        (worker, batches)
+    }
+
+    #[tokio::test]
+    async fn test_inference_worker_splits_large_batches() {
+        let (worker, batches) = recording_worker(InferenceConfig {
+            max_batch_size: 2,
+            batch_window: Duration::ZERO,
+            ..Default::default()
+        });
+        let inputs: Vec<String> = ["a", "bb", "ccc", "dddd", "eeeee"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let outputs = worker.predict(inputs).await.unwrap();
+
+        assert_eq!(outputs, vec![1, 2, 3, 4, 5]);
+        assert_eq!(*batches.lock().unwrap(), vec![2, 2, 1]);
+    }
+
+    #[tokio::test]
+    async fn test_inference_worker_merges_concurrent_callers() {
+        let (worker, batches) = recording_worker(InferenceConfig {
+            max_batch_size: 8,
+            batch_window: Duration::from_millis(200),
+            ..Default::default()
+        });
+
+        let (a, b, c) = tokio::join!(
+            worker.predict(vec!["a".to_string()]),
+            worker.predict(vec!["bb".to_string(), "ccc".to_string()]),
+            worker.predict(vec!["dddd".to_string()]),
+        );
+
+        assert_eq!(a.unwrap(), vec![1]);
+        assert_eq!(b.unwrap(), vec![2, 3]);
+        assert_eq!(c.unwrap(), vec![4]);
+        assert_eq!(*batches.lock().unwrap(), vec![4]);
+    }
+
+    /// Answers with the name of the inference thread it runs on.
+    struct ThreadNameModel;
+
+    impl Model<String, String> for ThreadNameModel {
+        fn predict(&mut self, inputs: &[String]) -> Result<Vec<String>> {
+            let name = thread::current().name().unwrap_or_default().to_string();
+            Ok(inputs.iter().map(|_| name.clone()).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inference_worker_pool_round_robin() {
+        let config = InferenceConfig {
+            workers: 2,
+            ..Default::default()
+        };
+        let worker = InferenceWorker::spawn("pool", config, || Ok(ThreadNameModel)).unwrap();
+
+        let mut threads = Vec::new();
+        for _ in 0..4 {
+            threads.extend(worker.predict(vec!["a".to_string()]).await.unwrap());
+        }
+
+        assert_eq!(
+            threads,
+            vec![
+                "inference-pool-0",
+                "inference-pool-1",
+                "inference-pool-0",
+                "inference-pool-1"
+            ]
+        );
+    }
+
+    /// Sleeps before answering with the lengths of the inputs.
+    struct SlowModel;
+
+    impl Model<String, usize> for SlowModel {
+        fn predict(&mut self, inputs: &[String]) -> Result<Vec<usize>> {
+            thread::sleep(Duration::from_millis(200));
+            Ok(inputs.iter().map(String::len).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inference_worker_busy_and_timeout() {
+        let config = InferenceConfig {
+            batch_window: Duration::ZERO,
+            queue_capacity: 1,
+            queue_timeout: Duration::from_millis(20),
+            ..Default::default()
+        };
+        let worker = InferenceWorker::spawn("slow", config, || Ok(SlowModel)).unwrap();
+
+        let (first, second) = tokio::join!(
+            worker.predict(vec!["a".to_string()]),
+            worker.predict(vec!["b".to_string()]),
+        );
+
+        assert_eq!(first.unwrap(), vec![1]);
+        assert_eq!(
+            second.unwrap_err().downcast_ref::<InferenceError>(),
+            Some(&InferenceError::Busy("slow".to_string()))
+        );
+
+        let config = InferenceConfig {
+            batch_window: Duration::ZERO,
+            queue_capacity: 1,
+            queue_timeout: Duration::from_millis(20),
+            request_timeout: Duration::from_millis(20),
+            ..Default::default()
+        };
+        let worker = InferenceWorker::spawn("slow", config, || Ok(SlowModel)).unwrap();
+
+        let err = worker.predict(vec!["a".to_string()]).await.unwrap_err();
+        let busy = worker.predict(vec!["b".to_string()]).await.unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<InferenceError>(),
+            Some(&InferenceError::Timeout("slow".to_string()))
+        );
+        assert_eq!(
+            busy.downcast_ref::<InferenceError>(),
+            Some(&InferenceError::Busy("slow".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inference_worker_progress() {
+        let config = InferenceConfig {
+            max_batch_size: 2,
+            ..Default::default()
+        };
+        let worker = InferenceWorker::spawn("progress", config, || Ok(LengthModel)).unwrap();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let inputs: Vec<String> = ["a", "bb", "ccc", "dddd", "eeeee"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let outputs = worker.predict_with_progress(inputs, sender).await.unwrap();
+        let single = worker.predict(vec!["a".to_string()]).await.unwrap();
+
+        let mut reports = Vec::new();
+        while let Some(progress) = receiver.recv().await {
+            reports.push((progress.completed, progress.total));
+        }
+        assert_eq!(outputs, vec![1, 2, 3, 4, 5]);
+        assert_eq!(reports, vec![(2, 5), (4, 5), (5, 5)]);
+        assert_eq!(single, vec![1]);
+    }
+
+    /// Records every measurement as a line of text.
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl MetricsSink for RecordingSink {
+        fn model_loaded(&self, model: &str, _duration: Duration) {
+            self.events.lock().unwrap().push(format!("loaded {model}"));
+        }
+
+        fn batch_predicted(&self, model: &str, batch_size: usize, _duration: Duration) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("batch {model} {batch_size}"));
+        }
+
+        fn queue_depth(&self, model: &str, depth: usize) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("depth {model} {depth}"));
+        }
+
+        fn request_completed(&self, model: &str, _latency: Duration) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("completed {model}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inference_worker_metrics() {
+        let sink = Arc::new(RecordingSink::default());
+        let config = InferenceConfig {
+            max_batch_size: 2,
+            metrics: sink.clone(),
+            ..Default::default()
+        };
+        let worker = InferenceWorker::spawn("metrics", config, || Ok(LengthModel)).unwrap();
+
+        worker
+            .predict(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *sink.events.lock().unwrap(),
+            vec![
+                "loaded metrics",
+                "depth metrics 1",
+                "batch metrics 2",
+                "batch metrics 1",
+                "depth metrics 0",
+                "completed metrics"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_inference_worker_predict() {
+        let worker =
+            InferenceWorker::spawn("length", InferenceConfig::default(), || Ok(LengthModel))
+                .unwrap();
+
+        let outputs = worker
+            .predict(vec!["bitcoin".to_string(), "eth".to_string()])
+            .await
+            .unwrap();
+
+        assert_eq!(outputs, vec![7, 3]);
+        assert!(worker.predict(Vec::new()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_inference_worker_errors() {
+        let err = InferenceWorker::<String, usize>::spawn(
+            "missing",
+            InferenceConfig::default(),
+            || -> Result<LengthModel> { Err(anyhow!("weights not found")) },
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.downcast_ref::<InferenceError>(),
+            Some(&InferenceError::ModelLoad {
+                name: "missing".to_string(),
+                reason: "weights not found".to_string()
+            })
+        );
+
+        let worker =
+            InferenceWorker::spawn("broken", InferenceConfig::default(), || Ok(BrokenModel))
+                .unwrap();
+        let err = worker
+            .predict(vec!["a".to_string(), "b".to_string()])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<InferenceError>(),
+            Some(&InferenceError::OutputMismatch {
+                expected: 2,
+                got: 1
+            })
+        );
+    }
+}
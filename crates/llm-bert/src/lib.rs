@@ -1,9 +1,29 @@
+mod chunking;
+mod config;
+mod embeddings;
+mod inference;
+mod metrics;
+mod ner;
+mod qa;
+mod router;
 mod sentiment;
+mod source;
+mod topics;
 
 use anyhow::Result;
-// pub use sentiment::*;
+pub use chunking::ChunkConfig;
+pub use config::*;
+pub use embeddings::*;
+pub use inference::*;
+pub use metrics::*;
+pub use ner::*;
+pub use qa::*;
+pub use router::*;
+pub use sentiment::*;
 use serde::{Deserialize, Serialize};
+pub use source::*;
 use std::fmt::Debug;
+pub use topics::*;
 
 /// BertAnalityze represents an entity that offers bert analitics.
 pub trait BertAnalityze<'a, T>
@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+/// MetricsSink receives measurements of the inference threads, implement it to export them,
+/// e.g. to Prometheus.
+///
+/// All methods default to no-ops so sinks implement only what they export.
+pub trait MetricsSink: Send + Sync {
+    /// A model instance was loaded on an inference thread.
+    fn model_loaded(&self, _model: &str, _duration: Duration) {}
+
+    /// A batch of inputs was passed to the model.
+    fn batch_predicted(&self, _model: &str, _batch_size: usize, _duration: Duration) {}
+
+    /// Number of requests queued or in progress changed.
+    fn queue_depth(&self, _model: &str, _depth: usize) {}
+
+    /// A request was answered, the latency includes waiting in the queue.
+    fn request_completed(&self, _model: &str, _latency: Duration) {}
+}
+
+/// NoopMetrics discards all measurements.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl MetricsSink for NoopMetrics {}
@@ -0,0 +1,261 @@
+use crate::BertAnalityze;
+use crate::chunking::{ChunkConfig, chunk_texts};
+use crate::inference::{InferenceConfig, InferenceWorker, Model};
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use shared_states::{Entity, EntityKind};
+use std::sync::LazyLock;
+
+const NER_MODEL_NAME: &str = "ner";
+const TICKER_SCORE: f32 = 1.0;
+
+const KNOWN_TICKERS: [&str; 20] = [
+    "BTC", "ETH", "SOL", "XRP", "BNB", "ADA", "DOGE", "DOT", "AVAX", "LINK", "MATIC", "LTC", "TRX",
+    "ATOM", "XLM", "USDT", "USDC", "SHIB", "TON", "ARB",
+];
+
+static CASHTAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\$([A-Z][A-Z0-9]{1,9})\b").expect("valid cashtag regex"));
+
+static UPPERCASE_WORD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[A-Z][A-Z0-9]{1,9}\b").expect("valid uppercase word regex"));
+
+/// NerResult holds the entities recognized in a single text.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct NerResult {
+    pub entities: Vec<Entity>,
+}
+
+/// NerClassifier recognizes persons, organizations, locations and crypto tickers in texts.
+///
+/// The token classification model runs on a dedicated inference thread, crypto tickers are
+/// detected from cashtags and well known symbols on top of the model output.
+pub struct NerClassifier {
+    worker: InferenceWorker<String, Vec<Entity>>,
+    min_score: f32,
+    chunking: ChunkConfig,
+}
+
+impl NerClassifier {
+    /// Creates a new classifier loading the token classification model on its inference thread.
+    ///
+    /// # Arguments
+    /// * `config` - Batching configuration of the inference thread.
+    /// * `loader` - Creates the model, one vector of entities is expected per text.
+    ///
+    /// # Returns
+    /// A new classifier or error if the model could not be loaded.
+    pub fn new<M, F>(config: InferenceConfig, loader: F) -> Result<Self>
+    where
+        M: Model<String, Vec<Entity>>,
+        F: Fn() -> Result<M> + Send + Sync + 'static,
+    {
+        Ok(Self {
+            worker: InferenceWorker::spawn(NER_MODEL_NAME, config, loader)?,
+            min_score: 0.0,
+            chunking: ChunkConfig::default(),
+        })
+    }
+
+    /// Sets the minimum score of entities recognized by the model, entities scored below are dropped.
+    pub fn with_min_score(mut self, min_score: f32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    /// Sets how long texts are split, entities of all chunks are merged into the result of the text.
+    pub fn with_chunking(mut self, chunking: ChunkConfig) -> Self {
+        self.chunking = chunking;
+        self
+    }
+}
+
+impl<'a> BertAnalityze<'a, NerResult> for NerClassifier {
+    async fn analyze(&self, texts: &[String]) -> Result<Vec<NerResult>> {
+        let chunks = chunk_texts(texts, &self.chunking);
+        let inputs = chunks.iter().map(|c| c.content.to_string()).collect();
+        let predictions = self.worker.predict(inputs).await?;
+
+        let mut merged: Vec<Vec<Entity>> = vec![Vec::new(); texts.len()];
+        for (chunk, entities) in chunks.iter().zip(predictions) {
+            for entity in entities {
+                let entity = Entity {
+                    start: chunk.offset + entity.start,
+                    end: chunk.offset + entity.end,
+                    ..entity
+                };
+                let text_entities = &mut merged[chunk.text];
+                match text_entities.iter_mut().find(|e| {
+                    e.kind == entity.kind && e.start == entity.start && e.end == entity.end
+                }) {
+                    Some(existing) => existing.score = existing.score.max(entity.score),
+                    None => text_entities.push(entity),
+                }
+            }
+        }
+
+        Ok(texts
+            .iter()
+            .zip(merged)
+            .map(|(text, mut entities)| {
+                entities.retain(|entity| entity.score >= self.min_score);
+                for ticker in detect_tickers(text) {
+                    if !entities
+                        .iter()
+                        .any(|e| e.start < ticker.end && ticker.start < e.end)
+                    {
+                        entities.push(ticker);
+                    }
+                }
+                entities.sort_by_key(|entity| entity.start);
+                NerResult { entities }
+            })
+            .collect())
+    }
+}
+
+/// Detects crypto tickers written as cashtags (`$BTC`) or as well known uppercase symbols.
+///
+/// # Arguments
+/// * `text` - The text to scan.
+///
+/// # Returns
+/// Ticker entities ordered by position, the span excludes the `$` sign.
+pub fn detect_tickers(text: &str) -> Vec<Entity> {
+    let cashtags = CASHTAG
+        .captures_iter(text)
+        .filter_map(|captures| captures.get(1));
+    let symbols = UPPERCASE_WORD
+        .find_iter(text)
+        .filter(|symbol| KNOWN_TICKERS.contains(&symbol.as_str()));
+
+    let mut tickers: Vec<Entity> = cashtags
+        .chain(symbols)
+        .map(|symbol| Entity {
+            kind: EntityKind::CryptoTicker,
+            text: symbol.as_str().to_string(),
+            start: symbol.start(),
+            end: symbol.end(),
+            score: TICKER_SCORE,
+        })
+        .collect();
+    tickers.sort_by_key(|ticker| ticker.start);
+    tickers.dedup_by_key(|ticker| ticker.start);
+
+    tickers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tags every capitalized word found in the gazetteer.
+    struct GazetteerModel;
+
+    impl Model<String, Vec<Entity>> for GazetteerModel {
+        fn predict(&mut self, inputs: &[String]) -> Result<Vec<Vec<Entity>>> {
+            let gazetteer = [
+                ("Gensler", "B-PER"),
+                ("BlackRock", "B-ORG"),
+                ("Singapore", "B-LOC"),
+                ("BTC", "B-ORG"),
+            ];
+
+            Ok(inputs
+                .iter()
+                .map(|text| {
+                    gazetteer
+                        .iter()
+                        .filter_map(|(word, label)| {
+                            let start = text.find(word)?;
+                            Some(Entity {
+                                kind: EntityKind::from_label(label)?,
+                                text: word.to_string(),
+                                start,
+                                end: start + word.len(),
+                                score: 0.9,
+                            })
+                        })
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ner_classifier_analyze() {
+        let classifier =
+            NerClassifier::new(InferenceConfig::default(), || Ok(GazetteerModel)).unwrap();
+        let texts = vec![
+            "Gensler said BlackRock may list $SOL and ETH funds in Singapore".to_string(),
+            "BTC ETF outflows continue".to_string(),
+        ];
+
+        let results = classifier.analyze(&texts).await.unwrap();
+
+        let kinds: Vec<(EntityKind, &str)> = results[0]
+            .entities
+            .iter()
+            .map(|e| (e.kind, e.text.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (EntityKind::Person, "Gensler"),
+                (EntityKind::Organization, "BlackRock"),
+                (EntityKind::CryptoTicker, "SOL"),
+                (EntityKind::CryptoTicker, "ETH"),
+                (EntityKind::Location, "Singapore"),
+            ]
+        );
+        assert_eq!(
+            &texts[0][results[0].entities[2].start..results[0].entities[2].end],
+            "SOL"
+        );
+        assert_eq!(results[1].entities.len(), 1);
+        assert_eq!(results[1].entities[0].kind, EntityKind::Organization);
+
+        let classifier = classifier.with_min_score(0.95);
+        let results = classifier.analyze(&texts).await.unwrap();
+
+        let kinds: Vec<EntityKind> = results[0].entities.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![EntityKind::CryptoTicker, EntityKind::CryptoTicker]
+        );
+        assert_eq!(results[1].entities[0].kind, EntityKind::CryptoTicker);
+    }
+
+    #[tokio::test]
+    async fn test_ner_classifier_long_text() {
+        let classifier = NerClassifier::new(InferenceConfig::default(), || Ok(GazetteerModel))
+            .unwrap()
+            .with_chunking(ChunkConfig {
+                max_words: 3,
+                overlap_words: 1,
+            });
+        let text = "Yesterday Gensler met bankers, later BlackRock filed in Singapore".to_string();
+
+        let result = classifier
+            .analyze(std::slice::from_ref(&text))
+            .await
+            .unwrap();
+
+        let spans: Vec<&str> = result[0]
+            .entities
+            .iter()
+            .map(|e| &text[e.start..e.end])
+            .collect();
+        assert_eq!(spans, vec!["Gensler", "BlackRock", "Singapore"]);
+    }
+
+    #[test]
+    fn test_detect_tickers() {
+        let tickers = detect_tickers("$PEPE pumps while BTC and $BTC stall, CEO and USD unchanged");
+
+        let symbols: Vec<&str> = tickers.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(symbols, vec!["PEPE", "BTC", "BTC"]);
+        assert_eq!(tickers[0].start, 1);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::chunking::split_words;
+use crate::inference::{InferenceConfig, InferenceWorker, Model};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+const QA_MODEL_NAME: &str = "qa";
+
+/// QaInput is a question asked about a context passage.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct QaInput {
+    pub question: String,
+    pub context: String,
+}
+
+/// Answer is a span of the context answering the question.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Answer {
+    pub text: String,
+    /// Byte offset of the first character of the answer in the context.
+    pub start: usize,
+    /// Byte offset past the last character of the answer in the context.
+    pub end: usize,
+    /// Confidence of the model in range `0.0..=1.0`.
+    pub score: f32,
+}
+
+/// QaConfig configures how long contexts are split for the model.
+#[derive(Debug, Clone)]
+pub struct QaConfig {
+    /// Maximum number of context words passed to the model at once.
+    pub max_context_words: usize,
+    /// Number of words shared by consecutive context windows so answers on a boundary are not lost.
+    pub stride_words: usize,
+    /// Answers scored below are discarded.
+    pub min_score: f32,
+    /// Batching configuration of the inference thread.
+    pub inference: InferenceConfig,
+}
+
+impl Default for QaConfig {
+    fn default() -> Self {
+        Self {
+            max_context_words: 300,
+            stride_words: 64,
+            min_score: 0.1,
+            inference: InferenceConfig::default(),
+        }
+    }
+}
+
+/// QaModel answers questions with spans extracted from a context, the model runs on a dedicated
+/// inference thread.
+pub struct QaModel {
+    worker: InferenceWorker<QaInput, Option<Answer>>,
+    config: QaConfig,
+}
+
+impl QaModel {
+    /// Creates a new question answering model loading it on its inference thread.
+    ///
+    /// # Arguments
+    /// * `config` - Context splitting configuration.
+    /// * `loader` - Creates the model, answer offsets are expected relative to the passed context.
+    ///
+    /// # Returns
+    /// A new model or error if the model could not be loaded.
+    pub fn new<M, F>(config: QaConfig, loader: F) -> Result<Self>
+    where
+        M: Model<QaInput, Option<Answer>>,
+        F: Fn() -> Result<M> + Send + Sync + 'static,
+    {
+        Ok(Self {
+            worker: InferenceWorker::spawn(QA_MODEL_NAME, config.inference.clone(), loader)?,
+            config,
+        })
+    }
+
+    /// Answers the question with a span of the context.
+    ///
+    /// Long contexts are split into overlapping windows and the best scoring answer is returned.
+    ///
+    /// # Arguments
+    /// * `question` - The question.
+    /// * `context` - The passage to search the answer in, usually an article.
+    ///
+    /// # Returns
+    /// The answer, `None` if no answer scored above the minimum, or error otherwise.
+    pub async fn answer(&self, question: &str, context: &str) -> Result<Option<Answer>> {
+        let windows = split_words(
+            context,
+            self.config.max_context_words,
+            self.config.stride_words,
+        );
+        let inputs = windows
+            .iter()
+            .map(|(offset, window)| QaInput {
+                question: question.to_string(),
+                context: context[*offset..*offset + window.len()].to_string(),
+            })
+            .collect();
+
+        let answers = self.worker.predict(inputs).await?;
+
+        Ok(windows
+            .iter()
+            .zip(answers)
+            .filter_map(|((offset, window), answer)| {
+                let answer = answer?;
+                (answer.end <= window.len() && answer.start < answer.end).then(|| Answer {
+                    start: offset + answer.start,
+                    end: offset + answer.end,
+                    ..answer
+                })
+            })
+            .filter(|answer| answer.score >= self.config.min_score)
+            .max_by(|a, b| a.score.total_cmp(&b.score)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers with the word following "by" scored by how early it appears.
+    struct ByModel;
+
+    impl Model<QaInput, Option<Answer>> for ByModel {
+        fn predict(&mut self, inputs: &[QaInput]) -> Result<Vec<Option<Answer>>> {
+            Ok(inputs
+                .iter()
+                .map(|input| {
+                    let start = input.context.find(" by ")? + 4;
+                    let end = input.context[start..]
+                        .find(|c: char| !c.is_alphanumeric())
+                        .map_or(input.context.len(), |i| start + i);
+                    Some(Answer {
+                        text: input.context[start..end].to_string(),
+                        start,
+                        end,
+                        score: 1.0 - start as f32 / (input.context.len() as f32 + 1.0),
+                    })
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_qa_model_answer() {
+        let config = QaConfig {
+            max_context_words: 6,
+            stride_words: 2,
+            min_score: 0.1,
+            inference: InferenceConfig::default(),
+        };
+        let model = QaModel::new(config, || Ok(ByModel)).unwrap();
+        let context = "Markets moved a lot today. The ETF was approved by SEC on Tuesday.";
+
+        let answer = model
+            .answer("Who approved the ETF?", context)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(answer.text, "SEC");
+        assert_eq!(&context[answer.start..answer.end], "SEC");
+        assert!(
+            model
+                .answer("Who approved the ETF?", "No answer here.")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
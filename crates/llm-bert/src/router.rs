@@ -0,0 +1,144 @@
+use crate::BertAnalityze;
+use anyhow::{Result, anyhow, ensure};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// LanguageRouter dispatches texts to models trained for their language.
+///
+/// Texts of a language without a dedicated model, or of unknown language, go to the fallback model,
+/// usually a multilingual one.
+pub struct LanguageRouter<A> {
+    models: HashMap<String, A>,
+    fallback: A,
+}
+
+impl<A> LanguageRouter<A> {
+    /// Creates a new router sending all texts to the fallback model until language models are added.
+    pub fn new(fallback: A) -> Self {
+        Self {
+            models: HashMap::new(),
+            fallback,
+        }
+    }
+
+    /// Adds the model used for texts of the language.
+    ///
+    /// # Arguments
+    /// * `language` - ISO 639-3 language code (e.g. `eng`) as detected by `shared_states::detect_language`.
+    /// * `model` - The model for the language.
+    pub fn with_model(mut self, language: &str, model: A) -> Self {
+        self.models.insert(normalize(language), model);
+        self
+    }
+
+    /// Model used for texts of the language, the fallback model for unknown languages.
+    pub fn model_for(&self, language: Option<&str>) -> &A {
+        language
+            .and_then(|language| self.models.get(&normalize(language)))
+            .unwrap_or(&self.fallback)
+    }
+
+    /// Analyzes the texts with the models of their languages.
+    ///
+    /// # Arguments
+    /// * `texts` - The texts to analyze.
+    /// * `languages` - Language hint of every text, `None` or empty if not known.
+    ///
+    /// # Returns
+    /// One result per text in the same order, or error otherwise.
+    pub async fn analyze_with_languages<'a, T>(
+        &self,
+        texts: &[String],
+        languages: &[Option<&str>],
+    ) -> Result<Vec<T>>
+    where
+        A: BertAnalityze<'a, T>,
+        T: Debug + Serialize + Deserialize<'a>,
+    {
+        ensure!(
+            texts.len() == languages.len(),
+            "Got {} language hints for {} texts",
+            languages.len(),
+            texts.len()
+        );
+
+        let mut groups: Vec<(Option<String>, Vec<usize>)> = Vec::new();
+        for (i, language) in languages.iter().enumerate() {
+            let key = language
+                .map(normalize)
+                .filter(|language| self.models.contains_key(language));
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, indices)) => indices.push(i),
+                None => groups.push((key, vec![i])),
+            }
+        }
+
+        let mut results: Vec<Option<T>> = texts.iter().map(|_| None).collect();
+        for (key, indices) in groups {
+            let model = self.model_for(key.as_deref());
+            let group: Vec<String> = indices.iter().map(|&i| texts[i].clone()).collect();
+            for (i, result) in indices.into_iter().zip(model.analyze(&group).await?) {
+                results[i] = Some(result);
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.ok_or_else(|| anyhow!("Model returned too few results")))
+            .collect()
+    }
+}
+
+fn normalize(language: &str) -> String {
+    language.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Tags every text with the model name.
+    struct TaggingModel(&'static str);
+
+    impl<'a> BertAnalityze<'a, String> for TaggingModel {
+        async fn analyze(&self, texts: &[String]) -> Result<Vec<String>> {
+            Ok(texts
+                .iter()
+                .map(|text| format!("{}:{text}", self.0))
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_language_router_analyze() {
+        let router = LanguageRouter::new(TaggingModel("multilingual"))
+            .with_model("eng", TaggingModel("english"))
+            .with_model("RUS", TaggingModel("russian"));
+        let texts: Vec<String> = ["btc up", "биткоин", "比特币", "eth down"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let results = router
+            .analyze_with_languages(&texts, &[Some("eng"), Some("rus"), Some("cmn"), None])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            results,
+            vec![
+                "english:btc up",
+                "russian:биткоин",
+                "multilingual:比特币",
+                "multilingual:eth down"
+            ]
+        );
+        assert!(
+            router
+                .analyze_with_languages(&texts, &[None])
+                .await
+                .is_err()
+        );
+    }
+}
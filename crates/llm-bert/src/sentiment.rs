@@ -1 +1,478 @@
+use crate::BertAnalityze;
+use crate::chunking::{ChunkConfig, chunk_texts};
+use crate::config::{BertConfig, BertConfigError, ModelSettings};
+use crate::inference::{InferenceWorker, Model};
+use crate::source::ModelSource;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use shared_states::{SentimentLabel, SentimentScore};
+use std::collections::HashMap;
+use std::str::FromStr;
 
+const SENTIMENT_MODEL_NAME: &str = "sentiment";
+const GENERAL_MODEL_NAME: &str = "distilbert-base-uncased-finetuned-sst-2-english";
+const FINANCIAL_MODEL_NAME: &str = "ProsusAI/finbert";
+
+/// LabelScore is the probability the model assigned to one of its labels.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LabelScore {
+    /// Label as named by the model, e.g. `POSITIVE` or `LABEL_1`.
+    pub label: String,
+    pub score: f32,
+}
+
+/// SentimentConfig configures how raw model scores are turned into a sentiment.
+#[derive(Debug, Clone)]
+pub struct SentimentConfig {
+    /// Labels scored below are reported as neutral.
+    pub min_confidence: f32,
+    /// Texts with absolute polarity below are reported as neutral, models without a neutral label
+    /// otherwise never report one.
+    pub neutral_band: f32,
+    /// Maps lowercase model label names to sentiment labels, unmapped labels are ignored.
+    pub labels: HashMap<String, SentimentLabel>,
+    /// Long texts are split into chunks and their scores averaged weighted by chunk length.
+    pub chunking: ChunkConfig,
+}
+
+impl Default for SentimentConfig {
+    fn default() -> Self {
+        let labels = [
+            ("positive", SentimentLabel::Positive),
+            ("pos", SentimentLabel::Positive),
+            ("negative", SentimentLabel::Negative),
+            ("neg", SentimentLabel::Negative),
+            ("neutral", SentimentLabel::Neutral),
+            ("label_0", SentimentLabel::Negative),
+            ("label_1", SentimentLabel::Positive),
+        ]
+        .into_iter()
+        .map(|(name, label)| (name.to_string(), label))
+        .collect();
+
+        Self {
+            min_confidence: 0.5,
+            neutral_band: 0.2,
+            labels,
+            chunking: ChunkConfig::default(),
+        }
+    }
+}
+
+impl SentimentConfig {
+    /// Configuration for FinBERT-style models scoring market text as positive, negative or neutral.
+    ///
+    /// Unnamed labels follow the FinBERT order, the explicit neutral label makes a narrow
+    /// neutral band sufficient.
+    pub fn financial() -> Self {
+        let labels = [
+            ("positive", SentimentLabel::Positive),
+            ("negative", SentimentLabel::Negative),
+            ("neutral", SentimentLabel::Neutral),
+            ("label_0", SentimentLabel::Positive),
+            ("label_1", SentimentLabel::Negative),
+            ("label_2", SentimentLabel::Neutral),
+        ]
+        .into_iter()
+        .map(|(name, label)| (name.to_string(), label))
+        .collect();
+
+        Self {
+            min_confidence: 0.4,
+            neutral_band: 0.1,
+            labels,
+            chunking: ChunkConfig::default(),
+        }
+    }
+
+    /// Adds or replaces the mapping of a model label name.
+    pub fn with_label(mut self, name: &str, label: SentimentLabel) -> Self {
+        self.labels.insert(name.to_lowercase(), label);
+        self
+    }
+
+    /// Turns the label scores of a text into its sentiment.
+    ///
+    /// # Arguments
+    /// * `scores` - Probabilities of the model labels.
+    ///
+    /// # Returns
+    /// The sentiment, neutral if the best label is not confident enough or the polarity falls
+    /// in the neutral band.
+    pub fn apply(&self, scores: &[LabelScore]) -> SentimentScore {
+        let mut probabilities: HashMap<SentimentLabel, f32> = HashMap::new();
+        for score in scores {
+            if let Some(label) = self.labels.get(&score.label.to_lowercase()) {
+                *probabilities.entry(*label).or_default() += score.score;
+            }
+        }
+        let probability = |label| probabilities.get(&label).copied().unwrap_or_default();
+        let polarity = (probability(SentimentLabel::Positive)
+            - probability(SentimentLabel::Negative))
+        .clamp(-1.0, 1.0);
+
+        let best = [
+            SentimentLabel::Positive,
+            SentimentLabel::Negative,
+            SentimentLabel::Neutral,
+        ]
+        .into_iter()
+        .map(|label| (label, probability(label)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        match best {
+            Some((label, score))
+                if score >= self.min_confidence
+                    && (label == SentimentLabel::Neutral
+                        || polarity.abs() >= self.neutral_band) =>
+            {
+                SentimentScore {
+                    label,
+                    score,
+                    polarity,
+                }
+            }
+            _ => SentimentScore {
+                label: SentimentLabel::Neutral,
+                score: probability(SentimentLabel::Neutral).max(1.0 - polarity.abs()),
+                polarity,
+            },
+        }
+    }
+}
+
+/// SentimentDomain selects the sentiment model and its configuration for the kind of analyzed text.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SentimentDomain {
+    /// General purpose English model trained on movie reviews (SST-2).
+    #[default]
+    General,
+    /// Model trained on financial news, fitting crypto and market content.
+    Financial,
+}
+
+impl SentimentDomain {
+    /// Default model of the domain.
+    pub fn model(&self) -> ModelSource {
+        let name = match self {
+            Self::General => GENERAL_MODEL_NAME,
+            Self::Financial => FINANCIAL_MODEL_NAME,
+        };
+
+        ModelSource::Named {
+            name: name.to_string(),
+            revision: None,
+        }
+    }
+
+    /// Post-processing configuration matching the labels of the domain model.
+    pub fn config(&self) -> SentimentConfig {
+        match self {
+            Self::General => SentimentConfig::default(),
+            Self::Financial => SentimentConfig::financial(),
+        }
+    }
+}
+
+impl FromStr for SentimentDomain {
+    type Err = BertConfigError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_lowercase().as_str() {
+            "general" | "sst2" | "sst-2" => Ok(Self::General),
+            "financial" | "finance" | "finbert" => Ok(Self::Financial),
+            _ => Err(BertConfigError::InvalidValue {
+                name: "sentiment domain".to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+}
+
+/// SentimentClassifier scores texts as positive, negative or neutral.
+///
+/// The sequence classification model runs on a dedicated inference thread, its label scores are
+/// post-processed with the sentiment configuration.
+pub struct SentimentClassifier {
+    worker: InferenceWorker<String, Vec<LabelScore>>,
+    config: SentimentConfig,
+}
+
+impl SentimentClassifier {
+    /// Creates a new classifier loading the sequence classification model on its inference thread.
+    ///
+    /// Files of local and remote models are made available first, see [`ModelSource::fetch`].
+    /// Label scores are post-processed with the configuration of the sentiment domain.
+    ///
+    /// # Arguments
+    /// * `config` - The model, the hardware it runs on and the batching of the inference thread.
+    /// * `client` - HTTP client used to download remote models.
+    /// * `loader` - Creates the model from the settings, probabilities of all labels are expected
+    ///   per text.
+    ///
+    /// # Returns
+    /// A new classifier or error if the model files are not available or the model could not be
+    /// loaded.
+    pub async fn spawn<M, F>(
+        config: &BertConfig,
+        client: &reqwest::Client,
+        loader: F,
+    ) -> Result<Self>
+    where
+        M: Model<String, Vec<LabelScore>>,
+        F: Fn(&ModelSettings) -> Result<M> + Send + Sync + 'static,
+    {
+        let settings = ModelSettings {
+            device: config.device,
+            model: config.model.clone(),
+            model_dir: config.model.fetch(client, &config.cache_dir).await?,
+            num_threads: config.num_threads,
+        };
+
+        Ok(Self {
+            worker: InferenceWorker::spawn(
+                SENTIMENT_MODEL_NAME,
+                config.inference.clone(),
+                move || loader(&settings),
+            )?,
+            config: config.sentiment_domain.config(),
+        })
+    }
+
+    /// Replaces the post-processing configuration, e.g. for the labels of a fine-tuned model.
+    pub fn with_sentiment_config(mut self, config: SentimentConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+impl<'a> BertAnalityze<'a, SentimentScore> for SentimentClassifier {
+    async fn analyze(&self, texts: &[String]) -> Result<Vec<SentimentScore>> {
+        let chunks = chunk_texts(texts, &self.config.chunking);
+        let inputs = chunks.iter().map(|c| c.content.to_string()).collect();
+        let predictions = self.worker.predict(inputs).await?;
+
+        let mut weighted: Vec<Vec<(LabelScore, f32)>> = vec![Vec::new(); texts.len()];
+        for (chunk, scores) in chunks.iter().zip(predictions) {
+            let weight = chunk.words.max(1) as f32;
+            weighted[chunk.text].extend(scores.into_iter().map(|score| (score, weight)));
+        }
+
+        Ok(weighted
+            .iter()
+            .map(|scores| self.config.apply(&weighted_average(scores)))
+            .collect())
+    }
+}
+
+/// Averages the scores of every label weighted by the length of the chunk they were predicted for.
+fn weighted_average(scores: &[(LabelScore, f32)]) -> Vec<LabelScore> {
+    let mut labels: Vec<(String, f32, f32)> = Vec::new();
+    for (score, weight) in scores {
+        match labels
+            .iter_mut()
+            .find(|(label, _, _)| *label == score.label)
+        {
+            Some((_, sum, weights)) => {
+                *sum += score.score * weight;
+                *weights += weight;
+            }
+            None => labels.push((score.label.clone(), score.score * weight, *weight)),
+        }
+    }
+
+    labels
+        .into_iter()
+        .map(|(label, sum, weights)| LabelScore {
+            label,
+            score: sum / weights,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Device;
+
+    fn scores(scores: &[(&str, f32)]) -> Vec<LabelScore> {
+        scores
+            .iter()
+            .map(|(label, score)| LabelScore {
+                label: label.to_string(),
+                score: *score,
+            })
+            .collect()
+    }
+
+    /// Scores texts mentioning `rally` as positive and `crash` as negative, SST-2 style.
+    struct KeywordModel;
+
+    impl Model<String, Vec<LabelScore>> for KeywordModel {
+        fn predict(&mut self, inputs: &[String]) -> Result<Vec<Vec<LabelScore>>> {
+            Ok(inputs
+                .iter()
+                .map(|text| {
+                    let positive = match (text.contains("rally"), text.contains("crash")) {
+                        (true, false) => 0.95,
+                        (false, true) => 0.05,
+                        _ => 0.55,
+                    };
+                    scores(&[("LABEL_1", positive), ("LABEL_0", 1.0 - positive)])
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_sentiment_config_apply() {
+        let config = SentimentConfig::default().with_label("Bullish", SentimentLabel::Positive);
+
+        let sentiment = config.apply(&scores(&[("bullish", 0.8), ("NEGATIVE", 0.2)]));
+        assert_eq!(sentiment.label, SentimentLabel::Positive);
+        assert!((sentiment.polarity - 0.6).abs() < 1e-6);
+
+        let sentiment = config.apply(&scores(&[("POSITIVE", 0.55), ("NEGATIVE", 0.45)]));
+        assert_eq!(sentiment.label, SentimentLabel::Neutral);
+
+        let sentiment = config.apply(&scores(&[
+            ("positive", 0.4),
+            ("negative", 0.1),
+            ("neutral", 0.5),
+        ]));
+        assert_eq!(sentiment.label, SentimentLabel::Neutral);
+        assert_eq!(sentiment.score, 0.5);
+
+        let sentiment = config.apply(&scores(&[("joy", 1.0)]));
+        assert_eq!(sentiment.label, SentimentLabel::Neutral);
+        assert_eq!(sentiment.polarity, 0.0);
+    }
+
+    #[test]
+    fn test_sentiment_domain_financial() {
+        let domain: SentimentDomain = "FinBERT".parse().unwrap();
+        let config = domain.config();
+
+        assert_eq!(domain, SentimentDomain::Financial);
+        assert_eq!(
+            domain.model(),
+            ModelSource::Named {
+                name: "ProsusAI/finbert".to_string(),
+                revision: None
+            }
+        );
+        let sentiment = config.apply(&scores(&[
+            ("LABEL_0", 0.1),
+            ("LABEL_1", 0.5),
+            ("LABEL_2", 0.4),
+        ]));
+        assert_eq!(sentiment.label, SentimentLabel::Negative);
+        let sentiment = config.apply(&scores(&[
+            ("positive", 0.2),
+            ("negative", 0.1),
+            ("neutral", 0.7),
+        ]));
+        assert_eq!(sentiment.label, SentimentLabel::Neutral);
+        assert!("movies".parse::<SentimentDomain>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sentiment_classifier_long_text() {
+        let config = SentimentConfig {
+            chunking: ChunkConfig {
+                max_words: 4,
+                overlap_words: 0,
+            },
+            ..Default::default()
+        };
+        let classifier =
+            SentimentClassifier::spawn(&BertConfig::default(), &reqwest::Client::new(), |_| {
+                Ok(KeywordModel)
+            })
+            .await
+            .unwrap()
+            .with_sentiment_config(config);
+        let text =
+            "Bitcoin rally extends today. Ether rally follows it. Altcoins crash".to_string();
+
+        let sentiment = classifier.analyze(&[text]).await.unwrap()[0];
+
+        assert_eq!(sentiment.label, SentimentLabel::Positive);
+        assert!((sentiment.polarity - 0.54).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_sentiment_classifier_analyze() {
+        let classifier =
+            SentimentClassifier::spawn(&BertConfig::default(), &reqwest::Client::new(), |_| {
+                Ok(KeywordModel)
+            })
+            .await
+            .unwrap();
+        let texts = vec![
+            "Bitcoin rally extends".to_string(),
+            "Altcoins crash overnight".to_string(),
+            "Ether trades sideways".to_string(),
+        ];
+
+        let labels: Vec<SentimentLabel> = classifier
+            .analyze(&texts)
+            .await
+            .unwrap()
+            .iter()
+            .map(|sentiment| sentiment.label)
+            .collect();
+
+        assert_eq!(
+            labels,
+            vec![
+                SentimentLabel::Positive,
+                SentimentLabel::Negative,
+                SentimentLabel::Neutral
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sentiment_classifier_spawn_settings() {
+        let dir = std::env::temp_dir().join(format!("llm-bert-sentiment-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = BertConfig {
+            device: Device::Cuda(1),
+            sentiment_domain: SentimentDomain::Financial,
+            model: ModelSource::Local(dir.clone()),
+            num_threads: 4,
+            ..Default::default()
+        };
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        let classifier =
+            SentimentClassifier::spawn(&config, &reqwest::Client::new(), move |settings| {
+                sender.send(settings.clone()).unwrap();
+                Ok(KeywordModel)
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(
+            receiver.recv().unwrap(),
+            ModelSettings {
+                device: Device::Cuda(1),
+                model: ModelSource::Local(dir.clone()),
+                model_dir: Some(dir.clone()),
+                num_threads: 4,
+            }
+        );
+        assert_eq!(classifier.config.min_confidence, 0.4);
+
+        let config = BertConfig {
+            model: ModelSource::Local(dir.join("missing")),
+            ..Default::default()
+        };
+        let spawned =
+            SentimentClassifier::spawn(&config, &reqwest::Client::new(), |_| Ok(KeywordModel))
+                .await;
+        assert!(spawned.is_err());
+    }
+}
@@ -0,0 +1,265 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// ModelSourceError describes failures of making model files available locally.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ModelSourceError {
+    #[error("Model directory {0} not found")]
+    NotFound(PathBuf),
+
+    #[error("Failed to download {url}: {reason}")]
+    Download { url: String, reason: String },
+
+    #[error("Checksum of {file} is {got}, expected {expected}")]
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        got: String,
+    },
+}
+
+/// RemoteFile is a model file downloaded from a remote location.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct RemoteFile {
+    /// File name relative to the base URL and the cache directory.
+    pub name: String,
+    /// Hex encoded SHA-256 of the file content.
+    pub sha256: String,
+}
+
+/// ModelSource tells where model weights, configuration and vocabulary come from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum ModelSource {
+    /// Model resolved by the model runtime itself, usually from the model hub.
+    Named {
+        name: String,
+        revision: Option<String>,
+    },
+    /// Directory with the model files, for example a fine-tuned model.
+    Local(PathBuf),
+    /// Model files downloaded from the base URL to the cache directory.
+    Remote {
+        base_url: String,
+        files: Vec<RemoteFile>,
+    },
+}
+
+impl ModelSource {
+    /// Makes the model files available locally.
+    ///
+    /// Remote files are downloaded to a subdirectory of the cache directory and verified against
+    /// their checksums, files already cached with the right checksum are not downloaded again.
+    ///
+    /// # Arguments
+    /// * `client` - HTTP client used to download remote files.
+    /// * `cache_dir` - Directory remote models are stored in.
+    ///
+    /// # Returns
+    /// The directory with the model files, `None` for named models, or error otherwise.
+    pub async fn fetch(
+        &self,
+        client: &reqwest::Client,
+        cache_dir: &Path,
+    ) -> Result<Option<PathBuf>> {
+        match self {
+            Self::Named { .. } => Ok(None),
+            Self::Local(path) => {
+                if !tokio::fs::metadata(path).await.is_ok_and(|m| m.is_dir()) {
+                    return Err(ModelSourceError::NotFound(path.clone()).into());
+                }
+                Ok(Some(path.clone()))
+            }
+            Self::Remote { base_url, files } => {
+                let dir = cache_dir.join(&sha256_hex(base_url.as_bytes())[..16]);
+                tokio::fs::create_dir_all(&dir).await?;
+                for file in files {
+                    download(client, base_url, file, &dir).await?;
+                }
+                Ok(Some(dir))
+            }
+        }
+    }
+}
+
+/// Downloads the file to the directory unless it is cached with the right checksum.
+///
+/// The body is streamed to a `.partial` file and hashed while it is written, so large model files
+/// are never held in memory. The file replaces the cached one only once its checksum matches.
+async fn download(
+    client: &reqwest::Client,
+    base_url: &str,
+    file: &RemoteFile,
+    dir: &Path,
+) -> Result<()> {
+    let path = dir.join(&file.name);
+    if let Ok(cached) = sha256_file(&path).await
+        && cached.eq_ignore_ascii_case(&file.sha256)
+    {
+        return Ok(());
+    }
+
+    let url = format!("{}/{}", base_url.trim_end_matches('/'), file.name);
+    let download_error = |reason: String| ModelSourceError::Download {
+        url: url.clone(),
+        reason,
+    };
+    let mut response = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| download_error(e.to_string()))?;
+
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let partial = path.with_extension("partial");
+    let mut writer = tokio::fs::File::create(&partial).await?;
+    let mut hasher = Sha256::new();
+    loop {
+        let chunk = match response.chunk().await {
+            Ok(Some(chunk)) => chunk,
+            Ok(None) => break,
+            Err(e) => {
+                drop(writer);
+                let _ = tokio::fs::remove_file(&partial).await;
+                return Err(download_error(e.to_string()).into());
+            }
+        };
+        hasher.update(&chunk);
+        writer.write_all(&chunk).await?;
+    }
+    writer.flush().await?;
+    drop(writer);
+
+    let got = hex::encode(hasher.finalize());
+    if !got.eq_ignore_ascii_case(&file.sha256) {
+        let _ = tokio::fs::remove_file(&partial).await;
+        return Err(ModelSourceError::ChecksumMismatch {
+            file: file.name.clone(),
+            expected: file.sha256.clone(),
+            got,
+        }
+        .into());
+    }
+    tokio::fs::rename(&partial, &path).await?;
+
+    Ok(())
+}
+
+/// Hex encoded SHA-256 of the file, read in chunks.
+async fn sha256_file(path: &Path) -> Result<String> {
+    let mut reader = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Serves the body to every request on a local port.
+    async fn serve(body: &'static [u8]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await;
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    body.len()
+                );
+                let _ = stream.write_all(head.as_bytes()).await;
+                let _ = stream.write_all(body).await;
+            }
+        });
+
+        format!("http://{address}/models/finbert")
+    }
+
+    fn cache_dir(test: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("llm-bert-{test}-{}", std::process::id()))
+    }
+
+    #[tokio::test]
+    async fn test_model_source_fetch_remote() {
+        let base_url = serve(b"{\"hidden_size\": 768}").await;
+        let cache = cache_dir("remote");
+        let client = reqwest::Client::new();
+        let file = RemoteFile {
+            name: "config.json".to_string(),
+            sha256: sha256_hex(b"{\"hidden_size\": 768}"),
+        };
+
+        let source = ModelSource::Remote {
+            base_url: base_url.clone(),
+            files: vec![file],
+        };
+        let dir = source.fetch(&client, &cache).await.unwrap().unwrap();
+        assert_eq!(
+            std::fs::read(dir.join("config.json")).unwrap(),
+            b"{\"hidden_size\": 768}"
+        );
+
+        let source = ModelSource::Remote {
+            base_url,
+            files: vec![RemoteFile {
+                name: "vocab.txt".to_string(),
+                sha256: "0".repeat(64),
+            }],
+        };
+        let err = source.fetch(&client, &cache).await.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ModelSourceError>(),
+            Some(ModelSourceError::ChecksumMismatch { .. })
+        ));
+        assert!(!dir.join("vocab.partial").exists());
+        assert!(!dir.join("vocab.txt").exists());
+
+        std::fs::remove_dir_all(cache).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_model_source_fetch_local_and_named() {
+        let client = reqwest::Client::new();
+        let cache = cache_dir("local");
+        let named = ModelSource::Named {
+            name: "ProsusAI/finbert".to_string(),
+            revision: None,
+        };
+
+        assert_eq!(named.fetch(&client, &cache).await.unwrap(), None);
+        let local = ModelSource::Local(std::env::temp_dir());
+        assert_eq!(
+            local.fetch(&client, &cache).await.unwrap(),
+            Some(std::env::temp_dir())
+        );
+        let missing = ModelSource::Local(cache.join("missing"));
+        assert_eq!(
+            missing
+                .fetch(&client, &cache)
+                .await
+                .unwrap_err()
+                .downcast_ref::<ModelSourceError>(),
+            Some(&ModelSourceError::NotFound(cache.join("missing")))
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use crate::BertAnalityze;
+use crate::chunking::{ChunkConfig, chunk_texts};
+use crate::inference::{InferenceConfig, InferenceWorker, Model};
+use crate::sentiment::LabelScore;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+const TOPIC_MODEL_NAME: &str = "topics";
+
+const DEFAULT_TAXONOMY: [&str; 8] = [
+    "crypto",
+    "equities",
+    "macro",
+    "tech",
+    "regulation",
+    "commodities",
+    "forex",
+    "security",
+];
+
+/// TopicScore is the probability that a text is about the topic.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopicScore {
+    pub topic: String,
+    /// Probability in range `0.0..=1.0`, independent of the other topics.
+    pub score: f32,
+}
+
+/// Topics holds the topics of a text scored above the minimum, best first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Topics {
+    pub topics: Vec<TopicScore>,
+}
+
+impl Topics {
+    /// Names of the topics, best first.
+    pub fn names(&self) -> Vec<&str> {
+        self.topics.iter().map(|t| t.topic.as_str()).collect()
+    }
+}
+
+/// TopicConfig configures the taxonomy and the selection of topics.
+#[derive(Debug, Clone)]
+pub struct TopicConfig {
+    /// Lowercase names of the topics, model labels outside of the taxonomy are ignored.
+    pub taxonomy: Vec<String>,
+    /// Topics scored below are dropped.
+    pub min_score: f32,
+    /// Long texts are split into chunks, a topic scores as high as its best scoring chunk.
+    pub chunking: ChunkConfig,
+}
+
+impl Default for TopicConfig {
+    fn default() -> Self {
+        Self {
+            taxonomy: DEFAULT_TAXONOMY.iter().map(|t| t.to_string()).collect(),
+            min_score: 0.5,
+            chunking: ChunkConfig::default(),
+        }
+    }
+}
+
+/// TopicClassifier assigns taxonomy topics to texts, a text may have any number of topics.
+///
+/// The multi-label classification model runs on a dedicated inference thread and is expected to
+/// score every topic independently, e.g. with a sigmoid output or zero-shot classification over
+/// the taxonomy.
+pub struct TopicClassifier {
+    worker: InferenceWorker<String, Vec<LabelScore>>,
+    config: TopicConfig,
+}
+
+impl TopicClassifier {
+    /// Creates a new classifier loading the model on its inference thread.
+    ///
+    /// # Arguments
+    /// * `config` - Taxonomy and topic selection configuration.
+    /// * `inference` - Batching configuration of the inference thread.
+    /// * `loader` - Creates the model, scores of the taxonomy topics are expected per text.
+    ///
+    /// # Returns
+    /// A new classifier or error if the model could not be loaded.
+    pub fn new<M, F>(config: TopicConfig, inference: InferenceConfig, loader: F) -> Result<Self>
+    where
+        M: Model<String, Vec<LabelScore>>,
+        F: Fn() -> Result<M> + Send + Sync + 'static,
+    {
+        Ok(Self {
+            worker: InferenceWorker::spawn(TOPIC_MODEL_NAME, inference, loader)?,
+            config,
+        })
+    }
+
+    /// Topics of the taxonomy.
+    pub fn taxonomy(&self) -> &[String] {
+        &self.config.taxonomy
+    }
+}
+
+impl<'a> BertAnalityze<'a, Topics> for TopicClassifier {
+    async fn analyze(&self, texts: &[String]) -> Result<Vec<Topics>> {
+        let chunks = chunk_texts(texts, &self.config.chunking);
+        let inputs = chunks.iter().map(|c| c.content.to_string()).collect();
+        let predictions = self.worker.predict(inputs).await?;
+
+        let mut results = vec![Topics::default(); texts.len()];
+        for (chunk, scores) in chunks.iter().zip(predictions) {
+            let topics = &mut results[chunk.text].topics;
+            for score in scores {
+                let topic = score.label.to_lowercase();
+                if !self.config.taxonomy.contains(&topic) {
+                    continue;
+                }
+                match topics.iter_mut().find(|t| t.topic == topic) {
+                    Some(existing) => existing.score = existing.score.max(score.score),
+                    None => topics.push(TopicScore {
+                        topic,
+                        score: score.score,
+                    }),
+                }
+            }
+        }
+
+        for result in &mut results {
+            result.topics.retain(|t| t.score >= self.config.min_score);
+            result.topics.sort_by(|a, b| b.score.total_cmp(&a.score));
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scores topics by keywords found in the text.
+    struct KeywordModel;
+
+    impl Model<String, Vec<LabelScore>> for KeywordModel {
+        fn predict(&mut self, inputs: &[String]) -> Result<Vec<Vec<LabelScore>>> {
+            let keywords = [
+                ("Crypto", "bitcoin", 0.9),
+                ("regulation", "SEC", 0.7),
+                ("equities", "Nasdaq", 0.6),
+                ("sports", "football", 0.99),
+            ];
+
+            Ok(inputs
+                .iter()
+                .map(|text| {
+                    keywords
+                        .iter()
+                        .map(|(label, keyword, score)| LabelScore {
+                            label: label.to_string(),
+                            score: if text.contains(keyword) { *score } else { 0.1 },
+                        })
+                        .collect()
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_topic_classifier_analyze() {
+        let config = TopicConfig {
+            chunking: ChunkConfig {
+                max_words: 4,
+                overlap_words: 0,
+            },
+            ..Default::default()
+        };
+        let classifier =
+            TopicClassifier::new(config, InferenceConfig::default(), || Ok(KeywordModel)).unwrap();
+        let texts = vec![
+            "SEC delays decision on spot bitcoin funds".to_string(),
+            "Football transfer window opens".to_string(),
+        ];
+
+        let results = classifier.analyze(&texts).await.unwrap();
+
+        assert_eq!(results[0].names(), vec!["crypto", "regulation"]);
+        assert_eq!(results[0].topics[0].score, 0.9);
+        assert!(results[1].topics.is_empty());
+        assert!(classifier.taxonomy().contains(&"macro".to_string()));
+    }
+}
@@ -1,9 +1,11 @@
-use async_nats::{Client, ConnectOptions, Message};
-use serde::{Deserialize, Serialize};
-use std::{env, time::Duration};
+use async_nats::jetstream::{self, kv};
+use async_nats::{Client, ConnectOptions, HeaderMap, Message, RequestErrorKind};
+use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerError};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::{env, future::Future, sync::Arc, time::Duration};
 use thiserror::Error;
 use tokio::time::timeout;
-use tracing::{error, info};
+use tracing::info;
 
 #[derive(Error, Debug)]
 pub enum NatsError {
@@ -21,10 +23,64 @@ pub enum NatsError {
 
     #[error("Subject error: {0}")]
     Subject(String),
+
+    #[error("Circuit breaker of {0} is open")]
+    CircuitOpen(String),
+
+    #[error("Key-value store error: {0}")]
+    KeyValue(String),
+
+    #[error("JetStream error: {0}")]
+    JetStream(String),
 }
 
 pub type NatsResult<T> = Result<T, NatsError>;
 
+/// Header carrying the ID of the request a message was published for, so logs of the services
+/// handling the message can be correlated with it.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Run the future with the request ID, messages published and requests made by it carry the ID in
+/// the [`REQUEST_ID_HEADER`] header
+///
+/// # Arguments
+/// * `request_id` - The ID of the request
+/// * `future` - The future handling the request
+///
+/// # Returns
+/// * `F::Output` - The output of the future
+pub async fn with_request_id<F: Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+/// The request ID of the running [`with_request_id`] future, if any
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// The request ID in the [`REQUEST_ID_HEADER`] header of the message, if any
+pub fn message_request_id(message: &Message) -> Option<&str> {
+    message
+        .headers
+        .as_ref()?
+        .get(REQUEST_ID_HEADER)
+        .map(|value| value.as_str())
+        .filter(|value| !value.is_empty())
+}
+
+/// Headers of a published message, the request ID of the running future if any
+fn headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Some(request_id) = current_request_id() {
+        headers.insert(REQUEST_ID_HEADER, request_id.as_str());
+    }
+    headers
+}
+
 /// NATS queue configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NatsConfig {
@@ -105,6 +161,7 @@ impl Default for NatsConfig {
 pub struct NatsQueue {
     client: Client,
     config: NatsConfig,
+    breaker: Arc<CircuitBreaker>,
 }
 
 impl NatsQueue {
@@ -140,7 +197,34 @@ impl NatsQueue {
 
         info!("Successfully connected to NATS server");
 
-        Ok(Self { client, config })
+        Ok(Self {
+            client,
+            config,
+            breaker: Arc::new(CircuitBreaker::new("nats", CircuitBreakerConfig::default())),
+        })
+    }
+
+    /// Circuit breaker guarding publishing and requests
+    ///
+    /// # Returns
+    /// * `Arc<CircuitBreaker>` - The circuit breaker, calls fail right away while it is open
+    pub fn circuit_breaker(&self) -> Arc<CircuitBreaker> {
+        self.breaker.clone()
+    }
+
+    async fn guarded<T, F>(&self, operation: F) -> NatsResult<T>
+    where
+        F: Future<Output = NatsResult<T>>,
+    {
+        self.breaker
+            .call_filtered(operation, |e| {
+                matches!(e, NatsError::Connection(_) | NatsError::Timeout { .. })
+            })
+            .await
+            .map_err(|e| match e {
+                CircuitBreakerError::Open(name) => NatsError::CircuitOpen(name),
+                CircuitBreakerError::Inner(e) => e,
+            })
     }
 
     /// Publish a message to a subject
@@ -157,12 +241,13 @@ impl NatsQueue {
     {
         let data = serde_json::to_vec(payload)?;
 
-        self.client
-            .publish(subject.to_string(), data.into())
-            .await
-            .map_err(|e| NatsError::Connection(e.to_string()))?;
-
-        Ok(())
+        self.guarded(async {
+            self.client
+                .publish_with_headers(subject.to_string(), headers(), data.into())
+                .await
+                .map_err(|e| NatsError::Connection(e.to_string()))
+        })
+        .await
     }
 
     /// Publish a message with a reply subject
@@ -185,12 +270,60 @@ impl NatsQueue {
     {
         let data = serde_json::to_vec(payload)?;
 
-        self.client
-            .publish_with_reply(subject.to_string(), reply.to_string(), data.into())
-            .await
-            .map_err(|e| NatsError::Connection(e.to_string()))?;
+        self.guarded(async {
+            self.client
+                .publish_with_reply_and_headers(
+                    subject.to_string(),
+                    reply.to_string(),
+                    headers(),
+                    data.into(),
+                )
+                .await
+                .map_err(|e| NatsError::Connection(e.to_string()))
+        })
+        .await
+    }
 
-        Ok(())
+    /// Publish a message to a subject captured by a JetStream stream and wait for the server to
+    /// acknowledge it was stored
+    ///
+    /// # Arguments
+    /// * `subject` - The subject to publish the message to
+    /// * `payload` - The payload to publish
+    ///
+    /// # Returns
+    /// * `NatsResult<u64>` - Sequence of the message in the stream, or an error if the server did
+    ///   not acknowledge it within the request timeout
+    pub async fn publish_acked<T>(&self, subject: &str, payload: &T) -> NatsResult<u64>
+    where
+        T: Serialize,
+    {
+        let data = serde_json::to_vec(payload)?;
+        let mut context = jetstream::new(self.client.clone());
+        context.set_timeout(Duration::from_millis(self.config.request_timeout_ms));
+
+        self.guarded(async {
+            let ack = context
+                .publish_with_headers(subject.to_string(), headers(), data.into())
+                .await
+                .map_err(|e| self.publish_error(e))?
+                .await
+                .map_err(|e| self.publish_error(e))?;
+            Ok(ack.sequence)
+        })
+        .await
+    }
+
+    fn publish_error(&self, e: jetstream::context::PublishError) -> NatsError {
+        match e.kind() {
+            jetstream::context::PublishErrorKind::TimedOut => NatsError::Timeout {
+                timeout_ms: self.config.request_timeout_ms,
+            },
+            jetstream::context::PublishErrorKind::BrokenPipe => {
+                NatsError::Connection(e.to_string())
+            }
+            _ => NatsError::JetStream(e.to_string()),
+        }
     }
 
     /// Subscribe to a subject
@@ -255,15 +388,24 @@ impl NatsQueue {
     {
         let data = serde_json::to_vec(payload)?;
 
-        let response = timeout(
-            Duration::from_millis(self.config.request_timeout_ms),
-            self.client.request(subject.to_string(), data.into()),
-        )
-        .await
-        .map_err(|_| NatsError::Timeout {
-            timeout_ms: self.config.request_timeout_ms,
-        })?
-        .map_err(|e| NatsError::Connection(e.to_string()))?;
+        let response = self
+            .guarded(async {
+                timeout(
+                    Duration::from_millis(self.config.request_timeout_ms),
+                    self.client
+                        .request_with_headers(subject.to_string(), headers(), data.into()),
+                )
+                .await
+                .map_err(|_| NatsError::Timeout {
+                    timeout_ms: self.config.request_timeout_ms,
+                })?
+                .map_err(|e| match e.kind() {
+                    // Nobody listening is not a failure of the server.
+                    RequestErrorKind::NoResponders => NatsError::Subject(e.to_string()),
+                    _ => NatsError::Connection(e.to_string()),
+                })
+            })
+            .await?;
 
         let result = serde_json::from_slice(&response.payload)?;
         Ok(result)
@@ -317,11 +459,27 @@ impl NatsQueue {
     /// * `ConnectionStatus` - Connection status information
     pub fn connection_status(&self) -> ConnectionStatus {
         ConnectionStatus {
-            is_connected: true, // Simplified - async-nats doesn't have is_closed method
+            is_connected: self.client.connection_state()
+                == async_nats::connection::State::Connected,
             server_info: self.client.server_info().clone(),
         }
     }
 
+    /// Check the server answers
+    ///
+    /// Fails right away if the client is disconnected, otherwise flushes and waits for the server
+    /// to acknowledge.
+    ///
+    /// # Returns
+    /// * `NatsResult<()>` - Result of the check
+    pub async fn ping(&self) -> NatsResult<()> {
+        let state = self.client.connection_state();
+        if state != async_nats::connection::State::Connected {
+            return Err(NatsError::Connection(format!("Client is {state}")));
+        }
+        self.flush().await
+    }
+
     /// Flush pending messages
     ///
     /// # Returns
@@ -333,54 +491,181 @@ impl NatsQueue {
             .map_err(|e| NatsError::Connection(e.to_string()))?;
         Ok(())
     }
+
+    /// Open a JetStream key-value bucket, created if it does not exist
+    ///
+    /// # Arguments
+    /// * `bucket` - Name of the bucket
+    /// * `max_age` - Time entries are kept since they were last written, unlimited if zero
+    ///
+    /// # Returns
+    /// * `NatsResult<KeyValueStore>` - The bucket, or an error if JetStream is not enabled
+    pub async fn key_value(&self, bucket: &str, max_age: Duration) -> NatsResult<KeyValueStore> {
+        let context = jetstream::new(self.client.clone());
+        let store = match context.get_key_value(bucket).await {
+            Ok(store) => store,
+            Err(_) => context
+                .create_key_value(jetstream::kv::Config {
+                    bucket: bucket.to_string(),
+                    history: 1,
+                    max_age,
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| NatsError::KeyValue(e.to_string()))?,
+        };
+
+        Ok(KeyValueStore { store })
+    }
+
+    /// Open a JetStream stream storing the messages of the subjects, created if it does not exist
+    ///
+    /// # Arguments
+    /// * `name` - Name of the stream
+    /// * `subjects` - Subjects captured by the stream, used only if the stream is created
+    /// * `max_age` - Time messages are kept, unlimited if zero
+    ///
+    /// # Returns
+    /// * `NatsResult<()>` - Result of the attempt, an error if JetStream is not enabled
+    pub async fn ensure_stream(
+        &self,
+        name: &str,
+        subjects: &[&str],
+        max_age: Duration,
+    ) -> NatsResult<()> {
+        let context = jetstream::new(self.client.clone());
+        context
+            .get_or_create_stream(jetstream::stream::Config {
+                name: name.to_string(),
+                subjects: subjects.iter().map(|subject| subject.to_string()).collect(),
+                max_age,
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| NatsError::JetStream(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Drain the connection
+    ///
+    /// Subscriptions stop receiving new messages, already received messages are still delivered and
+    /// subscribers end afterwards, pending published messages are flushed and the connection closes.
+    ///
+    /// # Returns
+    /// * `NatsResult<()>` - Result of the drain request
+    pub async fn drain(&self) -> NatsResult<()> {
+        self.client
+            .drain()
+            .await
+            .map_err(|e| NatsError::Connection(e.to_string()))?;
+        Ok(())
+    }
 }
 
-/// Connection status information
+/// JetStream key-value bucket storing JSON values
 #[derive(Debug, Clone)]
-pub struct ConnectionStatus {
-    pub is_connected: bool,
-    pub server_info: async_nats::ServerInfo,
+pub struct KeyValueStore {
+    store: jetstream::kv::Store,
 }
 
-/// Webhook event for NATS messaging
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct WebhookEventMessage {
-    pub event_id: uuid::Uuid,
-    pub event_type: String,
-    pub source: String,
-    pub data: serde_json::Value,
-    pub timestamp: chrono::DateTime<chrono::Utc>,
-    pub retry_count: u32,
-    pub max_retries: u32,
-}
+impl KeyValueStore {
+    /// Get the value of a key
+    ///
+    /// # Arguments
+    /// * `key` - The key, letters, digits and `-/_=.` only
+    ///
+    /// # Returns
+    /// * `NatsResult<Option<T>>` - The value, None if the key does not exist
+    pub async fn get<T>(&self, key: &str) -> NatsResult<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let value = self
+            .store
+            .get(key)
+            .await
+            .map_err(|e| NatsError::KeyValue(e.to_string()))?;
+        Ok(value
+            .map(|value| serde_json::from_slice(&value))
+            .transpose()?)
+    }
 
-impl WebhookEventMessage {
-    pub fn new(
-        event_id: uuid::Uuid,
-        event_type: String,
-        source: String,
-        data: serde_json::Value,
-    ) -> Self {
-        Self {
-            event_id,
-            event_type,
-            source,
-            data,
-            timestamp: chrono::Utc::now(),
-            retry_count: 0,
-            max_retries: 3,
-        }
+    /// Put the value of a key
+    ///
+    /// # Arguments
+    /// * `key` - The key, letters, digits and `-/_=.` only
+    /// * `value` - The value
+    ///
+    /// # Returns
+    /// * `NatsResult<()>` - Result of the put
+    pub async fn put<T>(&self, key: &str, value: &T) -> NatsResult<()>
+    where
+        T: Serialize,
+    {
+        let data = serde_json::to_vec(value)?;
+        self.store
+            .put(key, data.into())
+            .await
+            .map_err(|e| NatsError::KeyValue(e.to_string()))?;
+        Ok(())
     }
 
-    pub fn increment_retry(&mut self) {
-        self.retry_count += 1;
+    /// Get the value of a key with its revision, to update it with [`KeyValueStore::update`]
+    ///
+    /// # Arguments
+    /// * `key` - The key, letters, digits and `-/_=.` only
+    ///
+    /// # Returns
+    /// * `NatsResult<(Option<T>, u64)>` - The value, None if the key does not exist, and the
+    ///   revision of the key, 0 if it was never written
+    pub async fn get_with_revision<T>(&self, key: &str) -> NatsResult<(Option<T>, u64)>
+    where
+        T: DeserializeOwned,
+    {
+        let entry = self
+            .store
+            .entry(key)
+            .await
+            .map_err(|e| NatsError::KeyValue(e.to_string()))?;
+        match entry {
+            Some(entry) if entry.operation == kv::Operation::Put => {
+                Ok((Some(serde_json::from_slice(&entry.value)?), entry.revision))
+            }
+            Some(entry) => Ok((None, entry.revision)),
+            None => Ok((None, 0)),
+        }
     }
 
-    pub fn should_retry(&self) -> bool {
-        self.retry_count < self.max_retries
+    /// Put the value of a key unless it was written after the revision
+    ///
+    /// # Arguments
+    /// * `key` - The key, letters, digits and `-/_=.` only
+    /// * `value` - The value
+    /// * `revision` - The revision the value was read at
+    ///
+    /// # Returns
+    /// * `NatsResult<bool>` - Whether the value was put, false if the key was written after the
+    ///   revision
+    pub async fn update<T>(&self, key: &str, value: &T, revision: u64) -> NatsResult<bool>
+    where
+        T: Serialize,
+    {
+        let data = serde_json::to_vec(value)?;
+        match self.store.update(key, data.into(), revision).await {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == kv::UpdateErrorKind::WrongLastRevision => Ok(false),
+            Err(e) => Err(NatsError::KeyValue(e.to_string())),
+        }
     }
 }
 
+/// Connection status information
+#[derive(Debug, Clone)]
+pub struct ConnectionStatus {
+    pub is_connected: bool,
+    pub server_info: async_nats::ServerInfo,
+}
+
 /// Subject builder for consistent naming
 pub struct SubjectBuilder {
     prefix: String,
@@ -421,30 +706,6 @@ impl SubjectBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use uuid::Uuid;
-
-    #[test]
-    fn test_webhook_event_message() {
-        let event_id = Uuid::new_v4();
-        let mut message = WebhookEventMessage::new(
-            event_id,
-            "user.created".to_string(),
-            "api.example.com".to_string(),
-            serde_json::json!({"user_id": 123}),
-        );
-
-        assert_eq!(message.event_id, event_id);
-        assert_eq!(message.retry_count, 0);
-        assert!(message.should_retry());
-
-        message.increment_retry();
-        assert_eq!(message.retry_count, 1);
-        assert!(message.should_retry());
-
-        // Exceed max retries
-        message.retry_count = 5;
-        assert!(!message.should_retry());
-    }
 
     #[test]
     fn test_subject_builder() {
@@ -466,6 +727,25 @@ mod tests {
         assert_eq!(builder.custom("test"), "semantic_machine.webhooks.test");
     }
 
+    #[tokio::test]
+    async fn test_request_id_headers() {
+        assert!(headers().is_empty());
+
+        let headers = with_request_id("4f1c-aa".to_string(), async { headers() }).await;
+        let message = Message {
+            subject: "rss_items".into(),
+            reply: None,
+            payload: Default::default(),
+            headers: Some(headers),
+            status: None,
+            description: None,
+            length: 0,
+        };
+
+        assert_eq!(message_request_id(&message), Some("4f1c-aa"));
+        assert_eq!(current_request_id(), None);
+    }
+
     #[test]
     fn test_nats_config_default() {
         let config = NatsConfig::default();
@@ -474,20 +754,4 @@ mod tests {
         assert_eq!(config.connect_timeout_ms, 5000);
         assert!(!config.tls_enabled);
     }
-
-    #[tokio::test]
-    async fn test_serialization() {
-        let message = WebhookEventMessage::new(
-            Uuid::new_v4(),
-            "test.event".to_string(),
-            "test.source".to_string(),
-            serde_json::json!({"test": "data"}),
-        );
-
-        let serialized = serde_json::to_string(&message).unwrap();
-        let deserialized: WebhookEventMessage = serde_json::from_str(&serialized).unwrap();
-
-        assert_eq!(message.event_id, deserialized.event_id);
-        assert_eq!(message.event_type, deserialized.event_type);
-    }
 }
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
-use redis::AsyncCommands;
-use std::env;
+use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig};
+use redis::{AsyncCommands, ExistenceCheck, RedisError, SetExpiry, SetOptions};
+use std::{env, sync::Arc};
 
 pub struct Config {
     pub redis_url: String,
@@ -25,42 +26,231 @@ impl Config {
     }
 }
 
+#[derive(Clone)]
 pub struct RedisMiddleware {
     client: redis::Client,
+    breaker: Arc<CircuitBreaker>,
 }
 
 impl RedisMiddleware {
     pub fn new(url: &str) -> Result<Self> {
         let client = redis::Client::open(url)?;
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            breaker: Arc::new(CircuitBreaker::new(
+                "redis",
+                CircuitBreakerConfig::default(),
+            )),
+        })
+    }
+
+    /// Circuit breaker guarding the commands, commands fail right away while it is open.
+    pub fn circuit_breaker(&self) -> Arc<CircuitBreaker> {
+        self.breaker.clone()
     }
 
     pub async fn store(&self, key: &str, value: &str) -> Result<()> {
         Ok(self
-            .client
-            .get_multiplexed_async_connection()
-            .await?
-            .set(key, value)
+            .breaker
+            .call_filtered(
+                async {
+                    self.client
+                        .get_multiplexed_async_connection()
+                        .await?
+                        .set(key, value)
+                        .await
+                },
+                is_connection_error,
+            )
+            .await?)
+    }
+
+    pub async fn store_with_ttl(&self, key: &str, value: &str, ttl_seconds: u64) -> Result<()> {
+        Ok(self
+            .breaker
+            .call_filtered(
+                async {
+                    self.client
+                        .get_multiplexed_async_connection()
+                        .await?
+                        .set_ex(key, value, ttl_seconds)
+                        .await
+                },
+                is_connection_error,
+            )
+            .await?)
+    }
+
+    /// Stores the value only if the key does not exist, atomically.
+    ///
+    /// # Returns
+    /// True if the value was stored, false if the key already existed.
+    pub async fn store_if_absent_with_ttl(
+        &self,
+        key: &str,
+        value: &str,
+        ttl_seconds: u64,
+    ) -> Result<bool> {
+        let options = SetOptions::default()
+            .conditional_set(ExistenceCheck::NX)
+            .with_expiration(SetExpiry::EX(ttl_seconds));
+        let stored: Option<String> = self
+            .breaker
+            .call_filtered(
+                async {
+                    self.client
+                        .get_multiplexed_async_connection()
+                        .await?
+                        .set_options(key, value, options)
+                        .await
+                },
+                is_connection_error,
+            )
+            .await?;
+        Ok(stored.is_some())
+    }
+
+    pub async fn increment(&self, key: &str) -> Result<i64> {
+        Ok(self
+            .breaker
+            .call_filtered(
+                async {
+                    self.client
+                        .get_multiplexed_async_connection()
+                        .await?
+                        .incr(key, 1)
+                        .await
+                },
+                is_connection_error,
+            )
+            .await?)
+    }
+
+    /// Increments the counter and sets it to expire, atomically.
+    ///
+    /// # Returns
+    /// The value of the counter after the increment.
+    pub async fn increment_with_ttl(&self, key: &str, ttl_seconds: u64) -> Result<i64> {
+        let (count,): (i64,) = self
+            .breaker
+            .call_filtered(
+                async {
+                    redis::pipe()
+                        .atomic()
+                        .incr(key, 1)
+                        .expire(key, ttl_seconds as i64)
+                        .ignore()
+                        .query_async(&mut self.client.get_multiplexed_async_connection().await?)
+                        .await
+                },
+                is_connection_error,
+            )
+            .await?;
+        Ok(count)
+    }
+
+    /// Stores the value with the TTL only if the key holds the expected value, atomically.
+    ///
+    /// # Returns
+    /// True if the value was stored, false if the key is missing or holds another value.
+    pub async fn store_if_equal_with_ttl(
+        &self,
+        key: &str,
+        expected: &str,
+        value: &str,
+        ttl_seconds: u64,
+    ) -> Result<bool> {
+        let script = redis::Script::new(
+            r"if redis.call('GET', KEYS[1]) == ARGV[1] then
+                redis.call('SET', KEYS[1], ARGV[2], 'EX', ARGV[3])
+                return 1
+            end
+            return 0",
+        );
+        let stored: i64 = self
+            .breaker
+            .call_filtered(
+                async {
+                    script
+                        .key(key)
+                        .arg(expected)
+                        .arg(value)
+                        .arg(ttl_seconds)
+                        .invoke_async(&mut self.client.get_multiplexed_async_connection().await?)
+                        .await
+                },
+                is_connection_error,
+            )
+            .await?;
+        Ok(stored == 1)
+    }
+
+    /// Sets the key to expire if it has no expiration yet, keys stored without a TTL stop living
+    /// forever. Requires Redis 7 or newer.
+    ///
+    /// # Returns
+    /// True if the expiration was set, false if the key does not exist or expires already.
+    pub async fn expire_if_persistent(&self, key: &str, ttl_seconds: u64) -> Result<bool> {
+        Ok(self
+            .breaker
+            .call_filtered(
+                async {
+                    redis::cmd("EXPIRE")
+                        .arg(key)
+                        .arg(ttl_seconds)
+                        .arg("NX")
+                        .query_async(&mut self.client.get_multiplexed_async_connection().await?)
+                        .await
+                },
+                is_connection_error,
+            )
             .await?)
     }
 
     pub async fn retrieve(&self, key: &str) -> Result<Option<String>> {
         Ok(self
-            .client
-            .get_multiplexed_async_connection()
-            .await?
-            .get(key)
+            .breaker
+            .call_filtered(
+                async {
+                    self.client
+                        .get_multiplexed_async_connection()
+                        .await?
+                        .get(key)
+                        .await
+                },
+                is_connection_error,
+            )
             .await?)
     }
 
     pub async fn delete(&self, key: &str) -> Result<()> {
         Ok(self
-            .client
-            .get_multiplexed_async_connection()
-            .await?
-            .del(key)
+            .breaker
+            .call_filtered(
+                async {
+                    self.client
+                        .get_multiplexed_async_connection()
+                        .await?
+                        .del(key)
+                        .await
+                },
+                is_connection_error,
+            )
             .await?)
     }
+
+    pub async fn ping(&self) -> Result<()> {
+        let mut connection = self.client.get_multiplexed_async_connection().await?;
+        let _: String = redis::cmd("PING").query_async(&mut connection).await?;
+        Ok(())
+    }
+}
+
+fn is_connection_error(error: &RedisError) -> bool {
+    error.is_io_error()
+        || error.is_timeout()
+        || error.is_connection_dropped()
+        || error.is_connection_refusal()
 }
 
 #[cfg(feature = "integrations")]
@@ -108,4 +298,57 @@ mod test {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_increment_with_ttl() -> Result<()> {
+        let middleware = RedisMiddleware::new(REDIS_URL)?;
+        let key = "test_key_4";
+
+        middleware.delete(key).await?;
+        assert_eq!(middleware.increment_with_ttl(key, 60).await?, 1);
+        assert_eq!(middleware.increment_with_ttl(key, 60).await?, 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_store_if_equal_with_ttl() -> Result<()> {
+        let middleware = RedisMiddleware::new(REDIS_URL)?;
+        let key = "test_key_6";
+
+        middleware.store(key, "owner").await?;
+        assert!(
+            middleware
+                .store_if_equal_with_ttl(key, "owner", "owner", 60)
+                .await?
+        );
+        assert!(
+            !middleware
+                .store_if_equal_with_ttl(key, "other", "other", 60)
+                .await?
+        );
+        assert_eq!(middleware.retrieve(key).await?, Some("owner".to_string()));
+        middleware.delete(key).await?;
+        assert!(
+            !middleware
+                .store_if_equal_with_ttl(key, "owner", "owner", 60)
+                .await?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_expire_if_persistent() -> Result<()> {
+        let middleware = RedisMiddleware::new(REDIS_URL)?;
+        let key = "test_key_5";
+
+        middleware.store(key, "value").await?;
+        assert!(middleware.expire_if_persistent(key, 60).await?);
+        assert!(!middleware.expire_if_persistent(key, 60).await?);
+        middleware.delete(key).await?;
+        assert!(!middleware.expire_if_persistent(key, 60).await?);
+
+        Ok(())
+    }
 }
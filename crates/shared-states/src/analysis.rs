@@ -0,0 +1,149 @@
+use crate::events::SentimentLabel;
+use serde::{Deserialize, Serialize};
+
+/// SentimentScore is the sentiment of an analyzed text.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct SentimentScore {
+    pub label: SentimentLabel,
+    /// Confidence of the label in range `0.0..=1.0`.
+    pub score: f32,
+    /// Positive minus negative probability in range `-1.0..=1.0`.
+    pub polarity: f32,
+}
+
+/// EntityKind is the type of a recognized entity.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Person,
+    Organization,
+    Location,
+    CryptoTicker,
+}
+
+impl EntityKind {
+    /// Maps a token classification label such as `B-PER`, `I-ORG` or `LOC` to the entity kind.
+    ///
+    /// # Returns
+    /// The entity kind or `None` for labels that are not tracked (`O`, `MISC`).
+    pub fn from_label(label: &str) -> Option<Self> {
+        let label = label
+            .strip_prefix("B-")
+            .or_else(|| label.strip_prefix("I-"))
+            .unwrap_or(label);
+
+        match label.to_uppercase().as_str() {
+            "PER" | "PERSON" => Some(Self::Person),
+            "ORG" | "ORGANIZATION" => Some(Self::Organization),
+            "LOC" | "LOCATION" | "GPE" => Some(Self::Location),
+            "TICKER" | "CRYPTO" | "CRYPTO_TICKER" => Some(Self::CryptoTicker),
+            _ => None,
+        }
+    }
+}
+
+/// Entity represents an entity mentioned in a text.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Entity {
+    pub kind: EntityKind,
+    pub text: String,
+    /// Byte offset of the first character of the entity in the text.
+    pub start: usize,
+    /// Byte offset past the last character of the entity in the text.
+    pub end: usize,
+    /// Confidence of the model in range `0.0..=1.0`.
+    pub score: f32,
+}
+
+/// Summary is a shortened version of an analyzed text.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Summary {
+    pub text: String,
+    /// Name of the model that produced the summary.
+    pub model: String,
+}
+
+/// Length of the embeddings stored for semantic search, e.g. of `all-MiniLM-L6-v2`.
+pub const EMBEDDING_DIMENSION: usize = 384;
+
+/// Embedding is a fixed-size, unit length vector representing the meaning of a text.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Embedding {
+    /// Stored as `REAL[]`.
+    pub vector: Vec<f32>,
+}
+
+impl Embedding {
+    /// Cosine similarity to the other embedding in range `-1.0..=1.0`.
+    pub fn similarity(&self, other: &Embedding) -> f32 {
+        cosine_similarity(&self.vector, &other.vector)
+    }
+}
+
+/// Cosine similarity of two vectors in range `-1.0..=1.0`, `0.0` if any of them is zero.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        return 0.0;
+    }
+
+    (dot / norms).clamp(-1.0, 1.0)
+}
+
+fn norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|value| value * value).sum::<f32>().sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entity_kind_from_label() {
+        assert_eq!(EntityKind::from_label("B-PER"), Some(EntityKind::Person));
+        assert_eq!(EntityKind::from_label("I-LOC"), Some(EntityKind::Location));
+        assert_eq!(
+            EntityKind::from_label("org"),
+            Some(EntityKind::Organization)
+        );
+        assert_eq!(EntityKind::from_label("B-MISC"), None);
+        assert_eq!(EntityKind::from_label("O"), None);
+    }
+
+    #[test]
+    fn test_analysis_results_serde() {
+        let entity = Entity {
+            kind: EntityKind::CryptoTicker,
+            text: "BTC".to_string(),
+            start: 0,
+            end: 3,
+            score: 1.0,
+        };
+        let sentiment = SentimentScore {
+            label: SentimentLabel::Negative,
+            score: 0.75,
+            polarity: -0.5,
+        };
+
+        let json = serde_json::to_string(&entity).unwrap();
+        assert!(json.contains(r#""kind":"crypto_ticker""#));
+        assert_eq!(serde_json::from_str::<Entity>(&json).unwrap(), entity);
+        let json = serde_json::to_string(&sentiment).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SentimentScore>(&json).unwrap(),
+            sentiment
+        );
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 0.0]), 0.0);
+        assert!(
+            (Embedding {
+                vector: vec![1.0, 1.0]
+            }
+            .similarity(&Embedding {
+                vector: vec![2.0, 2.0]
+            }) - 1.0)
+                .abs()
+                < 1e-6
+        );
+    }
+}
@@ -1,48 +1,377 @@
-use anyhow::{Result, anyhow};
-use regex::bytes::Regex;
-use scraper::{Html, Selector};
+use crate::charset::decode_html;
+use crate::fetcher::ArticleFetcher;
+use crate::markdown::element_to_markdown;
+use crate::metadata::{ArticleMetadata, extract_metadata};
+use crate::pdf::{extract_pdf_text, is_pdf};
+use crate::readability::{find_content_root, inner_text};
+use crate::robots::RobotsCache;
+use crate::sanitize::{normalize_text, strip_boilerplate};
+use anyhow::Result;
+use reqwest::Url;
+use scraper::Html;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::task::spawn_blocking;
+
+/// Average adult silent reading speed used for reading time estimates.
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// ExtractionError describes why an article could not be extracted.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ExtractionError {
+    #[error("Extraction of ( {0} ) is disallowed by robots.txt")]
+    DisallowedByRobots(String),
+
+    #[error("Fetching ( {url} ) failed with status {status}")]
+    HttpStatus { url: String, status: u16 },
+
+    #[error("Article extraction failed")]
+    ContentNotFound,
+}
+
+/// Article represents the content extracted from an article page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Article {
+    pub text: String,
+    /// The content with headings, lists, links and emphasis kept as Markdown.
+    pub markdown: String,
+    pub metadata: ArticleMetadata,
+    pub word_count: usize,
+    pub reading_time_minutes: usize,
+}
+
+impl Article {
+    fn new(text: String, markdown: String, metadata: ArticleMetadata) -> Self {
+        let word_count = count_words(&text);
+
+        Self {
+            text,
+            markdown,
+            metadata,
+            word_count,
+            reading_time_minutes: reading_time_minutes(word_count),
+        }
+    }
+}
 
 /// Extracts the article content from a given URL.
 ///
+/// robots.txt of the host is consulted first, disallowed pages are not fetched and the crawl
+/// delay of the host is honored.
+///
 /// # Arguments
 ///
+/// * `fetcher` - The fetcher used to download robots.txt and the article page.
 /// * `url` - The URL of the article to extract.
 ///
 /// # Returns
 ///
-/// A `Result` containing the extracted article content as a `String`, or an `anyhow::Error` if extraction fails.
-pub async fn extract_article(url: &str) -> Result<String> {
-    let resp = reqwest::get(url).await?;
-    let body = resp.text().await?;
+/// A `Result` containing the extracted `Article`, or an `anyhow::Error` if extraction fails.
+/// Skipped and empty pages are reported with an `ExtractionError` that can be downcast from the error.
+pub async fn extract_article<F: ArticleFetcher>(fetcher: &F, url: &str) -> Result<Article> {
+    let url = Url::parse(url)?;
+
+    let robots = RobotsCache::global();
+    if !robots.is_allowed(fetcher, &url).await {
+        return Err(ExtractionError::DisallowedByRobots(url.to_string()).into());
+    }
+    robots.wait_for_crawl_delay(fetcher, &url).await;
+
+    let page = fetcher.fetch(&url).await?;
+    if !page.is_success() {
+        return Err(ExtractionError::HttpStatus {
+            url: page.url,
+            status: page.status,
+        }
+        .into());
+    }
 
-    let document = Html::parse_document(&body);
+    if is_pdf(page.content_type.as_deref(), &page.body) {
+        let body = page.body;
+        return spawn_blocking(move || parse_pdf_article(&body)).await?;
+    }
+
+    let base_url = Url::parse(&page.url).unwrap_or(url);
+    parse_article(
+        &decode_html(&page.body, page.content_type.as_deref()),
+        Some(&base_url),
+    )
+}
 
-    if let Ok(content_selector) = Selector::parse("article")
-        && let Some(element) = document.select(&content_selector).next()
-    {
-        let text = element.text().collect::<Vec<_>>().join(" ");
-        return Ok(replace_tags(&text).unwrap_or(text));
+/// Parses the article content and metadata from an HTML page.
+///
+/// # Arguments
+///
+/// * `html` - The HTML of the article page.
+/// * `base_url` - URL of the page used to resolve relative links in the Markdown output.
+///
+/// # Returns
+///
+/// A `Result` containing the parsed `Article`, or an `anyhow::Error` if no content was found.
+pub fn parse_article(html: &str, base_url: Option<&Url>) -> Result<Article> {
+    let mut document = Html::parse_document(html);
+    let metadata = extract_metadata(&document);
+    strip_boilerplate(&mut document);
+
+    let Some(element) = find_content_root(&document) else {
+        return Err(ExtractionError::ContentNotFound.into());
     };
 
-    if let Ok(fallback_selector) = Selector::parse("div.post-content")
-        && let Some(el2) = document.select(&fallback_selector).next()
-    {
-        let text = el2.text().collect::<Vec<_>>().join(" ");
-        return Ok(replace_tags(&text).unwrap_or(text));
+    Ok(Article::new(
+        normalize_text(&inner_text(&element)),
+        element_to_markdown(&element, base_url),
+        metadata,
+    ))
+}
+
+/// Parses the article content from an HTML fragment, such as the `content:encoded` of a feed item.
+///
+/// # Arguments
+///
+/// * `html` - The HTML fragment with the full article.
+/// * `base_url` - URL of the article used to resolve relative links in the Markdown output.
+///
+/// # Returns
+///
+/// A `Result` containing the parsed `Article` without metadata, or an `anyhow::Error` if the
+/// fragment has no text.
+pub fn parse_article_fragment(html: &str, base_url: Option<&Url>) -> Result<Article> {
+    let mut fragment = Html::parse_fragment(html);
+    strip_boilerplate(&mut fragment);
+    let root = fragment.root_element();
+
+    let text = normalize_text(&inner_text(&root));
+    if text.is_empty() {
+        return Err(ExtractionError::ContentNotFound.into());
+    }
+
+    Ok(Article::new(
+        text,
+        element_to_markdown(&root, base_url),
+        ArticleMetadata::default(),
+    ))
+}
+
+/// Parses the article content from a PDF document.
+///
+/// # Arguments
+///
+/// * `bytes` - The PDF document.
+///
+/// # Returns
+///
+/// A `Result` containing the parsed `Article` without metadata, or an `anyhow::Error` if the
+/// document could not be read or has no text.
+pub fn parse_pdf_article(bytes: &[u8]) -> Result<Article> {
+    let text = normalize_text(&extract_pdf_text(bytes)?);
+    if text.is_empty() {
+        return Err(ExtractionError::ContentNotFound.into());
     }
 
-    Err(anyhow!("Article extraction failed"))
+    let markdown = text.clone();
+    Ok(Article::new(text, markdown, ArticleMetadata::default()))
+}
+
+/// Counts the whitespace separated words containing at least one alphanumeric character.
+pub fn count_words(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
 }
 
-fn replace_tags(content: &str) -> Result<String> {
-    let re_tags = Regex::new(r"</?[^>]+>")?;
-    let without_tags = re_tags.replace_all(content.as_bytes(), b"");
-    let cleaned = String::from_utf8(without_tags.to_vec())?;
+/// Estimates the reading time of a text with `word_count` words.
+///
+/// # Arguments
+///
+/// * `word_count` - Number of words in the text.
+///
+/// # Returns
+///
+/// Whole minutes rounded up, `0` only for a text without words.
+pub fn reading_time_minutes(word_count: usize) -> usize {
+    word_count.div_ceil(WORDS_PER_MINUTE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetcher::FetchedPage;
+    use crate::pdf::tests::single_page_pdf;
+    use anyhow::anyhow;
+    use std::collections::HashMap;
+
+    const ARTICLE_HTML: &str = r#"<html><head><meta property="og:title" content="Ether ETFs approved"></head>
+        <body><nav><a href="/">Home</a></nav><article><p>The Securities and Exchange Commission approved the
+        first spot ether exchange traded funds on Tuesday, clearing the way for trading to begin soon.</p>
+        <p>Issuers spent months going back and forth with the regulator, amending filings, fees and custody
+        arrangements before the final approval arrived.</p></article></body></html>"#;
+
+    struct MockFetcher {
+        pages: HashMap<String, FetchedPage>,
+    }
+
+    impl MockFetcher {
+        fn new(pages: &[(&str, u16, &str)]) -> Self {
+            let pages = pages
+                .iter()
+                .map(|(url, status, body)| {
+                    (
+                        url.to_string(),
+                        FetchedPage {
+                            url: url.to_string(),
+                            status: *status,
+                            content_type: Some("text/html; charset=utf-8".to_string()),
+                            body: body.as_bytes().to_vec(),
+                        },
+                    )
+                })
+                .collect();
+            Self { pages }
+        }
+    }
+
+    impl ArticleFetcher for MockFetcher {
+        async fn fetch(&self, url: &Url) -> Result<FetchedPage> {
+            match self.pages.get(url.as_str()) {
+                Some(page) => Ok(page.clone()),
+                // Hosts without a robots.txt allow everything.
+                None if url.path() == "/robots.txt" => Ok(FetchedPage {
+                    url: url.to_string(),
+                    status: 404,
+                    content_type: None,
+                    body: Vec::new(),
+                }),
+                None => Err(anyhow!("connection refused")),
+            }
+        }
+    }
 
-    let without_pipes = cleaned.replace("|", "");
+    #[tokio::test]
+    async fn test_extract_article() {
+        let fetcher = MockFetcher::new(&[("https://news.example.com/eth-etf", 200, ARTICLE_HTML)]);
 
-    Ok(without_pipes
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" "))
+        let article = extract_article(&fetcher, "https://news.example.com/eth-etf")
+            .await
+            .unwrap();
+
+        assert!(
+            article
+                .text
+                .starts_with("The Securities and Exchange Commission")
+        );
+        assert!(!article.text.contains("Home"));
+        assert!(
+            article
+                .markdown
+                .starts_with("The Securities and Exchange Commission")
+        );
+        assert!(
+            article
+                .markdown
+                .contains("traded funds on Tuesday, clearing the way")
+        );
+        assert!(
+            article
+                .markdown
+                .contains("begin soon.\n\nIssuers spent months")
+        );
+        assert_eq!(article.word_count, 44);
+        assert_eq!(article.reading_time_minutes, 1);
+        assert_eq!(
+            article.metadata.title.as_deref(),
+            Some("Ether ETFs approved")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extract_article_pdf() {
+        let pdf = String::from_utf8(single_page_pdf("Quarterly research note on staking yields"))
+            .unwrap();
+        let fetcher = MockFetcher::new(&[("https://research.example.com/note.pdf", 200, &pdf)]);
+
+        let article = extract_article(&fetcher, "https://research.example.com/note.pdf")
+            .await
+            .unwrap();
+
+        assert_eq!(article.text, "Quarterly research note on staking yields");
+        assert_eq!(article.word_count, 6);
+        assert_eq!(article.metadata, ArticleMetadata::default());
+    }
+
+    #[test]
+    fn test_parse_article_fragment() {
+        let base_url = Url::parse("https://news.example.com/fed").unwrap();
+        let article = parse_article_fragment(
+            "<p>The Fed held rates steady.</p><p>See the <a href=\"/notes\">meeting notes</a> online.</p>",
+            Some(&base_url),
+        )
+        .unwrap();
+
+        assert_eq!(
+            article.text,
+            "The Fed held rates steady. See the meeting notes online."
+        );
+        assert!(
+            article
+                .markdown
+                .contains("[meeting notes](https://news.example.com/notes)")
+        );
+        assert_eq!(article.word_count, 10);
+
+        let err = parse_article_fragment("<p> </p>", None).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<ExtractionError>(),
+            Some(&ExtractionError::ContentNotFound)
+        );
+    }
+
+    #[test]
+    fn test_reading_time() {
+        assert_eq!(count_words("Ether ETFs — approved, finally! 2024"), 5);
+        assert_eq!(reading_time_minutes(0), 0);
+        assert_eq!(reading_time_minutes(1), 1);
+        assert_eq!(reading_time_minutes(200), 1);
+        assert_eq!(reading_time_minutes(201), 2);
+    }
+
+    #[tokio::test]
+    async fn test_extract_article_disallowed_by_robots() {
+        let fetcher = MockFetcher::new(&[
+            (
+                "https://blocked.example.com/robots.txt",
+                200,
+                "User-agent: *\nDisallow: /premium/",
+            ),
+            (
+                "https://blocked.example.com/premium/story",
+                200,
+                ARTICLE_HTML,
+            ),
+        ]);
+
+        let err = extract_article(&fetcher, "https://blocked.example.com/premium/story")
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<ExtractionError>(),
+            Some(&ExtractionError::DisallowedByRobots(
+                "https://blocked.example.com/premium/story".to_string()
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extract_article_http_status() {
+        let fetcher = MockFetcher::new(&[("https://gone.example.com/story", 404, "")]);
+
+        let err = extract_article(&fetcher, "https://gone.example.com/story")
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<ExtractionError>(),
+            Some(ExtractionError::HttpStatus { status: 404, .. })
+        ));
+    }
 }
@@ -0,0 +1,198 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use sqlx::types::Json;
+use sqlx::{Decode, Encode, Postgres, Type};
+use std::fmt;
+
+/// Author represents the author of an RSS item.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Author {
+    /// Display name, empty if only the email is known.
+    pub name: String,
+    pub email: Option<String>,
+}
+
+impl Author {
+    /// Parses an author string.
+    ///
+    /// Supports the RSS `email (Name)` format, the mailbox `Name <email>` format and plain
+    /// names or emails.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The raw author string.
+    ///
+    /// # Returns
+    ///
+    /// The parsed author, default if the value is blank.
+    pub fn parse(value: &str) -> Self {
+        let value = value.trim();
+
+        if let Some((email, rest)) = value.split_once('(')
+            && let Some(name) = rest.strip_suffix(')')
+            && is_email(email.trim())
+        {
+            return Self::new(name, Some(email));
+        }
+
+        if let Some((name, rest)) = value.split_once('<')
+            && let Some(email) = rest.strip_suffix('>')
+            && is_email(email.trim())
+        {
+            return Self::new(name.trim().trim_matches('"'), Some(email));
+        }
+
+        if is_email(value) {
+            return Self::new("", Some(value));
+        }
+
+        Self::new(value, None)
+    }
+
+    /// Checks if neither the name nor the email is known.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_empty() && self.email.is_none()
+    }
+
+    fn new(name: &str, email: Option<&str>) -> Self {
+        Self {
+            name: name.trim().to_string(),
+            email: email.map(|email| email.trim().trim_start_matches("mailto:").to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Author {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.email, self.name.is_empty()) {
+            (Some(email), true) => write!(f, "{email}"),
+            (Some(email), false) => write!(f, "{email} ({})", self.name),
+            (None, _) => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl Type<Postgres> for Author {
+    fn type_info() -> PgTypeInfo {
+        <Json<Self> as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <Json<Self> as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, Postgres> for Author {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        Json(self).encode_by_ref(buf)
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for Author {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(Json::<Self>::decode(value)?.0)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AuthorRepr {
+    Structured(Author),
+    Raw(String),
+}
+
+/// Deserializes an author from the structured form or from the legacy raw string.
+pub(crate) fn deserialize_author<'de, D>(deserializer: D) -> Result<Author, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match AuthorRepr::deserialize(deserializer)? {
+        AuthorRepr::Structured(author) => author,
+        AuthorRepr::Raw(raw) => Author::parse(&raw),
+    })
+}
+
+fn is_email(value: &str) -> bool {
+    let value = value.trim_start_matches("mailto:");
+    match value.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && domain.contains('.')
+                && !value.contains(char::is_whitespace)
+                && !domain.contains('@')
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_author_parse() {
+        assert_eq!(
+            Author::parse("jane@example.com (Jane Doe)"),
+            Author {
+                name: "Jane Doe".to_string(),
+                email: Some("jane@example.com".to_string())
+            }
+        );
+        assert_eq!(
+            Author::parse("\"Jane Doe\" <jane@example.com>"),
+            Author {
+                name: "Jane Doe".to_string(),
+                email: Some("jane@example.com".to_string())
+            }
+        );
+        assert_eq!(
+            Author::parse(" jane@example.com "),
+            Author {
+                name: String::new(),
+                email: Some("jane@example.com".to_string())
+            }
+        );
+        assert_eq!(
+            Author::parse("Jane Doe <mailto:jane@example.com>"),
+            Author {
+                name: "Jane Doe".to_string(),
+                email: Some("jane@example.com".to_string())
+            }
+        );
+        assert_eq!(
+            Author::parse("mailto:jane@example.com (Jane Doe)"),
+            Author {
+                name: "Jane Doe".to_string(),
+                email: Some("jane@example.com".to_string())
+            }
+        );
+        assert_eq!(
+            Author::parse("mailto:jane@example.com"),
+            Author {
+                name: String::new(),
+                email: Some("jane@example.com".to_string())
+            }
+        );
+        assert_eq!(
+            Author::parse("Jane Doe (Markets desk)"),
+            Author {
+                name: "Jane Doe (Markets desk)".to_string(),
+                email: None
+            }
+        );
+        assert!(Author::parse("  ").is_empty());
+    }
+
+    #[test]
+    fn test_author_display_round_trip() {
+        for raw in [
+            "jane@example.com (Jane Doe)",
+            "jane@example.com",
+            "Jane Doe",
+        ] {
+            assert_eq!(Author::parse(raw).to_string(), raw);
+        }
+    }
+}
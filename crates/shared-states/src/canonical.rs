@@ -0,0 +1,81 @@
+use reqwest::Url;
+
+/// Query parameters of campaign and click tracking, they do not change the linked article.
+const TRACKING_PARAMETERS: [&str; 12] = [
+    "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "yclid", "_hsenc",
+    "_hsmi", "ref", "ref_src",
+];
+
+/// Normalizes an article URL, so links to the same article syndicated by different feeds are
+/// equal.
+///
+/// The scheme becomes `https`, the `www.` prefix, the fragment, the default port, tracking
+/// parameters such as `utm_*` and the trailing slash of the path are removed and the remaining
+/// query parameters are sorted.
+///
+/// # Arguments
+///
+/// * `link` - Link of the article as published by the feed.
+///
+/// # Returns
+///
+/// The canonical URL, None if the link is not an absolute http(s) URL.
+pub fn canonical_url(link: &str) -> Option<String> {
+    let url = Url::parse(link.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = url.host_str()?;
+    let host = host.strip_prefix("www.").unwrap_or(host);
+
+    let mut parameters = url
+        .query_pairs()
+        .filter(|(name, _)| {
+            let name = name.to_lowercase();
+            !name.starts_with("utm_") && !TRACKING_PARAMETERS.contains(&name.as_str())
+        })
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect::<Vec<_>>();
+    parameters.sort();
+
+    let mut canonical = match url.port() {
+        Some(port) => Url::parse(&format!("https://{host}:{port}")).ok()?,
+        None => Url::parse(&format!("https://{host}")).ok()?,
+    };
+    canonical.set_path(url.path().trim_end_matches('/'));
+    if !parameters.is_empty() {
+        canonical.query_pairs_mut().extend_pairs(parameters);
+    }
+
+    Some(canonical.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonical_url() {
+        let canonical = Some("https://news.example.com/markets/fed?id=7&page=2".to_string());
+        assert_eq!(
+            canonical_url("https://news.example.com/markets/fed?id=7&page=2"),
+            canonical
+        );
+        assert_eq!(
+            canonical_url(
+                " http://WWW.News.Example.com:80/markets/fed/?page=2&utm_source=rss&id=7&fbclid=x#comments "
+            ),
+            canonical
+        );
+        assert_eq!(
+            canonical_url("https://news.example.com/?utm_medium=feed"),
+            Some("https://news.example.com/".to_string())
+        );
+        assert_eq!(
+            canonical_url("https://news.example.com:8443/fed"),
+            Some("https://news.example.com:8443/fed".to_string())
+        );
+        assert_eq!(canonical_url("mailto:desk@news.example.com"), None);
+        assert_eq!(canonical_url("/markets/fed"), None);
+    }
+}
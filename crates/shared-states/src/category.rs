@@ -0,0 +1,94 @@
+use serde::{Deserialize, Deserializer};
+
+/// Normalizes a category name into a lowercase slug.
+///
+/// # Arguments
+///
+/// * `name` - Category name as published by the feed.
+///
+/// # Returns
+///
+/// The slug with every run of non alphanumeric characters replaced by a single `-`,
+/// empty if the name has no alphanumeric characters.
+pub fn normalize_category(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+/// Normalizes category names into unique, non empty slugs keeping the first occurrence order.
+///
+/// # Arguments
+///
+/// * `names` - Category names as published by the feed.
+///
+/// # Returns
+///
+/// The normalized categories.
+pub fn normalize_categories<I, S>(names: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut categories: Vec<String> = Vec::new();
+    for name in names {
+        let category = normalize_category(name.as_ref());
+        if !category.is_empty() && !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+
+    categories
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CategoriesRepr {
+    List(Vec<String>),
+    Joined(String),
+}
+
+/// Deserializes categories from a list or from the legacy comma joined string.
+pub(crate) fn deserialize_categories<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match CategoriesRepr::deserialize(deserializer)? {
+        CategoriesRepr::List(names) => normalize_categories(names),
+        CategoriesRepr::Joined(joined) => normalize_categories(joined.split(',')),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Categorized {
+        #[serde(deserialize_with = "deserialize_categories")]
+        category: Vec<String>,
+    }
+
+    #[test]
+    fn test_normalize_categories() {
+        assert_eq!(normalize_category("  Crypto News "), "crypto-news");
+        assert_eq!(normalize_category("DeFi & Web3"), "defi-web3");
+        assert_eq!(
+            normalize_categories(["Bitcoin", " bitcoin", "Crypto News", "", "--"]),
+            vec!["bitcoin", "crypto-news"]
+        );
+    }
+
+    #[test]
+    fn test_deserialize_categories() {
+        let legacy: Categorized =
+            serde_json::from_str(r#"{"category":"Markets, Bitcoin ,markets"}"#).unwrap();
+        let list: Categorized =
+            serde_json::from_str(r#"{"category":["Markets","Bitcoin"]}"#).unwrap();
+
+        assert_eq!(legacy.category, vec!["markets", "bitcoin"]);
+        assert_eq!(list.category, vec!["markets", "bitcoin"]);
+    }
+}
@@ -0,0 +1,110 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+use regex::bytes::Regex;
+use std::sync::LazyLock;
+
+const META_SNIFF_LIMIT: usize = 4096;
+
+static META_CHARSET: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?\s*([a-z0-9_\-:.]+)"#)
+        .expect("valid meta charset regex")
+});
+
+/// Detects the character encoding of an HTML page.
+///
+/// The byte order mark wins, then the `Content-Type` header charset, then a `<meta>` charset
+/// declaration. Undeclared pages are decoded as UTF-8 when valid and guessed otherwise.
+///
+/// # Arguments
+///
+/// * `bytes` - Raw response body.
+/// * `content_type` - Value of the `Content-Type` response header, if any.
+///
+/// # Returns
+///
+/// The detected encoding.
+pub fn detect_encoding(bytes: &[u8], content_type: Option<&str>) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+
+    if let Some(encoding) = content_type.and_then(charset_from_content_type) {
+        return encoding;
+    }
+
+    let head = &bytes[..bytes.len().min(META_SNIFF_LIMIT)];
+    if let Some(encoding) = META_CHARSET
+        .captures(head)
+        .and_then(|captures| captures.get(1))
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+    {
+        return encoding;
+    }
+
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+/// Decodes an HTML page into UTF-8 using the detected encoding.
+///
+/// # Arguments
+///
+/// * `bytes` - Raw response body.
+/// * `content_type` - Value of the `Content-Type` response header, if any.
+///
+/// # Returns
+///
+/// The decoded page, malformed sequences are replaced with U+FFFD.
+pub fn decode_html(bytes: &[u8], content_type: Option<&str>) -> String {
+    let encoding = detect_encoding(bytes, content_type);
+    let (text, _, _) = encoding.decode(bytes);
+    text.into_owned()
+}
+
+fn charset_from_content_type(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        if !name.trim().eq_ignore_ascii_case("charset") {
+            return None;
+        }
+        Encoding::for_label(value.trim().trim_matches(['"', '\'']).as_bytes())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{UTF_16LE, WINDOWS_1251, WINDOWS_1252};
+
+    #[test]
+    fn test_decode_html_from_header() {
+        let (bytes, _, _) = WINDOWS_1251.encode("<p>Биткоин</p>");
+
+        assert_eq!(
+            decode_html(&bytes, Some("text/html; charset=windows-1251")),
+            "<p>Биткоин</p>"
+        );
+    }
+
+    #[test]
+    fn test_decode_html_from_meta() {
+        let html = "<html><head><meta http-equiv=\"Content-Type\" content=\"text/html; charset=ISO-8859-1\"></head><body>café</body></html>";
+        let (bytes, _, _) = WINDOWS_1252.encode(html);
+
+        assert_eq!(decode_html(&bytes, Some("text/html")), html);
+    }
+
+    #[test]
+    fn test_detect_encoding_fallbacks() {
+        assert_eq!(detect_encoding("żółw".as_bytes(), None), UTF_8);
+        assert_eq!(
+            detect_encoding(b"\xFF\xFEa\x00", Some("text/html; charset=utf-8")),
+            UTF_16LE
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+const ZONED_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S%z",
+    "%Y-%m-%d %H:%M:%S %z",
+    "%d %b %Y %H:%M:%S %z",
+    "%a %b %d %H:%M:%S %z %Y",
+];
+
+const NAIVE_FORMATS: [&str; 7] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%d %b %Y %H:%M:%S",
+    "%a, %d %b %Y %H:%M:%S",
+    "%d.%m.%Y %H:%M:%S",
+];
+
+const DATE_FORMATS: [&str; 5] = ["%Y-%m-%d", "%Y/%m/%d", "%d %b %Y", "%B %d, %Y", "%d.%m.%Y"];
+
+/// Parses a feed publication date.
+///
+/// RFC 2822 is tried first, then RFC 3339 / ISO-8601 and common non-standard formats.
+/// Dates without a time zone are taken as UTC, dates without a time as midnight UTC.
+///
+/// # Arguments
+///
+/// * `value` - The raw date string.
+///
+/// # Returns
+///
+/// The parsed date or `None` if no format matched.
+pub fn parse_publication_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc2822(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+
+    // Feeds often publish a week day that does not match the date, chrono rejects those.
+    if let Some((_, rest)) = value.split_once(", ")
+        && let Ok(dt) = DateTime::parse_from_rfc2822(rest)
+    {
+        return Some(dt.with_timezone(&Utc));
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+
+    let zoned = value
+        .strip_suffix(" UTC")
+        .or_else(|| value.strip_suffix(" GMT"))
+        .or_else(|| value.strip_suffix('Z'))
+        .map(|value| format!("{value} +0000"))
+        .unwrap_or_else(|| value.to_string());
+
+    ZONED_FORMATS
+        .iter()
+        .find_map(|format| DateTime::parse_from_str(&zoned, format).ok())
+        .map(|dt| dt.with_timezone(&Utc))
+        .or_else(|| {
+            NAIVE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+                .map(|dt| dt.and_utc())
+        })
+        .or_else(|| {
+            DATE_FORMATS
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|dt| dt.and_utc())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_publication_date() {
+        let expected = DateTime::parse_from_rfc3339("2024-05-21T14:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        for value in [
+            "Tue, 21 May 2024 14:00:00 GMT",
+            "Mon, 21 May 2024 14:00:00 +0000",
+            "Tue, 21 May 2024 16:00:00 +0200",
+            "2024-05-21T14:00:00Z",
+            "2024-05-21T16:00:00+02:00",
+            "2024-05-21 14:00:00",
+            "2024-05-21 14:00:00 UTC",
+            "2024-05-21T14:00:00.000",
+            "21 May 2024 14:00:00",
+            "Tue May 21 14:00:00 +0000 2024",
+        ] {
+            assert_eq!(parse_publication_date(value), Some(expected), "{value}");
+        }
+    }
+
+    #[test]
+    fn test_parse_publication_date_fallbacks() {
+        let midnight = DateTime::parse_from_rfc3339("2024-05-21T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(parse_publication_date("2024-05-21"), Some(midnight));
+        assert_eq!(parse_publication_date("May 21, 2024"), Some(midnight));
+        assert_eq!(parse_publication_date(""), None);
+        assert_eq!(parse_publication_date("yesterday"), None);
+    }
+}
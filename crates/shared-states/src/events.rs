@@ -0,0 +1,387 @@
+use crate::analysis::Embedding;
+use crate::feeds::{
+    RSS_FEED_ALERTS_QUEUE_NAME, RSS_FEED_CHANGES_QUEUE_NAME, RssFeedAlert, RssFeedChange,
+};
+use crate::rss::{
+    RSS_CHANNEL_QUEUE_NAME, RSS_QUEUE_NAME, RSS_RETRY_QUEUE_NAME, RssChannelInfo, RssItem,
+};
+use crate::telegram::{TELEGRAM_QUEUE_NAME, TelegramMessage};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Version of the message schema written by this build.
+///
+/// Bump it on breaking changes of any event type and keep decoding of the older versions working.
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+pub const SENTIMENT_QUEUE_NAME: &str = "sentiment_results";
+pub const EMBEDDING_QUEUE_NAME: &str = "embedding_results";
+/// Subject the api-server requests embeddings of search queries on, replied with `EmbeddingResponse`.
+pub const EMBEDDING_REQUEST_SUBJECT: &str = "embedding_request";
+pub const WEBHOOK_QUEUE_NAME: &str = "webhook_events";
+
+/// EventError describes why a queue message could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EventError {
+    #[error("Unsupported event schema version {version}, supported up to {supported}")]
+    UnsupportedVersion { version: u32, supported: u32 },
+}
+
+/// Event is a message type exchanged between the workers and the api-server.
+pub trait Event: Serialize + DeserializeOwned {
+    /// Queue subject the event is published on.
+    const SUBJECT: &'static str;
+}
+
+impl Event for RssItem {
+    const SUBJECT: &'static str = RSS_QUEUE_NAME;
+}
+
+impl Event for RssItemRetry {
+    const SUBJECT: &'static str = RSS_RETRY_QUEUE_NAME;
+}
+
+impl Event for RssChannelInfo {
+    const SUBJECT: &'static str = RSS_CHANNEL_QUEUE_NAME;
+}
+
+impl Event for RssFeedChange {
+    const SUBJECT: &'static str = RSS_FEED_CHANGES_QUEUE_NAME;
+}
+
+impl Event for RssFeedAlert {
+    const SUBJECT: &'static str = RSS_FEED_ALERTS_QUEUE_NAME;
+}
+
+impl Event for SentimentResult {
+    const SUBJECT: &'static str = SENTIMENT_QUEUE_NAME;
+}
+
+impl Event for EmbeddingResult {
+    const SUBJECT: &'static str = EMBEDDING_QUEUE_NAME;
+}
+
+impl Event for TelegramMessage {
+    const SUBJECT: &'static str = TELEGRAM_QUEUE_NAME;
+}
+
+impl Event for WebhookEvent {
+    const SUBJECT: &'static str = WEBHOOK_QUEUE_NAME;
+}
+
+/// EventEnvelope wraps an event with the schema version it was written with.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EventEnvelope<T> {
+    pub version: u32,
+    pub payload: T,
+}
+
+impl<T: Event> EventEnvelope<T> {
+    /// Wraps the event with the current schema version.
+    pub fn new(payload: T) -> Self {
+        Self {
+            version: EVENT_SCHEMA_VERSION,
+            payload,
+        }
+    }
+}
+
+/// Decodes an event from a queue message payload.
+///
+/// Payloads published before versioning was introduced carry the bare event and are accepted as
+/// version `0`.
+///
+/// # Arguments
+///
+/// * `bytes` - The message payload.
+///
+/// # Returns
+///
+/// A `Result` containing the event, or an `anyhow::Error` if the payload is malformed.
+/// Payloads of a newer schema are reported with an `EventError` that can be downcast from the error.
+pub fn decode_event<T: Event>(bytes: &[u8]) -> Result<T> {
+    let value: Value = serde_json::from_slice(bytes)?;
+    let is_envelope = value.as_object().is_some_and(|object| {
+        object.len() == 2 && object.contains_key("version") && object.contains_key("payload")
+    });
+    if !is_envelope {
+        return Ok(serde_json::from_value(value)?);
+    }
+
+    let envelope: EventEnvelope<T> = serde_json::from_value(value)?;
+    if envelope.version > EVENT_SCHEMA_VERSION {
+        return Err(EventError::UnsupportedVersion {
+            version: envelope.version,
+            supported: EVENT_SCHEMA_VERSION,
+        }
+        .into());
+    }
+
+    Ok(envelope.payload)
+}
+
+/// SentimentLabel is the overall polarity of an analyzed text.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum SentimentLabel {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+/// SentimentResult represents the sentiment analysis of an RSS item.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SentimentResult {
+    /// Hash of the analyzed RSS item.
+    pub item_hash: String,
+    pub label: SentimentLabel,
+    /// Confidence of the label in range `0.0..=1.0`.
+    pub score: f32,
+    /// Name of the model that produced the result.
+    pub model: String,
+    pub analyzed_timestamp: i64,
+}
+
+/// EmbeddingResult represents the embedding of an RSS item produced by the embedding pipeline.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmbeddingResult {
+    /// Hash of the embedded RSS item.
+    pub item_hash: String,
+    /// Unit length vector of `EMBEDDING_DIMENSION` values.
+    pub embedding: Embedding,
+    /// Name of the model that produced the embedding.
+    pub model: String,
+    pub embedded_timestamp: i64,
+}
+
+/// EmbeddingRequest asks the embedding pipeline to embed texts, such as search queries.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EmbeddingRequest {
+    pub texts: Vec<String>,
+}
+
+/// EmbeddingResponse carries the embeddings of the texts of an `EmbeddingRequest`, in order.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EmbeddingResponse {
+    pub embeddings: Vec<Embedding>,
+    /// Name of the model that produced the embeddings.
+    pub model: String,
+}
+
+/// WebhookEvent represents a webhook call relayed through the queue.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebhookEvent {
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub source: String,
+    pub data: Value,
+    pub timestamp: DateTime<Utc>,
+    pub retry_count: u32,
+    pub max_retries: u32,
+}
+
+impl WebhookEvent {
+    pub fn new(event_id: Uuid, event_type: String, source: String, data: Value) -> Self {
+        Self {
+            event_id,
+            event_type,
+            source,
+            data,
+            timestamp: Utc::now(),
+            retry_count: 0,
+            max_retries: 3,
+        }
+    }
+
+    pub fn increment_retry(&mut self) {
+        self.retry_count += 1;
+    }
+
+    pub fn should_retry(&self) -> bool {
+        self.retry_count < self.max_retries
+    }
+}
+
+/// Delay of the first retry of an item, doubled on every next retry.
+const RSS_ITEM_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// RssItemRetry is an RSS item the rss-worker failed to publish, sent to the retry subject and
+/// published again after a delay.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RssItemRetry {
+    pub item: RssItem,
+    /// URL of the feed the item was fetched from.
+    pub feed_url: String,
+    pub failed_timestamp: i64,
+    pub retry_count: u32,
+    pub max_retries: u32,
+}
+
+impl RssItemRetry {
+    pub fn new(item: RssItem, feed_url: String) -> Self {
+        Self {
+            item,
+            feed_url,
+            failed_timestamp: Utc::now().timestamp_millis(),
+            retry_count: 0,
+            max_retries: 3,
+        }
+    }
+
+    pub fn increment_retry(&mut self) {
+        self.retry_count += 1;
+    }
+
+    pub fn should_retry(&self) -> bool {
+        self.retry_count < self.max_retries
+    }
+
+    /// Time to wait before the next attempt to publish the item.
+    pub fn delay(&self) -> Duration {
+        RSS_ITEM_RETRY_DELAY * 2u32.saturating_pow(self.retry_count.min(10))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY_RSS_ITEM: &str = r#"{
+        "hash": "abc",
+        "title": "Ether ETFs approved",
+        "link": "https://news.example.com/eth-etf",
+        "description": "The SEC approved spot ether ETFs.",
+        "published_timestamp": 1716300000000,
+        "fetched_timestamp": 1716300060000,
+        "comments_url": "",
+        "category": "Markets, ETF",
+        "author": "Jane Doe",
+        "article": ""
+    }"#;
+
+    #[test]
+    fn test_decode_legacy_rss_item() {
+        let item: RssItem = decode_event(LEGACY_RSS_ITEM.as_bytes()).unwrap();
+
+        assert_eq!(item.hash, "abc");
+        assert_eq!(item.category, vec!["markets", "etf"]);
+        assert_eq!(item.language, "");
+        assert_eq!(item.preview_title, None);
+        assert_eq!(item.simhash, 0);
+        assert_eq!(item.author.name, "Jane Doe");
+    }
+
+    #[test]
+    fn test_event_envelope_round_trip() {
+        let item: RssItem = serde_json::from_str(LEGACY_RSS_ITEM).unwrap();
+        let sentiment = SentimentResult {
+            item_hash: item.hash.clone(),
+            label: SentimentLabel::Positive,
+            score: 0.75,
+            model: "distilbert-sst2".to_string(),
+            analyzed_timestamp: 1716300120000,
+        };
+
+        let bytes = serde_json::to_vec(&EventEnvelope::new(item.clone())).unwrap();
+        assert_eq!(decode_event::<RssItem>(&bytes).unwrap(), item);
+
+        let bytes = serde_json::to_vec(&EventEnvelope::new(sentiment.clone())).unwrap();
+        assert!(String::from_utf8_lossy(&bytes).contains(r#""label":"positive""#));
+        assert_eq!(decode_event::<SentimentResult>(&bytes).unwrap(), sentiment);
+
+        let embedding = EmbeddingResult {
+            item_hash: item.hash.clone(),
+            embedding: Embedding {
+                vector: vec![0.6, 0.8],
+            },
+            model: "all-MiniLM-L6-v2".to_string(),
+            embedded_timestamp: 1716300120000,
+        };
+        let bytes = serde_json::to_vec(&EventEnvelope::new(embedding.clone())).unwrap();
+        assert_eq!(decode_event::<EmbeddingResult>(&bytes).unwrap(), embedding);
+    }
+
+    #[test]
+    fn test_decode_unsupported_version() {
+        let message = TelegramMessage {
+            chat_id: -1001234567890,
+            channel: "crypto_news".to_string(),
+            message_id: 42,
+            text: "BTC breaks 70k".to_string(),
+            author: String::new(),
+            link: "https://t.me/crypto_news/42".to_string(),
+            published_timestamp: 1716300000000,
+            fetched_timestamp: 1716300060000,
+            ..Default::default()
+        };
+        let envelope = EventEnvelope {
+            version: EVENT_SCHEMA_VERSION + 1,
+            payload: message,
+        };
+
+        let err =
+            decode_event::<TelegramMessage>(&serde_json::to_vec(&envelope).unwrap()).unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<EventError>(),
+            Some(&EventError::UnsupportedVersion {
+                version: EVENT_SCHEMA_VERSION + 1,
+                supported: EVENT_SCHEMA_VERSION
+            })
+        );
+    }
+
+    #[test]
+    fn test_webhook_event() {
+        let event_id = Uuid::new_v4();
+        let mut event = WebhookEvent::new(
+            event_id,
+            "user.created".to_string(),
+            "api.example.com".to_string(),
+            serde_json::json!({"user_id": 123}),
+        );
+
+        assert_eq!(event.event_id, event_id);
+        assert_eq!(event.retry_count, 0);
+        assert!(event.should_retry());
+
+        event.increment_retry();
+        assert_eq!(event.retry_count, 1);
+        assert!(event.should_retry());
+
+        // Exceed max retries
+        event.retry_count = 5;
+        assert!(!event.should_retry());
+
+        let bytes = serde_json::to_vec(&EventEnvelope::new(event.clone())).unwrap();
+        assert_eq!(decode_event::<WebhookEvent>(&bytes).unwrap(), event);
+    }
+
+    #[test]
+    fn test_rss_item_retry() {
+        let item = RssItem {
+            hash: "abc".to_string(),
+            ..Default::default()
+        };
+        let mut retry = RssItemRetry::new(item, "https://news.example.com/rss".to_string());
+
+        assert!(retry.should_retry());
+        assert_eq!(retry.delay(), Duration::from_secs(30));
+
+        retry.increment_retry();
+        retry.increment_retry();
+        assert_eq!(retry.delay(), Duration::from_secs(120));
+        assert!(retry.should_retry());
+
+        retry.increment_retry();
+        assert!(!retry.should_retry());
+
+        let bytes = serde_json::to_vec(&EventEnvelope::new(retry.clone())).unwrap();
+        assert_eq!(decode_event::<RssItemRetry>(&bytes).unwrap(), retry);
+    }
+}
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::prelude::FromRow;
+
+pub const RSS_FEED_CHANGES_QUEUE_NAME: &str = "rss_feed_changes";
+pub const RSS_FEED_ALERTS_QUEUE_NAME: &str = "rss_feed_alerts";
+/// Subject the rss-worker requests the list of enabled feeds on, replied with `Vec<RssFeedSource>`.
+pub const RSS_FEEDS_REQUEST_SUBJECT: &str = "rss_feeds_request";
+/// NATS key-value bucket the rss-worker persists the state of the feeds in.
+pub const RSS_FEED_STATE_BUCKET: &str = "rss_feed_state";
+/// Subject prefix of the rss-worker, it replies on the `<prefix>.health` subject with
+/// `RssWorkerHealth`.
+pub const RSS_WORKER_SUBJECT_PREFIX: &str = "rss_worker";
+
+/// RssFeedSource is a feed the rss-worker polls for new items.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, PartialEq, Eq, Hash)]
+pub struct RssFeedSource {
+    /// Hash of the feed URL, equal to the hash of its `RssChannelInfo`.
+    pub hash: String,
+    pub url: String,
+    /// Seconds between polls of the feed.
+    pub interval_seconds: i64,
+    /// Disabled feeds are kept but not polled.
+    pub enabled: bool,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+impl RssFeedSource {
+    /// Creates a new enabled feed source.
+    ///
+    /// # Arguments
+    /// * `url` - URL of the feed, surrounding whitespace is ignored.
+    /// * `interval_seconds` - Seconds between polls of the feed.
+    /// * `timestamp` - Creation time in Unix milliseconds.
+    pub fn new(url: &str, interval_seconds: i64, timestamp: i64) -> Self {
+        let url = url.trim();
+        Self {
+            hash: feed_hash(url),
+            url: url.to_string(),
+            interval_seconds,
+            enabled: true,
+            created_at: timestamp,
+            updated_at: timestamp,
+        }
+    }
+}
+
+/// RssFeedChange notifies the rss-worker that a feed was added, updated or removed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct RssFeedChange {
+    pub feed: RssFeedSource,
+    /// True if the feed was deleted, the worker stops polling it.
+    #[serde(default)]
+    pub removed: bool,
+}
+
+/// DeadFeedReason is why the rss-worker considers a feed dead.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadFeedReason {
+    /// The feed failed to be fetched or parsed too many times in a row.
+    Failing,
+    /// The feed published no new items for too long.
+    Stale,
+}
+
+/// RssFeedAlert notifies operators that a feed is dead, it has likely moved or shut down. The
+/// rss-worker keeps polling dead feeds at the longest interval.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct RssFeedAlert {
+    pub url: String,
+    pub reason: DeadFeedReason,
+    /// Number of polls failed in a row.
+    pub failures: u32,
+    /// Time the feed was marked dead in Unix milliseconds.
+    pub alerted_timestamp: i64,
+}
+
+/// WorkerStatus is the overall health of a worker.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkerStatus {
+    Healthy,
+    /// Some feeds fail to be polled.
+    Degraded,
+    /// The queue is disconnected or every feed fails to be polled.
+    Unhealthy,
+}
+
+/// FeedError is a feed whose latest polls failed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct FeedError {
+    pub url: String,
+    /// Number of polls failed in a row.
+    pub failures: u32,
+}
+
+/// RssWorkerHealth is the reply of the rss-worker to a health request.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct RssWorkerHealth {
+    pub status: WorkerStatus,
+    /// Time the latest successful poll of any feed finished in Unix milliseconds, None before the
+    /// first one.
+    pub last_successful_poll_timestamp: Option<i64>,
+    /// Number of scheduled feeds.
+    pub feeds: usize,
+    pub feeds_in_error: Vec<FeedError>,
+    pub queue_connected: bool,
+    /// Number of published items and retries waiting for the ack of the JetStream stream.
+    #[serde(default)]
+    pub unacked_items: usize,
+}
+
+impl RssWorkerHealth {
+    /// Creates the health report, the status is derived from the queue connection and the failing
+    /// feeds.
+    ///
+    /// # Arguments
+    /// * `last_successful_poll_timestamp` - Time the latest successful poll finished.
+    /// * `feeds` - Number of scheduled feeds.
+    /// * `feeds_in_error` - Feeds whose latest polls failed.
+    /// * `queue_connected` - Whether the queue client is connected.
+    pub fn new(
+        last_successful_poll_timestamp: Option<i64>,
+        feeds: usize,
+        feeds_in_error: Vec<FeedError>,
+        queue_connected: bool,
+    ) -> Self {
+        let status = if !queue_connected || (feeds > 0 && feeds_in_error.len() >= feeds) {
+            WorkerStatus::Unhealthy
+        } else if !feeds_in_error.is_empty() {
+            WorkerStatus::Degraded
+        } else {
+            WorkerStatus::Healthy
+        };
+        Self {
+            status,
+            last_successful_poll_timestamp,
+            feeds,
+            feeds_in_error,
+            queue_connected,
+            unacked_items: 0,
+        }
+    }
+
+    /// Sets the number of published items waiting for their ack, the worker is degraded while
+    /// there are any.
+    pub fn with_unacked_items(mut self, unacked_items: usize) -> Self {
+        self.unacked_items = unacked_items;
+        if unacked_items > 0 && self.status == WorkerStatus::Healthy {
+            self.status = WorkerStatus::Degraded;
+        }
+        self
+    }
+}
+
+/// Hash identifying the feed with the URL.
+pub fn feed_hash(url: &str) -> String {
+    hex::encode(Sha256::digest(url.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rss_feed_source_new() {
+        let feed = RssFeedSource::new(" https://news.example.com/rss ", 300, 1716300000000);
+
+        assert_eq!(feed.url, "https://news.example.com/rss");
+        assert_eq!(feed.hash, feed_hash("https://news.example.com/rss"));
+        assert_eq!(feed.hash.len(), 64);
+        assert!(feed.enabled);
+        assert_eq!(feed.created_at, feed.updated_at);
+    }
+
+    #[test]
+    fn test_rss_worker_health_status() {
+        let failing = || {
+            vec![FeedError {
+                url: "https://news.example.com/rss".to_string(),
+                failures: 3,
+            }]
+        };
+
+        let health = RssWorkerHealth::new(Some(1716300000000), 2, Vec::new(), true);
+        assert_eq!(health.status, WorkerStatus::Healthy);
+        assert_eq!(
+            RssWorkerHealth::new(None, 2, failing(), true).status,
+            WorkerStatus::Degraded
+        );
+        assert_eq!(
+            RssWorkerHealth::new(None, 1, failing(), true).status,
+            WorkerStatus::Unhealthy
+        );
+        assert_eq!(
+            RssWorkerHealth::new(None, 2, Vec::new(), false).status,
+            WorkerStatus::Unhealthy
+        );
+        assert_eq!(
+            RssWorkerHealth::new(None, 2, Vec::new(), true)
+                .with_unacked_items(3)
+                .status,
+            WorkerStatus::Degraded
+        );
+        assert_eq!(
+            RssWorkerHealth::new(None, 2, Vec::new(), false)
+                .with_unacked_items(3)
+                .status,
+            WorkerStatus::Unhealthy
+        );
+
+        let value = serde_json::to_value(&health).unwrap();
+        assert_eq!(value["status"], "healthy");
+        assert_eq!(value["last_successful_poll_timestamp"], 1716300000000i64);
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::{Result, anyhow};
+use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerError};
+use reqwest::{Client, Proxy, StatusCode, Url, header::CONTENT_TYPE, redirect::Policy};
+use std::{sync::Arc, time::Duration};
+use tokio::time::sleep;
+
+/// Default User-Agent sent with article requests.
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (compatible; SemanticMachineBot/1.0; +https://github.com/bartossh/Semantic-Machine)";
+
+/// FetchedPage represents a raw HTTP response of a fetched page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchedPage {
+    pub url: String,
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: Vec<u8>,
+}
+
+impl FetchedPage {
+    /// Checks if the response status is 2xx.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// ArticleFetcher represents an entity that fetches pages for article extraction.
+pub trait ArticleFetcher: Send + Sync {
+    /// Fetches the page under the URL.
+    ///
+    /// * `url` - URL of the page.
+    ///
+    /// # Returns
+    /// * The fetched page with any HTTP status or error if the page could not be fetched at all.
+    fn fetch(&self, url: &Url) -> impl Future<Output = Result<FetchedPage>> + Send;
+}
+
+/// HttpFetcherConfig configures the HTTP client used by `HttpArticleFetcher`.
+#[derive(Debug, Clone)]
+pub struct HttpFetcherConfig {
+    /// Total request timeout.
+    pub timeout: Duration,
+    /// Connection establishment timeout.
+    pub connect_timeout: Duration,
+    /// Maximum number of followed redirects.
+    pub max_redirects: usize,
+    /// Number of retries of transient failures (connection errors, timeouts, 429 and 5xx).
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled on every next attempt.
+    pub retry_backoff: Duration,
+    /// User-Agent header value.
+    pub user_agent: String,
+    /// URL of the proxy all requests go through, e.g. `http://proxy.internal:3128`.
+    pub proxy: Option<String>,
+    /// Circuit breaker of outgoing requests, only connection errors and timeouts count as failures.
+    pub circuit_breaker: CircuitBreakerConfig,
+}
+
+impl Default for HttpFetcherConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(20),
+            connect_timeout: Duration::from_secs(5),
+            max_redirects: 5,
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(500),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            proxy: None,
+            // Pages come from many hosts, a single unreachable host must not open the circuit.
+            circuit_breaker: CircuitBreakerConfig {
+                failure_threshold: 20,
+                open_duration: Duration::from_secs(30),
+            },
+        }
+    }
+}
+
+/// HttpArticleFetcher fetches pages with a configured reqwest client.
+#[derive(Debug, Clone)]
+pub struct HttpArticleFetcher {
+    client: Client,
+    config: HttpFetcherConfig,
+    breaker: Arc<CircuitBreaker>,
+}
+
+impl HttpArticleFetcher {
+    /// Creates a new fetcher building the HTTP client from the configuration.
+    ///
+    /// # Arguments
+    /// * `config` - The fetcher configuration.
+    ///
+    /// # Returns
+    /// A new fetcher or error if the HTTP client could not be built or the proxy URL is invalid.
+    pub fn new(config: HttpFetcherConfig) -> Result<Self> {
+        let mut builder = Client::builder()
+            .timeout(config.timeout)
+            .connect_timeout(config.connect_timeout)
+            .redirect(Policy::limited(config.max_redirects))
+            .user_agent(config.user_agent.clone());
+        if let Some(proxy) = &config.proxy {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+        let client = builder.build()?;
+
+        Ok(Self::with_client(client, config))
+    }
+
+    /// Creates a new fetcher using an already configured HTTP client.
+    ///
+    /// Only the timeout, retry and circuit breaker settings of the configuration are used, the
+    /// timeout is set per request.
+    pub fn with_client(client: Client, config: HttpFetcherConfig) -> Self {
+        let breaker = Arc::new(CircuitBreaker::new("external_http", config.circuit_breaker));
+        Self {
+            client,
+            config,
+            breaker,
+        }
+    }
+
+    /// Circuit breaker guarding the requests, requests fail right away while it is open.
+    pub fn circuit_breaker(&self) -> Arc<CircuitBreaker> {
+        self.breaker.clone()
+    }
+
+    async fn fetch_once(&self, url: &Url) -> reqwest::Result<FetchedPage> {
+        let response = self
+            .client
+            .get(url.clone())
+            .timeout(self.config.timeout)
+            .send()
+            .await?;
+        let status = response.status().as_u16();
+        let final_url = response.url().to_string();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = response.bytes().await?.to_vec();
+
+        Ok(FetchedPage {
+            url: final_url,
+            status,
+            content_type,
+            body,
+        })
+    }
+}
+
+impl ArticleFetcher for HttpArticleFetcher {
+    async fn fetch(&self, url: &Url) -> Result<FetchedPage> {
+        let mut backoff = self.config.retry_backoff;
+        let mut attempt = 0;
+
+        loop {
+            let exhausted = attempt >= self.config.max_retries;
+            let result = self
+                .breaker
+                .call_filtered(self.fetch_once(url), |e| e.is_timeout() || e.is_connect())
+                .await;
+            match result {
+                Ok(page) if exhausted || !is_retryable_status(page.status) => return Ok(page),
+                Err(CircuitBreakerError::Open(_)) => {
+                    return Err(anyhow!(
+                        "Failed to fetch ( {url} ): circuit breaker is open"
+                    ));
+                }
+                Err(CircuitBreakerError::Inner(e)) if exhausted || !is_retryable_error(&e) => {
+                    return Err(anyhow!("Failed to fetch ( {url} ): {e}"));
+                }
+                _ => (),
+            }
+
+            attempt += 1;
+            sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+}
+
+fn is_retryable_status(status: u16) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS.as_u16() || (500..600).contains(&status)
+}
+
+fn is_retryable_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.is_request() || error.is_body()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_retryable_status() {
+        assert!(is_retryable_status(429));
+        assert!(is_retryable_status(503));
+        assert!(!is_retryable_status(404));
+        assert!(!is_retryable_status(200));
+    }
+
+    #[test]
+    fn test_http_article_fetcher_new() {
+        assert!(HttpArticleFetcher::new(HttpFetcherConfig::default()).is_ok());
+        assert!(
+            HttpArticleFetcher::new(HttpFetcherConfig {
+                user_agent: "FeedReader/2.0".to_string(),
+                proxy: Some("http://127.0.0.1:3128".to_string()),
+                ..Default::default()
+            })
+            .is_ok()
+        );
+    }
+}
@@ -0,0 +1,43 @@
+use whatlang::detect;
+
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// Detects the language of the text.
+///
+/// # Arguments
+///
+/// * `text` - Text to detect the language of.
+///
+/// # Returns
+///
+/// ISO 639-3 language code (e.g. `eng`, `rus`, `cmn`), or `None` if the language cannot be detected with sufficient confidence.
+pub fn detect_language(text: &str) -> Option<String> {
+    let info = detect(text)?;
+    if info.confidence() < MIN_CONFIDENCE {
+        return None;
+    }
+
+    Some(info.lang().code().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language(
+                "Bitcoin price climbs above resistance as institutional investors return to the market."
+            ),
+            Some("eng".to_string())
+        );
+        assert_eq!(
+            detect_language(
+                "Биткоин вырос выше уровня сопротивления, поскольку институциональные инвесторы возвращаются на рынок."
+            ),
+            Some("rus".to_string())
+        );
+        assert_eq!(detect_language(""), None);
+    }
+}
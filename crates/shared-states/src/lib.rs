@@ -1,5 +1,48 @@
+mod analysis;
 mod article;
+mod author;
+mod canonical;
+mod category;
+mod charset;
+mod dates;
+mod events;
+mod feeds;
+mod fetcher;
+mod language;
+mod markdown;
+mod metadata;
+mod network;
+mod opml;
+mod pdf;
+mod readability;
+mod robots;
 mod rss;
+mod sanitize;
+mod simhash;
+mod sitemap;
+mod syndication;
+mod telegram;
 
+pub use analysis::*;
 pub use article::*;
+pub use author::*;
+pub use canonical::*;
+pub use category::*;
+pub use charset::*;
+pub use dates::*;
+pub use events::*;
+pub use feeds::*;
+pub use fetcher::*;
+pub use language::*;
+pub use markdown::*;
+pub use metadata::*;
+pub use network::*;
+pub use opml::*;
+pub use pdf::*;
+pub use robots::*;
 pub use rss::*;
+pub use sanitize::*;
+pub use simhash::*;
+pub use sitemap::*;
+pub use syndication::*;
+pub use telegram::*;
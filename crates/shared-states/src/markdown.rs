@@ -0,0 +1,286 @@
+use regex::Regex;
+use reqwest::Url;
+use scraper::{ElementRef, Node};
+use std::sync::LazyLock;
+
+static LIST_ITEM_LINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^ +(- |\d+\. )").expect("valid list item regex"));
+
+const LIST_INDENT: &str = "  ";
+const CODE_FENCE: &str = "```";
+
+struct Context<'a> {
+    base_url: Option<&'a Url>,
+    list_depth: usize,
+}
+
+/// Converts the element into Markdown keeping headings, paragraphs, lists, links, emphasis,
+/// quotes and code.
+///
+/// # Arguments
+///
+/// * `element` - The element to convert, usually the content root of an article.
+/// * `base_url` - URL of the page used to resolve relative links.
+///
+/// # Returns
+///
+/// The Markdown text with blocks separated by a single blank line.
+pub fn element_to_markdown(element: &ElementRef, base_url: Option<&Url>) -> String {
+    let mut context = Context {
+        base_url,
+        list_depth: 0,
+    };
+
+    tidy(&render_children(element, &mut context))
+}
+
+fn render_children(element: &ElementRef, context: &mut Context) -> String {
+    element
+        .children()
+        .map(|child| match child.value() {
+            Node::Text(text) => collapse_whitespace(text),
+            Node::Element(_) => ElementRef::wrap(child)
+                .map(|child| render_element(&child, context))
+                .unwrap_or_default(),
+            _ => String::new(),
+        })
+        .collect()
+}
+
+fn render_element(element: &ElementRef, context: &mut Context) -> String {
+    let name = element.value().name();
+    match name {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+            let level = name[1..].parse::<usize>().unwrap_or(1);
+            let text = render_children(element, context);
+            block(&format!("{} {}", "#".repeat(level), text.trim()))
+        }
+        "p" | "div" | "section" | "article" | "main" | "header" | "figure" | "figcaption"
+        | "table" | "tr" => block(render_children(element, context).trim()),
+        "br" => "\n".to_string(),
+        "hr" => block("---"),
+        "ul" | "ol" => render_list(element, name == "ol", context),
+        "li" => format!("\n- {}\n", render_children(element, context).trim()),
+        "a" => render_link(element, context),
+        "strong" | "b" => wrap_inline(&render_children(element, context), "**"),
+        "em" | "i" => wrap_inline(&render_children(element, context), "*"),
+        "code" => wrap_inline(&element.text().collect::<String>(), "`"),
+        "pre" => {
+            let code = element.text().collect::<String>();
+            block(&format!(
+                "{CODE_FENCE}\n{}\n{CODE_FENCE}",
+                code.trim_matches('\n')
+            ))
+        }
+        "blockquote" => {
+            let quote = tidy(&render_children(element, context));
+            let quoted = quote
+                .lines()
+                .map(|line| format!("> {line}").trim_end().to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            block(&quoted)
+        }
+        "img" | "script" | "style" | "noscript" | "svg" | "iframe" => String::new(),
+        _ => render_children(element, context),
+    }
+}
+
+fn render_list(element: &ElementRef, ordered: bool, context: &mut Context) -> String {
+    let indent = LIST_INDENT.repeat(context.list_depth);
+    context.list_depth += 1;
+
+    let mut lines = Vec::new();
+    let items = element
+        .children()
+        .filter_map(ElementRef::wrap)
+        .filter(|child| child.value().name() == "li");
+    for (i, item) in items.enumerate() {
+        let marker = if ordered {
+            format!("{}.", i + 1)
+        } else {
+            "-".to_string()
+        };
+        let content = render_children(&item, context);
+        let mut content_lines = content
+            .split('\n')
+            .map(str::trim_end)
+            .filter(|line| !line.trim().is_empty());
+
+        let Some(first) = content_lines.next() else {
+            continue;
+        };
+        lines.push(format!("{indent}{marker} {}", first.trim()));
+        lines.extend(content_lines.map(|line| {
+            if LIST_ITEM_LINE.is_match(line) {
+                line.to_string()
+            } else {
+                format!("{indent}{LIST_INDENT}{}", line.trim())
+            }
+        }));
+    }
+
+    context.list_depth -= 1;
+    if context.list_depth > 0 {
+        format!("\n{}\n", lines.join("\n"))
+    } else {
+        block(&lines.join("\n"))
+    }
+}
+
+fn render_link(element: &ElementRef, context: &mut Context) -> String {
+    let text = render_children(element, context);
+    let label = text.trim();
+    let href = element
+        .value()
+        .attr("href")
+        .map(str::trim)
+        .filter(|href| {
+            !href.is_empty() && !href.starts_with('#') && !href.starts_with("javascript:")
+        })
+        .and_then(|href| match context.base_url {
+            Some(base) => base.join(href).ok().map(|url| url.to_string()),
+            None => Some(href.to_string()),
+        });
+
+    match href {
+        Some(href) if !label.is_empty() => {
+            let leading = if text.starts_with(' ') { " " } else { "" };
+            let trailing = if text.ends_with(' ') { " " } else { "" };
+            format!("{leading}[{label}]({href}){trailing}")
+        }
+        _ => text,
+    }
+}
+
+fn wrap_inline(text: &str, marker: &str) -> String {
+    let content = text.trim();
+    if content.is_empty() {
+        return text.to_string();
+    }
+    let leading = if text.starts_with(char::is_whitespace) {
+        " "
+    } else {
+        ""
+    };
+    let trailing = if text.ends_with(char::is_whitespace) {
+        " "
+    } else {
+        ""
+    };
+
+    format!("{leading}{marker}{content}{marker}{trailing}")
+}
+
+fn block(content: &str) -> String {
+    if content.is_empty() {
+        return String::new();
+    }
+
+    format!("\n\n{content}\n\n")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_whitespace = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !in_whitespace {
+                collapsed.push(' ');
+            }
+            in_whitespace = true;
+        } else {
+            collapsed.push(c);
+            in_whitespace = false;
+        }
+    }
+
+    collapsed
+}
+
+fn tidy(markdown: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_code = false;
+
+    for line in markdown.split('\n') {
+        let line = if in_code || LIST_ITEM_LINE.is_match(line) {
+            line.trim_end().to_string()
+        } else {
+            line.trim().to_string()
+        };
+        if line.starts_with(CODE_FENCE) {
+            in_code = !in_code;
+        }
+
+        let blank = line.is_empty();
+        if blank && !in_code && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+
+    lines.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::{Html, Selector};
+
+    fn to_markdown(html: &str) -> String {
+        let fragment = Html::parse_fragment(html);
+        let selector = Selector::parse("article").unwrap();
+        let article = fragment.select(&selector).next().unwrap();
+        let base = Url::parse("https://news.example.com/markets/eth-etf").unwrap();
+
+        element_to_markdown(&article, Some(&base))
+    }
+
+    #[test]
+    fn test_element_to_markdown() {
+        let markdown = to_markdown(
+            r#"<article>
+                <h1>Ether ETFs   approved</h1>
+                <p>The <strong>SEC</strong> approved the <a href="/tags/etf">first spot ether</a>
+                   funds on <em>Tuesday</em>.</p>
+                <h2>What changes</h2>
+                <ul>
+                    <li>Trading starts soon</li>
+                    <li>Fees are <b>low</b>
+                        <ol><li>0.15% for one</li><li>0.25% for <a href="https://other.example.com/">another</a></li></ol>
+                    </li>
+                </ul>
+                <blockquote><p>A landmark day.</p><p>Really.</p></blockquote>
+                <pre><code>let price = 3800;
+let supply = 120;</code></pre>
+            </article>"#,
+        );
+
+        assert_eq!(
+            markdown,
+            "# Ether ETFs approved\n\n\
+             The **SEC** approved the [first spot ether](https://news.example.com/tags/etf) funds on *Tuesday*.\n\n\
+             ## What changes\n\n\
+             - Trading starts soon\n\
+             - Fees are **low**\n\
+             \x20 1. 0.15% for one\n\
+             \x20 2. 0.25% for [another](https://other.example.com/)\n\n\
+             > A landmark day.\n\
+             >\n\
+             > Really.\n\n\
+             ```\n\
+             let price = 3800;\n\
+             let supply = 120;\n\
+             ```"
+        );
+    }
+
+    #[test]
+    fn test_element_to_markdown_skips_empty_links() {
+        let markdown = to_markdown(
+            r##"<article><p>Read <a href="#comments">comments</a> or <a href="/x"><img src="a.png"></a>.</p></article>"##,
+        );
+
+        assert_eq!(markdown, "Read comments or .");
+    }
+}
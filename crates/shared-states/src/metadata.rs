@@ -0,0 +1,112 @@
+use chrono::DateTime;
+use scraper::{Html, Selector};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const TITLE_KEYS: [&str; 2] = ["og:title", "twitter:title"];
+const DESCRIPTION_KEYS: [&str; 3] = ["og:description", "twitter:description", "description"];
+const IMAGE_KEYS: [&str; 5] = [
+    "og:image:secure_url",
+    "og:image",
+    "og:image:url",
+    "twitter:image",
+    "twitter:image:src",
+];
+const PUBLISHED_KEYS: [&str; 4] = [
+    "article:published_time",
+    "og:published_time",
+    "published_time",
+    "datepublished",
+];
+
+/// ArticleMetadata holds OpenGraph / Twitter-card preview data of a page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct ArticleMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    /// Publication time in milliseconds since the Unix epoch.
+    pub published_timestamp: Option<i64>,
+}
+
+/// Extracts OpenGraph, Twitter-card and standard meta tags from the document.
+///
+/// # Arguments
+///
+/// * `document` - Parsed HTML document.
+///
+/// # Returns
+///
+/// Metadata with every field that was found on the page, OpenGraph values take precedence.
+pub fn extract_metadata(document: &Html) -> ArticleMetadata {
+    let Ok(selector) = Selector::parse("meta") else {
+        return ArticleMetadata::default();
+    };
+
+    let mut tags: HashMap<String, String> = HashMap::new();
+    for meta in document.select(&selector) {
+        let value = meta.value();
+        let Some(key) = value
+            .attr("property")
+            .or_else(|| value.attr("name"))
+            .or_else(|| value.attr("itemprop"))
+        else {
+            continue;
+        };
+        let content = value.attr("content").unwrap_or_default().trim();
+        if content.is_empty() {
+            continue;
+        }
+        tags.entry(key.trim().to_lowercase())
+            .or_insert_with(|| content.to_string());
+    }
+
+    let first = |keys: &[&str]| keys.iter().find_map(|key| tags.get(*key).cloned());
+
+    ArticleMetadata {
+        title: first(&TITLE_KEYS),
+        description: first(&DESCRIPTION_KEYS),
+        image: first(&IMAGE_KEYS),
+        published_timestamp: first(&PUBLISHED_KEYS)
+            .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+            .map(|dt| dt.timestamp_millis()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_metadata() {
+        let document = Html::parse_document(
+            r#"<html><head>
+                <meta name="description" content="Plain description">
+                <meta property="og:description" content="Open graph description">
+                <meta name="twitter:title" content="Twitter title">
+                <meta property="og:image" content="https://example.com/lead.jpg">
+                <meta property="article:published_time" content="2025-10-01T12:00:00+00:00">
+            </head><body></body></html>"#,
+        );
+
+        let metadata = extract_metadata(&document);
+
+        assert_eq!(metadata.title.as_deref(), Some("Twitter title"));
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("Open graph description")
+        );
+        assert_eq!(
+            metadata.image.as_deref(),
+            Some("https://example.com/lead.jpg")
+        );
+        assert_eq!(metadata.published_timestamp, Some(1_759_320_000_000));
+    }
+
+    #[test]
+    fn test_extract_metadata_empty() {
+        let document = Html::parse_document("<html><head></head><body></body></html>");
+
+        assert_eq!(extract_metadata(&document), ArticleMetadata::default());
+    }
+}
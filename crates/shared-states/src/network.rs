@@ -0,0 +1,465 @@
+use anyhow::{Result, anyhow};
+use reqwest::{
+    ClientBuilder, Response, StatusCode, Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect::Policy,
+};
+use std::{
+    error::Error,
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+};
+
+/// Checks the address is reachable on the public internet, loopback, private, link-local, shared,
+/// documentation and reserved ranges are not.
+///
+/// # Arguments
+///
+/// * `ip` - The address.
+///
+/// # Returns
+///
+/// True if the address is global.
+pub fn is_global_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_global_ipv4(ip),
+        IpAddr::V6(ip) => match embedded_ipv4(ip) {
+            Some(embedded) => is_global_ipv4(embedded),
+            None => is_global_ipv6(ip),
+        },
+    }
+}
+
+/// IPv4 address an IPv6 address reaches, for the forms translated or tunnelled to IPv4.
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let segments = ip.segments();
+    let [.., a, b, c, d] = ip.octets();
+    match segments {
+        // ::ffff:0:0/96 IPv4-mapped
+        [0, 0, 0, 0, 0, 0xffff, _, _] => Some(Ipv4Addr::new(a, b, c, d)),
+        // 64:ff9b::/96 NAT64
+        [0x64, 0xff9b, 0, 0, 0, 0, _, _] => Some(Ipv4Addr::new(a, b, c, d)),
+        // ::/96 deprecated IPv4-compatible, except the unspecified and loopback addresses
+        [0, 0, 0, 0, 0, 0, _, _] if !ip.is_unspecified() && !ip.is_loopback() => {
+            Some(Ipv4Addr::new(a, b, c, d))
+        }
+        // 2002::/16 6to4, the IPv4 address follows the prefix
+        [0x2002, high, low, ..] => Some(Ipv4Addr::from((u32::from(high) << 16) | u32::from(low))),
+        _ => None,
+    }
+}
+
+fn is_global_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // 0.0.0.0/8 "this network"
+        || a == 0
+        // 100.64.0.0/10 shared address space of carrier-grade NAT
+        || (a == 100 && (64..128).contains(&b))
+        // 192.0.0.0/24 IETF protocol assignments
+        || (a == 192 && b == 0 && c == 0)
+        // 198.18.0.0/15 benchmarking
+        || (a == 198 && (18..20).contains(&b))
+        // 240.0.0.0/4 reserved
+        || a >= 240)
+}
+
+fn is_global_ipv6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // fc00::/7 unique local
+        || (segments[0] & 0xfe00) == 0xfc00
+        // fe80::/10 link-local
+        || (segments[0] & 0xffc0) == 0xfe80
+        // 2001:db8::/32 documentation
+        || (segments[0] == 0x2001 && segments[1] == 0x0db8)
+        // 64:ff9b:1::/48 local-use IPv4/IPv6 translation
+        || (segments[0] == 0x64 && segments[1] == 0xff9b && segments[2] == 1))
+}
+
+/// Checks the URL is an http(s) URL whose host is a name or a global address, names are checked
+/// once they are resolved by [`PublicResolver`].
+///
+/// # Arguments
+///
+/// * `url` - The URL.
+///
+/// # Returns
+///
+/// An error naming why the URL must not be requested.
+pub fn check_public_url(url: &Url) -> Result<()> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(anyhow!("Unsupported scheme {}", url.scheme()));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| anyhow!("URL ( {url} ) has no host"))?;
+    // IPv6 hosts are bracketed, names are left to the resolver.
+    let Ok(ip) = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    else {
+        return Ok(());
+    };
+    if !is_global_ip(ip) {
+        return Err(anyhow!(
+            "URL ( {url} ) points to the non-public address {ip}"
+        ));
+    }
+    Ok(())
+}
+
+/// Checks the URL passes [`check_public_url`] and its host name, if it is not an address, resolves
+/// to global addresses only. Clients built with [`public_client_builder`] check the resolved
+/// addresses on every connection anyway, this is for rejecting URLs before they are stored.
+///
+/// # Arguments
+///
+/// * `url` - The URL.
+///
+/// # Returns
+///
+/// An error naming why the URL must not be requested, also if the host cannot be resolved.
+pub async fn check_public_destination(url: &Url) -> Result<()> {
+    check_public_url(url)?;
+    let host = url.host_str().unwrap_or_default();
+    if host.starts_with('[') || host.parse::<IpAddr>().is_ok() {
+        return Ok(());
+    }
+    let port = url.port_or_known_default().unwrap_or(0);
+    for addr in tokio::net::lookup_host((host, port)).await? {
+        if !is_global_ip(addr.ip()) {
+            return Err(anyhow!(
+                "Host {host} resolves to the non-public address {}",
+                addr.ip()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// PublicResolver resolves host names with the system resolver and fails for names resolving to
+/// any non-global address, so requests of a client using it never reach internal services, also
+/// after redirects.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|addr| !is_global_ip(addr.ip())) {
+                let e: Box<dyn Error + Send + Sync> = format!(
+                    "Host {host} resolves to the non-public address {}",
+                    addr.ip()
+                )
+                .into();
+                return Err(e);
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Redirect policy following at most `max_redirects` redirects, each one to a URL passing
+/// [`check_public_url`].
+///
+/// # Arguments
+///
+/// * `max_redirects` - Maximum number of followed redirects, none if zero.
+pub fn public_redirect_policy(max_redirects: usize) -> Policy {
+    Policy::custom(move |attempt| {
+        if attempt.previous().len() > max_redirects {
+            return attempt.error(format!("More than {max_redirects} redirects"));
+        }
+        match check_public_url(attempt.url()) {
+            Ok(()) => attempt.follow(),
+            Err(e) => attempt.error(e.to_string()),
+        }
+    })
+}
+
+/// Client builder connecting to public addresses only, with the [`PublicResolver`] and the
+/// [`public_redirect_policy`]. Request URLs still have to be checked with [`check_public_url`],
+/// addresses given as IP literals are not resolved. A proxy must not be set, its address would
+/// be checked instead of the address of the requested host.
+///
+/// # Arguments
+///
+/// * `max_redirects` - Maximum number of followed redirects, none if zero.
+pub fn public_client_builder(max_redirects: usize) -> ClientBuilder {
+    ClientBuilder::new()
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(public_redirect_policy(max_redirects))
+        .no_proxy()
+}
+
+/// HttpFailure describes why a single attempt of an HTTP request failed, e.g. of a feed fetch or
+/// a webhook delivery.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpFailure {
+    Timeout,
+    Connection(String),
+    Status(StatusCode),
+    /// The URL is invalid or points to a non-public address.
+    Forbidden(String),
+}
+
+impl HttpFailure {
+    /// Label of the failure in the `failure_reason` dimension of metrics.
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::Connection(_) => "connection",
+            Self::Status(status) if status.is_server_error() => "server_error",
+            Self::Status(_) => "client_error",
+            Self::Forbidden(_) => "forbidden_destination",
+        }
+    }
+
+    /// Client errors other than timeouts and rate limiting will not succeed on retry.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Timeout | Self::Connection(_) => true,
+            Self::Status(status) => {
+                status.is_server_error()
+                    || *status == StatusCode::TOO_MANY_REQUESTS
+                    || *status == StatusCode::REQUEST_TIMEOUT
+            }
+            Self::Forbidden(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for HttpFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "timed out"),
+            Self::Connection(e) => write!(f, "{e}"),
+            Self::Status(status) => write!(f, "got HTTP status {status}"),
+            Self::Forbidden(e) => write!(f, "forbidden destination: {e}"),
+        }
+    }
+}
+
+impl From<reqwest::Error> for HttpFailure {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Self::Timeout
+        } else {
+            Self::Connection(e.to_string())
+        }
+    }
+}
+
+/// Reads the body of the response, failing once it has more than `max_bytes` bytes. The declared
+/// length may be missing or wrong, so the body is limited while it is read.
+///
+/// # Arguments
+///
+/// * `response` - The response.
+/// * `max_bytes` - Largest accepted body.
+///
+/// # Returns
+///
+/// The body, or an error if it is too large or cannot be read.
+pub async fn read_body_capped(mut response: Response, max_bytes: usize) -> Result<Vec<u8>> {
+    if let Some(length) = response.content_length()
+        && length > max_bytes as u64
+    {
+        return Err(anyhow!("Body has {length} bytes, limit is {max_bytes}"));
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > max_bytes {
+            return Err(anyhow!("Body has more than {max_bytes} bytes"));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
+}
+
+/// Adds the scheme to S3 endpoints given as `host:port`.
+///
+/// # Arguments
+///
+/// * `endpoint` - The endpoint, with or without a scheme.
+/// * `use_ssl` - Whether endpoints without a scheme use https.
+pub fn endpoint_url(endpoint: &str, use_ssl: bool) -> String {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    if endpoint.contains("://") {
+        return endpoint.to_string();
+    }
+    let scheme = if use_ssl { "https" } else { "http" };
+    format!("{scheme}://{endpoint}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves the body to every request on a local port, with its length declared if `declared`.
+    async fn serve(body: &'static [u8], declared: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request).await;
+                let length = if declared {
+                    format!("content-length: {}\r\n", body.len())
+                } else {
+                    String::new()
+                };
+                let head = format!("HTTP/1.1 200 OK\r\n{length}connection: close\r\n\r\n");
+                let _ = stream.write_all(head.as_bytes()).await;
+                let _ = stream.write_all(body).await;
+            }
+        });
+
+        format!("http://{address}/episode.mp3")
+    }
+
+    #[test]
+    fn test_is_global_ip() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+            "64:ff9b::7f00:1",
+            "64:ff9b::a9fe:a9fe",
+            "2002:7f00:1::1",
+            "2002:a9fe:a9fe::",
+            "::127.0.0.1",
+            "::169.254.169.254",
+        ] {
+            assert!(!is_global_ip(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "93.184.216.34",
+            "1.1.1.1",
+            "2606:4700:4700::1111",
+            "64:ff9b::5db8:d822",
+            "2002:5db8:d822::1",
+        ] {
+            assert!(is_global_ip(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[test]
+    fn test_check_public_url() {
+        let check = |url: &str| check_public_url(&Url::parse(url).unwrap());
+
+        assert!(check("https://news.example.com/lead.jpg").is_ok());
+        assert!(check("http://93.184.216.34/feed").is_ok());
+        assert!(check("http://169.254.169.254/latest/meta-data").is_err());
+        assert!(check("http://[::1]:8080/").is_err());
+        assert!(check("http://[64:ff9b::a9fe:a9fe]/latest/meta-data").is_err());
+        assert!(check("http://[2002:7f00:1::1]/").is_err());
+        assert!(check("http://[::7f00:1]/").is_err());
+        assert!(check("http://10.0.0.5/").is_err());
+        assert!(check("file:///etc/passwd").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_public_resolver_rejects_loopback() {
+        let resolved = PublicResolver.resolve("localhost".parse().unwrap()).await;
+
+        assert!(resolved.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_check_public_destination() {
+        let check = |url: &str| Url::parse(url).unwrap();
+
+        assert!(
+            check_public_destination(&check("http://localhost:8080/hook"))
+                .await
+                .is_err()
+        );
+        assert!(
+            check_public_destination(&check("http://127.0.0.1/hook"))
+                .await
+                .is_err()
+        );
+        assert!(
+            check_public_destination(&check("https://93.184.216.34/hook"))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_body_capped() {
+        let client = reqwest::Client::new();
+        for declared in [true, false] {
+            let url = serve(b"0123456789", declared).await;
+
+            let response = client.get(&url).send().await.unwrap();
+            assert_eq!(read_body_capped(response, 10).await.unwrap(), b"0123456789");
+            let response = client.get(&url).send().await.unwrap();
+            assert!(read_body_capped(response, 9).await.is_err(), "{declared}");
+        }
+    }
+
+    #[test]
+    fn test_endpoint_url() {
+        assert_eq!(endpoint_url("minio:9000", false), "http://minio:9000");
+        assert_eq!(endpoint_url("minio:9000", true), "https://minio:9000");
+        assert_eq!(
+            endpoint_url("http://localhost:9000/", true),
+            "http://localhost:9000"
+        );
+    }
+
+    #[test]
+    fn test_http_failure_retries() {
+        assert!(HttpFailure::Timeout.is_retryable());
+        assert!(HttpFailure::Connection("refused".to_string()).is_retryable());
+        assert!(HttpFailure::Status(StatusCode::BAD_GATEWAY).is_retryable());
+        assert!(HttpFailure::Status(StatusCode::TOO_MANY_REQUESTS).is_retryable());
+        assert!(HttpFailure::Status(StatusCode::REQUEST_TIMEOUT).is_retryable());
+        assert!(!HttpFailure::Status(StatusCode::NOT_FOUND).is_retryable());
+        assert!(!HttpFailure::Status(StatusCode::GONE).is_retryable());
+        assert!(!HttpFailure::Forbidden("loopback".to_string()).is_retryable());
+        assert_eq!(
+            HttpFailure::Status(StatusCode::NOT_FOUND).reason(),
+            "client_error"
+        );
+        assert_eq!(
+            HttpFailure::Connection("refused".to_string()).reason(),
+            "connection"
+        );
+        assert_eq!(
+            HttpFailure::Status(StatusCode::GONE).to_string(),
+            "got HTTP status 410 Gone"
+        );
+    }
+}
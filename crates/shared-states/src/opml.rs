@@ -0,0 +1,229 @@
+use crate::category::normalize_categories;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+const OPML_VERSION: &str = "2.0";
+
+/// OpmlFeed is a feed subscription listed in an OPML document.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct OpmlFeed {
+    pub title: String,
+    pub xml_url: String,
+    pub html_url: Option<String>,
+    /// Normalized category slugs taken from the parent outlines and the `category` attribute.
+    pub categories: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename = "opml")]
+struct OpmlDocument {
+    #[serde(rename = "@version", default)]
+    version: String,
+    #[serde(default)]
+    head: OpmlHead,
+    #[serde(default)]
+    body: OpmlBody,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OpmlHead {
+    #[serde(default)]
+    title: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OpmlBody {
+    #[serde(rename = "outline", default)]
+    outlines: Vec<Outline>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Outline {
+    #[serde(rename = "@text", default)]
+    text: String,
+    #[serde(rename = "@title", default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(rename = "@type", default, skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(rename = "@xmlUrl", default, skip_serializing_if = "Option::is_none")]
+    xml_url: Option<String>,
+    #[serde(rename = "@htmlUrl", default, skip_serializing_if = "Option::is_none")]
+    html_url: Option<String>,
+    #[serde(rename = "@category", default, skip_serializing_if = "Option::is_none")]
+    category: Option<String>,
+    #[serde(rename = "outline", default, skip_serializing_if = "Vec::is_empty")]
+    outlines: Vec<Outline>,
+}
+
+impl Outline {
+    fn name(&self) -> &str {
+        match self.title.as_deref() {
+            Some(title) if !title.trim().is_empty() => title.trim(),
+            _ => self.text.trim(),
+        }
+    }
+}
+
+/// Parses an OPML document into a flat list of feeds.
+///
+/// Folder outlines are flattened, their names become categories of the nested feeds.
+/// Feeds listed more than once are merged into the first occurrence.
+///
+/// # Arguments
+///
+/// * `xml` - The OPML XML.
+///
+/// # Returns
+///
+/// A `Result` containing the feeds in document order, or an `anyhow::Error` if the XML is malformed.
+pub fn parse_opml(xml: &str) -> Result<Vec<OpmlFeed>> {
+    let document: OpmlDocument = quick_xml::de::from_str(xml)?;
+
+    let mut feeds: Vec<OpmlFeed> = Vec::new();
+    let mut folders: Vec<String> = Vec::new();
+    flatten_outlines(&document.body.outlines, &mut folders, &mut feeds);
+
+    Ok(feeds)
+}
+
+/// Serializes feeds into an OPML 2.0 document.
+///
+/// # Arguments
+///
+/// * `title` - Title of the document.
+/// * `feeds` - The feeds to export, categories are written to the `category` attribute.
+///
+/// # Returns
+///
+/// A `Result` containing the OPML XML, or an `anyhow::Error` if serialization fails.
+pub fn write_opml(title: &str, feeds: &[OpmlFeed]) -> Result<String> {
+    let document = OpmlDocument {
+        version: OPML_VERSION.to_string(),
+        head: OpmlHead {
+            title: title.to_string(),
+        },
+        body: OpmlBody {
+            outlines: feeds
+                .iter()
+                .map(|feed| Outline {
+                    text: feed.title.clone(),
+                    title: Some(feed.title.clone()),
+                    kind: Some("rss".to_string()),
+                    xml_url: Some(feed.xml_url.clone()),
+                    html_url: feed.html_url.clone(),
+                    category: (!feed.categories.is_empty()).then(|| feed.categories.join(",")),
+                    outlines: Vec::new(),
+                })
+                .collect(),
+        },
+    };
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&quick_xml::se::to_string(&document)?);
+
+    Ok(xml)
+}
+
+fn flatten_outlines(outlines: &[Outline], folders: &mut Vec<String>, feeds: &mut Vec<OpmlFeed>) {
+    for outline in outlines {
+        let Some(xml_url) = outline
+            .xml_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+        else {
+            folders.push(outline.name().to_string());
+            flatten_outlines(&outline.outlines, folders, feeds);
+            folders.pop();
+            continue;
+        };
+
+        let attribute_categories = outline
+            .category
+            .as_deref()
+            .unwrap_or_default()
+            .split([',', '/']);
+        let categories = normalize_categories(
+            folders
+                .iter()
+                .map(String::as_str)
+                .chain(attribute_categories),
+        );
+
+        match feeds.iter_mut().find(|feed| feed.xml_url == xml_url) {
+            Some(feed) => {
+                feed.categories = normalize_categories(feed.categories.iter().chain(&categories));
+            }
+            None => feeds.push(OpmlFeed {
+                title: outline.name().to_string(),
+                xml_url: xml_url.to_string(),
+                html_url: outline
+                    .html_url
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|url| !url.is_empty())
+                    .map(str::to_string),
+                categories,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <opml version="2.0">
+            <head><title>Subscriptions</title></head>
+            <body>
+                <outline text="Crypto">
+                    <outline text="Markets">
+                        <outline type="rss" text="CoinDesk" xmlUrl="https://www.coindesk.com/arc/outboundfeeds/rss/"
+                                 htmlUrl="https://www.coindesk.com" category="/Bitcoin,/DeFi"/>
+                    </outline>
+                    <outline type="rss" text="The Block" title="The Block News" xmlUrl="https://www.theblock.co/rss.xml"/>
+                </outline>
+                <outline type="rss" text="CoinDesk again" xmlUrl="https://www.coindesk.com/arc/outboundfeeds/rss/"
+                         category="Regulation"/>
+                <outline text="Empty folder"/>
+            </body>
+        </opml>"#;
+
+    #[test]
+    fn test_parse_opml() {
+        let feeds = parse_opml(OPML).unwrap();
+
+        assert_eq!(
+            feeds,
+            vec![
+                OpmlFeed {
+                    title: "CoinDesk".to_string(),
+                    xml_url: "https://www.coindesk.com/arc/outboundfeeds/rss/".to_string(),
+                    html_url: Some("https://www.coindesk.com".to_string()),
+                    categories: vec!["crypto", "markets", "bitcoin", "defi", "regulation"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect(),
+                },
+                OpmlFeed {
+                    title: "The Block News".to_string(),
+                    xml_url: "https://www.theblock.co/rss.xml".to_string(),
+                    html_url: None,
+                    categories: vec!["crypto".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_opml_round_trip() {
+        let feeds = parse_opml(OPML).unwrap();
+
+        let xml = write_opml("Semantic Machine & friends", &feeds).unwrap();
+
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("Semantic Machine &amp; friends"));
+        assert_eq!(parse_opml(&xml).unwrap(), feeds);
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{Result, anyhow};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+const PDF_MAGIC: &[u8] = b"%PDF-";
+
+/// Checks if the response is a PDF document.
+///
+/// # Arguments
+///
+/// * `content_type` - Value of the `Content-Type` response header, if any.
+/// * `body` - Raw response body, sniffed when the header is missing or generic.
+///
+/// # Returns
+///
+/// True if the header declares `application/pdf` or the body starts with the PDF signature.
+pub fn is_pdf(content_type: Option<&str>, body: &[u8]) -> bool {
+    let declared = content_type
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/pdf"));
+
+    declared || body.starts_with(PDF_MAGIC)
+}
+
+/// Extracts the text of a PDF document.
+///
+/// Malformed documents that make the extractor panic are reported as errors.
+///
+/// # Arguments
+///
+/// * `bytes` - The PDF document.
+///
+/// # Returns
+///
+/// A `Result` containing the text of all pages, or an `anyhow::Error` if the document could not be read.
+pub fn extract_pdf_text(bytes: &[u8]) -> Result<String> {
+    match catch_unwind(AssertUnwindSafe(|| {
+        pdf_extract::extract_text_from_mem(bytes)
+    })) {
+        Ok(Ok(text)) => Ok(text),
+        Ok(Err(e)) => Err(anyhow!("Failed to extract PDF text: {e}")),
+        Err(_) => Err(anyhow!("Failed to extract PDF text: malformed document")),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Builds a single page PDF document showing `text` in Helvetica.
+    pub(crate) fn single_page_pdf(text: &str) -> Vec<u8> {
+        let content = format!("BT /F1 12 Tf 72 720 Td ({text}) Tj ET");
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >>".to_string(),
+            format!("<< /Length {} >>\nstream\n{content}\nendstream", content.len()),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+                .to_string(),
+        ];
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n{object}\nendobj\n", i + 1).as_bytes());
+        }
+        let xref = pdf.len();
+        pdf.extend_from_slice(
+            format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+        );
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{offset:010} 00000 n \n").as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+                objects.len() + 1
+            )
+            .as_bytes(),
+        );
+
+        pdf
+    }
+
+    #[test]
+    fn test_is_pdf() {
+        assert!(is_pdf(Some("application/pdf"), b""));
+        assert!(is_pdf(Some("Application/PDF; qs=0.001"), b""));
+        assert!(is_pdf(Some("application/octet-stream"), b"%PDF-1.7\n"));
+        assert!(!is_pdf(Some("text/html; charset=utf-8"), b"<html></html>"));
+        assert!(!is_pdf(None, b""));
+    }
+
+    #[test]
+    fn test_extract_pdf_text() {
+        let text = extract_pdf_text(&single_page_pdf("Quarterly research note")).unwrap();
+
+        assert!(text.contains("Quarterly research note"));
+        assert!(extract_pdf_text(b"%PDF-1.4\ngarbage").is_err());
+    }
+}
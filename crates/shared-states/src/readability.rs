@@ -0,0 +1,170 @@
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+const CANDIDATE_TAGS: &str = "p, pre, td, blockquote, li";
+const MIN_PARAGRAPH_LENGTH: usize = 25;
+const MIN_CONTENT_LENGTH: usize = 140;
+
+static POSITIVE_HINTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)article|body|content|entry|hentry|main|page|post|text|blog|story")
+        .expect("valid positive hints regex")
+});
+
+static NEGATIVE_HINTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)comment|combx|contact|foot|footer|footnote|masthead|media|meta|menu|nav|outbrain|promo|related|scroll|shoutbox|sidebar|sponsor|shopping|social|share|tags|tool|widget|banner|\bad\b|ads",
+    )
+    .expect("valid negative hints regex")
+});
+
+/// Finds the element most likely to hold the main content of the document.
+///
+/// Paragraph-like elements are scored by text length and comma count, the score is propagated
+/// to the parent and (halved) to the grandparent, and every candidate container is then
+/// weighted by its class/id hints and penalised by its link density.
+///
+/// # Arguments
+///
+/// * `document` - Parsed HTML document.
+///
+/// # Returns
+///
+/// The best scoring element, or `None` if the document holds no meaningful content.
+pub fn find_content_root(document: &Html) -> Option<ElementRef<'_>> {
+    let paragraphs = Selector::parse(CANDIDATE_TAGS).ok()?;
+    let mut scores = HashMap::new();
+
+    for paragraph in document.select(&paragraphs) {
+        let text = inner_text(&paragraph);
+        if text.len() < MIN_PARAGRAPH_LENGTH {
+            continue;
+        }
+
+        let score = 1.0 + text.matches(',').count() as f64 + (text.len() / 100).min(3) as f64;
+
+        let Some(parent) = paragraph.parent().and_then(ElementRef::wrap) else {
+            continue;
+        };
+        *scores
+            .entry(parent.id())
+            .or_insert_with(|| initial_score(&parent)) += score;
+
+        if let Some(grandparent) = parent.parent().and_then(ElementRef::wrap) {
+            *scores
+                .entry(grandparent.id())
+                .or_insert_with(|| initial_score(&grandparent)) += score / 2.0;
+        }
+    }
+
+    scores
+        .into_iter()
+        .filter_map(|(id, score)| {
+            let element = document.tree.get(id).and_then(ElementRef::wrap)?;
+            Some((element, score * (1.0 - link_density(&element))))
+        })
+        .filter(|(element, score)| *score > 0.0 && inner_text(element).len() >= MIN_CONTENT_LENGTH)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(element, _)| element)
+}
+
+/// Collects the whitespace normalized text of the element.
+pub fn inner_text(element: &ElementRef) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Ratio of text inside links to the whole text of the element, in range `0.0..=1.0`.
+pub fn link_density(element: &ElementRef) -> f64 {
+    let total = inner_text(element).len();
+    if total == 0 {
+        return 0.0;
+    }
+    let Ok(links) = Selector::parse("a") else {
+        return 0.0;
+    };
+    let linked: usize = element.select(&links).map(|a| inner_text(&a).len()).sum();
+
+    (linked as f64 / total as f64).min(1.0)
+}
+
+fn initial_score(element: &ElementRef) -> f64 {
+    let tag_score = match element.value().name() {
+        "article" | "main" => 10.0,
+        "div" | "section" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "ul" | "ol" | "dl" | "form" | "address" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" | "header" | "footer" | "nav" | "aside" => {
+            -5.0
+        }
+        _ => 0.0,
+    };
+
+    tag_score + class_weight(element)
+}
+
+fn class_weight(element: &ElementRef) -> f64 {
+    let mut weight = 0.0;
+    let hints = [
+        element.value().attr("class").unwrap_or_default(),
+        element.value().attr("id").unwrap_or_default(),
+    ];
+
+    for hint in hints.iter().filter(|h| !h.is_empty()) {
+        if NEGATIVE_HINTS.is_match(hint) {
+            weight -= 25.0;
+        }
+        if POSITIVE_HINTS.is_match(hint) {
+            weight += 25.0;
+        }
+    }
+
+    weight
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAGRAPH: &str = "Bitcoin rallied on Monday, extending gains from last week, as traders \
+        priced in a more dovish central bank, according to several market analysts.";
+
+    #[test]
+    fn test_find_content_root_prefers_dense_text() {
+        let html = format!(
+            r#"<html><body>
+                <div class="sidebar"><a href="/a">Link one here</a><a href="/b">Link two here</a></div>
+                <div class="story-body"><p>{PARAGRAPH}</p><p>{PARAGRAPH}</p><p>{PARAGRAPH}</p></div>
+                <div class="footer"><p>Copyright, all rights reserved, some company name here.</p></div>
+            </body></html>"#
+        );
+        let document = Html::parse_document(&html);
+
+        let root = find_content_root(&document).expect("content root");
+
+        assert_eq!(root.value().attr("class"), Some("story-body"));
+    }
+
+    #[test]
+    fn test_find_content_root_none_without_content() {
+        let document =
+            Html::parse_document("<html><body><nav><a href=\"/\">Home</a></nav></body></html>");
+
+        assert!(find_content_root(&document).is_none());
+    }
+
+    #[test]
+    fn test_link_density() {
+        let fragment = Html::parse_fragment(r#"<div>plain <a href="/x">link</a></div>"#);
+        let selector = Selector::parse("div").unwrap();
+        let div = fragment.select(&selector).next().unwrap();
+
+        let density = link_density(&div);
+
+        assert!(density > 0.3 && density < 0.5);
+    }
+}
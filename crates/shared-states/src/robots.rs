@@ -0,0 +1,422 @@
+use crate::fetcher::ArticleFetcher;
+use reqwest::{StatusCode, Url};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+
+/// User agent token used when matching robots.txt groups.
+pub const ROBOTS_USER_AGENT: &str = "SemanticMachineBot";
+
+const ROBOTS_CACHE_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Time the full disallow of a host with an unreachable robots.txt is cached, so crawling resumes
+/// soon after the host recovers.
+const UNREACHABLE_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+/// Longest honored crawl delay, hosts asking for more are still crawled at this pace.
+const MAX_CRAWL_DELAY: Duration = Duration::from_secs(60);
+
+static ROBOTS_CACHE: LazyLock<RobotsCache> = LazyLock::new(|| RobotsCache::new(ROBOTS_CACHE_TTL));
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Group {
+    agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+/// RobotsRules holds the rules of a robots.txt file that apply to a single user agent.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RobotsRules {
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+impl RobotsRules {
+    /// Rules that allow every path.
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// Rules that disallow every path.
+    pub fn disallow_all() -> Self {
+        Self {
+            rules: vec![Rule {
+                allow: false,
+                pattern: "/".to_string(),
+            }],
+            crawl_delay: None,
+        }
+    }
+
+    /// Parses robots.txt content and selects the group matching the user agent.
+    ///
+    /// # Arguments
+    ///
+    /// * `content` - The robots.txt file content.
+    /// * `user_agent` - The user agent to select rules for.
+    ///
+    /// # Returns
+    ///
+    /// Rules of the most specific group matching the user agent, falling back to the `*` group.
+    pub fn parse(content: &str, user_agent: &str) -> Self {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut current = Group::default();
+        let mut collecting_agents = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    if !collecting_agents && !current.agents.is_empty() {
+                        groups.push(std::mem::take(&mut current));
+                    }
+                    current.agents.push(value.to_lowercase());
+                    collecting_agents = true;
+                }
+                "allow" | "disallow" => {
+                    collecting_agents = false;
+                    if value.is_empty() {
+                        continue;
+                    }
+                    current.rules.push(Rule {
+                        allow: key.trim().eq_ignore_ascii_case("allow"),
+                        pattern: value.to_string(),
+                    });
+                }
+                "crawl-delay" => {
+                    collecting_agents = false;
+                    current.crawl_delay = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                        .map(Duration::from_secs_f64);
+                }
+                _ => {}
+            }
+        }
+        if !current.agents.is_empty() {
+            groups.push(current);
+        }
+
+        let user_agent = user_agent.to_lowercase();
+        let matched = groups
+            .iter()
+            .filter_map(|group| {
+                group
+                    .agents
+                    .iter()
+                    .filter(|agent| agent.as_str() != "*" && user_agent.contains(agent.as_str()))
+                    .map(|agent| agent.len())
+                    .max()
+                    .map(|len| (len, group))
+            })
+            .max_by_key(|(len, _)| *len)
+            .map(|(_, group)| group)
+            .or_else(|| groups.iter().find(|g| g.agents.iter().any(|a| a == "*")));
+
+        match matched {
+            Some(group) => Self {
+                rules: group.rules.clone(),
+                crawl_delay: group.crawl_delay,
+            },
+            None => Self::allow_all(),
+        }
+    }
+
+    /// Checks if the path (with optional query) may be crawled.
+    ///
+    /// The longest matching rule wins, `Allow` wins ties.
+    pub fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|rule| pattern_matches(&rule.pattern, path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+
+    /// Crawl delay requested for the user agent, if any.
+    pub fn crawl_delay(&self) -> Option<Duration> {
+        self.crawl_delay
+    }
+}
+
+/// RobotsCache fetches and caches robots.txt rules per host and paces requests to hosts asking
+/// for a crawl delay.
+pub struct RobotsCache {
+    ttl: Duration,
+    /// Rules per host with the time they expire.
+    entries: Mutex<HashMap<String, (Instant, Arc<RobotsRules>)>>,
+    /// Earliest time of the next request per host with a crawl delay.
+    next_requests: Mutex<HashMap<String, Instant>>,
+}
+
+impl RobotsCache {
+    /// Creates a new cache keeping rules for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            next_requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Process wide cache shared by article extraction.
+    pub fn global() -> &'static RobotsCache {
+        &ROBOTS_CACHE
+    }
+
+    /// Returns the rules for the host of the URL, fetching robots.txt when not cached or stale.
+    ///
+    /// Missing robots.txt files are treated as allowing everything, unreachable ones and server
+    /// errors as disallowing everything for a short time, as RFC 9309 requires.
+    ///
+    /// # Arguments
+    ///
+    /// * `fetcher` - Fetcher used to download robots.txt.
+    /// * `url` - Any URL on the host.
+    pub async fn rules_for<F: ArticleFetcher>(&self, fetcher: &F, url: &Url) -> Arc<RobotsRules> {
+        let origin = url.origin().ascii_serialization();
+
+        if let Ok(entries) = self.entries.lock()
+            && let Some((expires_at, rules)) = entries.get(&origin)
+            && Instant::now() < *expires_at
+        {
+            return rules.clone();
+        }
+
+        let (rules, reachable) = fetch_rules(fetcher, &origin).await;
+        let rules = Arc::new(rules);
+        let ttl = match reachable {
+            true => self.ttl,
+            false => self.ttl.min(UNREACHABLE_CACHE_TTL),
+        };
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(origin, (Instant::now() + ttl, rules.clone()));
+        }
+
+        rules
+    }
+
+    /// Checks if the URL may be crawled according to its host robots.txt.
+    pub async fn is_allowed<F: ArticleFetcher>(&self, fetcher: &F, url: &Url) -> bool {
+        let rules = self.rules_for(fetcher, url).await;
+        let path = match url.query() {
+            Some(query) => format!("{}?{query}", url.path()),
+            None => url.path().to_string(),
+        };
+
+        rules.is_allowed(&path)
+    }
+
+    /// Waits until a request to the host of the URL honors its robots.txt crawl delay.
+    ///
+    /// Concurrent callers are queued, each one delay after the previous one. Delays are capped at
+    /// one minute.
+    ///
+    /// # Arguments
+    ///
+    /// * `fetcher` - Fetcher used to download robots.txt.
+    /// * `url` - The URL about to be requested.
+    pub async fn wait_for_crawl_delay<F: ArticleFetcher>(&self, fetcher: &F, url: &Url) {
+        let Some(delay) = self.rules_for(fetcher, url).await.crawl_delay() else {
+            return;
+        };
+        let origin = url.origin().ascii_serialization();
+        let wait = self.reserve(&origin, delay.min(MAX_CRAWL_DELAY), Instant::now());
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+    }
+
+    /// Reserves the next request slot of the host, returning the time to wait for it.
+    fn reserve(&self, origin: &str, delay: Duration, now: Instant) -> Duration {
+        let Ok(mut next_requests) = self.next_requests.lock() else {
+            return Duration::ZERO;
+        };
+        let slot = next_requests
+            .get(origin)
+            .copied()
+            .filter(|next| *next > now)
+            .unwrap_or(now);
+        next_requests.insert(origin.to_string(), slot + delay);
+
+        slot - now
+    }
+}
+
+/// Fetches the rules of the host, returning whether robots.txt was reachable as well.
+async fn fetch_rules<F: ArticleFetcher>(fetcher: &F, origin: &str) -> (RobotsRules, bool) {
+    let Ok(url) = Url::parse(&format!("{origin}/robots.txt")) else {
+        return (RobotsRules::allow_all(), true);
+    };
+    let Ok(page) = fetcher.fetch(&url).await else {
+        return (RobotsRules::disallow_all(), false);
+    };
+
+    match StatusCode::from_u16(page.status) {
+        Ok(status) if status.is_success() => (
+            RobotsRules::parse(&String::from_utf8_lossy(&page.body), ROBOTS_USER_AGENT),
+            true,
+        ),
+        Ok(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => (RobotsRules::disallow_all(), true),
+        Ok(status) if status.is_server_error() => (RobotsRules::disallow_all(), false),
+        _ => (RobotsRules::allow_all(), true),
+    }
+}
+
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let mut parts = pattern.split('*');
+    let Some(prefix) = parts.next() else {
+        return true;
+    };
+    let Some(mut rest) = path.strip_prefix(prefix) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        let is_last = i == parts.len() - 1;
+        if is_last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(position) => rest = &rest[position + part.len()..],
+            None => return false,
+        }
+    }
+
+    !anchored || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fetcher::FetchedPage;
+    use anyhow::{Result, anyhow};
+
+    /// Answers robots.txt requests with the status, fails to connect without one.
+    struct StatusFetcher(Option<u16>);
+
+    impl ArticleFetcher for StatusFetcher {
+        async fn fetch(&self, url: &Url) -> Result<FetchedPage> {
+            let status = self.0.ok_or_else(|| anyhow!("connection refused"))?;
+            Ok(FetchedPage {
+                url: url.to_string(),
+                status,
+                content_type: Some("text/plain".to_string()),
+                body: b"User-agent: *\nDisallow: /private/".to_vec(),
+            })
+        }
+    }
+
+    const ROBOTS: &str = "
+        # Global rules
+        User-agent: *
+        Disallow: /private/
+        Allow: /private/press/
+        Disallow: /*.pdf$
+        Crawl-delay: 2
+
+        User-agent: GPTBot
+        User-agent: SemanticMachineBot
+        Disallow: /news/archive
+    ";
+
+    #[test]
+    fn test_robots_rules_wildcard_group() {
+        let rules = RobotsRules::parse(ROBOTS, "SomeOtherBot/1.0");
+
+        assert!(rules.is_allowed("/news/today"));
+        assert!(!rules.is_allowed("/private/data"));
+        assert!(rules.is_allowed("/private/press/release"));
+        assert!(!rules.is_allowed("/files/report.pdf"));
+        assert!(rules.is_allowed("/files/report.pdf?download=1"));
+        assert_eq!(rules.crawl_delay(), Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_robots_rules_specific_group() {
+        let rules = RobotsRules::parse(ROBOTS, "Mozilla/5.0 (compatible; SemanticMachineBot/1.0)");
+
+        assert!(!rules.is_allowed("/news/archive/2020"));
+        assert!(rules.is_allowed("/private/data"));
+        assert_eq!(rules.crawl_delay(), None);
+    }
+
+    #[test]
+    fn test_robots_cache_reserve() {
+        let cache = RobotsCache::new(ROBOTS_CACHE_TTL);
+        let now = Instant::now();
+        let delay = Duration::from_secs(2);
+
+        assert_eq!(
+            cache.reserve("https://a.example.com", delay, now),
+            Duration::ZERO
+        );
+        assert_eq!(cache.reserve("https://a.example.com", delay, now), delay);
+        assert_eq!(
+            cache.reserve("https://a.example.com", delay, now),
+            delay * 2
+        );
+        assert_eq!(
+            cache.reserve("https://b.example.com", delay, now),
+            Duration::ZERO
+        );
+        assert_eq!(
+            cache.reserve("https://a.example.com", delay, now + delay * 10),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_robots_rules_empty() {
+        let rules = RobotsRules::parse("", ROBOTS_USER_AGENT);
+
+        assert!(rules.is_allowed("/anything"));
+        assert_eq!(rules, RobotsRules::allow_all());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rules_status() {
+        let origin = "https://news.example.com";
+        let allowed =
+            |(rules, reachable): (RobotsRules, bool)| (rules.is_allowed("/news"), reachable);
+
+        assert_eq!(
+            allowed(fetch_rules(&StatusFetcher(Some(200)), origin).await),
+            (true, true)
+        );
+        assert_eq!(
+            allowed(fetch_rules(&StatusFetcher(Some(404)), origin).await),
+            (true, true)
+        );
+        assert_eq!(
+            allowed(fetch_rules(&StatusFetcher(Some(403)), origin).await),
+            (false, true)
+        );
+        assert_eq!(
+            allowed(fetch_rules(&StatusFetcher(Some(503)), origin).await),
+            (false, false)
+        );
+        assert_eq!(
+            allowed(fetch_rules(&StatusFetcher(None), origin).await),
+            (false, false)
+        );
+    }
+}
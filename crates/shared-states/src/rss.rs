@@ -1,67 +1,326 @@
-use crate::extract_article;
-use chrono::{DateTime, Utc};
-use rss::Item;
+use crate::author::{Author, deserialize_author};
+use crate::category::deserialize_categories;
+use crate::{
+    Article, ArticleFetcher, detect_language, extract_article, feed_hash, normalize_categories,
+    parse_publication_date, sanitize_html, simhash,
+};
+use chrono::Utc;
+use rss::{Channel, Item};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use sqlx::prelude::FromRow;
 
 pub const RSS_QUEUE_NAME: &str = "rss_items";
+/// Subject items failing to be published are retried on, see `RssItemRetry`.
+pub const RSS_RETRY_QUEUE_NAME: &str = "rss_items.retry";
+pub const RSS_CHANNEL_QUEUE_NAME: &str = "rss_channels";
 
-/// RssItem represents an item in an RSS feed.
+/// RssChannelInfo represents the feed level metadata of an RSS channel.
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow, PartialEq, Eq, Hash)]
+pub struct RssChannelInfo {
+    /// Hash of the URL the feed is fetched from.
+    pub hash: String,
+    /// URL the feed is fetched from.
+    pub url: String,
+    pub title: String,
+    /// Link to the website of the feed.
+    pub link: String,
+    pub description: String,
+    pub language: String,
+    /// Minutes the feed may be cached for.
+    pub ttl: Option<i32>,
+    pub last_build_timestamp: Option<i64>,
+    /// Canonical feed URL advertised with `atom:link rel="self"`, the fetch URL if missing.
+    pub self_url: String,
+    pub fetched_timestamp: i64,
+}
+
+impl RssChannelInfo {
+    /// Creates the channel info from a parsed feed.
+    ///
+    /// # Arguments
+    /// * `channel` - The parsed feed.
+    /// * `url` - URL the feed was fetched from.
+    pub fn new(channel: &Channel, url: &str) -> Self {
+        let self_url = channel
+            .atom_ext()
+            .and_then(|atom| atom.links().iter().find(|link| link.rel() == "self"))
+            .map(|link| link.href().trim())
+            .filter(|href| !href.is_empty())
+            .unwrap_or(url);
+
+        RssChannelInfo {
+            hash: feed_hash(url),
+            url: url.to_string(),
+            title: channel.title().trim().to_string(),
+            link: channel.link().trim().to_string(),
+            description: sanitize_html(channel.description()),
+            language: channel
+                .language()
+                .map(|language| language.trim().to_lowercase())
+                .unwrap_or_default(),
+            ttl: channel
+                .ttl()
+                .and_then(|ttl| ttl.trim().parse::<i32>().ok())
+                .filter(|ttl| *ttl >= 0),
+            last_build_timestamp: channel
+                .last_build_date()
+                .and_then(parse_publication_date)
+                .map(|dt| dt.timestamp_millis()),
+            self_url: self_url.to_string(),
+            fetched_timestamp: Utc::now().timestamp_millis(),
+        }
+    }
+}
+
+/// RssItem represents an item in an RSS feed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow, PartialEq, Eq, Hash)]
 pub struct RssItem {
     pub hash: String,
     pub title: String,
     pub link: String,
     pub description: String,
     pub published_timestamp: i64,
+    /// True if the feed had no parsable publication date and the fetch time is used instead.
+    #[serde(default)]
+    pub published_timestamp_estimated: bool,
     pub fetched_timestamp: i64,
     pub comments_url: String,
-    pub category: String,
-    pub author: String,
+    /// Normalized category slugs, legacy comma joined strings are accepted when deserializing.
+    #[serde(default, deserialize_with = "deserialize_categories")]
+    pub category: Vec<String>,
+    /// Author parsed from the item author or Dublin Core creator, legacy raw strings are accepted when deserializing.
+    #[serde(default, deserialize_with = "deserialize_author")]
+    pub author: Author,
     pub article: String,
+    /// The extracted article as Markdown, empty if not extracted.
+    #[serde(default)]
+    pub article_markdown: String,
+    /// ISO 639-3 code of the item language, empty if it could not be detected.
+    #[serde(default)]
+    pub language: String,
+    #[serde(default)]
+    pub preview_title: Option<String>,
+    #[serde(default)]
+    pub preview_description: Option<String>,
+    #[serde(default)]
+    pub preview_image: Option<String>,
+    #[serde(default)]
+    pub preview_published_timestamp: Option<i64>,
+    /// URL of the media file attached to the item, e.g. a podcast episode or an image.
+    #[serde(default)]
+    pub enclosure_url: Option<String>,
+    /// Object storage key of the archived enclosure, set for audio enclosures only.
+    #[serde(default)]
+    pub enclosure_object_key: Option<String>,
+    /// SimHash fingerprint of the item text stored as the bit pattern of an `i64`.
+    #[serde(default)]
+    pub simhash: i64,
+    /// Number of words of the extracted article, `0` if not extracted.
+    #[serde(default)]
+    pub word_count: i32,
+    /// Estimated article reading time in minutes, `0` if not extracted.
+    #[serde(default)]
+    pub reading_time_minutes: i32,
 }
 
 impl RssItem {
-    pub async fn extract_article_from_source(&mut self) -> anyhow::Result<()> {
-        self.article = extract_article(&self.link).await?;
+    /// Returns the SimHash fingerprint of the item.
+    pub fn fingerprint(&self) -> u64 {
+        self.simhash as u64
+    }
+
+    /// Extracts the article from the item link and fills the article derived fields.
+    ///
+    /// # Arguments
+    /// * `fetcher` - The fetcher used to download the article page.
+    /// * `detect_language` - Whether the language is detected from the article.
+    pub async fn extract_article_from_source<F: ArticleFetcher>(
+        &mut self,
+        fetcher: &F,
+        detect_language: bool,
+    ) -> anyhow::Result<()> {
+        let article = extract_article(fetcher, &self.link).await?;
+        self.set_article(article, detect_language);
         Ok(())
     }
+
+    /// Fills the article derived fields from an already extracted article.
+    ///
+    /// # Arguments
+    /// * `article` - The article of the item.
+    /// * `detect_language` - Whether the language is detected from the article.
+    pub fn set_article(&mut self, article: Article, detect_language: bool) {
+        self.article = article.text;
+        self.article_markdown = article.markdown;
+        self.preview_title = article.metadata.title;
+        self.preview_description = article.metadata.description;
+        self.preview_image = article.metadata.image;
+        self.preview_published_timestamp = article.metadata.published_timestamp;
+        self.word_count = i32::try_from(article.word_count).unwrap_or(i32::MAX);
+        self.reading_time_minutes = i32::try_from(article.reading_time_minutes).unwrap_or(i32::MAX);
+        if detect_language && let Some(language) = crate::detect_language(&self.article) {
+            self.language = language;
+        }
+        if !self.article.is_empty() {
+            self.simhash = simhash(&self.article) as i64;
+        }
+    }
+}
+
+/// Hash identifying the RSS item with its content, items fetched from feeds and ingested by other
+/// services are hashed the same way.
+///
+/// # Arguments
+/// * `title` - The title of the item.
+/// * `author` - The author of the item as published.
+/// * `link` - The link of the item.
+/// * `description` - The description of the item before sanitizing.
+/// * `published` - The publication date of the item as published.
+pub fn item_hash(
+    title: &str,
+    author: &str,
+    link: &str,
+    description: &str,
+    published: &str,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(title.as_bytes());
+    hasher.update(author.as_bytes());
+    hasher.update(link.as_bytes());
+    hasher.update(description.as_bytes());
+    hasher.update(published.as_bytes());
+    hex::encode(hasher.finalize())
 }
 
 impl TryFrom<&Item> for RssItem {
     type Error = anyhow::Error;
 
     fn try_from(item: &Item) -> Result<Self, Self::Error> {
-        let dt = DateTime::parse_from_rfc2822(item.pub_date().unwrap_or_default())?;
-        let dt_utc = dt.with_timezone(&Utc);
-        let published_timestamp = dt_utc.timestamp_millis();
         let fetched_timestamp = Utc::now().timestamp_millis();
-        let mut hasher = Sha256::new();
-        hasher.update(item.title().unwrap_or_default().as_bytes());
-        hasher.update(item.author().unwrap_or_default().as_bytes());
-        hasher.update(item.link().unwrap_or_default().as_bytes());
-        hasher.update(item.description().unwrap_or_default().as_bytes());
-        hasher.update(item.pub_date().unwrap_or_default().as_bytes());
-        let result = hasher.finalize();
-        let hash = hex::encode(result);
+        let published = item
+            .pub_date()
+            .and_then(parse_publication_date)
+            .or_else(|| {
+                item.dublin_core_ext()
+                    .and_then(|dc| dc.dates().iter().find_map(|d| parse_publication_date(d)))
+            });
+        let (published_timestamp, published_timestamp_estimated) = match published {
+            Some(dt) => (dt.timestamp_millis(), false),
+            None => (fetched_timestamp, true),
+        };
+        let hash = item_hash(
+            item.title().unwrap_or_default(),
+            item.author().unwrap_or_default(),
+            item.link().unwrap_or_default(),
+            item.description().unwrap_or_default(),
+            item.pub_date().unwrap_or_default(),
+        );
+
+        let description = sanitize_html(item.description().unwrap_or_default());
+        let text = format!("{} {description}", item.title().unwrap_or_default());
+        let language = detect_language(&text).unwrap_or_default();
 
         Ok(RssItem {
             hash,
             title: item.title().unwrap_or_default().to_string(),
             link: item.link().unwrap_or_default().to_string(),
-            description: item.description().unwrap_or_default().to_string(),
+            description,
             published_timestamp,
+            published_timestamp_estimated,
             fetched_timestamp,
             comments_url: item.comments().unwrap_or_default().to_string(),
-            category: item
-                .categories()
-                .iter()
-                .map(|c| c.name().to_string())
-                .collect::<Vec<String>>()
-                .join(", "),
-            author: item.author().unwrap_or_default().to_string(),
+            category: normalize_categories(item.categories().iter().map(|c| c.name())),
+            author: Author::parse(
+                item.author()
+                    .or_else(|| {
+                        item.dublin_core_ext()
+                            .and_then(|dc| dc.creators().first())
+                            .map(String::as_str)
+                    })
+                    .unwrap_or_default(),
+            ),
             article: String::new(),
+            article_markdown: String::new(),
+            language,
+            preview_title: None,
+            preview_description: None,
+            preview_image: None,
+            preview_published_timestamp: None,
+            enclosure_url: item
+                .enclosure()
+                .map(|enclosure| enclosure.url().trim().to_string())
+                .filter(|url| !url.is_empty()),
+            enclosure_object_key: None,
+            simhash: simhash(&text) as i64,
+            word_count: 0,
+            reading_time_minutes: 0,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rss_channel_info() {
+        let xml = r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+                <channel>
+                    <title> Crypto News </title>
+                    <link>https://news.example.com</link>
+                    <description>Daily &lt;b&gt;crypto&lt;/b&gt; news</description>
+                    <language>en-US</language>
+                    <ttl>30</ttl>
+                    <lastBuildDate>Tue, 21 May 2024 14:00:00 GMT</lastBuildDate>
+                    <atom:link href="https://news.example.com/feed.xml" rel="self" type="application/rss+xml"/>
+                </channel>
+            </rss>"#;
+        let channel = Channel::read_from(xml.as_bytes()).unwrap();
+
+        let info = RssChannelInfo::new(&channel, "http://news.example.com/rss");
+
+        assert_eq!(info.title, "Crypto News");
+        assert_eq!(info.link, "https://news.example.com");
+        assert_eq!(info.description, "Daily crypto news");
+        assert_eq!(info.language, "en-us");
+        assert_eq!(info.ttl, Some(30));
+        assert_eq!(info.last_build_timestamp, Some(1716300000000));
+        assert_eq!(info.self_url, "https://news.example.com/feed.xml");
+        assert_eq!(info.url, "http://news.example.com/rss");
+        assert_eq!(info.hash.len(), 64);
+    }
+
+    #[test]
+    fn test_rss_item_enclosure() {
+        let xml = r#"<?xml version="1.0"?>
+            <rss version="2.0">
+                <channel>
+                    <title>Crypto Podcast</title>
+                    <item>
+                        <title>Episode 1</title>
+                        <link>https://news.example.com/episode-1</link>
+                        <enclosure url=" https://cdn.example.com/episode-1.mp3 " length="1024" type="audio/mpeg"/>
+                    </item>
+                    <item>
+                        <title>Episode 2</title>
+                        <link>https://news.example.com/episode-2</link>
+                    </item>
+                </channel>
+            </rss>"#;
+        let channel = Channel::read_from(xml.as_bytes()).unwrap();
+
+        let items: Vec<RssItem> = channel
+            .items()
+            .iter()
+            .map(|item| RssItem::try_from(item).unwrap())
+            .collect();
+
+        assert_eq!(
+            items[0].enclosure_url.as_deref(),
+            Some("https://cdn.example.com/episode-1.mp3")
+        );
+        assert_eq!(items[1].enclosure_url, None);
+    }
+}
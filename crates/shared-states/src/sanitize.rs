@@ -0,0 +1,133 @@
+use regex::Regex;
+use scraper::{ElementRef, Html, Selector};
+use std::sync::LazyLock;
+
+const BOILERPLATE_TAGS: &str = "script, style, noscript, template, iframe, object, embed, svg, \
+    canvas, button, input, select, textarea, nav, footer, aside";
+
+const PROTECTED_TAGS: [&str; 4] = ["html", "body", "main", "article"];
+
+static BOILERPLATE_HINTS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(ad|ads|advert\w*|sponsor\w*|promo\w*|related\w*|share|sharing|social|newsletter|subscribe|comments?|cookie\w*|popup|modal|breadcrumbs?|paywall)\b",
+    )
+    .expect("valid boilerplate hints regex")
+});
+
+/// Removes scripts, styles, navigation, form controls, ads, related-posts and similar blocks from
+/// the document. Forms themselves are kept, some CMSs wrap the whole page in one.
+///
+/// # Arguments
+///
+/// * `document` - Parsed HTML document that is modified in place.
+pub fn strip_boilerplate(document: &mut Html) {
+    let Ok(tags) = Selector::parse(BOILERPLATE_TAGS) else {
+        return;
+    };
+    let Ok(all) = Selector::parse("*") else {
+        return;
+    };
+
+    let mut ids: Vec<_> = document.select(&tags).map(|el| el.id()).collect();
+    ids.extend(
+        document
+            .select(&all)
+            .filter(is_boilerplate_container)
+            .map(|el| el.id()),
+    );
+
+    for id in ids {
+        if let Some(mut node) = document.tree.get_mut(id) {
+            node.detach();
+        }
+    }
+}
+
+/// Converts an HTML fragment (for example an RSS item description) into clean plain text.
+///
+/// # Arguments
+///
+/// * `html` - HTML fragment or plain text.
+///
+/// # Returns
+///
+/// Text without markup and boilerplate, with entities decoded and whitespace normalized.
+pub fn sanitize_html(html: &str) -> String {
+    let mut fragment = Html::parse_fragment(html);
+    strip_boilerplate(&mut fragment);
+    normalize_text(&fragment.root_element().text().collect::<Vec<_>>().join(" "))
+}
+
+/// Normalizes whitespace and removes invisible characters from the text.
+///
+/// # Arguments
+///
+/// * `text` - Text to normalize.
+///
+/// # Returns
+///
+/// Text with zero width characters removed and any whitespace run collapsed to a single space.
+pub fn normalize_text(text: &str) -> String {
+    text.chars()
+        .filter(|c| {
+            !matches!(
+                c,
+                '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{FEFF}' | '\u{00AD}'
+            )
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn is_boilerplate_container(element: &ElementRef) -> bool {
+    let value = element.value();
+    if PROTECTED_TAGS.contains(&value.name()) {
+        return false;
+    }
+
+    [value.attr("class"), value.attr("id"), value.attr("role")]
+        .into_iter()
+        .flatten()
+        .any(|hint| BOILERPLATE_HINTS.is_match(hint))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_html_removes_boilerplate() {
+        let html = r#"<p>Markets&nbsp;rose &amp; fell.</p>
+            <script>var tracking = 1;</script>
+            <style>p { color: red; }</style>
+            <div class="related-posts"><a href="/x">Read more</a></div>
+            <div class="ad-slot">Buy now</div>"#;
+
+        assert_eq!(sanitize_html(html), "Markets rose & fell.");
+    }
+
+    #[test]
+    fn test_sanitize_html_keeps_form_content() {
+        let html = r#"<form id="aspnetForm"><p>Rates held steady.</p>
+            <input type="hidden" value="state"><button>Search</button></form>"#;
+
+        assert_eq!(sanitize_html(html), "Rates held steady.");
+    }
+
+    #[test]
+    fn test_sanitize_html_keeps_legitimate_text() {
+        let html = "Price <b>x < y</b> | volume 3 > 2";
+
+        assert_eq!(sanitize_html(html), "Price x < y | volume 3 > 2");
+    }
+
+    #[test]
+    fn test_normalize_text() {
+        assert_eq!(
+            normalize_text("  a\u{200B}b \n\t c\u{00A0}d  "),
+            "ab c d".to_string()
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+const SHINGLE_SIZE: usize = 3;
+
+/// Default maximum Hamming distance between fingerprints of near-duplicate texts.
+pub const NEAR_DUPLICATE_DISTANCE: u32 = 3;
+
+/// Computes the 64 bit SimHash fingerprint of the text.
+///
+/// Features are lowercase word shingles weighted by their frequency, so texts that share most of
+/// their wording produce fingerprints with a small Hamming distance.
+///
+/// # Arguments
+///
+/// * `text` - Text to fingerprint.
+///
+/// # Returns
+///
+/// The fingerprint, `0` for a text without words.
+pub fn simhash(text: &str) -> u64 {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let mut features: HashMap<u64, i64> = HashMap::new();
+    if words.len() < SHINGLE_SIZE {
+        for word in &words {
+            *features.entry(fnv1a(word.as_bytes())).or_default() += 1;
+        }
+    } else {
+        for shingle in words.windows(SHINGLE_SIZE) {
+            *features
+                .entry(fnv1a(shingle.join(" ").as_bytes()))
+                .or_default() += 1;
+        }
+    }
+
+    if features.is_empty() {
+        return 0;
+    }
+
+    let mut vector = [0i64; 64];
+    for (hash, weight) in features {
+        for (bit, value) in vector.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 {
+                *value += weight;
+            } else {
+                *value -= weight;
+            }
+        }
+    }
+
+    vector
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| **value > 0)
+        .fold(0u64, |acc, (bit, _)| acc | (1 << bit))
+}
+
+/// Counts the differing bits of two fingerprints.
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Checks if two fingerprints are at most `max_distance` bits apart.
+pub fn is_near_duplicate(a: u64, b: u64, max_distance: u32) -> bool {
+    hamming_distance(a, b) <= max_distance
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STORY: &str = "The Securities and Exchange Commission approved the first spot ether \
+        exchange traded funds on Tuesday, clearing the way for trading to begin as soon as next \
+        week after months of back and forth with issuers about the structure of the products.";
+
+    #[test]
+    fn test_simhash_near_duplicates() {
+        let syndicated = STORY.replace("Tuesday", "Tuesday afternoon");
+        let unrelated = "Local football club wins the regional championship after a dramatic \
+            penalty shootout in front of a sold out home crowd on Saturday evening.";
+
+        let original = simhash(STORY);
+
+        assert_eq!(original, simhash(&STORY.to_uppercase()));
+        assert!(
+            hamming_distance(original, simhash(&syndicated))
+                < hamming_distance(original, simhash(unrelated))
+        );
+        assert!(!is_near_duplicate(
+            original,
+            simhash(unrelated),
+            NEAR_DUPLICATE_DISTANCE
+        ));
+    }
+
+    #[test]
+    fn test_simhash_empty() {
+        assert_eq!(simhash(""), 0);
+        assert_eq!(simhash(" ,.! "), 0);
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+        assert!(is_near_duplicate(0b1011, 0b0001, 2));
+        assert!(!is_near_duplicate(0b1011, 0b0001, 1));
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// SitemapEntry is a page listed in a `urlset` sitemap.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SitemapEntry {
+    pub loc: String,
+    /// Last modification time in milliseconds since the Unix epoch.
+    pub lastmod: Option<i64>,
+    pub changefreq: Option<String>,
+    /// Priority of the page relative to the other pages of the site in range `0.0..=1.0`.
+    pub priority: Option<f32>,
+}
+
+/// SitemapIndexEntry is a child sitemap listed in a `sitemapindex`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct SitemapIndexEntry {
+    pub loc: String,
+    /// Last modification time in milliseconds since the Unix epoch.
+    pub lastmod: Option<i64>,
+}
+
+/// Sitemap is a parsed sitemap document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sitemap {
+    /// Pages of the site.
+    UrlSet(Vec<SitemapEntry>),
+    /// Child sitemaps that have to be fetched and parsed on their own.
+    Index(Vec<SitemapIndexEntry>),
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawSitemap {
+    #[serde(default)]
+    url: Vec<RawEntry>,
+    #[serde(default)]
+    sitemap: Vec<RawEntry>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawEntry {
+    #[serde(default)]
+    loc: String,
+    lastmod: Option<String>,
+    changefreq: Option<String>,
+    priority: Option<String>,
+}
+
+/// Parses a sitemap or a sitemap index document.
+///
+/// Entries without a location are skipped, malformed `lastmod` and `priority` values are ignored.
+///
+/// # Arguments
+///
+/// * `xml` - The sitemap XML.
+///
+/// # Returns
+///
+/// A `Result` containing the parsed `Sitemap`, or an `anyhow::Error` if the XML is malformed.
+pub fn parse_sitemap(xml: &str) -> Result<Sitemap> {
+    let raw: RawSitemap = quick_xml::de::from_str(xml)?;
+
+    if !raw.sitemap.is_empty() {
+        return Ok(Sitemap::Index(
+            raw.sitemap
+                .into_iter()
+                .filter(|entry| !entry.loc.trim().is_empty())
+                .map(|entry| SitemapIndexEntry {
+                    loc: entry.loc.trim().to_string(),
+                    lastmod: entry.lastmod.as_deref().and_then(parse_lastmod),
+                })
+                .collect(),
+        ));
+    }
+
+    Ok(Sitemap::UrlSet(
+        raw.url
+            .into_iter()
+            .filter(|entry| !entry.loc.trim().is_empty())
+            .map(|entry| SitemapEntry {
+                loc: entry.loc.trim().to_string(),
+                lastmod: entry.lastmod.as_deref().and_then(parse_lastmod),
+                changefreq: entry
+                    .changefreq
+                    .map(|value| value.trim().to_lowercase())
+                    .filter(|value| !value.is_empty()),
+                priority: entry
+                    .priority
+                    .and_then(|value| value.trim().parse::<f32>().ok())
+                    .filter(|value| (0.0..=1.0).contains(value)),
+            })
+            .collect(),
+    ))
+}
+
+fn parse_lastmod(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.timestamp_millis());
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|dt| dt.and_utc().timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sitemap_urlset() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+                    xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+                <url>
+                    <loc>https://news.example.com/eth-etf</loc>
+                    <lastmod>2024-05-21T14:00:00+00:00</lastmod>
+                    <changefreq>Daily</changefreq>
+                    <priority>0.8</priority>
+                    <image:image><image:loc>https://news.example.com/eth.png</image:loc></image:image>
+                </url>
+                <url>
+                    <loc> https://news.example.com/about </loc>
+                    <lastmod>2024-05-01</lastmod>
+                    <priority>high</priority>
+                </url>
+                <url><lastmod>2024-05-01</lastmod></url>
+            </urlset>"#;
+
+        let Sitemap::UrlSet(entries) = parse_sitemap(xml).unwrap() else {
+            panic!("expected urlset");
+        };
+
+        assert_eq!(
+            entries,
+            vec![
+                SitemapEntry {
+                    loc: "https://news.example.com/eth-etf".to_string(),
+                    lastmod: Some(1716300000000),
+                    changefreq: Some("daily".to_string()),
+                    priority: Some(0.8),
+                },
+                SitemapEntry {
+                    loc: "https://news.example.com/about".to_string(),
+                    lastmod: Some(1714521600000),
+                    changefreq: None,
+                    priority: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_sitemap_index() {
+        let xml = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <sitemap>
+                    <loc>https://news.example.com/sitemap-2024-05.xml</loc>
+                    <lastmod>2024-05-21T14:00:00Z</lastmod>
+                </sitemap>
+                <sitemap><loc>https://news.example.com/sitemap-2024-04.xml</loc></sitemap>
+            </sitemapindex>"#;
+
+        assert_eq!(
+            parse_sitemap(xml).unwrap(),
+            Sitemap::Index(vec![
+                SitemapIndexEntry {
+                    loc: "https://news.example.com/sitemap-2024-05.xml".to_string(),
+                    lastmod: Some(1716300000000),
+                },
+                SitemapIndexEntry {
+                    loc: "https://news.example.com/sitemap-2024-04.xml".to_string(),
+                    lastmod: None,
+                },
+            ])
+        );
+        assert!(parse_sitemap("<urlset><url>").is_err());
+    }
+}
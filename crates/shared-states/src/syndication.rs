@@ -0,0 +1,425 @@
+use anyhow::{Result, anyhow};
+use atom_syndication::{Entry, Feed, Link, Person};
+use rss::{Category, Channel, Enclosure, Guid, Item, extension::atom::AtomExtension};
+use serde::Deserialize;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// FeedFormat is the syndication format of a feed document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeedFormat {
+    /// RSS 0.9x, 1.0 (RDF) or 2.0.
+    Rss,
+    Atom,
+    /// JSON Feed 1.0 or 1.1.
+    JsonFeed,
+}
+
+impl FeedFormat {
+    /// Detects the format from the document, a JSON object is a JSON Feed, an XML document with
+    /// a `feed` root element is Atom and anything else is tried as RSS.
+    pub fn sniff(document: &[u8]) -> Self {
+        let document = document.strip_prefix(UTF8_BOM).unwrap_or(document);
+        let start = document
+            .iter()
+            .position(|byte| !byte.is_ascii_whitespace())
+            .unwrap_or(document.len());
+        if document[start..].starts_with(b"{") {
+            return Self::JsonFeed;
+        }
+
+        match root_element(&document[start..]) {
+            Some(name) if name == b"feed" => Self::Atom,
+            _ => Self::Rss,
+        }
+    }
+}
+
+/// Parses an RSS, Atom or JSON Feed document into an RSS channel.
+///
+/// Atom and JSON Feed documents are converted, so every format is processed the same way. The
+/// canonical feed URL is kept as the `atom:link rel="self"` of the channel.
+///
+/// # Arguments
+/// * `document` - The raw feed document.
+///
+/// # Returns
+/// The channel, or an error if the document is not a valid feed of the sniffed format.
+pub fn parse_feed(document: &[u8]) -> Result<Channel> {
+    match FeedFormat::sniff(document) {
+        FeedFormat::Rss => Ok(Channel::read_from(document)?),
+        FeedFormat::Atom => Ok(atom_to_channel(&Feed::read_from(document)?)),
+        FeedFormat::JsonFeed => {
+            let document = document.strip_prefix(UTF8_BOM).unwrap_or(document);
+            let feed: JsonFeed = serde_json::from_slice(document)?;
+            if !feed.version.starts_with("https://jsonfeed.org/version/") {
+                return Err(anyhow!(
+                    "Unsupported JSON Feed version ( {} )",
+                    feed.version
+                ));
+            }
+            Ok(json_feed_to_channel(feed))
+        }
+    }
+}
+
+/// Local name of the first element, skipping the prolog, comments and processing instructions.
+fn root_element(document: &[u8]) -> Option<&[u8]> {
+    let mut rest = document;
+    loop {
+        let open = rest.iter().position(|byte| *byte == b'<')?;
+        rest = &rest[open + 1..];
+        match rest.first()? {
+            b'?' | b'!' => continue,
+            _ => {
+                let end = rest
+                    .iter()
+                    .position(|byte| byte.is_ascii_whitespace() || matches!(byte, b'>' | b'/'))
+                    .unwrap_or(rest.len());
+                let name = &rest[..end];
+                let local = name
+                    .iter()
+                    .rposition(|byte| *byte == b':')
+                    .map_or(name, |colon| &name[colon + 1..]);
+                return Some(local);
+            }
+        }
+    }
+}
+
+fn atom_to_channel(feed: &Feed) -> Channel {
+    let mut channel = Channel::default();
+    channel.set_title(feed.title().as_str().trim());
+    channel.set_link(alternate_link(feed.links()).unwrap_or_default());
+    channel.set_description(
+        feed.subtitle()
+            .map(|subtitle| subtitle.as_str())
+            .unwrap_or_default(),
+    );
+    channel.set_language(feed.lang().map(str::to_string));
+    channel.set_last_build_date(feed.updated().to_rfc2822());
+    channel.set_atom_ext(self_link(
+        feed.links()
+            .iter()
+            .find(|link| link.rel() == "self")
+            .map(Link::href),
+    ));
+    channel.set_items(
+        feed.entries()
+            .iter()
+            .map(atom_entry_to_item)
+            .collect::<Vec<_>>(),
+    );
+    channel
+}
+
+fn atom_entry_to_item(entry: &Entry) -> Item {
+    let mut item = Item::default();
+    item.set_title(entry.title().as_str().trim().to_string());
+    item.set_link(alternate_link(entry.links()));
+    item.set_description(
+        entry
+            .summary()
+            .map(|summary| summary.as_str().to_string())
+            .or_else(|| {
+                entry
+                    .content()
+                    .and_then(|content| content.value())
+                    .map(str::to_string)
+            }),
+    );
+    item.set_pub_date(entry.published().unwrap_or(entry.updated()).to_rfc2822());
+    item.set_author(entry.authors().first().map(person_author));
+    item.set_categories(
+        entry
+            .categories()
+            .iter()
+            .map(|category| category_of(category.label().unwrap_or(category.term())))
+            .collect::<Vec<_>>(),
+    );
+    item.set_guid(guid_of(entry.id()));
+    item.set_enclosure(
+        entry
+            .links()
+            .iter()
+            .find(|link| link.rel() == "enclosure")
+            .map(|link| {
+                enclosure_of(
+                    link.href(),
+                    link.length().unwrap_or("0"),
+                    link.mime_type().unwrap_or_default(),
+                )
+            }),
+    );
+    item
+}
+
+/// The `alternate` link, the default relation of Atom links.
+fn alternate_link(links: &[Link]) -> Option<String> {
+    links
+        .iter()
+        .find(|link| link.rel() == "alternate")
+        .map(|link| link.href().to_string())
+}
+
+/// Author as `email (name)`, the RSS convention `Author::parse` understands.
+fn person_author(person: &Person) -> String {
+    match person.email() {
+        Some(email) if !person.name().is_empty() => format!("{email} ({})", person.name()),
+        Some(email) => email.to_string(),
+        None => person.name().to_string(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFeed {
+    version: String,
+    #[serde(default)]
+    title: String,
+    home_page_url: Option<String>,
+    feed_url: Option<String>,
+    description: Option<String>,
+    language: Option<String>,
+    /// Authors of JSON Feed 1.1.
+    #[serde(default)]
+    authors: Vec<JsonFeedAuthor>,
+    /// Author of JSON Feed 1.0.
+    author: Option<JsonFeedAuthor>,
+    #[serde(default)]
+    items: Vec<JsonFeedItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFeedItem {
+    id: serde_json::Value,
+    url: Option<String>,
+    title: Option<String>,
+    content_html: Option<String>,
+    content_text: Option<String>,
+    summary: Option<String>,
+    date_published: Option<String>,
+    date_modified: Option<String>,
+    #[serde(default)]
+    authors: Vec<JsonFeedAuthor>,
+    author: Option<JsonFeedAuthor>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    attachments: Vec<JsonFeedAttachment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFeedAuthor {
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonFeedAttachment {
+    url: String,
+    mime_type: String,
+    size_in_bytes: Option<u64>,
+}
+
+fn json_feed_to_channel(feed: JsonFeed) -> Channel {
+    let feed_author = feed
+        .authors
+        .into_iter()
+        .chain(feed.author)
+        .find_map(|author| author.name);
+
+    let mut channel = Channel::default();
+    channel.set_title(feed.title.trim());
+    channel.set_link(feed.home_page_url.unwrap_or_default());
+    channel.set_description(feed.description.unwrap_or_default());
+    channel.set_language(feed.language);
+    channel.set_atom_ext(self_link(feed.feed_url.as_deref()));
+    channel.set_items(
+        feed.items
+            .into_iter()
+            .map(|item| json_feed_item_to_item(item, feed_author.as_deref()))
+            .collect::<Vec<_>>(),
+    );
+    channel
+}
+
+fn json_feed_item_to_item(feed_item: JsonFeedItem, feed_author: Option<&str>) -> Item {
+    let id = match feed_item.id {
+        serde_json::Value::String(id) => id,
+        id => id.to_string(),
+    };
+
+    let mut item = Item::default();
+    item.set_title(feed_item.title.map(|title| title.trim().to_string()));
+    item.set_link(feed_item.url);
+    item.set_description(
+        feed_item
+            .summary
+            .or(feed_item.content_html)
+            .or(feed_item.content_text),
+    );
+    item.set_pub_date(feed_item.date_published.or(feed_item.date_modified));
+    item.set_author(
+        feed_item
+            .authors
+            .into_iter()
+            .chain(feed_item.author)
+            .find_map(|author| author.name)
+            .or_else(|| feed_author.map(str::to_string)),
+    );
+    item.set_categories(
+        feed_item
+            .tags
+            .iter()
+            .map(|tag| category_of(tag))
+            .collect::<Vec<_>>(),
+    );
+    item.set_guid(guid_of(&id));
+    item.set_enclosure(feed_item.attachments.first().map(|attachment| {
+        enclosure_of(
+            &attachment.url,
+            &attachment.size_in_bytes.unwrap_or_default().to_string(),
+            &attachment.mime_type,
+        )
+    }));
+    item
+}
+
+fn self_link(href: Option<&str>) -> Option<AtomExtension> {
+    let href = href.map(str::trim).filter(|href| !href.is_empty())?;
+    let mut link = Link::default();
+    link.set_href(href);
+    link.set_rel("self");
+    Some(AtomExtension { links: vec![link] })
+}
+
+fn category_of(name: &str) -> Category {
+    let mut category = Category::default();
+    category.set_name(name);
+    category
+}
+
+fn guid_of(id: &str) -> Option<Guid> {
+    let id = id.trim();
+    if id.is_empty() {
+        return None;
+    }
+    let mut guid = Guid::default();
+    guid.set_value(id);
+    guid.set_permalink(false);
+    Some(guid)
+}
+
+fn enclosure_of(url: &str, length: &str, mime_type: &str) -> Enclosure {
+    let mut enclosure = Enclosure::default();
+    enclosure.set_url(url);
+    enclosure.set_length(length);
+    enclosure.set_mime_type(mime_type);
+    enclosure
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{RssChannelInfo, RssItem};
+
+    const ATOM: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+        <!-- generated -->
+        <feed xmlns="http://www.w3.org/2005/Atom" xml:lang="en">
+            <title>Example Atom</title>
+            <subtitle>Markets and more</subtitle>
+            <link href="https://news.example.com/"/>
+            <link rel="self" href="https://news.example.com/atom.xml"/>
+            <id>urn:uuid:60a76c80-d399-11d9-b93C-0003939e0af6</id>
+            <updated>2024-05-21T14:00:00Z</updated>
+            <entry>
+                <title>Fed holds rates</title>
+                <link href="https://news.example.com/fed"/>
+                <link rel="enclosure" href="https://news.example.com/fed.mp3" length="1024" type="audio/mpeg"/>
+                <id>urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a</id>
+                <published>2024-05-21T13:30:00Z</published>
+                <updated>2024-05-21T13:45:00Z</updated>
+                <summary>Rates stay put.</summary>
+                <author><name>Jane Doe</name><email>jane@example.com</email></author>
+                <category term="markets"/>
+            </entry>
+        </feed>"#;
+
+    const JSON_FEED: &str = r#"{
+        "version": "https://jsonfeed.org/version/1.1",
+        "title": "Example JSON",
+        "home_page_url": "https://news.example.com/",
+        "feed_url": "https://news.example.com/feed.json",
+        "authors": [{"name": "Newsroom"}],
+        "items": [
+            {
+                "id": 42,
+                "url": "https://news.example.com/ecb",
+                "title": "ECB cuts rates",
+                "content_html": "<p>Rates go down.</p>",
+                "date_published": "2024-05-21T13:30:00Z",
+                "tags": ["Markets", "ECB"]
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_feed_format_sniff() {
+        assert_eq!(FeedFormat::sniff(ATOM.as_bytes()), FeedFormat::Atom);
+        assert_eq!(
+            FeedFormat::sniff(JSON_FEED.as_bytes()),
+            FeedFormat::JsonFeed
+        );
+        assert_eq!(
+            FeedFormat::sniff(b"\xEF\xBB\xBF<?xml version=\"1.0\"?><rss version=\"2.0\"></rss>"),
+            FeedFormat::Rss
+        );
+        assert_eq!(
+            FeedFormat::sniff(b"<atom:feed xmlns:atom=\"http://www.w3.org/2005/Atom\"/>"),
+            FeedFormat::Atom
+        );
+        assert_eq!(FeedFormat::sniff(b""), FeedFormat::Rss);
+    }
+
+    #[test]
+    fn test_parse_feed_atom() {
+        let channel = parse_feed(ATOM.as_bytes()).unwrap();
+        let info = RssChannelInfo::new(&channel, "https://mirror.example.com/atom");
+        assert_eq!(info.title, "Example Atom");
+        assert_eq!(info.link, "https://news.example.com/");
+        assert_eq!(info.description, "Markets and more");
+        assert_eq!(info.language, "en");
+        assert_eq!(info.self_url, "https://news.example.com/atom.xml");
+        assert_eq!(info.last_build_timestamp, Some(1716300000000));
+
+        let item = RssItem::try_from(&channel.items()[0]).unwrap();
+        assert_eq!(item.title, "Fed holds rates");
+        assert_eq!(item.link, "https://news.example.com/fed");
+        assert_eq!(item.description, "Rates stay put.");
+        assert_eq!(item.published_timestamp, 1716298200000);
+        assert!(!item.published_timestamp_estimated);
+        assert_eq!(item.author.name, "Jane Doe");
+        assert_eq!(item.category, vec!["markets".to_string()]);
+        assert_eq!(
+            item.enclosure_url.as_deref(),
+            Some("https://news.example.com/fed.mp3")
+        );
+    }
+
+    #[test]
+    fn test_parse_feed_json_feed() {
+        let channel = parse_feed(JSON_FEED.as_bytes()).unwrap();
+        let info = RssChannelInfo::new(&channel, "https://news.example.com/feed.json");
+        assert_eq!(info.title, "Example JSON");
+        assert_eq!(info.self_url, "https://news.example.com/feed.json");
+
+        let item = RssItem::try_from(&channel.items()[0]).unwrap();
+        assert_eq!(item.title, "ECB cuts rates");
+        assert_eq!(item.link, "https://news.example.com/ecb");
+        assert_eq!(item.description, "Rates go down.");
+        assert_eq!(item.published_timestamp, 1716298200000);
+        assert_eq!(item.author.name, "Newsroom");
+        assert_eq!(channel.items()[0].guid().unwrap().value(), "42");
+
+        assert!(parse_feed(br#"{"version": "1", "items": []}"#).is_err());
+    }
+}
@@ -0,0 +1,589 @@
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::env;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Subject of the [`TelegramMessage`] events, no worker publishes them yet.
+pub const TELEGRAM_QUEUE_NAME: &str = "telegram_messages";
+
+/// TelegramEntity is a formatted part of the text of a Telegram message, e.g. a link, a mention
+/// or a hashtag.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct TelegramEntity {
+    /// Type of the entity as named by the Bot API, e.g. `url`, `text_link`, `mention` or
+    /// `hashtag`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Offset of the entity in the text in UTF-16 code units.
+    pub offset: usize,
+    /// Length of the entity in UTF-16 code units.
+    pub length: usize,
+    /// Target of `text_link` entities.
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+impl TelegramEntity {
+    /// Part of the text the entity covers, None if the entity is outside of the text.
+    ///
+    /// # Arguments
+    /// * `text` - The text of the message the entity belongs to.
+    pub fn text<'a>(&self, text: &'a str) -> Option<&'a str> {
+        let (mut start, mut end) = (None, None);
+        let mut units = 0;
+        for (index, char) in text.char_indices() {
+            if units == self.offset {
+                start = Some(index);
+            }
+            if units == self.offset + self.length {
+                end = Some(index);
+                break;
+            }
+            units += char.len_utf16();
+        }
+        if units == self.offset + self.length {
+            end = end.or(Some(text.len()));
+        }
+        text.get(start?..end?)
+    }
+}
+
+/// TelegramMessage represents a message read from a Telegram channel.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct TelegramMessage {
+    /// Identifier of the chat of the channel, negative for channels and groups.
+    #[serde(default)]
+    pub chat_id: i64,
+    /// Username of the channel.
+    pub channel: String,
+    pub message_id: i64,
+    pub text: String,
+    #[serde(default)]
+    pub entities: Vec<TelegramEntity>,
+    /// URLs linked from the text, see [`TelegramMessage::links_from_entities`].
+    #[serde(default)]
+    pub links: Vec<String>,
+    pub author: String,
+    /// Public link of the message.
+    pub link: String,
+    pub published_timestamp: i64,
+    /// Time of the latest edit of the message, None if it was never edited.
+    #[serde(default)]
+    pub edited_timestamp: Option<i64>,
+    pub fetched_timestamp: i64,
+}
+
+impl TelegramMessage {
+    /// URLs the text links to, the targets of `text_link` entities and the text of `url`
+    /// entities, in the order of the text without duplicates.
+    ///
+    /// # Arguments
+    /// * `text` - The text of the message.
+    /// * `entities` - The entities of the text.
+    pub fn links_from_entities(text: &str, entities: &[TelegramEntity]) -> Vec<String> {
+        let mut links: Vec<String> = Vec::new();
+        for entity in entities {
+            let link = match entity.kind.as_str() {
+                "text_link" => entity.url.as_deref(),
+                "url" => entity.text(text),
+                _ => None,
+            };
+            if let Some(link) = link.map(str::trim).filter(|link| !link.is_empty())
+                && !links.iter().any(|known| known == link)
+            {
+                links.push(link.to_string());
+            }
+        }
+        links
+    }
+}
+
+/// TelegramChannels is the allowlist of the channels and chats messages are to be ingested from,
+/// the syntax of the planned `TELEGRAM_CHANNELS` setting of the telegram worker.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TelegramChannels {
+    /// Lowercase usernames without the `@`.
+    usernames: BTreeSet<String>,
+    chat_ids: BTreeSet<i64>,
+}
+
+impl TelegramChannels {
+    /// Parses a comma separated list of channel usernames, given as `name`, `@name` or
+    /// `https://t.me/name`, and numeric chat ids.
+    ///
+    /// # Arguments
+    /// * `value` - The list.
+    ///
+    /// # Returns
+    /// The allowlist, or an error naming the first entry that is neither a username nor a chat id.
+    pub fn parse(value: &str) -> Result<Self> {
+        let mut channels = Self::default();
+        for entry in value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            if let Ok(chat_id) = entry.parse::<i64>() {
+                channels.chat_ids.insert(chat_id);
+                continue;
+            }
+            let username = entry
+                .trim_start_matches("https://")
+                .trim_start_matches("http://")
+                .trim_start_matches("t.me/")
+                .trim_start_matches('@')
+                .trim_end_matches('/');
+            if !is_username(username) {
+                return Err(anyhow!("Invalid Telegram channel ( {entry} )"));
+            }
+            channels.usernames.insert(username.to_lowercase());
+        }
+        Ok(channels)
+    }
+
+    /// Whether messages of the chat are ingested, usernames match case-insensitively.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat of the message.
+    /// * `username` - The username of the chat, empty for private chats and groups without one.
+    pub fn allows(&self, chat_id: i64, username: &str) -> bool {
+        self.chat_ids.contains(&chat_id)
+            || (!username.is_empty()
+                && self
+                    .usernames
+                    .contains(&username.trim_start_matches('@').to_lowercase()))
+    }
+
+    /// Usernames of the allowed channels, to be resolved to chats and joined by the worker.
+    pub fn usernames(&self) -> impl Iterator<Item = &str> {
+        self.usernames.iter().map(String::as_str)
+    }
+
+    pub fn chat_ids(&self) -> impl Iterator<Item = i64> {
+        self.chat_ids.iter().copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.usernames.is_empty() && self.chat_ids.is_empty()
+    }
+}
+
+/// Telegram usernames have 5 to 32 letters, digits and underscores and start with a letter.
+fn is_username(value: &str) -> bool {
+    (5..=32).contains(&value.len())
+        && value.starts_with(|char: char| char.is_ascii_alphabetic())
+        && value
+            .chars()
+            .all(|char| char.is_ascii_alphanumeric() || char == '_')
+}
+
+/// TelegramAuthState is an authorization state of TDLib, as sent in the
+/// `updateAuthorizationState` updates of its JSON interface.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "@type")]
+pub enum TelegramAuthState {
+    #[serde(rename = "authorizationStateWaitTdlibParameters")]
+    WaitTdlibParameters,
+    #[serde(rename = "authorizationStateWaitPhoneNumber")]
+    WaitPhoneNumber,
+    #[serde(rename = "authorizationStateWaitCode")]
+    WaitCode,
+    #[serde(rename = "authorizationStateWaitPassword")]
+    WaitPassword {
+        #[serde(default)]
+        password_hint: String,
+    },
+    #[serde(rename = "authorizationStateReady")]
+    Ready,
+    #[serde(rename = "authorizationStateClosed")]
+    Closed,
+    /// States needing no answer, e.g. logging out or closing.
+    #[serde(other)]
+    Other,
+}
+
+/// TelegramAuthRequest is the TDLib function answering an authorization state.
+///
+/// It does not implement `Debug` so the code and the password do not end up in logs.
+#[derive(Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "@type", rename_all = "camelCase")]
+pub enum TelegramAuthRequest {
+    SetTdlibParameters {
+        database_directory: String,
+        use_file_database: bool,
+        use_chat_info_database: bool,
+        use_message_database: bool,
+        api_id: i32,
+        api_hash: String,
+        system_language_code: String,
+        device_model: String,
+        application_version: String,
+    },
+    SetAuthenticationPhoneNumber {
+        phone_number: String,
+    },
+    CheckAuthenticationCode {
+        code: String,
+    },
+    CheckAuthenticationPassword {
+        password: String,
+    },
+}
+
+/// TelegramAuthConfig holds the login settings of the telegram worker.
+#[derive(Clone)]
+pub struct TelegramAuthConfig {
+    pub api_id: i32,
+    pub api_hash: String,
+    /// Phone number of the account in international format, asked for when unset.
+    pub phone_number: Option<String>,
+    /// Login code sent by Telegram, asked for when unset.
+    pub code: Option<String>,
+    /// 2FA password, asked for when unset.
+    pub password: Option<String>,
+    /// Directory TDLib keeps the session in, kept across restarts so the login is done once.
+    pub database_dir: PathBuf,
+}
+
+impl TelegramAuthConfig {
+    /// Reads `TELEGRAM_API_ID`, `TELEGRAM_API_HASH`, `TELEGRAM_PHONE_NUMBER`, `TELEGRAM_CODE`,
+    /// `TELEGRAM_PASSWORD` and `TELEGRAM_DATABASE_DIR`, the database directory defaults to
+    /// `tdlib`.
+    pub fn from_env() -> Result<Self> {
+        let optional = |name| env::var(name).ok().filter(|value| !value.trim().is_empty());
+
+        Ok(Self {
+            api_id: env::var("TELEGRAM_API_ID")
+                .context("TELEGRAM_API_ID is required")?
+                .parse()
+                .context("TELEGRAM_API_ID must be a valid number")?,
+            api_hash: env::var("TELEGRAM_API_HASH").context("TELEGRAM_API_HASH is required")?,
+            phone_number: optional("TELEGRAM_PHONE_NUMBER"),
+            code: optional("TELEGRAM_CODE"),
+            password: optional("TELEGRAM_PASSWORD"),
+            database_dir: optional("TELEGRAM_DATABASE_DIR")
+                .unwrap_or_else(|| "tdlib".to_string())
+                .into(),
+        })
+    }
+}
+
+/// TelegramAuthPrompt asks for the login secrets missing from the configuration.
+pub trait TelegramAuthPrompt {
+    /// Answer to the question, trimmed.
+    fn ask(&mut self, question: &str) -> Result<String>;
+}
+
+/// ConsoleAuthPrompt asks on stdout and reads the answers from stdin.
+pub struct ConsoleAuthPrompt;
+
+impl TelegramAuthPrompt for ConsoleAuthPrompt {
+    fn ask(&mut self, question: &str) -> Result<String> {
+        let mut stdout = io::stdout();
+        write!(stdout, "{question}: ")?;
+        stdout.flush()?;
+
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Err(anyhow!("No answer to ( {question} ), stdin is closed"));
+        }
+        Ok(answer.trim().to_string())
+    }
+}
+
+/// TelegramAuth answers the authorization states of TDLib until the login is done.
+///
+/// Secrets of the configuration are used once, when TDLib asks again they were rejected and the
+/// prompt is asked instead.
+pub struct TelegramAuth<P> {
+    config: TelegramAuthConfig,
+    prompt: P,
+}
+
+impl<P: TelegramAuthPrompt> TelegramAuth<P> {
+    pub fn new(config: TelegramAuthConfig, prompt: P) -> Self {
+        Self { config, prompt }
+    }
+
+    /// Request answering the authorization state.
+    ///
+    /// # Arguments
+    /// * `state` - The latest authorization state of TDLib.
+    ///
+    /// # Returns
+    /// The request, None if the state needs no answer, or an error if the session was closed,
+    /// the database directory could not be created or the prompt failed.
+    pub fn next(&mut self, state: &TelegramAuthState) -> Result<Option<TelegramAuthRequest>> {
+        let request = match state {
+            TelegramAuthState::WaitTdlibParameters => {
+                let database_dir = &self.config.database_dir;
+                std::fs::create_dir_all(database_dir).with_context(|| {
+                    format!(
+                        "Failed to create the TDLib database directory ( {} )",
+                        database_dir.display()
+                    )
+                })?;
+                TelegramAuthRequest::SetTdlibParameters {
+                    database_directory: database_dir.to_string_lossy().into_owned(),
+                    use_file_database: true,
+                    use_chat_info_database: true,
+                    use_message_database: true,
+                    api_id: self.config.api_id,
+                    api_hash: self.config.api_hash.clone(),
+                    system_language_code: "en".to_string(),
+                    device_model: "Semantic Machine".to_string(),
+                    application_version: env!("CARGO_PKG_VERSION").to_string(),
+                }
+            }
+            TelegramAuthState::WaitPhoneNumber => {
+                TelegramAuthRequest::SetAuthenticationPhoneNumber {
+                    phone_number: self.secret(|config| &mut config.phone_number, "Phone number")?,
+                }
+            }
+            TelegramAuthState::WaitCode => TelegramAuthRequest::CheckAuthenticationCode {
+                code: self.secret(|config| &mut config.code, "Login code")?,
+            },
+            TelegramAuthState::WaitPassword { password_hint } => {
+                let question = if password_hint.is_empty() {
+                    "2FA password".to_string()
+                } else {
+                    format!("2FA password (hint: {password_hint})")
+                };
+                TelegramAuthRequest::CheckAuthenticationPassword {
+                    password: self.secret(|config| &mut config.password, &question)?,
+                }
+            }
+            TelegramAuthState::Ready | TelegramAuthState::Other => return Ok(None),
+            TelegramAuthState::Closed => {
+                return Err(anyhow!(
+                    "Telegram session closed before the login completed"
+                ));
+            }
+        };
+
+        Ok(Some(request))
+    }
+
+    /// The configured secret the first time it is needed, the answer to the question after.
+    fn secret(
+        &mut self,
+        field: impl Fn(&mut TelegramAuthConfig) -> &mut Option<String>,
+        question: &str,
+    ) -> Result<String> {
+        match field(&mut self.config).take() {
+            Some(secret) => Ok(secret),
+            None => self.prompt.ask(question),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links_from_entities() {
+        let text = "🚀 ETF approved: https://news.example.com/etf, details here #ETH";
+        let entity = |kind: &str, offset, length, url: Option<&str>| TelegramEntity {
+            kind: kind.to_string(),
+            offset,
+            length,
+            url: url.map(str::to_string),
+        };
+        let entities = vec![
+            entity("url", 17, 28, None),
+            entity(
+                "text_link",
+                47,
+                12,
+                Some("https://news.example.com/etf-details"),
+            ),
+            entity("hashtag", 60, 4, None),
+            entity("url", 17, 28, None),
+            entity("url", 60, 10, None),
+        ];
+
+        assert_eq!(entities[0].text(text), Some("https://news.example.com/etf"));
+        assert_eq!(entities[2].text(text), Some("#ETH"));
+        assert_eq!(entities[4].text(text), None);
+        assert_eq!(
+            TelegramMessage::links_from_entities(text, &entities),
+            vec![
+                "https://news.example.com/etf".to_string(),
+                "https://news.example.com/etf-details".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_telegram_message_round_trip() {
+        let message = TelegramMessage {
+            chat_id: -1001234567890,
+            channel: "crypto_news".to_string(),
+            message_id: 42,
+            text: "ETF approved https://news.example.com/etf".to_string(),
+            entities: vec![TelegramEntity {
+                kind: "url".to_string(),
+                offset: 13,
+                length: 28,
+                url: None,
+            }],
+            links: vec!["https://news.example.com/etf".to_string()],
+            author: "Crypto News".to_string(),
+            link: "https://t.me/crypto_news/42".to_string(),
+            published_timestamp: 1716300000000,
+            edited_timestamp: Some(1716300030000),
+            fetched_timestamp: 1716300060000,
+        };
+
+        let json = serde_json::to_string(&message).unwrap();
+        assert!(json.contains(r#""type":"url""#));
+        assert_eq!(
+            serde_json::from_str::<TelegramMessage>(&json).unwrap(),
+            message
+        );
+
+        let bytes = serde_json::to_vec(&crate::EventEnvelope::new(message.clone())).unwrap();
+        assert_eq!(
+            crate::decode_event::<TelegramMessage>(&bytes).unwrap(),
+            message
+        );
+    }
+
+    #[test]
+    fn test_decode_legacy_telegram_message() {
+        let message: TelegramMessage = serde_json::from_str(
+            r#"{
+                "channel": "crypto_news",
+                "message_id": 42,
+                "text": "BTC breaks 70k",
+                "author": "Crypto News",
+                "link": "https://t.me/crypto_news/42",
+                "published_timestamp": 1716300000000,
+                "fetched_timestamp": 1716300060000
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(message.chat_id, 0);
+        assert!(message.entities.is_empty());
+        assert!(message.links.is_empty());
+        assert_eq!(message.edited_timestamp, None);
+        assert_eq!(message.text, "BTC breaks 70k");
+    }
+
+    #[test]
+    fn test_telegram_channels() {
+        let channels = TelegramChannels::parse(
+            " @Crypto_News, https://t.me/whale_alerts/ ,-1001234567890,defi_daily,, ",
+        )
+        .unwrap();
+
+        assert_eq!(
+            channels.usernames().collect::<Vec<_>>(),
+            vec!["crypto_news", "defi_daily", "whale_alerts"]
+        );
+        assert_eq!(
+            channels.chat_ids().collect::<Vec<_>>(),
+            vec![-1001234567890]
+        );
+        assert!(channels.allows(1, "CRYPTO_NEWS"));
+        assert!(channels.allows(1, "@whale_alerts"));
+        assert!(channels.allows(-1001234567890, ""));
+        assert!(!channels.allows(2, "other_channel"));
+        assert!(!channels.allows(2, ""));
+
+        assert!(TelegramChannels::parse("").unwrap().is_empty());
+        assert!(TelegramChannels::parse("news").is_err());
+        assert!(TelegramChannels::parse("crypto-news").is_err());
+    }
+
+    /// Prompt answering from a script and recording the questions.
+    #[derive(Default)]
+    struct ScriptedPrompt {
+        answers: Vec<&'static str>,
+        questions: Vec<String>,
+    }
+
+    impl TelegramAuthPrompt for ScriptedPrompt {
+        fn ask(&mut self, question: &str) -> Result<String> {
+            self.questions.push(question.to_string());
+            Ok(self.answers.remove(0).to_string())
+        }
+    }
+
+    fn state(json: &str) -> TelegramAuthState {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_telegram_auth_flow() {
+        let database_dir =
+            std::env::temp_dir().join(format!("telegram-auth-{}", std::process::id()));
+        let config = TelegramAuthConfig {
+            api_id: 94575,
+            api_hash: "a3406de8d171bb422bb6ddf3bbd800e2".to_string(),
+            phone_number: Some("+48123456789".to_string()),
+            code: Some("11111".to_string()),
+            password: None,
+            database_dir: database_dir.clone(),
+        };
+        let prompt = ScriptedPrompt {
+            answers: vec!["22222", "hunter2"],
+            ..Default::default()
+        };
+        let mut auth = TelegramAuth::new(config, prompt);
+        let mut answer = |json: &str| {
+            auth.next(&state(json))
+                .unwrap()
+                .map(|request| serde_json::to_value(request).unwrap())
+        };
+
+        let parameters = answer(r#"{"@type": "authorizationStateWaitTdlibParameters"}"#).unwrap();
+        assert_eq!(parameters["@type"], "setTdlibParameters");
+        assert_eq!(
+            parameters["database_directory"],
+            database_dir.to_string_lossy().as_ref()
+        );
+        assert_eq!(parameters["api_id"], 94575);
+        assert!(database_dir.is_dir());
+        assert_eq!(
+            answer(r#"{"@type": "authorizationStateWaitPhoneNumber"}"#),
+            Some(serde_json::json!({
+                "@type": "setAuthenticationPhoneNumber",
+                "phone_number": "+48123456789"
+            }))
+        );
+        let code_state = r#"{"@type": "authorizationStateWaitCode", "code_info": {}}"#;
+        assert_eq!(
+            answer(code_state),
+            Some(serde_json::json!({"@type": "checkAuthenticationCode", "code": "11111"}))
+        );
+        assert_eq!(
+            answer(code_state),
+            Some(serde_json::json!({"@type": "checkAuthenticationCode", "code": "22222"}))
+        );
+        assert_eq!(
+            answer(r#"{"@type": "authorizationStateWaitPassword", "password_hint": "pet"}"#),
+            Some(serde_json::json!({
+                "@type": "checkAuthenticationPassword",
+                "password": "hunter2"
+            }))
+        );
+        assert_eq!(answer(r#"{"@type": "authorizationStateReady"}"#), None);
+        assert_eq!(answer(r#"{"@type": "authorizationStateLoggingOut"}"#), None);
+
+        assert_eq!(
+            auth.prompt.questions,
+            vec!["Login code", "2FA password (hint: pet)"]
+        );
+        assert!(
+            auth.next(&state(r#"{"@type": "authorizationStateClosed"}"#))
+                .is_err()
+        );
+
+        std::fs::remove_dir_all(database_dir).unwrap();
+    }
+}